@@ -1,6 +1,6 @@
 //! A simple script to generate and verify the proof of a given program.
 
-use sp1_core::{SP1Prover, SP1Stdin, SP1Verifier};
+use sp1_core::{SP1Prover, SP1Stdin, UnverifiedProof};
 
 const ELF: &[u8] = include_bytes!("../../program/elf/riscv32im-succinct-zkvm-elf");
 
@@ -18,7 +18,9 @@ fn main() {
     println!("b: {}", b);
 
     // Verify proof.
-    SP1Verifier::verify(ELF, &proof).expect("verification failed");
+    let proof = UnverifiedProof::from(proof)
+        .verify(ELF)
+        .expect("verification failed");
 
     // Save proof.
     proof
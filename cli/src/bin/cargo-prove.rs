@@ -1,9 +1,11 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use sp1_cli::commands::{
-    build::BuildCmd, build_toolchain::BuildToolchainCmd, install_toolchain::InstallToolchainCmd,
-    new::NewCmd, prove::ProveCmd,
+    build::BuildCmd, build_toolchain::BuildToolchainCmd, dump_schema::DumpSchemaCmd,
+    inspect::InspectCmd, install_toolchain::InstallToolchainCmd, new::NewCmd, prove::ProveCmd,
+    verify::VerifyCmd, witness::WitnessCmd,
 };
+use sp1_cli::events::exit_code;
 
 const VERSION_MESSAGE: &str = concat!(
     "sp1",
@@ -37,9 +39,21 @@ pub enum ProveCliCommands {
     Prove(ProveCmd),
     BuildToolchain(BuildToolchainCmd),
     InstallToolchain(InstallToolchainCmd),
+    Verify(VerifyCmd),
+    Inspect(InspectCmd),
+    DumpSchema(DumpSchemaCmd),
+    Witness(WitnessCmd),
 }
 
-fn main() -> Result<()> {
+/// Runs the command, exiting with a code telling what failed if it does.
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("Error: {:?}", err);
+        std::process::exit(exit_code(&err));
+    }
+}
+
+fn run() -> Result<()> {
     let Cargo::Prove(args) = Cargo::parse();
     let command = args.command.unwrap_or(ProveCliCommands::Prove(args.prove));
     match command {
@@ -48,5 +62,9 @@ fn main() -> Result<()> {
         ProveCliCommands::Prove(cmd) => cmd.run(),
         ProveCliCommands::BuildToolchain(cmd) => cmd.run(),
         ProveCliCommands::InstallToolchain(cmd) => cmd.run(),
+        ProveCliCommands::Verify(cmd) => cmd.run(),
+        ProveCliCommands::Inspect(cmd) => cmd.run(),
+        ProveCliCommands::DumpSchema(cmd) => cmd.run(),
+        ProveCliCommands::Witness(cmd) => cmd.run(),
     }
 }
@@ -8,6 +8,8 @@ use std::{
     thread,
 };
 
+use crate::util::write_human;
+
 fn get_docker_image() -> String {
     // Get the docker image name from the environment variable
     std::env::var("SP1_DOCKER_IMAGE").unwrap_or_else(|_| "succinctlabs/sp1:latest".to_string())
@@ -67,7 +69,7 @@ pub fn build_program(args: &BuildArgs) -> Result<Utf8PathBuf> {
         // Pipe stdout and stderr to the parent process with [docker] prefix
         let stdout_handle = thread::spawn(move || {
             stdout.lines().for_each(|line| {
-                println!("[docker] {}", line.unwrap());
+                write_human(&format!("[docker] {}", line.unwrap()));
             });
         });
         stderr.lines().for_each(|line| {
@@ -78,8 +80,9 @@ pub fn build_program(args: &BuildArgs) -> Result<Utf8PathBuf> {
 
         let result = child.wait()?;
         if !result.success() {
-            // Error message is already printed by cargo
-            exit(result.code().unwrap_or(1))
+            // Error message is already printed by cargo. Return instead of exiting so that
+            // `--watch` can wait for the next change.
+            anyhow::bail!("failed to build the program ({})", result)
         }
     } else {
         let rust_flags = [
@@ -99,8 +102,9 @@ pub fn build_program(args: &BuildArgs) -> Result<Utf8PathBuf> {
             .context("Failed to run cargo command.")?;
 
         if !result.success() {
-            // Error message is already printed by cargo
-            exit(result.code().unwrap_or(1))
+            // Error message is already printed by cargo. Return instead of exiting so that
+            // `--watch` can wait for the next change.
+            anyhow::bail!("failed to build the program ({})", result)
         }
     }
 
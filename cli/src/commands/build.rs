@@ -1,4 +1,7 @@
-use crate::build::{build_program, BuildArgs};
+use crate::{
+    build::{build_program, BuildArgs},
+    util::write_compat_report,
+};
 use anyhow::Result;
 use clap::Parser;
 
@@ -14,7 +17,8 @@ pub struct BuildCmd {
 
 impl BuildCmd {
     pub fn run(&self) -> Result<()> {
-        build_program(&self.build_args)?;
+        let elf_path = build_program(&self.build_args)?;
+        write_compat_report(&std::fs::read(elf_path)?);
 
         Ok(())
     }
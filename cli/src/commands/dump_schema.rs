@@ -0,0 +1,50 @@
+use anstyle::*;
+use anyhow::Result;
+use clap::Parser;
+use sp1_core::{runtime::Program, stark::RiscvStark, utils::BabyBearBlake3};
+use std::{fs, path::PathBuf};
+
+use crate::util::write_status;
+
+#[derive(Parser)]
+#[command(
+    name = "dump-schema",
+    about = "Dump the structural constants of the machine as JSON, for verifiers outside of Rust"
+)]
+pub struct DumpSchemaCmd {
+    /// The path of an ELF whose verifying key must embed the digest of the dumped schema.
+    #[clap(long, action)]
+    elf: Option<PathBuf>,
+
+    /// The path to write the schema to. Prints it if not provided.
+    #[clap(short, long, action)]
+    output: Option<PathBuf>,
+}
+
+impl DumpSchemaCmd {
+    pub fn run(&self) -> Result<()> {
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let schema = machine.schema();
+        let digest = schema.digest();
+
+        if let Some(ref elf_path) = self.elf {
+            let program = Program::from(fs::read(elf_path)?.as_slice());
+            let (_, vk) = machine.setup(&program);
+            anyhow::ensure!(
+                vk.schema_digest() == &digest,
+                "the verifying key embeds another schema digest"
+            );
+        }
+
+        match self.output {
+            Some(ref path) => {
+                fs::write(path, schema.to_json())?;
+                let green = AnsiColor::Green.on_default().effects(Effects::BOLD);
+                write_status(&green, "Wrote", path.display().to_string().as_str());
+                write_status(&green, "Digest", hex::encode(digest).as_str());
+            }
+            None => println!("{}", schema.to_json()),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,19 @@
+use anyhow::Result;
+use clap::Parser;
+use sp1_core::inspect_proof;
+use std::{fs, path::PathBuf};
+
+#[derive(Parser)]
+#[command(name = "inspect", about = "Describe a proof without verifying it")]
+pub struct InspectCmd {
+    /// The path of the proof, saved as a JSON or encoded with bincode.
+    proof: PathBuf,
+}
+
+impl InspectCmd {
+    pub fn run(&self) -> Result<()> {
+        let bytes = fs::read(&self.proof)?;
+        println!("{}", inspect_proof(&bytes));
+        Ok(())
+    }
+}
@@ -1,5 +1,9 @@
 pub mod build;
 pub mod build_toolchain;
+pub mod dump_schema;
+pub mod inspect;
 pub mod install_toolchain;
 pub mod new;
 pub mod prove;
+pub mod verify;
+pub mod witness;
@@ -1,20 +1,37 @@
 use anstyle::*;
 use anyhow::Result;
 use clap::Parser;
+use ed25519_dalek::SigningKey;
+use serde::Deserialize;
 use sp1_core::{
-    utils::{self},
-    SP1Prover, SP1Stdin,
+    disassembler::CompatReport,
+    runtime::ExecutionError,
+    stark::{ProverHooks, StatsCollector},
+    utils::{self, BabyBearBlake3},
+    BatchOptions, CheckpointManifest, ProofFormat, ProveJob, SP1ProofWithIO, SP1Prover,
+    SP1ProverOptions, SP1Stdin, MANIFEST_FILE,
+};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::{
+    env, fs,
+    fs::File,
+    io::{BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
 };
-use std::time::Instant;
-use std::{env, fs::File, io::Read, path::PathBuf, str::FromStr};
 
 use crate::{
     build::{build_program, BuildArgs},
-    util::{elapsed, write_status},
+    events::{Event, EventSink, Failure, FailureKind, LogFormat},
+    manifest::{manifest_path, ProofManifest},
+    util::{elapsed, human_output_to_stderr, write_compat_report, write_human, write_status},
+    watch::{watch, GuestWatcher, WatchSession},
 };
 
 #[derive(Debug, Clone)]
-enum Input {
+pub(crate) enum Input {
     FilePath(PathBuf),
     HexBytes(Vec<u8>),
 }
@@ -55,35 +72,233 @@ impl FromStr for Input {
     }
 }
 
+/// Parses the argument of `--input-hex`, with an optional `0x` prefix.
+fn parse_hex_input(s: &str) -> Result<Input, String> {
+    let bytes = hex::decode(s.strip_prefix("0x").unwrap_or(s)).map_err(|e| e.to_string())?;
+    Ok(Input::HexBytes(bytes))
+}
+
+/// Parses the argument of `--shard-size`, which must be a power of two.
+fn parse_shard_size(s: &str) -> Result<usize, String> {
+    let shard_size = s.parse::<usize>().map_err(|e| e.to_string())?;
+    if !shard_size.is_power_of_two() {
+        return Err(format!("{} is not a power of two", shard_size));
+    }
+    Ok(shard_size)
+}
+
+/// The standard input of the program, the bytes of the `--input` arguments appended in order.
+pub(crate) fn read_stdin(inputs: &[Input]) -> Result<SP1Stdin> {
+    let mut stdin = SP1Stdin::new();
+    for input in inputs {
+        match input {
+            Input::FilePath(ref path) => {
+                let mut file = File::open(path).expect("failed to open input file");
+                let mut bytes = Vec::new();
+                file.read_to_end(&mut bytes)?;
+                stdin.write_slice(&bytes);
+            }
+            Input::HexBytes(ref bytes) => {
+                stdin.write_slice(bytes);
+            }
+        }
+    }
+    Ok(stdin)
+}
+
+/// Reads a hex encoded ed25519 secret key from the file.
+fn read_signing_key(path: &Path) -> Result<SigningKey> {
+    let hex_key = fs::read_to_string(path)?;
+    let bytes: [u8; 32] = hex::decode(hex_key.trim())?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("the signing key must be 32 bytes"))?;
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
+/// The message of a caught panic.
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+/// A job of a `--batch` file, which is a JSON array of them.
+#[derive(Debug, Deserialize)]
+struct BatchJob {
+    elf: PathBuf,
+    /// A file path or hex string, like `--input`.
+    input: Option<String>,
+    /// Where the proof is saved, in `--proof-format`.
+    output: Option<PathBuf>,
+}
+
+impl BatchJob {
+    fn read_all(path: &Path) -> Result<Vec<Self>> {
+        let jobs = serde_json::from_slice(&fs::read(path)?)
+            .map_err(|err| anyhow::anyhow!("invalid batch file {}: {}", path.display(), err))?;
+        Ok(jobs)
+    }
+
+    fn to_prove_job(&self) -> Result<ProveJob> {
+        let input = self
+            .input
+            .as_deref()
+            .map(Input::from_str)
+            .transpose()
+            .map_err(|err| anyhow::anyhow!("invalid input of {}: {}", self.elf.display(), err))?;
+        Ok(ProveJob {
+            elf: fs::read(&self.elf)?,
+            stdin: read_stdin(input.as_slice())?,
+            options: SP1ProverOptions::default(),
+        })
+    }
+}
+
 #[derive(Parser)]
 #[command(name = "prove", about = "(default) Build and prove a program")]
 pub struct ProveCmd {
+    /// A file path or hex string whose bytes are appended to the input of the program. May be
+    /// given several times, in order.
     #[clap(long, value_parser)]
-    input: Option<Input>,
+    input: Vec<Input>,
+
+    /// A hex string whose bytes are appended to the input of the program after those of
+    /// `--input`. May be given several times, in order.
+    #[clap(long, value_parser = parse_hex_input)]
+    input_hex: Vec<Input>,
 
     #[clap(long, action)]
     output: Option<PathBuf>,
 
+    /// Write the bytes the program wrote to its output stream to this file.
+    #[clap(long, value_name = "PATH")]
+    stdout: Option<PathBuf>,
+
+    /// Save the proof in a container signed with the hex encoded ed25519 secret key in this file,
+    /// instead of in `--proof-format`.
+    #[clap(long, action, requires = "output")]
+    sign_key: Option<PathBuf>,
+
+    /// The encoding of the proof file. Both are read back by `SP1ProofWithIO::load`.
+    #[clap(long, value_enum, default_value_t = ProofFormat::Bincode)]
+    proof_format: ProofFormat,
+
     #[clap(long, action)]
     profile: bool,
 
+    /// Execute the program once more before proving, and write the cycles spent in each stack of
+    /// guest functions to this file, in the folded format of flamegraph tools.
+    #[clap(long, value_name = "PATH")]
+    profile_out: Option<PathBuf>,
+
     #[clap(long, action)]
     verbose: bool,
 
+    /// With `ndjson`, write the progress and the result as one JSON event per line on stdout, and
+    /// the human readable output on stderr.
+    #[clap(long, value_enum, default_value_t = LogFormat::Human)]
+    log_format: LogFormat,
+
+    /// Check the constraints of every shard before committing to it.
+    #[clap(long, action)]
+    self_check: bool,
+
+    /// Before proving each shard, report every constraint which is not satisfied with the row of
+    /// the chip it fails at, and fail if there are any.
+    #[clap(long, action)]
+    debug: bool,
+
+    /// Before proving, report the interactions whose sends and receives don't balance, and fail
+    /// if there are any.
+    #[clap(long, action)]
+    debug_lookups: bool,
+
+    /// Print the rows and the trace generation, commit and quotient times of each chip, the
+    /// slowest first.
+    #[clap(long, action)]
+    stats: bool,
+
+    /// Print the bytes taken by each component of the proof, summed over the shards.
+    #[clap(long = "proof-size", action)]
+    proof_size_report: bool,
+
+    /// The number of shards proven at the same time, one per core by default. The proof is the
+    /// same for any number of workers.
+    #[clap(long, value_name = "N")]
+    shard_workers: Option<usize>,
+
+    /// The number of instructions of each shard, a power of two. Defaults to `SHARD_SIZE`, or
+    /// 2^19 if unset.
+    #[clap(long, value_name = "N", value_parser = parse_shard_size)]
+    shard_size: Option<usize>,
+
+    /// Dump the trace of a chip in each shard as CSV, given as `chip:path`. The index of the shard
+    /// is inserted before the extension of the path.
+    #[clap(long, value_name = "CHIP:PATH")]
+    dump_trace_csv: Option<String>,
+
+    /// Rewrite arithmetic by powers of two into shifts and masks before proving. The proof must
+    /// be verified with `--optimize-guest` too.
+    #[clap(long, action)]
+    optimize_guest: bool,
+
+    /// Checkpoint the records of the shards to this directory and prove them one at a time,
+    /// keeping the proof of each shard there. Proving again with the same directory resumes from
+    /// the shards which have no proof yet.
+    #[clap(long, value_name = "DIR", conflicts_with_all = ["optimize_guest", "watch", "batch"])]
+    checkpoint_dir: Option<PathBuf>,
+
+    /// Rebuild and re-execute the program whenever its sources change.
+    #[clap(long, action)]
+    watch: bool,
+
+    /// With `--watch`, also prove the program on every change.
+    #[clap(long, action, requires = "watch")]
+    watch_prove: bool,
+
+    /// Prove the prebuilt programs listed in this JSON file instead of building one, each entry
+    /// giving an `elf` path and optionally an `input` and an `output` path.
+    #[clap(long, value_name = "JOBS", conflicts_with = "watch")]
+    batch: Option<PathBuf>,
+
+    /// With `--batch`, the number of programs proven at the same time.
+    #[clap(long, default_value = "1", requires = "batch")]
+    batch_concurrency: usize,
+
+    /// With `--watch`, how long the sources must stay unchanged before rebuilding.
+    #[clap(long, default_value = "300")]
+    debounce_ms: u64,
+
     #[clap(flatten)]
     build_args: BuildArgs,
 }
 
 impl ProveCmd {
     pub fn run(&self) -> Result<()> {
-        let elf_path = build_program(&self.build_args)?;
+        let events = match self.log_format {
+            LogFormat::Human => None,
+            LogFormat::Ndjson => {
+                human_output_to_stderr();
+                Some(Arc::new(EventSink::stdout()))
+            }
+        };
 
         if !self.profile {
             match env::var("RUST_LOG") {
                 Ok(_) => {}
                 Err(_) => env::set_var("RUST_LOG", "info"),
             }
-            utils::setup_logger();
+            if events.is_some() {
+                utils::setup_stderr_logger();
+            } else {
+                utils::setup_logger();
+            }
         } else {
             match env::var("RUST_TRACER") {
                 Ok(_) => {}
@@ -92,43 +307,321 @@ impl ProveCmd {
             utils::setup_tracer();
         }
 
+        if self.self_check {
+            env::set_var("SELF_CHECK", "true");
+        }
+
+        if self.debug {
+            env::set_var("DEBUG_CONSTRAINTS", "true");
+        }
+
+        if let Some(ref dump) = self.dump_trace_csv {
+            if !dump.contains(':') {
+                anyhow::bail!("--dump-trace-csv must be of the form chip:path");
+            }
+            env::set_var("DUMP_TRACE_CSV", dump);
+        }
+
+        if self.watch {
+            return self.run_watch();
+        }
+
+        if let Some(ref path) = self.batch {
+            return self.run_batch(path);
+        }
+
+        let result = self.build_and_prove(events.clone());
+        if let (Err(err), Some(events)) = (&result, events) {
+            events.emit(&Event::error(err));
+        }
+        result
+    }
+
+    fn build_and_prove(&self, events: Option<Arc<EventSink>>) -> Result<()> {
+        let start_time = Instant::now();
+        let elf = self
+            .build()
+            .map_err(|err| Failure::new(FailureKind::Build, err))?;
+        if let Some(ref events) = events {
+            events.emit(&Event::Phase {
+                name: "build".to_string(),
+                elapsed_ms: millis(start_time.elapsed()),
+            });
+            for issue in CompatReport::from_elf(&elf).issues {
+                events.emit(&Event::Warning {
+                    message: issue.to_string(),
+                });
+            }
+        }
+        self.prove_elf(&elf, events)
+    }
+
+    /// Proves the ELF and saves the proof, reporting to the events if given.
+    ///
+    /// Fails with a `Failure` of the execution or of the proving.
+    pub fn prove_elf(&self, elf: &[u8], events: Option<Arc<EventSink>>) -> Result<()> {
+        let stdin = self.stdin()?;
+        let stdin_bytes = stdin.canonical_bytes();
+        if self.debug_lookups {
+            self.check_lookups(elf)
+                .map_err(|err| Failure::new(FailureKind::Proving, err))?;
+        }
+        if let Some(ref path) = self.profile_out {
+            self.write_profile(elf, path)
+                .map_err(|err| Failure::new(FailureKind::Execution, err))?;
+        }
+        let start_time = Instant::now();
+        let hooks = events.clone().map(|events| events as Arc<dyn ProverHooks>);
+        let stats = self
+            .stats
+            .then(|| Arc::new(StatsCollector::new(hooks.clone())));
+        let options = SP1ProverOptions {
+            hooks: match stats {
+                Some(ref stats) => Some(stats.clone()),
+                None => hooks,
+            },
+            shard_workers: self.shard_workers,
+            shard_size: self.shard_size,
+            ..Default::default()
+        };
+        let proof = panic::catch_unwind(AssertUnwindSafe(|| {
+            if let Some(ref dir) = self.checkpoint_dir {
+                Self::prove_checkpoints(elf, stdin, options, dir)
+            } else if self.optimize_guest {
+                SP1Prover::prove_optimized_with_options(elf, stdin, options)
+            } else {
+                SP1Prover::prove_with_options(elf, stdin, options)
+            }
+        }))
+        .map_err(|panic| {
+            Failure::new(
+                FailureKind::Proving,
+                anyhow::anyhow!("proving panicked: {}", panic_message(panic.as_ref())),
+            )
+        })?
+        .map_err(|err| {
+            let kind = if err.downcast_ref::<ExecutionError>().is_some() {
+                FailureKind::Execution
+            } else {
+                FailureKind::Proving
+            };
+            Failure::new(kind, err)
+        })?;
+        if let Some(ref events) = events {
+            events.emit(&Event::Phase {
+                name: "prove".to_string(),
+                elapsed_ms: millis(start_time.elapsed()),
+            });
+        }
+
+        if let Some(ref path) = self.stdout {
+            fs::write(path, &proof.stdout.buffer.data)?;
+        }
+
+        let mut proof_size = None;
+        if let Some(ref path) = self.output {
+            match self.sign_key {
+                Some(ref key_path) => {
+                    let key = read_signing_key(key_path)?;
+                    fs::write(path, proof.to_signed_bytes(&key))?;
+                }
+                None => proof
+                    .save_as(path.to_str().unwrap(), self.proof_format)
+                    .expect("failed to save proof"),
+            }
+            let mut manifest = ProofManifest::new(elf, &stdin_bytes, start_time.elapsed());
+            if let Some(shard_size) = self.shard_size {
+                manifest.shard_size = shard_size;
+            }
+            manifest
+                .save(&manifest_path(path))
+                .expect("failed to save manifest");
+            proof_size = Some(fs::metadata(path)?.len());
+        }
+
+        let duration = start_time.elapsed();
+        let green = AnsiColor::Green.on_default().effects(Effects::BOLD);
+        write_status(
+            &green,
+            "Finished",
+            format!("proving in {}", elapsed(duration)).as_str(),
+        );
+        if let Some(stats) = stats {
+            write_human(&stats.stats().to_string());
+        }
+        if self.proof_size_report {
+            write_human(&proof.proof.size_report().to_string());
+        }
+
+        if let Some(events) = events {
+            events.emit(&Event::Summary {
+                num_shards: proof.proof.shard_proofs.len(),
+                instructions: proof.proof.instruction_count(),
+                elapsed_ms: millis(duration),
+                proof_path: self.output.as_ref().map(|path| path.display().to_string()),
+                proof_size,
+                manifest_path: self
+                    .output
+                    .as_ref()
+                    .map(|path| manifest_path(path).display().to_string()),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Proves the ELF through the checkpoint in the directory, taking it first unless the directory
+    /// already holds one for the same input.
+    fn prove_checkpoints(
+        elf: &[u8],
+        stdin: SP1Stdin,
+        options: SP1ProverOptions,
+        dir: &Path,
+    ) -> Result<SP1ProofWithIO<BabyBearBlake3>> {
+        if dir.join(MANIFEST_FILE).exists() {
+            let manifest = CheckpointManifest::load(dir)?;
+            let checkpoint_stdin = manifest.stdin.map(|stdin| stdin.canonical_bytes());
+            if checkpoint_stdin != Some(stdin.canonical_bytes()) {
+                anyhow::bail!(
+                    "the checkpoint in {} was taken with another input",
+                    dir.display()
+                );
+            }
+        } else {
+            SP1Prover::execute_with_checkpoints(elf, stdin, options, dir)?;
+        }
+        SP1Prover::prove_checkpoints(elf, dir)
+    }
+
+    /// Reports the unbalanced interactions of the execution of the ELF, failing if there are any.
+    fn check_lookups(&self, elf: &[u8]) -> Result<()> {
+        let unbalanced = SP1Prover::debug_lookups(elf, self.stdin()?)?;
+        if unbalanced.is_empty() {
+            return Ok(());
+        }
+        for interaction in unbalanced.iter() {
+            write_human(&interaction.to_string());
+        }
+        anyhow::bail!("{} interactions are unbalanced", unbalanced.len())
+    }
+
+    /// Writes the folded stacks of the functions of the execution of the ELF to the file.
+    fn write_profile(&self, elf: &[u8], path: &Path) -> Result<()> {
+        let profiler = SP1Prover::profile(elf, self.stdin()?)?;
+        let mut out = BufWriter::new(File::create(path)?);
+        profiler.write_folded(&mut out)?;
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Builds the program and returns its ELF.
+    fn build(&self) -> Result<Vec<u8>> {
+        let elf_path = build_program(&self.build_args)?;
         let mut elf = Vec::new();
         File::open(elf_path.as_path().as_str())
             .expect("failed to open input file")
             .read_to_end(&mut elf)
             .expect("failed to read from input file");
+        write_compat_report(&elf);
+        Ok(elf)
+    }
 
-        let mut stdin = SP1Stdin::new();
-        if let Some(ref input) = self.input {
-            match input {
-                Input::FilePath(ref path) => {
-                    let mut file = File::open(path).expect("failed to open input file");
-                    let mut bytes = Vec::new();
-                    file.read_to_end(&mut bytes)?;
-                    stdin.write_slice(&bytes);
-                }
-                Input::HexBytes(ref bytes) => {
-                    stdin.write_slice(bytes);
-                }
-            }
-        }
+    fn stdin(&self) -> Result<SP1Stdin> {
+        let inputs = self
+            .input
+            .iter()
+            .chain(self.input_hex.iter())
+            .cloned()
+            .collect::<Vec<_>>();
+        read_stdin(&inputs)
+    }
+
+    /// Proves the jobs of the batch file, saving the proof of each job which succeeds. Fails if
+    /// any job failed, after all of them were run.
+    fn run_batch(&self, path: &Path) -> Result<()> {
+        let batch_jobs = BatchJob::read_all(path)?;
+        let jobs = batch_jobs
+            .iter()
+            .map(|job| {
+                let mut job = job.to_prove_job()?;
+                job.options.shard_workers = self.shard_workers;
+                job.options.shard_size = self.shard_size;
+                Ok(job)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let options = BatchOptions {
+            concurrency: self.batch_concurrency,
+            ..Default::default()
+        };
         let start_time = Instant::now();
-        let proof = SP1Prover::prove(&elf, stdin).unwrap();
+        let batch = SP1Prover::prove_batch_with_options(jobs, options);
 
-        if let Some(ref path) = self.output {
-            proof
-                .save(path.to_str().unwrap())
-                .expect("failed to save proof");
+        let mut failed = 0;
+        for (job, result) in batch_jobs.iter().zip(batch.results.iter()) {
+            match (result, &job.output) {
+                (Ok(proof), Some(output)) => proof
+                    .save_as(output.to_str().unwrap(), self.proof_format)
+                    .expect("failed to save proof"),
+                (Ok(_), None) => {}
+                (Err(_), _) => failed += 1,
+            }
         }
 
-        let elapsed = elapsed(start_time.elapsed());
         let green = AnsiColor::Green.on_default().effects(Effects::BOLD);
         write_status(
             &green,
             "Finished",
-            format!("proving in {}", elapsed).as_str(),
+            format!(
+                "proving {} programs in {}",
+                batch_jobs.len(),
+                elapsed(start_time.elapsed())
+            )
+            .as_str(),
         );
-
+        write_human(&batch.to_string());
+        if failed > 0 {
+            anyhow::bail!("{} of {} jobs failed", failed, batch_jobs.len());
+        }
         Ok(())
     }
+
+    fn run_watch(&self) -> Result<()> {
+        let source_dir = env::current_dir()?.join("src");
+        let watcher = GuestWatcher::new(&source_dir, Duration::from_millis(self.debounce_ms))?;
+        let mut session = WatchSession::new(self.stdin()?.buffer.data, self.watch_prove);
+        let green = AnsiColor::Green.on_default().effects(Effects::BOLD);
+        let cyan = AnsiColor::Cyan.on_default().effects(Effects::BOLD);
+        write_status(
+            &cyan,
+            "Watching",
+            format!("{} for changes", source_dir.display()).as_str(),
+        );
+        let action = if self.watch_prove {
+            "proving"
+        } else {
+            "executing"
+        };
+        watch(
+            &watcher,
+            &mut session,
+            || self.build(),
+            |report, diff, duration| {
+                write_status(
+                    &green,
+                    "Finished",
+                    format!(
+                        "{} in {}: {} cycles, {} shards",
+                        action,
+                        elapsed(duration),
+                        report.cycles,
+                        report.shards
+                    )
+                    .as_str(),
+                );
+                for line in diff {
+                    write_status(&cyan, "Changed", line);
+                }
+            },
+        )
+    }
 }
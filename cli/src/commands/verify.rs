@@ -0,0 +1,105 @@
+use anstyle::*;
+use anyhow::Result;
+use clap::Parser;
+use ed25519_dalek::VerifyingKey;
+use sp1_core::{utils::BabyBearBlake3, SP1ProofWithIO, UnverifiedProof};
+use std::{fs, path::PathBuf};
+
+use crate::{
+    build::{build_program, BuildArgs},
+    events::{Failure, FailureKind},
+    manifest::{manifest_path, ProofManifest},
+    util::write_status,
+};
+
+#[derive(Parser)]
+#[command(name = "verify", about = "Verify a proof of a program")]
+pub struct VerifyCmd {
+    /// The path of the proof to verify.
+    #[clap(long, action)]
+    proof: PathBuf,
+
+    /// The path of the ELF the proof is for. Builds the program if not provided.
+    #[clap(long, action)]
+    elf: Option<PathBuf>,
+
+    /// Check the manifest next to the proof against the ELF and the current config.
+    #[clap(long, action)]
+    check_manifest: bool,
+
+    /// Verify a proof generated with `prove --optimize-guest`.
+    #[clap(long, action)]
+    optimize_guest: bool,
+
+    /// Require the proof to be saved with `prove --sign-key`, by the owner of this hex encoded
+    /// ed25519 public key. The signature is checked before the proof is decoded.
+    #[clap(long, value_name = "PUBLIC_KEY")]
+    require_signer: Option<String>,
+
+    #[clap(flatten)]
+    build_args: BuildArgs,
+}
+
+impl VerifyCmd {
+    pub fn run(&self) -> Result<()> {
+        let elf_path = match self.elf {
+            Some(ref path) => path.clone(),
+            None => build_program(&self.build_args)
+                .map_err(|err| Failure::new(FailureKind::Build, err))?
+                .into_std_path_buf(),
+        };
+        let elf = fs::read(&elf_path)?;
+
+        if self.check_manifest {
+            let manifest = ProofManifest::load(&manifest_path(&self.proof))?;
+            let yellow = AnsiColor::Yellow.on_default().effects(Effects::BOLD);
+            for mismatch in manifest.check(&elf) {
+                write_status(&yellow, "Warning", mismatch.to_string().as_str());
+            }
+        }
+
+        let proof = match self.require_signer {
+            Some(ref public_key) => {
+                let bytes: [u8; 32] = hex::decode(public_key.trim_start_matches("0x"))?
+                    .try_into()
+                    .map_err(|_| anyhow::anyhow!("the public key must be 32 bytes"))?;
+                let verifier = VerifyingKey::from_bytes(&bytes)?;
+                let proof = SP1ProofWithIO::<BabyBearBlake3>::from_signed_bytes(
+                    &fs::read(&self.proof)?,
+                    &verifier,
+                )
+                .map_err(|err| Failure::new(FailureKind::Verification, err))?;
+                UnverifiedProof::from(proof)
+            }
+            None => UnverifiedProof::<BabyBearBlake3>::load(self.proof.to_str().unwrap())?,
+        };
+        let proof = if self.optimize_guest {
+            proof.verify_optimized(&elf)
+        } else {
+            proof.verify(&elf)
+        };
+        let proof = proof.map_err(|e| {
+            Failure::new(
+                FailureKind::Verification,
+                anyhow::anyhow!("failed to verify proof: {:?}", e),
+            )
+        })?;
+
+        let green = AnsiColor::Green.on_default().effects(Effects::BOLD);
+        write_status(
+            &green,
+            "Verified",
+            self.proof.display().to_string().as_str(),
+        );
+        if let Some(exit_code) = proof.exit_code() {
+            write_status(&green, "Exit code", exit_code.to_string().as_str());
+        }
+        write_status(
+            &green,
+            "Instructions",
+            proof.instruction_count().to_string().as_str(),
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use anstyle::*;
+use anyhow::Result;
+use clap::Parser;
+use sp1_core::{utils, SP1Prover, SP1ProverOptions};
+use std::{env, fs, path::PathBuf, time::Instant};
+
+use crate::{
+    build::{build_program, BuildArgs},
+    commands::prove::{read_stdin, Input},
+    events::{Failure, FailureKind},
+    util::{elapsed, write_status},
+};
+
+#[derive(Parser)]
+#[command(
+    name = "witness",
+    about = "Build and execute a program, and save the traces of its shards without proving them"
+)]
+pub struct WitnessCmd {
+    #[clap(long, value_parser)]
+    input: Option<Input>,
+
+    /// The directory to save the witness bundle to, with a manifest and a file per chip per shard.
+    #[clap(short, long, action)]
+    output: PathBuf,
+
+    #[clap(flatten)]
+    build_args: BuildArgs,
+}
+
+impl WitnessCmd {
+    pub fn run(&self) -> Result<()> {
+        if env::var("RUST_LOG").is_err() {
+            env::set_var("RUST_LOG", "info");
+        }
+        utils::setup_logger();
+
+        let start_time = Instant::now();
+        let elf_path =
+            build_program(&self.build_args).map_err(|err| Failure::new(FailureKind::Build, err))?;
+        let elf = fs::read(elf_path.as_std_path())?;
+        let stdin = read_stdin(self.input.as_slice())?;
+
+        let bundle = SP1Prover::generate_witness(&elf, stdin, SP1ProverOptions::default())
+            .map_err(|err| Failure::new(FailureKind::Execution, err))?;
+        bundle.save(&self.output)?;
+
+        let green = AnsiColor::Green.on_default().effects(Effects::BOLD);
+        write_status(
+            &green,
+            "Finished",
+            format!(
+                "generating the traces of {} shards in {}",
+                bundle.manifest.shards.len(),
+                elapsed(start_time.elapsed())
+            )
+            .as_str(),
+        );
+        write_status(&green, "Wrote", self.output.display().to_string().as_str());
+        Ok(())
+    }
+}
@@ -0,0 +1,158 @@
+//! Machine readable output of the CLI, for the systems which orchestrate it.
+//!
+//! With `--log-format ndjson`, a command writes one JSON object per line to stdout, each with a
+//! `type` field naming the event, and its human readable output goes to stderr.
+
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sp1_core::stark::ProverHooks;
+
+/// How a command reports its progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum LogFormat {
+    /// Colored status lines for a terminal.
+    #[default]
+    Human,
+    /// One JSON event per line on stdout, and the human output on stderr.
+    Ndjson,
+}
+
+/// What failed in a command, which decides its exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    Build,
+    Execution,
+    Proving,
+    Verification,
+}
+
+impl FailureKind {
+    /// The exit code of the command. Other failures exit with 1, and panics with 101.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            FailureKind::Build => 2,
+            FailureKind::Execution => 3,
+            FailureKind::Proving => 4,
+            FailureKind::Verification => 5,
+        }
+    }
+}
+
+/// An error of a command, with what failed.
+#[derive(Debug)]
+pub struct Failure {
+    pub kind: FailureKind,
+    pub error: anyhow::Error,
+}
+
+impl Failure {
+    pub fn new(kind: FailureKind, error: impl Into<anyhow::Error>) -> Self {
+        Self {
+            kind,
+            error: error.into(),
+        }
+    }
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#}", self.error)
+    }
+}
+
+impl std::error::Error for Failure {}
+
+/// The exit code of a command which failed with the error.
+pub fn exit_code(error: &anyhow::Error) -> i32 {
+    error
+        .downcast_ref::<Failure>()
+        .map_or(1, |failure| failure.kind.exit_code())
+}
+
+/// An event of the NDJSON output. The `type` of each event is stable.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// A phase of the command, such as `build` or `prove`, is done.
+    Phase { name: String, elapsed_ms: u64 },
+    /// Something the user should know, which does not stop the command.
+    Warning { message: String },
+    /// A shard starts being proven.
+    ShardStart { index: usize, num_shards: usize },
+    /// A shard is proven.
+    ShardDone {
+        index: usize,
+        num_shards: usize,
+        elapsed_ms: u64,
+    },
+    /// The command succeeded.
+    Summary {
+        num_shards: usize,
+        instructions: u64,
+        elapsed_ms: u64,
+        proof_path: Option<String>,
+        proof_size: Option<u64>,
+        manifest_path: Option<String>,
+    },
+    /// The command failed, and exits with `exit_code`.
+    Error {
+        kind: Option<FailureKind>,
+        message: String,
+        exit_code: i32,
+    },
+}
+
+impl Event {
+    /// The event for a command which failed with the error.
+    pub fn error(error: &anyhow::Error) -> Self {
+        Event::Error {
+            kind: error.downcast_ref::<Failure>().map(|failure| failure.kind),
+            message: format!("{:#}", error),
+            exit_code: exit_code(error),
+        }
+    }
+}
+
+/// Writes events as NDJSON, and reports the progress of proofs as events.
+pub struct EventSink {
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl EventSink {
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            writer: Mutex::new(Box::new(writer)),
+        }
+    }
+
+    pub fn stdout() -> Self {
+        Self::new(std::io::stdout())
+    }
+
+    /// Writes the event as a line, flushed so that a reader sees it as soon as it happens.
+    pub fn emit(&self, event: &Event) {
+        let mut writer = self.writer.lock().unwrap();
+        let line = serde_json::to_string(event).unwrap();
+        writeln!(writer, "{}", line).expect("failed to write event");
+        writer.flush().expect("failed to write event");
+    }
+}
+
+impl ProverHooks for EventSink {
+    fn shard_start(&self, index: usize, num_shards: usize) {
+        self.emit(&Event::ShardStart { index, num_shards });
+    }
+
+    fn shard_done(&self, index: usize, num_shards: usize, elapsed: Duration) {
+        self.emit(&Event::ShardDone {
+            index,
+            num_shards,
+            elapsed_ms: elapsed.as_millis() as u64,
+        });
+    }
+}
@@ -1,6 +1,9 @@
 mod build;
 pub mod commands;
+pub mod events;
+pub mod manifest;
 mod util;
+pub mod watch;
 
 use anyhow::Result;
 use futures_util::StreamExt;
@@ -0,0 +1,198 @@
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sp1_core::utils::{env, BabyBearBlake3};
+
+use crate::RUSTUP_TOOLCHAIN_NAME;
+
+/// The name of the manifest file written next to a proof.
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// The name of the config that `cargo prove` proves with.
+pub const CONFIG_NAME: &str = "BabyBearBlake3";
+
+/// The parameters of the FRI protocol used by a config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FriParams {
+    pub log_blowup: usize,
+    pub num_queries: usize,
+    pub proof_of_work_bits: usize,
+}
+
+/// A description of the environment a proof was generated in.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProofManifest {
+    /// The git SHA of the sp1 checkout the CLI was built from.
+    pub sp1_git_sha: String,
+
+    /// The version of the toolchain used to build guest programs.
+    pub toolchain: String,
+
+    /// The SHA-256 of the guest ELF, hex encoded.
+    pub elf_sha256: String,
+
+    /// The name of the STARK config.
+    pub config: String,
+
+    /// The FRI parameters of the STARK config.
+    pub fri: FriParams,
+
+    /// The number of cycles per shard.
+    pub shard_size: usize,
+
+    /// The SHA-256 of the canonical encoding of the stdin and its environment, hex encoded.
+    pub stdin_sha256: String,
+
+    /// The operating system of the host.
+    pub host_os: String,
+
+    /// The architecture of the host.
+    pub host_arch: String,
+
+    /// The wall time it took to generate the proof, in seconds.
+    pub wall_time_secs: f64,
+}
+
+/// A field of a manifest that does not match the environment it is checked against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestMismatch {
+    pub field: &'static str,
+    pub expected: String,
+    pub found: String,
+}
+
+impl Display for ManifestMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} mismatch: manifest has {}, found {}",
+            self.field, self.expected, self.found
+        )
+    }
+}
+
+impl FriParams {
+    /// The FRI parameters of the config used by `cargo prove`.
+    pub fn current() -> Self {
+        let (log_blowup, num_queries, proof_of_work_bits) = BabyBearBlake3::FRI_PARAMS;
+        Self {
+            log_blowup,
+            num_queries,
+            proof_of_work_bits,
+        }
+    }
+}
+
+impl ProofManifest {
+    /// Capture the current environment for a proof of `elf` on `stdin`.
+    pub fn new(elf: &[u8], stdin: &[u8], wall_time: Duration) -> Self {
+        Self {
+            sp1_git_sha: env!("VERGEN_GIT_SHA").to_string(),
+            toolchain: toolchain_version(),
+            elf_sha256: sha256_hex(elf),
+            config: CONFIG_NAME.to_string(),
+            fri: FriParams::current(),
+            shard_size: env::shard_size(),
+            stdin_sha256: sha256_hex(stdin),
+            host_os: std::env::consts::OS.to_string(),
+            host_arch: std::env::consts::ARCH.to_string(),
+            wall_time_secs: wall_time.as_secs_f64(),
+        }
+    }
+
+    /// Save the manifest as JSON to the given path.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let data = serde_json::to_string_pretty(self)?;
+        fs::write(path, data).with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// Load a manifest from the given path.
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    /// Re-derive the ELF hash and the config identity and return every field that differs.
+    pub fn check(&self, elf: &[u8]) -> Vec<ManifestMismatch> {
+        let mut mismatches = Vec::new();
+        let mut compare = |field, expected: &dyn Display, found: &dyn Display| {
+            let (expected, found) = (expected.to_string(), found.to_string());
+            if expected != found {
+                mismatches.push(ManifestMismatch {
+                    field,
+                    expected,
+                    found,
+                });
+            }
+        };
+        compare("elf_sha256", &self.elf_sha256, &sha256_hex(elf));
+        compare("config", &self.config, &CONFIG_NAME);
+        let fri = FriParams::current();
+        compare("fri.log_blowup", &self.fri.log_blowup, &fri.log_blowup);
+        compare("fri.num_queries", &self.fri.num_queries, &fri.num_queries);
+        compare(
+            "fri.proof_of_work_bits",
+            &self.fri.proof_of_work_bits,
+            &fri.proof_of_work_bits,
+        );
+        compare("sp1_git_sha", &self.sp1_git_sha, &env!("VERGEN_GIT_SHA"));
+        mismatches
+    }
+}
+
+/// The path of the manifest belonging to the proof at `proof_path`.
+pub fn manifest_path(proof_path: &Path) -> PathBuf {
+    proof_path.with_file_name(MANIFEST_FILE_NAME)
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+fn toolchain_version() -> String {
+    Command::new("rustup")
+        .args(["run", RUSTUP_TOOLCHAIN_NAME, "rustc", "--version"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{manifest_path, ProofManifest};
+
+    #[test]
+    fn test_manifest_round_trip() {
+        let dir = std::env::temp_dir().join(format!("sp1-manifest-{}", rand::random::<u64>()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = manifest_path(&dir.join("proof.json"));
+        assert_eq!(path, dir.join("manifest.json"));
+
+        let manifest = ProofManifest::new(b"elf", b"stdin", Duration::from_millis(1500));
+        manifest.save(&path).unwrap();
+        let loaded = ProofManifest::load(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded, manifest);
+        assert!(loaded.check(b"elf").is_empty());
+    }
+
+    #[test]
+    fn test_manifest_detects_swapped_elf() {
+        let manifest = ProofManifest::new(b"original elf", &[], Duration::ZERO);
+        let mismatches = manifest.check(b"swapped elf");
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].field, "elf_sha256");
+        assert_eq!(mismatches[0].expected, manifest.elf_sha256);
+    }
+}
@@ -1,7 +1,30 @@
-use std::{fmt::Display, time::Duration};
+use anstyle::*;
+use sp1_core::disassembler::{CompatReport, CompatSeverity};
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+/// Whether the human readable output goes to stderr, to keep stdout for machine readable output.
+static HUMAN_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Sends the human readable output of the rest of the command to stderr.
+pub(crate) fn human_output_to_stderr() {
+    HUMAN_TO_STDERR.store(true, Ordering::Relaxed);
+}
+
+/// Writes a line of human readable output.
+pub(crate) fn write_human(line: &str) {
+    if HUMAN_TO_STDERR.load(Ordering::Relaxed) {
+        eprintln!("{}", line);
+    } else {
+        println!("{}", line);
+    }
+}
 
 pub(crate) fn write_status(style: &dyn Display, status: &str, msg: &str) {
-    println!("{style}{status:>12}{style:#} {msg}");
+    write_human(&format!("{style}{status:>12}{style:#} {msg}"));
 }
 
 pub(crate) fn elapsed(duration: Duration) -> String {
@@ -13,3 +36,16 @@ pub(crate) fn elapsed(duration: Duration) -> String {
         format!("{}.{:02}s", secs, duration.subsec_nanos() / 10_000_000)
     }
 }
+
+/// Print the settings of a guest ELF that the runtime does not support.
+pub(crate) fn write_compat_report(elf: &[u8]) {
+    let report = CompatReport::from_elf(elf);
+    let yellow = AnsiColor::Yellow.on_default().effects(Effects::BOLD);
+    let red = AnsiColor::Red.on_default().effects(Effects::BOLD);
+    for issue in report.issues.iter() {
+        match issue.severity() {
+            CompatSeverity::Warning => write_status(&yellow, "Warning", &issue.to_string()),
+            CompatSeverity::Error => write_status(&red, "Error", &issue.to_string()),
+        }
+    }
+}
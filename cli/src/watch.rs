@@ -0,0 +1,263 @@
+use anyhow::{Context, Result};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use sha2::{Digest, Sha256};
+use sp1_core::{
+    runtime::{Program, Runtime, SyscallCode},
+    stark::{LocalProver, ProvingKey, RiscvStark},
+    utils::{BabyBearBlake3, StarkUtils},
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver, RecvTimeoutError},
+    time::{Duration, Instant},
+};
+
+/// A compact summary of one run of the guest, compared across iterations of `--watch`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WatchReport {
+    /// The number of cycles executed.
+    pub cycles: u64,
+
+    /// The number of shards the execution splits into.
+    pub shards: usize,
+
+    /// The number of times each syscall was invoked.
+    pub syscall_counts: HashMap<SyscallCode, u64>,
+}
+
+impl WatchReport {
+    pub fn from_runtime(runtime: &Runtime) -> Self {
+        // The shards of the proof are those of the runtime, numbered from one.
+        Self {
+            cycles: runtime.state.global_clk as u64,
+            shards: runtime
+                .record
+                .cpu_events
+                .last()
+                .map_or(0, |event| event.shard as usize),
+            syscall_counts: runtime.report.syscall_counts.clone(),
+        }
+    }
+
+    /// Describes the changes from the previous report, one line per change.
+    pub fn diff(&self, previous: &WatchReport) -> Vec<String> {
+        let mut lines = Vec::new();
+        if self.cycles != previous.cycles {
+            let delta = self.cycles as i64 - previous.cycles as i64;
+            lines.push(format!(
+                "cycles: {} -> {} ({:+})",
+                previous.cycles, self.cycles, delta
+            ));
+        }
+        if self.shards != previous.shards {
+            lines.push(format!("shards: {} -> {}", previous.shards, self.shards));
+        }
+
+        // Sort by name so that the output is stable across runs.
+        let syscalls = self
+            .syscall_counts
+            .keys()
+            .chain(previous.syscall_counts.keys())
+            .map(|code| (format!("{:?}", code), code))
+            .collect::<BTreeMap<_, _>>();
+        for (name, code) in syscalls {
+            let before = previous.syscall_counts.get(code).copied().unwrap_or(0);
+            let after = self.syscall_counts.get(code).copied().unwrap_or(0);
+            match (before, after) {
+                (0, _) => lines.push(format!("new syscall {}: {} calls", name, after)),
+                (_, 0) => lines.push(format!("removed syscall {}", name)),
+                _ if before != after => {
+                    lines.push(format!("syscall {}: {} -> {} calls", name, before, after))
+                }
+                _ => {}
+            }
+        }
+        lines
+    }
+}
+
+/// Watches a guest source directory and reports debounced changes.
+pub struct GuestWatcher {
+    // Dropping the watcher stops the events, so it is kept alive alongside the receiver.
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    debounce: Duration,
+}
+
+impl GuestWatcher {
+    pub fn new(dir: &Path, debounce: Duration) -> Result<Self> {
+        let (sender, events) = channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = sender.send(event);
+        })?;
+        watcher
+            .watch(dir, RecursiveMode::Recursive)
+            .with_context(|| format!("failed to watch {}", dir.display()))?;
+        Ok(Self {
+            _watcher: watcher,
+            events,
+            debounce,
+        })
+    }
+
+    /// Blocks until a file changes, then until no further change happens for the debounce
+    /// period, and returns the changed paths.
+    pub fn wait_for_change(&self) -> Result<Vec<PathBuf>> {
+        let mut paths = Vec::new();
+        while paths.is_empty() {
+            paths.extend(Self::changed_paths(self.events.recv()??));
+        }
+        loop {
+            match self.events.recv_timeout(self.debounce) {
+                Ok(event) => paths.extend(Self::changed_paths(event?)),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => anyhow::bail!("file watcher stopped"),
+            }
+        }
+        paths.sort();
+        paths.dedup();
+        Ok(paths)
+    }
+
+    fn changed_paths(event: Event) -> Vec<PathBuf> {
+        match event.kind {
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) => event.paths,
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// The state kept across iterations of `--watch`.
+pub struct WatchSession {
+    stdin: Vec<u8>,
+    prove: bool,
+    machine: RiscvStark<BabyBearBlake3>,
+    /// The proving key, with the hash of the ELF it was set up for.
+    pk: Option<([u8; 32], ProvingKey<BabyBearBlake3>)>,
+    previous: Option<WatchReport>,
+}
+
+impl WatchSession {
+    pub fn new(stdin: Vec<u8>, prove: bool) -> Self {
+        Self {
+            stdin,
+            prove,
+            machine: RiscvStark::new(BabyBearBlake3::new()),
+            pk: None,
+            previous: None,
+        }
+    }
+
+    /// Executes the ELF, and proves it if the session was created with `prove`, returning the
+    /// report of the run and its differences with the previous one.
+    pub fn run(&mut self, elf: &[u8]) -> Result<(WatchReport, Vec<String>)> {
+        let program = Program::from(elf);
+        let mut runtime = Runtime::new(program);
+        runtime.write_stdin_slice(&self.stdin);
+        runtime.run();
+        let report = WatchReport::from_runtime(&runtime);
+
+        if self.prove {
+            // The proving key only depends on the program, so it is reused while the ELF is
+            // unchanged.
+            let elf_hash: [u8; 32] = Sha256::digest(elf).into();
+            if self.pk.as_ref().map(|(hash, _)| *hash) != Some(elf_hash) {
+                let (pk, _) = self.machine.setup(runtime.program.as_ref());
+                self.pk = Some((elf_hash, pk));
+            }
+            let (_, pk) = self.pk.as_ref().unwrap();
+            let mut challenger = self.machine.config().challenger();
+            self.machine
+                .prove::<LocalProver<_>>(pk, runtime.record, &mut challenger)?;
+        }
+
+        let diff = match self.previous.as_ref() {
+            Some(previous) => report.diff(previous),
+            None => Vec::new(),
+        };
+        self.previous = Some(report.clone());
+        Ok((report, diff))
+    }
+}
+
+/// Runs the session on every debounced change of the watched directory, rebuilding the guest with
+/// `rebuild` first. Runs once before waiting for the first change.
+pub fn watch(
+    watcher: &GuestWatcher,
+    session: &mut WatchSession,
+    mut rebuild: impl FnMut() -> Result<Vec<u8>>,
+    mut on_report: impl FnMut(&WatchReport, &[String], Duration),
+) -> Result<()> {
+    loop {
+        let start = Instant::now();
+        match rebuild().and_then(|elf| session.run(&elf)) {
+            Ok((report, diff)) => on_report(&report, &diff, start.elapsed()),
+            // A broken guest should not stop the loop; the next save may fix it.
+            Err(err) => eprintln!("{:?}", err),
+        }
+        watcher.wait_for_change()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, thread, time::Duration};
+
+    use sp1_core::runtime::SyscallCode;
+
+    use super::{GuestWatcher, WatchReport, WatchSession};
+
+    const FIBONACCI_ELF: &[u8] =
+        include_bytes!("../../examples/fibonacci/program/elf/riscv32im-succinct-zkvm-elf");
+
+    #[test]
+    fn test_report_diff() {
+        let previous = WatchReport {
+            cycles: 100,
+            shards: 1,
+            syscall_counts: [(SyscallCode::HALT, 1)].into_iter().collect(),
+        };
+        let report = WatchReport {
+            cycles: 90,
+            shards: 1,
+            syscall_counts: [(SyscallCode::HALT, 1), (SyscallCode::WRITE, 2)]
+                .into_iter()
+                .collect(),
+        };
+        assert_eq!(
+            report.diff(&previous),
+            vec![
+                "cycles: 100 -> 90 (-10)".to_string(),
+                "new syscall WRITE: 2 calls".to_string()
+            ]
+        );
+        assert!(report.diff(&report).is_empty());
+    }
+
+    #[test]
+    fn test_watch_produces_report_on_change() {
+        let dir = std::env::temp_dir().join(format!("sp1-watch-{}", rand::random::<u64>()));
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("main.rs");
+        fs::write(&source, "fn main() {}").unwrap();
+
+        let watcher = GuestWatcher::new(&dir, Duration::from_millis(50)).unwrap();
+        let mut session = WatchSession::new(Vec::new(), false);
+        let (first, diff) = session.run(FIBONACCI_ELF).unwrap();
+        assert!(diff.is_empty());
+
+        let writer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            fs::write(&source, "fn main() { loop {} }").unwrap();
+        });
+        let changed = watcher.wait_for_change().unwrap();
+        writer.join().unwrap();
+        assert!(changed.iter().any(|path| path.ends_with("main.rs")));
+
+        let (second, diff) = session.run(FIBONACCI_ELF).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(second, first);
+        assert!(diff.is_empty());
+    }
+}
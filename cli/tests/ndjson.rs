@@ -0,0 +1,107 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use clap::Parser;
+use sp1_cli::commands::prove::ProveCmd;
+use sp1_cli::events::{exit_code, Event, EventSink, Failure, FailureKind};
+
+const FIBONACCI_ELF: &[u8] =
+    include_bytes!("../../examples/fibonacci/program/elf/riscv32im-succinct-zkvm-elf");
+
+/// A writer whose bytes are read back by the test.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_prove_ndjson() {
+    let dir = std::env::temp_dir().join(format!("sp1-ndjson-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let output = dir.join("proof.json");
+    let cmd = ProveCmd::try_parse_from([
+        "prove",
+        "--log-format",
+        "ndjson",
+        "--output",
+        output.to_str().unwrap(),
+    ])
+    .unwrap();
+
+    let buffer = SharedBuffer::default();
+    let events = Arc::new(EventSink::new(buffer.clone()));
+    cmd.prove_elf(FIBONACCI_ELF, Some(events)).unwrap();
+
+    let stream = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+    let lines = stream.lines().collect::<Vec<_>>();
+    let types = lines
+        .iter()
+        .map(|line| {
+            let value = serde_json::from_str::<serde_json::Value>(line).unwrap();
+            value["type"].as_str().unwrap().to_string()
+        })
+        .collect::<Vec<_>>();
+    let events = lines
+        .iter()
+        .map(|line| serde_json::from_str::<Event>(line).unwrap())
+        .collect::<Vec<_>>();
+
+    // Every shard starts before it is done, and the proving phase and the summary come last.
+    let num_shards = types.iter().filter(|ty| *ty == "shard_start").count();
+    assert!(num_shards > 0);
+    assert_eq!(types.len(), 2 * num_shards + 2);
+    for index in 0..num_shards {
+        let start = events.iter().position(
+            |event| matches!(event, Event::ShardStart { index: i, num_shards: n } if *i == index && *n == num_shards),
+        );
+        let done = events.iter().position(
+            |event| matches!(event, Event::ShardDone { index: i, num_shards: n, .. } if *i == index && *n == num_shards),
+        );
+        assert!(start.unwrap() < done.unwrap());
+    }
+    assert_eq!(types[types.len() - 2], "phase");
+    let Event::Summary {
+        num_shards: summary_shards,
+        instructions,
+        proof_path,
+        proof_size,
+        manifest_path,
+        ..
+    } = events.last().unwrap()
+    else {
+        panic!("the stream does not end with a summary: {}", stream);
+    };
+    assert_eq!(*summary_shards, num_shards);
+    assert!(*instructions > 0);
+    assert_eq!(proof_path.as_deref(), output.to_str());
+    assert_eq!(*proof_size, Some(std::fs::metadata(&output).unwrap().len()));
+    assert!(std::path::Path::new(manifest_path.as_ref().unwrap()).exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_error_event() {
+    let err = anyhow::Error::new(Failure::new(
+        FailureKind::Verification,
+        anyhow::anyhow!("bad proof"),
+    ));
+    assert_eq!(exit_code(&err), 5);
+    let line = serde_json::to_string(&Event::error(&err)).unwrap();
+    assert_eq!(
+        line,
+        r#"{"type":"error","kind":"verification","message":"bad proof","exit_code":5}"#
+    );
+    assert_eq!(exit_code(&anyhow::anyhow!("anything else")), 1);
+    assert_eq!(FailureKind::Build.exit_code(), 2);
+    assert_eq!(FailureKind::Execution.exit_code(), 3);
+    assert_eq!(FailureKind::Proving.exit_code(), 4);
+}
@@ -0,0 +1,27 @@
+use clap::Parser;
+use sp1_cli::commands::prove::ProveCmd;
+
+const FIBONACCI_ELF: &[u8] =
+    include_bytes!("../../examples/fibonacci/program/elf/riscv32im-succinct-zkvm-elf");
+
+#[test]
+fn test_prove_profile_out() {
+    let dir = std::env::temp_dir().join(format!("sp1-profile-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let profile = dir.join("fibonacci.folded");
+
+    let cmd =
+        ProveCmd::try_parse_from(["prove", "--profile-out", profile.to_str().unwrap()]).unwrap();
+    cmd.prove_elf(FIBONACCI_ELF, None).unwrap();
+
+    // Every line is a stack of functions and the cycles spent in its innermost function.
+    let folded = std::fs::read_to_string(&profile).unwrap();
+    assert!(folded.lines().count() > 0);
+    for line in folded.lines() {
+        let (stack, cycles) = line.rsplit_once(' ').unwrap();
+        assert!(!stack.is_empty());
+        assert!(cycles.parse::<u64>().unwrap() > 0);
+    }
+    assert!(folded.contains("main"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,62 @@
+use clap::Parser;
+use sp1_cli::commands::prove::ProveCmd;
+
+const IO_ELF: &[u8] = include_bytes!("../../examples/io/program/elf/riscv32im-succinct-zkvm-elf");
+
+/// The bincode of a point of the `io` example, whose `usize` fields are encoded as `u64`.
+fn point(x: u64, y: u64, b: bool) -> Vec<u8> {
+    [&x.to_le_bytes()[..], &y.to_le_bytes()[..], &[b as u8]].concat()
+}
+
+#[test]
+fn test_prove_input_output() {
+    let dir = std::env::temp_dir().join(format!("sp1-prove-io-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("input.bin");
+    let stdout = dir.join("stdout.bin");
+    std::fs::write(&input, point(3, 7, true)).unwrap();
+
+    // The program adds the two points it reads, so adding the origin echoes the first one.
+    let origin = hex::encode(point(0, 0, true));
+    let cmd = ProveCmd::try_parse_from([
+        "prove",
+        "--input",
+        input.to_str().unwrap(),
+        "--input-hex",
+        &format!("0x{}", origin),
+        "--stdout",
+        stdout.to_str().unwrap(),
+    ])
+    .unwrap();
+    cmd.prove_elf(IO_ELF, None).unwrap();
+    assert_eq!(
+        std::fs::read(&stdout).unwrap(),
+        std::fs::read(&input).unwrap()
+    );
+
+    // Several `--input` arguments are appended in order.
+    let origin_path = dir.join("origin.bin");
+    std::fs::write(&origin_path, point(0, 0, true)).unwrap();
+    let cmd = ProveCmd::try_parse_from([
+        "prove",
+        "--input",
+        input.to_str().unwrap(),
+        "--input",
+        origin_path.to_str().unwrap(),
+        "--stdout",
+        stdout.to_str().unwrap(),
+    ])
+    .unwrap();
+    cmd.prove_elf(IO_ELF, None).unwrap();
+    assert_eq!(std::fs::read(&stdout).unwrap(), point(3, 7, true));
+
+    assert!(ProveCmd::try_parse_from(["prove", "--input-hex", "0xzz"]).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_shard_size_argument() {
+    assert!(ProveCmd::try_parse_from(["prove", "--shard-size", "131072"]).is_ok());
+    assert!(ProveCmd::try_parse_from(["prove", "--shard-size", "100000"]).is_err());
+    assert!(ProveCmd::try_parse_from(["prove", "--shard-size", "0"]).is_err());
+}
@@ -1,6 +1,17 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use sp1_core::runtime::{Program, Runtime};
-use sp1_core::utils::prove;
+use p3_air::BaseAir;
+use p3_baby_bear::BabyBear;
+use p3_field::extension::BinomialExtensionField;
+use p3_matrix::dense::RowMajorMatrix;
+use rand::{thread_rng, Rng};
+use sp1_core::air::MachineAir;
+use sp1_core::cpu::CpuChip;
+use sp1_core::runtime::{ExecutionRecord, Program, Runtime};
+use sp1_core::stark::{Chip, RiscvAir};
+use sp1_core::syscall::precompiles::sha256::ShaExtendChip;
+use sp1_core::utils::test_utils::RecordBuilder;
+use sp1_core::utils::{prove, BabyBearPoseidon2, Poseidon2Params};
+use sp1_core::{SP1Prover, SP1Stdin, SP1Verifier};
 
 #[allow(unreachable_code)]
 pub fn criterion_benchmark(c: &mut Criterion) {
@@ -24,7 +35,125 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         );
     }
     group.finish();
+
+    let mut group = c.benchmark_group("execute");
+    group.sample_size(10);
+    for p in programs {
+        let elf_path = format!("../programs/demo/{}/elf/riscv32im-succinct-zkvm-elf", p);
+        group.bench_function(format!("from_elf:{}", p), |b| {
+            b.iter(|| {
+                let mut runtime = Runtime::new(Program::from_elf(&elf_path));
+                runtime.run();
+            })
+        });
+        let mut runtime = Runtime::new(Program::from_elf(&elf_path));
+        group.bench_function(format!("reset:{}", p), |b| {
+            b.iter(|| {
+                runtime.reset(black_box(&[]));
+                runtime.run();
+            })
+        });
+    }
+    group.finish();
+}
+
+pub fn permutation_benchmark(c: &mut Criterion) {
+    type EF = BinomialExtensionField<BabyBear, 4>;
+
+    let mut rng = thread_rng();
+    let chip = Chip::new(RiscvAir::<BabyBear>::Cpu(CpuChip::default()));
+    let height = 1 << 22;
+    let width = chip.width();
+    let main = RowMajorMatrix::new(
+        (0..height * width)
+            .map(|_| rng.gen())
+            .collect::<Vec<BabyBear>>(),
+        width,
+    );
+    let random_elements: [EF; 2] = [rng.gen(), rng.gen()];
+
+    let mut group = c.benchmark_group("permutation");
+    group.sample_size(10);
+    group.bench_function(format!("cpu:{}", height), |b| {
+        b.iter(|| chip.generate_permutation_trace(None, black_box(&main), &random_elements))
+    });
+    group.finish();
+}
+
+pub fn sha_extend_benchmark(c: &mut Criterion) {
+    // Hashing 1 MB takes one SHA extend call per 64-byte block.
+    let nb_blocks = (1 << 20) / 64;
+    let mut rng = thread_rng();
+    let mut builder = RecordBuilder::new();
+    for i in 0..nb_blocks {
+        let mut w = [0u32; 64];
+        w[..16].iter_mut().for_each(|w_i| *w_i = rng.gen());
+        builder.sha_extend(0x1000 + (i as u32 % 1024) * 0x100, w);
+    }
+    let shard = builder.build();
+
+    let mut group = c.benchmark_group("sha_extend");
+    group.sample_size(10);
+    group.bench_function(format!("generate_trace:{}", nb_blocks), |b| {
+        b.iter(|| {
+            let mut output = ExecutionRecord {
+                index: shard.index,
+                ..Default::default()
+            };
+            MachineAir::<BabyBear>::generate_trace(&ShaExtendChip::new(), &shard, &mut output)
+        })
+    });
+    group.finish();
+}
+
+pub fn config_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("config");
+    // Parameters other than the known sets are built on every call, as every config used to be.
+    group.bench_function("baby_bear_poseidon2:build", |b| {
+        b.iter(|| {
+            BabyBearPoseidon2::with_params(black_box(Poseidon2Params {
+                name: "uncached",
+                ..Poseidon2Params::default()
+            }))
+        })
+    });
+    group.bench_function("baby_bear_poseidon2:new", |b| {
+        b.iter(BabyBearPoseidon2::new)
+    });
+    group.finish();
+}
+
+pub fn batch_verify_benchmark(c: &mut Criterion) {
+    let nb_proofs = 8;
+    let elf = std::fs::read("../programs/demo/fibonacci/elf/riscv32im-succinct-zkvm-elf").unwrap();
+    let proof = SP1Prover::prove(&elf, SP1Stdin::new()).unwrap();
+    let proofs = vec![&proof; nb_proofs];
+
+    let mut group = c.benchmark_group("verify");
+    group.sample_size(10);
+    group.bench_function(format!("naive:{}", nb_proofs), |b| {
+        b.iter(|| {
+            for proof in proofs.iter() {
+                SP1Verifier::verify(&elf, black_box(proof)).unwrap();
+            }
+        })
+    });
+    let batch = SP1Verifier::batch(&elf);
+    let proofs = proofs.iter().map(|proof| &proof.proof).collect::<Vec<_>>();
+    for parallel in [false, true] {
+        group.bench_function(format!("batch:{}:parallel={}", nb_proofs, parallel), |b| {
+            b.iter(|| batch.verify_all(black_box(&proofs), parallel))
+        });
+    }
+    group.finish();
 }
 
-criterion_group!(benches, criterion_benchmark);
+criterion_group!(
+    benches,
+    criterion_benchmark,
+    permutation_benchmark,
+    sha_extend_benchmark,
+    config_benchmark,
+    batch_verify_benchmark
+);
 criterion_main!(benches);
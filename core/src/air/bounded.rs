@@ -0,0 +1,218 @@
+use core::ops::{Add, Mul, Neg, Sub};
+use std::iter::Sum;
+
+use num::BigUint;
+#[cfg(debug_assertions)]
+use num::ToPrimitive;
+use p3_field::AbstractField;
+
+/// An expression with the range of the integer it evaluates to on a valid trace, before any
+/// reduction modulo the field.
+///
+/// Constraints only compare expressions modulo the field, so an equality of integers whose
+/// difference can reach the modulus also holds for integers which differ by it. The range is
+/// propagated through `+`, `-` and `*`, and checked when asserting with
+/// [`BaseAirBuilder::assert_bounded_eq`](super::BaseAirBuilder::assert_bounded_eq), which panics
+/// when the constraints are built. The range is only tracked in debug builds: in release builds a
+/// `BoundedExpr` is just its expression.
+#[derive(Clone, Debug)]
+#[cfg_attr(not(debug_assertions), repr(transparent))]
+pub struct BoundedExpr<E> {
+    expr: E,
+    #[cfg(debug_assertions)]
+    min: i128,
+    #[cfg(debug_assertions)]
+    max: i128,
+}
+
+impl<E: AbstractField> BoundedExpr<E> {
+    /// An expression which evaluates to an integer between `min` and `max`, both included.
+    #[allow(unused_variables)]
+    pub fn new(expr: impl Into<E>, min: i128, max: i128) -> Self {
+        debug_assert!(min <= max);
+        Self {
+            expr: expr.into(),
+            #[cfg(debug_assertions)]
+            min,
+            #[cfg(debug_assertions)]
+            max,
+        }
+    }
+
+    /// An expression range checked to `bits` bits.
+    pub fn bits(expr: impl Into<E>, bits: u32) -> Self {
+        Self::new(expr, 0, (1i128 << bits) - 1)
+    }
+
+    /// An expression range checked to a byte.
+    pub fn byte(expr: impl Into<E>) -> Self {
+        Self::bits(expr, 8)
+    }
+
+    /// An expression constrained to be boolean.
+    pub fn bool(expr: impl Into<E>) -> Self {
+        Self::bits(expr, 1)
+    }
+
+    pub fn constant(value: u32) -> Self {
+        Self::new(E::from_canonical_u32(value), value as i128, value as i128)
+    }
+
+    pub fn zero() -> Self {
+        Self::constant(0)
+    }
+
+    pub fn into_expr(self) -> E {
+        self.expr
+    }
+
+    /// The smallest and largest integers the expression evaluates to.
+    #[cfg(debug_assertions)]
+    pub fn range(&self) -> (i128, i128) {
+        (self.min, self.max)
+    }
+
+    /// Panics if the expression and `other` can be equal modulo `modulus` without being equal
+    /// integers, i.e. if their difference can reach a nonzero multiple of the modulus.
+    ///
+    /// Does nothing in release builds.
+    #[allow(unused_variables)]
+    pub fn check_eq(&self, other: &Self, modulus: &BigUint) {
+        #[cfg(debug_assertions)]
+        {
+            let modulus = modulus.to_i128().unwrap_or(i128::MAX);
+            let max_difference = self.max.saturating_sub(other.min);
+            let min_difference = self.min.saturating_sub(other.max);
+            if max_difference >= modulus || min_difference <= -modulus {
+                panic!(
+                    "an equality between integers in [{}, {}] and [{}, {}] can wrap around the \
+                     modulus {}",
+                    self.min, self.max, other.min, other.max, modulus
+                );
+            }
+        }
+    }
+}
+
+impl<E: AbstractField> Add for BoundedExpr<E> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            expr: self.expr + rhs.expr,
+            #[cfg(debug_assertions)]
+            min: self.min.saturating_add(rhs.min),
+            #[cfg(debug_assertions)]
+            max: self.max.saturating_add(rhs.max),
+        }
+    }
+}
+
+impl<E: AbstractField> Sub for BoundedExpr<E> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self + -rhs
+    }
+}
+
+impl<E: AbstractField> Neg for BoundedExpr<E> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            expr: -self.expr,
+            #[cfg(debug_assertions)]
+            min: self.max.saturating_neg(),
+            #[cfg(debug_assertions)]
+            max: self.min.saturating_neg(),
+        }
+    }
+}
+
+impl<E: AbstractField> Mul for BoundedExpr<E> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        #[cfg(debug_assertions)]
+        let products = [
+            self.min.saturating_mul(rhs.min),
+            self.min.saturating_mul(rhs.max),
+            self.max.saturating_mul(rhs.min),
+            self.max.saturating_mul(rhs.max),
+        ];
+        Self {
+            expr: self.expr * rhs.expr,
+            #[cfg(debug_assertions)]
+            min: products.into_iter().min().unwrap(),
+            #[cfg(debug_assertions)]
+            max: products.into_iter().max().unwrap(),
+        }
+    }
+}
+
+/// Multiplication by a constant.
+impl<E: AbstractField> Mul<u32> for BoundedExpr<E> {
+    type Output = Self;
+
+    fn mul(self, rhs: u32) -> Self {
+        self * Self::constant(rhs)
+    }
+}
+
+impl<E: AbstractField> Sum for BoundedExpr<E> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |sum, x| sum + x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+    use p3_field::Field;
+
+    use super::*;
+
+    type Expr = BoundedExpr<BabyBear>;
+
+    fn byte(value: u32) -> Expr {
+        Expr::byte(BabyBear::from_canonical_u32(value))
+    }
+
+    #[test]
+    fn test_bounded_expr() {
+        let sum = byte(200) + byte(100) * 256 - Expr::bool(BabyBear::one());
+        assert_eq!(sum.clone().into_expr(), BabyBear::from_canonical_u32(25799));
+        #[cfg(debug_assertions)]
+        assert_eq!(sum.range(), (-1, 255 + 255 * 256));
+
+        // A carry chain over 16 bytes stays far from the modulus.
+        let modulus = BabyBear::order();
+        let sum = (0..16).map(byte).sum::<Expr>() + Expr::bits(BabyBear::one(), 4);
+        sum.check_eq(&(byte(0) + Expr::bits(BabyBear::zero(), 5) * 256), &modulus);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "can wrap around the modulus")]
+    fn test_over_bounded_sum() {
+        // 16 summands of 27 bits each reach past the 31 bit modulus.
+        let sum = (0..16)
+            .map(|_| Expr::bits(BabyBear::one(), 27))
+            .sum::<Expr>();
+        sum.check_eq(&Expr::zero(), &BabyBear::order());
+    }
+
+    #[test]
+    #[cfg(not(debug_assertions))]
+    fn test_no_overhead() {
+        assert_eq!(
+            core::mem::size_of::<Expr>(),
+            core::mem::size_of::<BabyBear>()
+        );
+        let sum = (0..16)
+            .map(|_| Expr::bits(BabyBear::one(), 27))
+            .sum::<Expr>();
+        sum.check_eq(&Expr::zero(), &BabyBear::order());
+    }
+}
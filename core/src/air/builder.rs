@@ -2,11 +2,13 @@ use p3_air::PermutationAirBuilder;
 use p3_air::{AirBuilder, FilteredAirBuilder};
 use p3_uni_stark::{ProverConstraintFolder, SymbolicAirBuilder, VerifierConstraintFolder};
 
-use super::interaction::AirInteraction;
-use super::word::Word;
+use super::bounded::BoundedExpr;
+use super::interaction::{AirInteraction, IntoInteractionValues};
+use super::public_values::PublicValuesBuilder;
+use super::word::{DoubleWord, Word};
 use crate::cpu::columns::InstructionCols;
 use crate::cpu::columns::OpcodeSelectorCols;
-use crate::lookup::InteractionKind;
+use crate::lookup::{InteractionKind, InteractionTag};
 use crate::{bytes::ByteOpcode, memory::MemoryCols};
 use p3_field::{AbstractField, Field};
 
@@ -53,6 +55,79 @@ pub trait BaseAirBuilder: AirBuilder + MessageBuilder<AirInteraction<Self::Expr>
             self.assert_eq(left, right);
         }
     }
+
+    /// Asserts that two bounded expressions are equal.
+    ///
+    /// In debug builds, panics if the equality can hold modulo the field for integers which
+    /// differ, i.e. if the ranges of the expressions are too wide for the field.
+    fn assert_bounded_eq(&mut self, left: BoundedExpr<Self::Expr>, right: BoundedExpr<Self::Expr>) {
+        #[cfg(debug_assertions)]
+        left.check_eq(&right, &Self::F::order());
+        self.assert_eq(left.into_expr(), right.into_expr());
+    }
+
+    /// Asserts that a bounded expression is zero, see [`BaseAirBuilder::assert_bounded_eq`].
+    fn assert_bounded_zero(&mut self, expr: BoundedExpr<Self::Expr>) {
+        self.assert_bounded_eq(expr, BoundedExpr::zero());
+    }
+
+    /// Sends the flattened `values` as an interaction of the given kind.
+    fn send_values<V, EMult>(&mut self, kind: InteractionKind, values: V, multiplicity: EMult)
+    where
+        V: IntoInteractionValues<Self::Expr>,
+        EMult: Into<Self::Expr>,
+    {
+        self.send(AirInteraction::new(
+            values.into_interaction_values(),
+            multiplicity.into(),
+            kind,
+        ));
+    }
+
+    /// Receives the flattened `values` as an interaction of the given kind.
+    fn receive_values<V, EMult>(&mut self, kind: InteractionKind, values: V, multiplicity: EMult)
+    where
+        V: IntoInteractionValues<Self::Expr>,
+        EMult: Into<Self::Expr>,
+    {
+        self.receive(AirInteraction::new(
+            values.into_interaction_values(),
+            multiplicity.into(),
+            kind,
+        ));
+    }
+
+    /// Sends the flattened `values`, led by the tag of their type of message, as an interaction of
+    /// the given kind.
+    fn send_tagged_values<V, EMult>(
+        &mut self,
+        kind: InteractionKind,
+        tag: InteractionTag,
+        values: V,
+        multiplicity: EMult,
+    ) where
+        V: IntoInteractionValues<Self::Expr>,
+        EMult: Into<Self::Expr>,
+    {
+        let tag = Self::Expr::from_canonical_u32(tag as u32);
+        self.send_values(kind, ([tag], values), multiplicity);
+    }
+
+    /// Receives the flattened `values`, led by the tag of their type of message, as an interaction
+    /// of the given kind.
+    fn receive_tagged_values<V, EMult>(
+        &mut self,
+        kind: InteractionKind,
+        tag: InteractionTag,
+        values: V,
+        multiplicity: EMult,
+    ) where
+        V: IntoInteractionValues<Self::Expr>,
+        EMult: Into<Self::Expr>,
+    {
+        let tag = Self::Expr::from_canonical_u32(tag as u32);
+        self.receive_values(kind, ([tag], values), multiplicity);
+    }
 }
 
 /// A trait which contains methods for byte interactions in an AIR.
@@ -152,11 +227,12 @@ pub trait FieldAirBuilder: BaseAirBuilder {
         Ec: Into<Self::Expr>,
         EMult: Into<Self::Expr>,
     {
-        self.send(AirInteraction::new(
-            vec![a.into(), b.into(), c.into()],
-            multiplicity.into(),
+        self.send_tagged_values(
             InteractionKind::Field,
-        ));
+            InteractionTag::FieldLtu,
+            [a.into(), b.into(), c.into()],
+            multiplicity,
+        );
     }
 
     /// Receives a field operation to be processed.
@@ -166,11 +242,44 @@ pub trait FieldAirBuilder: BaseAirBuilder {
         Eb: Into<Self::Expr>,
         Ec: Into<Self::Expr>,
         EMult: Into<Self::Expr>,
+    {
+        self.receive_tagged_values(
+            InteractionKind::Field,
+            InteractionTag::FieldLtu,
+            [a.into(), b.into(), c.into()],
+            multiplicity,
+        );
+    }
+}
+
+/// A trait which contains methods for range check interactions in an AIR.
+pub trait RangeAirBuilder: BaseAirBuilder {
+    /// Sends a range check of `value` to fit in `bits` bits, which must be one of the widths of
+    /// `RANGE_CHECK_BITS`.
+    fn send_range_check<Ev, Eb, EMult>(&mut self, value: Ev, bits: Eb, multiplicity: EMult)
+    where
+        Ev: Into<Self::Expr>,
+        Eb: Into<Self::Expr>,
+        EMult: Into<Self::Expr>,
+    {
+        self.send(AirInteraction::new(
+            vec![value.into(), bits.into()],
+            multiplicity.into(),
+            InteractionKind::Range,
+        ));
+    }
+
+    /// Receives a range check of `value` to fit in `bits` bits.
+    fn receive_range_check<Ev, Eb, EMult>(&mut self, value: Ev, bits: Eb, multiplicity: EMult)
+    where
+        Ev: Into<Self::Expr>,
+        Eb: Into<Self::Expr>,
+        EMult: Into<Self::Expr>,
     {
         self.receive(AirInteraction::new(
-            vec![a.into(), b.into(), c.into()],
+            vec![value.into(), bits.into()],
             multiplicity.into(),
-            InteractionKind::Field,
+            InteractionKind::Range,
         ));
     }
 }
@@ -184,6 +293,51 @@ pub trait WordAirBuilder: ByteAirBuilder {
         }
     }
 
+    /// Asserts that the two double words are equal.
+    fn assert_double_word_eq<I: Into<Self::Expr>>(
+        &mut self,
+        left: DoubleWord<I>,
+        right: DoubleWord<I>,
+    ) {
+        for (left, right) in left.0.into_iter().zip(right.0) {
+            self.assert_eq(left, right);
+        }
+    }
+
+    /// Splits a double word into its low and its high word.
+    fn double_word_to_words<I: Into<Self::Expr>>(
+        &self,
+        double_word: DoubleWord<I>,
+    ) -> [Word<Self::Expr>; 2] {
+        double_word.map(Into::into).to_words()
+    }
+
+    /// Check that each limb of the double word is a u8.
+    fn range_check_double_word<I: Into<Self::Expr> + Copy, EMult: Into<Self::Expr> + Clone>(
+        &mut self,
+        double_word: DoubleWord<I>,
+        mult: EMult,
+    ) {
+        self.slice_range_check_u8(&double_word.0, mult);
+    }
+
+    /// Returns `a` if `flag` is one and `b` if `flag` is zero, limb by limb.
+    ///
+    /// The caller is responsible for constraining `flag` to be boolean.
+    fn select_word<EFlag, Ea, Eb>(&self, flag: EFlag, a: Word<Ea>, b: Word<Eb>) -> Word<Self::Expr>
+    where
+        EFlag: Into<Self::Expr>,
+        Ea: Into<Self::Expr>,
+        Eb: Into<Self::Expr>,
+    {
+        let flag = flag.into();
+        let mut b = b.0.into_iter();
+        a.map(|a| {
+            let b = b.next().unwrap().into();
+            b.clone() + flag.clone() * (a.into() - b)
+        })
+    }
+
     /// Check that each limb of the given slice is a u8.
     fn slice_range_check_u8<EWord: Into<Self::Expr> + Copy, EMult: Into<Self::Expr> + Clone>(
         &mut self,
@@ -247,17 +401,7 @@ pub trait AluAirBuilder: BaseAirBuilder {
         Ec: Into<Self::Expr>,
         EMult: Into<Self::Expr>,
     {
-        let values = once(opcode.into())
-            .chain(a.0.into_iter().map(Into::into))
-            .chain(b.0.into_iter().map(Into::into))
-            .chain(c.0.into_iter().map(Into::into))
-            .collect();
-
-        self.send(AirInteraction::new(
-            values,
-            multiplicity.into(),
-            InteractionKind::Alu,
-        ));
+        self.send_values(InteractionKind::Alu, ([opcode], a, b, c), multiplicity);
     }
 
     /// Receives an ALU operation to be processed.
@@ -275,17 +419,7 @@ pub trait AluAirBuilder: BaseAirBuilder {
         Ec: Into<Self::Expr>,
         EMult: Into<Self::Expr>,
     {
-        let values = once(opcode.into())
-            .chain(a.0.into_iter().map(Into::into))
-            .chain(b.0.into_iter().map(Into::into))
-            .chain(c.0.into_iter().map(Into::into))
-            .collect();
-
-        self.receive(AirInteraction::new(
-            values,
-            multiplicity.into(),
-            InteractionKind::Alu,
-        ));
+        self.receive_values(InteractionKind::Alu, ([opcode], a, b, c), multiplicity);
     }
 }
 
@@ -353,31 +487,27 @@ pub trait MemoryAirBuilder: BaseAirBuilder {
         );
 
         //// Check the previous and current memory access via a lookup to the memory table.
-        let addr_expr = addr.into();
-        let prev_values = once(prev_shard_expr)
-            .chain(once(prev_clk_expr))
-            .chain(once(addr_expr.clone()))
-            .chain(memory_access.prev_value().clone().map(Into::into))
-            .collect();
-        let current_values = once(current_shard_expr)
-            .chain(once(current_clk_expr))
-            .chain(once(addr_expr.clone()))
-            .chain(memory_access.value().clone().map(Into::into))
-            .collect();
+        let addr_expr: Self::Expr = addr.into();
 
         // The previous values get sent with multiplicity * 1, for "read".
-        self.send(AirInteraction::new(
-            prev_values,
-            verify_memory_access_expr.clone(),
+        self.send_values(
             InteractionKind::Memory,
-        ));
+            (
+                [prev_shard_expr, prev_clk_expr, addr_expr.clone()],
+                memory_access.prev_value().clone(),
+            ),
+            verify_memory_access_expr.clone(),
+        );
 
         // The current values get "received", i.e. multiplicity = -1
-        self.receive(AirInteraction::new(
-            current_values,
-            verify_memory_access_expr.clone(),
+        self.receive_values(
             InteractionKind::Memory,
-        ));
+            (
+                [current_shard_expr, current_clk_expr, addr_expr],
+                memory_access.value().clone(),
+            ),
+            verify_memory_access_expr,
+        );
     }
 
     /// Constraints a memory read or write to a slice of `MemoryAccessCols`.
@@ -462,6 +592,72 @@ pub trait ProgramAirBuilder: BaseAirBuilder {
     }
 }
 
+/// A trait which contains methods related to syscall interactions in an AIR.
+pub trait SyscallAirBuilder: BaseAirBuilder {
+    /// Sends a syscall from the `ECALL` which invokes it.
+    ///
+    /// `suspends` is one when the precompile suspends and the `ECALL` executes again, rather than
+    /// moving on to the next instruction.
+    fn send_syscall<EShard, EClk, ECode, EArg, ESuspends, EMult>(
+        &mut self,
+        shard: EShard,
+        clk: EClk,
+        syscall_code: ECode,
+        arg: EArg,
+        suspends: ESuspends,
+        multiplicity: EMult,
+    ) where
+        EShard: Into<Self::Expr>,
+        EClk: Into<Self::Expr>,
+        ECode: Into<Self::Expr>,
+        EArg: Into<Self::Expr>,
+        ESuspends: Into<Self::Expr>,
+        EMult: Into<Self::Expr>,
+    {
+        self.send_values(
+            InteractionKind::Syscall,
+            [
+                shard.into(),
+                clk.into(),
+                syscall_code.into(),
+                arg.into(),
+                suspends.into(),
+            ],
+            multiplicity,
+        );
+    }
+
+    /// Receives a syscall in the chip of its precompile.
+    fn receive_syscall<EShard, EClk, ECode, EArg, ESuspends, EMult>(
+        &mut self,
+        shard: EShard,
+        clk: EClk,
+        syscall_code: ECode,
+        arg: EArg,
+        suspends: ESuspends,
+        multiplicity: EMult,
+    ) where
+        EShard: Into<Self::Expr>,
+        EClk: Into<Self::Expr>,
+        ECode: Into<Self::Expr>,
+        EArg: Into<Self::Expr>,
+        ESuspends: Into<Self::Expr>,
+        EMult: Into<Self::Expr>,
+    {
+        self.receive_values(
+            InteractionKind::Syscall,
+            [
+                shard.into(),
+                clk.into(),
+                syscall_code.into(),
+                arg.into(),
+                suspends.into(),
+            ],
+            multiplicity,
+        );
+    }
+}
+
 pub trait MultiTableAirBuilder: PermutationAirBuilder {
     type Sum: Into<Self::ExprEF>;
 
@@ -472,10 +668,13 @@ pub trait MultiTableAirBuilder: PermutationAirBuilder {
 pub trait SP1AirBuilder:
     BaseAirBuilder
     + ByteAirBuilder
+    + RangeAirBuilder
     + WordAirBuilder
     + AluAirBuilder
     + MemoryAirBuilder
     + ProgramAirBuilder
+    + SyscallAirBuilder
+    + PublicValuesBuilder
 {
 }
 
@@ -489,17 +688,73 @@ impl<'a, AB: AirBuilder + MessageBuilder<M>, M> MessageBuilder<M> for FilteredAi
     }
 }
 
+impl<'a, AB: PublicValuesBuilder> PublicValuesBuilder for FilteredAirBuilder<'a, AB> {
+    type PublicVar = AB::PublicVar;
+
+    const STANDALONE: bool = AB::STANDALONE;
+
+    fn public_values(&self) -> &[Self::PublicVar] {
+        self.inner.public_values()
+    }
+}
+
 impl<AB: AirBuilder + MessageBuilder<AirInteraction<AB::Expr>>> BaseAirBuilder for AB {}
 impl<AB: AirBuilder + MessageBuilder<AirInteraction<AB::Expr>>> ByteAirBuilder for AB {}
 impl<AB: AirBuilder + MessageBuilder<AirInteraction<AB::Expr>>> FieldAirBuilder for AB {}
+impl<AB: AirBuilder + MessageBuilder<AirInteraction<AB::Expr>>> RangeAirBuilder for AB {}
 impl<AB: AirBuilder + MessageBuilder<AirInteraction<AB::Expr>>> WordAirBuilder for AB {}
 impl<AB: AirBuilder + MessageBuilder<AirInteraction<AB::Expr>>> AluAirBuilder for AB {}
 impl<AB: AirBuilder + MessageBuilder<AirInteraction<AB::Expr>>> MemoryAirBuilder for AB {}
 impl<AB: AirBuilder + MessageBuilder<AirInteraction<AB::Expr>>> ProgramAirBuilder for AB {}
-impl<AB: AirBuilder + MessageBuilder<AirInteraction<AB::Expr>>> SP1AirBuilder for AB {}
+impl<AB: AirBuilder + MessageBuilder<AirInteraction<AB::Expr>>> SyscallAirBuilder for AB {}
+impl<AB: AirBuilder + MessageBuilder<AirInteraction<AB::Expr>> + PublicValuesBuilder> SP1AirBuilder
+    for AB
+{
+}
 
 impl<'a, SC: StarkGenericConfig> EmptyMessageBuilder for ProverConstraintFolder<'a, SC> {}
 impl<'a, Challenge: Field> EmptyMessageBuilder for VerifierConstraintFolder<'a, Challenge> {}
 impl<F: Field> EmptyMessageBuilder for SymbolicAirBuilder<F> {}
 
 impl<'a, F: Field> EmptyMessageBuilder for p3_uni_stark::DebugConstraintBuilder<'a, F> {}
+
+// The uni-stark folders prove a single chip, which has no public values.
+impl<'a, SC: StarkGenericConfig> PublicValuesBuilder for ProverConstraintFolder<'a, SC> {
+    type PublicVar = Self::F;
+
+    const STANDALONE: bool = true;
+
+    fn public_values(&self) -> &[Self::PublicVar] {
+        &[]
+    }
+}
+
+impl<'a, Challenge: Field> PublicValuesBuilder for VerifierConstraintFolder<'a, Challenge> {
+    type PublicVar = Self::F;
+
+    const STANDALONE: bool = true;
+
+    fn public_values(&self) -> &[Self::PublicVar] {
+        &[]
+    }
+}
+
+impl<F: Field> PublicValuesBuilder for SymbolicAirBuilder<F> {
+    type PublicVar = Self::F;
+
+    const STANDALONE: bool = true;
+
+    fn public_values(&self) -> &[Self::PublicVar] {
+        &[]
+    }
+}
+
+impl<'a, F: Field> PublicValuesBuilder for p3_uni_stark::DebugConstraintBuilder<'a, F> {
+    type PublicVar = Self::F;
+
+    const STANDALONE: bool = true;
+
+    fn public_values(&self) -> &[Self::PublicVar] {
+        &[]
+    }
+}
@@ -0,0 +1,215 @@
+use core::borrow::{Borrow, BorrowMut};
+use std::mem::size_of;
+
+use p3_air::{AirBuilder, ExtensionBuilder};
+use p3_field::extension::{BinomialExtensionField, BinomiallyExtendable};
+use p3_field::{AbstractExtensionField, AbstractField, PrimeField32};
+use sp1_derive::AlignedBorrow;
+
+use super::{SP1AirBuilder, Word, WORD_SIZE};
+use crate::bytes::{ByteLookupEvent, ByteOpcode};
+use crate::runtime::ExecutionRecord;
+
+/// The degree of the extension field over the base field.
+pub const EXTENSION_DEGREE: usize = 4;
+
+/// The extension field whose coefficients are stored as blocks.
+pub type Extension<F> = BinomialExtensionField<F, EXTENSION_DEGREE>;
+
+/// Converts four words, each holding a canonical base field element, to the extension field
+/// element with these coefficients.
+pub fn ext_from_words<F>(words: [Word<F>; EXTENSION_DEGREE]) -> Extension<F>
+where
+    F: PrimeField32 + BinomiallyExtendable<EXTENSION_DEGREE>,
+{
+    Extension::<F>::from_base_slice(&words.map(|word| F::from_canonical_u32(word.to_u32())))
+}
+
+/// Converts an extension field element to the words of its canonical coefficients.
+pub fn words_from_ext<F>(ext: Extension<F>) -> [Word<F>; EXTENSION_DEGREE]
+where
+    F: PrimeField32 + BinomiallyExtendable<EXTENSION_DEGREE>,
+{
+    let coefficients: &[F] = ext.as_base_slice();
+    core::array::from_fn(|i| Word::from(coefficients[i].as_canonical_u32()))
+}
+
+/// An extension field element represented in an AIR by its coefficients.
+#[derive(AlignedBorrow, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+pub struct Block<T>(pub [T; EXTENSION_DEGREE]);
+
+impl<T> Block<T> {
+    /// Applies `f` to each coefficient of the block.
+    pub fn map<F, S>(self, f: F) -> Block<S>
+    where
+        F: FnMut(T) -> S,
+    {
+        Block(self.0.map(f))
+    }
+}
+
+impl<V: Copy> Block<V> {
+    /// The block whose coefficients are the values of the given words.
+    pub fn from_words<AB: AirBuilder<Var = V>>(
+        words: &[Word<V>; EXTENSION_DEGREE],
+    ) -> Block<AB::Expr> {
+        Block(core::array::from_fn(|i| words[i].reduce::<AB>()))
+    }
+}
+
+impl<F: BinomiallyExtendable<EXTENSION_DEGREE>> From<Extension<F>> for Block<F> {
+    fn from(ext: Extension<F>) -> Self {
+        let coefficients: &[F] = ext.as_base_slice();
+        Block(core::array::from_fn(|i| coefficients[i]))
+    }
+}
+
+impl<F: BinomiallyExtendable<EXTENSION_DEGREE>> From<Block<F>> for Extension<F> {
+    fn from(block: Block<F>) -> Self {
+        Extension::<F>::from_base_slice(&block.0)
+    }
+}
+
+/// Converts base field expressions to an extension field expression in constraints.
+pub trait AsExtension<AB: ExtensionBuilder> {
+    fn as_extension(&self) -> AB::ExprEF;
+}
+
+impl<AB: ExtensionBuilder, T: Into<AB::Expr> + Clone> AsExtension<AB> for Block<T> {
+    fn as_extension(&self) -> AB::ExprEF {
+        AB::ExprEF::from_base_slice(&self.0.clone().map(Into::into))
+    }
+}
+
+impl<AB: ExtensionBuilder> AsExtension<AB> for [Word<AB::Var>; EXTENSION_DEGREE] {
+    fn as_extension(&self) -> AB::ExprEF {
+        <Block<AB::Expr> as AsExtension<AB>>::as_extension(&Block::from_words::<AB>(self))
+    }
+}
+
+/// The columns decomposing a block into the words of its canonical coefficients.
+///
+/// The modulus must be of the form `m * 2^24 + 1`, as the one of BabyBear is, so that a word is
+/// canonical if and only if its most significant byte is below `m`, or is `m` with the other bytes
+/// zero.
+#[derive(AlignedBorrow, Default, Debug, Clone, Copy)]
+#[repr(C)]
+pub struct BlockDecomposition<T> {
+    /// The coefficients of the block as words.
+    pub words: [Word<T>; EXTENSION_DEGREE],
+
+    /// Whether the most significant byte of each word is below the one of the modulus.
+    pub is_msb_lt_modulus: [T; EXTENSION_DEGREE],
+}
+
+impl<F: PrimeField32> BlockDecomposition<F> {
+    pub fn populate(&mut self, record: &mut ExecutionRecord, block: Block<F>) {
+        let modulus_msb = F::ORDER_U32 >> 24;
+        for (i, coefficient) in block.0.iter().enumerate() {
+            let bytes = coefficient.as_canonical_u32().to_le_bytes();
+            self.words[i] = Word(bytes.map(F::from_canonical_u8));
+
+            let is_msb_lt_modulus = (bytes[3] as u32) < modulus_msb;
+            self.is_msb_lt_modulus[i] = F::from_bool(is_msb_lt_modulus);
+            record.add_byte_lookup_event(ByteLookupEvent::new(
+                ByteOpcode::LTU,
+                is_msb_lt_modulus as u32,
+                0,
+                bytes[3] as u32,
+                modulus_msb,
+            ));
+            for pair in bytes.chunks_exact(2) {
+                record.add_byte_lookup_event(ByteLookupEvent::new(
+                    ByteOpcode::U8Range,
+                    0,
+                    0,
+                    pair[0] as u32,
+                    pair[1] as u32,
+                ));
+            }
+        }
+    }
+}
+
+impl<V: Copy> BlockDecomposition<V> {
+    /// Constrains the words to be the canonical decompositions of the coefficients of `block`.
+    pub fn eval<AB: SP1AirBuilder<Var = V>>(
+        builder: &mut AB,
+        block: Block<AB::Expr>,
+        cols: BlockDecomposition<V>,
+        is_real: AB::Var,
+    ) where
+        AB::F: PrimeField32,
+    {
+        debug_assert_eq!(AB::F::ORDER_U32 & 0x00ff_ffff, 1);
+        let modulus_msb = AB::F::from_canonical_u32(AB::F::ORDER_U32 >> 24);
+        for (i, coefficient) in block.0.into_iter().enumerate() {
+            let word = cols.words[i];
+            let is_msb_lt_modulus = cols.is_msb_lt_modulus[i];
+            builder.slice_range_check_u8(&word.0, is_real);
+            builder.send_byte(
+                AB::F::from_canonical_u32(ByteOpcode::LTU as u32),
+                is_msb_lt_modulus,
+                word[WORD_SIZE - 1],
+                modulus_msb,
+                is_real,
+            );
+
+            let mut builder_is_real = builder.when(is_real);
+            builder_is_real.assert_eq(coefficient, word.reduce::<AB>());
+
+            // A most significant byte which is not below the modulus must equal it, and the other
+            // bytes must then be zero.
+            let mut builder_msb_not_lt =
+                builder_is_real.when_ne(is_msb_lt_modulus, AB::Expr::one());
+            builder_msb_not_lt.assert_eq(word[WORD_SIZE - 1], modulus_msb);
+            builder_msb_not_lt.assert_zero(word[0] + word[1] + word[2]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+    use p3_field::{AbstractExtensionField, AbstractField, PrimeField32};
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+
+    fn random_ext() -> Extension<BabyBear> {
+        let mut rng = thread_rng();
+        Extension::<BabyBear>::from_base_fn(|_| {
+            BabyBear::from_canonical_u32(rng.gen_range(0..BabyBear::ORDER_U32))
+        })
+    }
+
+    #[test]
+    fn test_words_round_trip() {
+        for _ in 0..100 {
+            let ext = random_ext();
+            let words = words_from_ext(ext);
+            assert_eq!(ext_from_words(words), ext);
+            assert_eq!(Extension::from(Block::from(ext)), ext);
+        }
+
+        let max = Extension::<BabyBear>::from_base(BabyBear::neg_one());
+        assert_eq!(words_from_ext(max)[0], Word::from(BabyBear::ORDER_U32 - 1));
+        assert_eq!(ext_from_words(words_from_ext(max)), max);
+    }
+
+    #[test]
+    fn test_populate_decomposition() {
+        for _ in 0..100 {
+            let ext = random_ext();
+            let mut record = ExecutionRecord::default();
+            let mut cols = BlockDecomposition::<BabyBear>::default();
+            cols.populate(&mut record, Block::from(ext));
+            assert_eq!(ext_from_words(cols.words), ext);
+            for (word, is_msb_lt_modulus) in cols.words.iter().zip(cols.is_msb_lt_modulus) {
+                let expected = word.to_u32() >> 24 < BabyBear::ORDER_U32 >> 24;
+                assert_eq!(is_msb_lt_modulus, BabyBear::from_bool(expected));
+            }
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use super::Word;
 use crate::lookup::InteractionKind;
 
 /// An interaction is a cross-table lookup.
@@ -17,3 +18,57 @@ impl<E> AirInteraction<E> {
         }
     }
 }
+
+/// Values which are flattened into the values of an interaction.
+///
+/// This fixes the order in which senders and receivers lay out a message: words limb by limb from
+/// the least significant, arrays and vectors element by element, and tuples field by field.
+pub trait IntoInteractionValues<E> {
+    /// Appends the flattened values to `values`.
+    fn extend_values(self, values: &mut Vec<E>);
+
+    /// Returns the flattened values.
+    fn into_interaction_values(self) -> Vec<E>
+    where
+        Self: Sized,
+    {
+        let mut values = Vec::new();
+        self.extend_values(&mut values);
+        values
+    }
+}
+
+impl<E, T: Into<E>> IntoInteractionValues<E> for Word<T> {
+    fn extend_values(self, values: &mut Vec<E>) {
+        self.0.extend_values(values);
+    }
+}
+
+impl<E, T: Into<E>, const N: usize> IntoInteractionValues<E> for [T; N] {
+    fn extend_values(self, values: &mut Vec<E>) {
+        values.extend(self.into_iter().map(Into::into));
+    }
+}
+
+impl<E, T: Into<E>> IntoInteractionValues<E> for Vec<T> {
+    fn extend_values(self, values: &mut Vec<E>) {
+        values.extend(self.into_iter().map(Into::into));
+    }
+}
+
+macro_rules! impl_into_interaction_values_for_tuple {
+    ($($name:ident),+) => {
+        impl<E, $($name: IntoInteractionValues<E>),+> IntoInteractionValues<E> for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn extend_values(self, values: &mut Vec<E>) {
+                let ($($name,)+) = self;
+                $($name.extend_values(values);)+
+            }
+        }
+    };
+}
+
+impl_into_interaction_values_for_tuple!(A, B);
+impl_into_interaction_values_for_tuple!(A, B, C);
+impl_into_interaction_values_for_tuple!(A, B, C, D);
+impl_into_interaction_values_for_tuple!(A, B, C, D, G);
@@ -1,11 +1,45 @@
+use core::fmt::{Display, Formatter};
+
 use p3_air::BaseAir;
-use p3_field::Field;
-use p3_matrix::dense::RowMajorMatrix;
+use p3_field::{Field, PrimeField32};
+use p3_matrix::{dense::RowMajorMatrix, Matrix};
+use serde::{Deserialize, Serialize};
 
 use crate::runtime::{ExecutionRecord, Program};
 
 pub use sp1_derive::MachineAir;
 
+/// A kind of event of an execution record, which a chip turns into rows of its trace or emits for
+/// other chips while generating its trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum EventKind {
+    Cpu,
+    Add,
+    Sub,
+    Bitwise,
+    Mul,
+    DivRem,
+    Lt,
+    ShiftLeft,
+    ShiftRight,
+    ByteLookup,
+    RangeCheck,
+    Field,
+    Memory,
+    ShaExtend,
+    ShaCompress,
+    KeccakPermute,
+    EdAdd,
+    EdDecompress,
+    WeierstrassAdd,
+    WeierstrassDouble,
+    K256Decompress,
+    Blake3CompressInner,
+    BitManip,
+    Memcpy,
+    Uint256MulMod,
+}
+
 /// An AIR that is part of a Risc-V AIR arithmetization.
 pub trait MachineAir<F: Field>: BaseAir<F> {
     /// A unique identifier for this AIR as part of a machine.
@@ -22,6 +56,43 @@ pub trait MachineAir<F: Field>: BaseAir<F> {
         output: &mut ExecutionRecord,
     ) -> RowMajorMatrix<F>;
 
+    /// Generate the trace for a given execution record into `trace`, like `generate_trace`.
+    ///
+    /// Chips with large traces override this to resize `trace` to its padded height and write
+    /// every row, padding included, in place, rather than collecting the rows before flattening
+    /// them into a second allocation. The allocation of `trace` is reused when it is big enough.
+    fn generate_trace_into(
+        &self,
+        input: &ExecutionRecord,
+        output: &mut ExecutionRecord,
+        trace: &mut RowMajorMatrix<F>,
+    ) {
+        *trace = self.generate_trace(input, output);
+    }
+
+    /// The number of rows of the trace for the given shard which hold events rather than padding,
+    /// counted without generating the trace.
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize;
+
+    /// Whether the shard has events for this AIR, i.e. whether its trace is generated and proven
+    /// for the shard.
+    fn included(&self, shard: &ExecutionRecord) -> bool {
+        self.num_real_rows(shard) > 0
+    }
+
+    /// The kinds of events this AIR emits for other AIRs while generating its trace.
+    fn produces(&self) -> &[EventKind] {
+        &[]
+    }
+
+    /// The kinds of events this AIR turns into rows of its trace.
+    ///
+    /// The machine generates the dependencies of the AIRs producing these events first, so that
+    /// the record holds all of them by the time this AIR generates its own.
+    fn consumes(&self) -> &[EventKind] {
+        &[]
+    }
+
     /// Generate the dependencies for a given execution record.
     fn generate_dependencies(&self, input: &ExecutionRecord, output: &mut ExecutionRecord) {
         self.generate_trace(input, output);
@@ -32,8 +103,107 @@ pub trait MachineAir<F: Field>: BaseAir<F> {
         0
     }
 
+    /// Generate the columns of the trace which only depend on the program.
+    ///
+    /// The trace must be a pure function of the program, with its rows in a canonical order, as the
+    /// keys commit to its digest.
     #[allow(unused_variables)]
     fn generate_preprocessed_trace(&self, program: &Program) -> Option<RowMajorMatrix<F>> {
         None
     }
+
+    /// The digest of the preprocessed trace for the given program, which the keys commit to.
+    fn preprocessed_digest(&self, program: &Program) -> [u8; 32]
+    where
+        F: PrimeField32,
+    {
+        matrix_digest(self.generate_preprocessed_trace(program).as_ref())
+    }
+
+    /// Check that the events of this AIR in a record are well-formed, so that a corrupt event is
+    /// reported before it surfaces as a constraint failure.
+    #[allow(unused_variables)]
+    fn validate(&self, input: &ExecutionRecord) -> Result<(), EventValidationError> {
+        Ok(())
+    }
+
+    /// The values of a row of the main trace by the names of the columns of this AIR, to report a
+    /// row at which the constraints are not satisfied. `None` if the AIR does not name its columns.
+    #[allow(unused_variables)]
+    fn describe_row(&self, row: &[F]) -> Option<String> {
+        None
+    }
 }
+
+/// The dimensions of a generated trace, telling its real rows apart from its padding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct TraceShape {
+    /// The number of rows holding events, or table entries for the fixed tables.
+    pub real_rows: usize,
+
+    /// The number of rows of the trace, including the padding.
+    pub padded_rows: usize,
+
+    /// The number of columns of the trace.
+    pub width: usize,
+}
+
+impl TraceShape {
+    /// The number of padding rows.
+    pub fn padding_rows(&self) -> usize {
+        self.padded_rows - self.real_rows
+    }
+
+    /// The number of cells of the trace, including the padding.
+    pub fn cells(&self) -> usize {
+        self.padded_rows * self.width
+    }
+}
+
+/// Hashes the dimensions and the canonical values of a matrix in row-major order. A missing matrix
+/// hashes like an empty one.
+pub fn matrix_digest<F: PrimeField32>(matrix: Option<&RowMajorMatrix<F>>) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new();
+    let (width, height) = matrix.map_or((0, 0), |matrix| (matrix.width(), matrix.height()));
+    hasher.update(&(width as u64).to_le_bytes());
+    hasher.update(&(height as u64).to_le_bytes());
+    for value in matrix.iter().flat_map(|matrix| matrix.values.iter()) {
+        hasher.update(&value.as_canonical_u32().to_le_bytes());
+    }
+    *hasher.finalize().as_bytes()
+}
+
+/// An event which cannot be proven by the AIR it belongs to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventValidationError {
+    /// The name of the AIR.
+    pub chip: String,
+
+    /// The index of the event among the events of the AIR.
+    pub index: usize,
+
+    /// Why the event is invalid.
+    pub reason: String,
+}
+
+impl EventValidationError {
+    pub fn new(chip: String, index: usize, reason: impl Into<String>) -> Self {
+        Self {
+            chip,
+            index,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl Display for EventValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "invalid event {} of chip {}: {}",
+            self.index, self.chip, self.reason
+        )
+    }
+}
+
+impl std::error::Error for EventValidationError {}
@@ -1,13 +1,19 @@
+mod bounded;
 mod builder;
+mod extension;
 mod interaction;
 mod machine;
 mod polynomial;
+mod public_values;
 mod sub_builder;
 mod word;
 
+pub use bounded::*;
 pub use builder::*;
+pub use extension::*;
 pub use interaction::*;
 pub use machine::*;
 pub use polynomial::*;
+pub use public_values::*;
 pub use sub_builder::*;
 pub use word::*;
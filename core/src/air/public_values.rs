@@ -0,0 +1,427 @@
+use core::borrow::{Borrow, BorrowMut};
+use std::mem::size_of;
+
+use p3_air::AirBuilder;
+use p3_field::AbstractField;
+use serde::{Deserialize, Serialize};
+use sp1_derive::AlignedBorrow;
+
+use super::Word;
+use crate::runtime::{ExecutionRecord, Opcode, SyscallCode};
+
+/// The number of field elements in the public values of a shard.
+pub const NUM_PUBLIC_VALUES: usize = size_of::<PublicValues<u8>>();
+
+/// The length of the canonical encoding of the public values of a shard.
+pub const CANONICAL_PUBLIC_VALUES_LEN: usize = 64;
+
+/// The fields of the canonical encoding of the public values, in order, each a little-endian `u32`.
+/// The encoding pads them with zeros to `CANONICAL_PUBLIC_VALUES_LEN` bytes.
+pub const CANONICAL_PUBLIC_VALUES_FIELDS: [&str; 10] = [
+    "shard",
+    "start_pc",
+    "end_pc",
+    "start_clk",
+    "end_clk",
+    "halted",
+    "exit_code",
+    "instruction_count",
+    "prior_instruction_count",
+    "input_read",
+];
+
+/// The public values of a shard, which pin the boundary state of its CPU trace.
+#[derive(AlignedBorrow, Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[repr(C)]
+pub struct PublicValues<T> {
+    /// The shard of the first CPU row.
+    pub shard: T,
+
+    /// The program counter of the first CPU row.
+    pub start_pc: T,
+
+    /// The program counter following the last real CPU row, where the next shard resumes.
+    pub end_pc: T,
+
+    /// The clock of the first CPU row.
+    pub start_clk: T,
+
+    /// The clock of the last real CPU row.
+    pub end_clk: T,
+
+    /// Whether the last real CPU row is a halt ecall.
+    pub halted: T,
+
+    /// The exit code of the program, if it halted in this shard.
+    pub exit_code: Word<T>,
+
+    /// The number of instructions executed in this shard, i.e. of real CPU rows. The verifier sums
+    /// them into the instruction count of the whole execution.
+    pub instruction_count: T,
+
+    /// The number of instructions executed in the shards before this one, which the cycle count
+    /// syscall adds to the instructions executed so far in this shard. The verifier checks it
+    /// against the instruction counts of the previous shards.
+    pub prior_instruction_count: T,
+
+    /// The number of bytes of the input stream read by the `LWA` ecalls of this shard. The
+    /// verifier sums them into the input read by the whole execution.
+    pub input_read: T,
+}
+
+impl PublicValues<u32> {
+    /// Reads the public values of a shard from its CPU events.
+    pub fn from_record(record: &ExecutionRecord) -> Self {
+        let (Some(first), Some(last)) = (record.cpu_events.first(), record.cpu_events.last())
+        else {
+            return Self::default();
+        };
+        let halted = last.instruction.opcode == Opcode::ECALL && last.b == SyscallCode::HALT as u32;
+        let input_read = record
+            .cpu_events
+            .iter()
+            .filter(|event| {
+                event.instruction.opcode == Opcode::ECALL && event.b == SyscallCode::LWA as u32
+            })
+            .map(|event| event.memory.unwrap_or_default())
+            .sum();
+        Self {
+            shard: first.shard,
+            start_pc: first.pc,
+            end_pc: last.next_pc,
+            start_clk: first.clk,
+            end_clk: last.clk,
+            halted: halted as u32,
+            exit_code: Word(if halted { last.a } else { 0 }.to_le_bytes().map(u32::from)),
+            instruction_count: record.cpu_events.len() as u32,
+            prior_instruction_count: first.global_clk,
+            input_read,
+        }
+    }
+
+    /// Converts the public values to field elements, in the order of their columns.
+    pub fn to_field_elements<F: AbstractField>(&self) -> Vec<F> {
+        let mut values = vec![
+            self.shard,
+            self.start_pc,
+            self.end_pc,
+            self.start_clk,
+            self.end_clk,
+            self.halted,
+        ];
+        values.extend(self.exit_code.0);
+        values.push(self.instruction_count);
+        values.push(self.prior_instruction_count);
+        values.push(self.input_read);
+        values.into_iter().map(F::from_canonical_u32).collect()
+    }
+
+    /// The exit code as a `u32`, if every limb of the word is a byte.
+    fn exit_code_u32(&self) -> Option<u32> {
+        let mut bytes = [0u8; 4];
+        for (byte, limb) in bytes.iter_mut().zip(self.exit_code.0) {
+            *byte = u8::try_from(limb).ok()?;
+        }
+        Some(u32::from_le_bytes(bytes))
+    }
+
+    /// Encodes the public values in their canonical layout, which external verifiers hash to
+    /// recompute the commitment observed by the proof.
+    ///
+    /// The layout is little-endian `u32` fields padded with zeros to 64 bytes, so that it fills
+    /// exactly two EVM words:
+    ///
+    /// | offset | field                     |
+    /// |--------|---------------------------|
+    /// | 0      | `shard`                   |
+    /// | 4      | `start_pc`                |
+    /// | 8      | `end_pc`                  |
+    /// | 12     | `start_clk`               |
+    /// | 16     | `end_clk`                 |
+    /// | 20     | `halted`                  |
+    /// | 24     | `exit_code`               |
+    /// | 28     | `instruction_count`       |
+    /// | 32     | `prior_instruction_count` |
+    /// | 36     | `input_read`              |
+    /// | 40     | zero padding              |
+    ///
+    /// # Panics
+    ///
+    /// Panics if a limb of the exit code is not a byte. The verifier rejects such public values.
+    pub fn to_canonical_bytes(&self) -> [u8; CANONICAL_PUBLIC_VALUES_LEN] {
+        let exit_code = self
+            .exit_code_u32()
+            .expect("the limbs of the exit code must be bytes");
+        let fields = [
+            self.shard,
+            self.start_pc,
+            self.end_pc,
+            self.start_clk,
+            self.end_clk,
+            self.halted,
+            exit_code,
+            self.instruction_count,
+            self.prior_instruction_count,
+            self.input_read,
+        ];
+        let mut bytes = [0u8; CANONICAL_PUBLIC_VALUES_LEN];
+        for (chunk, field) in bytes.chunks_exact_mut(4).zip(fields) {
+            chunk.copy_from_slice(&field.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Decodes public values from their canonical layout, rejecting any other encoding.
+    pub fn from_canonical_bytes(bytes: &[u8]) -> Result<Self, CanonicalPublicValuesError> {
+        if bytes.len() != CANONICAL_PUBLIC_VALUES_LEN {
+            return Err(CanonicalPublicValuesError::InvalidLength(bytes.len()));
+        }
+        let padding = 4 * CANONICAL_PUBLIC_VALUES_FIELDS.len();
+        if bytes[padding..].iter().any(|&byte| byte != 0) {
+            return Err(CanonicalPublicValuesError::NonZeroPadding);
+        }
+        let field = |i: usize| u32::from_le_bytes(bytes[4 * i..4 * i + 4].try_into().unwrap());
+        let public_values = Self {
+            shard: field(0),
+            start_pc: field(1),
+            end_pc: field(2),
+            start_clk: field(3),
+            end_clk: field(4),
+            halted: field(5),
+            exit_code: Word(field(6).to_le_bytes().map(u32::from)),
+            instruction_count: field(7),
+            prior_instruction_count: field(8),
+            input_read: field(9),
+        };
+        public_values.check_canonical()?;
+        Ok(public_values)
+    }
+
+    /// Checks that the public values have a canonical encoding, i.e. that `halted` is a boolean,
+    /// the exit code is a word of bytes, and it is zero unless the shard halts.
+    pub fn check_canonical(&self) -> Result<(), CanonicalPublicValuesError> {
+        if self.halted > 1 {
+            return Err(CanonicalPublicValuesError::InvalidHalted(self.halted));
+        }
+        match self.exit_code_u32() {
+            None => Err(CanonicalPublicValuesError::InvalidExitCode),
+            Some(exit_code) if exit_code != 0 && self.halted == 0 => {
+                Err(CanonicalPublicValuesError::ExitCodeWithoutHalt(exit_code))
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// The commitment to the public values observed by the proof: the BLAKE3 hash of their
+    /// canonical encoding.
+    pub fn commitment(&self) -> [u8; 32] {
+        *blake3::hash(&self.to_canonical_bytes()).as_bytes()
+    }
+}
+
+/// An error when decoding or validating the canonical encoding of public values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanonicalPublicValuesError {
+    /// The encoding is not `CANONICAL_PUBLIC_VALUES_LEN` bytes long.
+    InvalidLength(usize),
+    /// The halted flag is neither zero nor one.
+    InvalidHalted(u32),
+    /// A limb of the exit code is not a byte.
+    InvalidExitCode,
+    /// The exit code is nonzero, but the shard does not halt.
+    ExitCodeWithoutHalt(u32),
+    /// A byte of the padding after the fields is not zero.
+    NonZeroPadding,
+}
+
+impl std::fmt::Display for CanonicalPublicValuesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidLength(len) => write!(
+                f,
+                "expected {} bytes of public values, found {}",
+                CANONICAL_PUBLIC_VALUES_LEN, len
+            ),
+            Self::InvalidHalted(halted) => write!(f, "invalid halted flag {}", halted),
+            Self::InvalidExitCode => write!(f, "the limbs of the exit code are not bytes"),
+            Self::ExitCodeWithoutHalt(exit_code) => {
+                write!(f, "exit code {} in a shard which does not halt", exit_code)
+            }
+            Self::NonZeroPadding => write!(f, "the padding of the public values is not zero"),
+        }
+    }
+}
+
+impl std::error::Error for CanonicalPublicValuesError {}
+
+/// A builder which exposes the public values of the proof to the constraints.
+pub trait PublicValuesBuilder: AirBuilder {
+    /// A public value in the constraints: a field element, or a variable for builders which
+    /// evaluate the constraints symbolically.
+    type PublicVar: Into<Self::Expr> + Copy;
+
+    /// Whether the builder evaluates an AIR on its own, outside of a shard, e.g. when proving a
+    /// single chip. Such builders have no public values, and the constraints tying a chip to the
+    /// public values of its shard do not apply.
+    const STANDALONE: bool;
+
+    /// Returns the public values: all `NUM_PUBLIC_VALUES` of them, or none if the builder is
+    /// standalone.
+    fn public_values(&self) -> &[Self::PublicVar];
+}
+
+impl std::fmt::Display for PublicValues<u32> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "shard {}, pc {:#x}..{:#x}, clk {}..{}, halted {}, exit code {:?}, {} instructions after {}, {} input bytes read",
+            self.shard,
+            self.start_pc,
+            self.end_pc,
+            self.start_clk,
+            self.end_clk,
+            self.halted,
+            self.exit_code.0,
+            self.instruction_count,
+            self.prior_instruction_count,
+            self.input_read
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{thread_rng, Rng};
+
+    use super::{CanonicalPublicValuesError, PublicValues, CANONICAL_PUBLIC_VALUES_LEN};
+    use crate::air::Word;
+
+    /// Public values with every field distinct, and their expected encoding.
+    const GOLDEN: (PublicValues<u32>, &str) = (
+        PublicValues {
+            shard: 1,
+            start_pc: 0x0020_0800,
+            end_pc: 0,
+            start_clk: 1,
+            end_clk: 0x1234_5678,
+            halted: 1,
+            exit_code: Word([7, 0, 0, 0x80]),
+            instruction_count: 0x0001_0203,
+            prior_instruction_count: 0x0a0b_0c0d,
+            input_read: 0x0000_0024,
+        },
+        concat!(
+            "0100000000082000000000000100000078563412010000000700008003020100",
+            "0d0c0b0a24000000000000000000000000000000000000000000000000000000",
+        ),
+    );
+
+    #[test]
+    fn test_canonical_golden_vectors() {
+        let (public_values, expected) = GOLDEN;
+        let bytes = public_values.to_canonical_bytes();
+        assert_eq!(hex::encode(bytes), expected);
+        assert_eq!(
+            PublicValues::from_canonical_bytes(&bytes).unwrap(),
+            public_values
+        );
+        assert_eq!(
+            public_values.commitment(),
+            *blake3::hash(&hex::decode(expected).unwrap()).as_bytes()
+        );
+
+        // A shard which neither starts nor ends the program.
+        let public_values = PublicValues {
+            shard: 2,
+            start_pc: 0x0020_1000,
+            end_pc: 0x0020_1040,
+            start_clk: 0x0040_0001,
+            end_clk: 0x0080_0000,
+            halted: 0,
+            exit_code: Word([0; 4]),
+            instruction_count: 0x0010_0000,
+            prior_instruction_count: 0x0020_0000,
+            input_read: 0,
+        };
+        assert_eq!(
+            hex::encode(public_values.to_canonical_bytes()),
+            concat!(
+                "0200000000102000401020000100400000008000000000000000000000001000",
+                "0000200000000000000000000000000000000000000000000000000000000000",
+            )
+        );
+    }
+
+    #[test]
+    fn test_canonical_rejects_invalid_encodings() {
+        let (public_values, _) = GOLDEN;
+        let bytes = public_values.to_canonical_bytes();
+        let decode = |bytes: &[u8]| PublicValues::from_canonical_bytes(bytes).unwrap_err();
+
+        assert_eq!(
+            decode(&bytes[..31]),
+            CanonicalPublicValuesError::InvalidLength(31)
+        );
+        assert_eq!(
+            decode(&[bytes.as_slice(), &[0]].concat()),
+            CanonicalPublicValuesError::InvalidLength(65)
+        );
+
+        let mut padded = bytes;
+        padded[CANONICAL_PUBLIC_VALUES_LEN - 1] = 1;
+        assert_eq!(decode(&padded), CanonicalPublicValuesError::NonZeroPadding);
+
+        let mut halted = bytes;
+        halted[20] = 2;
+        assert_eq!(
+            decode(&halted),
+            CanonicalPublicValuesError::InvalidHalted(2)
+        );
+
+        let mut running = bytes;
+        running[20] = 0;
+        assert_eq!(
+            decode(&running),
+            CanonicalPublicValuesError::ExitCodeWithoutHalt(0x8000_0007)
+        );
+
+        let invalid = PublicValues {
+            exit_code: Word([0x100, 0, 0, 0]),
+            ..public_values
+        };
+        assert_eq!(
+            invalid.check_canonical(),
+            Err(CanonicalPublicValuesError::InvalidExitCode)
+        );
+    }
+
+    #[test]
+    fn test_canonical_roundtrip() {
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let halted = rng.gen_bool(0.5);
+            let public_values = PublicValues {
+                shard: rng.gen(),
+                start_pc: rng.gen(),
+                end_pc: rng.gen(),
+                start_clk: rng.gen(),
+                end_clk: rng.gen(),
+                halted: halted as u32,
+                exit_code: Word(
+                    if halted { rng.gen::<u32>() } else { 0 }
+                        .to_le_bytes()
+                        .map(u32::from),
+                ),
+                instruction_count: rng.gen(),
+                prior_instruction_count: rng.gen(),
+                input_read: rng.gen(),
+            };
+            let bytes = public_values.to_canonical_bytes();
+            assert_eq!(bytes.len(), CANONICAL_PUBLIC_VALUES_LEN);
+            assert_eq!(
+                PublicValues::from_canonical_bytes(&bytes).unwrap(),
+                public_values
+            );
+        }
+    }
+}
@@ -6,15 +6,21 @@ use core::borrow::{Borrow, BorrowMut};
 use p3_air::AirBuilder;
 use p3_field::AbstractField;
 use p3_field::Field;
+use serde::{Deserialize, Serialize};
 use sp1_derive::AlignedBorrow;
 
-use super::SP1AirBuilder;
+use super::{BoundedExpr, SP1AirBuilder};
 
 /// The size of a word in bytes.
 pub const WORD_SIZE: usize = 4;
 
+/// The size of a double word in bytes.
+pub const DOUBLE_WORD_SIZE: usize = 2 * WORD_SIZE;
+
 /// A word is a 32-bit value represented in an AIR.
-#[derive(AlignedBorrow, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[derive(
+    AlignedBorrow, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
 #[repr(C)]
 pub struct Word<T>(pub [T; WORD_SIZE]);
 
@@ -59,12 +65,19 @@ impl<F: Field> Word<F> {
 
 impl<V: Copy> Word<V> {
     /// Reduces a word to a single variable.
+    ///
+    /// A word of range checked bytes can exceed the modulus of a 31 bit field, so the result is
+    /// unchecked. Use [`Word::reduce_bounded`] in equalities which must not wrap around.
     pub fn reduce<AB: AirBuilder<Var = V>>(&self) -> AB::Expr {
-        let base = [1, 1 << 8, 1 << 16, 1 << 24].map(AB::Expr::from_canonical_u32);
+        self.reduce_bounded::<AB>().into_expr()
+    }
+
+    /// Recomposes a word of range checked bytes, keeping track of the range of the result.
+    pub fn reduce_bounded<AB: AirBuilder<Var = V>>(&self) -> BoundedExpr<AB::Expr> {
         self.0
             .iter()
             .enumerate()
-            .map(|(i, x)| base[i].clone() * *x)
+            .map(|(i, x)| BoundedExpr::byte(*x) * (1 << (8 * i)))
             .sum()
     }
 }
@@ -104,3 +117,98 @@ impl<T> IntoIterator for Word<T> {
         self.0.into_iter()
     }
 }
+
+/// A double word is a 64-bit value represented in an AIR, e.g. a lane of a Keccak or SHA-512
+/// state, with its bytes in little endian order.
+#[derive(
+    AlignedBorrow, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+#[repr(C)]
+pub struct DoubleWord<T>(pub [T; DOUBLE_WORD_SIZE]);
+
+impl<T> DoubleWord<T> {
+    /// Applies `f` to each element of the double word.
+    pub fn map<F, S>(self, f: F) -> DoubleWord<S>
+    where
+        F: FnMut(T) -> S,
+    {
+        DoubleWord(self.0.map(f))
+    }
+
+    /// Concatenates the low and the high word of a double word.
+    pub fn from_words(lo: Word<T>, hi: Word<T>) -> Self {
+        let mut limbs = lo.0.into_iter().chain(hi.0);
+        DoubleWord(core::array::from_fn(|_| limbs.next().unwrap()))
+    }
+
+    /// Splits the double word into its low and its high word.
+    pub fn to_words(self) -> [Word<T>; 2] {
+        let mut limbs = self.0.into_iter();
+        [(); 2].map(|_| Word(core::array::from_fn(|_| limbs.next().unwrap())))
+    }
+}
+
+impl<F: Field> DoubleWord<F> {
+    /// Converts a double word to a u64.
+    pub fn to_u64(&self) -> u64 {
+        u64::from_le_bytes(self.0.map(|x| x.to_string().parse::<u8>().unwrap()))
+    }
+}
+
+impl<T> Index<usize> for DoubleWord<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+impl<T> IndexMut<usize> for DoubleWord<T> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.0[index]
+    }
+}
+
+impl<F: Field> From<u64> for DoubleWord<F> {
+    fn from(value: u64) -> Self {
+        DoubleWord(value.to_le_bytes().map(F::from_canonical_u8))
+    }
+}
+
+impl<T> From<[Word<T>; 2]> for DoubleWord<T> {
+    fn from([lo, hi]: [Word<T>; 2]) -> Self {
+        Self::from_words(lo, hi)
+    }
+}
+
+impl<T> IntoIterator for DoubleWord<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T, DOUBLE_WORD_SIZE>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+
+    use super::{DoubleWord, Word};
+
+    #[test]
+    fn test_double_word_words() {
+        for value in [0, 1, 0xdead_beef_0123_4567, u64::MAX] {
+            let double_word = DoubleWord::<BabyBear>::from(value);
+            assert_eq!(double_word.to_u64(), value);
+            let [lo, hi] = double_word.to_words();
+            assert_eq!(lo.to_u32(), value as u32);
+            assert_eq!(hi.to_u32(), (value >> 32) as u32);
+            assert_eq!(DoubleWord::from([lo, hi]), double_word);
+            assert_eq!(
+                DoubleWord::from_words(Word::from(value as u32), Word::from((value >> 32) as u32)),
+                double_word
+            );
+        }
+    }
+}
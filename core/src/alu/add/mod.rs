@@ -9,7 +9,7 @@ use p3_maybe_rayon::prelude::ParallelSlice;
 use sp1_derive::AlignedBorrow;
 use tracing::instrument;
 
-use crate::air::MachineAir;
+use crate::air::{EventKind, MachineAir};
 use crate::air::{SP1AirBuilder, Word};
 use crate::operations::AddOperation;
 use crate::runtime::{ExecutionRecord, Opcode};
@@ -44,6 +44,18 @@ impl<F: PrimeField> MachineAir<F> for AddChip {
         "Add".to_string()
     }
 
+    fn produces(&self) -> &[EventKind] {
+        &[EventKind::ByteLookup]
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::Add]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.add_events.len()
+    }
+
     #[instrument(name = "generate add trace", skip_all)]
     fn generate_trace(
         &self,
@@ -7,7 +7,7 @@ use p3_matrix::MatrixRowSlices;
 use sp1_derive::AlignedBorrow;
 use tracing::instrument;
 
-use crate::air::MachineAir;
+use crate::air::{EventKind, MachineAir};
 use crate::air::{SP1AirBuilder, Word};
 use crate::bytes::{ByteLookupEvent, ByteOpcode};
 use crate::runtime::{ExecutionRecord, Opcode};
@@ -47,6 +47,18 @@ impl<F: PrimeField> MachineAir<F> for BitwiseChip {
         "Bitwise".to_string()
     }
 
+    fn produces(&self) -> &[EventKind] {
+        &[EventKind::ByteLookup]
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::Bitwise]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.bitwise_events.len()
+    }
+
     #[instrument(name = "generate bitwise trace", skip_all)]
     fn generate_trace(
         &self,
@@ -0,0 +1,191 @@
+//! Differential tests of the runtime and the constraints of the CPU and ALU chips.
+//!
+//! Each case is a single register-register ALU instruction. Its encoding is decoded with the
+//! disassembler, executed by the runtime, and the traces of the chips it touches are checked
+//! against their constraints. The result is compared to an independent reference implementation of
+//! the RV32IM semantics.
+
+use p3_baby_bear::BabyBear;
+use p3_field::AbstractField;
+use rand::{thread_rng, Rng};
+
+use crate::air::{MachineAir, PublicValues};
+use crate::disassembler::transpile;
+use crate::runtime::{ExecutionRecord, Instruction, Opcode, Program, Register, Runtime};
+use crate::stark::{check_constraints, Chip, RiscvAir};
+use crate::utils::BabyBearBlake3;
+
+type EF = p3_field::extension::BinomialExtensionField<BabyBear, 4>;
+
+/// The register-register ALU opcodes, with their `funct3` and `funct7` fields.
+const OPCODES: [(Opcode, u32, u32); 18] = [
+    (Opcode::ADD, 0b000, 0x00),
+    (Opcode::SUB, 0b000, 0x20),
+    (Opcode::SLL, 0b001, 0x00),
+    (Opcode::SLT, 0b010, 0x00),
+    (Opcode::SLTU, 0b011, 0x00),
+    (Opcode::XOR, 0b100, 0x00),
+    (Opcode::SRL, 0b101, 0x00),
+    (Opcode::SRA, 0b101, 0x20),
+    (Opcode::OR, 0b110, 0x00),
+    (Opcode::AND, 0b111, 0x00),
+    (Opcode::MUL, 0b000, 0x01),
+    (Opcode::MULH, 0b001, 0x01),
+    (Opcode::MULHSU, 0b010, 0x01),
+    (Opcode::MULHU, 0b011, 0x01),
+    (Opcode::DIV, 0b100, 0x01),
+    (Opcode::DIVU, 0b101, 0x01),
+    (Opcode::REM, 0b110, 0x01),
+    (Opcode::REMU, 0b111, 0x01),
+];
+
+/// Operands at the edges of the signed and unsigned ranges, and of the shift amounts.
+const OPERANDS: [u32; 12] = [
+    0,
+    1,
+    2,
+    31,
+    32,
+    0xff,
+    0x8000,
+    0x7fff_ffff,
+    0x8000_0000,
+    0x8000_0001,
+    u32::MAX - 1,
+    u32::MAX,
+];
+
+const RD: u32 = 31;
+const RS1: u32 = 29;
+const RS2: u32 = 30;
+
+/// Encodes `op x31, x29, x30` as an R-type instruction.
+fn encode(funct3: u32, funct7: u32) -> u32 {
+    (funct7 << 25) | (RS2 << 20) | (RS1 << 15) | (funct3 << 12) | (RD << 7) | 0b0110011
+}
+
+/// The result of an ALU instruction according to the RV32IM specification.
+fn oracle(opcode: Opcode, b: u32, c: u32) -> u32 {
+    let (sb, sc) = (b as i32, c as i32);
+    match opcode {
+        Opcode::ADD => b.wrapping_add(c),
+        Opcode::SUB => b.wrapping_sub(c),
+        Opcode::SLL => b << (c & 31),
+        Opcode::SLT => (sb < sc) as u32,
+        Opcode::SLTU => (b < c) as u32,
+        Opcode::XOR => b ^ c,
+        Opcode::SRL => b >> (c & 31),
+        Opcode::SRA => (sb >> (c & 31)) as u32,
+        Opcode::OR => b | c,
+        Opcode::AND => b & c,
+        Opcode::MUL => b.wrapping_mul(c),
+        Opcode::MULH => ((sb as i64 * sc as i64) >> 32) as u32,
+        Opcode::MULHSU => ((sb as i128 * c as i128) >> 32) as u32,
+        Opcode::MULHU => ((b as u64 * c as u64) >> 32) as u32,
+        Opcode::DIV if c == 0 => u32::MAX,
+        Opcode::DIV => sb.wrapping_div(sc) as u32,
+        Opcode::DIVU if c == 0 => u32::MAX,
+        Opcode::DIVU => b / c,
+        Opcode::REM if c == 0 => b,
+        Opcode::REM => sb.wrapping_rem(sc) as u32,
+        Opcode::REMU if c == 0 => b,
+        Opcode::REMU => b % c,
+        _ => unreachable!("{} is not an ALU opcode", opcode.mnemonic()),
+    }
+}
+
+/// Runs one case, returning a description of every disagreement.
+fn run_case(opcode: Opcode, funct3: u32, funct7: u32, b: u32, c: u32) -> Result<(), String> {
+    let word = encode(funct3, funct7);
+    let instruction = transpile(&[word])[0];
+    let mut errors = Vec::new();
+    if instruction.opcode != opcode
+        || (instruction.op_a, instruction.op_b, instruction.op_c) != (RD, RS1, RS2)
+        || instruction.imm_b
+        || instruction.imm_c
+    {
+        errors.push(format!("decoded as {:?}", instruction));
+    }
+
+    let instructions = vec![
+        Instruction::new(Opcode::ADD, RS1, 0, b, false, true),
+        Instruction::new(Opcode::ADD, RS2, 0, c, false, true),
+        instruction,
+    ];
+    let mut runtime = Runtime::new(Program::new(instructions, 0, 0));
+    runtime.run();
+    let expected = oracle(opcode, b, c);
+    let result = runtime.register(Register::X31);
+    if result != expected {
+        errors.push(format!(
+            "runtime: {:#010x}, oracle: {:#010x}",
+            result, expected
+        ));
+    }
+
+    // The byte table does not depend on the instruction, and is expensive to generate.
+    let challenges = [EF::from_canonical_u32(3), EF::from_canonical_u32(7)];
+    let public_values = PublicValues::from_record(&runtime.record).to_field_elements();
+    for air in RiscvAir::<BabyBear>::get_all() {
+        if !air.included(&runtime.record) || matches!(air, RiscvAir::ByteLookup(_)) {
+            continue;
+        }
+        let chip = Chip::new(air);
+        let trace = chip.generate_trace(&runtime.record, &mut ExecutionRecord::default());
+        let perm = chip.generate_permutation_trace(None, &trace, &challenges);
+        if let Err(failure) = check_constraints::<BabyBearBlake3>(
+            &chip,
+            None,
+            &trace,
+            &perm,
+            &challenges,
+            &public_values,
+        ) {
+            errors.push(format!("constraints: {}", failure));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} {:#010x}, {:#010x} (word {:#010x}):\n{}",
+            opcode.mnemonic(),
+            b,
+            c,
+            word,
+            errors.join("\n")
+        ))
+    }
+}
+
+fn assert_agree(cases: impl Iterator<Item = (usize, u32, u32)>) {
+    let divergences = cases
+        .filter_map(|(i, b, c)| {
+            let (opcode, funct3, funct7) = OPCODES[i];
+            run_case(opcode, funct3, funct7, b, c).err()
+        })
+        .collect::<Vec<_>>();
+    assert!(divergences.is_empty(), "{}", divergences.join("\n\n"));
+}
+
+#[test]
+fn test_differential_operand_classes() {
+    assert_agree((0..OPCODES.len()).flat_map(|i| {
+        OPERANDS
+            .iter()
+            .flat_map(move |b| OPERANDS.iter().map(move |c| (i, *b, *c)))
+    }));
+}
+
+/// Runs `DIFFERENTIAL_CASES` random cases, 10000 by default, e.g. with
+/// `DIFFERENTIAL_CASES=1000000 cargo test --release test_differential_extended -- --ignored`.
+#[test]
+#[ignore]
+fn test_differential_extended() {
+    let cases = std::env::var("DIFFERENTIAL_CASES")
+        .map(|val| val.parse().unwrap())
+        .unwrap_or(10000);
+    let mut rng = thread_rng();
+    assert_agree((0..cases).map(|_| (rng.gen_range(0..OPCODES.len()), rng.gen(), rng.gen())));
+}
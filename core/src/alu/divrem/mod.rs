@@ -73,7 +73,7 @@ use sp1_derive::AlignedBorrow;
 use tracing::instrument;
 
 use self::utils::eval_abs_value;
-use crate::air::MachineAir;
+use crate::air::{EventKind, MachineAir};
 use crate::air::{SP1AirBuilder, Word};
 use crate::alu::divrem::utils::{get_msb, get_quotient_and_remainder, is_signed_operation};
 use crate::alu::AluEvent;
@@ -186,6 +186,18 @@ impl<F: PrimeField> MachineAir<F> for DivRemChip {
         "DivRem".to_string()
     }
 
+    fn produces(&self) -> &[EventKind] {
+        &[EventKind::Mul, EventKind::Lt, EventKind::ByteLookup]
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::DivRem]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.divrem_events.len()
+    }
+
     #[instrument(name = "generate divrem trace", skip_all)]
     fn generate_trace(
         &self,
@@ -1,4 +1,4 @@
-use crate::air::MachineAir;
+use crate::air::{EventKind, MachineAir};
 use core::borrow::{Borrow, BorrowMut};
 use core::mem::size_of;
 use p3_air::{Air, AirBuilder, BaseAir};
@@ -78,6 +78,14 @@ impl<F: PrimeField> MachineAir<F> for LtChip {
         "Lt".to_string()
     }
 
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::Lt]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.lt_events.len()
+    }
+
     #[instrument(name = "generate lt trace", skip_all)]
     fn generate_trace(
         &self,
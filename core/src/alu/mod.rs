@@ -1,5 +1,7 @@
 pub mod add;
 pub mod bitwise;
+#[cfg(test)]
+mod differential;
 pub mod divrem;
 pub mod lt;
 pub mod mul;
@@ -16,10 +18,12 @@ pub use sll::*;
 pub use sr::*;
 pub use sub::*;
 
+use serde::{Deserialize, Serialize};
+
 use crate::runtime::Opcode;
 
 /// A standard format for describing ALU operations that need to be proven.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct AluEvent {
     /// The clock cycle that the operation occurs on.
     pub clk: u32,
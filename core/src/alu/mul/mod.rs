@@ -37,7 +37,7 @@ use p3_maybe_rayon::prelude::ParallelSlice;
 use sp1_derive::AlignedBorrow;
 use tracing::instrument;
 
-use crate::air::MachineAir;
+use crate::air::{EventKind, MachineAir};
 use crate::air::{SP1AirBuilder, Word};
 use crate::alu::mul::utils::get_msb;
 use crate::bytes::{ByteLookupEvent, ByteOpcode};
@@ -114,6 +114,18 @@ impl<F: PrimeField> MachineAir<F> for MulChip {
         "Mul".to_string()
     }
 
+    fn produces(&self) -> &[EventKind] {
+        &[EventKind::ByteLookup]
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::Mul]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.mul_events.len()
+    }
+
     #[instrument(name = "generate mul trace", skip_all)]
     fn generate_trace(
         &self,
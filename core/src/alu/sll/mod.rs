@@ -40,7 +40,7 @@ use p3_matrix::MatrixRowSlices;
 use sp1_derive::AlignedBorrow;
 use tracing::instrument;
 
-use crate::air::MachineAir;
+use crate::air::{EventKind, MachineAir};
 use crate::air::{SP1AirBuilder, Word};
 use crate::disassembler::WORD_SIZE;
 use crate::runtime::{ExecutionRecord, Opcode};
@@ -95,6 +95,18 @@ impl<F: PrimeField> MachineAir<F> for ShiftLeft {
         "ShiftLeft".to_string()
     }
 
+    fn produces(&self) -> &[EventKind] {
+        &[EventKind::ByteLookup]
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::ShiftLeft]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.shift_left_events.len()
+    }
+
     #[instrument(name = "generate sll trace", skip_all)]
     fn generate_trace(
         &self,
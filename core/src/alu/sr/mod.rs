@@ -53,8 +53,8 @@ use p3_matrix::MatrixRowSlices;
 use sp1_derive::AlignedBorrow;
 use tracing::instrument;
 
-use crate::air::MachineAir;
-use crate::air::{SP1AirBuilder, Word};
+use crate::air::{EventKind, MachineAir};
+use crate::air::{SP1AirBuilder, Word, WordAirBuilder};
 use crate::alu::sr::utils::{nb_bits_to_shift, nb_bytes_to_shift};
 use crate::bytes::utils::shr_carry;
 use crate::bytes::{ByteLookupEvent, ByteOpcode};
@@ -127,6 +127,18 @@ impl<F: PrimeField> MachineAir<F> for ShiftRightChip {
         "ShiftRight".to_string()
     }
 
+    fn produces(&self) -> &[EventKind] {
+        &[EventKind::ByteLookup]
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::ShiftRight]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.shift_right_events.len()
+    }
+
     #[instrument(name = "generate sr trace", skip_all)]
     fn generate_trace(
         &self,
@@ -347,14 +359,17 @@ where
         // Byte shift the sign-extended b.
         {
             // The leading bytes of b should be 0xff if b's MSB is 1 & opcode = SRA, 0 otherwise.
-            let leading_byte = local.is_sra * local.b_msb * AB::Expr::from_canonical_u8(0xff);
-            let mut sign_extended_b: Vec<AB::Expr> = vec![];
-            for i in 0..WORD_SIZE {
-                sign_extended_b.push(local.b[i].into());
-            }
-            for _ in 0..WORD_SIZE {
-                sign_extended_b.push(leading_byte.clone());
-            }
+            let leading_bytes = builder.select_word(
+                local.is_sra * local.b_msb,
+                Word(core::array::from_fn(|_| AB::Expr::from_canonical_u8(0xff))),
+                Word(core::array::from_fn(|_| AB::Expr::zero())),
+            );
+            let sign_extended_b: Vec<AB::Expr> = local
+                .b
+                .into_iter()
+                .map(|x| x.into())
+                .chain(leading_bytes)
+                .collect();
 
             // Shift the bytes of sign_extended_b by num_bytes_to_shift.
             for num_bytes_to_shift in 0..WORD_SIZE {
@@ -9,7 +9,7 @@ use p3_matrix::MatrixRowSlices;
 use sp1_derive::AlignedBorrow;
 use tracing::instrument;
 
-use crate::air::MachineAir;
+use crate::air::{EventKind, MachineAir};
 use crate::air::{SP1AirBuilder, Word};
 use crate::runtime::{ExecutionRecord, Opcode};
 use crate::utils::pad_to_power_of_two;
@@ -45,6 +45,18 @@ impl<F: PrimeField> MachineAir<F> for SubChip {
         "Sub".to_string()
     }
 
+    fn produces(&self) -> &[EventKind] {
+        &[EventKind::ByteLookup]
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::Sub]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.sub_events.len()
+    }
+
     #[instrument(name = "generate sub trace", skip_all)]
     fn generate_trace(
         &self,
@@ -0,0 +1,283 @@
+//! A signed container for shipping proofs, e.g. from a proving service to its customers.
+//!
+//! The container only authenticates the bytes of the proof: the signature says who produced the
+//! artifact, not that the proof is valid, which is still checked by verifying it. A container is
+//! laid out as
+//!
+//! ```text
+//! magic (4 bytes) | version (1 byte) | signer id length (u16 LE) | signer id
+//!     | signature length (u16 LE) | signature | payload
+//! ```
+//!
+//! where the signature covers everything before it and the payload.
+
+use std::fmt::{Display, Formatter};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::stark::StarkGenericConfig;
+use crate::SP1ProofWithIO;
+
+/// The bytes every signed artifact starts with.
+pub const ARTIFACT_MAGIC: [u8; 4] = *b"SP1S";
+
+/// The version of the container layout.
+pub const ARTIFACT_VERSION: u8 = 1;
+
+/// Signs artifacts on behalf of a signer.
+pub trait ArtifactSigner {
+    /// The identifier of the signer, embedded in the header of the artifacts it signs.
+    fn signer_id(&self) -> Vec<u8>;
+
+    /// The signature of the message.
+    fn sign(&self, message: &[u8]) -> Vec<u8>;
+}
+
+/// Checks the signatures of a single signer.
+pub trait ArtifactVerifier {
+    /// The identifier of the signer, which artifacts must be signed by.
+    fn signer_id(&self) -> Vec<u8>;
+
+    /// Whether the signature is one of the signer over the message.
+    fn verify(&self, message: &[u8], signature: &[u8]) -> bool;
+}
+
+/// Why a signed artifact was rejected.
+#[derive(Debug)]
+pub enum ArtifactError {
+    /// The header does not decode.
+    Malformed(&'static str),
+    /// The artifact was signed by another signer than the expected one.
+    UnexpectedSigner { signer_id: Vec<u8> },
+    /// The signature does not match the header and the payload.
+    InvalidSignature,
+    /// The payload is authentic but does not decode.
+    Payload(bincode::Error),
+}
+
+impl Display for ArtifactError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArtifactError::Malformed(reason) => write!(f, "malformed artifact: {}", reason),
+            ArtifactError::UnexpectedSigner { signer_id } => write!(
+                f,
+                "the artifact is signed by an unexpected signer {}",
+                hex::encode(signer_id)
+            ),
+            ArtifactError::InvalidSignature => {
+                write!(f, "the signature of the artifact is invalid")
+            }
+            ArtifactError::Payload(e) => write!(f, "the payload of the artifact is invalid: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ArtifactError {}
+
+fn write_field(bytes: &mut Vec<u8>, field: &[u8]) {
+    let len = u16::try_from(field.len()).expect("header fields are at most 65535 bytes");
+    bytes.extend_from_slice(&len.to_le_bytes());
+    bytes.extend_from_slice(field);
+}
+
+fn read_field<'a>(bytes: &mut &'a [u8], name: &'static str) -> Result<&'a [u8], ArtifactError> {
+    if bytes.len() < 2 {
+        return Err(ArtifactError::Malformed(name));
+    }
+    let len = u16::from_le_bytes([bytes[0], bytes[1]]) as usize;
+    if bytes.len() < 2 + len {
+        return Err(ArtifactError::Malformed(name));
+    }
+    let field = &bytes[2..2 + len];
+    *bytes = &bytes[2 + len..];
+    Ok(field)
+}
+
+/// Wraps the payload in a container signed by the signer.
+pub fn sign_artifact(payload: &[u8], signer: &dyn ArtifactSigner) -> Vec<u8> {
+    let mut header = ARTIFACT_MAGIC.to_vec();
+    header.push(ARTIFACT_VERSION);
+    write_field(&mut header, &signer.signer_id());
+    let signature = signer.sign(&[header.as_slice(), payload].concat());
+
+    let mut bytes = header;
+    write_field(&mut bytes, &signature);
+    bytes.extend_from_slice(payload);
+    bytes
+}
+
+/// The payload of a container, once its signature is checked to be one of the verifier's signer.
+///
+/// Nothing but the fixed layout of the header is read before the signature is checked.
+pub fn open_artifact<'a>(
+    bytes: &'a [u8],
+    verifier: &dyn ArtifactVerifier,
+) -> Result<&'a [u8], ArtifactError> {
+    let Some(rest) = bytes.strip_prefix(ARTIFACT_MAGIC.as_slice()) else {
+        return Err(ArtifactError::Malformed("not a signed artifact"));
+    };
+    let Some((&version, mut rest)) = rest.split_first() else {
+        return Err(ArtifactError::Malformed("missing version"));
+    };
+    if version != ARTIFACT_VERSION {
+        return Err(ArtifactError::Malformed("unsupported version"));
+    }
+    let signer_id = read_field(&mut rest, "truncated signer id")?;
+    let header_len = bytes.len() - rest.len();
+    let signature = read_field(&mut rest, "truncated signature")?;
+    let payload = rest;
+
+    if signer_id != verifier.signer_id() {
+        return Err(ArtifactError::UnexpectedSigner {
+            signer_id: signer_id.to_vec(),
+        });
+    }
+    if !verifier.verify(&[&bytes[..header_len], payload].concat(), signature) {
+        return Err(ArtifactError::InvalidSignature);
+    }
+    Ok(payload)
+}
+
+impl<SC: StarkGenericConfig + Serialize + DeserializeOwned> SP1ProofWithIO<SC> {
+    /// Encodes the proof with bincode in a container signed by the signer.
+    pub fn to_signed_bytes(&self, signer: &dyn ArtifactSigner) -> Vec<u8> {
+        sign_artifact(&bincode::serialize(self).unwrap(), signer)
+    }
+
+    /// Decodes a proof encoded with `to_signed_bytes`, only once its signature is checked to be one
+    /// of the verifier's signer. The proof itself is still to be verified.
+    pub fn from_signed_bytes(
+        bytes: &[u8],
+        verifier: &dyn ArtifactVerifier,
+    ) -> Result<Self, ArtifactError> {
+        let payload = open_artifact(bytes, verifier)?;
+        bincode::deserialize(payload).map_err(ArtifactError::Payload)
+    }
+}
+
+#[cfg(feature = "ed25519")]
+mod ed25519 {
+    use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+
+    use super::{ArtifactSigner, ArtifactVerifier};
+
+    /// Signs with an ed25519 key, identified by its public key.
+    impl ArtifactSigner for SigningKey {
+        fn signer_id(&self) -> Vec<u8> {
+            self.verifying_key().to_bytes().to_vec()
+        }
+
+        fn sign(&self, message: &[u8]) -> Vec<u8> {
+            Signer::sign(self, message).to_bytes().to_vec()
+        }
+    }
+
+    /// Checks the ed25519 signatures of the owner of the public key.
+    impl ArtifactVerifier for VerifyingKey {
+        fn signer_id(&self) -> Vec<u8> {
+            self.to_bytes().to_vec()
+        }
+
+        fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+            Signature::from_slice(signature)
+                .map(|signature| Verifier::verify(self, message, &signature).is_ok())
+                .unwrap_or(false)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{tests::FIBONACCI_IO_ELF, BabyBearBlake3};
+    use crate::{SP1Prover, SP1Stdin, SP1Verifier};
+
+    /// Signs with a keyed blake3 hash, standing in for a real signature scheme.
+    struct KeyedSigner([u8; 32]);
+
+    impl ArtifactSigner for KeyedSigner {
+        fn signer_id(&self) -> Vec<u8> {
+            blake3::hash(&self.0).as_bytes().to_vec()
+        }
+
+        fn sign(&self, message: &[u8]) -> Vec<u8> {
+            blake3::keyed_hash(&self.0, message).as_bytes().to_vec()
+        }
+    }
+
+    impl ArtifactVerifier for KeyedSigner {
+        fn signer_id(&self) -> Vec<u8> {
+            ArtifactSigner::signer_id(self)
+        }
+
+        fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+            self.sign(message) == signature
+        }
+    }
+
+    #[test]
+    fn test_artifact_roundtrip() {
+        let signer = KeyedSigner([1; 32]);
+        let bytes = sign_artifact(b"payload", &signer);
+        assert!(bytes.starts_with(b"SP1S"));
+        assert_eq!(open_artifact(&bytes, &signer).unwrap(), b"payload");
+
+        let other = KeyedSigner([2; 32]);
+        assert!(matches!(
+            open_artifact(&bytes, &other),
+            Err(ArtifactError::UnexpectedSigner { .. })
+        ));
+
+        // Flipping any byte of the header or the payload breaks the signature or the header.
+        for i in 0..bytes.len() {
+            let mut tampered = bytes.clone();
+            tampered[i] ^= 1;
+            assert!(open_artifact(&tampered, &signer).is_err(), "byte {}", i);
+        }
+        for len in 0..bytes.len() - b"payload".len() {
+            assert!(open_artifact(&bytes[..len], &signer).is_err());
+        }
+    }
+
+    #[test]
+    fn test_signed_proof() {
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&3u32);
+        let proof = SP1Prover::prove(FIBONACCI_IO_ELF, stdin).unwrap();
+
+        let signer = KeyedSigner([1; 32]);
+        let bytes = proof.to_signed_bytes(&signer);
+        let decoded = SP1ProofWithIO::<BabyBearBlake3>::from_signed_bytes(&bytes, &signer).unwrap();
+        SP1Verifier::verify(FIBONACCI_IO_ELF, &decoded).unwrap();
+
+        // A tampered payload is rejected by its signature, before it is decoded.
+        let mut tampered = bytes.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(matches!(
+            SP1ProofWithIO::<BabyBearBlake3>::from_signed_bytes(&tampered, &signer),
+            Err(ArtifactError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "ed25519")]
+    fn test_ed25519_artifact() {
+        use ed25519_dalek::SigningKey;
+
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let bytes = sign_artifact(b"payload", &key);
+        assert_eq!(
+            open_artifact(&bytes, &key.verifying_key()).unwrap(),
+            b"payload"
+        );
+
+        let mut tampered = bytes.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(matches!(
+            open_artifact(&tampered, &key.verifying_key()),
+            Err(ArtifactError::InvalidSignature)
+        ));
+    }
+}
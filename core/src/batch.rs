@@ -0,0 +1,250 @@
+//! Proving several independent programs in one invocation.
+//!
+//! The jobs of a batch share the config of the prover and its thread pool, and run up to a given
+//! number at a time, as long as the trace cells they need together fit in a global budget.
+
+use core::fmt::{Display, Formatter};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+use crate::runtime::Program;
+use crate::stark::{ChipCells, RiscvStark};
+use crate::utils::{env, BabyBearBlake3};
+use crate::{execute_program, prove_runtime, stdin_binding};
+use crate::{SP1ProofWithIO, SP1ProverOptions, SP1Stdin, SP1Stdout};
+
+/// A program to prove in a batch, with its input and options.
+pub struct ProveJob {
+    pub elf: Vec<u8>,
+    pub stdin: SP1Stdin,
+    pub options: SP1ProverOptions,
+}
+
+/// How the jobs of a batch are run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchOptions {
+    /// The number of jobs proven at the same time.
+    pub concurrency: usize,
+    /// The number of trace cells the jobs proven at the same time may need together. A job which
+    /// needs more than the whole budget is proven alone.
+    pub max_cells: usize,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 1,
+            max_cells: env::max_cells(),
+        }
+    }
+}
+
+/// The time spent on a job of a batch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JobTiming {
+    pub execution: Duration,
+    /// Zero if the job failed before proving.
+    pub proving: Duration,
+    /// The estimated trace cells of the job, zero if it failed before proving.
+    pub cells: usize,
+}
+
+/// The results of the jobs of a batch, in the order of the jobs.
+pub struct BatchResults {
+    pub results: Vec<Result<SP1ProofWithIO<BabyBearBlake3>>>,
+    pub timings: Vec<JobTiming>,
+}
+
+impl Display for BatchResults {
+    /// One line per job, with its outcome and timings.
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        for (index, (result, timing)) in self.results.iter().zip(self.timings.iter()).enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "job {}: executed in {:?}, proved in {:?}, {} cells: ",
+                index, timing.execution, timing.proving, timing.cells
+            )?;
+            match result {
+                Ok(proof) => write!(f, "{} shards", proof.proof.shard_proofs.len())?,
+                Err(err) => write!(f, "failed: {:#}", err)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The trace cells in use by the jobs being proven.
+struct CellBudget {
+    max_cells: usize,
+    used: Mutex<usize>,
+    released: Condvar,
+}
+
+impl CellBudget {
+    /// Waits until `cells` fit in the budget, and takes them. Returns the cells taken, at most the
+    /// whole budget.
+    fn acquire(&self, cells: usize) -> usize {
+        let cells = cells.min(self.max_cells);
+        let mut used = self.used.lock().unwrap();
+        while *used + cells > self.max_cells {
+            used = self.released.wait(used).unwrap();
+        }
+        *used += cells;
+        cells
+    }
+
+    fn release(&self, cells: usize) {
+        *self.used.lock().unwrap() -= cells;
+        self.released.notify_all();
+    }
+}
+
+/// Proves the jobs with the options, a failing or panicking job failing alone.
+pub(crate) fn prove_batch(jobs: Vec<ProveJob>, options: BatchOptions) -> BatchResults {
+    let config = BabyBearBlake3::new();
+    let machine = RiscvStark::new(config.clone());
+    let budget = CellBudget {
+        max_cells: options.max_cells,
+        used: Mutex::new(0),
+        released: Condvar::new(),
+    };
+    let num_jobs = jobs.len();
+    let jobs = jobs
+        .into_iter()
+        .map(|job| Mutex::new(Some(job)))
+        .collect::<Vec<_>>();
+    let next = AtomicUsize::new(0);
+    let outcomes = Mutex::new(
+        (0..num_jobs)
+            .map(|_| None)
+            .collect::<Vec<Option<(Result<_>, JobTiming)>>>(),
+    );
+
+    let prove_job = |job: ProveJob, timing: &mut JobTiming| -> Result<_> {
+        let start = Instant::now();
+        let runtime = execute_program(Program::from(job.elf.as_slice()), &job.stdin, &job.options)?;
+        timing.execution = start.elapsed();
+
+        timing.cells = machine
+            .shard_cells(&runtime.record)
+            .iter()
+            .map(ChipCells::total)
+            .sum();
+        let cells = budget.acquire(timing.cells);
+        let start = Instant::now();
+        let stdout = SP1Stdout::from(&runtime.state.output_stream);
+        let binding = stdin_binding(&runtime, &job.stdin, job.options.stdin_policy);
+        let proof = panic::catch_unwind(AssertUnwindSafe(|| {
+            prove_runtime(config.clone(), runtime, &[], binding, &job.options)
+        }));
+        budget.release(cells);
+        timing.proving = start.elapsed();
+        let proof = proof.map_err(|_| anyhow::anyhow!("proving panicked"))??;
+        Ok(SP1ProofWithIO {
+            proof,
+            stdin: job.stdin,
+            stdout,
+        })
+    };
+
+    std::thread::scope(|scope| {
+        for _ in 0..options.concurrency.clamp(1, num_jobs.max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::SeqCst);
+                let Some(job) = jobs.get(index) else {
+                    break;
+                };
+                let job = job.lock().unwrap().take().expect("every job is taken once");
+                let mut timing = JobTiming::default();
+                let result = panic::catch_unwind(AssertUnwindSafe(|| prove_job(job, &mut timing)))
+                    .unwrap_or_else(|_| Err(anyhow::anyhow!("execution panicked")));
+                outcomes.lock().unwrap()[index] = Some((result, timing));
+            });
+        }
+    });
+
+    let (results, timings) = outcomes
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|outcome| outcome.expect("every job is run"))
+        .unzip();
+    BatchResults { results, timings }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tests::FIBONACCI_IO_ELF;
+    use crate::{SP1Prover, SP1Verifier};
+
+    fn job(elf: &[u8], n: u32) -> ProveJob {
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&n);
+        ProveJob {
+            elf: elf.to_vec(),
+            stdin,
+            options: SP1ProverOptions::default(),
+        }
+    }
+
+    #[test]
+    fn test_prove_batch() {
+        let jobs = vec![job(FIBONACCI_IO_ELF, 3), job(FIBONACCI_IO_ELF, 10)];
+        let options = BatchOptions {
+            concurrency: 2,
+            ..Default::default()
+        };
+        let mut batch = SP1Prover::prove_batch_with_options(jobs, options);
+
+        assert_eq!(batch.results.len(), 2);
+        for result in batch.results.iter() {
+            SP1Verifier::verify(FIBONACCI_IO_ELF, result.as_ref().unwrap()).unwrap();
+        }
+        assert!(batch
+            .timings
+            .iter()
+            .all(|timing| timing.proving > Duration::ZERO && timing.cells > 0));
+        let summary = batch.to_string();
+        assert!(summary.starts_with("job 0: executed in"));
+        assert!(summary.contains("\njob 1: executed in"));
+
+        // The outputs are those of the inputs of each job.
+        let mut outputs = batch
+            .results
+            .iter_mut()
+            .map(|result| result.as_mut().unwrap().stdout.read::<u32>())
+            .collect::<Vec<_>>();
+        outputs.dedup();
+        assert_eq!(outputs.len(), 2);
+    }
+
+    #[test]
+    fn test_failing_job() {
+        // A job with a budget of one cell and a job which cannot be executed.
+        let jobs = vec![
+            ProveJob {
+                elf: vec![0; 16],
+                ..job(FIBONACCI_IO_ELF, 3)
+            },
+            job(FIBONACCI_IO_ELF, 3),
+        ];
+        let options = BatchOptions {
+            concurrency: 1,
+            max_cells: 1,
+        };
+        let batch = SP1Prover::prove_batch_with_options(jobs, options);
+        assert!(batch.results[0].is_err());
+        assert_eq!(batch.timings[0].proving, Duration::ZERO);
+        SP1Verifier::verify(FIBONACCI_IO_ELF, batch.results[1].as_ref().unwrap()).unwrap();
+        assert!(batch.to_string().contains("job 0: executed in"));
+        assert!(batch.to_string().contains("failed: "));
+    }
+}
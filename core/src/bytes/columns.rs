@@ -5,12 +5,15 @@ use std::mem::size_of;
 
 use super::NUM_BYTE_OPS;
 
+/// The number of preprocessed trace columns for `ByteChip`, which form the fixed table.
+pub const NUM_BYTE_PREPROCESSED_COLS: usize = size_of::<BytePreprocessedCols<u8>>();
+
 /// The number of main trace columns for `ByteChip`.
-pub const NUM_BYTE_COLS: usize = size_of::<ByteCols<u8>>();
+pub const NUM_BYTE_MULT_COLS: usize = size_of::<ByteMultCols<u8>>();
 
 #[derive(Debug, Clone, Copy, AlignedBorrow)]
 #[repr(C)]
-pub struct ByteCols<T> {
+pub struct BytePreprocessedCols<T> {
     /// The first byte operand.
     pub b: T,
 
@@ -42,5 +45,19 @@ pub struct ByteCols<T> {
     /// A u16 value used for `U16Range`.
     pub value_u16: T,
 
+    /// The number of set bits of `b`.
+    pub popcount: T,
+
+    /// The number of leading zeros of `b`.
+    pub clz: T,
+
+    /// The number of trailing zeros of `b`.
+    pub ctz: T,
+}
+
+/// The multiplicities of each byte operation, in the main trace of `ByteChip`.
+#[derive(Debug, Clone, Copy, AlignedBorrow)]
+#[repr(C)]
+pub struct ByteMultCols<T> {
     pub multiplicities: [T; NUM_BYTE_OPS],
 }
@@ -1,7 +1,9 @@
+use serde::{Deserialize, Serialize};
+
 use super::ByteOpcode;
 
 /// A byte lookup event.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct ByteLookupEvent {
     /// The opcode of the operation.
     pub opcode: ByteOpcode,
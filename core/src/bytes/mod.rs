@@ -15,12 +15,12 @@ use p3_field::Field;
 use p3_matrix::dense::RowMajorMatrix;
 use std::marker::PhantomData;
 
-use self::columns::{ByteCols, NUM_BYTE_COLS};
+use self::columns::{BytePreprocessedCols, NUM_BYTE_PREPROCESSED_COLS};
 use self::utils::shr_carry;
 use crate::bytes::trace::NUM_ROWS;
 
 /// The number of different byte operations.
-pub const NUM_BYTE_OPS: usize = 9;
+pub const NUM_BYTE_OPS: usize = 12;
 
 /// A chip for computing byte operations.
 ///
@@ -33,7 +33,7 @@ impl<F: Field> ByteChip<F> {
     /// Creates the preprocessed byte trace and event map.
     ///
     /// This function returns a pair `(trace, map)`, where:
-    ///  - `trace` is a matrix containing all possible byte operations.
+    ///  - `trace` is the preprocessed matrix containing all possible byte operations.
     /// - `map` is a map map from a byte lookup to the corresponding row it appears in the table and
     /// the index of the result in the array of multiplicities.
     pub fn trace_and_map() -> (RowMajorMatrix<F>, BTreeMap<ByteLookupEvent, (usize, usize)>) {
@@ -41,9 +41,11 @@ impl<F: Field> ByteChip<F> {
         // multiplicities.
         let mut event_map = BTreeMap::new();
 
-        // The trace containing all values, with all multiplicities set to zero.
-        let mut initial_trace =
-            RowMajorMatrix::new(vec![F::zero(); NUM_ROWS * NUM_BYTE_COLS], NUM_BYTE_COLS);
+        // The trace containing all values.
+        let mut initial_trace = RowMajorMatrix::new(
+            vec![F::zero(); NUM_ROWS * NUM_BYTE_PREPROCESSED_COLS],
+            NUM_BYTE_PREPROCESSED_COLS,
+        );
 
         // Record all the necessary operations for each byte lookup.
         let opcodes = ByteOpcode::all();
@@ -52,7 +54,7 @@ impl<F: Field> ByteChip<F> {
         for (row_index, (b, c)) in (0..=u8::MAX).cartesian_product(0..=u8::MAX).enumerate() {
             let b = b as u8;
             let c = c as u8;
-            let col: &mut ByteCols<F> = initial_trace.row_mut(row_index).borrow_mut();
+            let col: &mut BytePreprocessedCols<F> = initial_trace.row_mut(row_index).borrow_mut();
 
             // Set the values of `b` and `c`.
             col.b = F::from_canonical_u8(b);
@@ -103,6 +105,21 @@ impl<F: Field> ByteChip<F> {
                         col.value_u16 = F::from_canonical_u32(v);
                         ByteLookupEvent::new(*opcode, v, 0, 0, 0)
                     }
+                    ByteOpcode::Popcount => {
+                        let popcount = b.count_ones();
+                        col.popcount = F::from_canonical_u32(popcount);
+                        ByteLookupEvent::new(*opcode, popcount, 0, b as u32, 0)
+                    }
+                    ByteOpcode::CLZ => {
+                        let clz = b.leading_zeros();
+                        col.clz = F::from_canonical_u32(clz);
+                        ByteLookupEvent::new(*opcode, clz, 0, b as u32, 0)
+                    }
+                    ByteOpcode::CTZ => {
+                        let ctz = b.trailing_zeros();
+                        col.ctz = F::from_canonical_u32(ctz);
+                        ByteLookupEvent::new(*opcode, ctz, 0, b as u32, 0)
+                    }
                 };
                 event_map.insert(event, (row_index, i));
             }
@@ -1,9 +1,10 @@
 use p3_field::Field;
+use serde::{Deserialize, Serialize};
 
 use crate::{bytes::NUM_BYTE_OPS, runtime::Opcode};
 
 /// A byte opcode which the chip can process.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum ByteOpcode {
     /// Bitwise AND.
     AND = 0,
@@ -31,6 +32,15 @@ pub enum ByteOpcode {
 
     /// U16 Range check.
     U16Range = 8,
+
+    /// The number of set bits of the given byte.
+    Popcount = 9,
+
+    /// The number of leading zeros of the given byte.
+    CLZ = 10,
+
+    /// The number of trailing zeros of the given byte.
+    CTZ = 11,
 }
 
 impl ByteOpcode {
@@ -46,6 +56,9 @@ impl ByteOpcode {
             ByteOpcode::LTU,
             ByteOpcode::MSB,
             ByteOpcode::U16Range,
+            ByteOpcode::Popcount,
+            ByteOpcode::CLZ,
+            ByteOpcode::CTZ,
         ];
         assert_eq!(opcodes.len(), NUM_BYTE_OPS);
         opcodes
@@ -1,8 +1,15 @@
 use p3_field::Field;
 use p3_matrix::dense::RowMajorMatrix;
 
-use super::{air::BYTE_MULT_INDICES, ByteChip};
-use crate::{air::MachineAir, runtime::ExecutionRecord};
+use super::{
+    air::BYTE_MULT_INDICES,
+    columns::{NUM_BYTE_MULT_COLS, NUM_BYTE_PREPROCESSED_COLS},
+    ByteChip,
+};
+use crate::{
+    air::{EventKind, MachineAir},
+    runtime::{ExecutionRecord, Program},
+};
 
 pub const NUM_ROWS: usize = 1 << 16;
 
@@ -11,12 +18,37 @@ impl<F: Field> MachineAir<F> for ByteChip<F> {
         "Byte".to_string()
     }
 
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::ByteLookup]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        NUM_ROWS
+    }
+
+    fn included(&self, shard: &ExecutionRecord) -> bool {
+        !shard.byte_lookups.is_empty()
+    }
+
+    fn preprocessed_width(&self) -> usize {
+        NUM_BYTE_PREPROCESSED_COLS
+    }
+
+    fn generate_preprocessed_trace(&self, _program: &Program) -> Option<RowMajorMatrix<F>> {
+        let (trace, _) = ByteChip::<F>::trace_and_map();
+        Some(trace)
+    }
+
     fn generate_trace(
         &self,
         input: &ExecutionRecord,
         _output: &mut ExecutionRecord,
     ) -> RowMajorMatrix<F> {
-        let (mut trace, event_map) = ByteChip::trace_and_map();
+        let (_, event_map) = ByteChip::<F>::trace_and_map();
+        let mut trace = RowMajorMatrix::new(
+            vec![F::zero(); NUM_ROWS * NUM_BYTE_MULT_COLS],
+            NUM_BYTE_MULT_COLS,
+        );
 
         for (lookup, mult) in input.byte_lookups.iter() {
             let (row, index) = event_map[lookup];
@@ -0,0 +1,540 @@
+//! Checkpoints of executions, for proving their shards one at a time and resuming a proof which
+//! was interrupted.
+//!
+//! A checkpoint is a directory holding
+//!
+//! ```text
+//! manifest.json
+//! shard-<index>.record
+//! shard-<index>.commit
+//! shard-<index>.proof
+//! ```
+//!
+//! where the manifest is a [`CheckpointManifest`] in JSON, written after the records, and each
+//! record is the bincode encoding of the `ExecutionRecord` of a shard. Every shard proof observes
+//! the commitments to the main traces of all the shards, so the commitments are written the first
+//! time a shard is proven and read back by the others. The proof of a shard is written once it is
+//! proven, so a shard whose proof file is missing is proven again from its record alone, and the
+//! proof of the execution is assembled once every shard has its proof file.
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use p3_commit::Pcs;
+use p3_field::PrimeField32;
+use p3_matrix::dense::RowMajorMatrix;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::air::PublicValues;
+use crate::io::StdinBinding;
+use crate::runtime::{ExecutionError, ExecutionRecord, Runtime, ShardStats, ShardingConfig};
+use crate::stark::{observe_claimed_values, observe_main_commitments, observe_stdin};
+use crate::stark::{CellBudgetExceededError, Com, LocalProver, OpeningProof, Proof, RiscvStark};
+use crate::stark::{ProverError, ShardMainData, ShardProof, StarkGenericConfig};
+use crate::utils::{env, BabyBearBlake3, StarkUtils};
+use crate::witness::MANIFEST_FILE;
+use crate::{SP1Stdin, SP1Stdout};
+
+/// The version of the layout of checkpoints.
+pub const CHECKPOINT_VERSION: u8 = 1;
+
+/// The description of a checkpoint, and everything in it but the records and the proofs.
+#[derive(Serialize, Deserialize)]
+pub struct CheckpointManifest {
+    pub version: u8,
+    /// The hex digests of the preprocessed traces of the program, by chip name.
+    pub preprocessed_digests: BTreeMap<String, String>,
+    /// The input of the program, unknown to checkpoints taken from a runtime.
+    pub stdin: Option<SP1Stdin>,
+    pub stdout: SP1Stdout,
+    /// The binding of the proof to `stdin`, if the proof binds it.
+    pub stdin_binding: Option<StdinBinding>,
+    /// The bytes the program committed, which the proof carries as claimed values.
+    #[serde(rename = "committed_values")]
+    pub claimed_values: Vec<u8>,
+    pub shards: Vec<ShardCheckpoint>,
+}
+
+/// The description of the record of a shard.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardCheckpoint {
+    pub index: usize,
+    /// The public values of the shard, which every shard proof observes.
+    pub public_values: PublicValues<u32>,
+    /// The number of events of each kind in the shard.
+    pub stats: ShardStats,
+}
+
+/// Why a checkpoint could not be taken, proven or assembled into a proof.
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(io::Error),
+    Manifest(serde_json::Error),
+    /// The manifest is of a version this build does not read.
+    UnsupportedVersion(u8),
+    /// A record, commitment or proof file does not decode.
+    InvalidFile {
+        file: String,
+        reason: String,
+    },
+    /// The shards are not indexed contiguously from zero.
+    InvalidShardIndex {
+        expected: usize,
+        found: usize,
+    },
+    /// The checkpoint has no shard with this index.
+    NoSuchShard {
+        index: usize,
+        num_shards: usize,
+    },
+    /// The proof of the shard with this index has not been written yet.
+    MissingProof(usize),
+    /// The checkpoint is not one of the program, whose preprocessed traces differ.
+    ProgramMismatch,
+    /// A shard has too many cells to generate its traces.
+    CellBudget(CellBudgetExceededError),
+    Execution(ExecutionError),
+    /// A shard fails the self check of the prover.
+    Prover(ProverError),
+}
+
+impl Display for CheckpointError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CheckpointError::Io(e) => write!(f, "{}", e),
+            CheckpointError::Manifest(e) => write!(f, "invalid checkpoint manifest: {}", e),
+            CheckpointError::UnsupportedVersion(version) => {
+                write!(f, "unsupported checkpoint version {}", version)
+            }
+            CheckpointError::InvalidFile { file, reason } => {
+                write!(f, "invalid checkpoint file {}: {}", file, reason)
+            }
+            CheckpointError::InvalidShardIndex { expected, found } => {
+                write!(
+                    f,
+                    "expected shard {} in the manifest, found {}",
+                    expected, found
+                )
+            }
+            CheckpointError::NoSuchShard { index, num_shards } => {
+                write!(
+                    f,
+                    "no shard {} in a checkpoint of {} shards",
+                    index, num_shards
+                )
+            }
+            CheckpointError::MissingProof(index) => {
+                write!(f, "shard {} has not been proven", index)
+            }
+            CheckpointError::ProgramMismatch => {
+                write!(f, "the checkpoint was taken for a different program")
+            }
+            CheckpointError::CellBudget(e) => write!(f, "{}", e),
+            CheckpointError::Execution(e) => write!(f, "{}", e),
+            CheckpointError::Prover(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+impl From<io::Error> for CheckpointError {
+    fn from(e: io::Error) -> Self {
+        CheckpointError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for CheckpointError {
+    fn from(e: serde_json::Error) -> Self {
+        CheckpointError::Manifest(e)
+    }
+}
+
+impl From<ProverError> for CheckpointError {
+    fn from(e: ProverError) -> Self {
+        CheckpointError::Prover(e)
+    }
+}
+
+impl From<ExecutionError> for CheckpointError {
+    fn from(e: ExecutionError) -> Self {
+        CheckpointError::Execution(e)
+    }
+}
+
+impl Runtime {
+    /// Executes the program and checkpoints the records of its shards to the directory, creating
+    /// it if needed, in shards of at most `shard_size` instructions.
+    ///
+    /// The record is moved to the checkpoint, so the runtime is left with an empty one. The input
+    /// of the runtime is not known, so the proof of the checkpoint does not bind it.
+    pub fn execute_with_checkpoints(
+        &mut self,
+        dir: &Path,
+    ) -> Result<CheckpointManifest, CheckpointError> {
+        self.try_run()?;
+        let stdout = SP1Stdout::from(&self.state.output_stream);
+        CheckpointManifest::write(self, None, stdout, None, dir)
+    }
+}
+
+impl CheckpointManifest {
+    /// Shards the record of the executed runtime as `SP1Prover::prove_with_options` does, and
+    /// writes the record of each shard and then the manifest to the directory.
+    pub(crate) fn write(
+        runtime: &mut Runtime,
+        stdin: Option<SP1Stdin>,
+        stdout: SP1Stdout,
+        stdin_binding: Option<StdinBinding>,
+        dir: &Path,
+    ) -> Result<Self, CheckpointError> {
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let preprocessed_digests = machine
+            .preprocessed_digests(&runtime.program)
+            .into_iter()
+            .map(|(name, digest)| (name, hex::encode(digest)))
+            .collect();
+
+        let record = std::mem::take(&mut runtime.record);
+        let claimed_values = record.committed_values.clone();
+        let config = ShardingConfig::with_shard_size(runtime.shard_size as usize);
+        let shards = machine.shard(record, &config);
+        machine
+            .check_cell_budget(&shards, env::max_cells(), config.shard_size())
+            .map_err(CheckpointError::CellBudget)?;
+
+        fs::create_dir_all(dir)?;
+        let shards = shards
+            .iter()
+            .enumerate()
+            .map(|(index, shard)| {
+                write_file(dir, &record_file(index), shard)?;
+                Ok(ShardCheckpoint {
+                    index,
+                    public_values: PublicValues::from_record(shard),
+                    stats: shard.stats(),
+                })
+            })
+            .collect::<Result<Vec<_>, CheckpointError>>()?;
+
+        let manifest = Self {
+            version: CHECKPOINT_VERSION,
+            preprocessed_digests,
+            stdin,
+            stdout,
+            stdin_binding,
+            claimed_values,
+            shards,
+        };
+        // The manifest comes last, so that a directory with one holds all of its records.
+        fs::write(
+            dir.join(MANIFEST_FILE),
+            serde_json::to_vec_pretty(&manifest)?,
+        )?;
+        Ok(manifest)
+    }
+
+    /// Reads the manifest of the checkpoint in the directory.
+    pub fn load(dir: &Path) -> Result<Self, CheckpointError> {
+        let manifest =
+            serde_json::from_slice::<CheckpointManifest>(&fs::read(dir.join(MANIFEST_FILE))?)?;
+        if manifest.version != CHECKPOINT_VERSION {
+            return Err(CheckpointError::UnsupportedVersion(manifest.version));
+        }
+        for (expected, shard) in manifest.shards.iter().enumerate() {
+            if shard.index != expected {
+                return Err(CheckpointError::InvalidShardIndex {
+                    expected,
+                    found: shard.index,
+                });
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Checks that the checkpoint is one of the program with the given digests of preprocessed
+    /// traces.
+    pub fn check_program(
+        &self,
+        preprocessed_digests: &BTreeMap<String, [u8; 32]>,
+    ) -> Result<(), CheckpointError> {
+        let digests = preprocessed_digests
+            .iter()
+            .map(|(name, digest)| (name.clone(), hex::encode(digest)))
+            .collect::<BTreeMap<_, _>>();
+        if digests != self.preprocessed_digests {
+            return Err(CheckpointError::ProgramMismatch);
+        }
+        Ok(())
+    }
+
+    /// The indices of the shards whose proof file is not in the directory.
+    pub fn missing_proofs(&self, dir: &Path) -> Vec<usize> {
+        self.shards
+            .iter()
+            .map(|shard| shard.index)
+            .filter(|index| !dir.join(proof_file(*index)).exists())
+            .collect()
+    }
+
+    fn public_values(&self) -> Vec<PublicValues<u32>> {
+        self.shards
+            .iter()
+            .map(|shard| shard.public_values)
+            .collect()
+    }
+}
+
+/// Proves the shard of the checkpoint in the directory from its record, and writes its proof to
+/// the directory.
+///
+/// The commitments of the shards which have none in the directory yet are computed and written
+/// first. The proof is the one `SP1Prover::prove_with_options` proves the shard into.
+pub fn prove_shard_from_checkpoint<SC>(
+    dir: &Path,
+    shard_index: usize,
+    config: SC,
+) -> Result<ShardProof<SC>, CheckpointError>
+where
+    SC: StarkGenericConfig + StarkUtils + Send + Sync + Serialize + DeserializeOwned,
+    SC::Challenger: Clone,
+    OpeningProof<SC>: Send + Sync,
+    <SC::Pcs as Pcs<SC::Val, RowMajorMatrix<SC::Val>>>::Commitment: Send + Sync,
+    <SC::Pcs as Pcs<SC::Val, RowMajorMatrix<SC::Val>>>::ProverData: Send + Sync,
+    ShardMainData<SC>: Serialize + DeserializeOwned,
+    SC::Val: PrimeField32,
+{
+    let manifest = CheckpointManifest::load(dir)?;
+    if shard_index >= manifest.shards.len() {
+        return Err(CheckpointError::NoSuchShard {
+            index: shard_index,
+            num_shards: manifest.shards.len(),
+        });
+    }
+    let record = read_file::<ExecutionRecord>(dir, &record_file(shard_index))?;
+    let machine = RiscvStark::new(config);
+    manifest.check_program(&machine.preprocessed_digests(&record.program))?;
+    let (pk, _) = machine.setup(&record.program);
+
+    let mut shard_data = None;
+    let mut commitments = Vec::with_capacity(manifest.shards.len());
+    for shard in manifest.shards.iter() {
+        let file = commitment_file(shard.index);
+        if dir.join(&file).exists() {
+            commitments.push(read_file::<Com<SC>>(dir, &file)?);
+        } else if shard.index == shard_index {
+            let data = LocalProver::commit_shard(&machine, &record, shard.index)?;
+            write_file(dir, &file, &data.main_commit)?;
+            commitments.push(data.main_commit.clone());
+            shard_data = Some(data);
+        } else {
+            let shard_record = read_file::<ExecutionRecord>(dir, &record_file(shard.index))?;
+            let data = LocalProver::commit_shard(&machine, &shard_record, shard.index)?;
+            write_file(dir, &file, &data.main_commit)?;
+            commitments.push(data.main_commit);
+        }
+    }
+
+    // The challenger observes what the one of `prove_with_stdin` does before proving the shards.
+    let mut challenger = machine.config().challenger();
+    observe_stdin::<SC>(&mut challenger, manifest.stdin_binding.as_ref());
+    observe_claimed_values::<SC>(&mut challenger, &manifest.claimed_values);
+    observe_main_commitments::<SC>(
+        &mut challenger,
+        &pk.preprocessed_commits,
+        commitments,
+        &manifest.public_values(),
+    );
+
+    let data = match shard_data {
+        Some(data) => data,
+        None => LocalProver::commit_shard(&machine, &record, shard_index)?,
+    };
+    let proof = LocalProver::prove_committed_shard(&machine, &pk, &record, data, &mut challenger);
+    write_file(dir, &proof_file(shard_index), &proof)?;
+    Ok(proof)
+}
+
+/// Assembles the proof of the execution from the proofs of all the shards of the checkpoint in
+/// the directory, replaying the observations of their commitments in the order of the shards to
+/// recover the digest binding them together.
+///
+/// The commitments to the preprocessed traces, which the digest binds too, are recomputed from the
+/// program of the record of the first shard.
+pub fn assemble_checkpoint_proof<SC>(dir: &Path, config: SC) -> Result<Proof<SC>, CheckpointError>
+where
+    SC: StarkGenericConfig + StarkUtils + Serialize + DeserializeOwned,
+{
+    let manifest = CheckpointManifest::load(dir)?;
+    let mut shard_proofs = Vec::with_capacity(manifest.shards.len());
+    let mut commitments = Vec::with_capacity(manifest.shards.len());
+    for shard in manifest.shards.iter() {
+        let file = proof_file(shard.index);
+        if !dir.join(&file).exists() {
+            return Err(CheckpointError::MissingProof(shard.index));
+        }
+        shard_proofs.push(read_file::<ShardProof<SC>>(dir, &file)?);
+        commitments.push(read_file::<Com<SC>>(dir, &commitment_file(shard.index))?);
+    }
+    let first = manifest
+        .shards
+        .first()
+        .ok_or(CheckpointError::NoSuchShard {
+            index: 0,
+            num_shards: 0,
+        })?;
+    let record = read_file::<ExecutionRecord>(dir, &record_file(first.index))?;
+    let machine = RiscvStark::new(config);
+    manifest.check_program(&machine.preprocessed_digests(&record.program))?;
+    let (pk, _) = machine.setup(&record.program);
+
+    let mut challenger = machine.config().challenger();
+    observe_stdin::<SC>(&mut challenger, manifest.stdin_binding.as_ref());
+    observe_claimed_values::<SC>(&mut challenger, &manifest.claimed_values);
+    let digest = observe_main_commitments::<SC>(
+        &mut challenger,
+        &pk.preprocessed_commits,
+        commitments,
+        &manifest.public_values(),
+    );
+
+    Ok(Proof {
+        shard_proofs,
+        digest,
+        context: Vec::new(),
+        stdin: manifest.stdin_binding,
+        hasher_params: machine.config().hasher_params_id(),
+        claimed_values: manifest.claimed_values,
+        preprocessed_digests: pk.preprocessed_digests,
+    })
+}
+
+fn record_file(index: usize) -> String {
+    format!("shard-{}.record", index)
+}
+
+fn commitment_file(index: usize) -> String {
+    format!("shard-{}.commit", index)
+}
+
+fn proof_file(index: usize) -> String {
+    format!("shard-{}.proof", index)
+}
+
+/// Writes the bincode encoding of the value to the file of the directory, through a temporary
+/// file, so that an interrupted write never leaves a truncated file behind.
+fn write_file<T: Serialize>(dir: &Path, file: &str, value: &T) -> Result<(), CheckpointError> {
+    let bytes = bincode::serialize(value).map_err(|e| CheckpointError::InvalidFile {
+        file: file.to_string(),
+        reason: e.to_string(),
+    })?;
+    let partial = dir.join(format!("{}.partial", file));
+    fs::write(&partial, bytes)?;
+    fs::rename(partial, dir.join(file))?;
+    Ok(())
+}
+
+fn read_file<T: DeserializeOwned>(dir: &Path, file: &str) -> Result<T, CheckpointError> {
+    let bytes = fs::read(dir.join(file))?;
+    bincode::deserialize(&bytes).map_err(|e| CheckpointError::InvalidFile {
+        file: file.to_string(),
+        reason: e.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::Program;
+    use crate::utils::tests::{FIBONACCI_ELF, SHA_EXTEND_ELF};
+    use crate::{SP1Prover, SP1ProverOptions, SP1Verifier};
+
+    fn options() -> SP1ProverOptions {
+        SP1ProverOptions {
+            shard_size: Some(1 << 10),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_resume_from_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = SP1Prover::execute_with_checkpoints(
+            SHA_EXTEND_ELF,
+            SP1Stdin::new(),
+            options(),
+            dir.path(),
+        )
+        .unwrap();
+        let num_shards = manifest.shards.len();
+        assert!(num_shards >= 3);
+        assert_eq!(manifest.missing_proofs(dir.path()).len(), num_shards);
+        assert!(matches!(
+            assemble_checkpoint_proof(dir.path(), BabyBearBlake3::new()),
+            Err(CheckpointError::MissingProof(0))
+        ));
+
+        // The proof of the shards proven one at a time is the one of the execution.
+        let proof = SP1Prover::prove_checkpoints(SHA_EXTEND_ELF, dir.path()).unwrap();
+        assert!(manifest.missing_proofs(dir.path()).is_empty());
+        let direct =
+            SP1Prover::prove_with_options(SHA_EXTEND_ELF, SP1Stdin::new(), options()).unwrap();
+        let proof_bytes = bincode::serialize(&proof.proof).unwrap();
+        assert_eq!(proof_bytes, bincode::serialize(&direct.proof).unwrap());
+        SP1Verifier::verify(SHA_EXTEND_ELF, &proof).unwrap();
+
+        // A shard whose proof was lost is proven again alone, into the same proof.
+        let lost = dir.path().join(proof_file(1));
+        let modified = fs::metadata(dir.path().join(proof_file(0)))
+            .unwrap()
+            .modified()
+            .unwrap();
+        fs::remove_file(&lost).unwrap();
+        assert_eq!(manifest.missing_proofs(dir.path()), vec![1]);
+        let resumed = SP1Prover::prove_checkpoints(SHA_EXTEND_ELF, dir.path()).unwrap();
+        assert!(lost.exists());
+        assert_eq!(
+            fs::metadata(dir.path().join(proof_file(0)))
+                .unwrap()
+                .modified()
+                .unwrap(),
+            modified
+        );
+        assert_eq!(bincode::serialize(&resumed.proof).unwrap(), proof_bytes);
+        SP1Verifier::verify(SHA_EXTEND_ELF, &resumed).unwrap();
+
+        // A checkpoint is only proven for its program.
+        assert!(matches!(
+            prove_shard_from_checkpoint(dir.path(), num_shards, BabyBearBlake3::new()),
+            Err(CheckpointError::NoSuchShard { .. })
+        ));
+        let Err(err) = SP1Prover::prove_checkpoints(FIBONACCI_ELF, dir.path()) else {
+            panic!("the checkpoint was proven for another program");
+        };
+        assert!(matches!(
+            err.downcast::<CheckpointError>(),
+            Ok(CheckpointError::ProgramMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_record_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut runtime = Runtime::new(Program::from(SHA_EXTEND_ELF));
+        let manifest = runtime.execute_with_checkpoints(dir.path()).unwrap();
+        assert!(runtime.record.cpu_events.is_empty());
+        assert!(manifest.stdin_binding.is_none());
+
+        let record = read_file::<ExecutionRecord>(dir.path(), &record_file(0)).unwrap();
+        assert_eq!(record.stats(), manifest.shards[0].stats);
+        assert!(!record.sha_extend_events.is_empty());
+        assert_eq!(
+            record.program.instructions.len(),
+            runtime.program.instructions.len()
+        );
+    }
+}
@@ -34,7 +34,6 @@ impl CpuChip {
         builder: &mut AB,
         is_branch_instruction: AB::Expr,
         local: &CpuCols<AB::Var>,
-        next: &CpuCols<AB::Var>,
     ) {
         // Get the branch specific columns.
         let branch_cols: BranchCols<AB::Var> =
@@ -47,12 +46,6 @@ impl CpuChip {
                 .when(local.branching)
                 .assert_eq(branch_cols.pc.reduce::<AB>(), local.pc);
 
-            // When we are branching, assert that local.pc <==> branch_columns.next_pc as Word.
-            builder
-                .when_transition()
-                .when(local.branching)
-                .assert_eq(branch_cols.next_pc.reduce::<AB>(), next.pc);
-
             // When we are branching, calculate branch_cols.next_pc <==> branch_cols.pc + c.
             builder.send_alu(
                 Opcode::ADD.as_field::<AB::F>(),
@@ -62,12 +55,21 @@ impl CpuChip {
                 local.branching,
             );
 
-            // When we are not branching, assert that local.pc + 4 <==> next.pc.
+            // Select local.next_pc between branch_cols.next_pc when we are branching and
+            // local.pc + 4 when we are not.
+            let next_pc = builder.select_word(
+                local.branching,
+                branch_cols.next_pc,
+                Word::extend_expr::<AB>(local.pc + AB::Expr::from_canonical_u8(4)),
+            );
+            let base = AB::Expr::from_canonical_u32(1 << 8);
+            let next_pc = next_pc
+                .into_iter()
+                .rev()
+                .fold(AB::Expr::zero(), |acc, limb| acc * base.clone() + limb);
             builder
-                .when(local.not_branching)
-                .when_transition()
-                .when(next.is_real)
-                .assert_eq(local.pc + AB::Expr::from_canonical_u8(4), next.pc);
+                .when(is_branch_instruction.clone())
+                .assert_eq(next_pc, local.next_pc);
         }
 
         // Evaluate branching value constraints.
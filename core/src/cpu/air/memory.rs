@@ -7,7 +7,6 @@ use crate::air::{BaseAirBuilder, SP1AirBuilder, Word, WordAirBuilder};
 use crate::cpu::columns::{CpuCols, MemoryColumns, OpcodeSelectorCols, NUM_MEMORY_COLUMNS};
 use crate::cpu::CpuChip;
 use crate::memory::MemoryCols;
-use crate::runtime::Opcode;
 
 impl CpuChip {
     /// Computes whether the opcode is a memory instruction.
@@ -79,26 +78,29 @@ impl CpuChip {
                 memory_columns.most_sig_byte_decomp[7],
             );
 
-        // Use the SUB opcode to compute the signed value of the memory value.
-        let signed_value = Word([
-            AB::Expr::zero(),
-            AB::Expr::one() * local.selectors.is_lb,
-            AB::Expr::one() * local.selectors.is_lh,
-            AB::Expr::zero(),
-        ]);
-        builder.send_alu(
-            Opcode::SUB.as_field::<AB::F>(),
-            local.op_a_val(),
-            local.unsigned_mem_val,
-            signed_value,
+        // Sign extend the memory value: the bytes above the loaded byte or half are 0xff when the
+        // value is negative and 0 otherwise. Unsigned loads and LW leave the value as is.
+        builder.assert_bool(local.mem_value_is_neg);
+        let fill = builder.select_word(
             local.mem_value_is_neg,
+            Word(core::array::from_fn(|_| AB::Expr::from_canonical_u8(0xff))),
+            Word(core::array::from_fn(|_| AB::Expr::zero())),
         );
+        let is_extended = Word([
+            AB::Expr::zero(),
+            local.selectors.is_lb.into(),
+            local.selectors.is_lb + local.selectors.is_lh,
+            local.selectors.is_lb + local.selectors.is_lh,
+        ]);
+        let mut fill = fill.into_iter();
+        let signed_value = Word(core::array::from_fn(|i| {
+            local.unsigned_mem_val[i] + is_extended[i].clone() * fill.next().unwrap()
+        }));
 
         // Assert that the result is in op_a.
         builder
             .when(is_load)
-            .when_not(local.mem_value_is_neg)
-            .assert_word_eq(local.unsigned_mem_val, local.op_a_val());
+            .assert_word_eq(signed_value, local.op_a_val().map(|x| x.into()));
     }
 
     /// Evaluates constraints related to storing to memory.
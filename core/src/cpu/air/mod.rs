@@ -9,12 +9,21 @@ use p3_field::AbstractField;
 use p3_matrix::MatrixRowSlices;
 
 use super::columns::{NUM_AUIPC_COLS, NUM_JUMP_COLS, NUM_MEMORY_COLUMNS};
-use crate::air::{SP1AirBuilder, WordAirBuilder};
+use crate::air::{
+    ByteAirBuilder, PublicValues, PublicValuesBuilder, SP1AirBuilder, SyscallAirBuilder,
+    WordAirBuilder, NUM_PUBLIC_VALUES, WORD_SIZE,
+};
+use crate::bytes::ByteOpcode;
 use crate::cpu::columns::OpcodeSelectorCols;
 use crate::cpu::columns::{AuipcCols, CpuCols, JumpCols, MemoryColumns, NUM_CPU_COLS};
 use crate::cpu::CpuChip;
 use crate::memory::MemoryCols;
-use crate::runtime::{AccessPosition, Opcode};
+use crate::operations::IsZeroOperation;
+use crate::runtime::{AccessPosition, Opcode, Register, SyscallCode};
+
+/// The bound on the most significant byte of a cycle count: the one of the BabyBear modulus
+/// `15 * 2^27 + 1`, so that every count of bytes below it is smaller than the modulus.
+pub(crate) const CYCLE_COUNT_MSB_BOUND: u32 = 120;
 
 impl<AB> Air<AB> for CpuChip
 where
@@ -40,14 +49,6 @@ where
         // Program constraints.
         builder.send_program(local.pc, local.instruction, local.selectors, local.is_real);
 
-        // Load immediates into b and c, if the immediate flags are on.
-        builder
-            .when(local.selectors.imm_b)
-            .assert_word_eq(local.op_b_val(), local.instruction.op_b);
-        builder
-            .when(local.selectors.imm_c)
-            .assert_word_eq(local.op_c_val(), local.instruction.op_c);
-
         // If they are not immediates, read `b` and `c` from memory.
         builder.constraint_memory_access(
             local.shard,
@@ -56,10 +57,6 @@ where
             &local.op_b_access,
             AB::Expr::one() - local.selectors.imm_b,
         );
-        builder
-            .when_not(local.selectors.imm_b)
-            .assert_word_eq(local.op_b_val(), *local.op_b_access.prev_value());
-
         builder.constraint_memory_access(
             local.shard,
             local.clk + AB::F::from_canonical_u32(AccessPosition::C as u32),
@@ -67,9 +64,21 @@ where
             &local.op_c_access,
             AB::Expr::one() - local.selectors.imm_c,
         );
-        builder
-            .when_not(local.selectors.imm_c)
-            .assert_word_eq(local.op_c_val(), *local.op_c_access.prev_value());
+
+        // Load immediates into b and c if the immediate flags are on, and the values read from
+        // memory otherwise.
+        let op_b_val = builder.select_word(
+            local.selectors.imm_b,
+            local.instruction.op_b,
+            *local.op_b_access.prev_value(),
+        );
+        builder.assert_word_eq(local.op_b_val().map(|x| x.into()), op_b_val);
+        let op_c_val = builder.select_word(
+            local.selectors.imm_c,
+            local.instruction.op_c,
+            *local.op_c_access.prev_value(),
+        );
+        builder.assert_word_eq(local.op_c_val().map(|x| x.into()), op_c_val);
 
         // Write the `a` or the result to the first register described in the instruction unless
         // we are performing a branch or a store.
@@ -95,7 +104,7 @@ where
             local.clk + AB::F::from_canonical_u32(AccessPosition::Memory as u32),
             memory_columns.addr_aligned,
             &memory_columns.memory_access,
-            is_memory_instruction.clone(),
+            is_memory_instruction.clone() + local.is_cycle_count + local.is_lwa,
         );
 
         // Check that reduce(addr_word) == addr_aligned + addr_offset.
@@ -123,10 +132,10 @@ where
         self.eval_memory_store::<AB>(builder, local);
 
         // Branch instructions.
-        self.branch_ops_eval::<AB>(builder, is_branch_instruction.clone(), local, next);
+        self.branch_ops_eval::<AB>(builder, is_branch_instruction.clone(), local);
 
         // Jump instructions.
-        self.jump_ops_eval::<AB>(builder, local, next);
+        self.jump_ops_eval::<AB>(builder, local);
 
         // AUIPC instruction.
         self.auipc_eval(builder, local);
@@ -141,15 +150,26 @@ where
         );
 
         // ECALL instructions.
-        // TODO:  Need to handle HALT ecall
-        // For all non branch or jump instructions, verify that next.pc == pc + 4
-        // builder
-        //     .when_not(is_branch_instruction + local.selectors.is_jal + local.selectors.is_jalr)
-        //     .assert_eq(local.pc + AB::Expr::from_canonical_u8(4), next.pc);
+        self.ecall_eval(builder, local);
+
+        // For all instructions but branches, jumps and ECALLs, verify that next_pc == pc + 4. The
+        // other instructions constrain next_pc themselves.
+        builder
+            .when(local.is_real)
+            .when_not(
+                is_branch_instruction
+                    + local.selectors.is_jal
+                    + local.selectors.is_jalr
+                    + local.selectors.is_ecall,
+            )
+            .assert_eq(local.next_pc, local.pc + AB::Expr::from_canonical_u8(4));
 
         // Range checks.
         builder.assert_bool(local.is_real);
 
+        // Boundary constraints.
+        self.boundary_eval::<AB>(builder, local, next);
+
         // Dummy constraint of degree 3.
         builder.assert_eq(
             local.pc * local.pc * local.pc,
@@ -159,6 +179,118 @@ where
 }
 
 impl CpuChip {
+    /// Constraints tying the first and last real rows to the public values of the shard.
+    pub(crate) fn boundary_eval<AB: SP1AirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &CpuCols<AB::Var>,
+        next: &CpuCols<AB::Var>,
+    ) {
+        // The real rows form a prefix of the trace, and `is_last_real` marks the last of them.
+        builder.assert_bool(local.is_last_real);
+        builder
+            .when_transition()
+            .when(next.is_real)
+            .assert_one(local.is_real);
+        builder
+            .when_transition()
+            .assert_eq(local.is_last_real, local.is_real - next.is_real);
+        builder
+            .when_last_row()
+            .assert_eq(local.is_last_real, local.is_real);
+
+        // Nothing is executed after a halt: its row is the last real row of the shard.
+        builder.when(local.is_halt).assert_one(local.is_last_real);
+
+        // Each real row is followed by the instruction at its next program counter.
+        builder
+            .when_transition()
+            .when(next.is_real)
+            .assert_eq(local.next_pc, next.pc);
+
+        // The instruction count accumulates the real rows.
+        builder
+            .when_first_row()
+            .assert_eq(local.instruction_count, local.is_real);
+        builder.when_transition().assert_eq(
+            next.instruction_count,
+            local.instruction_count + next.is_real,
+        );
+
+        // The input read count accumulates the bytes read by the `LWA` rows, whose count is the
+        // low byte of the value of the memory columns.
+        let local_memory: MemoryColumns<AB::Var> =
+            *local.opcode_specific_columns[..NUM_MEMORY_COLUMNS].borrow();
+        let next_memory: MemoryColumns<AB::Var> =
+            *next.opcode_specific_columns[..NUM_MEMORY_COLUMNS].borrow();
+        builder.when_first_row().assert_eq(
+            local.input_read,
+            local.is_lwa * local_memory.memory_access.value()[0],
+        );
+        builder.when_transition().assert_eq(
+            next.input_read,
+            local.input_read + next.is_lwa * next_memory.memory_access.value()[0],
+        );
+
+        // A chip proven on its own has no shard to tie its boundary rows to.
+        if AB::STANDALONE {
+            return;
+        }
+        assert_eq!(
+            builder.public_values().len(),
+            NUM_PUBLIC_VALUES,
+            "the builder of a shard must supply all the public values"
+        );
+        let public_values: PublicValues<AB::PublicVar> = *builder.public_values().borrow();
+
+        // The first row starts the shard at the public shard, clock, and program counter.
+        builder.when_first_row().assert_one(local.is_real);
+        builder
+            .when_first_row()
+            .assert_eq(local.shard, public_values.shard);
+        builder
+            .when_first_row()
+            .assert_eq(local.clk, public_values.start_clk);
+        builder
+            .when_first_row()
+            .assert_eq(local.pc, public_values.start_pc);
+
+        // The last real row ends at the public clock, and the next shard resumes at the public
+        // program counter.
+        builder
+            .when(local.is_last_real)
+            .assert_eq(local.clk, public_values.end_clk);
+        builder
+            .when(local.is_last_real)
+            .assert_eq(local.next_pc, public_values.end_pc);
+
+        // The last real row counts all the instructions executed in the shard, and all the input
+        // bytes they read.
+        builder
+            .when(local.is_last_real)
+            .assert_eq(local.instruction_count, public_values.instruction_count);
+        builder
+            .when(local.is_last_real)
+            .assert_eq(local.input_read, public_values.input_read);
+
+        // A cycle count returns the instructions executed before its row, over all shards.
+        let prior_instruction_count: AB::Expr = public_values.prior_instruction_count.into();
+        builder.when(local.is_cycle_count).assert_eq(
+            local.op_a_val().reduce::<AB>(),
+            local.instruction_count + prior_instruction_count - AB::Expr::one(),
+        );
+
+        // The shard halts exactly if its last real row is a halt ecall, which is then its only halt
+        // row, and the exit code of the halt is the public one.
+        builder
+            .when(local.is_last_real)
+            .assert_eq(local.is_halt, public_values.halted);
+        builder.when(local.is_halt).assert_word_eq(
+            local.op_a_val().map(|x| x.into()),
+            public_values.exit_code.map(|x| x.into()),
+        );
+    }
+
     /// Whether the instruction is a memory instruction.
     pub(crate) fn is_alu_instruction<AB: SP1AirBuilder>(
         &self,
@@ -172,7 +304,6 @@ impl CpuChip {
         &self,
         builder: &mut AB,
         local: &CpuCols<AB::Var>,
-        next: &CpuCols<AB::Var>,
     ) {
         // Get the jump specific columns
         let jump_columns: JumpCols<AB::Var> =
@@ -191,11 +322,10 @@ impl CpuChip {
             .when(local.selectors.is_jal)
             .assert_eq(jump_columns.pc.reduce::<AB>(), local.pc);
 
-        // Verify that the word form of next.pc is correct for both jump instructions.
+        // Verify that the word form of next_pc is correct for both jump instructions.
         builder
-            .when_transition()
             .when(local.selectors.is_jal + local.selectors.is_jalr)
-            .assert_eq(jump_columns.next_pc.reduce::<AB>(), next.pc);
+            .assert_eq(jump_columns.next_pc.reduce::<AB>(), local.next_pc);
 
         // Verify that the new pc is calculated correctly for JAL instructions.
         builder.send_alu(
@@ -236,6 +366,146 @@ impl CpuChip {
             local.selectors.is_auipc,
         );
     }
+
+    /// Constraints related to the ECALL opcode.
+    pub(crate) fn ecall_eval<AB: SP1AirBuilder>(&self, builder: &mut AB, local: &CpuCols<AB::Var>) {
+        // Verify whether the syscall id in op_b is `CYCLE_COUNT`.
+        IsZeroOperation::<AB::F>::eval(
+            builder,
+            local.op_b_val().reduce::<AB>()
+                - AB::F::from_canonical_u32(SyscallCode::CYCLE_COUNT as u32),
+            local.ecall_is_cycle_count,
+            local.selectors.is_ecall.into(),
+        );
+        builder.assert_eq(
+            local.is_cycle_count,
+            local.selectors.is_ecall * local.ecall_is_cycle_count.result,
+        );
+
+        // Verify whether the syscall id in op_b is `LWA`.
+        IsZeroOperation::<AB::F>::eval(
+            builder,
+            local.op_b_val().reduce::<AB>() - AB::F::from_canonical_u32(SyscallCode::LWA as u32),
+            local.ecall_is_lwa,
+            local.selectors.is_ecall.into(),
+        );
+        builder.assert_eq(
+            local.is_lwa,
+            local.selectors.is_ecall * local.ecall_is_lwa.result,
+        );
+
+        // Verify whether the syscall id in op_b is `HALT`.
+        IsZeroOperation::<AB::F>::eval(
+            builder,
+            local.op_b_val().reduce::<AB>() - AB::F::from_canonical_u32(SyscallCode::HALT as u32),
+            local.ecall_is_halt,
+            local.selectors.is_ecall.into(),
+        );
+        builder.assert_eq(
+            local.is_halt,
+            local.selectors.is_ecall * local.ecall_is_halt.result,
+        );
+
+        // Verify whether the syscall id in op_b is the code of a precompile, through the running
+        // products of its differences with the codes of the precompiles.
+        let syscall_id = local.op_b_val().reduce::<AB>();
+        let mut product: AB::Expr = AB::F::one().into();
+        for (code, running_product) in SyscallCode::PRECOMPILES
+            .iter()
+            .zip(local.ecall_precompile_products.iter())
+        {
+            builder.when(local.selectors.is_ecall).assert_eq(
+                *running_product,
+                product * (syscall_id.clone() - AB::F::from_canonical_u32(*code as u32)),
+            );
+            product = (*running_product).into();
+        }
+        IsZeroOperation::<AB::F>::eval(
+            builder,
+            product,
+            local.ecall_is_precompile,
+            local.selectors.is_ecall.into(),
+        );
+        builder.assert_eq(
+            local.is_precompile,
+            local.selectors.is_ecall * local.ecall_is_precompile.result,
+        );
+
+        // Send the syscall of a precompile, with its pointer in `a0`, to the chip which executes
+        // it. The row has the clock of the end of the precompile. The chip also tells whether the
+        // precompile suspends, e.g. after a chunk of a longer memcpy.
+        builder.assert_bool(local.ecall_suspends);
+        builder
+            .when(local.ecall_suspends)
+            .assert_one(local.is_precompile);
+        builder.send_syscall(
+            local.shard,
+            local.clk,
+            syscall_id,
+            local.op_a_access.prev_value().reduce::<AB>(),
+            local.ecall_suspends,
+            local.is_precompile,
+        );
+
+        // An ECALL moves on to the next instruction, unless its precompile suspends, in which case
+        // it executes again. A halt jumps to zero.
+        builder
+            .when(local.selectors.is_ecall)
+            .when_not(local.is_halt)
+            .assert_eq(
+                local.next_pc,
+                local.pc + AB::Expr::from_canonical_u8(4)
+                    - local.ecall_suspends * AB::F::from_canonical_u8(4),
+            );
+        builder.when(local.is_halt).assert_zero(local.next_pc);
+
+        // The cycle count returned in `a0` is a word of bytes whose most significant byte is below
+        // the one of the BabyBear modulus, so that it reduces to a unique field element. The
+        // boundary constraints tie that element to the instruction count.
+        let cycle_count = local.op_a_val();
+        builder.slice_range_check_u8(&cycle_count.0, local.is_cycle_count);
+        builder.send_byte(
+            AB::F::from_canonical_u32(ByteOpcode::LTU as u32),
+            AB::F::one(),
+            cycle_count[WORD_SIZE - 1],
+            AB::F::from_canonical_u32(CYCLE_COUNT_MSB_BOUND),
+            local.is_cycle_count,
+        );
+
+        // The high word of the cycle count is written to `a1` through the memory columns, and is
+        // zero.
+        let memory_columns: MemoryColumns<AB::Var> =
+            *local.opcode_specific_columns[..NUM_MEMORY_COLUMNS].borrow();
+        builder.when(local.is_cycle_count).assert_eq(
+            memory_columns.addr_aligned,
+            AB::F::from_canonical_u32(Register::X11 as u32),
+        );
+        for limb in memory_columns.memory_access.value().0 {
+            builder.when(local.is_cycle_count).assert_zero(limb);
+        }
+
+        // The number of bytes an input read takes is read from `a1` through the memory columns,
+        // and is at most a word.
+        builder.when(local.is_lwa).assert_eq(
+            memory_columns.addr_aligned,
+            AB::F::from_canonical_u32(Register::X11 as u32),
+        );
+        builder.when(local.is_lwa).assert_word_eq(
+            *memory_columns.memory_access.value(),
+            *memory_columns.memory_access.prev_value(),
+        );
+        let num_bytes = memory_columns.memory_access.value();
+        for limb in num_bytes.0[1..].iter() {
+            builder.when(local.is_lwa).assert_zero(*limb);
+        }
+        builder.send_byte(
+            AB::F::from_canonical_u32(ByteOpcode::LTU as u32),
+            AB::F::one(),
+            num_bytes[0],
+            AB::F::from_canonical_usize(WORD_SIZE + 1),
+            local.is_lwa,
+        );
+    }
 }
 
 impl<F> BaseAir<F> for CpuChip {
@@ -20,6 +20,8 @@ use std::mem::{size_of, transmute};
 use crate::{
     air::Word,
     memory::{MemoryCols, MemoryReadCols, MemoryReadWriteCols},
+    operations::IsZeroOperation,
+    runtime::NUM_PRECOMPILES,
 };
 
 pub const NUM_CPU_COLS: usize = size_of::<CpuCols<u8>>();
@@ -41,6 +43,9 @@ pub struct CpuCols<T> {
     /// The program counter value.
     pub pc: T,
 
+    /// The program counter of the next instruction.
+    pub next_pc: T,
+
     /// Columns related to the instruction.
     pub instruction: InstructionCols<T>,
 
@@ -58,6 +63,15 @@ pub struct CpuCols<T> {
     /// Selector to label whether this row is a non padded row.
     pub is_real: T,
 
+    /// Selector to label the last non padded row.
+    pub is_last_real: T,
+
+    /// The number of non padded rows up to and including this one.
+    pub instruction_count: T,
+
+    /// The number of input bytes read by the `LWA` rows up to and including this one.
+    pub input_read: T,
+
     /// The branching column is equal to:
     ///
     /// > is_beq & a_eq_b ||
@@ -66,22 +80,63 @@ pub struct CpuCols<T> {
     /// > (is_bge | is_bgeu) & (a_eq_b | a_gt_b)
     pub branching: T,
 
-    /// The not branching column is equal to:
-    ///
-    /// > is_beq & !a_eq_b ||
-    /// > is_bne & !(a_lt_b | a_gt_b) ||
-    /// > (is_blt | is_bltu) & !a_lt_b ||
-    /// > (is_bge | is_bgeu) & !(a_eq_b | a_gt_b)
-    pub not_branching: T,
-
     /// The memory value is negative column is equal to:
     ///
-    /// > (is_lbu | is_lhu) & (most_sig_byte_decomp[7] == 1)
+    /// > (is_lb | is_lh) & (most_sig_byte_decomp[7] == 1)
     pub mem_value_is_neg: T,
 
     /// The unsigned memory value is the value after the offset logic is applied. Used for the load
     /// memory opcodes (i.e. LB, LH, LW, LBU, and LHU).
     pub unsigned_mem_val: Word<T>,
+
+    /// Whether the syscall id of an ECALL, in op_b, is `CYCLE_COUNT`.
+    pub ecall_is_cycle_count: IsZeroOperation<T>,
+
+    /// The cycle count column is equal to:
+    ///
+    /// > is_ecall & (op_b == CYCLE_COUNT)
+    ///
+    /// Its row writes the shard to `a1` through the memory columns.
+    pub is_cycle_count: T,
+
+    /// Whether the syscall id of an ECALL, in op_b, is `LWA`.
+    pub ecall_is_lwa: IsZeroOperation<T>,
+
+    /// The LWA column is equal to:
+    ///
+    /// > is_ecall & (op_b == LWA)
+    ///
+    /// Its row reads the number of bytes it takes from the input stream in `a1` through the memory
+    /// columns.
+    pub is_lwa: T,
+
+    /// Whether the syscall id of an ECALL, in op_b, is `HALT`.
+    pub ecall_is_halt: IsZeroOperation<T>,
+
+    /// The halt column is equal to:
+    ///
+    /// > is_ecall & (op_b == HALT)
+    ///
+    /// Its row is the last real row of the shard, and the shard halts with the exit code in op_a.
+    pub is_halt: T,
+
+    /// The running products of `op_b - code` over the codes of the precompiles, of an ECALL.
+    pub ecall_precompile_products: [T; NUM_PRECOMPILES],
+
+    /// Whether the last of the running products is zero, that is whether the syscall id of an
+    /// ECALL, in op_b, is the code of a precompile.
+    pub ecall_is_precompile: IsZeroOperation<T>,
+
+    /// The precompile column is equal to:
+    ///
+    /// > is_ecall & (op_b in PRECOMPILES)
+    ///
+    /// Its row sends the syscall to the chip of its precompile.
+    pub is_precompile: T,
+
+    /// Whether the precompile of an ECALL suspends, so that the same ECALL executes next, as the
+    /// chip of the precompile confirms through the syscall.
+    pub ecall_suspends: T,
 }
 
 impl<T: Clone> CpuCols<T> {
@@ -1,9 +1,11 @@
-use crate::runtime::Instruction;
+use serde::{Deserialize, Serialize};
+
+use crate::runtime::{Instruction, Opcode, Register};
 
 use super::memory::MemoryRecordEnum;
 
 /// A standard format for describing CPU operations that need to be proven.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub struct CpuEvent {
     /// The current shard.
     pub shard: u32,
@@ -11,9 +13,15 @@ pub struct CpuEvent {
     /// The current clock.
     pub clk: u32,
 
+    /// The number of instructions executed before this one, over all shards.
+    pub global_clk: u32,
+
     /// The current program counter.
     pub pc: u32,
 
+    /// The program counter of the next instruction.
+    pub next_pc: u32,
+
     /// The current instruction.
     pub instruction: Instruction,
 
@@ -41,3 +49,24 @@ pub struct CpuEvent {
     /// The memory access record for the memory value.
     pub memory_record: Option<MemoryRecordEnum>,
 }
+
+impl CpuEvent {
+    /// The memory accesses of the event with their addresses: the registers of the operands and,
+    /// for loads and stores, the word containing the accessed address. An ECALL which returns a
+    /// second word writes it to `a1` in place of the memory access.
+    pub fn memory_accesses(&self) -> impl Iterator<Item = (u32, MemoryRecordEnum)> + '_ {
+        let addr = if self.instruction.opcode == Opcode::ECALL {
+            Register::X11 as u32
+        } else {
+            self.b.wrapping_add(self.c)
+        };
+        [
+            (self.instruction.op_a, self.a_record),
+            (self.instruction.op_b, self.b_record),
+            (self.instruction.op_c, self.c_record),
+            (addr - addr % 4, self.memory_record),
+        ]
+        .into_iter()
+        .filter_map(|(addr, record)| record.map(|record| (addr, record)))
+    }
+}
@@ -1,17 +1,19 @@
-#[derive(Debug, Copy, Clone)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub enum MemoryRecordEnum {
     Read(MemoryReadRecord),
     Write(MemoryWriteRecord),
 }
 
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
 pub struct MemoryRecord {
     pub value: u32,
     pub shard: u32,
     pub timestamp: u32,
 }
 
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
 #[non_exhaustive]
 pub struct MemoryReadRecord {
     pub value: u32,
@@ -21,7 +23,7 @@ pub struct MemoryReadRecord {
     pub prev_timestamp: u32,
 }
 
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
 #[non_exhaustive]
 pub struct MemoryWriteRecord {
     pub value: u32,
@@ -39,6 +41,66 @@ impl MemoryRecordEnum {
             MemoryRecordEnum::Write(record) => record.value,
         }
     }
+
+    /// The value, shard and timestamp left by the access.
+    pub fn current_record(&self) -> MemoryRecord {
+        match self {
+            MemoryRecordEnum::Read(record) => MemoryRecord {
+                value: record.value,
+                shard: record.shard,
+                timestamp: record.timestamp,
+            },
+            MemoryRecordEnum::Write(record) => MemoryRecord {
+                value: record.value,
+                shard: record.shard,
+                timestamp: record.timestamp,
+            },
+        }
+    }
+
+    /// The value, shard and timestamp left by the previous access to the same address.
+    pub fn previous_record(&self) -> MemoryRecord {
+        match self {
+            MemoryRecordEnum::Read(record) => MemoryRecord {
+                value: record.value,
+                shard: record.prev_shard,
+                timestamp: record.prev_timestamp,
+            },
+            MemoryRecordEnum::Write(record) => MemoryRecord {
+                value: record.prev_value,
+                shard: record.prev_shard,
+                timestamp: record.prev_timestamp,
+            },
+        }
+    }
+
+    /// Check that the access happens at `shard` and `timestamp`, after the previous access to the
+    /// same address.
+    pub fn check_access(&self, shard: u32, timestamp: u32) -> Result<(), String> {
+        let (current, prev) = match self {
+            MemoryRecordEnum::Read(record) => (
+                (record.shard, record.timestamp),
+                (record.prev_shard, record.prev_timestamp),
+            ),
+            MemoryRecordEnum::Write(record) => (
+                (record.shard, record.timestamp),
+                (record.prev_shard, record.prev_timestamp),
+            ),
+        };
+        if current != (shard, timestamp) {
+            return Err(format!(
+                "accessed at shard {} clk {} instead of shard {} clk {}",
+                current.0, current.1, shard, timestamp
+            ));
+        }
+        if prev >= current {
+            return Err(format!(
+                "previous access at shard {} clk {} is not before shard {} clk {}",
+                prev.0, prev.1, current.0, current.1
+            ));
+        }
+        Ok(())
+    }
 }
 
 impl From<MemoryReadRecord> for MemoryRecordEnum {
@@ -1,9 +1,10 @@
+use super::air::CYCLE_COUNT_MSB_BOUND;
 use super::columns::{
     AuipcCols, BranchCols, JumpCols, CPU_COL_MAP, NUM_AUIPC_COLS, NUM_BRANCH_COLS, NUM_CPU_COLS,
     NUM_JUMP_COLS, NUM_MEMORY_COLUMNS,
 };
 use super::{CpuChip, CpuEvent};
-use crate::air::MachineAir;
+use crate::air::{EventKind, MachineAir};
 use crate::alu::{self, AluEvent};
 use crate::bytes::{ByteLookupEvent, ByteOpcode};
 use crate::cpu::columns::{CpuCols, MemoryColumns};
@@ -11,14 +12,13 @@ use crate::cpu::memory::MemoryRecordEnum;
 use crate::disassembler::WORD_SIZE;
 use crate::field::event::FieldEvent;
 use crate::memory::MemoryCols;
-use crate::runtime::{ExecutionRecord, Opcode};
+use crate::runtime::{ExecutionRecord, Opcode, Register, SyscallCode};
+use crate::utils::zero_trace;
 use hashbrown::HashMap;
 use p3_field::PrimeField;
 use p3_matrix::dense::RowMajorMatrix;
-use p3_maybe_rayon::prelude::IntoParallelRefIterator;
-use p3_maybe_rayon::prelude::ParallelIterator;
-use p3_maybe_rayon::prelude::ParallelSlice;
-use std::borrow::BorrowMut;
+use p3_maybe_rayon::prelude::*;
+use std::borrow::{Borrow, BorrowMut};
 use tracing::instrument;
 
 impl<F: PrimeField> MachineAir<F> for CpuChip {
@@ -26,27 +26,70 @@ impl<F: PrimeField> MachineAir<F> for CpuChip {
         "CPU".to_string()
     }
 
-    #[instrument(name = "generate CPU trace", skip_all)]
+    fn produces(&self) -> &[EventKind] {
+        &[
+            EventKind::Add,
+            EventKind::Sub,
+            EventKind::Bitwise,
+            EventKind::Mul,
+            EventKind::DivRem,
+            EventKind::Lt,
+            EventKind::ShiftLeft,
+            EventKind::ShiftRight,
+            EventKind::ByteLookup,
+            EventKind::Field,
+        ]
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::Cpu]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.cpu_events.len()
+    }
+
+    fn included(&self, _: &ExecutionRecord) -> bool {
+        true
+    }
+
     fn generate_trace(
         &self,
         input: &ExecutionRecord,
         output: &mut ExecutionRecord,
     ) -> RowMajorMatrix<F> {
+        let mut trace = RowMajorMatrix::new(Vec::new(), NUM_CPU_COLS);
+        self.generate_trace_into(input, output, &mut trace);
+        trace
+    }
+
+    #[instrument(name = "generate CPU trace", skip_all)]
+    fn generate_trace_into(
+        &self,
+        input: &ExecutionRecord,
+        output: &mut ExecutionRecord,
+        trace: &mut RowMajorMatrix<F>,
+    ) {
         let mut new_alu_events = HashMap::new();
         let mut new_blu_events = Vec::new();
         let mut new_field_events: Vec<FieldEvent> = Vec::new();
 
-        // Generate the trace rows for each event.
-        let rows_with_events = input
-            .cpu_events
-            .par_iter()
-            .map(|op: &CpuEvent| self.event_to_row::<F>(*op))
+        let n_real_rows = input.cpu_events.len();
+        zero_trace(trace, NUM_CPU_COLS, n_real_rows.next_power_of_two());
+
+        // Write the row of each event in place.
+        let events = trace.values[..n_real_rows * NUM_CPU_COLS]
+            .par_chunks_mut(NUM_CPU_COLS)
+            .zip(input.cpu_events.par_iter())
+            .map(|(row, op): (&mut [F], &CpuEvent)| {
+                let (values, alu_events, blu_events, field_events) = self.event_to_row::<F>(*op);
+                row.copy_from_slice(&values);
+                (alu_events, blu_events, field_events)
+            })
             .collect::<Vec<_>>();
 
-        let mut rows = Vec::<F>::new();
-        rows_with_events.into_iter().for_each(|row_with_events| {
-            let (row, alu_events, blu_events, field_events) = row_with_events;
-            rows.extend(row);
+        events.into_iter().for_each(|events| {
+            let (alu_events, blu_events, field_events) = events;
             for (key, value) in alu_events {
                 new_alu_events
                     .entry(key)
@@ -64,13 +107,7 @@ impl<F: PrimeField> MachineAir<F> for CpuChip {
         output.add_byte_lookup_events(new_blu_events);
         output.add_field_events(&new_field_events);
 
-        // Convert the trace to a row major matrix.
-        let mut trace = RowMajorMatrix::new(rows, NUM_CPU_COLS);
-
-        // Pad the trace to a power of two.
-        Self::pad_to_power_of_two::<F>(&mut trace.values);
-
-        trace
+        Self::pad_rows::<F>(&mut trace.values, n_real_rows);
     }
 
     #[instrument(name = "generate CPU dependencies", skip_all)]
@@ -138,6 +175,7 @@ impl CpuChip {
         cols.shard = F::from_canonical_u32(event.shard);
         cols.clk = F::from_canonical_u32(event.clk);
         cols.pc = F::from_canonical_u32(event.pc);
+        cols.next_pc = F::from_canonical_u32(event.next_pc);
         cols.instruction.populate(event.instruction);
         cols.selectors.populate(event.instruction);
         *cols.op_a_access.value_mut() = event.a.into();
@@ -170,6 +208,7 @@ impl CpuChip {
         self.populate_branch(cols, event, &mut new_alu_events);
         self.populate_jump(cols, event, &mut new_alu_events);
         self.populate_auipc(cols, event, &mut new_alu_events);
+        self.populate_ecall(cols, event, &mut new_blu_events);
 
         // Assert that the instruction is not a no-op.
         cols.is_real = F::one();
@@ -254,15 +293,11 @@ impl CpuChip {
 
             // For the signed load instructions, we need to check if the loaded value is negative.
             if matches!(event.instruction.opcode, Opcode::LB | Opcode::LH) {
-                let most_sig_mem_value_byte: u8;
-                let sign_value: u32;
-                if matches!(event.instruction.opcode, Opcode::LB) {
-                    sign_value = 256;
-                    most_sig_mem_value_byte = cols.unsigned_mem_val.to_u32().to_le_bytes()[0];
+                let most_sig_mem_value_byte = if matches!(event.instruction.opcode, Opcode::LB) {
+                    cols.unsigned_mem_val.to_u32().to_le_bytes()[0]
                 } else {
-                    // LHU case
-                    sign_value = 65536;
-                    most_sig_mem_value_byte = cols.unsigned_mem_val.to_u32().to_le_bytes()[1];
+                    // LH case
+                    cols.unsigned_mem_val.to_u32().to_le_bytes()[1]
                 };
 
                 for i in (0..8).rev() {
@@ -271,18 +306,6 @@ impl CpuChip {
                 }
                 if memory_columns.most_sig_byte_decomp[7] == F::one() {
                     cols.mem_value_is_neg = F::one();
-                    let sub_event = AluEvent {
-                        clk: event.clk,
-                        opcode: Opcode::SUB,
-                        a: event.a,
-                        b: cols.unsigned_mem_val.to_u32(),
-                        c: sign_value,
-                    };
-
-                    new_alu_events
-                        .entry(Opcode::SUB)
-                        .and_modify(|op_new_events| op_new_events.push(sub_event))
-                        .or_insert(vec![sub_event]);
                 }
             }
         }
@@ -390,8 +413,6 @@ impl CpuChip {
                     .entry(Opcode::ADD)
                     .and_modify(|op_new_events| op_new_events.push(add_event))
                     .or_insert(vec![add_event]);
-            } else {
-                cols.not_branching = F::one();
             }
         }
     }
@@ -476,15 +497,108 @@ impl CpuChip {
         }
     }
 
-    fn pad_to_power_of_two<F: PrimeField>(values: &mut Vec<F>) {
-        let len: usize = values.len();
-        let n_real_rows = values.len() / NUM_CPU_COLS;
-        let last_row = &values[len - NUM_CPU_COLS..];
+    /// Populate columns related to ECALL.
+    fn populate_ecall<F: PrimeField>(
+        &self,
+        cols: &mut CpuCols<F>,
+        event: CpuEvent,
+        new_blu_events: &mut Vec<ByteLookupEvent>,
+    ) {
+        if !matches!(event.instruction.opcode, Opcode::ECALL) {
+            return;
+        }
+
+        let syscall_id = F::from_canonical_u32(event.b);
+        let cycle_count_id = F::from_canonical_u32(SyscallCode::CYCLE_COUNT as u32);
+        if cols
+            .ecall_is_cycle_count
+            .populate_from_field_element(syscall_id - cycle_count_id)
+            == 1
+        {
+            cols.is_cycle_count = F::one();
+            let memory_columns: &mut MemoryColumns<F> =
+                cols.opcode_specific_columns[..NUM_MEMORY_COLUMNS].borrow_mut();
+            memory_columns.addr_aligned = F::from_canonical_u32(Register::X11 as u32);
+
+            // Range check the bytes of the cycle count and bound its most significant byte.
+            let bytes = event.a.to_le_bytes();
+            for byte_pair in bytes.chunks_exact(2) {
+                new_blu_events.push(ByteLookupEvent {
+                    opcode: ByteOpcode::U8Range,
+                    a1: 0,
+                    a2: 0,
+                    b: byte_pair[0] as u32,
+                    c: byte_pair[1] as u32,
+                });
+            }
+            new_blu_events.push(ByteLookupEvent {
+                opcode: ByteOpcode::LTU,
+                a1: 1,
+                a2: 0,
+                b: bytes[WORD_SIZE - 1] as u32,
+                c: CYCLE_COUNT_MSB_BOUND,
+            });
+        }
+
+        let lwa_id = F::from_canonical_u32(SyscallCode::LWA as u32);
+        if cols
+            .ecall_is_lwa
+            .populate_from_field_element(syscall_id - lwa_id)
+            == 1
+        {
+            cols.is_lwa = F::one();
+            let memory_columns: &mut MemoryColumns<F> =
+                cols.opcode_specific_columns[..NUM_MEMORY_COLUMNS].borrow_mut();
+            memory_columns.addr_aligned = F::from_canonical_u32(Register::X11 as u32);
+
+            // Bound the number of bytes read by a word.
+            new_blu_events.push(ByteLookupEvent {
+                opcode: ByteOpcode::LTU,
+                a1: 1,
+                a2: 0,
+                b: event.memory.expect("an input read has a memory access"),
+                c: WORD_SIZE as u32 + 1,
+            });
+        }
+
+        let halt_id = F::from_canonical_u32(SyscallCode::HALT as u32);
+        if cols
+            .ecall_is_halt
+            .populate_from_field_element(syscall_id - halt_id)
+            == 1
+        {
+            cols.is_halt = F::one();
+        }
+
+        let mut product = F::one();
+        for (code, running_product) in SyscallCode::PRECOMPILES
+            .iter()
+            .zip(cols.ecall_precompile_products.iter_mut())
+        {
+            product *= syscall_id - F::from_canonical_u32(*code as u32);
+            *running_product = product;
+        }
+        if cols
+            .ecall_is_precompile
+            .populate_from_field_element(product)
+            == 1
+        {
+            cols.is_precompile = F::one();
+            cols.ecall_suspends = F::from_bool(event.next_pc == event.pc);
+        }
+    }
+
+    /// Fills the padding rows after the `n_real_rows` real rows of the trace in place.
+    fn pad_rows<F: PrimeField>(values: &mut [F], n_real_rows: usize) {
+        let len = n_real_rows * NUM_CPU_COLS;
+
+        // Mark the last real row, which the boundary constraints refer to.
+        values[len - NUM_CPU_COLS + CPU_COL_MAP.is_last_real] = F::one();
+
+        let last_row = &values[len - NUM_CPU_COLS..len];
         let pc = last_row[CPU_COL_MAP.pc];
         let clk = last_row[CPU_COL_MAP.clk];
 
-        values.resize(n_real_rows.next_power_of_two() * NUM_CPU_COLS, F::zero());
-
         // Interpret values as a slice of arrays of length `NUM_CPU_COLS`
         let rows = unsafe {
             core::slice::from_raw_parts_mut(
@@ -493,11 +607,27 @@ impl CpuChip {
             )
         };
 
+        // Number the real rows, and repeat the count of the last one in the padding rows.
+        for (i, row) in rows.iter_mut().enumerate() {
+            row[CPU_COL_MAP.instruction_count] = F::from_canonical_usize((i + 1).min(n_real_rows));
+        }
+
+        // Count the input bytes read up to each row. The padding rows read none.
+        let mut input_read = F::zero();
+        for row in rows.iter_mut() {
+            let cols: &mut CpuCols<F> = row.as_mut_slice().borrow_mut();
+            let memory_columns: &MemoryColumns<F> =
+                cols.opcode_specific_columns[..NUM_MEMORY_COLUMNS].borrow();
+            input_read += cols.is_lwa * memory_columns.memory_access.value()[0];
+            cols.input_read = input_read;
+        }
+
         rows[n_real_rows..]
             .iter_mut()
             .enumerate()
             .for_each(|(n, padded_row)| {
                 padded_row[CPU_COL_MAP.pc] = pc;
+                padded_row[CPU_COL_MAP.next_pc] = pc;
                 padded_row[CPU_COL_MAP.clk] = clk + F::from_canonical_u32((n as u32 + 1) * 4);
                 padded_row[CPU_COL_MAP.selectors.is_noop] = F::one();
                 padded_row[CPU_COL_MAP.selectors.imm_b] = F::one();
@@ -513,11 +643,16 @@ mod tests {
 
     use super::*;
 
+    use crate::air::{PublicValues, Word};
+    use crate::runtime::{Program, SyscallCode};
+    use crate::stark::{check_constraints, Chip, RiscvAir};
     use crate::utils::{uni_stark_prove as prove, uni_stark_verify as verify};
     use crate::{
         runtime::{tests::simple_program, ExecutionRecord, Instruction, Runtime},
-        utils::{BabyBearPoseidon2, StarkUtils},
+        utils::{BabyBearBlake3, BabyBearPoseidon2, StarkUtils},
     };
+    use p3_field::extension::BinomialExtensionField;
+    use p3_field::AbstractField;
 
     #[test]
     fn generate_trace() {
@@ -525,7 +660,9 @@ mod tests {
         shard.cpu_events = vec![CpuEvent {
             shard: 1,
             clk: 6,
+            global_clk: 0,
             pc: 1,
+            next_pc: 5,
             instruction: Instruction {
                 opcode: Opcode::ADD,
                 op_a: 0,
@@ -581,4 +718,131 @@ mod tests {
         let mut challenger = config.challenger();
         verify(&config, &chip, &mut challenger, &proof).unwrap();
     }
+    #[test]
+    fn boundary_constraints() {
+        type EF = BinomialExtensionField<BabyBear, 4>;
+
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, SyscallCode::HALT as u32, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 7, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+        ];
+        // Start away from zero, so that halting leaves the program.
+        let mut runtime = Runtime::new(Program::new(instructions, 4, 4));
+        runtime.run();
+        let public_values = PublicValues::from_record(&runtime.record);
+        assert_eq!(public_values.halted, 1);
+        assert_eq!(public_values.exit_code, Word([7, 0, 0, 0]));
+
+        let chip = Chip::new(RiscvAir::<BabyBear>::Cpu(CpuChip::default()));
+        let trace = chip.generate_trace(&runtime.record, &mut ExecutionRecord::default());
+        let challenges = [EF::from_canonical_u32(3), EF::from_canonical_u32(7)];
+        let perm = chip.generate_permutation_trace(None, &trace, &challenges);
+        let check = |public_values: PublicValues<u32>| {
+            check_constraints::<BabyBearBlake3>(
+                &chip,
+                None,
+                &trace,
+                &perm,
+                &challenges,
+                &public_values.to_field_elements(),
+            )
+        };
+        check(public_values).unwrap();
+
+        // Claim a different entrypoint.
+        let mut tampered = public_values;
+        tampered.start_pc += 4;
+        assert_eq!(check(tampered).unwrap_err().row, 0);
+
+        // Claim a different next program counter.
+        let mut tampered = public_values;
+        tampered.end_pc += 4;
+        assert_eq!(check(tampered).unwrap_err().row, 2);
+
+        // Claim a different cycle count.
+        let mut tampered = public_values;
+        tampered.end_clk += 4;
+        assert_eq!(check(tampered).unwrap_err().row, 2);
+
+        // Claim more executed instructions.
+        let mut tampered = public_values;
+        tampered.instruction_count += 1;
+        assert_eq!(check(tampered).unwrap_err().row, 2);
+
+        // Claim a different exit code.
+        let mut tampered = public_values;
+        tampered.exit_code = Word([8, 0, 0, 0]);
+        assert_eq!(check(tampered).unwrap_err().row, 2);
+
+        // Claim the shard does not halt.
+        let mut tampered = public_values;
+        tampered.halted = 0;
+        tampered.exit_code = Word([0, 0, 0, 0]);
+        assert_eq!(check(tampered).unwrap_err().row, 2);
+    }
+
+    #[test]
+    fn halt_is_last() {
+        type EF = BinomialExtensionField<BabyBear, 4>;
+
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, SyscallCode::HALT as u32, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+        ];
+        let mut runtime = Runtime::new(Program::new(instructions, 4, 4));
+        runtime.run();
+
+        let chip = Chip::new(RiscvAir::<BabyBear>::Cpu(CpuChip::default()));
+        let check = |record: &ExecutionRecord| {
+            let trace = chip.generate_trace(record, &mut ExecutionRecord::default());
+            let challenges = [EF::from_canonical_u32(3), EF::from_canonical_u32(7)];
+            let perm = chip.generate_permutation_trace(None, &trace, &challenges);
+            let public_values = PublicValues::from_record(record);
+            check_constraints::<BabyBearBlake3>(
+                &chip,
+                None,
+                &trace,
+                &perm,
+                &challenges,
+                &public_values.to_field_elements(),
+            )
+        };
+        check(&runtime.record).unwrap();
+
+        // A second halt row, at the program counter the first one jumps to.
+        let halt = *runtime.record.cpu_events.last().unwrap();
+        let mut record = runtime.record.clone();
+        record.cpu_events.push(CpuEvent {
+            pc: halt.next_pc,
+            clk: halt.clk + 4,
+            ..halt
+        });
+        assert_eq!(check(&record).unwrap_err().row, 1);
+
+        // A real row after the halt.
+        let add = runtime.record.cpu_events[0];
+        let mut record = runtime.record.clone();
+        record.cpu_events.push(CpuEvent {
+            pc: halt.next_pc,
+            next_pc: halt.next_pc + 4,
+            clk: halt.clk + 4,
+            ..add
+        });
+        assert_eq!(check(&record).unwrap_err().row, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "the constraints of a shard need all its public values")]
+    fn missing_public_values() {
+        type EF = BinomialExtensionField<BabyBear, 4>;
+
+        let mut runtime = Runtime::new(simple_program());
+        runtime.run();
+        let chip = Chip::new(RiscvAir::<BabyBear>::Cpu(CpuChip::default()));
+        let trace = chip.generate_trace(&runtime.record, &mut ExecutionRecord::default());
+        let challenges = [EF::from_canonical_u32(3), EF::from_canonical_u32(7)];
+        let perm = chip.generate_permutation_trace(None, &trace, &challenges);
+        let _ = check_constraints::<BabyBearBlake3>(&chip, None, &trace, &perm, &challenges, &[]);
+    }
 }
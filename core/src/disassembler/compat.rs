@@ -0,0 +1,596 @@
+use core::fmt::{Display, Formatter};
+
+use elf::abi::{EM_RISCV, PF_X, PT_LOAD};
+use elf::endian::LittleEndian;
+use elf::file::Class;
+use elf::ElfBytes;
+use serde::{Deserialize, Serialize};
+
+use super::WORD_SIZE;
+
+/// The `e_flags` bit of an ELF using compressed instructions.
+const EF_RISCV_RVC: u32 = 0x0001;
+
+/// The `e_flags` bits of the floating-point ABI of an ELF, zero for the soft-float ABI.
+const EF_RISCV_FLOAT_ABI: u32 = 0x0006;
+
+/// The `e_flags` bit of an ELF using the RV32E ABI.
+const EF_RISCV_RVE: u32 = 0x0008;
+
+/// The section holding the RISC-V build attributes.
+const RISCV_ATTRIBUTES: &str = ".riscv.attributes";
+
+/// The tag of the attributes which apply to the whole file.
+const TAG_FILE: u64 = 1;
+
+/// The tag of the attribute holding the ISA string.
+const TAG_RISCV_ARCH: u64 = 5;
+
+/// The multi-letter extensions which do not add instructions the runtime cannot execute.
+const SUPPORTED_EXTENSIONS: &[&str] = &["zicsr", "zifencei", "zmmul"];
+
+/// How a guest binary fails to match what the runtime executes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompatSeverity {
+    /// The binary may still run, e.g. if the offending code is never reached.
+    Warning,
+    /// The binary cannot be loaded, or traps as soon as the offending code is reached.
+    Error,
+}
+
+/// A setting a guest binary was compiled with that the runtime does not support.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompatIssue {
+    /// The binary is not a 32-bit ELF.
+    NotElf32,
+    /// The binary is built for another machine, usually because it was built for the host.
+    WrongMachine(u16),
+    /// The ISA string declares a 64-bit base.
+    Rv64,
+    /// The binary uses the RV32E base or ABI.
+    EmbeddedBase,
+    /// The binary uses a hard-float ABI.
+    HardFloatAbi,
+    /// The ELF header declares compressed instructions.
+    CompressedAbi,
+    /// The ISA string declares an extension the runtime does not execute.
+    Extension(String),
+    /// The ISA string could not be parsed.
+    InvalidIsa(String),
+    /// Floating-point instructions were found in the executable segments.
+    FloatInstructions { count: usize, first: u32 },
+    /// Atomic instructions were found in the executable segments.
+    AtomicInstructions { count: usize, first: u32 },
+    /// Compressed instructions were found in the executable segments.
+    CompressedInstructions { count: usize, first: u32 },
+}
+
+impl CompatIssue {
+    pub fn severity(&self) -> CompatSeverity {
+        match self {
+            CompatIssue::Extension(extension) => match extension.as_str() {
+                "a" | "c" | "d" | "f" | "g" | "q" => CompatSeverity::Error,
+                _ => CompatSeverity::Warning,
+            },
+            CompatIssue::InvalidIsa(_)
+            | CompatIssue::FloatInstructions { .. }
+            | CompatIssue::AtomicInstructions { .. }
+            | CompatIssue::CompressedInstructions { .. } => CompatSeverity::Warning,
+            _ => CompatSeverity::Error,
+        }
+    }
+}
+
+impl Display for CompatIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CompatIssue::NotElf32 => write!(f, "not a 32-bit ELF"),
+            CompatIssue::WrongMachine(machine) => write!(
+                f,
+                "built for machine {} instead of RISC-V, was it built with the host toolchain?",
+                machine
+            ),
+            CompatIssue::Rv64 => write!(f, "built for a 64-bit RISC-V target"),
+            CompatIssue::EmbeddedBase => write!(f, "built for the RV32E base"),
+            CompatIssue::HardFloatAbi => write!(f, "built with a hard-float ABI"),
+            CompatIssue::CompressedAbi => write!(f, "built with compressed instructions"),
+            CompatIssue::Extension(extension) => {
+                write!(f, "built with the unsupported extension '{}'", extension)
+            }
+            CompatIssue::InvalidIsa(isa) => write!(f, "invalid ISA string '{}'", isa),
+            CompatIssue::FloatInstructions { count, first } => write!(
+                f,
+                "{} floating-point instructions, the first at 0x{:08x}",
+                count, first
+            ),
+            CompatIssue::AtomicInstructions { count, first } => write!(
+                f,
+                "{} atomic instructions, the first at 0x{:08x}",
+                count, first
+            ),
+            CompatIssue::CompressedInstructions { count, first } => write!(
+                f,
+                "{} compressed instructions, the first at 0x{:08x}",
+                count, first
+            ),
+        }
+    }
+}
+
+/// The settings a guest binary was compiled with which the runtime does not support.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompatReport {
+    /// The ISA string of the `.riscv.attributes` section, if the binary has one.
+    pub isa: Option<String>,
+
+    /// The issues found, in the order they were detected.
+    pub issues: Vec<CompatIssue>,
+}
+
+impl CompatReport {
+    /// Inspect the header, the RISC-V attributes and, if there are no attributes, the executable
+    /// segments of an ELF.
+    pub fn from_elf(input: &[u8]) -> Self {
+        let mut report = Self::default();
+        let Ok(elf) = ElfBytes::<LittleEndian>::minimal_parse(input) else {
+            report.issues.push(CompatIssue::NotElf32);
+            return report;
+        };
+        if elf.ehdr.class != Class::ELF32 {
+            report.issues.push(CompatIssue::NotElf32);
+        }
+        if elf.ehdr.e_machine != EM_RISCV {
+            report
+                .issues
+                .push(CompatIssue::WrongMachine(elf.ehdr.e_machine));
+            return report;
+        }
+
+        let flags = elf.ehdr.e_flags;
+        if flags & EF_RISCV_RVC != 0 {
+            report.issues.push(CompatIssue::CompressedAbi);
+        }
+        if flags & EF_RISCV_FLOAT_ABI != 0 {
+            report.issues.push(CompatIssue::HardFloatAbi);
+        }
+        if flags & EF_RISCV_RVE != 0 {
+            report.issues.push(CompatIssue::EmbeddedBase);
+        }
+
+        let attributes = elf
+            .section_header_by_name(RISCV_ATTRIBUTES)
+            .ok()
+            .flatten()
+            .and_then(|header| elf.section_data(&header).ok())
+            .map(|(data, _)| data);
+        match attributes.and_then(parse_arch_attribute) {
+            Some(isa) => {
+                report.issues.extend(isa_issues(&isa));
+                report.isa = Some(isa);
+            }
+            None => report.issues.extend(scan_instructions(&elf)),
+        }
+        report
+    }
+
+    /// Whether the binary can be executed, i.e. no issue is an error.
+    pub fn is_compatible(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &CompatIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity() == CompatSeverity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &CompatIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity() == CompatSeverity::Warning)
+    }
+
+    /// Log every issue at the level of its severity.
+    pub fn log(&self) {
+        for issue in self.errors() {
+            log::error!("incompatible guest binary: {}", issue);
+        }
+        for issue in self.warnings() {
+            log::warn!("possibly incompatible guest binary: {}", issue);
+        }
+    }
+}
+
+/// Read an unsigned LEB128 integer, advancing `pos` past it.
+fn read_uleb128(data: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *data.get(*pos)?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Read a NUL-terminated string, advancing `pos` past the terminator.
+fn read_ntbs<'a>(data: &'a [u8], pos: &mut usize) -> Option<&'a str> {
+    let len = data.get(*pos..)?.iter().position(|byte| *byte == 0)?;
+    let s = core::str::from_utf8(&data[*pos..*pos + len]).ok()?;
+    *pos += len + 1;
+    Some(s)
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Option<usize> {
+    let bytes = data.get(pos..pos + 4)?;
+    Some(u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+}
+
+/// Find the ISA string in the contents of a `.riscv.attributes` section.
+///
+/// Reference: https://github.com/riscv-non-isa/riscv-elf-psabi-doc/blob/master/riscv-elf.adoc
+fn parse_arch_attribute(data: &[u8]) -> Option<String> {
+    if data.first() != Some(&b'A') {
+        return None;
+    }
+    let mut pos = 1;
+    while pos < data.len() {
+        let len = read_u32(data, pos)?;
+        let subsection = data.get(pos..pos + len)?;
+        pos += len.max(1);
+
+        let mut sub_pos = 4;
+        if read_ntbs(subsection, &mut sub_pos)? != "riscv" {
+            continue;
+        }
+        while sub_pos < subsection.len() {
+            let start = sub_pos;
+            let tag = read_uleb128(subsection, &mut sub_pos)?;
+            let size = read_u32(subsection, sub_pos)?;
+            sub_pos += 4;
+            let end = start + size.max(sub_pos - start);
+            let attributes = subsection.get(..end)?;
+            if tag == TAG_FILE {
+                while sub_pos < end {
+                    let tag = read_uleb128(attributes, &mut sub_pos)?;
+                    // Odd tags hold strings, even tags hold integers.
+                    if tag == TAG_RISCV_ARCH {
+                        return read_ntbs(attributes, &mut sub_pos).map(str::to_string);
+                    } else if tag % 2 == 1 {
+                        read_ntbs(attributes, &mut sub_pos)?;
+                    } else {
+                        read_uleb128(attributes, &mut sub_pos)?;
+                    }
+                }
+            }
+            sub_pos = end;
+        }
+    }
+    None
+}
+
+/// Strip the version (e.g. `2p1`) from the end of a multi-letter extension name.
+fn strip_version(extension: &str) -> &str {
+    let start = extension
+        .trim_end_matches(|c: char| c.is_ascii_digit() || c == 'p')
+        .len();
+    match extension[start..].chars().next() {
+        Some(c) if c.is_ascii_digit() => &extension[..start],
+        _ => extension,
+    }
+}
+
+/// The single-letter extensions of a part of an ISA string, skipping their versions.
+fn single_letter_extensions(part: &str) -> Vec<char> {
+    let chars = part.chars().collect::<Vec<_>>();
+    chars
+        .iter()
+        .enumerate()
+        .filter(|(i, c)| {
+            let is_version_separator = **c == 'p'
+                && *i > 0
+                && chars[i - 1].is_ascii_digit()
+                && chars.get(i + 1).map_or(false, char::is_ascii_digit);
+            c.is_ascii_alphabetic() && !is_version_separator
+        })
+        .map(|(_, c)| *c)
+        .collect()
+}
+
+/// The issues of an ISA string such as `rv32i2p1_m2p0_zicsr2p0`.
+fn isa_issues(isa: &str) -> Vec<CompatIssue> {
+    let lower = isa.to_ascii_lowercase();
+    let mut parts = lower.split('_');
+    let first = parts.next().unwrap_or_default();
+    let (base, rest) = if let Some(rest) = first.strip_prefix("rv32") {
+        (None, rest)
+    } else if let Some(rest) = first.strip_prefix("rv64") {
+        (Some(CompatIssue::Rv64), rest)
+    } else {
+        return vec![CompatIssue::InvalidIsa(isa.to_string())];
+    };
+
+    let mut issues = base.into_iter().collect::<Vec<_>>();
+    let mut extensions = single_letter_extensions(rest);
+    for part in parts {
+        if part.starts_with(['z', 'x', 's', 'h']) {
+            let extension = strip_version(part);
+            if !SUPPORTED_EXTENSIONS.contains(&extension) {
+                issues.push(CompatIssue::Extension(extension.to_string()));
+            }
+        } else {
+            extensions.extend(single_letter_extensions(part));
+        }
+    }
+    for extension in extensions {
+        match extension {
+            'i' | 'm' => {}
+            'e' => issues.push(CompatIssue::EmbeddedBase),
+            _ => issues.push(CompatIssue::Extension(extension.to_string())),
+        }
+    }
+    issues
+}
+
+/// Scan the words of the executable segments for instructions of unsupported extensions.
+fn scan_instructions(elf: &ElfBytes<LittleEndian>) -> Vec<CompatIssue> {
+    let Some(segments) = elf.segments() else {
+        return Vec::new();
+    };
+    let (mut float, mut atomic, mut compressed) = (Vec::new(), Vec::new(), Vec::new());
+    for segment in segments
+        .iter()
+        .filter(|segment| segment.p_type == PT_LOAD && segment.p_flags & PF_X != 0)
+    {
+        let Ok(data) = elf.segment_data(&segment) else {
+            continue;
+        };
+        for (i, chunk) in data.chunks_exact(WORD_SIZE).enumerate() {
+            let addr = segment.p_vaddr as u32 + (i * WORD_SIZE) as u32;
+            let word = u32::from_le_bytes(chunk.try_into().unwrap());
+            if word == 0 {
+                continue;
+            }
+            if word & 0b11 != 0b11 {
+                compressed.push(addr);
+                continue;
+            }
+            match word & 0x7f {
+                // LOAD-FP, STORE-FP, the fused multiply-adds and OP-FP.
+                0x07 | 0x27 | 0x43 | 0x47 | 0x4b | 0x4f | 0x53 => float.push(addr),
+                // AMO.
+                0x2f => atomic.push(addr),
+                _ => {}
+            }
+        }
+    }
+
+    let mut issues = Vec::new();
+    if let Some(first) = float.first() {
+        issues.push(CompatIssue::FloatInstructions {
+            count: float.len(),
+            first: *first,
+        });
+    }
+    if let Some(first) = atomic.first() {
+        issues.push(CompatIssue::AtomicInstructions {
+            count: atomic.len(),
+            first: *first,
+        });
+    }
+    if let Some(first) = compressed.first() {
+        issues.push(CompatIssue::CompressedInstructions {
+            count: compressed.len(),
+            first: *first,
+        });
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use elf::abi::{EM_RISCV, EM_X86_64, ET_EXEC, PF_R, PF_X, PT_LOAD};
+
+    use super::*;
+    use crate::runtime::Program;
+
+    /// The section type of `.riscv.attributes`.
+    const SHT_RISCV_ATTRIBUTES: u32 = 0x7000_0003;
+
+    /// `addi x0, x0, 0`.
+    const NOP: u32 = 0x00000013;
+
+    /// `fadd.s f1, f2, f3`.
+    const FADD_S: u32 = 0x003100d3;
+
+    /// `amoadd.w x1, x2, (x3)`.
+    const AMOADD_W: u32 = 0x0021a0af;
+
+    /// Two `c.nop`s.
+    const C_NOPS: u32 = 0x00010001;
+
+    /// The contents of a `.riscv.attributes` section declaring the given ISA string.
+    fn attributes(isa: &str) -> Vec<u8> {
+        let mut attribute = vec![TAG_RISCV_ARCH as u8];
+        attribute.extend(isa.as_bytes());
+        attribute.push(0);
+
+        let mut file = vec![TAG_FILE as u8];
+        file.extend((5 + attribute.len() as u32).to_le_bytes());
+        file.extend(attribute);
+
+        let mut subsection = (4 + 6 + file.len() as u32).to_le_bytes().to_vec();
+        subsection.extend(b"riscv\0");
+        subsection.extend(file);
+
+        let mut data = vec![b'A'];
+        data.extend(subsection);
+        data
+    }
+
+    /// Builds a 32-bit little-endian executable with one text segment and optionally a
+    /// `.riscv.attributes` section, like those produced by a toolchain with the given settings.
+    fn build_elf(machine: u16, flags: u32, text: &[u32], isa: Option<&str>) -> Vec<u8> {
+        const EHDR_SIZE: u32 = 52;
+        const PHDR_SIZE: u32 = 32;
+        const SHDR_SIZE: u32 = 40;
+        let push_u16 = |bytes: &mut Vec<u8>, value: u16| bytes.extend(value.to_le_bytes());
+        let push_u32 = |bytes: &mut Vec<u8>, value: u32| bytes.extend(value.to_le_bytes());
+
+        let text = text
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect::<Vec<_>>();
+        let attributes = isa.map(attributes).unwrap_or_default();
+        let names = b"\0.riscv.attributes\0.shstrtab\0".to_vec();
+        let text_offset = EHDR_SIZE + PHDR_SIZE;
+        let attributes_offset = text_offset + text.len() as u32;
+        let names_offset = attributes_offset + attributes.len() as u32;
+        let (shoff, shnum, shstrndx) = match isa {
+            Some(_) => (names_offset + names.len() as u32, 3, 2),
+            None => (0, 0, 0),
+        };
+
+        let mut bytes = vec![0x7f, b'E', b'L', b'F', 1, 1, 1, 0];
+        bytes.resize(16, 0);
+        push_u16(&mut bytes, ET_EXEC);
+        push_u16(&mut bytes, machine);
+        push_u32(&mut bytes, 1);
+        push_u32(&mut bytes, 0x1000);
+        push_u32(&mut bytes, EHDR_SIZE);
+        push_u32(&mut bytes, shoff);
+        push_u32(&mut bytes, flags);
+        push_u16(&mut bytes, EHDR_SIZE as u16);
+        push_u16(&mut bytes, PHDR_SIZE as u16);
+        push_u16(&mut bytes, 1);
+        push_u16(&mut bytes, SHDR_SIZE as u16);
+        push_u16(&mut bytes, shnum);
+        push_u16(&mut bytes, shstrndx);
+
+        for value in [
+            PT_LOAD,
+            text_offset,
+            0x1000,
+            0x1000,
+            text.len() as u32,
+            text.len() as u32,
+            PF_R | PF_X,
+            4,
+        ] {
+            push_u32(&mut bytes, value);
+        }
+        bytes.extend(text);
+
+        if isa.is_some() {
+            bytes.extend(&attributes);
+            bytes.extend(&names);
+            let sections = [
+                [0; 10],
+                [
+                    1,
+                    SHT_RISCV_ATTRIBUTES,
+                    0,
+                    0,
+                    attributes_offset,
+                    attributes.len() as u32,
+                    0,
+                    0,
+                    1,
+                    0,
+                ],
+                [19, 3, 0, 0, names_offset, names.len() as u32, 0, 0, 1, 0],
+            ];
+            for value in sections.into_iter().flatten() {
+                push_u32(&mut bytes, value);
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_compatible() {
+        let elf = build_elf(EM_RISCV, 0, &[NOP], Some("rv32i2p1_m2p0_zicsr2p0"));
+        let report = CompatReport::from_elf(&elf);
+        assert_eq!(report.isa.as_deref(), Some("rv32i2p1_m2p0_zicsr2p0"));
+        assert!(report.issues.is_empty());
+        assert!(report.is_compatible());
+
+        let program = Program::from(&elf);
+        assert_eq!(program.compat_report(), &report);
+    }
+
+    #[test]
+    fn test_float_extensions() {
+        let elf = build_elf(EM_RISCV, 0x4, &[NOP], Some("rv32i2p1_m2p0_f2p2_d2p2"));
+        let report = CompatReport::from_elf(&elf);
+        assert_eq!(
+            report.issues,
+            vec![
+                CompatIssue::HardFloatAbi,
+                CompatIssue::Extension("f".to_string()),
+                CompatIssue::Extension("d".to_string()),
+            ]
+        );
+        assert!(!report.is_compatible());
+    }
+
+    #[test]
+    fn test_compressed_and_atomic_extensions() {
+        let elf = build_elf(EM_RISCV, 0x1, &[NOP], Some("rv32imac"));
+        let report = CompatReport::from_elf(&elf);
+        assert_eq!(
+            report.issues,
+            vec![
+                CompatIssue::CompressedAbi,
+                CompatIssue::Extension("a".to_string()),
+                CompatIssue::Extension("c".to_string()),
+            ]
+        );
+        assert_eq!(report.errors().count(), 3);
+    }
+
+    #[test]
+    fn test_unknown_extension_warns() {
+        let elf = build_elf(EM_RISCV, 0, &[NOP], Some("rv32i2p1_m2p0_zba1p0"));
+        let report = CompatReport::from_elf(&elf);
+        assert_eq!(
+            report.issues,
+            vec![CompatIssue::Extension("zba".to_string())]
+        );
+        assert!(report.is_compatible());
+        assert_eq!(report.warnings().count(), 1);
+    }
+
+    #[test]
+    fn test_host_toolchain() {
+        let elf = build_elf(EM_X86_64, 0, &[NOP], None);
+        let report = CompatReport::from_elf(&elf);
+        assert_eq!(report.issues, vec![CompatIssue::WrongMachine(EM_X86_64)]);
+    }
+
+    #[test]
+    fn test_instruction_scan() {
+        let elf = build_elf(EM_RISCV, 0, &[NOP, FADD_S, AMOADD_W, C_NOPS, FADD_S], None);
+        let report = CompatReport::from_elf(&elf);
+        assert_eq!(report.isa, None);
+        assert_eq!(
+            report.issues,
+            vec![
+                CompatIssue::FloatInstructions {
+                    count: 2,
+                    first: 0x1004
+                },
+                CompatIssue::AtomicInstructions {
+                    count: 1,
+                    first: 0x1008
+                },
+                CompatIssue::CompressedInstructions {
+                    count: 1,
+                    first: 0x100c
+                },
+            ]
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use elf::abi::{EM_RISCV, ET_EXEC, PF_X, PT_LOAD};
+use elf::abi::{EM_RISCV, ET_EXEC, PF_X, PT_LOAD, STT_FUNC, STT_OBJECT};
 use elf::endian::LittleEndian;
 use elf::file::Class;
 use elf::ElfBytes;
@@ -80,6 +80,7 @@ impl Elf {
 
         let mut instructions: Vec<u32> = Vec::new();
         let mut base_address = u32::MAX;
+        let mut loaded_ranges: Vec<(u32, u32)> = Vec::new();
 
         // Only read segments that are executable instructions that are also PT_LOAD.
         for segment in segments.iter().filter(|x| x.p_type == PT_LOAD) {
@@ -110,6 +111,22 @@ impl Elf {
                 panic!("vaddr {vaddr:08x} is unaligned");
             }
 
+            // The zero-filled part of the segment must come after the bytes read from the file.
+            if file_size > mem_size {
+                panic!("segment file_size {file_size} is larger than mem_size {mem_size}");
+            }
+
+            // Make sure that the segment does not overlap with a previously loaded segment.
+            let end = vaddr
+                .checked_add(mem_size)
+                .expect("segment exceeds the maximum address");
+            for (start_other, end_other) in loaded_ranges.iter() {
+                if vaddr < *end_other && *start_other < end {
+                    panic!("overlapping segments [0x{vaddr:08x}, 0x{end:08x}) and [0x{start_other:08x}, 0x{end_other:08x})");
+                }
+            }
+            loaded_ranges.push((vaddr, end));
+
             // If the virtual address is less than the first memory address, then update the first
             // memory address.
             if (segment.p_flags & PF_X) != 0 && base_address > vaddr {
@@ -152,4 +169,168 @@ impl Elf {
 
         Elf::new(instructions, entry, base_address, image)
     }
+
+    /// The addresses of the function symbols of the ELF file, by name. Empty if the ELF file is
+    /// stripped.
+    pub fn function_symbols(input: &[u8]) -> BTreeMap<String, u32> {
+        Self::symbols(input, STT_FUNC)
+    }
+
+    /// The addresses of the data symbols of the ELF file, by name. Empty if the ELF file is
+    /// stripped.
+    pub fn object_symbols(input: &[u8]) -> BTreeMap<String, u32> {
+        Self::symbols(input, STT_OBJECT)
+    }
+
+    fn symbols(input: &[u8], symtype: u8) -> BTreeMap<String, u32> {
+        let elf = ElfBytes::<LittleEndian>::minimal_parse(input).expect("failed to parse elf");
+        let Ok(Some((symbols, strings))) = elf.symbol_table() else {
+            return BTreeMap::new();
+        };
+        symbols
+            .iter()
+            .filter(|symbol| symbol.st_symtype() == symtype)
+            .filter_map(|symbol| {
+                let name = strings.get(symbol.st_name as usize).ok()?;
+                let addr = u32::try_from(symbol.st_value).ok()?;
+                Some((name.to_string(), addr))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use elf::abi::{EM_RISCV, ET_EXEC, PF_R, PF_W, PF_X, PT_LOAD};
+
+    use super::Elf;
+    use crate::runtime::Program;
+
+    /// A segment of a hand-built ELF: (vaddr, flags, file bytes, mem_size).
+    type Segment = (u32, u32, Vec<u8>, u32);
+
+    /// Builds a minimal 32-bit little-endian RISC-V executable with the given loadable segments.
+    fn build_elf(entry: u32, segments: &[Segment]) -> Vec<u8> {
+        const EHDR_SIZE: u32 = 52;
+        const PHDR_SIZE: u32 = 32;
+        let push_u16 = |bytes: &mut Vec<u8>, value: u16| bytes.extend(value.to_le_bytes());
+        let push_u32 = |bytes: &mut Vec<u8>, value: u32| bytes.extend(value.to_le_bytes());
+
+        let mut bytes = vec![0x7f, b'E', b'L', b'F', 1, 1, 1, 0];
+        bytes.resize(16, 0);
+        push_u16(&mut bytes, ET_EXEC);
+        push_u16(&mut bytes, EM_RISCV);
+        push_u32(&mut bytes, 1);
+        push_u32(&mut bytes, entry);
+        push_u32(&mut bytes, EHDR_SIZE);
+        push_u32(&mut bytes, 0);
+        push_u32(&mut bytes, 0);
+        push_u16(&mut bytes, EHDR_SIZE as u16);
+        push_u16(&mut bytes, PHDR_SIZE as u16);
+        push_u16(&mut bytes, segments.len() as u16);
+        push_u16(&mut bytes, 40);
+        push_u16(&mut bytes, 0);
+        push_u16(&mut bytes, 0);
+
+        let mut offset = EHDR_SIZE + PHDR_SIZE * segments.len() as u32;
+        for (vaddr, flags, data, mem_size) in segments.iter() {
+            push_u32(&mut bytes, PT_LOAD);
+            push_u32(&mut bytes, offset);
+            push_u32(&mut bytes, *vaddr);
+            push_u32(&mut bytes, *vaddr);
+            push_u32(&mut bytes, data.len() as u32);
+            push_u32(&mut bytes, *mem_size);
+            push_u32(&mut bytes, *flags);
+            push_u32(&mut bytes, 4);
+            offset += data.len() as u32;
+        }
+        for (_, _, data, _) in segments.iter() {
+            bytes.extend(data);
+        }
+        bytes
+    }
+
+    fn words_to_bytes(words: &[u32]) -> Vec<u8> {
+        words.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    /// `addi x0, x0, 0`.
+    const NOP: u32 = 0x00000013;
+
+    #[test]
+    fn test_bss_zero_fill() {
+        let elf = build_elf(
+            0x1000,
+            &[
+                (0x1000, PF_R | PF_X, words_to_bytes(&[NOP, NOP]), 8),
+                (0x2000, PF_R | PF_W, words_to_bytes(&[7, 8]), 16),
+            ],
+        );
+        let program = Program::from(&elf);
+        let memory = program.initial_memory();
+        assert_eq!(program.instructions.len(), 2);
+        assert_eq!(memory.get(&0x2000), Some(&7));
+        assert_eq!(memory.get(&0x2004), Some(&8));
+        assert_eq!(memory.get(&0x2008), Some(&0));
+        assert_eq!(memory.get(&0x200c), Some(&0));
+        assert_eq!(memory.get(&0x2010), None);
+    }
+
+    #[test]
+    fn test_partial_word_zero_fill() {
+        let elf = build_elf(
+            0x1000,
+            &[
+                (0x1000, PF_R | PF_X, words_to_bytes(&[NOP]), 4),
+                (0x2000, PF_R | PF_W, vec![0x11, 0x22, 0x33, 0x44, 0x55], 8),
+            ],
+        );
+        let memory = Elf::decode(&elf).memory_image;
+        assert_eq!(memory.get(&0x2000), Some(&0x44332211));
+        assert_eq!(memory.get(&0x2004), Some(&0x55));
+    }
+
+    #[test]
+    fn test_rodata_placement() {
+        let elf = build_elf(
+            0x1000,
+            &[
+                (0x1000, PF_R | PF_X, words_to_bytes(&[NOP, NOP, NOP]), 12),
+                (0x3000, PF_R, words_to_bytes(&[0xdeadbeef, 0xcafebabe]), 8),
+            ],
+        );
+        let elf = Elf::decode(&elf);
+        assert_eq!(elf.pc_start, 0x1000);
+        assert_eq!(elf.pc_base, 0x1000);
+        assert_eq!(elf.instructions, vec![NOP, NOP, NOP]);
+        assert_eq!(elf.memory_image.get(&0x3000), Some(&0xdeadbeef));
+        assert_eq!(elf.memory_image.get(&0x3004), Some(&0xcafebabe));
+        assert_eq!(elf.memory_image.len(), 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "overlapping segments")]
+    fn test_overlapping_segments() {
+        let elf = build_elf(
+            0x1000,
+            &[
+                (0x1000, PF_R | PF_X, words_to_bytes(&[NOP, NOP]), 8),
+                (0x1004, PF_R | PF_W, words_to_bytes(&[1]), 8),
+            ],
+        );
+        Elf::decode(&elf);
+    }
+
+    #[test]
+    #[should_panic(expected = "unaligned")]
+    fn test_unaligned_segment() {
+        let elf = build_elf(
+            0x1000,
+            &[
+                (0x1000, PF_R | PF_X, words_to_bytes(&[NOP]), 4),
+                (0x2002, PF_R | PF_W, words_to_bytes(&[1]), 4),
+            ],
+        );
+        Elf::decode(&elf);
+    }
 }
@@ -1,10 +1,12 @@
+mod compat;
 mod elf;
 mod instruction;
 
+pub use compat::*;
 pub use elf::*;
 pub use instruction::*;
 
-use crate::runtime::{Instruction, Program};
+use crate::runtime::{Instruction, MemoryLayout, Program, STACK_SIZE_SYMBOL, STACK_TOP};
 use std::{collections::BTreeMap, fs::File, io::Read};
 
 impl Program {
@@ -12,16 +14,31 @@ impl Program {
     pub fn new(instructions: Vec<Instruction>, pc_start: u32, pc_base: u32) -> Self {
         Self {
             instructions,
+            encodings: Vec::new(),
             pc_start,
             pc_base,
             memory_image: BTreeMap::new(),
+            compat: CompatReport::default(),
+            symbols: BTreeMap::new(),
+            memory_layout: None,
         }
     }
 
     /// Disassemble a RV32IM ELF to a program that be executed by the VM.
     pub fn from(input: &[u8]) -> Self {
+        // Report incompatible settings first, since decoding panics on some of them.
+        let compat = CompatReport::from_elf(input);
+        compat.log();
+
         // Decode the bytes as an ELF.
         let elf = Elf::decode(input);
+        let symbols = Elf::function_symbols(input);
+
+        // Guests built with the entrypoint macro export the size of their stack.
+        let memory_layout = Elf::object_symbols(input)
+            .get(STACK_SIZE_SYMBOL)
+            .and_then(|addr| elf.memory_image.get(addr))
+            .map(|stack_size| MemoryLayout::new(STACK_TOP, *stack_size));
 
         // Transpile the RV32IM instructions.
         let instructions = transpile(&elf.instructions);
@@ -29,9 +46,13 @@ impl Program {
         // Return the program.
         Program {
             instructions,
+            encodings: elf.instructions,
             pc_start: elf.pc_start,
             pc_base: elf.pc_base,
             memory_image: elf.memory_image,
+            compat,
+            symbols,
+            memory_layout,
         }
     }
 
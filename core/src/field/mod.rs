@@ -12,8 +12,8 @@ use p3_maybe_rayon::prelude::ParallelIterator;
 use sp1_derive::AlignedBorrow;
 
 use crate::air::FieldAirBuilder;
-use crate::air::MachineAir;
 use crate::air::SP1AirBuilder;
+use crate::air::{EventKind, MachineAir};
 use crate::runtime::ExecutionRecord;
 use crate::utils::pad_to_power_of_two;
 
@@ -39,8 +39,12 @@ pub struct FieldLtuCols<T> {
     /// The second field operand.
     pub c: T,
 
-    /// The difference between `b` and `c` in little-endian order.
-    pub diff_bits: [T; LTU_NB_BITS + 1],
+    /// The low 28 bits of `b - c + 2^LTU_NB_BITS`, as a 16-bit and a 12-bit limb in little-endian
+    /// order, which are range checked.
+    pub diff_limbs: [T; 2],
+
+    /// Bit 28 of `b - c + 2^LTU_NB_BITS`. Bit 29 is `1 - lt`.
+    pub diff_bit_28: T,
 
     // TODO:  Support multiplicities > 1.  Right now there can be duplicate rows.
     // pub multiplicities: T,
@@ -52,11 +56,23 @@ impl<F: PrimeField> MachineAir<F> for FieldLtuChip {
         "FieldLTU".to_string()
     }
 
+    fn produces(&self) -> &[EventKind] {
+        &[EventKind::RangeCheck]
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::Field]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.field_events.len()
+    }
+
     #[instrument(name = "generate FieldLTU trace", skip_all)]
     fn generate_trace(
         &self,
         input: &ExecutionRecord,
-        _output: &mut ExecutionRecord,
+        output: &mut ExecutionRecord,
     ) -> RowMajorMatrix<F> {
         // Generate the trace rows for each event.
         let rows = input
@@ -68,9 +84,8 @@ impl<F: PrimeField> MachineAir<F> for FieldLtuChip {
                 let diff = event.b.wrapping_sub(event.c).wrapping_add(1 << LTU_NB_BITS);
                 cols.b = F::from_canonical_u32(event.b);
                 cols.c = F::from_canonical_u32(event.c);
-                for i in 0..cols.diff_bits.len() {
-                    cols.diff_bits[i] = F::from_canonical_u32((diff >> i) & 1);
-                }
+                cols.diff_limbs = diff_limbs(diff).map(F::from_canonical_u32);
+                cols.diff_bit_28 = F::from_canonical_u32((diff >> 28) & 1);
                 let max = 1 << LTU_NB_BITS;
                 if diff >= max {
                     panic!("diff overflow");
@@ -81,6 +96,14 @@ impl<F: PrimeField> MachineAir<F> for FieldLtuChip {
             })
             .collect::<Vec<_>>();
 
+        // Range check the limbs of the differences.
+        for event in input.field_events.iter() {
+            let diff = event.b.wrapping_sub(event.c).wrapping_add(1 << LTU_NB_BITS);
+            for (limb, bits) in diff_limbs(diff).into_iter().zip(DIFF_LIMB_BITS) {
+                output.add_range_check(limb, bits);
+            }
+        }
+
         // Convert the trace to a row major matrix.
         let mut trace = RowMajorMatrix::new(
             rows.into_iter().flatten().collect::<Vec<_>>(),
@@ -96,6 +119,14 @@ impl<F: PrimeField> MachineAir<F> for FieldLtuChip {
 
 pub const LTU_NB_BITS: usize = 29;
 
+/// The widths of the limbs of the difference, which are supported by the range check table.
+const DIFF_LIMB_BITS: [u32; 2] = [16, 12];
+
+/// The limbs of the low 28 bits of the difference.
+fn diff_limbs(diff: u32) -> [u32; 2] {
+    [diff & 0xffff, (diff >> 16) & 0xfff]
+}
+
 impl<F: Field> BaseAir<F> for FieldLtuChip {
     fn width(&self) -> usize {
         NUM_FIELD_COLS
@@ -113,27 +144,58 @@ impl<AB: SP1AirBuilder> Air<AB> for FieldLtuChip {
         // Verify that lt is a boolean.
         builder.assert_bool(local.lt);
 
-        // Verify that the diff bits are boolean.
-        for i in 0..local.diff_bits.len() {
-            builder.assert_bool(local.diff_bits[i]);
+        // Verify that bit 28 of the difference is boolean, and range check its limbs.
+        builder.assert_bool(local.diff_bit_28);
+        for (limb, bits) in local.diff_limbs.into_iter().zip(DIFF_LIMB_BITS) {
+            builder.send_range_check(limb, AB::F::from_canonical_u32(bits), local.is_real);
         }
 
-        // Verify the decomposition of b - c.
-        let mut diff = AB::Expr::zero();
-        for i in 0..local.diff_bits.len() {
-            diff += local.diff_bits[i] * AB::F::from_canonical_u32(1 << i);
-        }
+        // Verify the decomposition of b - c, whose bit 29 is set unless `b < c`, which asserts
+        // that the output is correct.
+        let diff = local.diff_limbs[0]
+            + local.diff_limbs[1] * AB::F::from_canonical_u32(1 << 16)
+            + local.diff_bit_28 * AB::F::from_canonical_u32(1 << 28)
+            + (AB::Expr::one() - local.lt) * AB::F::from_canonical_u32(1 << LTU_NB_BITS);
         builder.when(local.is_real).assert_eq(
             local.b - local.c + AB::F::from_canonical_u32(1 << LTU_NB_BITS),
             diff,
         );
 
-        // Assert that the output is correct.
-        builder
-            .when(local.is_real)
-            .assert_eq(local.lt, AB::Expr::one() - local.diff_bits[LTU_NB_BITS]);
-
         // Receive the field operation.
         builder.receive_field_op(local.lt, local.b, local.c, local.is_real);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+
+    use super::event::FieldEvent;
+    use super::*;
+    use crate::utils::test_utils::GadgetBuilder;
+
+    #[test]
+    fn test_out_of_range_limb() {
+        let mut record = ExecutionRecord::default();
+        record.add_field_event(FieldEvent::new(true, 3, 5));
+        let trace: RowMajorMatrix<BabyBear> =
+            FieldLtuChip.generate_trace(&record, &mut ExecutionRecord::default());
+        let mut row = trace.row_slice(0).to_vec();
+
+        let mut builder = GadgetBuilder::new(&row);
+        FieldLtuChip.eval(&mut builder);
+        assert!(builder.failures().is_empty());
+
+        // Moving a unit from the 12-bit limb to the 16-bit limb keeps the decomposition of the
+        // difference, which only the range check of the 16-bit limb catches.
+        let cols: &mut FieldLtuCols<BabyBear> = row.as_mut_slice().borrow_mut();
+        cols.diff_limbs[0] += BabyBear::from_canonical_u32(1 << 16);
+        cols.diff_limbs[1] -= BabyBear::one();
+        let mut builder = GadgetBuilder::new(&row);
+        FieldLtuChip.eval(&mut builder);
+        assert_eq!(
+            builder.failures(),
+            ["invalid range check RangeCheckEvent { value: 131070, bits: 16 }"]
+        );
+    }
+}
@@ -1,11 +1,66 @@
+use std::collections::BTreeMap;
+
+use p3_field::{AbstractExtensionField, PrimeField32};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use crate::utils::Buffer;
+use crate::utils::{codec, Buffer};
 
 /// Standard input for the prover.
 #[derive(Serialize, Deserialize)]
 pub struct SP1Stdin {
     pub buffer: Buffer,
+
+    /// The environment variables the program can read with `sp1_zkvm::env::var`.
+    #[serde(default)]
+    pub env: BTreeMap<String, Vec<u8>>,
+}
+
+/// Which part of the standard input of the program a proof records.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StdinPolicy {
+    /// The proof records the digest of the bytes the program read and how many it read, so two
+    /// inputs which only differ after them have the same proof.
+    #[default]
+    ConsumedPrefix,
+    /// The program must read its whole input: the execution fails if it halts with bytes left,
+    /// and the proof records the digest of the whole input.
+    ExactConsume,
+}
+
+/// The standard input of the program as claimed by the prover, recorded in the proof and
+/// observed by the challenger before the shards.
+///
+/// The shards constrain how many bytes the program read from its input stream, and the verifier
+/// checks that the binding does not claim more consumed bytes than that. The input stream also
+/// holds the hints and the looked up environment variables, so the count only bounds the
+/// consumed length: nothing checks that the program read the bytes of the digest. The binding
+/// lets a verifier check that the input accompanying a proof is the one the prover says it ran
+/// the program on, which is no guarantee against a dishonest prover.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StdinBinding {
+    /// The policy the proof was generated with.
+    pub policy: StdinPolicy,
+
+    /// The number of bytes of the buffer the program read.
+    pub consumed: u64,
+
+    /// The digest of the first `consumed` bytes of the buffer and of the environment.
+    pub digest: [u8; 32],
+}
+
+/// The reasons the input accompanying a proof does not match its `StdinBinding`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StdinError {
+    /// The proof does not bind its input.
+    Unbound,
+    /// The program read more bytes than the input has.
+    ConsumedPastEnd { consumed: u64, len: u64 },
+    /// The proof requires the whole input to be read, but bytes are left.
+    Leftover { consumed: u64, len: u64 },
+    /// The input does not have the digest recorded in the binding.
+    DigestMismatch,
+    /// The binding claims more consumed bytes than the shards read from the input stream.
+    ConsumedUnread { consumed: u64, read: u64 },
 }
 
 /// Standard output for the prover.
@@ -14,11 +69,59 @@ pub struct SP1Stdout {
     pub buffer: Buffer,
 }
 
+/// The length of the prefix of a frame of committed output.
+pub const FRAME_LEN_BYTES: usize = 8;
+
+/// A value committed by the program with `sp1_zkvm::io::commit`: the bincode of the value, which
+/// is preceded in the output by its length as a little-endian `u64`, as the fields of
+/// `SP1Stdin::canonical_bytes` are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame<'a> {
+    /// The position of the frame in the output.
+    pub index: usize,
+    pub bytes: &'a [u8],
+}
+
+/// Why the committed output of a program does not decode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputError {
+    /// The frame starting at `offset` needs more bytes than the output has left, either for its
+    /// length prefix or for its value.
+    Truncated {
+        offset: usize,
+        needed: u64,
+        available: usize,
+    },
+    /// Bytes are left after the value decoded from the frame, or after the single frame expected.
+    TrailingData { frame: usize, len: usize },
+    /// The frame does not hold a value of the requested type.
+    Deserialize { frame: usize, reason: String },
+}
+
+impl<'a> Frame<'a> {
+    /// Decodes the value of the frame, which must fill it.
+    pub fn decode<T: DeserializeOwned>(&self) -> Result<T, OutputError> {
+        let mut rest = self.bytes;
+        let value = bincode::deserialize_from(&mut rest).map_err(|e| OutputError::Deserialize {
+            frame: self.index,
+            reason: e.to_string(),
+        })?;
+        if !rest.is_empty() {
+            return Err(OutputError::TrailingData {
+                frame: self.index,
+                len: rest.len(),
+            });
+        }
+        Ok(value)
+    }
+}
+
 impl SP1Stdin {
     /// Create a new `SP1Stdin`.
     pub fn new() -> Self {
         Self {
             buffer: Buffer::new(),
+            env: BTreeMap::new(),
         }
     }
 
@@ -26,6 +129,7 @@ impl SP1Stdin {
     pub fn from(data: &[u8]) -> Self {
         Self {
             buffer: Buffer::from(data),
+            env: BTreeMap::new(),
         }
     }
 
@@ -48,8 +152,142 @@ impl SP1Stdin {
     pub fn write_slice(&mut self, slice: &[u8]) {
         self.buffer.write_slice(slice);
     }
+
+    /// Write field elements to the buffer in their canonical encoding, which the guest reads back
+    /// as little-endian `u32`s.
+    pub fn write_fields<F: PrimeField32>(&mut self, values: &[F]) {
+        self.buffer.write_slice(&codec::encode_fields(values));
+    }
+
+    /// Write elements of the degree-4 extension field to the buffer in their canonical encoding.
+    pub fn write_exts<F: PrimeField32, EF: AbstractExtensionField<F>>(&mut self, values: &[EF]) {
+        self.buffer.write_slice(&codec::encode_exts(values));
+    }
+
+    /// Set the environment variable `key`, replacing any previous value.
+    pub fn set_env(&mut self, key: &str, value: &[u8]) {
+        self.env.insert(key.to_string(), value.to_vec());
+    }
+
+    /// The encoding of the buffer and the environment which the stdin digest is computed over.
+    ///
+    /// Every field is length-prefixed and the variables are in the order of their keys, so two
+    /// inputs have the same encoding exactly when they have the same buffer and environment.
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        self.canonical_prefix_bytes(self.buffer.data.len())
+    }
+
+    /// The encoding of the first `len` bytes of the buffer and of the whole environment, in the
+    /// layout of `canonical_bytes`.
+    pub fn canonical_prefix_bytes(&self, len: usize) -> Vec<u8> {
+        let prefix = &self.buffer.data[..len];
+        let mut bytes = Vec::new();
+        bytes.extend((prefix.len() as u64).to_le_bytes());
+        bytes.extend(prefix);
+        bytes.extend((self.env.len() as u64).to_le_bytes());
+        for (key, value) in &self.env {
+            bytes.extend((key.len() as u64).to_le_bytes());
+            bytes.extend(key.as_bytes());
+            bytes.extend((value.len() as u64).to_le_bytes());
+            bytes.extend(value);
+        }
+        bytes
+    }
+
+    /// The BLAKE3 digest of `canonical_bytes`, which binds a proof to both the input and the
+    /// environment it was run with.
+    pub fn digest(&self) -> [u8; 32] {
+        *blake3::hash(&self.canonical_bytes()).as_bytes()
+    }
+
+    /// The BLAKE3 digest of `canonical_prefix_bytes`, which a proof binds when the program only
+    /// reads the first `len` bytes of the buffer.
+    pub fn prefix_digest(&self, len: usize) -> [u8; 32] {
+        *blake3::hash(&self.canonical_prefix_bytes(len)).as_bytes()
+    }
+}
+
+impl StdinBinding {
+    /// The binding to `stdin` of a program which read its first `consumed` bytes.
+    pub fn new(policy: StdinPolicy, stdin: &SP1Stdin, consumed: usize) -> Self {
+        Self {
+            policy,
+            consumed: consumed as u64,
+            digest: stdin.prefix_digest(consumed),
+        }
+    }
+
+    /// Checks that `stdin` is an input the proof can have been generated from under its policy.
+    pub fn check(&self, stdin: &SP1Stdin) -> Result<(), StdinError> {
+        let len = stdin.buffer.data.len() as u64;
+        if self.consumed > len {
+            return Err(StdinError::ConsumedPastEnd {
+                consumed: self.consumed,
+                len,
+            });
+        }
+        if self.policy == StdinPolicy::ExactConsume && self.consumed != len {
+            return Err(StdinError::Leftover {
+                consumed: self.consumed,
+                len,
+            });
+        }
+        if stdin.prefix_digest(self.consumed as usize) != self.digest {
+            return Err(StdinError::DigestMismatch);
+        }
+        Ok(())
+    }
+
+    /// Checks that the shards of the proof, which read `read` bytes of the input stream, can have
+    /// consumed the bytes the binding claims.
+    pub fn check_read(&self, read: u64) -> Result<(), StdinError> {
+        if self.consumed > read {
+            return Err(StdinError::ConsumedUnread {
+                consumed: self.consumed,
+                read,
+            });
+        }
+        Ok(())
+    }
+
+    /// The bytes the challenger observes: the policy, the consumed length and the digest.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.policy as u8];
+        bytes.extend(self.consumed.to_le_bytes());
+        bytes.extend(self.digest);
+        bytes
+    }
+}
+
+impl std::fmt::Display for StdinError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StdinError::Unbound => write!(f, "the proof does not bind its input"),
+            StdinError::ConsumedPastEnd { consumed, len } => write!(
+                f,
+                "the program read {} bytes of an input of {} bytes",
+                consumed, len
+            ),
+            StdinError::Leftover { consumed, len } => write!(
+                f,
+                "the program left {} of its {} input bytes unread",
+                len - consumed,
+                len
+            ),
+            StdinError::DigestMismatch => {
+                write!(f, "the input does not match the digest of the proof")
+            }
+            StdinError::ConsumedUnread { consumed, read } => write!(
+                f,
+                "the proof claims {} consumed input bytes, but its shards read {}",
+                consumed, read
+            ),
+        }
+    }
 }
 
+impl std::error::Error for StdinError {}
+
 impl SP1Stdout {
     /// Create a new `SP1Stdout`.
     pub fn new() -> Self {
@@ -84,12 +322,95 @@ impl SP1Stdout {
     pub fn write_slice(&mut self, slice: &[u8]) {
         self.buffer.write_slice(slice);
     }
+
+    /// Commit a value to the buffer as one frame, as `sp1_zkvm::io::commit` does in the guest.
+    pub fn commit<T: Serialize>(&mut self, value: &T) {
+        let bytes = bincode::serialize(value).expect("serialization failed");
+        self.buffer.write_slice(&(bytes.len() as u64).to_le_bytes());
+        self.buffer.write_slice(&bytes);
+    }
+
+    /// Splits the whole buffer into the frames committed by the program, each decoded to its own
+    /// type with `Frame::decode`.
+    pub fn decode_all(&self) -> Result<Vec<Frame<'_>>, OutputError> {
+        let data = self.buffer.data.as_slice();
+        let mut frames = Vec::new();
+        let mut offset = 0;
+        while offset < data.len() {
+            let available = data.len() - offset;
+            let truncated = |needed| OutputError::Truncated {
+                offset,
+                needed,
+                available,
+            };
+            let prefix = data[offset..]
+                .get(..FRAME_LEN_BYTES)
+                .ok_or(truncated(FRAME_LEN_BYTES as u64))?;
+            let len = u64::from_le_bytes(prefix.try_into().unwrap());
+            let end = usize::try_from(len)
+                .ok()
+                .and_then(|len| len.checked_add(offset + FRAME_LEN_BYTES))
+                .filter(|&end| end <= data.len())
+                .ok_or(truncated((FRAME_LEN_BYTES as u64).saturating_add(len)))?;
+            frames.push(Frame {
+                index: frames.len(),
+                bytes: &data[offset + FRAME_LEN_BYTES..end],
+            });
+            offset = end;
+        }
+        Ok(frames)
+    }
+
+    /// Decodes the output as the single value the program committed.
+    pub fn decode<T: DeserializeOwned>(&self) -> Result<T, OutputError> {
+        let frames = self.decode_all()?;
+        match frames.as_slice() {
+            [] => Err(OutputError::Truncated {
+                offset: 0,
+                needed: FRAME_LEN_BYTES as u64,
+                available: 0,
+            }),
+            [frame] => frame.decode(),
+            [_, rest @ ..] => Err(OutputError::TrailingData {
+                frame: 0,
+                len: rest
+                    .iter()
+                    .map(|frame| FRAME_LEN_BYTES + frame.bytes.len())
+                    .sum(),
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for OutputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputError::Truncated {
+                offset,
+                needed,
+                available,
+            } => write!(
+                f,
+                "the frame at offset {} needs {} bytes but only {} are left",
+                offset, needed, available
+            ),
+            OutputError::TrailingData { frame, len } => {
+                write!(f, "{} bytes are left after frame {}", len, frame)
+            }
+            OutputError::Deserialize { frame, reason } => {
+                write!(f, "frame {} does not decode: {}", frame, reason)
+            }
+        }
+    }
 }
 
+impl std::error::Error for OutputError {}
+
 pub mod proof_serde {
     use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
 
     use crate::stark::{Proof, StarkGenericConfig};
+    use crate::version::{decode_proof, encode_proof};
 
     pub fn serialize<S, SC: StarkGenericConfig + Serialize>(
         proof: &Proof<SC>,
@@ -99,8 +420,7 @@ pub mod proof_serde {
         S: serde::Serializer,
     {
         if serializer.is_human_readable() {
-            let bytes = bincode::serialize(proof).unwrap();
-            let hex_bytes = hex::encode(bytes);
+            let hex_bytes = hex::encode(encode_proof(proof));
             serializer.serialize_str(&hex_bytes)
         } else {
             proof.serialize(serializer)
@@ -116,7 +436,7 @@ pub mod proof_serde {
         if deserializer.is_human_readable() {
             let hex_bytes = String::deserialize(deserializer).unwrap();
             let bytes = hex::decode(hex_bytes).unwrap();
-            let proof = bincode::deserialize(&bytes).map_err(serde::de::Error::custom)?;
+            let (proof, _) = decode_proof(&bytes).map_err(serde::de::Error::custom)?;
             Ok(proof)
         } else {
             Proof::<SC>::deserialize(deserializer)
@@ -154,3 +474,264 @@ pub mod proof_serde {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::{
+        OutputError, SP1Stdin, SP1Stdout, StdinBinding, StdinError, StdinPolicy, FRAME_LEN_BYTES,
+    };
+    use crate::utils::tests::FIBONACCI_IO_ELF;
+    use crate::{SP1Prover, SP1ProverOptions, SP1Verifier};
+
+    #[test]
+    fn test_write_fields() {
+        use p3_baby_bear::BabyBear;
+        use p3_field::{AbstractField, PrimeField32};
+
+        let mut stdin = SP1Stdin::new();
+        stdin.write_fields(&[BabyBear::from_canonical_u32(5), -BabyBear::one()]);
+        let mut words = [0u8; 8];
+        stdin.read_slice(&mut words);
+        assert_eq!(words[..4], 5u32.to_le_bytes());
+        assert_eq!(words[4..], (BabyBear::ORDER_U32 - 1).to_le_bytes());
+    }
+
+    #[test]
+    fn test_stdin_digest_binds_env() {
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&3u32);
+        let digest = stdin.digest();
+
+        stdin.set_env("MODE", b"fast");
+        let with_env = stdin.digest();
+        assert_ne!(with_env, digest);
+
+        stdin.set_env("MODE", b"slow");
+        assert_ne!(stdin.digest(), with_env);
+        stdin.set_env("MODE", b"fast");
+        assert_eq!(stdin.digest(), with_env);
+
+        // Moving bytes between a key and its value changes the digest.
+        let mut other = SP1Stdin::from(&stdin.buffer.data);
+        other.set_env("MODEf", b"ast");
+        assert_ne!(other.digest(), with_env);
+    }
+
+    #[test]
+    fn test_stdin_binding() {
+        let stdin = SP1Stdin::from(b"readunread");
+        let binding = StdinBinding::new(StdinPolicy::ConsumedPrefix, &stdin, 4);
+        binding.check(&stdin).unwrap();
+
+        // Only the consumed prefix is bound.
+        binding.check(&SP1Stdin::from(b"read")).unwrap();
+        binding.check(&SP1Stdin::from(b"readother")).unwrap();
+        assert_eq!(
+            binding.check(&SP1Stdin::from(b"rEadunread")),
+            Err(StdinError::DigestMismatch)
+        );
+        assert_eq!(
+            binding.check(&SP1Stdin::from(b"rea")),
+            Err(StdinError::ConsumedPastEnd {
+                consumed: 4,
+                len: 3
+            })
+        );
+
+        // The shards must have read at least the consumed bytes.
+        binding.check_read(4).unwrap();
+        assert_eq!(
+            binding.check_read(3),
+            Err(StdinError::ConsumedUnread {
+                consumed: 4,
+                read: 3
+            })
+        );
+
+        // The whole input must be consumed.
+        let binding = StdinBinding::new(StdinPolicy::ExactConsume, &stdin, 4);
+        assert_eq!(
+            binding.check(&stdin),
+            Err(StdinError::Leftover {
+                consumed: 4,
+                len: 10
+            })
+        );
+        let binding = StdinBinding::new(StdinPolicy::ExactConsume, &stdin, 10);
+        binding.check(&stdin).unwrap();
+        assert_eq!(binding.digest, stdin.digest());
+    }
+
+    #[test]
+    fn test_stdin_policy_prove() {
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&3u32);
+        stdin.write_slice(b"unread");
+        let len = stdin.buffer.data.len() as u64;
+
+        // The unread tail can be swapped without failing the check of the input.
+        let mut proof = SP1Prover::prove(FIBONACCI_IO_ELF, stdin).unwrap();
+        let binding = proof.proof.stdin.unwrap();
+        assert_eq!(binding.policy, StdinPolicy::ConsumedPrefix);
+        assert_eq!(binding.consumed, len - 6);
+        proof.stdin.buffer.data.truncate(len as usize - 6);
+        proof.stdin.write_slice(b"tail");
+        SP1Verifier::verify(FIBONACCI_IO_ELF, &proof).unwrap();
+
+        // The shards read at least the consumed bytes.
+        assert!(proof.proof.input_read() >= binding.consumed);
+
+        // The consumed bytes no longer have the digest recorded in the proof.
+        proof.stdin.buffer.data[0] ^= 1;
+        assert!(SP1Verifier::verify(FIBONACCI_IO_ELF, &proof).is_err());
+
+        let options = SP1ProverOptions {
+            stdin_policy: StdinPolicy::ExactConsume,
+            ..Default::default()
+        };
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&3u32);
+        stdin.write_slice(b"unread");
+        assert!(SP1Prover::prove_with_options(FIBONACCI_IO_ELF, stdin, options.clone()).is_err());
+
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&3u32);
+        let mut proof = SP1Prover::prove_with_options(FIBONACCI_IO_ELF, stdin, options).unwrap();
+        SP1Verifier::verify(FIBONACCI_IO_ELF, &proof).unwrap();
+
+        // Appending input the program did not read fails the check of the input, and relabeling
+        // the proof with a laxer policy changes the transcript.
+        proof.stdin.write_slice(b"unread");
+        assert!(SP1Verifier::verify(FIBONACCI_IO_ELF, &proof).is_err());
+        proof.proof.stdin.as_mut().unwrap().policy = StdinPolicy::ConsumedPrefix;
+        assert!(SP1Verifier::verify(FIBONACCI_IO_ELF, &proof).is_err());
+    }
+
+    #[test]
+    fn test_prove_shard_sizes() {
+        let outputs = [1 << 17, 1 << 20].map(|shard_size| {
+            let mut stdin = SP1Stdin::new();
+            stdin.write(&10u32);
+            let options = SP1ProverOptions {
+                shard_size: Some(shard_size),
+                ..Default::default()
+            };
+            let proof = SP1Prover::prove_with_options(FIBONACCI_IO_ELF, stdin, options).unwrap();
+            SP1Verifier::verify(FIBONACCI_IO_ELF, &proof).unwrap();
+            proof.stdout.buffer.data
+        });
+        assert_eq!(outputs[0], outputs[1]);
+
+        // A shard size which is not a power of two, or whose clock cycles overflow, is refused.
+        for shard_size in [3, 1 << 32] {
+            let mut stdin = SP1Stdin::new();
+            stdin.write(&10u32);
+            let options = SP1ProverOptions {
+                shard_size: Some(shard_size),
+                ..Default::default()
+            };
+            assert!(SP1Prover::prove_with_options(FIBONACCI_IO_ELF, stdin, options).is_err());
+        }
+    }
+
+    #[test]
+    fn test_commit_roundtrip() {
+        let mut stdout = SP1Stdout::new();
+        stdout.commit(&7u32);
+        stdout.commit(&"result".to_string());
+        stdout.commit(&(vec![1u8, 2, 3], Some(-5i64)));
+
+        let frames = stdout.decode_all().unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0].decode::<u32>(), Ok(7));
+        assert_eq!(frames[1].decode::<String>().unwrap(), "result");
+        assert_eq!(
+            frames[2].decode::<(Vec<u8>, Option<i64>)>().unwrap(),
+            (vec![1, 2, 3], Some(-5))
+        );
+
+        let mut single = SP1Stdout::new();
+        single.commit(&[9u64; 3]);
+        assert_eq!(single.decode::<[u64; 3]>(), Ok([9; 3]));
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let mut stdout = SP1Stdout::new();
+        stdout.commit(&1u64);
+        stdout.commit(&2u32);
+        assert_eq!(
+            stdout.decode::<u64>(),
+            Err(OutputError::TrailingData {
+                frame: 0,
+                len: FRAME_LEN_BYTES + 4
+            })
+        );
+
+        // A u16 leaves bytes of the u64 in its frame.
+        let frames = stdout.decode_all().unwrap();
+        assert_eq!(
+            frames[0].decode::<u16>(),
+            Err(OutputError::TrailingData { frame: 0, len: 6 })
+        );
+        assert!(matches!(
+            frames[1].decode::<u64>(),
+            Err(OutputError::Deserialize { frame: 1, .. })
+        ));
+
+        // A cut in the middle of the second frame.
+        let cut = SP1Stdout::from(&stdout.buffer.data[..FRAME_LEN_BYTES + 8 + 10]);
+        assert_eq!(
+            cut.decode_all(),
+            Err(OutputError::Truncated {
+                offset: FRAME_LEN_BYTES + 8,
+                needed: FRAME_LEN_BYTES as u64 + 4,
+                available: 10
+            })
+        );
+        let cut = SP1Stdout::from(&stdout.buffer.data[..3]);
+        assert!(matches!(
+            cut.decode_all(),
+            Err(OutputError::Truncated { offset: 0, .. })
+        ));
+        assert!(matches!(
+            SP1Stdout::new().decode::<u32>(),
+            Err(OutputError::Truncated { .. })
+        ));
+    }
+
+    proptest! {
+        #[test]
+        fn test_frame_parser(data in prop::collection::vec(any::<u8>(), 0..64)) {
+            // Any bytes either split into frames covering them exactly, or fail without panicking.
+            let stdout = SP1Stdout::from(&data);
+            if let Ok(frames) = stdout.decode_all() {
+                let len: usize = frames
+                    .iter()
+                    .map(|frame| FRAME_LEN_BYTES + frame.bytes.len())
+                    .sum();
+                prop_assert_eq!(len, data.len());
+                for frame in frames {
+                    let _ = frame.decode::<Vec<u8>>();
+                }
+            }
+        }
+
+        #[test]
+        fn test_frames_roundtrip(values in prop::collection::vec(any::<(u32, Vec<u8>)>(), 0..8)) {
+            let mut stdout = SP1Stdout::new();
+            for value in &values {
+                stdout.commit(value);
+            }
+            let decoded = stdout
+                .decode_all()
+                .unwrap()
+                .iter()
+                .map(|frame| frame.decode::<(u32, Vec<u8>)>())
+                .collect::<Result<Vec<_>, _>>();
+            prop_assert_eq!(decoded, Ok(values));
+        }
+    }
+}
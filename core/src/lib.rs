@@ -12,10 +12,15 @@
 )]
 
 extern crate alloc;
+// The code generated by `#[derive(MachineAir)]` refers to this crate by name.
+extern crate self as sp1_core;
 
 pub mod air;
 pub mod alu;
+pub mod artifact;
+pub mod batch;
 pub mod bytes;
+pub mod checkpoint;
 pub mod cpu;
 pub mod disassembler;
 pub mod field;
@@ -24,23 +29,43 @@ pub mod lookup;
 pub mod memory;
 pub mod operations;
 pub mod program;
+pub mod proof;
+pub mod range;
 pub mod runtime;
 pub mod stark;
 pub mod syscall;
 pub mod utils;
+pub mod version;
+pub mod witness;
 
+pub use artifact::*;
+pub use batch::{BatchOptions, BatchResults, JobTiming, ProveJob};
+pub use checkpoint::*;
 pub use io::*;
+pub use proof::*;
+pub use version::*;
+pub use witness::*;
 
 use anyhow::Result;
+use lookup::{unbalanced_interactions, InteractionKind, UnbalancedInteraction};
+use p3_baby_bear::BabyBear;
 use p3_commit::Pcs;
 use p3_matrix::dense::RowMajorMatrix;
-use runtime::{Program, Runtime};
+use runtime::{ExecutionError, Profiler, Program, Runtime, ShardingConfig};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use stark::{OpeningProof, ProgramVerificationError, Proof, ShardMainData};
-use stark::{RiscvStark, StarkGenericConfig};
+use stark::StatsCollector;
+use stark::{BatchVerifier, OpeningProof, ProgramVerificationError, Proof, ShardMainData};
+use stark::{
+    LocalProver, ProverError, ProverHooks, ProverStats, RiscvStark, StarkGenericConfig,
+    VerifyingKey,
+};
+use std::borrow::Borrow;
 use std::fs;
-use utils::{prove_core, BabyBearBlake3, StarkUtils};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use utils::{prove_core_with_machine, prove_core_with_stdin, BabyBearBlake3, StarkUtils};
 
 /// A prover that can prove RISCV ELFs.
 pub struct SP1Prover;
@@ -48,6 +73,49 @@ pub struct SP1Prover;
 /// A verifier that can verify proofs generated by `SP1Prover`.
 pub struct SP1Verifier;
 
+/// Options for `SP1Prover::prove_with_options`.
+#[derive(Clone, Default)]
+pub struct SP1ProverOptions {
+    /// How the proof binds the input of the program.
+    pub stdin_policy: StdinPolicy,
+    /// The wall-clock time the execution and the trace generation may take together, after which
+    /// proving fails with `ExecutionError::Timeout`.
+    pub timeout: Option<Duration>,
+    /// The callbacks on the progress of the proof, if any.
+    pub hooks: Option<Arc<dyn ProverHooks>>,
+    /// The number of shards proven at the same time, one per core if unset. The proof does not
+    /// depend on it.
+    pub shard_workers: Option<usize>,
+    /// The number of instructions of each shard, a power of two, `SHARD_SIZE` or 2^19 if unset.
+    pub shard_size: Option<usize>,
+    /// Whether to check each shard before committing to it, failing with the first check it does
+    /// not pass, `SELF_CHECK` if unset.
+    pub self_check: Option<bool>,
+}
+
+impl SP1ProverOptions {
+    /// The options for testing programs, which check each shard before proving it.
+    pub fn testing() -> Self {
+        Self {
+            self_check: Some(true),
+            ..Default::default()
+        }
+    }
+}
+
+impl std::fmt::Debug for SP1ProverOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SP1ProverOptions")
+            .field("stdin_policy", &self.stdin_policy)
+            .field("timeout", &self.timeout)
+            .field("hooks", &self.hooks.is_some())
+            .field("shard_workers", &self.shard_workers)
+            .field("shard_size", &self.shard_size)
+            .field("self_check", &self.self_check)
+            .finish()
+    }
+}
+
 /// A proof of a RISCV ELF execution with given inputs and outputs.
 #[derive(Serialize, Deserialize)]
 pub struct SP1ProofWithIO<SC: StarkGenericConfig + Serialize + DeserializeOwned> {
@@ -60,24 +128,224 @@ pub struct SP1ProofWithIO<SC: StarkGenericConfig + Serialize + DeserializeOwned>
 impl SP1Prover {
     /// Executes the elf with the given inputs and returns the output.
     pub fn execute(elf: &[u8], stdin: SP1Stdin) -> Result<SP1Stdout> {
-        let program = Program::from(elf);
-        let mut runtime = Runtime::new(program);
-        runtime.write_stdin_slice(&stdin.buffer.data);
+        let mut runtime = runtime_with_stdin(Program::from(elf), &stdin);
         runtime.run();
         Ok(SP1Stdout::from(&runtime.state.output_stream))
     }
 
+    /// Executes the ELF and attributes its cycles to the functions of its symbol table.
+    pub fn profile(elf: &[u8], stdin: SP1Stdin) -> Result<Profiler> {
+        let mut runtime = runtime_with_stdin(Program::from(elf), &stdin);
+        let profiler = Profiler::install(&mut runtime);
+        runtime.try_run()?;
+        let profiler = profiler.borrow().clone();
+        Ok(profiler)
+    }
+
+    /// Executes the ELF and returns the interactions whose sends and receives don't balance over
+    /// the chips, which make proving fail.
+    pub fn debug_lookups(
+        elf: &[u8],
+        stdin: SP1Stdin,
+    ) -> Result<Vec<UnbalancedInteraction<BabyBear>>> {
+        let runtime = execute_program(Program::from(elf), &stdin, &SP1ProverOptions::default())?;
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        Ok(unbalanced_interactions::<BabyBearBlake3>(
+            machine.chips(),
+            &runtime.record,
+            InteractionKind::all_kinds(),
+        ))
+    }
+
     /// Generate a proof for the execution of the ELF with the given public inputs.
     pub fn prove(elf: &[u8], stdin: SP1Stdin) -> Result<SP1ProofWithIO<BabyBearBlake3>> {
+        Self::prove_with_context(elf, stdin, &[])
+    }
+
+    /// Generate a proof for the execution of the ELF with the given public inputs, bound to an
+    /// application context such as a chain or application id.
+    pub fn prove_with_context(
+        elf: &[u8],
+        stdin: SP1Stdin,
+        context: &[u8],
+    ) -> Result<SP1ProofWithIO<BabyBearBlake3>> {
+        Self::prove_program(
+            Program::from(elf),
+            stdin,
+            context,
+            SP1ProverOptions::default(),
+        )
+    }
+
+    /// Generate a proof for the execution of the ELF with the given public inputs and options.
+    ///
+    /// With `StdinPolicy::ExactConsume`, proving fails if the program halts without reading its
+    /// whole input.
+    pub fn prove_with_options(
+        elf: &[u8],
+        stdin: SP1Stdin,
+        options: SP1ProverOptions,
+    ) -> Result<SP1ProofWithIO<BabyBearBlake3>> {
+        Self::prove_program(Program::from(elf), stdin, &[], options)
+    }
+
+    /// Proves independent programs one after the other, sharing the config of the prover. A job
+    /// which fails does not stop the others.
+    pub fn prove_batch(jobs: Vec<ProveJob>) -> Vec<Result<SP1ProofWithIO<BabyBearBlake3>>> {
+        Self::prove_batch_with_options(jobs, BatchOptions::default()).results
+    }
+
+    /// Proves independent programs, up to `options.concurrency` at a time within the cell budget
+    /// of the options, with the timings of each job.
+    pub fn prove_batch_with_options(jobs: Vec<ProveJob>, options: BatchOptions) -> BatchResults {
+        batch::prove_batch(jobs, options)
+    }
+
+    /// Generate a proof like `prove_with_options`, along with the statistics of each chip of each
+    /// shard. The hooks of the options, if any, still receive every callback.
+    pub fn prove_with_stats(
+        elf: &[u8],
+        stdin: SP1Stdin,
+        options: SP1ProverOptions,
+    ) -> Result<(SP1ProofWithIO<BabyBearBlake3>, ProverStats)> {
+        let collector = Arc::new(StatsCollector::new(options.hooks.clone()));
+        let options = SP1ProverOptions {
+            hooks: Some(collector.clone()),
+            ..options
+        };
+        let proof = Self::prove_with_options(elf, stdin, options)?;
+        Ok((proof, collector.stats()))
+    }
+
+    /// Generate a proof for the execution of the ELF from the function with the given name, which
+    /// reads the arguments with `sp1_zkvm::env::args()`.
+    pub fn prove_entry(
+        elf: &[u8],
+        entry: &str,
+        args: &[Vec<u8>],
+        stdin: SP1Stdin,
+    ) -> Result<SP1ProofWithIO<BabyBearBlake3>> {
+        let program = Program::from(elf).with_entry_symbol(entry).with_args(args);
+        Self::prove_program(program, stdin, &[], SP1ProverOptions::default())
+    }
+
+    /// Generate a proof for the execution of the ELF after `Program::with_strength_reduction`,
+    /// which must be verified with `SP1Verifier::verify_optimized`.
+    pub fn prove_optimized(elf: &[u8], stdin: SP1Stdin) -> Result<SP1ProofWithIO<BabyBearBlake3>> {
+        Self::prove_program(
+            Program::from(elf).with_strength_reduction(),
+            stdin,
+            &[],
+            SP1ProverOptions::default(),
+        )
+    }
+
+    /// Generate a proof like `prove_optimized`, with the given options.
+    pub fn prove_optimized_with_options(
+        elf: &[u8],
+        stdin: SP1Stdin,
+        options: SP1ProverOptions,
+    ) -> Result<SP1ProofWithIO<BabyBearBlake3>> {
+        Self::prove_program(
+            Program::from(elf).with_strength_reduction(),
+            stdin,
+            &[],
+            options,
+        )
+    }
+
+    /// Executes the ELF and generates the main traces of all its shards without proving them, for
+    /// `prove_witness` or another prover to prove.
+    pub fn generate_witness(
+        elf: &[u8],
+        stdin: SP1Stdin,
+        options: SP1ProverOptions,
+    ) -> Result<WitnessBundle> {
+        let runtime = execute_program(Program::from(elf), &stdin, &options)?;
+        let stdout = SP1Stdout::from(&runtime.state.output_stream);
+        let binding = stdin_binding(&runtime, &stdin, options.stdin_policy);
+        Ok(WitnessBundle::generate(runtime, stdin, stdout, binding)?)
+    }
+
+    /// Proves a witness bundle of the ELF, into the proof `prove_with_options` generates from the
+    /// same execution.
+    pub fn prove_witness(
+        elf: &[u8],
+        bundle: WitnessBundle,
+    ) -> Result<SP1ProofWithIO<BabyBearBlake3>> {
+        let config = BabyBearBlake3::new();
+        let mut challenger = config.challenger();
+        let machine = RiscvStark::new(config);
         let program = Program::from(elf);
-        let mut runtime = Runtime::new(program);
-        runtime.write_stdin_slice(&stdin.buffer.data);
-        tracing::info_span!("runtime.run(...)").in_scope(|| {
-            runtime.run();
-        });
+        bundle.check_program(&machine.preprocessed_digests(&program))?;
+        let (pk, _) = machine.setup(&program);
+
+        let (shards, manifest) = bundle.into_shard_traces();
+        let proof = machine.prove_traces::<LocalProver<_>>(
+            &pk,
+            shards,
+            &[],
+            Some(manifest.stdin_binding),
+            manifest.claimed_values,
+            &mut challenger,
+        );
+        Ok(SP1ProofWithIO {
+            proof,
+            stdin: manifest.stdin,
+            stdout: manifest.stdout,
+        })
+    }
+
+    /// Executes the ELF and checkpoints the records of its shards to the directory without proving
+    /// them, for `prove_checkpoints` to prove them one at a time.
+    pub fn execute_with_checkpoints(
+        elf: &[u8],
+        stdin: SP1Stdin,
+        options: SP1ProverOptions,
+        dir: &Path,
+    ) -> Result<CheckpointManifest> {
+        let mut runtime = execute_program(Program::from(elf), &stdin, &options)?;
+        let stdout = SP1Stdout::from(&runtime.state.output_stream);
+        let binding = stdin_binding(&runtime, &stdin, options.stdin_policy);
+        Ok(CheckpointManifest::write(
+            &mut runtime,
+            Some(stdin),
+            stdout,
+            Some(binding),
+            dir,
+        )?)
+    }
+
+    /// Proves the shards of a checkpoint of the ELF which have no proof in its directory yet, and
+    /// assembles the proof `prove_with_options` generates from the same execution.
+    pub fn prove_checkpoints(elf: &[u8], dir: &Path) -> Result<SP1ProofWithIO<BabyBearBlake3>> {
+        let config = BabyBearBlake3::new();
+        let manifest = CheckpointManifest::load(dir)?;
+        let machine = RiscvStark::new(config.clone());
+        manifest.check_program(&machine.preprocessed_digests(&Program::from(elf)))?;
+        for index in manifest.missing_proofs(dir) {
+            tracing::info!("proving shard {} of {}", index, manifest.shards.len());
+            prove_shard_from_checkpoint(dir, index, config.clone())?;
+        }
+        let proof = assemble_checkpoint_proof(dir, config)?;
+        Ok(SP1ProofWithIO {
+            proof,
+            stdin: manifest.stdin.unwrap_or_else(SP1Stdin::new),
+            stdout: manifest.stdout,
+        })
+    }
+
+    fn prove_program(
+        program: Program,
+        stdin: SP1Stdin,
+        context: &[u8],
+        options: SP1ProverOptions,
+    ) -> Result<SP1ProofWithIO<BabyBearBlake3>> {
+        let runtime = execute_program(program, &stdin, &options)?;
         let config = BabyBearBlake3::new();
         let stdout = SP1Stdout::from(&runtime.state.output_stream);
-        let proof = prove_core(config, runtime);
+        let binding = stdin_binding(&runtime, &stdin, options.stdin_policy);
+        let proof = prove_runtime(config, runtime, context, binding, &options)?;
         Ok(SP1ProofWithIO {
             proof,
             stdin,
@@ -100,12 +368,11 @@ impl SP1Prover {
         ShardMainData<SC>: Serialize + DeserializeOwned,
         <SC as StarkGenericConfig>::Val: p3_field::PrimeField32,
     {
-        let program = Program::from(elf);
-        let mut runtime = Runtime::new(program);
-        runtime.write_stdin_slice(&stdin.buffer.data);
+        let mut runtime = runtime_with_stdin(Program::from(elf), &stdin);
         runtime.run();
         let stdout = SP1Stdout::from(&runtime.state.output_stream);
-        let proof = prove_core(config, runtime);
+        let binding = stdin_binding(&runtime, &stdin, StdinPolicy::default());
+        let proof = prove_core_with_stdin(config, runtime, &[], Some(binding))?;
         Ok(SP1ProofWithIO {
             proof,
             stdin,
@@ -114,49 +381,250 @@ impl SP1Prover {
     }
 }
 
+/// Runs the program on `stdin` with the options, leaving what is left of their timeout for the
+/// trace generation.
+fn execute_program(
+    program: Program,
+    stdin: &SP1Stdin,
+    options: &SP1ProverOptions,
+) -> Result<Runtime> {
+    let mut runtime = runtime_with_stdin(program, stdin);
+    runtime.exact_input = options.stdin_policy == StdinPolicy::ExactConsume;
+    if let Some(shard_size) = options.shard_size {
+        if !shard_size.is_power_of_two() || shard_size > utils::env::MAX_SHARD_SIZE {
+            anyhow::bail!(
+                "the shard size {} is not a power of two of at most {}",
+                shard_size,
+                utils::env::MAX_SHARD_SIZE
+            );
+        }
+        runtime.shard_size = shard_size as u32;
+    }
+    let deadline = options.timeout.map(|timeout| Instant::now() + timeout);
+    runtime.timeout = options.timeout;
+    tracing::info_span!("runtime.run(...)").in_scope(|| runtime.try_run())?;
+    // The trace generation cannot stop halfway, so what is left of the timeout is checked before
+    // it starts.
+    if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+        return Err(ExecutionError::Timeout {
+            cycles_executed: runtime.state.global_clk as u64,
+        }
+        .into());
+    }
+    tracing::info!("execution report: {}", runtime.report);
+    Ok(runtime)
+}
+
+/// Proves the execution of the runtime with the machine settings of the options.
+fn prove_runtime(
+    config: BabyBearBlake3,
+    runtime: Runtime,
+    context: &[u8],
+    binding: StdinBinding,
+    options: &SP1ProverOptions,
+) -> Result<Proof<BabyBearBlake3>, ProverError> {
+    let mut machine = RiscvStark::new(config);
+    if let Some(hooks) = options.hooks.clone() {
+        machine = machine.with_hooks(hooks);
+    }
+    if let Some(workers) = options.shard_workers {
+        machine = machine.with_shard_workers(workers);
+    }
+    if let Some(shard_size) = options.shard_size {
+        machine = machine.with_sharding(ShardingConfig::with_shard_size(shard_size));
+    }
+    if let Some(self_check) = options.self_check {
+        machine = machine.with_self_check(self_check);
+    }
+    prove_core_with_machine(machine, runtime, context, Some(binding))
+}
+
+/// A runtime for the program which reads the buffer and the environment of `stdin`.
+fn runtime_with_stdin(program: Program, stdin: &SP1Stdin) -> Runtime {
+    let mut runtime = Runtime::new(program);
+    runtime.write_stdin_slice(&stdin.buffer.data);
+    for (key, value) in &stdin.env {
+        runtime.set_env(key.as_bytes(), value);
+    }
+    runtime
+}
+
+/// The binding to `stdin` of a proof of the execution of the runtime, which consumed the input up
+/// to the bytes it left unread.
+///
+/// Bytes read in unconstrained blocks are not counted by the shards, so the consumed length is at
+/// most the bytes read outside of them, which the verifier checks it against.
+fn stdin_binding(runtime: &Runtime, stdin: &SP1Stdin, policy: StdinPolicy) -> StdinBinding {
+    let consumed = stdin
+        .buffer
+        .data
+        .len()
+        .saturating_sub(runtime.unread_input())
+        .min(runtime.state.input_read);
+    StdinBinding::new(policy, stdin, consumed)
+}
+
+/// Checks that the input accompanying the proof is the one it binds, and that the shards read at
+/// least the bytes the binding claims were consumed.
+fn check_stdin<SC: StarkGenericConfig + Serialize + DeserializeOwned>(
+    proof: &SP1ProofWithIO<SC>,
+) -> Result<(), ProgramVerificationError> {
+    proof
+        .proof
+        .stdin
+        .ok_or(StdinError::Unbound)
+        .and_then(|binding| {
+            binding.check(&proof.stdin)?;
+            binding.check_read(proof.proof.input_read())
+        })
+        .map_err(ProgramVerificationError::InvalidStdin)
+}
+
 impl SP1Verifier {
-    /// Verify a proof generated by `SP1Prover`.
+    /// Verify a proof generated by `SP1Prover`, returning the number of instructions executed by
+    /// the program.
     #[allow(unused_variables)]
     pub fn verify(
         elf: &[u8],
         proof: &SP1ProofWithIO<BabyBearBlake3>,
-    ) -> Result<(), ProgramVerificationError> {
+    ) -> Result<u64, ProgramVerificationError> {
+        Self::verify_with_context(elf, proof, &[])
+    }
+
+    /// Verify a proof generated by `SP1Prover` that must be bound to the given context.
+    pub fn verify_with_context(
+        elf: &[u8],
+        proof: &SP1ProofWithIO<BabyBearBlake3>,
+        context: &[u8],
+    ) -> Result<u64, ProgramVerificationError> {
+        check_stdin(proof)?;
         let config = BabyBearBlake3::new();
         let mut challenger = config.challenger();
         let machine = RiscvStark::new(config);
         let (_, vk) = machine.setup(&Program::from(elf));
+        machine.verify_with_context(&vk, &proof.proof, context, &mut challenger)
+    }
+
+    /// Verify a proof generated by `SP1Prover` which claims the program committed the expected
+    /// values.
+    ///
+    /// The claimed values are not constrained by the shards, so a successful verification does not
+    /// prove the program committed them.
+    pub fn verify_with_claimed_values(
+        elf: &[u8],
+        proof: &SP1ProofWithIO<BabyBearBlake3>,
+        expected: &[u8],
+    ) -> Result<u64, ProgramVerificationError> {
+        if proof.claimed_values() != expected {
+            return Err(ProgramVerificationError::ClaimedValuesMismatch);
+        }
+        Self::verify(elf, proof)
+    }
+
+    /// Verify a proof generated by `SP1Prover::prove_optimized`.
+    pub fn verify_optimized(
+        elf: &[u8],
+        proof: &SP1ProofWithIO<BabyBearBlake3>,
+    ) -> Result<u64, ProgramVerificationError> {
+        check_stdin(proof)?;
+        let config = BabyBearBlake3::new();
+        let mut challenger = config.challenger();
+        let machine = RiscvStark::new(config);
+        let (_, vk) = machine.setup(&Program::from(elf).with_strength_reduction());
+        machine.verify(&vk, &proof.proof, &mut challenger)
+    }
+
+    /// Verify a proof generated by `SP1Prover::prove_entry` for the function with the given name.
+    pub fn verify_entry(
+        elf: &[u8],
+        entry: &str,
+        proof: &SP1ProofWithIO<BabyBearBlake3>,
+    ) -> Result<u64, ProgramVerificationError> {
+        check_stdin(proof)?;
+        let config = BabyBearBlake3::new();
+        let mut challenger = config.challenger();
+        let machine = RiscvStark::new(config);
+        let (_, vk) = machine.setup(&Program::from(elf).with_entry_symbol(entry));
         machine.verify(&vk, &proof.proof, &mut challenger)
     }
 
+    /// Set up a verifier for many proofs of the ELF generated by `SP1Prover`, which processes the
+    /// program once for all of them.
+    pub fn batch(elf: &[u8]) -> BatchVerifier<BabyBearBlake3> {
+        let config = BabyBearBlake3::new();
+        let challenger = config.challenger();
+        let machine = RiscvStark::new(config);
+        let (_, vk) = machine.setup(&Program::from(elf));
+        BatchVerifier::new(machine, vk, challenger)
+    }
+
     /// Verify a proof generated by `SP1Prover` with a custom config.
     #[allow(unused_variables)]
     pub fn verify_with_config<SC: StarkGenericConfig>(
         elf: &[u8],
         proof: &SP1ProofWithIO<SC>,
         config: SC,
-    ) -> Result<(), ProgramVerificationError>
+    ) -> Result<u64, ProgramVerificationError>
     where
-        SC: StarkUtils + Send + Sync + Serialize + DeserializeOwned,
+        SC: StarkUtils + Serialize + DeserializeOwned,
         SC::Challenger: Clone,
-        OpeningProof<SC>: Send + Sync,
-        <SC::Pcs as Pcs<SC::Val, RowMajorMatrix<SC::Val>>>::Commitment: Send + Sync,
-        <SC::Pcs as Pcs<SC::Val, RowMajorMatrix<SC::Val>>>::ProverData: Send + Sync,
-        ShardMainData<SC>: Serialize + DeserializeOwned,
-        <SC as StarkGenericConfig>::Val: p3_field::PrimeField32,
     {
+        check_stdin(proof)?;
         let mut challenger = config.challenger();
         let machine = RiscvStark::new(config);
 
         let (_, vk) = machine.setup(&Program::from(elf));
         machine.verify(&vk, &proof.proof, &mut challenger)
     }
+
+    /// The verifying key of the ELF, with which its proofs are verified by
+    /// [`SP1Verifier::verify_with_key`] without the ELF.
+    pub fn verifying_key(elf: &[u8]) -> VerifyingKey<BabyBearBlake3> {
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        machine.setup(&Program::from(elf)).1
+    }
+
+    /// Verify a proof generated by `SP1Prover` against the verifying key of its program, which
+    /// neither parses the program nor executes anything.
+    pub fn verify_with_key(
+        vk: &VerifyingKey<BabyBearBlake3>,
+        proof: &SP1ProofWithIO<BabyBearBlake3>,
+    ) -> Result<u64, ProgramVerificationError> {
+        check_stdin(proof)?;
+        let config = BabyBearBlake3::new();
+        let mut challenger = config.challenger();
+        stark::verify_proof(config, vk, &proof.proof, &mut challenger)
+    }
+}
+
+impl<SC: StarkGenericConfig + Serialize + DeserializeOwned> Borrow<Proof<SC>>
+    for SP1ProofWithIO<SC>
+{
+    fn borrow(&self) -> &Proof<SC> {
+        &self.proof
+    }
 }
 
 impl<SC: StarkGenericConfig + Serialize + DeserializeOwned> SP1ProofWithIO<SC> {
     /// Saves the proof as a JSON to the given path.
     pub fn save(&self, path: &str) -> Result<()> {
-        let data = serde_json::to_string(self).unwrap();
-        fs::write(path, data).unwrap();
+        self.save_as(path, ProofFormat::Json)
+    }
+
+    /// Saves the proof in the given format to the given path.
+    pub fn save_as(&self, path: &str, format: ProofFormat) -> Result<()> {
+        fs::write(path, encode_proof_file(self, format))?;
         Ok(())
     }
+
+    /// Loads a proof saved in either format, detected from the first bytes of the file.
+    pub fn load(path: &str) -> Result<Self> {
+        Ok(decode_proof_file(&fs::read(path)?)?)
+    }
+
+    /// UNVERIFIED: the bytes the program committed with `sp1_zkvm::io::commit_values`, as claimed
+    /// by the prover. The proof does not constrain them to the ones of the execution.
+    pub fn claimed_values(&self) -> &[u8] {
+        &self.proof.claimed_values
+    }
 }
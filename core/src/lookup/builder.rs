@@ -1,34 +1,56 @@
-use crate::air::{AirInteraction, MessageBuilder};
-use p3_air::{AirBuilder, PairCol, VirtualPairCol};
-use p3_field::Field;
+use crate::air::{AirInteraction, MessageBuilder, PublicValuesBuilder, NUM_PUBLIC_VALUES};
+use p3_air::{AirBuilder, PairBuilder, PairCol, VirtualPairCol};
+use p3_field::{AbstractField, Field};
 use p3_matrix::dense::RowMajorMatrix;
 use p3_uni_stark::{SymbolicExpression, SymbolicVariable};
 
 use super::Interaction;
 
 /// A builder for the lookup table interactions.
+///
+/// The preprocessed columns are numbered after the main ones, so that an interaction can tell
+/// them apart.
 pub struct InteractionBuilder<F: Field> {
+    preprocessed: RowMajorMatrix<SymbolicVariable<F>>,
     main: RowMajorMatrix<SymbolicVariable<F>>,
     sends: Vec<Interaction<F>>,
     receives: Vec<Interaction<F>>,
+    /// The interactions do not read the public values, which only appear in constraints, so the
+    /// builder holds zeros in their place.
+    public_values: Vec<F>,
+}
+
+/// A matrix of the local and next rows of symbolic variables for the columns in the range.
+fn symbolic_rows<F: Field>(columns: std::ops::Range<usize>) -> RowMajorMatrix<SymbolicVariable<F>> {
+    let width = columns.len();
+    let values = [false, true]
+        .into_iter()
+        .flat_map(|is_next| {
+            columns
+                .clone()
+                .map(move |column| SymbolicVariable::new(is_next, column))
+        })
+        .collect();
+    RowMajorMatrix::new(values, width)
 }
 
 impl<F: Field> InteractionBuilder<F> {
-    /// Creates a new `InteractionBuilder` with the given width.
-    pub fn new(width: usize) -> Self {
-        let values = [false, true]
-            .into_iter()
-            .flat_map(|is_next| {
-                (0..width).map(move |column| SymbolicVariable::new(is_next, column))
-            })
-            .collect();
+    /// Creates a new `InteractionBuilder` with the given preprocessed and main widths.
+    pub fn new(preprocessed_width: usize, width: usize) -> Self {
         Self {
-            main: RowMajorMatrix::new(values, width),
+            preprocessed: symbolic_rows(width..width + preprocessed_width),
+            main: symbolic_rows(0..width),
             sends: vec![],
             receives: vec![],
+            public_values: vec![F::zero(); NUM_PUBLIC_VALUES],
         }
     }
 
+    /// The width of the main trace, after which the preprocessed columns are numbered.
+    fn main_width(&self) -> usize {
+        self.main.width
+    }
+
     /// Returns the sends and receives.
     pub fn interactions(self) -> (Vec<Interaction<F>>, Vec<Interaction<F>>) {
         (self.sends, self.receives)
@@ -64,72 +86,101 @@ impl<F: Field> AirBuilder for InteractionBuilder<F> {
     fn assert_zero<I: Into<Self::Expr>>(&mut self, _x: I) {}
 }
 
+impl<F: Field> PairBuilder for InteractionBuilder<F> {
+    fn preprocessed(&self) -> Self::M {
+        self.preprocessed.clone()
+    }
+}
+
+impl<F: Field> PublicValuesBuilder for InteractionBuilder<F> {
+    type PublicVar = Self::F;
+
+    const STANDALONE: bool = false;
+
+    fn public_values(&self) -> &[Self::PublicVar] {
+        &self.public_values
+    }
+}
+
 impl<F: Field> MessageBuilder<AirInteraction<SymbolicExpression<F>>> for InteractionBuilder<F> {
     fn send(&mut self, message: AirInteraction<SymbolicExpression<F>>) {
+        let width = self.main_width();
         let values = message
             .values
             .into_iter()
-            .map(|v| symbolic_to_virtual_pair(&v))
+            .map(|v| symbolic_to_virtual_pair(&v, width))
             .collect::<Vec<_>>();
 
-        let multiplicity = symbolic_to_virtual_pair(&message.multiplicity);
+        let multiplicity = symbolic_to_virtual_pair(&message.multiplicity, width);
 
         self.sends
             .push(Interaction::new(values, multiplicity, message.kind));
     }
 
     fn receive(&mut self, message: AirInteraction<SymbolicExpression<F>>) {
+        let width = self.main_width();
         let values = message
             .values
             .into_iter()
-            .map(|v| symbolic_to_virtual_pair(&v))
+            .map(|v| symbolic_to_virtual_pair(&v, width))
             .collect::<Vec<_>>();
 
-        let multiplicity = symbolic_to_virtual_pair(&message.multiplicity);
+        let multiplicity = symbolic_to_virtual_pair(&message.multiplicity, width);
 
         self.receives
             .push(Interaction::new(values, multiplicity, message.kind));
     }
 }
 
-fn symbolic_to_virtual_pair<F: Field>(expression: &SymbolicExpression<F>) -> VirtualPairCol<F> {
+fn symbolic_to_virtual_pair<F: Field>(
+    expression: &SymbolicExpression<F>,
+    main_width: usize,
+) -> VirtualPairCol<F> {
     if expression.degree_multiple() > 1 {
         panic!("degree multiple is too high");
     }
 
-    let (column_weights, constant) = eval_symbolic_to_virtual_pair(expression);
+    let (column_weights, constant) = eval_symbolic_to_virtual_pair(expression, main_width);
 
     let column_weights = column_weights.into_iter().collect();
 
     VirtualPairCol::new(column_weights, constant)
 }
 
+/// Splits an affine expression into its column weights and constant, the columns from
+/// `main_width` on being the preprocessed ones.
 fn eval_symbolic_to_virtual_pair<F: Field>(
     expression: &SymbolicExpression<F>,
+    main_width: usize,
 ) -> (Vec<(PairCol, F)>, F) {
     match expression {
         SymbolicExpression::Constant(c) => (vec![], *c),
         SymbolicExpression::Variable(v) if !v.is_next => {
-            (vec![(PairCol::Main(v.column), F::one())], F::zero())
+            let column = if v.column < main_width {
+                PairCol::Main(v.column)
+            } else {
+                PairCol::Preprocessed(v.column - main_width)
+            };
+            (vec![(column, F::one())], F::zero())
         }
         SymbolicExpression::Add { x, y, .. } => {
-            let (v_l, c_l) = eval_symbolic_to_virtual_pair(x);
-            let (v_r, c_r) = eval_symbolic_to_virtual_pair(y);
+            let (v_l, c_l) = eval_symbolic_to_virtual_pair(x, main_width);
+            let (v_r, c_r) = eval_symbolic_to_virtual_pair(y, main_width);
             ([v_l, v_r].concat(), c_l + c_r)
         }
         SymbolicExpression::Sub { x, y, .. } => {
-            let (v_l, c_l) = eval_symbolic_to_virtual_pair(x);
-            let (v_r, c_r) = eval_symbolic_to_virtual_pair(y);
+            let (v_l, c_l) = eval_symbolic_to_virtual_pair(x, main_width);
+            let (v_r, c_r) = eval_symbolic_to_virtual_pair(y, main_width);
             let neg_v_r = v_r.iter().map(|(c, w)| (*c, -*w)).collect();
             ([v_l, neg_v_r].concat(), c_l - c_r)
         }
         SymbolicExpression::Neg { x, .. } => {
-            let (v, c) = eval_symbolic_to_virtual_pair(x);
+            let (v, c) = eval_symbolic_to_virtual_pair(x, main_width);
             (v.iter().map(|(c, w)| (*c, -*w)).collect(), -c)
         }
         SymbolicExpression::Mul { x, y, .. } => {
-            let (v_l, c_l) = eval_symbolic_to_virtual_pair(x);
-            let (v_r, c_r) = eval_symbolic_to_virtual_pair(y);
+            let (v_l, c_l) = eval_symbolic_to_virtual_pair(x, main_width);
+            let (v_r, c_r) = eval_symbolic_to_virtual_pair(y, main_width);
 
             let mut v = vec![];
             v.extend(v_l.iter().map(|(c, w)| (*c, *w * c_r)));
@@ -163,10 +214,11 @@ mod tests {
     use p3_baby_bear::BabyBear;
     use p3_field::{AbstractField, Field};
     use p3_matrix::MatrixRowSlices;
+    use rand::{thread_rng, Rng};
 
     use super::*;
     use crate::{
-        air::SP1AirBuilder,
+        air::{SP1AirBuilder, Word},
         lookup::{InteractionBuilder, InteractionKind},
     };
 
@@ -180,7 +232,7 @@ mod tests {
 
         let z = x + y;
 
-        let (column_weights, constant) = super::eval_symbolic_to_virtual_pair(&z);
+        let (column_weights, constant) = super::eval_symbolic_to_virtual_pair(&z, 2);
         println!("column_weights: {:?}", column_weights);
         println!("constant: {:?}", constant);
 
@@ -235,7 +287,7 @@ mod tests {
     fn test_lookup_interactions() {
         let air = LookupTestAir {};
 
-        let mut builder = InteractionBuilder::<BabyBear>::new(NUM_COLS);
+        let mut builder = InteractionBuilder::<BabyBear>::new(0, NUM_COLS);
 
         air.eval(&mut builder);
 
@@ -282,4 +334,90 @@ mod tests {
             println!(", multiplicity: {:?}", multiplicity);
         }
     }
+
+    /// Sends or receives an ALU and a memory message laid out from the same columns.
+    pub struct WordMessageAir {
+        receive: bool,
+    }
+
+    const WORD_MESSAGE_COLS: usize = 15;
+
+    impl<F: Field> BaseAir<F> for WordMessageAir {
+        fn width(&self) -> usize {
+            WORD_MESSAGE_COLS
+        }
+    }
+
+    impl<AB: SP1AirBuilder> Air<AB> for WordMessageAir {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let local = main.row_slice(0);
+            let word = |i: usize| Word([local[i], local[i + 1], local[i + 2], local[i + 3]]);
+            let (a, b, c) = (word(1), word(5), word(9));
+            if self.receive {
+                builder.receive_alu(local[0], a, b, c, local[13]);
+                builder.receive_values(
+                    InteractionKind::Memory,
+                    ([local[13], local[14], local[0]], a),
+                    local[13],
+                );
+            } else {
+                builder.send_alu(local[0], a, b, c, local[13]);
+                builder.send_values(
+                    InteractionKind::Memory,
+                    ([local[13], local[14], local[0]], a),
+                    local[13],
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_word_interactions_align() {
+        let interactions = |receive: bool| {
+            let mut builder = InteractionBuilder::<BabyBear>::new(0, WORD_MESSAGE_COLS);
+            WordMessageAir { receive }.eval(&mut builder);
+            let (sends, receives) = builder.interactions();
+            if receive {
+                assert!(sends.is_empty());
+                receives
+            } else {
+                assert!(receives.is_empty());
+                sends
+            }
+        };
+        let (sends, receives) = (interactions(false), interactions(true));
+        assert_eq!(sends.len(), receives.len());
+
+        let mut rng = thread_rng();
+        let row = (0..WORD_MESSAGE_COLS)
+            .map(|_| BabyBear::from_canonical_u32(rng.gen_range(0..1 << 30)))
+            .collect::<Vec<_>>();
+        for (send, receive) in sends.iter().zip(receives.iter()) {
+            assert_eq!(send.kind, receive.kind);
+            assert_eq!(send.values.len(), receive.values.len());
+            for (i, (sent, received)) in send.values.iter().zip(receive.values.iter()).enumerate() {
+                assert_eq!(
+                    sent.apply::<BabyBear, BabyBear>(&[], &row),
+                    received.apply::<BabyBear, BabyBear>(&[], &row),
+                    "{} value {}",
+                    send.kind,
+                    i
+                );
+            }
+        }
+
+        // The word limbs follow the opcode from the least significant, in the order of the words.
+        let alu = &sends[0];
+        assert_eq!(alu.values.len(), 13);
+        for (i, value) in alu.values.iter().enumerate() {
+            assert_eq!(value.apply::<BabyBear, BabyBear>(&[], &row), row[i]);
+        }
+        let memory = &sends[1];
+        assert_eq!(memory.values.len(), 7);
+        assert_eq!(
+            memory.values[3].apply::<BabyBear, BabyBear>(&[], &row),
+            row[1]
+        );
+    }
 }
@@ -1,13 +1,14 @@
+use core::fmt::{Display, Formatter};
 use std::collections::BTreeMap;
 
 use p3_baby_bear::BabyBear;
 use p3_field::AbstractField;
 use p3_field::{Field, PrimeField64};
-use p3_matrix::Matrix;
+use p3_matrix::{Matrix, MatrixRowSlices};
 
 use crate::air::MachineAir;
 use crate::runtime::ExecutionRecord;
-use crate::stark::{RiscvChip, StarkGenericConfig};
+use crate::stark::{Chip, StarkGenericConfig};
 
 use super::InteractionKind;
 
@@ -18,9 +19,37 @@ pub struct InteractionData<F: Field> {
     pub row: usize,
     pub interaction_number: usize,
     pub is_send: bool,
+    pub values: Vec<F>,
     pub multiplicity: F,
 }
 
+/// An interaction whose sends and receives don't cancel out over the chips.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnbalancedInteraction<F: Field> {
+    pub kind: InteractionKind,
+    pub values: Vec<F>,
+    /// The multiplicity sent minus the multiplicity received.
+    pub multiplicity: F,
+    /// The chip of the first occurrence of the interaction, in the order of the chips.
+    pub chip_name: String,
+    /// The row of the first occurrence of the interaction in the main trace of its chip.
+    pub row: usize,
+}
+
+impl Display for UnbalancedInteraction<BabyBear> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{} {}: send-receive discrepancy {}, first in {} row {}",
+            self.kind,
+            vec_to_string(self.values.clone()),
+            babybear_to_int(self.multiplicity),
+            self.chip_name,
+            self.row
+        )
+    }
+}
+
 pub fn vec_to_string<F: Field>(vec: Vec<F>) -> String {
     let mut result = String::from("(");
     for (i, value) in vec.iter().enumerate() {
@@ -44,7 +73,7 @@ fn babybear_to_int(n: BabyBear) -> i32 {
 }
 
 pub fn debug_interactions<SC: StarkGenericConfig>(
-    chip: &RiscvChip<SC>,
+    chip: &Chip<SC::Val, impl MachineAir<SC::Val>>,
     record: &ExecutionRecord,
     interaction_kinds: Vec<InteractionKind>,
 ) -> (
@@ -55,6 +84,7 @@ pub fn debug_interactions<SC: StarkGenericConfig>(
     let mut key_to_count = BTreeMap::new();
 
     let trace = chip.generate_trace(record, &mut ExecutionRecord::default());
+    let preprocessed = chip.generate_preprocessed_trace(&record.program);
     let mut main = trace.clone();
     let height = trace.clone().height();
 
@@ -70,18 +100,21 @@ pub fn debug_interactions<SC: StarkGenericConfig>(
                 continue;
             }
             let is_send = m < nb_send_interactions;
-            let multiplicity_eval: SC::Val = interaction.multiplicity.apply(&[], main.row_mut(row));
+            let preprocessed_row = preprocessed.as_ref().map_or(&[][..], |p| p.row_slice(row));
+            let multiplicity_eval: SC::Val = interaction
+                .multiplicity
+                .apply(preprocessed_row, main.row_mut(row));
 
             if !multiplicity_eval.is_zero() {
                 let mut values = vec![];
                 for value in &interaction.values {
-                    let expr: SC::Val = value.apply(&[], main.row_mut(row));
+                    let expr: SC::Val = value.apply(preprocessed_row, main.row_mut(row));
                     values.push(expr);
                 }
                 let key = format!(
                     "{} {}",
                     &interaction.kind.to_string(),
-                    vec_to_string(values)
+                    vec_to_string(values.clone())
                 );
                 key_to_vec_data
                     .entry(key.clone())
@@ -92,6 +125,7 @@ pub fn debug_interactions<SC: StarkGenericConfig>(
                         row,
                         interaction_number: m,
                         is_send,
+                        values,
                         multiplicity: multiplicity_eval,
                     });
                 let current = key_to_count.entry(key.clone()).or_insert(SC::Val::zero());
@@ -107,10 +141,47 @@ pub fn debug_interactions<SC: StarkGenericConfig>(
     (key_to_vec_data, key_to_count)
 }
 
+/// Evaluates the interactions of the given kinds over the main trace of every chip, and returns the
+/// ones whose sends and receives don't cancel out, with where each first occurs.
+pub fn unbalanced_interactions<SC: StarkGenericConfig>(
+    chips: &[Chip<SC::Val, impl MachineAir<SC::Val>>],
+    record: &ExecutionRecord,
+    interaction_kinds: Vec<InteractionKind>,
+) -> Vec<UnbalancedInteraction<SC::Val>> {
+    let mut interactions = BTreeMap::new();
+    for chip in chips.iter() {
+        let (data, _) = debug_interactions::<SC>(chip, record, interaction_kinds.clone());
+        // The occurrences of a key in a chip are in the order of the rows.
+        for (key, occurrences) in data {
+            for occurrence in occurrences {
+                let interaction =
+                    interactions
+                        .entry(key.clone())
+                        .or_insert_with(|| UnbalancedInteraction {
+                            kind: occurrence.kind,
+                            values: occurrence.values.clone(),
+                            multiplicity: SC::Val::zero(),
+                            chip_name: occurrence.chip_name.clone(),
+                            row: occurrence.row,
+                        });
+                if occurrence.is_send {
+                    interaction.multiplicity += occurrence.multiplicity;
+                } else {
+                    interaction.multiplicity -= occurrence.multiplicity;
+                }
+            }
+        }
+    }
+    interactions
+        .into_values()
+        .filter(|interaction| !interaction.multiplicity.is_zero())
+        .collect()
+}
+
 /// Calculate the the number of times we send and receive each event of the given interaction type,
 /// and print out the ones for which the set of sends and receives don't match.
 pub fn debug_interactions_with_all_chips<SC: StarkGenericConfig<Val = BabyBear>>(
-    chips: &[RiscvChip<SC>],
+    chips: &[Chip<SC::Val, impl MachineAir<SC::Val>>],
     segment: &ExecutionRecord,
     interaction_kinds: Vec<InteractionKind>,
 ) -> bool {
@@ -161,3 +232,59 @@ pub fn debug_interactions_with_all_chips<SC: StarkGenericConfig<Val = BabyBear>>
 
     !any_nonzero
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::air::Word;
+    use crate::runtime::tests::simple_program;
+    use crate::runtime::Runtime;
+    use crate::stark::RiscvStark;
+    use crate::utils::BabyBearBlake3;
+
+    #[test]
+    fn test_unbalanced_memory_record() {
+        let mut runtime = Runtime::new(simple_program());
+        runtime.run();
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        assert!(unbalanced_interactions::<BabyBearBlake3>(
+            machine.chips(),
+            &runtime.record,
+            InteractionKind::all_kinds(),
+        )
+        .is_empty());
+
+        // The final value of a word of memory differs from the last value written to it.
+        let mut record = runtime.record;
+        let row = record
+            .last_memory_record
+            .iter()
+            .position(|(_, _, multiplicity)| *multiplicity != 0)
+            .unwrap();
+        let (addr, memory_record, multiplicity) = &mut record.last_memory_record[row];
+        memory_record.value = memory_record.value.wrapping_add(1);
+        let (addr, value, multiplicity) = (*addr, memory_record.value, *multiplicity);
+
+        let unbalanced = unbalanced_interactions::<BabyBearBlake3>(
+            machine.chips(),
+            &record,
+            InteractionKind::all_kinds(),
+        );
+        assert!(!unbalanced.is_empty());
+        assert!(unbalanced
+            .iter()
+            .all(|interaction| interaction.kind == InteractionKind::Memory));
+        let finalize = unbalanced
+            .iter()
+            .find(|interaction| interaction.chip_name == "MemoryFinalize")
+            .unwrap();
+        assert_eq!(finalize.row, row);
+        assert_eq!(
+            finalize.multiplicity,
+            BabyBear::from_canonical_u32(multiplicity)
+        );
+        assert_eq!(finalize.values[2], BabyBear::from_canonical_u32(addr));
+        assert_eq!(finalize.values[3..], Word::<BabyBear>::from(value).0);
+        assert!(finalize.to_string().starts_with("Memory ("));
+    }
+}
@@ -14,6 +14,9 @@ pub struct Interaction<F: Field> {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum InteractionKind {
     /// Interaction with the memory table, such as read and write.
+    ///
+    /// Reads and writes are not different types of messages: the access which reads a value sends
+    /// the very message the access which wrote it received, so they are not tagged.
     Memory = 1,
 
     /// Interaction with the program table, loading an instruction at a given pc address.
@@ -33,6 +36,12 @@ pub enum InteractionKind {
 
     /// Interaction with the field op table for field operations.
     Field = 7,
+
+    /// Hand-off of the state of a syscall from one of its events to the next.
+    Continuation = 8,
+
+    /// Hand-off of a syscall from the `ECALL` which invokes it to the chip of its precompile.
+    Syscall = 9,
 }
 
 impl InteractionKind {
@@ -45,10 +54,26 @@ impl InteractionKind {
             InteractionKind::Byte,
             InteractionKind::Range,
             InteractionKind::Field,
+            InteractionKind::Continuation,
+            InteractionKind::Syscall,
         ]
     }
 }
 
+/// The tags leading the values of the kinds whose argument carries several types of messages, so
+/// that messages of different types never balance each other.
+///
+/// `Alu` and `Byte` messages are led by their opcode, which plays the same role.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum InteractionTag {
+    /// A comparison `a = b < c` of field elements, under `InteractionKind::Field`.
+    FieldLtu = 1,
+
+    /// The state of a `MEMCPY` handed from one of its events to the next, under
+    /// `InteractionKind::Continuation`.
+    MemcpyContinuation = 2,
+}
+
 impl<F: Field> Interaction<F> {
     /// Create a new interaction.
     pub fn new(
@@ -88,6 +113,8 @@ impl Display for InteractionKind {
             InteractionKind::Byte => write!(f, "Byte"),
             InteractionKind::Range => write!(f, "Range"),
             InteractionKind::Field => write!(f, "Field"),
+            InteractionKind::Continuation => write!(f, "Continuation"),
+            InteractionKind::Syscall => write!(f, "Syscall"),
         }
     }
 }
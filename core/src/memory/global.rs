@@ -1,6 +1,7 @@
-use crate::air::MachineAir;
-use crate::air::{AirInteraction, SP1AirBuilder, Word};
-use crate::utils::pad_to_power_of_two;
+use crate::air::{EventKind, MachineAir};
+use crate::air::{SP1AirBuilder, Word};
+use crate::lookup::InteractionKind;
+use crate::utils::zero_trace;
 use p3_field::PrimeField;
 use p3_matrix::dense::RowMajorMatrix;
 
@@ -9,7 +10,6 @@ use core::borrow::{Borrow, BorrowMut};
 use core::mem::{size_of, transmute};
 use p3_air::Air;
 use p3_air::BaseAir;
-use p3_field::AbstractField;
 use p3_matrix::MatrixRowSlices;
 use p3_util::indices_arr;
 use sp1_derive::AlignedBorrow;
@@ -18,7 +18,6 @@ use sp1_derive::AlignedBorrow;
 pub enum MemoryChipKind {
     Init,
     Finalize,
-    Program,
 }
 
 pub struct MemoryGlobalChip {
@@ -42,42 +41,59 @@ impl<F: PrimeField> MachineAir<F> for MemoryGlobalChip {
         match self.kind {
             MemoryChipKind::Init => "MemoryInit".to_string(),
             MemoryChipKind::Finalize => "MemoryFinalize".to_string(),
-            MemoryChipKind::Program => "MemoryProgram".to_string(),
+        }
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::Memory]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        match self.kind {
+            MemoryChipKind::Init => shard.first_memory_record.len(),
+            MemoryChipKind::Finalize => shard.last_memory_record.len(),
         }
     }
 
     fn generate_trace(
         &self,
         input: &ExecutionRecord,
-        _output: &mut ExecutionRecord,
+        output: &mut ExecutionRecord,
     ) -> RowMajorMatrix<F> {
+        let mut trace = RowMajorMatrix::new(Vec::new(), NUM_MEMORY_INIT_COLS);
+        self.generate_trace_into(input, output, &mut trace);
+        trace
+    }
+
+    fn generate_trace_into(
+        &self,
+        input: &ExecutionRecord,
+        _output: &mut ExecutionRecord,
+        trace: &mut RowMajorMatrix<F>,
+    ) {
         let memory_record = match self.kind {
             MemoryChipKind::Init => &input.first_memory_record,
             MemoryChipKind::Finalize => &input.last_memory_record,
-            MemoryChipKind::Program => &input.program_memory_record,
         };
-        let rows: Vec<[F; 8]> = (0..memory_record.len()) // TODO: change this back to par_iter
-            .map(|i| {
-                let (addr, record, multiplicity) = memory_record[i];
-                let mut row = [F::zero(); NUM_MEMORY_INIT_COLS];
-                let cols: &mut MemoryInitCols<F> = row.as_mut_slice().borrow_mut();
-                cols.addr = F::from_canonical_u32(addr);
-                cols.shard = F::from_canonical_u32(record.shard);
-                cols.timestamp = F::from_canonical_u32(record.timestamp);
-                cols.value = record.value.into();
-                cols.is_real = F::from_canonical_u32(multiplicity);
-                row
-            })
-            .collect::<Vec<_>>();
-
-        let mut trace = RowMajorMatrix::new(
-            rows.into_iter().flatten().collect::<Vec<_>>(),
-            NUM_MEMORY_INIT_COLS,
-        );
-
-        pad_to_power_of_two::<NUM_MEMORY_INIT_COLS, F>(&mut trace.values);
-
-        trace
+        // Padded like `pad_to_power_of_two`, with at least 8 rows.
+        let height = match memory_record.len() {
+            0 | 1 => 8,
+            n => n.next_power_of_two(),
+        };
+        zero_trace(trace, NUM_MEMORY_INIT_COLS, height);
+
+        for (row, &(addr, record, multiplicity)) in trace
+            .values
+            .chunks_exact_mut(NUM_MEMORY_INIT_COLS)
+            .zip(memory_record.iter())
+        {
+            let cols: &mut MemoryInitCols<F> = row.borrow_mut();
+            cols.addr = F::from_canonical_u32(addr);
+            cols.shard = F::from_canonical_u32(record.shard);
+            cols.timestamp = F::from_canonical_u32(record.timestamp);
+            cols.value = record.value.into();
+            cols.is_real = F::from_canonical_u32(multiplicity);
+        }
     }
 }
 
@@ -114,26 +130,20 @@ where
             local.is_real * local.is_real * local.is_real,
         );
 
-        if self.kind == MemoryChipKind::Init || self.kind == MemoryChipKind::Program {
-            let mut values = vec![AB::Expr::zero(), AB::Expr::zero(), local.addr.into()];
-            values.extend(local.value.map(Into::into));
-            builder.receive(AirInteraction::new(
-                values,
-                local.is_real.into(),
-                crate::lookup::InteractionKind::Memory,
-            ));
+        if self.kind == MemoryChipKind::Init {
+            // A whole execution initializes its memory at shard 0 and clock 0, while a range of
+            // shards initializes it with the state left by the shards before it.
+            builder.receive_values(
+                InteractionKind::Memory,
+                ([local.shard, local.timestamp, local.addr], local.value),
+                local.is_real,
+            );
         } else {
-            let mut values = vec![
-                local.shard.into(),
-                local.timestamp.into(),
-                local.addr.into(),
-            ];
-            values.extend(local.value.map(Into::into));
-            builder.send(AirInteraction::new(
-                values,
-                local.is_real.into(),
-                crate::lookup::InteractionKind::Memory,
-            ));
+            builder.send_values(
+                InteractionKind::Memory,
+                ([local.shard, local.timestamp, local.addr], local.value),
+                local.is_real,
+            );
         }
     }
 }
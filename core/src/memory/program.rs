@@ -0,0 +1,147 @@
+use crate::air::{EventKind, MachineAir};
+use crate::air::{SP1AirBuilder, Word};
+use crate::lookup::InteractionKind;
+use crate::utils::pad_to_power_of_two;
+use p3_field::PrimeField;
+use p3_matrix::dense::RowMajorMatrix;
+
+use crate::runtime::{ExecutionRecord, Program};
+use core::borrow::{Borrow, BorrowMut};
+use core::mem::size_of;
+use p3_air::BaseAir;
+use p3_air::{Air, PairBuilder};
+use p3_field::AbstractField;
+use p3_matrix::MatrixRowSlices;
+use sp1_derive::AlignedBorrow;
+
+/// The columns of the program memory chip which only depend on the program.
+#[derive(AlignedBorrow, Default, Debug, Clone, Copy)]
+#[repr(C)]
+pub struct MemoryProgramPreprocessedCols<T> {
+    pub addr: T,
+    pub value: Word<T>,
+}
+
+/// The columns of the main trace of the program memory chip.
+#[derive(AlignedBorrow, Default, Debug, Clone, Copy)]
+#[repr(C)]
+pub struct MemoryProgramMultCols<T> {
+    /// Whether the word of the row is used by the execution.
+    pub multiplicity: T,
+}
+
+pub(crate) const NUM_MEMORY_PROGRAM_PREPROCESSED_COLS: usize =
+    size_of::<MemoryProgramPreprocessedCols<u8>>();
+pub(crate) const NUM_MEMORY_PROGRAM_MULT_COLS: usize = size_of::<MemoryProgramMultCols<u8>>();
+
+/// A chip initializing the memory with the memory image of the program.
+///
+/// The addresses and values are a preprocessed trace, with a row for every word of the image in
+/// address order, so that a proof cannot initialize the memory with another image.
+#[derive(Default)]
+pub struct MemoryProgramChip;
+
+impl MemoryProgramChip {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl<F> BaseAir<F> for MemoryProgramChip {
+    fn width(&self) -> usize {
+        NUM_MEMORY_PROGRAM_MULT_COLS
+    }
+}
+
+impl<F: PrimeField> MachineAir<F> for MemoryProgramChip {
+    fn name(&self) -> String {
+        "MemoryProgram".to_string()
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::Memory]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.program_memory_record.len()
+    }
+
+    fn preprocessed_width(&self) -> usize {
+        NUM_MEMORY_PROGRAM_PREPROCESSED_COLS
+    }
+
+    fn generate_preprocessed_trace(&self, program: &Program) -> Option<RowMajorMatrix<F>> {
+        // The address and value of every word of the memory image, ordered by address.
+        let mut values = program
+            .initial_memory()
+            .iter()
+            .flat_map(|(&addr, &value)| {
+                let mut row = [F::zero(); NUM_MEMORY_PROGRAM_PREPROCESSED_COLS];
+                let cols: &mut MemoryProgramPreprocessedCols<F> = row.as_mut_slice().borrow_mut();
+                cols.addr = F::from_canonical_u32(addr);
+                cols.value = value.into();
+                row
+            })
+            .collect::<Vec<_>>();
+        pad_to_power_of_two::<NUM_MEMORY_PROGRAM_PREPROCESSED_COLS, F>(&mut values);
+        Some(RowMajorMatrix::new(
+            values,
+            NUM_MEMORY_PROGRAM_PREPROCESSED_COLS,
+        ))
+    }
+
+    fn generate_trace(
+        &self,
+        input: &ExecutionRecord,
+        _output: &mut ExecutionRecord,
+    ) -> RowMajorMatrix<F> {
+        // The rows are in the order of the preprocessed trace, since the record holds every word
+        // of the memory image, sorted by address.
+        let mut values = input
+            .program
+            .initial_memory()
+            .keys()
+            .zip(input.program_memory_record.iter())
+            .flat_map(|(&addr, &(record_addr, _, multiplicity))| {
+                assert_eq!(
+                    addr, record_addr,
+                    "the program memory record is not the image"
+                );
+                let mut row = [F::zero(); NUM_MEMORY_PROGRAM_MULT_COLS];
+                let cols: &mut MemoryProgramMultCols<F> = row.as_mut_slice().borrow_mut();
+                cols.multiplicity = F::from_canonical_u32(multiplicity);
+                row
+            })
+            .collect::<Vec<_>>();
+        pad_to_power_of_two::<NUM_MEMORY_PROGRAM_MULT_COLS, F>(&mut values);
+        RowMajorMatrix::new(values, NUM_MEMORY_PROGRAM_MULT_COLS)
+    }
+}
+
+impl<AB> Air<AB> for MemoryProgramChip
+where
+    AB: SP1AirBuilder + PairBuilder,
+{
+    fn eval(&self, builder: &mut AB) {
+        let preprocessed = builder.preprocessed();
+        let prep_local: &MemoryProgramPreprocessedCols<AB::Var> =
+            preprocessed.row_slice(0).borrow();
+        let main = builder.main();
+        let mult_local: &MemoryProgramMultCols<AB::Var> = main.row_slice(0).borrow();
+
+        // Dummy constraint of degree 3.
+        builder.assert_eq(
+            mult_local.multiplicity * mult_local.multiplicity * mult_local.multiplicity,
+            mult_local.multiplicity * mult_local.multiplicity * mult_local.multiplicity,
+        );
+
+        builder.receive_values(
+            InteractionKind::Memory,
+            (
+                [AB::Expr::zero(), AB::Expr::zero(), prep_local.addr.into()],
+                prep_local.value,
+            ),
+            mult_local.multiplicity,
+        );
+    }
+}
@@ -5,6 +5,7 @@ use p3_field::Field;
 use sp1_derive::AlignedBorrow;
 use std::mem::size_of;
 
+use crate::air::BoundedExpr;
 use crate::air::SP1AirBuilder;
 use crate::air::Word;
 
@@ -65,31 +66,34 @@ impl<F: Field> AddOperation<F> {
         cols: AddOperation<AB::Var>,
         is_real: AB::Var,
     ) {
-        let one = AB::Expr::one();
-        let base = AB::F::from_canonical_u32(256);
+        let one = BoundedExpr::<AB::Expr>::constant(1);
+        let base = BoundedExpr::<AB::Expr>::constant(256);
+        let byte = |x: AB::Var| BoundedExpr::<AB::Expr>::byte(x);
+        let carry = cols.carry.map(|x| BoundedExpr::<AB::Expr>::bool(x));
 
         let mut builder_is_real = builder.when(is_real);
 
         // For each limb, assert that difference between the carried result and the non-carried
         // result is either zero or the base.
-        let overflow_0 = a[0] + b[0] - cols.value[0];
-        let overflow_1 = a[1] + b[1] - cols.value[1] + cols.carry[0];
-        let overflow_2 = a[2] + b[2] - cols.value[2] + cols.carry[1];
-        let overflow_3 = a[3] + b[3] - cols.value[3] + cols.carry[2];
-        builder_is_real.assert_zero(overflow_0.clone() * (overflow_0.clone() - base));
-        builder_is_real.assert_zero(overflow_1.clone() * (overflow_1.clone() - base));
-        builder_is_real.assert_zero(overflow_2.clone() * (overflow_2.clone() - base));
-        builder_is_real.assert_zero(overflow_3.clone() * (overflow_3.clone() - base));
+        let overflow_0 = byte(a[0]) + byte(b[0]) - byte(cols.value[0]);
+        let overflow_1 = byte(a[1]) + byte(b[1]) - byte(cols.value[1]) + carry[0].clone();
+        let overflow_2 = byte(a[2]) + byte(b[2]) - byte(cols.value[2]) + carry[1].clone();
+        let overflow_3 = byte(a[3]) + byte(b[3]) - byte(cols.value[3]) + carry[2].clone();
+        let overflows = [overflow_0, overflow_1, overflow_2, overflow_3];
+        for overflow in overflows.iter() {
+            builder_is_real
+                .assert_bounded_zero(overflow.clone() * (overflow.clone() - base.clone()));
+        }
 
         // If the carry is one, then the overflow must be the base.
-        builder_is_real.assert_zero(cols.carry[0] * (overflow_0.clone() - base));
-        builder_is_real.assert_zero(cols.carry[1] * (overflow_1.clone() - base));
-        builder_is_real.assert_zero(cols.carry[2] * (overflow_2.clone() - base));
+        for (carry, overflow) in carry.iter().zip(overflows.iter()) {
+            builder_is_real.assert_bounded_zero(carry.clone() * (overflow.clone() - base.clone()));
+        }
 
         // If the carry is not one, then the overflow must be zero.
-        builder_is_real.assert_zero((cols.carry[0] - one.clone()) * overflow_0.clone());
-        builder_is_real.assert_zero((cols.carry[1] - one.clone()) * overflow_1.clone());
-        builder_is_real.assert_zero((cols.carry[2] - one.clone()) * overflow_2.clone());
+        for (carry, overflow) in carry.iter().zip(overflows.iter()) {
+            builder_is_real.assert_bounded_zero((carry.clone() - one.clone()) * overflow.clone());
+        }
 
         // Assert that the carry is either zero or one.
         builder_is_real.assert_bool(cols.carry[0]);
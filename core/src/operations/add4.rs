@@ -7,6 +7,7 @@ use p3_field::Field;
 use sp1_derive::AlignedBorrow;
 use std::mem::size_of;
 
+use crate::air::BoundedExpr;
 use crate::air::SP1AirBuilder;
 use crate::air::Word;
 use crate::air::WORD_SIZE;
@@ -138,15 +139,17 @@ impl<F: Field> Add4Operation<F> {
 
         // Compare the sum and summands by looking at carry.
         {
-            let base = AB::F::from_canonical_u32(256);
+            let byte = |x: AB::Var| BoundedExpr::<AB::Expr>::byte(x);
+            let carry = |i: usize| BoundedExpr::<AB::Expr>::bits(cols.carry[i], 2);
             // For each limb, assert that difference between the carried result and the non-carried
             // result is the product of carry and base.
             for i in 0..WORD_SIZE {
-                let mut overflow = a[i] + b[i] + c[i] + d[i] - cols.value[i];
+                let mut overflow =
+                    byte(a[i]) + byte(b[i]) + byte(c[i]) + byte(d[i]) - byte(cols.value[i]);
                 if i > 0 {
-                    overflow += cols.carry[i - 1].into();
+                    overflow = overflow + carry(i - 1);
                 }
-                builder_is_real.assert_eq(cols.carry[i] * base, overflow.clone());
+                builder_is_real.assert_bounded_eq(carry(i) * 256, overflow);
             }
         }
 
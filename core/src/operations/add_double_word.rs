@@ -0,0 +1,213 @@
+use core::borrow::Borrow;
+use core::borrow::BorrowMut;
+use p3_air::AirBuilder;
+use p3_field::Field;
+use sp1_derive::AlignedBorrow;
+use std::mem::size_of;
+
+use crate::air::BoundedExpr;
+use crate::air::DoubleWord;
+use crate::air::SP1AirBuilder;
+use crate::air::WordAirBuilder;
+use crate::air::DOUBLE_WORD_SIZE;
+
+use crate::runtime::ExecutionRecord;
+
+/// A set of columns needed to compute the add of two double words.
+#[derive(AlignedBorrow, Default, Debug, Clone, Copy)]
+#[repr(C)]
+pub struct AddDoubleWordOperation<T> {
+    /// The result of `a + b`.
+    pub value: DoubleWord<T>,
+
+    /// The carry out of each limb but the last one.
+    pub carry: [T; DOUBLE_WORD_SIZE - 1],
+}
+
+impl<F: Field> AddDoubleWordOperation<F> {
+    pub fn populate(&mut self, record: &mut ExecutionRecord, a_u64: u64, b_u64: u64) -> u64 {
+        let expected = a_u64.wrapping_add(b_u64);
+        self.value = DoubleWord::from(expected);
+        let a = a_u64.to_le_bytes();
+        let b = b_u64.to_le_bytes();
+
+        let mut carry = 0u32;
+        for i in 0..DOUBLE_WORD_SIZE - 1 {
+            carry = (a[i] as u32 + b[i] as u32 + carry) >> 8;
+            self.carry[i] = F::from_canonical_u32(carry);
+        }
+
+        // Range check
+        {
+            record.add_u8_range_checks(&a);
+            record.add_u8_range_checks(&b);
+            record.add_u8_range_checks(&expected.to_le_bytes());
+        }
+        expected
+    }
+
+    pub fn eval<AB: SP1AirBuilder>(
+        builder: &mut AB,
+        a: DoubleWord<AB::Var>,
+        b: DoubleWord<AB::Var>,
+        cols: AddDoubleWordOperation<AB::Var>,
+        is_real: AB::Var,
+    ) {
+        let one = BoundedExpr::<AB::Expr>::constant(1);
+        let base = BoundedExpr::<AB::Expr>::constant(256);
+        let byte = |x: AB::Var| BoundedExpr::<AB::Expr>::byte(x);
+        let carry = cols.carry.map(|x| BoundedExpr::<AB::Expr>::bool(x));
+
+        let mut builder_is_real = builder.when(is_real);
+
+        // For each limb, assert that difference between the carried result and the non-carried
+        // result is either zero or the base.
+        let overflows: [_; DOUBLE_WORD_SIZE] = core::array::from_fn(|i| {
+            let overflow = byte(a[i]) + byte(b[i]) - byte(cols.value[i]);
+            match i {
+                0 => overflow,
+                _ => overflow + carry[i - 1].clone(),
+            }
+        });
+        for overflow in overflows.iter() {
+            builder_is_real
+                .assert_bounded_zero(overflow.clone() * (overflow.clone() - base.clone()));
+        }
+
+        // If the carry is one, then the overflow must be the base.
+        for (carry, overflow) in carry.iter().zip(overflows.iter()) {
+            builder_is_real.assert_bounded_zero(carry.clone() * (overflow.clone() - base.clone()));
+        }
+
+        // If the carry is not one, then the overflow must be zero.
+        for (carry, overflow) in carry.iter().zip(overflows.iter()) {
+            builder_is_real.assert_bounded_zero((carry.clone() - one.clone()) * overflow.clone());
+        }
+
+        // Assert that the carry is either zero or one.
+        for carry in cols.carry {
+            builder_is_real.assert_bool(carry);
+        }
+        builder_is_real.assert_bool(is_real);
+
+        // Range check each byte.
+        {
+            builder.range_check_double_word(a, is_real);
+            builder.range_check_double_word(b, is_real);
+            builder.range_check_double_word(cols.value, is_real);
+        }
+
+        // Degree 3 constraint to avoid "OodEvaluationMismatch".
+        builder.assert_zero(a[0] * b[0] * cols.value[0] - a[0] * b[0] * cols.value[0]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+    use p3_field::AbstractField;
+
+    use super::*;
+    use crate::air::Word;
+    use crate::utils::test_utils::GadgetBuilder;
+
+    /// Populates the add of `a` and `b` and returns the failures of its constraints, after
+    /// applying `tamper` to the columns.
+    fn eval_add(
+        a: u64,
+        b: u64,
+        tamper: impl FnOnce(&mut AddDoubleWordOperation<BabyBear>),
+    ) -> Vec<String> {
+        let mut cols = AddDoubleWordOperation::<BabyBear>::default();
+        let value = cols.populate(&mut ExecutionRecord::default(), a, b);
+        assert_eq!(value, a.wrapping_add(b));
+        assert_eq!(cols.value.to_u64(), value);
+        tamper(&mut cols);
+
+        let mut builder = GadgetBuilder::new(&[]);
+        AddDoubleWordOperation::<BabyBear>::eval(
+            &mut builder,
+            DoubleWord::from(a),
+            DoubleWord::from(b),
+            cols,
+            BabyBear::one(),
+        );
+        builder.failures().to_vec()
+    }
+
+    #[test]
+    fn test_add_double_word() {
+        let cases = [
+            (0, 0),
+            (1, 2),
+            (0xffff_ffff, 1),
+            (u64::MAX, 1),
+            (u64::MAX, u64::MAX),
+            (u64::MAX - 0xff, 0xff),
+            (0x0123_4567_89ab_cdef, 0xfedc_ba98_7654_3210),
+        ];
+        for (a, b) in cases {
+            assert_eq!(eval_add(a, b, |_| {}), Vec::<String>::new(), "{a} + {b}");
+        }
+    }
+
+    #[test]
+    fn test_add_double_word_carries() {
+        // Every limb carries and the last carry out of the double word is dropped.
+        let mut cols = AddDoubleWordOperation::<BabyBear>::default();
+        assert_eq!(
+            cols.populate(&mut ExecutionRecord::default(), u64::MAX, 1),
+            0
+        );
+        assert_eq!(cols.carry, [BabyBear::one(); DOUBLE_WORD_SIZE - 1]);
+
+        // The carry between the two words of the double word is constrained.
+        assert!(!eval_add(0xffff_ffff, 1, |cols| cols.carry[3] = BabyBear::zero()).is_empty());
+        assert!(!eval_add(u64::MAX, 1, |cols| cols.carry[6] = BabyBear::zero()).is_empty());
+        assert!(!eval_add(1, 1, |cols| cols.carry[0] = BabyBear::one()).is_empty());
+        // A carry which is not boolean.
+        assert!(!eval_add(u64::MAX, u64::MAX, |cols| cols.carry[2] += BabyBear::one()).is_empty());
+    }
+
+    #[test]
+    fn test_add_double_word_wrong_value() {
+        // The sum without the wrapping carry, and a sum off by one in the high word.
+        assert!(!eval_add(u64::MAX, 1, |cols| cols.value[7] = BabyBear::one()).is_empty());
+        assert!(!eval_add(u64::MAX, u64::MAX, |cols| {
+            let [lo, hi] = cols.value.to_words();
+            cols.value = DoubleWord::from_words(lo, Word::from(hi.to_u32() - 1));
+        })
+        .is_empty());
+    }
+
+    #[test]
+    fn test_range_check_double_word() {
+        let mut builder = GadgetBuilder::new(&[]);
+        builder.range_check_double_word(DoubleWord::<BabyBear>::from(u64::MAX), BabyBear::one());
+        assert!(builder.failures().is_empty());
+
+        let mut limbs = DoubleWord::<BabyBear>::from(u64::MAX);
+        limbs[5] = BabyBear::from_canonical_u32(256);
+        builder.range_check_double_word(limbs, BabyBear::one());
+        assert_eq!(builder.failures().len(), 1);
+
+        // Nothing is sent without multiplicity.
+        let mut builder = GadgetBuilder::new(&[]);
+        builder.range_check_double_word(limbs, BabyBear::zero());
+        assert!(builder.failures().is_empty());
+    }
+
+    #[test]
+    fn test_assert_double_word_eq() {
+        let value = DoubleWord::<BabyBear>::from(u64::MAX);
+        let mut builder = GadgetBuilder::new(&[]);
+        builder.assert_double_word_eq(value, value);
+        let [lo, hi] = builder.double_word_to_words(value);
+        builder.assert_word_eq(lo, Word::from(u32::MAX));
+        builder.assert_word_eq(hi, Word::from(u32::MAX));
+        assert!(builder.failures().is_empty());
+
+        builder.assert_double_word_eq(value, DoubleWord::from(u64::MAX - (1 << 32)));
+        assert_eq!(builder.failures().len(), 1);
+    }
+}
@@ -136,8 +136,8 @@ mod tests {
 
     use crate::utils::ec::edwards::ed25519::Ed25519BaseField;
     use crate::utils::ec::field::FieldParameters;
-    use crate::utils::{uni_stark_prove as prove, uni_stark_verify as verify};
-    use crate::utils::{BabyBearPoseidon2, StarkUtils};
+    use crate::utils::BabyBearPoseidon2;
+    use crate::utils::{prove_single_chip, verify_single_chip};
     use crate::{air::SP1AirBuilder, runtime::ExecutionRecord};
     use core::borrow::{Borrow, BorrowMut};
     use core::mem::size_of;
@@ -176,6 +176,10 @@ mod tests {
             "FieldDen".to_string()
         }
 
+        fn num_real_rows(&self, _: &ExecutionRecord) -> usize {
+            1 << 8
+        }
+
         fn generate_trace(
             &self,
             _: &ExecutionRecord,
@@ -259,8 +263,6 @@ mod tests {
     #[test]
     fn prove_babybear() {
         let config = BabyBearPoseidon2::new();
-        let mut challenger = config.challenger();
-
         let shard = ExecutionRecord::default();
 
         let chip: FieldDenChip<Ed25519BaseField> = FieldDenChip::new(true);
@@ -269,9 +271,8 @@ mod tests {
         // This it to test that the proof DOESN'T work if messed up.
         // let row = trace.row_mut(0);
         // row[0] = BabyBear::from_canonical_u8(0);
-        let proof = prove::<BabyBearPoseidon2, _>(&config, &chip, &mut challenger, trace);
+        let proof = prove_single_chip(&config, &chip, trace).unwrap();
 
-        let mut challenger = config.challenger();
-        verify(&config, &chip, &mut challenger, &proof).unwrap();
+        verify_single_chip(&config, &chip, &proof).unwrap();
     }
 }
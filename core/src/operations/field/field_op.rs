@@ -179,8 +179,8 @@ mod tests {
 
     use crate::utils::ec::edwards::ed25519::Ed25519BaseField;
     use crate::utils::ec::field::FieldParameters;
-    use crate::utils::{pad_to_power_of_two, BabyBearPoseidon2, StarkUtils};
-    use crate::utils::{uni_stark_prove as prove, uni_stark_verify as verify};
+    use crate::utils::{pad_to_power_of_two, BabyBearPoseidon2};
+    use crate::utils::{prove_single_chip, verify_single_chip};
     use crate::{air::SP1AirBuilder, runtime::ExecutionRecord};
     use core::borrow::{Borrow, BorrowMut};
     use core::mem::size_of;
@@ -220,6 +220,10 @@ mod tests {
             format!("FieldOp{:?}", self.operation)
         }
 
+        fn num_real_rows(&self, _: &ExecutionRecord) -> usize {
+            1 << 8
+        }
+
         fn generate_trace(
             &self,
             _: &ExecutionRecord,
@@ -325,16 +329,13 @@ mod tests {
         {
             println!("op: {:?}", op);
 
-            let mut challenger = config.challenger();
-
             let chip: FieldOpChip<Ed25519BaseField> = FieldOpChip::new(*op);
             let shard = ExecutionRecord::default();
             let trace: RowMajorMatrix<BabyBear> =
                 chip.generate_trace(&shard, &mut ExecutionRecord::default());
-            let proof = prove::<BabyBearPoseidon2, _>(&config, &chip, &mut challenger, trace);
+            let proof = prove_single_chip(&config, &chip, trace).unwrap();
 
-            let mut challenger = config.challenger();
-            verify(&config, &chip, &mut challenger, &proof).unwrap();
+            verify_single_chip(&config, &chip, &proof).unwrap();
         }
     }
 }
@@ -86,8 +86,8 @@ mod tests {
 
     use crate::utils::ec::edwards::ed25519::{ed25519_sqrt, Ed25519BaseField};
     use crate::utils::ec::field::FieldParameters;
-    use crate::utils::{pad_to_power_of_two, BabyBearPoseidon2, StarkUtils};
-    use crate::utils::{uni_stark_prove as prove, uni_stark_verify as verify};
+    use crate::utils::{pad_to_power_of_two, BabyBearPoseidon2};
+    use crate::utils::{prove_single_chip, verify_single_chip};
     use crate::{air::SP1AirBuilder, runtime::ExecutionRecord};
     use core::borrow::{Borrow, BorrowMut};
     use core::mem::size_of;
@@ -123,6 +123,10 @@ mod tests {
             "EdSqrtChip".to_string()
         }
 
+        fn num_real_rows(&self, _: &ExecutionRecord) -> usize {
+            1 << 8
+        }
+
         fn generate_trace(
             &self,
             _: &ExecutionRecord,
@@ -202,15 +206,12 @@ mod tests {
     #[test]
     fn prove_babybear() {
         let config = BabyBearPoseidon2::new();
-        let mut challenger = config.challenger();
-
         let chip: EdSqrtChip<Ed25519BaseField> = EdSqrtChip::new();
         let shard = ExecutionRecord::default();
         let trace: RowMajorMatrix<BabyBear> =
             chip.generate_trace(&shard, &mut ExecutionRecord::default());
-        let proof = prove::<BabyBearPoseidon2, _>(&config, &chip, &mut challenger, trace);
+        let proof = prove_single_chip(&config, &chip, trace).unwrap();
 
-        let mut challenger = config.challenger();
-        verify(&config, &chip, &mut challenger, &proof).unwrap();
+        verify_single_chip(&config, &chip, &proof).unwrap();
     }
 }
@@ -4,6 +4,7 @@ use p3_field::Field;
 use sp1_derive::AlignedBorrow;
 use std::mem::size_of;
 
+use crate::air::BoundedExpr;
 use crate::air::SP1AirBuilder;
 use crate::air::Word;
 use crate::bytes::utils::shr_carry;
@@ -109,7 +110,7 @@ impl<F: Field> FixedShiftRightOperation<F> {
         // Compute some constants with respect to the rotation needed for the rotation.
         let nb_bytes_to_shift = Self::nb_bytes_to_shift(rotation);
         let nb_bits_to_shift = Self::nb_bits_to_shift(rotation);
-        let carry_multiplier = AB::F::from_canonical_u32(Self::carry_multiplier(rotation));
+        let carry_multiplier = Self::carry_multiplier(rotation);
 
         // Perform the byte shift.
         let mut word = vec![AB::Expr::zero(); WORD_SIZE];
@@ -122,8 +123,11 @@ impl<F: Field> FixedShiftRightOperation<F> {
 
         // For each byte, calculate the shift and carry. If it's not the first byte, calculate the
         // new byte value using the current shifted byte and the last carry.
-        let mut first_shift = AB::Expr::zero();
-        let mut last_carry = AB::Expr::zero();
+        // The shifted byte has `8 - nb_bits_to_shift` bits and the carry `nb_bits_to_shift` bits.
+        let shift = |x: AB::Var| BoundedExpr::<AB::Expr>::bits(x, 8 - nb_bits_to_shift as u32);
+        let carry = |x: AB::Var| BoundedExpr::<AB::Expr>::bits(x, nb_bits_to_shift as u32);
+        let mut first_shift = BoundedExpr::zero();
+        let mut last_carry = BoundedExpr::zero();
         for i in (0..WORD_SIZE).rev() {
             builder.send_byte_pair(
                 AB::F::from_canonical_u32(ByteOpcode::ShrCarry as u32),
@@ -135,15 +139,18 @@ impl<F: Field> FixedShiftRightOperation<F> {
             );
 
             if i == WORD_SIZE - 1 {
-                first_shift = cols.shift[i].into();
+                first_shift = shift(cols.shift[i]);
             } else {
-                builder.assert_eq(cols.value[i], cols.shift[i] + last_carry * carry_multiplier);
+                builder.assert_bounded_eq(
+                    BoundedExpr::byte(cols.value[i]),
+                    shift(cols.shift[i]) + last_carry * carry_multiplier,
+                );
             }
 
-            last_carry = cols.carry[i].into();
+            last_carry = carry(cols.carry[i]);
         }
 
         // For the first byte, we don't move over the carry as this is a shift, not a rotate.
-        builder.assert_eq(cols.value[WORD_SIZE - 1], first_shift);
+        builder.assert_bounded_eq(BoundedExpr::byte(cols.value[WORD_SIZE - 1]), first_shift);
     }
 }
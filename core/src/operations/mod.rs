@@ -7,6 +7,7 @@
 mod add;
 mod add4;
 mod add5;
+mod add_double_word;
 mod and;
 pub mod field;
 mod fixed_rotate_right;
@@ -16,11 +17,13 @@ mod is_zero;
 mod is_zero_word;
 mod not;
 mod or;
+mod select_word;
 mod xor;
 
 pub use add::*;
 pub use add4::*;
 pub use add5::*;
+pub use add_double_word::*;
 pub use and::*;
 pub use fixed_rotate_right::*;
 pub use fixed_shift_right::*;
@@ -29,4 +32,252 @@ pub use is_zero::*;
 pub use is_zero_word::*;
 pub use not::*;
 pub use or::*;
+pub use select_word::*;
 pub use xor::*;
+
+#[cfg(test)]
+mod tests {
+    use core::borrow::{Borrow, BorrowMut};
+    use core::mem::{size_of, transmute};
+
+    use p3_baby_bear::BabyBear;
+    use p3_util::indices_arr;
+    use proptest::prelude::*;
+
+    use super::{
+        AddOperation, AndOperation, FixedRotateRightOperation, IsEqualWordOperation,
+        IsZeroWordOperation, XorOperation,
+    };
+    use crate::air::{SP1AirBuilder, Word, WORD_SIZE};
+    use crate::runtime::ExecutionRecord;
+    use crate::utils::test_utils::{gadget_prop_test, GadgetUnderTest};
+
+    fn write_words(inputs: &mut [BabyBear], words: &[u32]) {
+        for (limbs, word) in inputs.chunks_exact_mut(WORD_SIZE).zip(words) {
+            limbs.copy_from_slice(&Word::<BabyBear>::from(*word).0);
+        }
+    }
+
+    fn read_word<T: Copy>(inputs: &[T], index: usize) -> Word<T> {
+        Word(core::array::from_fn(|i| inputs[index * WORD_SIZE + i]))
+    }
+
+    const NUM_IS_ZERO_WORD_COLS: usize = size_of::<IsZeroWordOperation<u8>>();
+
+    fn is_zero_word_col_map() -> IsZeroWordOperation<usize> {
+        let indices = indices_arr::<NUM_IS_ZERO_WORD_COLS>();
+        unsafe { transmute::<[usize; NUM_IS_ZERO_WORD_COLS], IsZeroWordOperation<usize>>(indices) }
+    }
+
+    /// The inverses of the zero bytes of a word, which the constraints leave free.
+    fn zero_byte_inverses(word: u32) -> Vec<usize> {
+        let col_map = is_zero_word_col_map();
+        word.to_le_bytes()
+            .iter()
+            .zip(col_map.is_zero_byte)
+            .filter(|(byte, _)| **byte == 0)
+            .map(|(_, cols)| cols.inverse)
+            .collect()
+    }
+
+    /// Implements `GadgetUnderTest` for a gadget computing a bitwise or arithmetic operation on
+    /// two words.
+    macro_rules! impl_word_pair_gadget {
+        ($gadget:ident) => {
+            impl GadgetUnderTest for $gadget<BabyBear> {
+                type Input = (u32, u32);
+                const NUM_INPUT_COLS: usize = 2 * WORD_SIZE;
+                const NUM_COLS: usize = size_of::<$gadget<u8>>();
+
+                fn populate(
+                    (a, b): (u32, u32),
+                    inputs: &mut [BabyBear],
+                    cols: &mut [BabyBear],
+                    record: &mut ExecutionRecord,
+                ) -> u32 {
+                    write_words(inputs, &[a, b]);
+                    let cols: &mut $gadget<BabyBear> = cols.borrow_mut();
+                    cols.populate(record, a, b)
+                }
+
+                fn eval<AB: SP1AirBuilder>(
+                    builder: &mut AB,
+                    _: (u32, u32),
+                    inputs: &[AB::Var],
+                    cols: &[AB::Var],
+                    is_real: AB::Var,
+                ) {
+                    let cols: &$gadget<AB::Var> = cols.borrow();
+                    $gadget::<AB::F>::eval(
+                        builder,
+                        read_word(inputs, 0),
+                        read_word(inputs, 1),
+                        *cols,
+                        is_real,
+                    );
+                }
+            }
+        };
+    }
+
+    impl_word_pair_gadget!(AddOperation);
+    impl_word_pair_gadget!(XorOperation);
+    impl_word_pair_gadget!(AndOperation);
+
+    impl GadgetUnderTest for FixedRotateRightOperation<BabyBear> {
+        type Input = (u32, usize);
+        const NUM_INPUT_COLS: usize = WORD_SIZE;
+        const NUM_COLS: usize = size_of::<FixedRotateRightOperation<u8>>();
+
+        fn populate(
+            (input, rotation): (u32, usize),
+            inputs: &mut [BabyBear],
+            cols: &mut [BabyBear],
+            record: &mut ExecutionRecord,
+        ) -> u32 {
+            write_words(inputs, &[input]);
+            let cols: &mut FixedRotateRightOperation<BabyBear> = cols.borrow_mut();
+            cols.populate(record, input, rotation)
+        }
+
+        fn eval<AB: SP1AirBuilder>(
+            builder: &mut AB,
+            (_, rotation): (u32, usize),
+            inputs: &[AB::Var],
+            cols: &[AB::Var],
+            is_real: AB::Var,
+        ) {
+            let cols: &FixedRotateRightOperation<AB::Var> = cols.borrow();
+            FixedRotateRightOperation::<AB::F>::eval(
+                builder,
+                read_word(inputs, 0),
+                rotation,
+                *cols,
+                is_real,
+            );
+        }
+    }
+
+    impl GadgetUnderTest for IsZeroWordOperation<BabyBear> {
+        type Input = u32;
+        const NUM_INPUT_COLS: usize = WORD_SIZE;
+        const NUM_COLS: usize = NUM_IS_ZERO_WORD_COLS;
+
+        fn populate(
+            input: u32,
+            inputs: &mut [BabyBear],
+            cols: &mut [BabyBear],
+            _: &mut ExecutionRecord,
+        ) -> u32 {
+            write_words(inputs, &[input]);
+            let cols: &mut IsZeroWordOperation<BabyBear> = cols.borrow_mut();
+            cols.populate(input)
+        }
+
+        fn eval<AB: SP1AirBuilder>(
+            builder: &mut AB,
+            _: u32,
+            inputs: &[AB::Var],
+            cols: &[AB::Var],
+            is_real: AB::Var,
+        ) {
+            let cols: &IsZeroWordOperation<AB::Var> = cols.borrow();
+            IsZeroWordOperation::<AB::F>::eval(
+                builder,
+                read_word(inputs, 0).map(|x| x.into()),
+                *cols,
+                is_real.into(),
+            );
+        }
+
+        fn free_cols(input: u32) -> Vec<usize> {
+            zero_byte_inverses(input)
+        }
+    }
+
+    impl GadgetUnderTest for IsEqualWordOperation<BabyBear> {
+        type Input = (u32, u32);
+        const NUM_INPUT_COLS: usize = 2 * WORD_SIZE;
+        const NUM_COLS: usize = size_of::<IsEqualWordOperation<u8>>();
+
+        fn populate(
+            (a, b): (u32, u32),
+            inputs: &mut [BabyBear],
+            cols: &mut [BabyBear],
+            _: &mut ExecutionRecord,
+        ) -> u32 {
+            write_words(inputs, &[a, b]);
+            let cols: &mut IsEqualWordOperation<BabyBear> = cols.borrow_mut();
+            cols.populate(a, b)
+        }
+
+        fn eval<AB: SP1AirBuilder>(
+            builder: &mut AB,
+            _: (u32, u32),
+            inputs: &[AB::Var],
+            cols: &[AB::Var],
+            is_real: AB::Var,
+        ) {
+            let cols: &IsEqualWordOperation<AB::Var> = cols.borrow();
+            IsEqualWordOperation::<AB::F>::eval(
+                builder,
+                read_word(inputs, 0).map(|x| x.into()),
+                read_word(inputs, 1).map(|x| x.into()),
+                *cols,
+                is_real.into(),
+            );
+        }
+
+        /// The columns are those of `IsZeroWordOperation` on the bytewise difference, whose
+        /// bytes are zero exactly where those of `a ^ b` are.
+        fn free_cols((a, b): (u32, u32)) -> Vec<usize> {
+            zero_byte_inverses(a ^ b)
+        }
+    }
+
+    #[test]
+    fn test_add_operation() {
+        gadget_prop_test!(AddOperation, any::<(u32, u32)>(), |(a, b): (u32, u32)| {
+            a.wrapping_add(b)
+        });
+    }
+
+    #[test]
+    fn test_xor_operation() {
+        gadget_prop_test!(XorOperation, any::<(u32, u32)>(), |(a, b): (u32, u32)| a
+            ^ b);
+    }
+
+    #[test]
+    fn test_and_operation() {
+        gadget_prop_test!(AndOperation, any::<(u32, u32)>(), |(a, b): (u32, u32)| a
+            & b);
+    }
+
+    #[test]
+    fn test_fixed_rotate_right_operation() {
+        gadget_prop_test!(
+            FixedRotateRightOperation,
+            (any::<u32>(), 0usize..32),
+            |(input, rotation): (u32, usize)| input.rotate_right(rotation as u32)
+        );
+    }
+
+    #[test]
+    fn test_is_zero_word_operation() {
+        let words = prop_oneof![Just(0u32), any::<u8>().prop_map(u32::from), any::<u32>()];
+        gadget_prop_test!(IsZeroWordOperation, words, |a: u32| (a == 0) as u32);
+    }
+
+    #[test]
+    fn test_is_equal_word_operation() {
+        let pairs = prop_oneof![
+            any::<u32>().prop_map(|a| (a, a)),
+            (any::<u32>(), any::<u8>()).prop_map(|(a, diff)| (a, a ^ diff as u32)),
+            any::<(u32, u32)>(),
+        ];
+        gadget_prop_test!(IsEqualWordOperation, pairs, |(a, b): (u32, u32)| {
+            (a == b) as u32
+        });
+    }
+}
@@ -0,0 +1,64 @@
+use p3_field::Field;
+
+use crate::air::SP1AirBuilder;
+use crate::air::Word;
+use crate::air::WordAirBuilder;
+
+/// An operation that selects one of two words based on a boolean flag.
+///
+/// The selection is a linear combination of the inputs, so no columns are needed.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct SelectWordOperation;
+
+impl SelectWordOperation {
+    /// Returns `a` if `flag` is one and `b` if `flag` is zero.
+    pub fn populate<F: Field>(flag: F, a: Word<F>, b: Word<F>) -> Word<F> {
+        debug_assert!(
+            flag == F::zero() || flag == F::one(),
+            "flag must be boolean"
+        );
+        let mut b = b.0.into_iter();
+        a.map(|a| {
+            let b = b.next().unwrap();
+            b + flag * (a - b)
+        })
+    }
+
+    /// Evaluates the selection, assuming that `flag` is constrained to be boolean by the caller.
+    pub fn eval<AB: SP1AirBuilder>(
+        builder: &mut AB,
+        flag: impl Into<AB::Expr>,
+        a: Word<impl Into<AB::Expr>>,
+        b: Word<impl Into<AB::Expr>>,
+    ) -> Word<AB::Expr> {
+        builder.select_word(flag, a, b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+    use p3_field::AbstractField;
+
+    use super::SelectWordOperation;
+    use crate::air::Word;
+
+    #[test]
+    fn test_select_word() {
+        let a = Word::<BabyBear>::from(0x12345678);
+        let b = Word::<BabyBear>::from(0xdeadbeef);
+        let selected = SelectWordOperation::populate(BabyBear::one(), a, b);
+        assert_eq!(selected.to_u32(), 0x12345678);
+        let selected = SelectWordOperation::populate(BabyBear::zero(), a, b);
+        assert_eq!(selected.to_u32(), 0xdeadbeef);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "flag must be boolean")]
+    fn test_select_word_non_boolean_flag() {
+        let a = Word::<BabyBear>::from(1);
+        let b = Word::<BabyBear>::from(2);
+        SelectWordOperation::populate(BabyBear::two(), a, b);
+    }
+}
@@ -0,0 +1,437 @@
+use core::borrow::{Borrow, BorrowMut};
+use core::mem::size_of;
+
+use p3_air::AirBuilder;
+use p3_field::{AbstractField, Field};
+use sp1_derive::AlignedBorrow;
+
+use crate::air::{SP1AirBuilder, Word, WORD_SIZE};
+use crate::cpu::columns::InstructionCols;
+
+pub const NUM_IMMEDIATE_COLS: usize = size_of::<ImmediateCols<u8>>();
+
+/// The major opcodes (the low seven bits of the encoding) of RV32IM.
+pub const OPCODE_OP_IMM: u32 = 0b0010011;
+pub const OPCODE_LOAD: u32 = 0b0000011;
+pub const OPCODE_JALR: u32 = 0b1100111;
+pub const OPCODE_STORE: u32 = 0b0100011;
+pub const OPCODE_BRANCH: u32 = 0b1100011;
+pub const OPCODE_LUI: u32 = 0b0110111;
+pub const OPCODE_AUIPC: u32 = 0b0010111;
+pub const OPCODE_JAL: u32 = 0b1101111;
+pub const OPCODE_OP: u32 = 0b0110011;
+pub const OPCODE_SYSTEM: u32 = 0b1110011;
+pub const OPCODE_MISC_MEM: u32 = 0b0001111;
+
+/// The encoding formats of RV32IM that carry an immediate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImmediateFormat {
+    I,
+    Shamt,
+    S,
+    B,
+    U,
+    J,
+}
+
+impl ImmediateFormat {
+    /// The format of the immediate of an encoded instruction, if it has one.
+    pub fn of(encoding: u32) -> Option<Self> {
+        match encoding & 0x7f {
+            OPCODE_OP_IMM if is_shift_funct3(encoding) => Some(ImmediateFormat::Shamt),
+            OPCODE_OP_IMM | OPCODE_LOAD | OPCODE_JALR => Some(ImmediateFormat::I),
+            OPCODE_STORE => Some(ImmediateFormat::S),
+            OPCODE_BRANCH => Some(ImmediateFormat::B),
+            OPCODE_LUI | OPCODE_AUIPC => Some(ImmediateFormat::U),
+            OPCODE_JAL => Some(ImmediateFormat::J),
+            _ => None,
+        }
+    }
+
+    /// The bit of the encoding holding bit `i` of the immediate, or `None` if the bit is zero.
+    pub fn source_bit(self, i: usize) -> Option<usize> {
+        match self {
+            ImmediateFormat::I => Some(20 + i.min(11)),
+            ImmediateFormat::Shamt => (i < 5).then_some(20 + i),
+            ImmediateFormat::S => match i {
+                0..=4 => Some(7 + i),
+                _ => Some(20 + i.min(11)),
+            },
+            ImmediateFormat::B => match i {
+                0 => None,
+                1..=4 => Some(7 + i),
+                5..=10 => Some(20 + i),
+                11 => Some(7),
+                _ => Some(31),
+            },
+            ImmediateFormat::U => (i >= 12).then_some(i),
+            ImmediateFormat::J => match i {
+                0 => None,
+                1..=10 => Some(20 + i),
+                11 => Some(20),
+                12..=19 => Some(i),
+                _ => Some(31),
+            },
+        }
+    }
+
+    /// Reconstructs the sign-extended immediate of an encoded instruction.
+    pub fn decode(self, encoding: u32) -> u32 {
+        (0..32)
+            .filter_map(|i| self.source_bit(i).map(|bit| ((encoding >> bit) & 1) << i))
+            .sum()
+    }
+}
+
+/// Whether an `OP-IMM` encoding is a shift by an immediate, i.e. `funct3` is `001` or `101`.
+fn is_shift_funct3(encoding: u32) -> bool {
+    (encoding >> 12) & 0b11 == 0b01
+}
+
+/// The columns binding the immediate of an instruction to its raw 32-bit encoding.
+///
+/// These columns only depend on the program. They bind the immediates to the encoding, but
+/// nothing here binds the encoding to the program: that needs the program table to be committed
+/// in the verifying key as a preprocessed trace.
+#[derive(AlignedBorrow, Clone, Copy, Default, Debug)]
+#[repr(C)]
+pub struct ImmediateCols<T> {
+    /// The raw encoding of the instruction, as bytes.
+    pub encoding: Word<T>,
+
+    /// The bits of the encoding, least significant first.
+    pub bits: [T; 32],
+
+    /// One-hot flags for the major opcode of the encoding. All of them are zero for padding.
+    pub is_op_imm: T,
+    pub is_load: T,
+    pub is_jalr: T,
+    pub is_store: T,
+    pub is_branch: T,
+    pub is_lui: T,
+    pub is_auipc: T,
+    pub is_jal: T,
+    pub is_op: T,
+    pub is_system: T,
+    pub is_misc_mem: T,
+
+    /// Whether the instruction is a shift by an immediate (an `OP-IMM` with a shamt).
+    pub is_shift: T,
+}
+
+impl<F: Field> ImmediateCols<F> {
+    pub fn populate(&mut self, encoding: u32) {
+        self.encoding = encoding.into();
+        for i in 0..32 {
+            self.bits[i] = F::from_canonical_u32((encoding >> i) & 1);
+        }
+        let opcode = encoding & 0x7f;
+        self.is_op_imm = F::from_bool(opcode == OPCODE_OP_IMM);
+        self.is_load = F::from_bool(opcode == OPCODE_LOAD);
+        self.is_jalr = F::from_bool(opcode == OPCODE_JALR);
+        self.is_store = F::from_bool(opcode == OPCODE_STORE);
+        self.is_branch = F::from_bool(opcode == OPCODE_BRANCH);
+        self.is_lui = F::from_bool(opcode == OPCODE_LUI);
+        self.is_auipc = F::from_bool(opcode == OPCODE_AUIPC);
+        self.is_jal = F::from_bool(opcode == OPCODE_JAL);
+        self.is_op = F::from_bool(opcode == OPCODE_OP);
+        self.is_system = F::from_bool(opcode == OPCODE_SYSTEM);
+        self.is_misc_mem = F::from_bool(opcode == OPCODE_MISC_MEM);
+        self.is_shift = F::from_bool(opcode == OPCODE_OP_IMM && is_shift_funct3(encoding));
+    }
+}
+
+impl<T: Copy> ImmediateCols<T> {
+    /// The major opcode flags paired with the opcode they select.
+    fn opcode_flags(&self) -> [(T, u32); 11] {
+        [
+            (self.is_op_imm, OPCODE_OP_IMM),
+            (self.is_load, OPCODE_LOAD),
+            (self.is_jalr, OPCODE_JALR),
+            (self.is_store, OPCODE_STORE),
+            (self.is_branch, OPCODE_BRANCH),
+            (self.is_lui, OPCODE_LUI),
+            (self.is_auipc, OPCODE_AUIPC),
+            (self.is_jal, OPCODE_JAL),
+            (self.is_op, OPCODE_OP),
+            (self.is_system, OPCODE_SYSTEM),
+            (self.is_misc_mem, OPCODE_MISC_MEM),
+        ]
+    }
+
+    /// Constrains the encoding and asserts that the stored operands match its immediate.
+    ///
+    /// On its own, this is not sound: a prover may pick any encoding together with matching
+    /// operands. It relies on the encoding being part of the committed program table.
+    pub fn eval<AB: SP1AirBuilder<Var = T>>(
+        &self,
+        builder: &mut AB,
+        instruction: &InstructionCols<AB::Var>,
+    ) {
+        // The bits decompose the encoding.
+        for bit in self.bits {
+            builder.assert_bool(bit);
+        }
+        for i in 0..WORD_SIZE {
+            let byte = (0..8).fold(AB::Expr::zero(), |acc, j| {
+                acc + self.bits[8 * i + j] * AB::F::from_canonical_u32(1 << j)
+            });
+            builder.assert_eq(self.encoding[i], byte);
+        }
+
+        // At most one major opcode is selected, and it must match the low seven bits. Rows
+        // without one must have an empty encoding.
+        let mut has_opcode = AB::Expr::zero();
+        for (flag, opcode) in self.opcode_flags() {
+            builder.assert_bool(flag);
+            has_opcode += flag.into();
+            for j in 0..7 {
+                builder
+                    .when(flag)
+                    .assert_eq(self.bits[j], AB::F::from_canonical_u32((opcode >> j) & 1));
+            }
+        }
+        builder.assert_bool(has_opcode.clone());
+        for bit in self.bits {
+            builder
+                .when_ne(has_opcode.clone(), AB::F::one())
+                .assert_zero(bit);
+        }
+
+        // Shifts by an immediate are the `OP-IMM` instructions with `funct3` equal to `x01`.
+        builder.assert_eq(
+            self.is_shift,
+            self.is_op_imm * self.bits[12] * (AB::Expr::one() - self.bits[13]),
+        );
+
+        // Reconstruct the immediate of each format and compare it with the stored operand.
+        let i_type = self.is_op_imm + self.is_load + self.is_jalr - self.is_shift;
+        let checks = [
+            (i_type, ImmediateFormat::I, instruction.op_c),
+            (
+                self.is_shift.into(),
+                ImmediateFormat::Shamt,
+                instruction.op_c,
+            ),
+            (self.is_store.into(), ImmediateFormat::S, instruction.op_c),
+            (self.is_branch.into(), ImmediateFormat::B, instruction.op_c),
+            (self.is_lui.into(), ImmediateFormat::U, instruction.op_c),
+            (self.is_auipc.into(), ImmediateFormat::U, instruction.op_b),
+            (self.is_auipc.into(), ImmediateFormat::U, instruction.op_c),
+            (self.is_jal.into(), ImmediateFormat::J, instruction.op_b),
+        ];
+        for (flag, format, operand) in checks {
+            let immediate = self.immediate::<AB>(format);
+            for i in 0..WORD_SIZE {
+                builder
+                    .when(flag.clone())
+                    .assert_eq(operand[i], immediate[i].clone());
+            }
+        }
+    }
+
+    /// The bytes of the immediate of the given format, as expressions of the encoding bits.
+    fn immediate<AB: SP1AirBuilder<Var = T>>(&self, format: ImmediateFormat) -> Word<AB::Expr> {
+        Word(core::array::from_fn(|i| {
+            (0..8).fold(AB::Expr::zero(), |acc, j| {
+                match format.source_bit(8 * i + j) {
+                    Some(bit) => acc + self.bits[bit] * AB::F::from_canonical_u32(1 << j),
+                    None => acc,
+                }
+            })
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use p3_baby_bear::BabyBear;
+    use p3_field::extension::BinomialExtensionField;
+    use p3_matrix::dense::RowMajorMatrix;
+
+    use crate::air::{MachineAir, PublicValues};
+    use crate::disassembler::transpile;
+    use crate::program::{ProgramChip, ProgramPreprocessedCols};
+    use crate::runtime::{ExecutionRecord, Program};
+    use crate::stark::{check_constraints, Chip, RiscvAir};
+    use crate::utils::BabyBearBlake3;
+
+    use super::*;
+
+    /// Immediates exercising zero, the extremes and every single bit, as signed 32-bit values.
+    fn edge_immediates(bits: u32, align: u32) -> Vec<i32> {
+        let min = i32::MIN >> (32 - bits);
+        let max = i32::MAX >> (32 - bits);
+        let mut immediates = vec![0, 1, -1, min, max, min + 1, max - 1, -2];
+        immediates.extend((0..bits - 1).map(|i| 1i32 << i));
+        immediates.extend((0..bits - 1).map(|i| -(1i32 << i)));
+        immediates.retain(|imm| *imm >= min && *imm <= max);
+        immediates
+            .into_iter()
+            .map(|imm| imm & !((1 << align) - 1))
+            .collect()
+    }
+
+    fn encode_i(opcode: u32, funct3: u32, imm: i32) -> u32 {
+        ((imm as u32 & 0xfff) << 20) | (2 << 15) | (funct3 << 12) | (1 << 7) | opcode
+    }
+
+    fn encode_s(imm: i32) -> u32 {
+        let imm = imm as u32;
+        (((imm >> 5) & 0x7f) << 25)
+            | (3 << 20)
+            | (2 << 15)
+            | (0b010 << 12)
+            | ((imm & 0x1f) << 7)
+            | OPCODE_STORE
+    }
+
+    fn encode_b(imm: i32) -> u32 {
+        let imm = imm as u32;
+        (((imm >> 12) & 1) << 31)
+            | (((imm >> 5) & 0x3f) << 25)
+            | (3 << 20)
+            | (2 << 15)
+            | (((imm >> 1) & 0xf) << 8)
+            | (((imm >> 11) & 1) << 7)
+            | OPCODE_BRANCH
+    }
+
+    fn encode_u(opcode: u32, imm: i32) -> u32 {
+        (imm as u32 & 0xfffff000) | (1 << 7) | opcode
+    }
+
+    fn encode_j(imm: i32) -> u32 {
+        let imm = imm as u32;
+        (((imm >> 20) & 1) << 31)
+            | (((imm >> 1) & 0x3ff) << 21)
+            | (((imm >> 11) & 1) << 20)
+            | (((imm >> 12) & 0xff) << 12)
+            | (1 << 7)
+            | OPCODE_JAL
+    }
+
+    /// A corpus of encodings covering every immediate format, paired with the expected immediate.
+    fn immediate_corpus() -> Vec<(u32, u32)> {
+        let mut corpus = vec![];
+        for imm in edge_immediates(12, 0) {
+            for funct3 in [0b000, 0b010, 0b011, 0b100, 0b110, 0b111] {
+                corpus.push((encode_i(OPCODE_OP_IMM, funct3, imm), imm as u32));
+            }
+            for funct3 in [0b000, 0b001, 0b010, 0b100, 0b101] {
+                corpus.push((encode_i(OPCODE_LOAD, funct3, imm), imm as u32));
+            }
+            corpus.push((encode_i(OPCODE_JALR, 0, imm), imm as u32));
+            corpus.push((encode_s(imm), imm as u32));
+        }
+        for shamt in 0..32 {
+            corpus.push((encode_i(OPCODE_OP_IMM, 0b001, shamt), shamt as u32));
+            corpus.push((encode_i(OPCODE_OP_IMM, 0b101, shamt), shamt as u32));
+            corpus.push((encode_i(OPCODE_OP_IMM, 0b101, shamt | 0x400), shamt as u32));
+        }
+        for imm in edge_immediates(13, 1) {
+            corpus.push((encode_b(imm), imm as u32));
+        }
+        for imm in edge_immediates(32, 12) {
+            corpus.push((encode_u(OPCODE_LUI, imm), imm as u32));
+            corpus.push((encode_u(OPCODE_AUIPC, imm), imm as u32));
+        }
+        for imm in edge_immediates(21, 1) {
+            corpus.push((encode_j(imm), imm as u32));
+        }
+        corpus
+    }
+
+    /// The operand in which the transpiler stores the immediate of an encoding.
+    fn stored_immediate(encoding: u32) -> u32 {
+        let instruction = transpile(&[encoding])[0];
+        match encoding & 0x7f {
+            OPCODE_JAL => instruction.op_b,
+            OPCODE_AUIPC => {
+                assert_eq!(instruction.op_b, instruction.op_c);
+                instruction.op_c
+            }
+            _ => instruction.op_c,
+        }
+    }
+
+    #[test]
+    fn test_decode_immediate_matches_transpiler() {
+        for (encoding, expected) in immediate_corpus() {
+            let format = ImmediateFormat::of(encoding).unwrap();
+            assert_eq!(
+                format.decode(encoding),
+                expected,
+                "{:?} {:#010x}",
+                format,
+                encoding
+            );
+            assert_eq!(
+                stored_immediate(encoding),
+                expected,
+                "{:?} {:#010x}",
+                format,
+                encoding
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_immediate_sign_extension() {
+        assert_eq!(ImmediateFormat::I.decode(0xfff00093), u32::MAX);
+        assert_eq!(ImmediateFormat::I.decode(0x80000093), 0xfffff800);
+        assert_eq!(ImmediateFormat::I.decode(0x7ff00093), 0x7ff);
+        assert_eq!(ImmediateFormat::Shamt.decode(0x41f0d093), 31);
+        assert_eq!(ImmediateFormat::S.decode(0xfe312fa3), u32::MAX);
+        assert_eq!(ImmediateFormat::B.decode(0x80000063), 0xfffff000);
+        assert_eq!(ImmediateFormat::B.decode(0x00000fe3), 0x800 | 0x1e);
+        assert_eq!(ImmediateFormat::U.decode(0xfffff0b7), 0xfffff000);
+        assert_eq!(ImmediateFormat::J.decode(0x800000ef), 0xfff00000);
+        assert_eq!(ImmediateFormat::J.decode(0x001000ef), 0x800);
+    }
+
+    #[test]
+    fn test_program_chip_constrains_immediates() {
+        type EF = BinomialExtensionField<BabyBear, 4>;
+
+        let encodings = immediate_corpus()
+            .into_iter()
+            .map(|(encoding, _)| encoding)
+            .collect::<Vec<_>>();
+        let shard = ExecutionRecord {
+            program: Arc::new(Program {
+                instructions: transpile(&encodings),
+                encodings,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let chip = Chip::new(RiscvAir::<BabyBear>::Program(ProgramChip::new()));
+        let mut preprocessed = chip.generate_preprocessed_trace(&shard.program).unwrap();
+        let trace = chip.generate_trace(&shard, &mut ExecutionRecord::default());
+        let challenges = [EF::from_canonical_u32(3), EF::from_canonical_u32(7)];
+        let public_values = PublicValues::from_record(&shard).to_field_elements();
+        let check = |preprocessed: &RowMajorMatrix<BabyBear>| {
+            let perm = chip.generate_permutation_trace(Some(preprocessed), &trace, &challenges);
+            check_constraints::<BabyBearBlake3>(
+                &chip,
+                Some(preprocessed),
+                &trace,
+                &perm,
+                &challenges,
+                &public_values,
+            )
+        };
+        assert!(check(&preprocessed).is_ok());
+
+        // Tamper with the stored immediate of the first instruction.
+        let cols: &mut ProgramPreprocessedCols<BabyBear> =
+            preprocessed.values[..chip.preprocessed_width()].borrow_mut();
+        cols.instruction.op_c[1] += BabyBear::one();
+        let failure = check(&preprocessed).unwrap_err();
+        assert_eq!(failure.chip, "Program");
+        assert_eq!(failure.row, 0);
+    }
+}
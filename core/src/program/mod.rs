@@ -1,29 +1,43 @@
 use core::borrow::{Borrow, BorrowMut};
 use core::mem::size_of;
-use p3_air::{Air, BaseAir};
+use p3_air::{Air, BaseAir, PairBuilder};
 use p3_field::PrimeField;
 use p3_matrix::dense::RowMajorMatrix;
 use p3_matrix::MatrixRowSlices;
+use p3_maybe_rayon::prelude::{IntoParallelIterator, ParallelIterator};
 use std::collections::HashMap;
 
 use sp1_derive::AlignedBorrow;
 
-use crate::air::MachineAir;
 use crate::air::SP1AirBuilder;
+use crate::air::{EventKind, MachineAir};
 use crate::cpu::columns::InstructionCols;
 use crate::cpu::columns::OpcodeSelectorCols;
-use crate::runtime::ExecutionRecord;
+use crate::runtime::{ExecutionRecord, Program};
 use crate::utils::pad_to_power_of_two;
 
-pub const NUM_PROGRAM_COLS: usize = size_of::<ProgramCols<u8>>();
+mod immediate;
 
-/// The column layout for the chip.
+pub use immediate::*;
+
+pub const NUM_PROGRAM_PREPROCESSED_COLS: usize = size_of::<ProgramPreprocessedCols<u8>>();
+pub const NUM_PROGRAM_MULT_COLS: usize = size_of::<ProgramMultiplicityCols<u8>>();
+
+/// The column layout of the preprocessed trace of the chip, which only depends on the program.
 #[derive(AlignedBorrow, Clone, Copy, Default)]
 #[repr(C)]
-pub struct ProgramCols<T> {
+pub struct ProgramPreprocessedCols<T> {
     pub pc: T,
     pub instruction: InstructionCols<T>,
     pub selectors: OpcodeSelectorCols<T>,
+    pub immediate: ImmediateCols<T>,
+}
+
+/// The column layout of the main trace of the chip.
+#[derive(AlignedBorrow, Clone, Copy, Default)]
+#[repr(C)]
+pub struct ProgramMultiplicityCols<T> {
+    /// The number of times the instruction of the row is executed in the shard.
     pub multiplicity: T,
 }
 
@@ -35,6 +49,20 @@ impl ProgramChip {
     pub fn new() -> Self {
         Self {}
     }
+
+    /// The preprocessed row of the `i`-th instruction of the program.
+    fn row<F: PrimeField>(program: &Program, i: usize) -> [F; NUM_PROGRAM_PREPROCESSED_COLS] {
+        let instruction = program.instructions[i];
+        let mut row = [F::zero(); NUM_PROGRAM_PREPROCESSED_COLS];
+        let cols: &mut ProgramPreprocessedCols<F> = row.as_mut_slice().borrow_mut();
+        cols.pc = F::from_canonical_u32(program.pc_base + (i as u32 * 4));
+        cols.instruction.populate(instruction);
+        cols.selectors.populate(instruction);
+        if let Some(encoding) = program.encodings.get(i) {
+            cols.immediate.populate(*encoding);
+        }
+        row
+    }
 }
 
 impl<F: PrimeField> MachineAir<F> for ProgramChip {
@@ -42,6 +70,32 @@ impl<F: PrimeField> MachineAir<F> for ProgramChip {
         "Program".to_string()
     }
 
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::Cpu]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.program.instructions.len()
+    }
+
+    fn included(&self, _: &ExecutionRecord) -> bool {
+        true
+    }
+
+    fn preprocessed_width(&self) -> usize {
+        NUM_PROGRAM_PREPROCESSED_COLS
+    }
+
+    fn generate_preprocessed_trace(&self, program: &Program) -> Option<RowMajorMatrix<F>> {
+        // The rows are in the order of the instructions, whichever thread generates them.
+        let mut values = (0..program.instructions.len())
+            .into_par_iter()
+            .flat_map(|i| Self::row::<F>(program, i).to_vec())
+            .collect::<Vec<_>>();
+        pad_to_power_of_two::<NUM_PROGRAM_PREPROCESSED_COLS, F>(&mut values);
+        Some(RowMajorMatrix::new(values, NUM_PROGRAM_PREPROCESSED_COLS))
+    }
+
     fn generate_trace(
         &self,
         input: &ExecutionRecord,
@@ -60,19 +114,12 @@ impl<F: PrimeField> MachineAir<F> for ProgramChip {
                 .or_insert(1);
         });
 
-        let rows = input
-            .program
-            .instructions
-            .clone()
-            .into_iter()
-            .enumerate()
-            .map(|(i, instruction)| {
+        // The rows are in the order of the instructions, like those of the preprocessed trace.
+        let rows = (0..input.program.instructions.len())
+            .map(|i| {
                 let pc = input.program.pc_base + (i as u32 * 4);
-                let mut row = [F::zero(); NUM_PROGRAM_COLS];
-                let cols: &mut ProgramCols<F> = row.as_mut_slice().borrow_mut();
-                cols.pc = F::from_canonical_u32(pc);
-                cols.instruction.populate(instruction);
-                cols.selectors.populate(instruction);
+                let mut row = [F::zero(); NUM_PROGRAM_MULT_COLS];
+                let cols: &mut ProgramMultiplicityCols<F> = row.as_mut_slice().borrow_mut();
                 cols.multiplicity =
                     F::from_canonical_usize(*instruction_counts.get(&pc).unwrap_or(&0));
                 row
@@ -82,11 +129,11 @@ impl<F: PrimeField> MachineAir<F> for ProgramChip {
         // Convert the trace to a row major matrix.
         let mut trace = RowMajorMatrix::new(
             rows.into_iter().flatten().collect::<Vec<_>>(),
-            NUM_PROGRAM_COLS,
+            NUM_PROGRAM_MULT_COLS,
         );
 
-        // Pad the trace to a power of two.
-        pad_to_power_of_two::<NUM_PROGRAM_COLS, F>(&mut trace.values);
+        // Pad the trace to a power of two, to the height of the preprocessed trace.
+        pad_to_power_of_two::<NUM_PROGRAM_MULT_COLS, F>(&mut trace.values);
 
         trace
     }
@@ -94,30 +141,35 @@ impl<F: PrimeField> MachineAir<F> for ProgramChip {
 
 impl<F> BaseAir<F> for ProgramChip {
     fn width(&self) -> usize {
-        NUM_PROGRAM_COLS
+        NUM_PROGRAM_MULT_COLS
     }
 }
 
 impl<AB> Air<AB> for ProgramChip
 where
-    AB: SP1AirBuilder,
+    AB: SP1AirBuilder + PairBuilder,
 {
     fn eval(&self, builder: &mut AB) {
         let main = builder.main();
-        let local: &ProgramCols<AB::Var> = main.row_slice(0).borrow();
+        let mult_local: &ProgramMultiplicityCols<AB::Var> = main.row_slice(0).borrow();
+        let preprocessed = builder.preprocessed();
+        let prep_local: &ProgramPreprocessedCols<AB::Var> = preprocessed.row_slice(0).borrow();
 
         // Dummy constraint of degree 3.
         builder.assert_eq(
-            local.pc * local.pc * local.pc,
-            local.pc * local.pc * local.pc,
+            prep_local.pc * prep_local.pc * prep_local.pc,
+            prep_local.pc * prep_local.pc * prep_local.pc,
         );
 
+        // Constrain the immediates against the raw encoding of the instruction.
+        prep_local.immediate.eval(builder, &prep_local.instruction);
+
         // Contrain the interaction with CPU table
         builder.receive_program(
-            local.pc,
-            local.instruction,
-            local.selectors,
-            local.multiplicity,
+            prep_local.pc,
+            prep_local.instruction,
+            prep_local.selectors,
+            mult_local.multiplicity,
         );
     }
 }
@@ -151,9 +203,13 @@ mod tests {
         let shard = ExecutionRecord {
             program: Arc::new(Program {
                 instructions,
+                encodings: vec![],
                 pc_start: 0,
                 pc_base: 0,
                 memory_image: BTreeMap::new(),
+                compat: Default::default(),
+                symbols: Default::default(),
+                memory_layout: None,
             }),
             ..Default::default()
         };
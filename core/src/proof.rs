@@ -0,0 +1,453 @@
+use std::fs;
+
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::air::PublicValues;
+use crate::stark::{ProgramVerificationError, StarkGenericConfig};
+use crate::utils::BabyBearBlake3;
+use crate::version::{decode_proof, encode_proof, ProofVersionError};
+use crate::{SP1ProofWithIO, SP1Stdin, SP1Stdout, SP1Verifier};
+
+/// The bytes every proof file saved in the bincode format starts with.
+pub const PROOF_FILE_MAGIC: [u8; 4] = *b"SP1F";
+
+/// The encoding of a proof file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ProofFormat {
+    /// A JSON object holding the hex of the encoded proof, as written by `SP1ProofWithIO::save`.
+    Json,
+    /// `PROOF_FILE_MAGIC` followed by the bincode of the encoded proof, the stdin and the stdout.
+    /// Smaller and faster to read than the JSON.
+    #[default]
+    Bincode,
+}
+
+impl ProofFormat {
+    /// The format of a proof file, from its first bytes.
+    pub fn of(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(&PROOF_FILE_MAGIC) {
+            return Some(ProofFormat::Bincode);
+        }
+        match bytes.iter().find(|byte| !byte.is_ascii_whitespace()) {
+            Some(b'{') => Some(ProofFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// The layout of a proof file in the bincode format after its magic. The proof is encoded with
+/// its version header, as in the JSON format, so that both formats are upgraded the same way.
+#[derive(Deserialize)]
+struct BinaryProofFile {
+    proof: Vec<u8>,
+    stdin: SP1Stdin,
+    stdout: SP1Stdout,
+}
+
+/// `BinaryProofFile` borrowing the stdin and the stdout, to encode them.
+#[derive(Serialize)]
+struct BinaryProofFileRef<'a> {
+    proof: Vec<u8>,
+    stdin: &'a SP1Stdin,
+    stdout: &'a SP1Stdout,
+}
+
+/// Encodes the proof as a proof file in the given format.
+pub fn encode_proof_file<SC: StarkGenericConfig + Serialize + DeserializeOwned>(
+    proof: &SP1ProofWithIO<SC>,
+    format: ProofFormat,
+) -> Vec<u8> {
+    match format {
+        ProofFormat::Json => serde_json::to_vec(proof).unwrap(),
+        ProofFormat::Bincode => {
+            let file = BinaryProofFileRef {
+                proof: encode_proof(&proof.proof),
+                stdin: &proof.stdin,
+                stdout: &proof.stdout,
+            };
+            let mut bytes = PROOF_FILE_MAGIC.to_vec();
+            bincode::serialize_into(&mut bytes, &file).unwrap();
+            bytes
+        }
+    }
+}
+
+/// Decodes a proof file in either format, detected from its first bytes.
+pub fn decode_proof_file<SC: StarkGenericConfig + Serialize + DeserializeOwned>(
+    bytes: &[u8],
+) -> Result<SP1ProofWithIO<SC>, ProofVersionError> {
+    let malformed = |e: &dyn std::fmt::Display| ProofVersionError::Malformed(e.to_string());
+    match ProofFormat::of(bytes) {
+        Some(ProofFormat::Json) => serde_json::from_slice(bytes).map_err(|e| malformed(&e)),
+        Some(ProofFormat::Bincode) => {
+            let file: BinaryProofFile = bincode::deserialize(&bytes[PROOF_FILE_MAGIC.len()..])
+                .map_err(|e| malformed(&e))?;
+            let (proof, _) = decode_proof(&file.proof)?;
+            Ok(SP1ProofWithIO {
+                proof,
+                stdin: file.stdin,
+                stdout: file.stdout,
+            })
+        }
+        None => Err(ProofVersionError::Malformed(
+            "neither a JSON nor a bincode proof file".to_string(),
+        )),
+    }
+}
+
+/// A proof whose claims have not been checked, e.g. one read from disk or received over the
+/// network.
+///
+/// Its accessors only deserialize what the proof claims. Call `verify` to get a `VerifiedProof`
+/// before trusting them.
+pub struct UnverifiedProof<SC: StarkGenericConfig + Serialize + DeserializeOwned>(
+    SP1ProofWithIO<SC>,
+);
+
+/// A proof which has been verified against the ELF of its program.
+pub struct VerifiedProof<SC: StarkGenericConfig + Serialize + DeserializeOwned>(SP1ProofWithIO<SC>);
+
+/// The public values of the shard which ends the execution, if any.
+fn final_public_values<SC: StarkGenericConfig + Serialize + DeserializeOwned>(
+    proof: &SP1ProofWithIO<SC>,
+) -> Option<&PublicValues<u32>> {
+    proof
+        .proof
+        .shard_proofs
+        .iter()
+        .map(|shard_proof| &shard_proof.public_values)
+        .find(|public_values| public_values.halted != 0)
+}
+
+fn exit_code(public_values: &PublicValues<u32>) -> u32 {
+    u32::from_le_bytes(public_values.exit_code.0.map(|byte| byte as u8))
+}
+
+impl<SC: StarkGenericConfig + Serialize + DeserializeOwned> UnverifiedProof<SC> {
+    pub fn new(proof: SP1ProofWithIO<SC>) -> Self {
+        Self(proof)
+    }
+
+    /// Loads a proof saved with `SP1ProofWithIO::save` or `SP1ProofWithIO::save_as`, in either
+    /// format.
+    pub fn load(path: &str) -> Result<Self> {
+        Ok(Self(SP1ProofWithIO::load(path)?))
+    }
+
+    /// UNVERIFIED: the public values of the shard which ends the execution, as claimed by the
+    /// proof.
+    pub fn public_values(&self) -> Option<&PublicValues<u32>> {
+        final_public_values(&self.0)
+    }
+
+    /// UNVERIFIED: the exit code of the program, as claimed by the proof.
+    pub fn claimed_exit_code(&self) -> Option<u32> {
+        self.public_values().map(exit_code)
+    }
+
+    /// UNVERIFIED: the output of the program, as claimed by the proof.
+    pub fn claimed_stdout(&self) -> &SP1Stdout {
+        &self.0.stdout
+    }
+}
+
+impl UnverifiedProof<BabyBearBlake3> {
+    /// Verifies the proof against the ELF of its program.
+    pub fn verify(
+        self,
+        elf: &[u8],
+    ) -> Result<VerifiedProof<BabyBearBlake3>, ProgramVerificationError> {
+        SP1Verifier::verify(elf, &self.0)?;
+        Ok(VerifiedProof(self.0))
+    }
+
+    /// Verifies the proof against the ELF of its program and the context it must be bound to.
+    pub fn verify_with_context(
+        self,
+        elf: &[u8],
+        context: &[u8],
+    ) -> Result<VerifiedProof<BabyBearBlake3>, ProgramVerificationError> {
+        SP1Verifier::verify_with_context(elf, &self.0, context)?;
+        Ok(VerifiedProof(self.0))
+    }
+
+    /// Verifies the proof against the ELF of its program, proven with `SP1Prover::prove_optimized`.
+    pub fn verify_optimized(
+        self,
+        elf: &[u8],
+    ) -> Result<VerifiedProof<BabyBearBlake3>, ProgramVerificationError> {
+        SP1Verifier::verify_optimized(elf, &self.0)?;
+        Ok(VerifiedProof(self.0))
+    }
+}
+
+impl<SC: StarkGenericConfig + Serialize + DeserializeOwned> From<SP1ProofWithIO<SC>>
+    for UnverifiedProof<SC>
+{
+    fn from(proof: SP1ProofWithIO<SC>) -> Self {
+        Self(proof)
+    }
+}
+
+/// Describes a proof file in either format, or a proof encoded with bincode, without verifying it.
+///
+/// A truncated or corrupt file is described as far as it decodes, followed by what does not.
+#[cfg(feature = "perf")]
+pub fn inspect_proof(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+
+    let mut summary = String::new();
+    let proof_bytes = if bytes.first() == Some(&b'{') {
+        writeln!(summary, "JSON proof file, {} bytes", bytes.len()).unwrap();
+        if serde_json::from_slice::<serde_json::Value>(bytes).is_err() {
+            writeln!(summary, "warning: the JSON is truncated or corrupt").unwrap();
+        }
+        match json_proof_bytes(bytes) {
+            Some(proof_bytes) => proof_bytes,
+            None => {
+                write!(summary, "error: no hex encoded proof field").unwrap();
+                return summary;
+            }
+        }
+    } else if bytes.starts_with(&PROOF_FILE_MAGIC) {
+        writeln!(summary, "bincode proof file, {} bytes", bytes.len()).unwrap();
+        match bincode::deserialize::<Vec<u8>>(&bytes[PROOF_FILE_MAGIC.len()..]) {
+            Ok(proof_bytes) => without_version_header(&proof_bytes).to_vec(),
+            Err(e) => {
+                write!(summary, "error: the encoded proof does not decode: {}", e).unwrap();
+                return summary;
+            }
+        }
+    } else {
+        writeln!(summary, "binary proof file, {} bytes", bytes.len()).unwrap();
+        bytes.to_vec()
+    };
+    summary += &describe_encoded_proof(&proof_bytes);
+    summary
+}
+
+/// The bincode encoding of the proof in the `proof` field of a JSON proof file, up to the first
+/// character which is not a hex digit, without the header of its version.
+#[cfg(feature = "perf")]
+fn json_proof_bytes(json: &[u8]) -> Option<Vec<u8>> {
+    const KEY: &[u8] = b"\"proof\":\"";
+    let start = json.windows(KEY.len()).position(|window| window == KEY)? + KEY.len();
+    let hex_len = json[start..]
+        .iter()
+        .take_while(|byte| byte.is_ascii_hexdigit())
+        .count();
+    let bytes = hex::decode(&json[start..start + hex_len - hex_len % 2]).ok()?;
+    Some(without_version_header(&bytes).to_vec())
+}
+
+/// The bincode encoding of an encoded proof, without the header of its version.
+#[cfg(feature = "perf")]
+fn without_version_header(bytes: &[u8]) -> &[u8] {
+    let header_len =
+        crate::version::ProofVersion::of(bytes).map_or(0, |version| version.header_len());
+    &bytes[header_len.min(bytes.len())..]
+}
+
+/// Decodes a bincode encoded proof field by field, describing each shard which decodes.
+#[cfg(feature = "perf")]
+fn describe_encoded_proof(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+
+    use bincode::Options;
+    use serde::Deserialize;
+
+    use crate::stark::{ShardProof, Val, PROOF_DIGEST_SIZE};
+    use crate::utils::codec::{decode_field, FIELD_BYTES};
+
+    let mut summary = String::new();
+    let options = bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes();
+    let mut deserializer = bincode::Deserializer::from_slice(bytes, options);
+
+    let num_shards = match u64::deserialize(&mut deserializer) {
+        Ok(num_shards) => num_shards,
+        Err(e) => {
+            write!(
+                summary,
+                "error: the number of shards does not decode: {}",
+                e
+            )
+            .unwrap();
+            return summary;
+        }
+    };
+    writeln!(summary, "proof with {} shards", num_shards).unwrap();
+    for i in 0..num_shards {
+        match ShardProof::<BabyBearBlake3>::deserialize(&mut deserializer) {
+            Ok(shard_proof) => writeln!(summary, "{}", shard_proof).unwrap(),
+            Err(e) => {
+                write!(summary, "error: shard {} does not decode: {}", i, e).unwrap();
+                return summary;
+            }
+        }
+    }
+    let digest = <[[u8; FIELD_BYTES]; PROOF_DIGEST_SIZE]>::deserialize(&mut deserializer)
+        .map_err(|e| e.to_string())
+        .and_then(|encodings| {
+            encodings
+                .into_iter()
+                .map(decode_field::<Val<BabyBearBlake3>>)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string())
+        });
+    match digest {
+        Ok(digest) => writeln!(summary, "digest: {:?}", digest).unwrap(),
+        Err(e) => {
+            write!(summary, "error: the digest does not decode: {}", e).unwrap();
+            return summary;
+        }
+    }
+    match Vec::<u8>::deserialize(&mut deserializer) {
+        Ok(context) => write!(summary, "context: {}", hex::encode(context)).unwrap(),
+        Err(e) => write!(summary, "error: the context does not decode: {}", e).unwrap(),
+    }
+    summary
+}
+
+impl<SC: StarkGenericConfig + Serialize + DeserializeOwned> VerifiedProof<SC> {
+    /// The public values of the shard which ends the execution.
+    pub fn public_values(&self) -> Option<&PublicValues<u32>> {
+        final_public_values(&self.0)
+    }
+
+    /// The exit code of the program, if it halted.
+    pub fn exit_code(&self) -> Option<u32> {
+        self.public_values().map(exit_code)
+    }
+
+    /// The number of instructions executed by the program.
+    pub fn instruction_count(&self) -> u64 {
+        self.0.proof.instruction_count()
+    }
+
+    pub fn stdout(&self) -> &SP1Stdout {
+        &self.0.stdout
+    }
+
+    /// Saves the proof as a JSON to the given path.
+    pub fn save(&self, path: &str) -> Result<()> {
+        self.0.save(path)
+    }
+
+    /// Saves the proof in the given format to the given path.
+    pub fn save_as(&self, path: &str, format: ProofFormat) -> Result<()> {
+        self.0.save_as(path, format)
+    }
+
+    pub fn into_inner(self) -> SP1ProofWithIO<SC> {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_proof_file, encode_proof_file, ProofFormat, UnverifiedProof};
+    use crate::air::Word;
+    use crate::utils::{tests::FIBONACCI_IO_ELF, BabyBearBlake3};
+    use crate::{SP1ProofWithIO, SP1Prover, SP1Stdin, SP1Verifier};
+
+    #[test]
+    fn test_unverified_accessors_on_tampered_proof() {
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&3u32);
+        let proof = UnverifiedProof::from(SP1Prover::prove(FIBONACCI_IO_ELF, stdin).unwrap());
+        let exit_code = proof.claimed_exit_code().unwrap();
+
+        // Tamper with the exit code claimed by the final shard.
+        let mut tampered: SP1ProofWithIO<BabyBearBlake3> =
+            serde_json::from_str(&serde_json::to_string(&proof.0).unwrap()).unwrap();
+        let public_values = tampered
+            .proof
+            .shard_proofs
+            .iter_mut()
+            .map(|shard_proof| &mut shard_proof.public_values)
+            .find(|public_values| public_values.halted != 0)
+            .unwrap();
+        public_values.exit_code = Word((exit_code + 1).to_le_bytes().map(u32::from));
+
+        let tampered = UnverifiedProof::from(tampered);
+        assert_eq!(tampered.claimed_exit_code(), Some(exit_code + 1));
+        assert!(tampered.verify(FIBONACCI_IO_ELF).is_err());
+
+        let verified = proof.verify(FIBONACCI_IO_ELF).unwrap();
+        assert_eq!(verified.exit_code(), Some(exit_code));
+        assert!(verified.instruction_count() > 0);
+    }
+
+    #[test]
+    fn test_proof_file_formats() {
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&3u32);
+        let proof = SP1Prover::prove(FIBONACCI_IO_ELF, stdin).unwrap();
+
+        let json = encode_proof_file(&proof, ProofFormat::Json);
+        let binary = encode_proof_file(&proof, ProofFormat::Bincode);
+        assert_eq!(ProofFormat::of(&json), Some(ProofFormat::Json));
+        assert_eq!(ProofFormat::of(&binary), Some(ProofFormat::Bincode));
+        assert!(binary.len() < json.len());
+
+        for bytes in [json, binary] {
+            let decoded = decode_proof_file::<BabyBearBlake3>(&bytes).unwrap();
+            assert_eq!(decoded.stdout.buffer.data, proof.stdout.buffer.data);
+            SP1Verifier::verify(FIBONACCI_IO_ELF, &decoded).unwrap();
+        }
+
+        assert!(decode_proof_file::<BabyBearBlake3>(b"SP1F").is_err());
+        assert!(decode_proof_file::<BabyBearBlake3>(&[1, 2]).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "perf")]
+    fn test_inspect_proof() {
+        use super::inspect_proof;
+
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&3u32);
+        let proof = SP1Prover::prove(FIBONACCI_IO_ELF, stdin).unwrap();
+        let num_shards = proof.proof.shard_proofs.len();
+        let shards = proof
+            .proof
+            .shard_proofs
+            .iter()
+            .map(|shard_proof| shard_proof.to_string())
+            .collect::<Vec<_>>();
+
+        let json = serde_json::to_vec(&proof).unwrap();
+        let summary = inspect_proof(&json);
+        assert!(summary.starts_with(&format!(
+            "JSON proof file, {} bytes\nproof with {} shards\n",
+            json.len(),
+            num_shards
+        )));
+        assert!(shards.iter().all(|shard| summary.contains(shard.as_str())));
+        assert!(summary.contains(&format!("context: {}", hex::encode(&proof.proof.context))));
+        assert!(!summary.contains("error"));
+
+        let binary = bincode::serialize(&proof).unwrap();
+        let summary = inspect_proof(&binary);
+        assert!(summary.starts_with("binary proof file"));
+        assert!(shards.iter().all(|shard| summary.contains(shard.as_str())));
+
+        let file = encode_proof_file(&proof, ProofFormat::Bincode);
+        let summary = inspect_proof(&file);
+        assert!(summary.starts_with("bincode proof file"));
+        assert!(shards.iter().all(|shard| summary.contains(shard.as_str())));
+        assert!(!summary.contains("error"));
+
+        // A truncated file is described up to the shard which does not decode.
+        let summary = inspect_proof(&json[..json.len() / 2]);
+        assert!(summary.contains("warning: the JSON is truncated or corrupt"));
+        assert!(summary.contains(&format!("proof with {} shards", num_shards)));
+        assert!(summary.contains("does not decode"));
+
+        assert!(inspect_proof(b"{}").ends_with("error: no hex encoded proof field"));
+        assert!(inspect_proof(&[1, 2]).contains("the number of shards does not decode"));
+    }
+}
@@ -0,0 +1,37 @@
+use core::borrow::Borrow;
+use p3_air::{Air, BaseAir, PairBuilder};
+use p3_field::AbstractField;
+use p3_field::Field;
+use p3_matrix::MatrixRowSlices;
+
+use super::columns::{RangeCheckMultCols, RangeCheckPreprocessedCols, NUM_RANGE_CHECK_MULT_COLS};
+use super::{RangeCheckChip, RANGE_CHECK_BITS};
+use crate::air::SP1AirBuilder;
+
+impl<F: Field> BaseAir<F> for RangeCheckChip {
+    fn width(&self) -> usize {
+        NUM_RANGE_CHECK_MULT_COLS
+    }
+}
+
+impl<AB: SP1AirBuilder + PairBuilder> Air<AB> for RangeCheckChip {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let mults: &RangeCheckMultCols<AB::Var> = main.row_slice(0).borrow();
+        let preprocessed = builder.preprocessed();
+        let local: &RangeCheckPreprocessedCols<AB::Var> = preprocessed.row_slice(0).borrow();
+
+        // Receive the range checks of the values of each width.
+        for (i, bits) in RANGE_CHECK_BITS.iter().enumerate() {
+            builder.receive_range_check(
+                local.values[i],
+                AB::F::from_canonical_u32(*bits),
+                mults.multiplicities[i],
+            );
+        }
+
+        // Dummy constraint for normalizing to degree 3.
+        let value = local.values[0];
+        builder.assert_zero(value * value * value - value * value * value);
+    }
+}
@@ -0,0 +1,26 @@
+use core::borrow::Borrow;
+use core::borrow::BorrowMut;
+use sp1_derive::AlignedBorrow;
+use std::mem::size_of;
+
+use super::NUM_RANGE_CHECK_WIDTHS;
+
+/// The number of preprocessed trace columns for `RangeCheckChip`, which form the fixed table.
+pub const NUM_RANGE_CHECK_PREPROCESSED_COLS: usize = size_of::<RangeCheckPreprocessedCols<u8>>();
+
+/// The number of main trace columns for `RangeCheckChip`.
+pub const NUM_RANGE_CHECK_MULT_COLS: usize = size_of::<RangeCheckMultCols<u8>>();
+
+#[derive(Debug, Clone, Copy, AlignedBorrow)]
+#[repr(C)]
+pub struct RangeCheckPreprocessedCols<T> {
+    /// The values of each width of `RANGE_CHECK_BITS`.
+    pub values: [T; NUM_RANGE_CHECK_WIDTHS],
+}
+
+#[derive(Debug, Clone, Copy, AlignedBorrow)]
+#[repr(C)]
+pub struct RangeCheckMultCols<T> {
+    /// The number of range checks of each value.
+    pub multiplicities: [T; NUM_RANGE_CHECK_WIDTHS],
+}
@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+/// A range check event, checking that `value` fits in `bits` bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct RangeCheckEvent {
+    /// The value to check.
+    pub value: u32,
+
+    /// The number of bits the value must fit in.
+    pub bits: u32,
+}
+
+impl RangeCheckEvent {
+    /// Creates a new `RangeCheckEvent`.
+    pub fn new(value: u32, bits: u32) -> Self {
+        Self { value, bits }
+    }
+}
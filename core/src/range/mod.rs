@@ -0,0 +1,133 @@
+pub mod air;
+pub mod columns;
+pub mod event;
+pub mod trace;
+
+pub use event::RangeCheckEvent;
+
+use core::borrow::BorrowMut;
+use p3_field::Field;
+use p3_matrix::dense::RowMajorMatrix;
+
+use self::columns::{RangeCheckPreprocessedCols, NUM_RANGE_CHECK_PREPROCESSED_COLS};
+
+/// The number of bit widths the range check table supports.
+pub const NUM_RANGE_CHECK_WIDTHS: usize = 3;
+
+/// The bit widths the range check table supports.
+///
+/// The table has a row for every value of the largest width, so the widths stay within 16 bits
+/// to keep it the size of the byte table.
+pub const RANGE_CHECK_BITS: [u32; NUM_RANGE_CHECK_WIDTHS] = [8, 12, 16];
+
+/// The number of rows of the range check table.
+pub const NUM_RANGE_CHECK_ROWS: usize = 1 << 16;
+
+/// A chip for checking that values fit in a given number of bits.
+///
+/// The chip contains a preprocessed table of all the values of each supported bit width. Other
+/// chips can then look up a value with its width in this table, instead of decomposing the value
+/// into bytes in their own trace.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RangeCheckChip;
+
+impl RangeCheckChip {
+    /// Creates the range check table, the preprocessed trace of the chip.
+    ///
+    /// The column of the values of a width holds the index of the row modulo `2^bits`, so that
+    /// every row of the column is in range.
+    pub fn table<F: Field>() -> RowMajorMatrix<F> {
+        let mut trace = RowMajorMatrix::new(
+            vec![F::zero(); NUM_RANGE_CHECK_ROWS * NUM_RANGE_CHECK_PREPROCESSED_COLS],
+            NUM_RANGE_CHECK_PREPROCESSED_COLS,
+        );
+        for row_index in 0..NUM_RANGE_CHECK_ROWS {
+            let cols: &mut RangeCheckPreprocessedCols<F> = trace.row_mut(row_index).borrow_mut();
+            for (value, bits) in cols.values.iter_mut().zip(RANGE_CHECK_BITS) {
+                *value = F::from_canonical_usize(row_index & ((1 << bits) - 1));
+            }
+        }
+        trace
+    }
+
+    /// The row of the table holding the value of a range check, and the index of its width in
+    /// `RANGE_CHECK_BITS`, or `None` if the width is not supported or the value is out of range.
+    pub fn table_position(event: &RangeCheckEvent) -> Option<(usize, usize)> {
+        let index = RANGE_CHECK_BITS
+            .iter()
+            .position(|bits| *bits == event.bits)?;
+        (event.value < 1 << event.bits).then_some((event.value as usize, index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::borrow::Borrow;
+    use p3_baby_bear::BabyBear;
+    use p3_field::AbstractField;
+    use p3_matrix::MatrixRowSlices;
+
+    use super::*;
+    use crate::air::MachineAir;
+    use crate::field::event::FieldEvent;
+    use crate::field::FieldLtuChip;
+    use crate::lookup::{unbalanced_interactions, InteractionKind};
+    use crate::runtime::ExecutionRecord;
+    use crate::stark::{Chip, RiscvAir};
+    use crate::utils::BabyBearBlake3;
+
+    #[test]
+    fn test_table_position() {
+        let position =
+            |value, bits| RangeCheckChip::table_position(&RangeCheckEvent::new(value, bits));
+        assert_eq!(position(255, 8), Some((255, 0)));
+        assert_eq!(position(4095, 12), Some((4095, 1)));
+        assert_eq!(position(256, 8), None);
+        assert_eq!(position(1, 20), None);
+
+        // Every row holds a value of each width.
+        let table = RangeCheckChip::table::<BabyBear>();
+        let cols: &RangeCheckPreprocessedCols<BabyBear> = table.row_slice(4097).borrow();
+        assert_eq!(cols.values, [1, 1, 4097].map(BabyBear::from_canonical_u32));
+    }
+
+    #[test]
+    fn test_field_ltu_range_checks() {
+        let mut record = ExecutionRecord::default();
+        record.add_field_events(&[
+            FieldEvent::new(true, 3, 5),
+            FieldEvent::new(true, 0, 1 << 28),
+        ]);
+        let mut output = ExecutionRecord::default();
+        MachineAir::<BabyBear>::generate_trace(&FieldLtuChip, &record, &mut output);
+        record.append(&mut output);
+        assert_eq!(record.range_checks.len(), 4);
+
+        let chips = [
+            Chip::new(RiscvAir::<BabyBear>::FieldLTU(FieldLtuChip)),
+            Chip::new(RiscvAir::RangeCheck(RangeCheckChip)),
+        ];
+        assert!(unbalanced_interactions::<BabyBearBlake3>(
+            &chips,
+            &record,
+            vec![InteractionKind::Range]
+        )
+        .is_empty());
+
+        // A range check which no chip sends is unbalanced.
+        record.add_range_check(7, 8);
+        let unbalanced = unbalanced_interactions::<BabyBearBlake3>(
+            &chips,
+            &record,
+            vec![InteractionKind::Range],
+        );
+        assert_eq!(unbalanced.len(), 1);
+        assert_eq!(unbalanced[0].chip_name, "RangeCheck");
+        assert_eq!(unbalanced[0].row, 7);
+        assert_eq!(
+            unbalanced[0].values,
+            [7, 8].map(BabyBear::from_canonical_u32)
+        );
+        assert_eq!(unbalanced[0].multiplicity, -BabyBear::one());
+    }
+}
@@ -0,0 +1,58 @@
+use core::borrow::BorrowMut;
+use p3_field::Field;
+use p3_matrix::dense::RowMajorMatrix;
+
+use super::columns::{
+    RangeCheckMultCols, NUM_RANGE_CHECK_MULT_COLS, NUM_RANGE_CHECK_PREPROCESSED_COLS,
+};
+use super::{RangeCheckChip, NUM_RANGE_CHECK_ROWS};
+use crate::{
+    air::{EventKind, MachineAir},
+    runtime::{ExecutionRecord, Program},
+};
+
+impl<F: Field> MachineAir<F> for RangeCheckChip {
+    fn name(&self) -> String {
+        "RangeCheck".to_string()
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::RangeCheck]
+    }
+
+    fn num_real_rows(&self, _shard: &ExecutionRecord) -> usize {
+        NUM_RANGE_CHECK_ROWS
+    }
+
+    fn included(&self, shard: &ExecutionRecord) -> bool {
+        !shard.range_checks.is_empty()
+    }
+
+    fn preprocessed_width(&self) -> usize {
+        NUM_RANGE_CHECK_PREPROCESSED_COLS
+    }
+
+    fn generate_preprocessed_trace(&self, _program: &Program) -> Option<RowMajorMatrix<F>> {
+        Some(RangeCheckChip::table::<F>())
+    }
+
+    fn generate_trace(
+        &self,
+        input: &ExecutionRecord,
+        _output: &mut ExecutionRecord,
+    ) -> RowMajorMatrix<F> {
+        let mut trace = RowMajorMatrix::new(
+            vec![F::zero(); NUM_RANGE_CHECK_ROWS * NUM_RANGE_CHECK_MULT_COLS],
+            NUM_RANGE_CHECK_MULT_COLS,
+        );
+
+        for (event, mult) in input.range_checks.iter() {
+            let (row, index) = RangeCheckChip::table_position(event)
+                .unwrap_or_else(|| panic!("{:?} is not in the range check table", event));
+            let cols: &mut RangeCheckMultCols<F> = trace.row_mut(row).borrow_mut();
+            cols.multiplicities[index] += F::from_canonical_usize(*mult);
+        }
+
+        trace
+    }
+}
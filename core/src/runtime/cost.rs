@@ -0,0 +1,143 @@
+use p3_keccak_air::NUM_ROUNDS;
+
+use super::{ExecutionReport, Opcode, SyscallCode};
+use crate::syscall::precompiles::blake3::{OPERATION_COUNT, ROUND_COUNT};
+
+/// The number of clock cycles the runtime charges for instructions and syscalls.
+///
+/// The clock of a shard advances by `instruction_cost` for every instruction, and an `ecall`
+/// additionally advances it by the `syscall_cost` of the invoked syscall. The runtime checks every
+/// syscall against this table, so the model is exactly what a shard pays for.
+pub struct CostModel;
+
+impl CostModel {
+    /// The number of cycles of an instruction, excluding the syscall an `ecall` invokes.
+    ///
+    /// Every instruction costs the same: the clock moves by one for each of its memory accesses.
+    pub const fn instruction_cost(_opcode: Opcode) -> u32 {
+        4
+    }
+
+    /// The number of cycles a syscall adds to its `ecall`.
+    ///
+    /// Precompiles pay for the rows of their chips, which the clock must leave room for. Every
+    /// current syscall works on fixed-size inputs or is free, so the cost does not yet depend on
+    /// the number of bytes it reads or writes.
+    pub const fn syscall_cost(code: SyscallCode, _input_len: usize) -> u64 {
+        (match code {
+            SyscallCode::SHA_EXTEND => 48 * 20,
+            SyscallCode::SHA_COMPRESS => 8 * 4 + 64 * 4 + 8 * 4,
+            SyscallCode::KECCAK_PERMUTE => NUM_ROUNDS * 4,
+            SyscallCode::BLAKE3_COMPRESS_INNER => 4 * ROUND_COUNT * OPERATION_COUNT,
+            SyscallCode::ED_ADD
+            | SyscallCode::SECP256K1_ADD
+            | SyscallCode::SECP256K1_DOUBLE
+            | SyscallCode::MEMCPY
+            | SyscallCode::UINT256_MULMOD => 8,
+            SyscallCode::ED_DECOMPRESS | SyscallCode::SECP256K1_DECOMPRESS => 4,
+            SyscallCode::HALT
+            | SyscallCode::LWA
+            | SyscallCode::ENTER_UNCONSTRAINED
+            | SyscallCode::EXIT_UNCONSTRAINED
+            | SyscallCode::BITMANIP
+            | SyscallCode::GETENV
+            | SyscallCode::CYCLE_COUNT
+            | SyscallCode::COMMIT
+            | SyscallCode::WRITE => 0,
+        }) as u64
+    }
+
+    /// The category of an instruction in the cost breakdown of an execution report.
+    pub const fn instruction_category(opcode: Opcode) -> &'static str {
+        match opcode {
+            Opcode::ADD
+            | Opcode::SUB
+            | Opcode::XOR
+            | Opcode::OR
+            | Opcode::AND
+            | Opcode::SLL
+            | Opcode::SRL
+            | Opcode::SRA
+            | Opcode::SLT
+            | Opcode::SLTU => "arithmetic",
+            Opcode::LB | Opcode::LH | Opcode::LW | Opcode::LBU | Opcode::LHU => "load",
+            Opcode::SB | Opcode::SH | Opcode::SW => "store",
+            Opcode::BEQ | Opcode::BNE | Opcode::BLT | Opcode::BGE | Opcode::BLTU | Opcode::BGEU => {
+                "branch"
+            }
+            Opcode::JAL | Opcode::JALR | Opcode::AUIPC => "jump",
+            Opcode::ECALL | Opcode::EBREAK | Opcode::UNIMP => "system",
+            Opcode::MUL
+            | Opcode::MULH
+            | Opcode::MULHU
+            | Opcode::MULHSU
+            | Opcode::DIV
+            | Opcode::DIVU
+            | Opcode::REM
+            | Opcode::REMU => "multiplication",
+        }
+    }
+
+    /// The number of cycles of the execution summarized by the report, predicted from its
+    /// instruction and syscall counts.
+    pub fn estimate_program(report: &ExecutionReport) -> u64 {
+        let instructions = report
+            .opcode_counts
+            .iter()
+            .map(|(opcode, count)| Self::instruction_cost(*opcode) as u64 * count)
+            .sum::<u64>();
+        let syscalls = report
+            .syscall_counts
+            .iter()
+            .map(|(code, count)| {
+                let input_len = report.syscall_bytes.get(code).copied().unwrap_or(0) / count;
+                Self::syscall_cost(*code, input_len as usize) * count
+            })
+            .sum::<u64>();
+        instructions + syscalls
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CostModel;
+    use crate::runtime::tests::{fibonacci_program, simple_program};
+    use crate::runtime::{Opcode, Program, Runtime, SyscallCode};
+    use crate::syscall::precompiles::keccak256::permute_tests::keccak_permute_program;
+    use crate::syscall::precompiles::sha256::extend_tests::sha_extend_program;
+
+    fn run(program: Program) -> Runtime {
+        let mut runtime = Runtime::new(program);
+        // Keep the whole execution in one shard, whose clock is never reset.
+        runtime.shard_size = 1 << 30;
+        runtime.run();
+        runtime
+    }
+
+    #[test]
+    fn test_cost_model_matches_runtime() {
+        for program in [
+            simple_program(),
+            fibonacci_program(),
+            keccak_permute_program(),
+            sha_extend_program(),
+        ] {
+            let runtime = run(program);
+            // The clock of the single shard starts at one.
+            assert_eq!(runtime.report.cycles, runtime.state.clk as u64 - 1);
+            assert_eq!(
+                CostModel::estimate_program(&runtime.report),
+                runtime.report.cycles
+            );
+        }
+    }
+
+    #[test]
+    fn test_keccak_cost() {
+        let runtime = run(keccak_permute_program());
+        let permutations = runtime.report.syscall_counts[&SyscallCode::KECCAK_PERMUTE];
+        let instructions = runtime.state.global_clk as u64;
+        assert_eq!(runtime.report.opcode_counts[&Opcode::ECALL], permutations);
+        assert_eq!(runtime.report.cycles, 4 * instructions + 96 * permutations);
+    }
+}
@@ -0,0 +1,56 @@
+use super::SyscallCode;
+
+/// An error which stops the execution of a program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecutionError {
+    /// A store below the stack floor of the program. The function is the one containing the
+    /// store, if the program has symbols.
+    StackOverflow {
+        pc: u32,
+        sp: u32,
+        function: Option<String>,
+    },
+    /// The program halted without reading the last `unread` bytes of its input.
+    LeftoverInput { unread: usize },
+    /// The wall-clock timeout elapsed after `cycles_executed` instructions.
+    Timeout { cycles_executed: u64 },
+    /// An `ECALL` invoked a syscall with arguments it cannot execute, e.g. a misaligned pointer.
+    InvalidSyscall {
+        pc: u32,
+        syscall: SyscallCode,
+        reason: String,
+    },
+}
+
+impl std::fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExecutionError::StackOverflow { pc, sp, function } => {
+                write!(f, "stack overflow at pc {:#x}", pc)?;
+                if let Some(function) = function {
+                    write!(f, " in {}", function)?;
+                }
+                write!(f, " with sp {:#x}", sp)
+            }
+            ExecutionError::LeftoverInput { unread } => {
+                write!(f, "halted with {} bytes of input unread", unread)
+            }
+            ExecutionError::Timeout { cycles_executed } => {
+                write!(f, "timed out after {} cycles", cycles_executed)
+            }
+            ExecutionError::InvalidSyscall {
+                pc,
+                syscall,
+                reason,
+            } => {
+                write!(
+                    f,
+                    "invalid {:?} syscall at pc {:#x}: {}",
+                    syscall, pc, reason
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExecutionError {}
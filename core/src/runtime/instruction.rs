@@ -1,9 +1,11 @@
 use core::fmt::Debug;
 
+use serde::{Deserialize, Serialize};
+
 use super::Opcode;
 
 /// An instruction specifies an operation to execute and the operands.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Instruction {
     pub opcode: Opcode,
     pub op_a: u32,
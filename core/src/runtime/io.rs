@@ -1,8 +1,11 @@
 use serde::de::DeserializeOwned;
 use serde::Serialize;
-use std::io::Read;
+use std::cell::RefCell;
+use std::io::{Read, Write};
+use std::rc::Rc;
 
 use super::Runtime;
+use crate::syscall::FD_PUBLIC_OUTPUT;
 
 impl Read for Runtime {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
@@ -22,6 +25,11 @@ impl Runtime {
         self.state.input_stream.extend(input);
     }
 
+    /// Sets the environment variable `key`, which the program can look up with `GETENV`.
+    pub fn set_env(&mut self, key: &[u8], value: &[u8]) {
+        self.env.insert(key.to_vec(), value.to_vec());
+    }
+
     pub fn read_stdout<T: DeserializeOwned>(&mut self) -> T {
         let result = bincode::deserialize_from::<_, T>(self);
         result.unwrap()
@@ -35,13 +43,36 @@ impl Runtime {
         buf.copy_from_slice(&self.state.output_stream[start..end]);
         self.state.output_stream_ptr = end;
     }
+
+    /// Forwards the bytes the program writes to the file descriptor `fd` to `sink`.
+    ///
+    /// The public output and hint streams keep their meaning and are mirrored to their sinks.
+    /// Writes to any file descriptor past the hint stream fail with `EBADF` in the program
+    /// unless a sink is registered for it.
+    pub fn with_output_stream(mut self, fd: u32, sink: impl Write + 'static) -> Self {
+        assert!(fd >= FD_PUBLIC_OUTPUT, "fd {} is reserved", fd);
+        self.output_sinks.insert(fd, Rc::new(RefCell::new(sink)));
+        self
+    }
+
+    /// Returns all bytes the program has written to the file descriptor `fd` so far.
+    pub fn output(&self, fd: u32) -> &[u8] {
+        if fd == FD_PUBLIC_OUTPUT {
+            return &self.state.output_stream;
+        }
+        self.state
+            .fd_outputs
+            .get(&fd)
+            .map_or(&[], |bytes| bytes.as_slice())
+    }
 }
 
 #[cfg(test)]
 pub mod tests {
     use super::*;
-    use crate::runtime::Program;
-    use crate::utils::tests::IO_ELF;
+    use crate::runtime::{Instruction, Opcode, Program, Register};
+    use crate::syscall::EBADF;
+    use crate::utils::tests::{FIBONACCI_IO_ELF, IO_ELF};
     use crate::utils::{self, prove_core, BabyBearBlake3};
     use serde::Deserialize;
 
@@ -97,6 +128,120 @@ pub mod tests {
         runtime.write_stdin(&points.1);
         runtime.run();
         let config = BabyBearBlake3::new();
-        prove_core(config, runtime);
+        prove_core(config, runtime).unwrap();
+    }
+
+    #[test]
+    fn test_reset_run() {
+        utils::setup_logger();
+        let program = Program::from(FIBONACCI_IO_ELF);
+        let mut runtime = Runtime::new(program.clone());
+        runtime.write_stdin(&10u32);
+        runtime.run();
+        let a = runtime.read_stdout::<u32>();
+        let b = runtime.read_stdout::<u32>();
+        assert_eq!((a, b), (34, 55));
+
+        let mut input = Vec::new();
+        bincode::serialize_into(&mut input, &20u32).unwrap();
+        runtime.reset(&input);
+        runtime.run();
+        let a = runtime.read_stdout::<u32>();
+        let b = runtime.read_stdout::<u32>();
+        assert_eq!((a, b), (4181, 6765));
+
+        // The record must match the one of a fresh execution.
+        let mut fresh = Runtime::new(program);
+        fresh.write_stdin(&20u32);
+        fresh.run();
+        assert_eq!(runtime.state.global_clk, fresh.state.global_clk);
+        assert_eq!(
+            runtime.record.cpu_events.len(),
+            fresh.record.cpu_events.len()
+        );
+        assert_eq!(
+            runtime.record.last_memory_record.len(),
+            fresh.record.last_memory_record.len()
+        );
+
+        // A clone executes independently of the original.
+        let mut clone = runtime.clone();
+        clone.reset(&input);
+        clone.run();
+        assert_eq!(clone.state.global_clk, fresh.state.global_clk);
+    }
+
+    /// A shared buffer which can be registered as an output sink and inspected afterwards.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Writes `word` to each of `fds` in turn, saving the result of the i-th write in x(20 + i).
+    fn write_fds_program(word: u32, fds: &[u32]) -> Program {
+        let addr = 0x1000;
+        let mut instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, word, false, true),
+            Instruction::new(Opcode::SW, 29, 0, addr, false, true),
+        ];
+        for (i, &fd) in fds.iter().enumerate() {
+            instructions.extend([
+                Instruction::new(Opcode::ADD, 5, 0, 999, false, true),
+                Instruction::new(Opcode::ADD, 10, 0, fd, false, true),
+                Instruction::new(Opcode::ADD, 11, 0, addr, false, true),
+                Instruction::new(Opcode::ADD, 12, 0, 4, false, true),
+                Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+                Instruction::new(Opcode::ADD, 20 + i as u32, 10, 0, false, true),
+            ]);
+        }
+        Program::new(instructions, 0, 0)
+    }
+
+    #[test]
+    fn test_write_fds() {
+        let word = u32::from_le_bytes(*b"sp1!");
+        let sink = SharedBuffer::default();
+        let program = write_fds_program(word, &[3, 4, 5, 6, 0]);
+        let mut runtime = Runtime::new(program).with_output_stream(5, sink.clone());
+        runtime.run();
+
+        assert_eq!(runtime.output(3), b"sp1!");
+        assert_eq!(runtime.output(4), b"sp1!");
+        assert_eq!(runtime.state.input_stream, b"sp1!");
+        assert_eq!(runtime.output(5), b"sp1!");
+        assert_eq!(sink.0.borrow().as_slice(), b"sp1!");
+        assert_eq!(runtime.output(6), b"");
+
+        assert_eq!(runtime.register(Register::X20), 0);
+        assert_eq!(runtime.register(Register::X21), 0);
+        assert_eq!(runtime.register(Register::X22), 0);
+        assert_eq!(runtime.register(Register::X23), EBADF);
+        assert_eq!(runtime.register(Register::X24), EBADF);
+    }
+
+    #[test]
+    fn test_write_fds_sink_mirrors_public_output() {
+        let word = u32::from_le_bytes(*b"abcd");
+        let sink = SharedBuffer::default();
+        let program = write_fds_program(word, &[3, 3]);
+        let mut runtime = Runtime::new(program).with_output_stream(3, sink.clone());
+        runtime.run();
+        assert_eq!(runtime.output(3), b"abcdabcd");
+        assert_eq!(sink.0.borrow().as_slice(), b"abcdabcd");
+
+        // The sink survives a reset, while the recorded output does not.
+        runtime.reset(&[]);
+        assert_eq!(runtime.output(3), b"");
+        runtime.run();
+        assert_eq!(sink.0.borrow().len(), 16);
     }
 }
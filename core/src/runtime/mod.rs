@@ -1,35 +1,53 @@
+mod cost;
+mod error;
 mod instruction;
 mod io;
+mod observer;
 mod opcode;
+mod profiler;
 mod program;
 mod record;
 mod register;
+mod report;
+mod snapshot;
 mod state;
 mod syscall;
+pub mod syscall_codes;
+mod watchdog;
 
 use crate::cpu::{MemoryReadRecord, MemoryRecord, MemoryWriteRecord};
 use crate::utils::env;
 use crate::{alu::AluEvent, cpu::CpuEvent};
+pub use cost::*;
+pub use error::*;
 use hashbrown::hash_map::Entry;
 pub use instruction::*;
 use nohash_hasher::BuildNoHashHasher;
+pub use observer::*;
 pub use opcode::*;
+pub use profiler::*;
 pub use program::*;
 pub use record::*;
 pub use register::*;
+pub use report::*;
+pub use snapshot::*;
 pub use state::*;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::BufWriter;
 use std::io::Write;
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
 pub use syscall::*;
 
 use p3_baby_bear::BabyBear;
 use p3_field::AbstractField;
 
 use self::state::ExecutionState;
+use self::watchdog::{Watchdog, TIMEOUT_CHECK_INTERVAL};
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum AccessPosition {
@@ -61,7 +79,7 @@ pub struct Runtime {
     /// The record for the current CPU opcode containing relevant events.
     pub cpu_record: CpuRecord,
 
-    /// The maximum size of each shard.
+    /// The maximum number of instructions of each shard.
     pub shard_size: u32,
 
     /// A counter for the number of cycles that have been executed in certain functions.
@@ -78,6 +96,67 @@ pub struct Runtime {
     pub(crate) unconstrained_state: ForkState,
 
     pub syscall_map: HashMap<SyscallCode, Rc<dyn Syscall>>,
+
+    /// The configuration for logging the invoked syscalls.
+    pub syscall_logging: SyscallLogging,
+
+    /// A summary of the execution so far.
+    pub report: ExecutionReport,
+
+    /// The host sinks which the writes to custom file descriptors are forwarded to.
+    pub(crate) output_sinks: HashMap<u32, Rc<RefCell<dyn Write>>>,
+
+    /// The environment variables which the program can look up with `GETENV`.
+    pub env: BTreeMap<Vec<u8>, Vec<u8>>,
+
+    /// Whether the execution fails with `ExecutionError::LeftoverInput` if the program halts
+    /// without reading all of its input.
+    pub exact_input: bool,
+
+    /// The wall-clock time after which `try_run` stops the execution with
+    /// `ExecutionError::Timeout`. It is checked every 1024 instructions, between two of them.
+    pub timeout: Option<Duration>,
+
+    /// The observers called before each instruction, in order.
+    pub observers: Vec<Rc<dyn ExecutionObserver>>,
+
+    /// The hook called with each instruction executed outside of unconstrained blocks.
+    instruction_hook: Option<InstructionHook>,
+
+    /// The hook called with each syscall executed outside of unconstrained blocks.
+    syscall_hook: Option<SyscallHook>,
+
+    /// The most extra cycles any syscall takes, computed when the execution starts.
+    max_syscall_cycles: u32,
+}
+
+impl Clone for Runtime {
+    /// Clone the runtime, sharing the program and the output sinks. The clone does not write to the
+    /// trace file nor call the instruction and syscall hooks.
+    fn clone(&self) -> Self {
+        Self {
+            program: self.program.clone(),
+            state: self.state.clone(),
+            record: self.record.clone(),
+            cpu_record: self.cpu_record,
+            shard_size: self.shard_size,
+            cycle_tracker: self.cycle_tracker.clone(),
+            trace_buf: None,
+            unconstrained: self.unconstrained,
+            unconstrained_state: self.unconstrained_state.clone(),
+            syscall_map: self.syscall_map.clone(),
+            syscall_logging: self.syscall_logging.clone(),
+            report: self.report.clone(),
+            output_sinks: self.output_sinks.clone(),
+            env: self.env.clone(),
+            exact_input: self.exact_input,
+            timeout: self.timeout,
+            observers: self.observers.clone(),
+            instruction_hook: None,
+            syscall_hook: None,
+            max_syscall_cycles: self.max_syscall_cycles,
+        }
+    }
 }
 
 impl Runtime {
@@ -101,15 +180,56 @@ impl Runtime {
             state: ExecutionState::new(program_arc.pc_start),
             program: program_arc,
             cpu_record: CpuRecord::default(),
-            shard_size: env::shard_size() as u32 * 4,
+            shard_size: env::shard_size() as u32,
             cycle_tracker: HashMap::new(),
             trace_buf,
             unconstrained: false,
             unconstrained_state: ForkState::default(),
             syscall_map: default_syscall_map(),
+            syscall_logging: SyscallLogging::default(),
+            report: ExecutionReport::default(),
+            output_sinks: HashMap::new(),
+            env: BTreeMap::new(),
+            exact_input: false,
+            timeout: None,
+            observers: Vec::new(),
+            instruction_hook: None,
+            syscall_hook: None,
+            max_syscall_cycles: 0,
         }
     }
 
+    /// Calls the hook with each instruction executed from now on, its program counter and the
+    /// global clock, replacing the previous hook. Instructions in unconstrained blocks, which are
+    /// not proven, are skipped.
+    pub fn set_instruction_hook(&mut self, hook: InstructionHook) {
+        self.instruction_hook = Some(hook);
+    }
+
+    /// Calls the hook with each syscall executed from now on, its arguments and the clock of the
+    /// shard, replacing the previous hook. Syscalls in unconstrained blocks are skipped.
+    pub fn set_syscall_hook(&mut self, hook: SyscallHook) {
+        self.syscall_hook = Some(hook);
+    }
+
+    /// Reset the runtime so that the same program can be executed again with a new input.
+    ///
+    /// Registers, memory, events, clocks and streams are cleared, while the program and its memory
+    /// image are reused without being copied. Registered output sinks and the environment are kept.
+    pub fn reset(&mut self, stdin: &[u8]) {
+        self.state = ExecutionState::new(self.program.pc_start);
+        self.record = ExecutionRecord {
+            program: self.program.clone(),
+            ..Default::default()
+        };
+        self.cpu_record = CpuRecord::default();
+        self.cycle_tracker.clear();
+        self.report = ExecutionReport::default();
+        self.unconstrained = false;
+        self.unconstrained_state = ForkState::default();
+        self.write_stdin_slice(stdin);
+    }
+
     /// Get the current values of the registers.
     pub fn registers(&self) -> [u32; 32] {
         let mut registers = [0; 32];
@@ -287,6 +407,36 @@ impl Runtime {
         self.mw_cpu(register as u32, value, AccessPosition::A)
     }
 
+    /// Read from register in the memory access of the CPU event, for a syscall which takes a
+    /// second argument besides the one in `a0`.
+    pub fn rr_memory(&mut self, register: Register) -> u32 {
+        let record = self.mr(
+            register as u32,
+            self.current_shard(),
+            self.clk_from_position(&AccessPosition::Memory),
+        );
+        if !self.unconstrained {
+            assert!(self.cpu_record.memory.is_none());
+            self.cpu_record.memory = Some(record.into());
+        }
+        record.value
+    }
+
+    /// Write to register in the memory access of the CPU event, for a syscall which returns a
+    /// second word besides the one written to `a0`.
+    pub fn rw_memory(&mut self, register: Register, value: u32) {
+        let record = self.mw(
+            register as u32,
+            value,
+            self.current_shard(),
+            self.clk_from_position(&AccessPosition::Memory),
+        );
+        if !self.unconstrained {
+            assert!(self.cpu_record.memory.is_none());
+            self.cpu_record.memory = Some(record.into());
+        }
+    }
+
     /// Emit a CPU event.
     #[allow(clippy::too_many_arguments)]
     fn emit_cpu(
@@ -294,6 +444,7 @@ impl Runtime {
         shard: u32,
         clk: u32,
         pc: u32,
+        next_pc: u32,
         instruction: Instruction,
         a: u32,
         b: u32,
@@ -304,7 +455,9 @@ impl Runtime {
         let cpu_event = CpuEvent {
             shard,
             clk,
+            global_clk: self.state.global_clk,
             pc,
+            next_pc,
             instruction,
             a,
             a_record: record.a,
@@ -327,33 +480,7 @@ impl Runtime {
             b,
             c,
         };
-        match opcode {
-            Opcode::ADD => {
-                self.record.add_events.push(event);
-            }
-            Opcode::SUB => {
-                self.record.sub_events.push(event);
-            }
-            Opcode::XOR | Opcode::OR | Opcode::AND => {
-                self.record.bitwise_events.push(event);
-            }
-            Opcode::SLL => {
-                self.record.shift_left_events.push(event);
-            }
-            Opcode::SRL | Opcode::SRA => {
-                self.record.shift_right_events.push(event);
-            }
-            Opcode::SLT | Opcode::SLTU => {
-                self.record.lt_events.push(event);
-            }
-            Opcode::MUL | Opcode::MULHU | Opcode::MULHSU | Opcode::MULH => {
-                self.record.mul_events.push(event);
-            }
-            Opcode::DIVU | Opcode::REMU | Opcode::DIV | Opcode::REM => {
-                self.record.divrem_events.push(event);
-            }
-            _ => {}
-        }
+        self.record.add_alu_event(event);
     }
 
     /// Fetch the destination register and input operand values for an ALU instruction.
@@ -425,6 +552,35 @@ impl Runtime {
         self.program.instructions[idx]
     }
 
+    /// Log the invocation of a syscall according to the syscall logging configuration.
+    fn log_syscall(&self, code: SyscallCode) {
+        let a0 = self.register(Register::X10);
+        let a1 = self.register(Register::X11);
+        let entry = self
+            .syscall_logging
+            .entry(code, self.state.clk, [a0, a1], || {
+                if a0 % 4 != 0 {
+                    return Vec::new();
+                }
+                (0..SYSCALL_LOG_SNIPPET_WORDS as u32)
+                    .map(|i| self.word(a0.wrapping_add(i * 4)))
+                    .collect()
+            });
+        if let Some(entry) = entry {
+            match (entry.args, entry.snippet) {
+                (Some(args), Some(snippet)) => tracing::info!(
+                    code = ?entry.code,
+                    clk = entry.clk,
+                    a0 = args[0],
+                    a1 = args[1],
+                    snippet = ?snippet,
+                    "syscall"
+                ),
+                _ => tracing::info!(code = ?entry.code, clk = entry.clk, "syscall"),
+            }
+        }
+    }
+
     fn get_syscall(&mut self, code: SyscallCode) -> Option<&Rc<dyn Syscall>> {
         self.syscall_map.get(&code)
     }
@@ -648,6 +804,12 @@ impl Runtime {
                 let a0 = Register::X10;
                 let syscall_id = self.register(t0);
                 let syscall = SyscallCode::from_u32(syscall_id);
+                self.log_syscall(syscall);
+                if self.syscall_hook.is_some() && !self.unconstrained {
+                    let args = [self.register(a0), self.register(Register::X11)];
+                    let clk = self.state.clk;
+                    self.syscall_hook.as_mut().unwrap()(syscall, args, clk);
+                }
 
                 let init_clk = self.state.clk;
                 let syscall_impl = self.get_syscall(syscall).cloned();
@@ -656,8 +818,16 @@ impl Runtime {
                 if let Some(syscall_impl) = syscall_impl {
                     a = syscall_impl.execute(&mut precompile_rt);
                     next_pc = precompile_rt.next_pc;
+                    let bytes_touched = precompile_rt.bytes_touched;
                     self.state.clk = precompile_rt.clk;
+                    self.report.record_syscall(syscall, bytes_touched);
                     assert_eq!(init_clk + syscall_impl.num_extra_cycles(), self.state.clk);
+                    assert_eq!(
+                        CostModel::syscall_cost(syscall, bytes_touched as usize),
+                        syscall_impl.num_extra_cycles() as u64,
+                        "the cost model of {:?} is out of date",
+                        syscall
+                    );
                 } else {
                     panic!("Unsupported syscall: {:?}", syscall);
                 }
@@ -667,6 +837,7 @@ impl Runtime {
                 // TODO: fix this.
                 self.rw(a0, a);
                 (b, c) = (self.rr(t0, AccessPosition::B), 0);
+                memory_store_value = self.cpu_record.memory.map(|record| record.value());
             }
 
             Opcode::EBREAK => {
@@ -745,6 +916,7 @@ impl Runtime {
             self.current_shard(),
             self.state.clk,
             pc,
+            next_pc,
             instruction,
             a,
             b,
@@ -754,83 +926,198 @@ impl Runtime {
         );
     }
 
-    /// Execute the program.
+    /// Execute the program, panicking if it fails.
     pub fn run(&mut self) {
+        if let Err(err) = self.try_run() {
+            panic!("{}", err);
+        }
+    }
+
+    /// Execute the program.
+    ///
+    /// If the program has a memory layout, a store below its stack floor stops the execution with
+    /// `ExecutionError::StackOverflow` before memory is corrupted. With `exact_input`, halting
+    /// with unread input fails with `ExecutionError::LeftoverInput`. Once the `timeout` elapses,
+    /// the execution stops with `ExecutionError::Timeout`, with the events of the instructions
+    /// executed so far.
+    pub fn try_run(&mut self) -> Result<(), ExecutionError> {
+        self.initialize();
+        let watchdog = self.timeout.map(Watchdog::start);
+        while !self.halted() {
+            if let Some(ref watchdog) = watchdog {
+                if self.state.global_clk % TIMEOUT_CHECK_INTERVAL == 0 && watchdog.expired() {
+                    return Err(ExecutionError::Timeout {
+                        cycles_executed: self.state.global_clk as u64,
+                    });
+                }
+            }
+            self.step()?;
+        }
+        self.finalize()
+    }
+
+    /// Load the memory image and start the clock, before the first `step` of an execution.
+    pub fn initialize(&mut self) {
         tracing::info_span!("load memory").in_scope(|| {
             // First load the memory image into the memory table.
-            for (addr, value) in self.program.memory_image.iter() {
+            for (addr, value) in self.program.initial_memory().iter() {
                 self.state.memory.insert(*addr, (*value, 0, 0));
             }
         });
 
-        let max_syscall_cycles = self.max_syscall_cycles();
+        self.max_syscall_cycles = self.max_syscall_cycles();
 
         self.state.clk += 1;
-        while self.state.pc.wrapping_sub(self.program.pc_base)
-            < (self.program.instructions.len() * 4) as u32
-        {
-            // Fetch the instruction at the current program counter.
-            let instruction = self.fetch();
+    }
 
-            if let Some(ref mut buf) = self.trace_buf {
-                if !self.unconstrained {
-                    buf.write_all(&u32::to_be_bytes(self.state.pc)).unwrap();
-                }
+    /// Whether the program executed the halt syscall or its program counter left the program,
+    /// which ends the execution.
+    pub fn halted(&self) -> bool {
+        self.state.halted
+            || self.state.pc.wrapping_sub(self.program.pc_base)
+                >= (self.program.instructions.len() * 4) as u32
+    }
+
+    /// Execute the instruction at the current program counter, after calling the observers.
+    pub fn step(&mut self) -> Result<(), ExecutionError> {
+        if !self.observers.is_empty() {
+            for observer in self.observers.clone() {
+                observer.before_instruction(self);
             }
+        }
 
-            let width = 12;
-            log::trace!(
-                "clk={} [pc=0x{:x?}] {:<width$?} |         x0={:<width$} x1={:<width$} x2={:<width$} x3={:<width$} x4={:<width$} x5={:<width$} x6={:<width$} x7={:<width$} x8={:<width$} x9={:<width$} x10={:<width$} x11={:<width$} x12={:<width$} x13={:<width$} x14={:<width$} x15={:<width$} x16={:<width$} x17={:<width$} x18={:<width$}",
-                self.state.global_clk,
-                self.state.pc,
-                instruction,
-                self.register(Register::X0),
-                self.register(Register::X1),
-                self.register(Register::X2),
-                self.register(Register::X3),
-                self.register(Register::X4),
-                self.register(Register::X5),
-                self.register(Register::X6),
-                self.register(Register::X7),
-                self.register(Register::X8),
-                self.register(Register::X9),
-                self.register(Register::X10),
-                self.register(Register::X11),
-                self.register(Register::X12),
-                self.register(Register::X13),
-                self.register(Register::X14),
-                self.register(Register::X15),
-                self.register(Register::X16),
-                self.register(Register::X17),
-                self.register(Register::X18),
-            );
+        // Fetch the instruction at the current program counter.
+        let instruction = self.fetch();
 
-            // Execute the instruction.
-            self.execute(instruction);
+        if let Some(ref mut buf) = self.trace_buf {
+            if !self.unconstrained {
+                buf.write_all(&u32::to_be_bytes(self.state.pc)).unwrap();
+            }
+        }
+        if let Some(hook) = self.instruction_hook.as_mut() {
+            if !self.unconstrained {
+                hook(&instruction, self.state.pc, self.state.global_clk);
+            }
+        }
 
-            // Increment the clock.
-            self.state.global_clk += 1;
-            self.state.clk += 4;
+        let width = 12;
+        log::trace!(
+            "clk={} [pc=0x{:x?}] {:<width$?} |         x0={:<width$} x1={:<width$} x2={:<width$} x3={:<width$} x4={:<width$} x5={:<width$} x6={:<width$} x7={:<width$} x8={:<width$} x9={:<width$} x10={:<width$} x11={:<width$} x12={:<width$} x13={:<width$} x14={:<width$} x15={:<width$} x16={:<width$} x17={:<width$} x18={:<width$}",
+            self.state.global_clk,
+            self.state.pc,
+            instruction,
+            self.register(Register::X0),
+            self.register(Register::X1),
+            self.register(Register::X2),
+            self.register(Register::X3),
+            self.register(Register::X4),
+            self.register(Register::X5),
+            self.register(Register::X6),
+            self.register(Register::X7),
+            self.register(Register::X8),
+            self.register(Register::X9),
+            self.register(Register::X10),
+            self.register(Register::X11),
+            self.register(Register::X12),
+            self.register(Register::X13),
+            self.register(Register::X14),
+            self.register(Register::X15),
+            self.register(Register::X16),
+            self.register(Register::X17),
+            self.register(Register::X18),
+        );
 
-            // If there's not enough cycles left for another instruction, move to the next shard.
-            // We multiply by 4 because clk is incremented by 4 for each normal instruction.
-            if !self.unconstrained && max_syscall_cycles + self.state.clk >= self.shard_size * 4 {
-                self.state.current_shard += 1;
-                self.state.clk = 0;
-            }
+        if let Some(layout) = self.program.memory_layout {
+            self.check_stack(instruction, layout)?;
+        }
+        self.check_syscall(instruction)?;
+
+        // Execute the instruction.
+        let clk = self.state.clk;
+        self.execute(instruction);
+
+        // Increment the clock.
+        self.state.global_clk += 1;
+        self.state.clk += CostModel::instruction_cost(instruction.opcode);
+        self.report
+            .record_instruction(instruction.opcode, (self.state.clk - clk) as u64);
+
+        // If there's not enough cycles left for another instruction, move to the next shard.
+        // We multiply by 4 because clk is incremented by 4 for each normal instruction.
+        if !self.unconstrained
+            && self.max_syscall_cycles + self.state.clk >= self.shard_size.saturating_mul(4)
+        {
+            self.state.current_shard += 1;
+            self.state.clk = 0;
         }
+        Ok(())
+    }
+
+    /// Check the input was read as required and set up the global tables, once the program halted.
+    pub fn finalize(&mut self) -> Result<(), ExecutionError> {
         if let Some(ref mut buf) = self.trace_buf {
             buf.flush().unwrap();
         }
 
+        if self.exact_input && self.unread_input() > 0 {
+            return Err(ExecutionError::LeftoverInput {
+                unread: self.unread_input(),
+            });
+        }
+
         // Call postprocess to set up all variables needed for global accounts, like memory
         // argument or any other deferred tables.
         tracing::info_span!("postprocess").in_scope(|| self.postprocess());
+        Ok(())
+    }
+
+    /// The number of bytes of the input stream the program has not read.
+    pub fn unread_input(&self) -> usize {
+        self.state.input_stream.len() - self.state.input_stream_ptr
+    }
+
+    /// Check that the instruction does not store below the stack floor of the layout.
+    fn check_stack(
+        &self,
+        instruction: Instruction,
+        layout: MemoryLayout,
+    ) -> Result<(), ExecutionError> {
+        if !matches!(instruction.opcode, Opcode::SB | Opcode::SH | Opcode::SW) {
+            return Ok(());
+        }
+        let (_, base, offset) = instruction.s_type();
+        let addr = self.register(base).wrapping_add(offset);
+        if addr >= layout.stack_floor() {
+            return Ok(());
+        }
+        Err(ExecutionError::StackOverflow {
+            pc: self.state.pc,
+            sp: self.register(Register::X2),
+            function: self.program.function_at(self.state.pc).map(String::from),
+        })
+    }
+
+    /// Check the arguments of the syscall an `ECALL` invokes, before the syscall executes.
+    fn check_syscall(&self, instruction: Instruction) -> Result<(), ExecutionError> {
+        if instruction.opcode != Opcode::ECALL {
+            return Ok(());
+        }
+        let syscall = SyscallCode::from_u32(self.register(Register::X5));
+        let Some(syscall_impl) = self.syscall_map.get(&syscall) else {
+            return Ok(());
+        };
+        syscall_impl
+            .check(self)
+            .map_err(|reason| ExecutionError::InvalidSyscall {
+                pc: self.state.pc,
+                syscall,
+                reason,
+            })
     }
 
     fn postprocess(&mut self) {
         let mut program_memory_used = HashMap::with_hasher(BuildNoHashHasher::<u32>::default());
-        for (key, value) in &self.program.memory_image {
+        for (key, value) in self.program.initial_memory() {
             // By default we assume that the program_memory is used.
             program_memory_used.insert(*key, (*value, 1));
         }
@@ -851,7 +1138,7 @@ impl Runtime {
             // If the memory addr was accessed, we only add it to "first_memory_record" if it was
             // not in the program_memory_image, otherwise we'll add to the memory argument from
             // the program_memory_image table.
-            if !self.program.memory_image.contains_key(&addr) {
+            if !self.program.initial_memory().contains_key(&addr) {
                 first_memory_record.push((
                     addr,
                     MemoryRecord {
@@ -901,10 +1188,17 @@ pub mod tests {
 
     use crate::{
         runtime::Register,
+        syscall::precompiles::sha256::extend_tests::sha_extend_program,
         utils::tests::{FIBONACCI_ELF, SSZ_WITHDRAWALS_ELF},
     };
 
-    use super::{Instruction, Opcode, Program, Runtime};
+    use super::{
+        ExecutionError, Instruction, Opcode, Program, Runtime, SyscallCode, SyscallLogMode,
+        SyscallLogging, STACK_TOP,
+    };
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::time::{Duration, Instant};
 
     pub fn simple_program() -> Program {
         let instructions = vec![
@@ -1541,4 +1835,194 @@ pub mod tests {
         assert_eq!(runtime.register(Register::X12), 0x12346525);
         assert_eq!(runtime.register(Register::X11), 0x65256525);
     }
+
+    #[test]
+    fn test_syscall_logging_redaction() {
+        let logging = SyscallLogging {
+            mode: SyscallLogMode::Full,
+            redact: vec![SyscallCode::SHA_EXTEND],
+        };
+        let entry = logging
+            .entry(SyscallCode::SHA_EXTEND, 4, [100, 0], || {
+                panic!("redacted memory must not be read")
+            })
+            .unwrap();
+        assert_eq!(entry.args, None);
+        assert_eq!(entry.snippet, None);
+
+        let entry = logging
+            .entry(SyscallCode::HALT, 8, [1, 2], || vec![3])
+            .unwrap();
+        assert_eq!(entry.args, Some([1, 2]));
+        assert_eq!(entry.snippet, Some(vec![3]));
+
+        let ids = SyscallLogging {
+            mode: SyscallLogMode::Ids,
+            redact: vec![],
+        };
+        let entry = ids.entry(SyscallCode::HALT, 8, [1, 2], Vec::new).unwrap();
+        assert_eq!(entry.args, None);
+        assert!(SyscallLogging::default()
+            .entry(SyscallCode::HALT, 8, [1, 2], Vec::new)
+            .is_none());
+
+        // Redacted syscalls are still counted in the report.
+        let mut runtime = Runtime::new(sha_extend_program());
+        runtime.syscall_logging = logging;
+        runtime.run();
+        assert_eq!(runtime.report.syscall_counts[&SyscallCode::SHA_EXTEND], 1);
+        assert_eq!(
+            runtime.report.syscall_bytes[&SyscallCode::SHA_EXTEND],
+            48 * 5 * 4
+        );
+    }
+
+    #[test]
+    fn test_execution_hooks() {
+        let mut runtime = Runtime::new(sha_extend_program());
+        let instructions = Rc::new(RefCell::new(Vec::new()));
+        let syscalls = Rc::new(RefCell::new(Vec::new()));
+        let hook = instructions.clone();
+        runtime.set_instruction_hook(Box::new(move |instruction, pc, clk| {
+            hook.borrow_mut().push((instruction.opcode, pc, clk))
+        }));
+        let hook = syscalls.clone();
+        runtime.set_syscall_hook(Box::new(move |code, _, _| hook.borrow_mut().push(code)));
+        runtime.run();
+
+        let instructions = instructions.borrow();
+        assert_eq!(instructions.len(), runtime.state.global_clk as usize);
+        assert!(instructions
+            .iter()
+            .enumerate()
+            .all(|(index, (_, _, clk))| *clk as usize == index));
+        assert_eq!(instructions[0].1, runtime.program.pc_start);
+        assert_eq!(*syscalls.borrow(), vec![SyscallCode::SHA_EXTEND]);
+
+        // Clones do not call the hooks.
+        let mut clone = runtime.clone();
+        clone.reset(&[]);
+        clone.run();
+        assert_eq!(instructions.len(), runtime.state.global_clk as usize);
+    }
+
+    /// A program pushing `depth` frames of 16 bytes on a stack of 256 bytes, like a recursion.
+    fn recursion_program(depth: u32) -> Program {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 2, 0, STACK_TOP, false, true),
+            Instruction::new(Opcode::ADD, 5, 0, depth, false, true),
+            Instruction::new(Opcode::SUB, 2, 2, 16, false, true),
+            Instruction::new(Opcode::SW, 1, 2, 12, false, true),
+            Instruction::new(Opcode::SUB, 5, 5, 1, false, true),
+            Instruction::new(Opcode::BNE, 5, 0, -12i32 as u32, false, true),
+        ];
+        let mut program = Program::new(instructions, 0, 0).with_stack_size(256);
+        program.symbols.insert("recurse".to_string(), 8);
+        program
+    }
+
+    #[test]
+    fn test_stack_overflow() {
+        // The deepest frame of the near-limit recursion starts at the stack floor.
+        let mut runtime = Runtime::new(recursion_program(16));
+        runtime.try_run().unwrap();
+        assert_eq!(runtime.register(Register::X2), STACK_TOP - 256);
+
+        // One more frame stores below the floor, which stops the execution before the store.
+        let mut runtime = Runtime::new(recursion_program(17));
+        assert_eq!(
+            runtime.try_run(),
+            Err(ExecutionError::StackOverflow {
+                pc: 12,
+                sp: STACK_TOP - 272,
+                function: Some("recurse".to_string()),
+            })
+        );
+        assert_eq!(runtime.word(STACK_TOP - 260), 0);
+
+        // Without a layout, the stack is not checked.
+        let mut program = recursion_program(17);
+        program.memory_layout = None;
+        Runtime::new(program).run();
+    }
+
+    #[test]
+    fn test_leftover_input() {
+        // Reads a word of the input.
+        let program = || {
+            let instructions = vec![
+                Instruction::new(Opcode::ADD, 5, 0, SyscallCode::LWA as u32, false, true),
+                Instruction::new(Opcode::ADD, 11, 0, 4, false, true),
+                Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+            ];
+            Program::new(instructions, 0, 0)
+        };
+
+        let mut runtime = Runtime::new(program());
+        runtime.write_stdin_slice(b"sp1!??");
+        runtime.try_run().unwrap();
+        assert_eq!(
+            runtime.register(Register::X10),
+            u32::from_le_bytes(*b"sp1!")
+        );
+        assert_eq!(runtime.unread_input(), 2);
+
+        let mut runtime = Runtime::new(program());
+        runtime.exact_input = true;
+        runtime.write_stdin_slice(b"sp1!??");
+        assert_eq!(
+            runtime.try_run(),
+            Err(ExecutionError::LeftoverInput { unread: 2 })
+        );
+
+        let mut runtime = Runtime::new(program());
+        runtime.exact_input = true;
+        runtime.write_stdin_slice(b"sp1!");
+        runtime.try_run().unwrap();
+    }
+
+    #[test]
+    fn test_timeout() {
+        // A guest counting in x29 forever.
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 29, 1, false, true),
+            Instruction::new(Opcode::BEQ, 0, 0, -4i32 as u32, false, true),
+        ];
+        let mut runtime = Runtime::new(Program::new(instructions, 0, 0));
+        runtime.timeout = Some(Duration::from_millis(50));
+        let start = Instant::now();
+        let Err(ExecutionError::Timeout { cycles_executed }) = runtime.try_run() else {
+            panic!("the busy loop must time out");
+        };
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        // The execution stops between two instructions, with the events of those executed.
+        assert!(cycles_executed > 0);
+        assert_eq!(cycles_executed, runtime.state.global_clk as u64);
+        assert_eq!(runtime.record.cpu_events.len() as u64, cycles_executed);
+        assert_eq!(runtime.register(Register::X29) as u64, cycles_executed / 2);
+
+        // A program halting in time is unaffected.
+        let mut runtime = Runtime::new(simple_program());
+        runtime.timeout = Some(Duration::from_secs(60));
+        runtime.try_run().unwrap();
+    }
+
+    #[test]
+    fn test_halt() {
+        // Halting jumps to zero, which is the start of this program.
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, SyscallCode::HALT as u32, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+        ];
+        let mut runtime = Runtime::new(Program::new(instructions, 0, 0));
+        runtime.run();
+
+        // No instruction is executed after the halt.
+        assert!(runtime.halted());
+        assert_eq!(runtime.state.pc, 0);
+        assert_eq!(runtime.record.cpu_events.len(), 2);
+        assert_eq!(runtime.register(Register::X29), 0);
+    }
 }
@@ -0,0 +1,20 @@
+use super::{Instruction, Runtime, SyscallCode};
+
+/// Observes an execution one instruction at a time.
+///
+/// The observers of a runtime are shared with its clones, so that re-executing a clone calls them
+/// again at the same instructions. An observer which keeps state of its own does so through
+/// interior mutability.
+pub trait ExecutionObserver {
+    /// Called before the runtime fetches the instruction at its program counter. The observer may
+    /// change the state of the runtime, which the instruction then executes over.
+    fn before_instruction(&self, rt: &mut Runtime);
+}
+
+/// Called with each instruction the runtime executes, its program counter and the global clock
+/// before it executes.
+pub type InstructionHook = Box<dyn FnMut(&Instruction, u32, u32)>;
+
+/// Called with each syscall the runtime executes, the arguments in `a0` and `a1` and the clock of
+/// the shard before it executes.
+pub type SyscallHook = Box<dyn FnMut(SyscallCode, [u32; 2], u32)>;
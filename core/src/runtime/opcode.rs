@@ -1,9 +1,10 @@
 use std::fmt::Display;
 
 use p3_field::Field;
+use serde::{Deserialize, Serialize};
 
 /// An opcode specifies which operation to execute.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[allow(non_camel_case_types)]
 pub enum Opcode {
     // Arithmetic instructions.
@@ -0,0 +1,214 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use super::{Instruction, Opcode, Program, Runtime};
+
+/// The name under which the cycles spent outside of the functions of the symbol table are folded.
+pub const UNKNOWN_FUNCTION: &str = "[unknown]";
+
+/// How the instruction executed last moves between functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transition {
+    None,
+    Call,
+    Return,
+}
+
+/// A function in the tree of the calls made by the program.
+#[derive(Debug, Clone)]
+struct CallNode {
+    /// The index of the function in the symbol table, `None` outside of the functions and at the
+    /// root.
+    function: Option<usize>,
+    parent: usize,
+    children: HashMap<Option<usize>, usize>,
+    /// The cycles spent in the function itself with this stack of callers.
+    cycles: u64,
+}
+
+/// Attributes the cycles of an execution to the functions of the program, following the calls
+/// and the returns of the guest to know the stack of the functions being executed.
+///
+/// A jump or a call with `jal` or `jalr` which links the return address is a call, a `jalr` to
+/// `ra` which links nothing is a return, and any other instruction moving to another function is a
+/// tail call which replaces the function on top of the stack.
+#[derive(Debug, Clone)]
+pub struct Profiler {
+    /// The start addresses of the functions of the program, sorted, with their names.
+    functions: Vec<(u32, String)>,
+    nodes: Vec<CallNode>,
+    current: usize,
+    transition: Transition,
+    total: u64,
+}
+
+impl Profiler {
+    /// A profiler of the functions of the symbol table of the program.
+    pub fn new(program: &Program) -> Self {
+        let mut functions = program
+            .symbols
+            .iter()
+            .map(|(name, addr)| (*addr, name.clone()))
+            .collect::<Vec<_>>();
+        functions.sort();
+        Self {
+            functions,
+            nodes: vec![CallNode {
+                function: None,
+                parent: 0,
+                children: HashMap::new(),
+                cycles: 0,
+            }],
+            current: 0,
+            transition: Transition::None,
+            total: 0,
+        }
+    }
+
+    /// Profiles the execution of the runtime from now on, through its instruction hook.
+    pub fn install(runtime: &mut Runtime) -> Rc<RefCell<Self>> {
+        let profiler = Rc::new(RefCell::new(Self::new(&runtime.program)));
+        let hook = profiler.clone();
+        runtime.set_instruction_hook(Box::new(move |instruction, pc, _| {
+            hook.borrow_mut().record(instruction, pc)
+        }));
+        profiler
+    }
+
+    /// Attributes a cycle to the function containing `pc`, where the instruction executes.
+    pub fn record(&mut self, instruction: &Instruction, pc: u32) {
+        let function = self.function_at(pc);
+        match self.transition {
+            Transition::Call => self.current = self.child(self.current, function),
+            Transition::Return => {
+                self.current = self.nodes[self.current].parent;
+                if self.nodes[self.current].function != function {
+                    self.current = self.child(self.nodes[self.current].parent, function);
+                }
+            }
+            Transition::None => {
+                if self.nodes[self.current].function != function {
+                    self.current = self.child(self.nodes[self.current].parent, function);
+                }
+            }
+        }
+        // The root stands for no function, so that the cycles are always attributed to a stack.
+        if self.current == 0 {
+            self.current = self.child(0, function);
+        }
+        self.transition = match instruction.opcode {
+            Opcode::JAL | Opcode::JALR if instruction.op_a != 0 => Transition::Call,
+            Opcode::JALR if instruction.op_b == 1 => Transition::Return,
+            _ => Transition::None,
+        };
+        self.nodes[self.current].cycles += 1;
+        self.total += 1;
+    }
+
+    /// The number of cycles attributed so far.
+    pub fn total_cycles(&self) -> u64 {
+        self.total
+    }
+
+    /// The cycles spent in each function itself, over all of its callers, the most first.
+    pub fn by_function(&self) -> Vec<(String, u64)> {
+        let mut cycles = HashMap::<&str, u64>::new();
+        for node in self.nodes.iter().filter(|node| node.cycles > 0) {
+            *cycles.entry(self.name(node.function)).or_default() += node.cycles;
+        }
+        let mut cycles = cycles
+            .into_iter()
+            .map(|(name, cycles)| (name.to_string(), cycles))
+            .collect::<Vec<_>>();
+        cycles.sort_by(|(a, a_cycles), (b, b_cycles)| b_cycles.cmp(a_cycles).then(a.cmp(b)));
+        cycles
+    }
+
+    /// Writes the cycles of each stack of functions in the folded format of flamegraph tools, one
+    /// `caller;callee cycles` line per stack.
+    pub fn write_folded(&self, out: &mut impl Write) -> io::Result<()> {
+        for (index, node) in self.nodes.iter().enumerate() {
+            if node.cycles == 0 {
+                continue;
+            }
+            let mut stack = Vec::new();
+            let mut index = index;
+            while index != 0 {
+                stack.push(self.name(self.nodes[index].function));
+                index = self.nodes[index].parent;
+            }
+            stack.reverse();
+            writeln!(out, "{} {}", stack.join(";"), node.cycles)?;
+        }
+        Ok(())
+    }
+
+    /// The index of the function containing `pc`, which is the last one starting at or before it.
+    fn function_at(&self, pc: u32) -> Option<usize> {
+        self.functions
+            .partition_point(|(addr, _)| *addr <= pc)
+            .checked_sub(1)
+    }
+
+    fn name(&self, function: Option<usize>) -> &str {
+        match function {
+            Some(function) => &self.functions[function].1,
+            None => UNKNOWN_FUNCTION,
+        }
+    }
+
+    /// The node of the function called from the node `parent`, added if it is the first call.
+    fn child(&mut self, parent: usize, function: Option<usize>) -> usize {
+        if let Some(child) = self.nodes[parent].children.get(&function) {
+            return *child;
+        }
+        let child = self.nodes.len();
+        self.nodes.push(CallNode {
+            function,
+            parent,
+            children: HashMap::new(),
+            cycles: 0,
+        });
+        self.nodes[parent].children.insert(function, child);
+        child
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tests::FIBONACCI_ELF;
+
+    #[test]
+    fn test_profile_fibonacci() {
+        let mut runtime = Runtime::new(Program::from(FIBONACCI_ELF));
+        let profiler = Profiler::install(&mut runtime);
+        runtime.run();
+        let profiler = profiler.borrow();
+
+        let global_clk = runtime.state.global_clk as u64;
+        assert_eq!(profiler.total_cycles(), global_clk);
+        let by_function = profiler.by_function();
+        assert_eq!(
+            by_function.iter().map(|(_, cycles)| cycles).sum::<u64>(),
+            global_clk
+        );
+        assert!(by_function.iter().any(|(name, _)| name == "main"));
+
+        let mut folded = Vec::new();
+        profiler.write_folded(&mut folded).unwrap();
+        let folded = String::from_utf8(folded).unwrap();
+        let cycles = folded
+            .lines()
+            .map(|line| {
+                let (stack, cycles) = line.rsplit_once(' ').unwrap();
+                assert!(!stack.is_empty());
+                cycles.parse::<u64>().unwrap()
+            })
+            .sum::<u64>();
+        assert_eq!(cycles, global_clk);
+        assert!(folded.lines().any(|line| line.contains(";main")));
+    }
+}
@@ -1,13 +1,19 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
-use super::Instruction;
+use serde::{Deserialize, Serialize};
+
+use super::{Instruction, Opcode, Register, SyscallCode};
+use crate::disassembler::CompatReport;
 
 /// A program that can be executed by the VM.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Program {
     /// The instructions of the program.
     pub instructions: Vec<Instruction>,
 
+    /// The raw 32-bit encodings of the instructions, empty if the program was not disassembled.
+    pub encodings: Vec<u32>,
+
     /// The start address of the program.
     pub pc_start: u32,
 
@@ -16,4 +22,394 @@ pub struct Program {
 
     /// The initial memory image, useful for global constants.
     pub memory_image: BTreeMap<u32, u32>,
+
+    /// The settings of the ELF the program was disassembled from that the runtime does not support.
+    pub compat: CompatReport,
+
+    /// The addresses of the functions of the ELF the program was disassembled from, by name.
+    pub symbols: BTreeMap<String, u32>,
+
+    /// The layout of the guest stack, if known, against which the runtime detects stack overflows.
+    pub memory_layout: Option<MemoryLayout>,
+}
+
+/// The start of the memory region holding the arguments passed with `Program::with_args`.
+///
+/// It lies above the guest heap, which never grows past this address.
+pub const ARGS_START: u32 = 0x0C00_0000;
+
+/// The address the guest entrypoint starts the stack pointer at. The stack grows down from it.
+pub const STACK_TOP: u32 = 0x0020_0400;
+
+/// The symbol under which `sp1_zkvm::entrypoint!` exports the size of the guest stack.
+pub const STACK_SIZE_SYMBOL: &str = "SP1_STACK_SIZE";
+
+/// The layout of the guest stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryLayout {
+    /// The address the stack grows down from.
+    pub stack_top: u32,
+
+    /// The number of bytes reserved for the stack.
+    pub stack_size: u32,
+}
+
+impl MemoryLayout {
+    pub fn new(stack_top: u32, stack_size: u32) -> Self {
+        assert!(
+            stack_size <= stack_top,
+            "a stack of {} bytes does not fit below 0x{:08x}",
+            stack_size,
+            stack_top
+        );
+        Self {
+            stack_top,
+            stack_size,
+        }
+    }
+
+    /// The lowest address of the stack. The guest keeps a canary word there, and any store below
+    /// it overflows the stack.
+    pub fn stack_floor(&self) -> u32 {
+        self.stack_top - self.stack_size
+    }
+}
+
+impl Program {
+    /// The initial memory image of the program as a map of word-aligned addresses to values.
+    ///
+    /// Zero-filled regions (such as `.bss`) are included explicitly, so this is exactly the memory
+    /// that the runtime starts from and that the program memory chip commits to.
+    pub fn initial_memory(&self) -> &BTreeMap<u32, u32> {
+        &self.memory_image
+    }
+
+    /// Starts the program at the function with the given name instead of the ELF entrypoint.
+    ///
+    /// The function must set up the stack itself, e.g. by being exported with the
+    /// `sp1_zkvm::entrypoints!` macro. The verifying key includes the start address, so proofs are
+    /// bound to the chosen entry point.
+    pub fn with_entry_symbol(mut self, name: &str) -> Self {
+        self.pc_start = *self
+            .symbols
+            .get(name)
+            .unwrap_or_else(|| panic!("no function named {} in the program", name));
+        self
+    }
+
+    /// Reserves `stack_size` bytes below `STACK_TOP` for the stack, so that the runtime stops the
+    /// program with `ExecutionError::StackOverflow` when it stores below them.
+    ///
+    /// Guests built with `sp1_zkvm::entrypoint!` export the size of their stack, which is used
+    /// unless overridden with this method.
+    pub fn with_stack_size(mut self, stack_size: u32) -> Self {
+        self.memory_layout = Some(MemoryLayout::new(STACK_TOP, stack_size));
+        self
+    }
+
+    /// Ends the program with a halt ecall, so that running past its last instruction halts it.
+    ///
+    /// A proof only accepts an execution ending with a halt, while programs built from a list of
+    /// instructions usually stop by leaving the program. The halt overwrites `t0` with its syscall
+    /// code and exits with the value of `a0`.
+    pub fn with_halt(mut self) -> Self {
+        let halt = SyscallCode::HALT as u32;
+        self.instructions.extend([
+            Instruction::new(Opcode::ADD, Register::X5 as u32, 0, halt, false, true),
+            Instruction::new(
+                Opcode::ECALL,
+                Register::X10 as u32,
+                Register::X5 as u32,
+                0,
+                false,
+                true,
+            ),
+        ]);
+        if !self.encodings.is_empty() {
+            // addi t0, zero, HALT; ecall
+            self.encodings
+                .extend([(halt << 20) | (5 << 7) | 0b0010011, 0b1110011]);
+        }
+        self
+    }
+
+    /// The name of the function containing `pc`, if the program has symbols.
+    pub fn function_at(&self, pc: u32) -> Option<&str> {
+        self.symbols
+            .iter()
+            .filter(|(_, addr)| **addr <= pc)
+            .max_by_key(|(_, addr)| **addr)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Passes arguments to the program, which reads them with `sp1_zkvm::env::args()`.
+    ///
+    /// The arguments are laid out in the initial memory from `ARGS_START`: the number of arguments,
+    /// then a pointer and a length for each argument, then the bytes of the arguments, each padded
+    /// to a whole number of words.
+    pub fn with_args(mut self, args: &[Vec<u8>]) -> Self {
+        let mut words = vec![args.len() as u32];
+        let mut ptr = ARGS_START + 4 * (1 + 2 * args.len() as u32);
+        for arg in args.iter() {
+            words.extend([ptr, arg.len() as u32]);
+            ptr += 4 * arg.len().div_ceil(4) as u32;
+        }
+        for arg in args.iter() {
+            words.extend(arg.chunks(4).map(|chunk| {
+                let mut bytes = [0u8; 4];
+                bytes[..chunk.len()].copy_from_slice(chunk);
+                u32::from_le_bytes(bytes)
+            }));
+        }
+        for (i, word) in words.into_iter().enumerate() {
+            let addr = ARGS_START + 4 * i as u32;
+            assert!(
+                self.memory_image.insert(addr, word).is_none(),
+                "the arguments overlap the program at 0x{:08x}",
+                addr
+            );
+        }
+        self
+    }
+
+    /// The incompatible settings the program's ELF was compiled with, empty if the program was not
+    /// disassembled.
+    pub fn compat_report(&self) -> &CompatReport {
+        &self.compat
+    }
+
+    /// Rewrites arithmetic by a power of two loaded just before into cheaper ALU instructions.
+    ///
+    /// Each rewrite replaces a single instruction, so no address moves. It applies to an
+    /// instruction preceded by `addi rc, x0, 2^k`, reading `rc` as the constant operand:
+    ///
+    /// - `divu rd, rs1, rc` becomes `srli rd, rs1, k`, since unsigned division by `2^k` is a right
+    ///   shift.
+    /// - `remu rd, rs1, rc` becomes `andi rd, rs1, 2^k - 1`, which keeps the low `k` bits.
+    /// - `mul rd, rs1, rc` and `mul rd, rc, rs1` become `slli rd, rs1, k`, since the low word of
+    ///   the product is the shifted word.
+    ///
+    /// The constant register is still written, so every register holds the same value afterwards.
+    /// Signed division and remainder round towards zero, unlike arithmetic shifts, and are left
+    /// alone, as are constants which are not powers of two, whose shift-add sequences would move
+    /// the following instructions.
+    ///
+    /// An instruction which is the target of a branch or jump, the entrypoint or a function symbol
+    /// may be reached without the preceding `addi`, and is not rewritten. The pass assumes that
+    /// indirect jumps, i.e. returns and jump tables, never land between the two instructions,
+    /// which holds for compiler output since the constant is materialized in the same basic block.
+    ///
+    /// The rewritten program is the one committed to by the verifying key, so proofs of it only
+    /// verify against a program rewritten the same way.
+    pub fn with_strength_reduction(mut self) -> Self {
+        let pc_base = self.pc_base;
+        let pc = |i: usize| pc_base + 4 * i as u32;
+        let mut targets = self
+            .instructions
+            .iter()
+            .enumerate()
+            .filter_map(|(i, instruction)| match instruction.opcode {
+                Opcode::JAL => Some(pc(i).wrapping_add(instruction.op_b)),
+                _ if instruction.is_branch_instruction() => {
+                    Some(pc(i).wrapping_add(instruction.op_c))
+                }
+                _ => None,
+            })
+            .collect::<BTreeSet<_>>();
+        targets.insert(self.pc_start);
+        targets.extend(self.symbols.values().copied());
+
+        let mut rewritten = 0;
+        for i in 1..self.instructions.len() {
+            if targets.contains(&pc(i)) {
+                continue;
+            }
+            let Some(instruction) =
+                strength_reduce(&self.instructions[i - 1], &self.instructions[i])
+            else {
+                continue;
+            };
+            if self.encodings.len() == self.instructions.len() {
+                self.encodings[i] = encode_i_type(&instruction);
+            }
+            self.instructions[i] = instruction;
+            rewritten += 1;
+        }
+        tracing::info!("strength reduction rewrote {} instructions", rewritten);
+        self
+    }
+}
+
+/// The instruction equivalent to `instruction` when it follows `load`, if `load` sets one of its
+/// register operands to a power of two.
+fn strength_reduce(load: &Instruction, instruction: &Instruction) -> Option<Instruction> {
+    let loads_constant =
+        load.opcode == Opcode::ADD && load.op_a != 0 && load.op_b == 0 && !load.imm_b && load.imm_c;
+    if !loads_constant || !load.op_c.is_power_of_two() || instruction.imm_b || instruction.imm_c {
+        return None;
+    }
+    let (constant, k) = (load.op_a, load.op_c.trailing_zeros());
+    let rd = instruction.op_a;
+    match instruction.opcode {
+        Opcode::DIVU if instruction.op_c == constant => Some(Instruction::new(
+            Opcode::SRL,
+            rd,
+            instruction.op_b,
+            k,
+            false,
+            true,
+        )),
+        Opcode::REMU if instruction.op_c == constant => Some(Instruction::new(
+            Opcode::AND,
+            rd,
+            instruction.op_b,
+            load.op_c - 1,
+            false,
+            true,
+        )),
+        Opcode::MUL if instruction.op_c == constant => Some(Instruction::new(
+            Opcode::SLL,
+            rd,
+            instruction.op_b,
+            k,
+            false,
+            true,
+        )),
+        Opcode::MUL if instruction.op_b == constant => Some(Instruction::new(
+            Opcode::SLL,
+            rd,
+            instruction.op_c,
+            k,
+            false,
+            true,
+        )),
+        _ => None,
+    }
+}
+
+/// Encodes `srli`, `slli` or `andi` with a non-negative immediate.
+fn encode_i_type(instruction: &Instruction) -> u32 {
+    let funct3 = match instruction.opcode {
+        Opcode::SLL => 0b001,
+        Opcode::SRL => 0b101,
+        Opcode::AND => 0b111,
+        _ => unreachable!("{} is not rewritten to", instruction.opcode.mnemonic()),
+    };
+    (instruction.op_c << 20)
+        | (instruction.op_b << 15)
+        | (funct3 << 12)
+        | (instruction.op_a << 7)
+        | 0b0010011
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{thread_rng, Rng};
+
+    use super::{Program, ARGS_START};
+    use crate::disassembler::transpile;
+    use crate::runtime::{Instruction, Opcode, Runtime};
+
+    #[test]
+    fn test_args_layout() {
+        let program = Program::new(Vec::new(), 0, 0).with_args(&[b"mint".to_vec(), vec![1; 5]]);
+        let memory = program.initial_memory();
+        let word = |i: u32| memory[&(ARGS_START + 4 * i)];
+        assert_eq!(word(0), 2);
+        assert_eq!((word(1), word(2)), (ARGS_START + 20, 4));
+        assert_eq!((word(3), word(4)), (ARGS_START + 24, 5));
+        assert_eq!(word(5), u32::from_le_bytes(*b"mint"));
+        assert_eq!((word(6), word(7)), (0x01010101, 0x01));
+        assert_eq!(memory.len(), 8);
+    }
+
+    /// Loads `b` and a power of two, then divides, takes the remainder and multiplies by it.
+    fn arithmetic_program(b: u32, k: u32) -> Program {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 10, 0, b, false, true),
+            Instruction::new(Opcode::ADD, 5, 0, 1 << k, false, true),
+            Instruction::new(Opcode::DIVU, 11, 10, 5, false, false),
+            Instruction::new(Opcode::ADD, 6, 0, 1 << k, false, true),
+            Instruction::new(Opcode::REMU, 12, 10, 6, false, false),
+            Instruction::new(Opcode::ADD, 7, 0, 1 << k, false, true),
+            Instruction::new(Opcode::MUL, 13, 10, 7, false, false),
+            Instruction::new(Opcode::ADD, 7, 0, 1 << k, false, true),
+            Instruction::new(Opcode::MUL, 14, 7, 10, false, false),
+            Instruction::new(Opcode::ADD, 10, 0, 1 << k, false, true),
+            Instruction::new(Opcode::DIVU, 10, 10, 10, false, false),
+            Instruction::new(Opcode::ADD, 8, 0, 3, false, true),
+            Instruction::new(Opcode::DIVU, 15, 10, 8, false, false),
+            Instruction::new(Opcode::ADD, 9, 0, 1 << k, false, true),
+            Instruction::new(Opcode::DIV, 16, 10, 9, false, false),
+        ];
+        Program::new(instructions, 0, 0)
+    }
+
+    #[test]
+    fn test_strength_reduction_rewrites() {
+        let program = arithmetic_program(1000, 3).with_strength_reduction();
+        let opcodes = program
+            .instructions
+            .iter()
+            .map(|instruction| instruction.opcode)
+            .collect::<Vec<_>>();
+        assert_eq!(opcodes[2], Opcode::SRL);
+        assert_eq!(opcodes[4], Opcode::AND);
+        assert_eq!(opcodes[6], Opcode::SLL);
+        assert_eq!(opcodes[8], Opcode::SLL);
+        assert_eq!(opcodes[10], Opcode::SRL);
+        // Neither a constant which is not a power of two nor a signed division is rewritten.
+        assert_eq!(opcodes[12], Opcode::DIVU);
+        assert_eq!(opcodes[14], Opcode::DIV);
+    }
+
+    #[test]
+    fn test_strength_reduction_encodings() {
+        let mut program = arithmetic_program(1000, 10);
+        program.encodings = vec![0; program.instructions.len()];
+        let program = program.with_strength_reduction();
+        for i in [2, 4, 6, 8, 10] {
+            let decoded = transpile(&[program.encodings[i]])[0];
+            let instruction = program.instructions[i];
+            assert_eq!(decoded.opcode, instruction.opcode);
+            assert_eq!(
+                (decoded.op_a, decoded.op_b, decoded.op_c),
+                (instruction.op_a, instruction.op_b, instruction.op_c)
+            );
+            assert_eq!((decoded.imm_b, decoded.imm_c), (false, true));
+        }
+    }
+
+    #[test]
+    fn test_strength_reduction_skips_jump_targets() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, 8, false, true),
+            Instruction::new(Opcode::BEQ, 0, 0, 8, false, true),
+            Instruction::new(Opcode::ADD, 5, 0, 4, false, true),
+            Instruction::new(Opcode::DIVU, 11, 10, 5, false, false),
+        ];
+        let program = Program::new(instructions, 0, 0).with_strength_reduction();
+        assert_eq!(program.instructions[3].opcode, Opcode::DIVU);
+    }
+
+    #[test]
+    fn test_strength_reduction_differential() {
+        let mut rng = thread_rng();
+        for _ in 0..256 {
+            let (b, k) = (rng.gen::<u32>(), rng.gen_range(0..11));
+            let run = |program: Program| {
+                let mut runtime = Runtime::new(program);
+                runtime.run();
+                (runtime.registers(), runtime.state.output_stream)
+            };
+            let program = arithmetic_program(b, k);
+            assert_eq!(
+                run(program.clone()),
+                run(program.with_strength_reduction()),
+                "b = {:#x}, k = {}",
+                b,
+                k
+            );
+        }
+    }
 }
@@ -1,25 +1,33 @@
 use hashbrown::HashMap;
-use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
 use std::sync::Arc;
 
 use super::program::Program;
 use super::Opcode;
+use super::Register;
 use crate::alu::AluEvent;
 use crate::bytes::{ByteLookupEvent, ByteOpcode};
-use crate::cpu::{CpuEvent, MemoryRecordEnum};
+use crate::cpu::{CpuEvent, MemoryReadRecord, MemoryRecordEnum};
 use crate::field::event::FieldEvent;
+use crate::range::RangeCheckEvent;
 use crate::runtime::MemoryRecord;
-use crate::syscall::precompiles::blake3::Blake3CompressInnerEvent;
+use crate::syscall::precompiles::bitmanip::BitManipEvent;
+use crate::syscall::precompiles::blake3::{Blake3CompressInnerEvent, G_INDEX, MSG_SCHEDULE};
 use crate::syscall::precompiles::edwards::EdDecompressEvent;
 use crate::syscall::precompiles::k256::K256DecompressEvent;
 use crate::syscall::precompiles::keccak256::KeccakPermuteEvent;
+use crate::syscall::precompiles::memcpy::MemcpyEvent;
 use crate::syscall::precompiles::sha256::{ShaCompressEvent, ShaExtendEvent};
+use crate::syscall::precompiles::uint256::Uint256MulModEvent;
 use crate::syscall::precompiles::{ECAddEvent, ECDoubleEvent};
+use crate::utils::ec::COMPRESSED_POINT_BYTES;
 use crate::utils::env;
 
 /// A record of the execution of a program. Contains event data for everything that happened during
 /// the execution of the shard.
-#[derive(Default, Clone, Debug)]
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct ExecutionRecord {
     /// The index of the shard.
     pub index: u32,
@@ -60,6 +68,9 @@ pub struct ExecutionRecord {
     /// A trace of the byte lookups needed.
     pub byte_lookups: BTreeMap<ByteLookupEvent, usize>,
 
+    /// A trace of the range checks needed.
+    pub range_checks: BTreeMap<RangeCheckEvent, usize>,
+
     /// A trace of field LTU events.
     pub field_events: Vec<FieldEvent>,
 
@@ -81,6 +92,16 @@ pub struct ExecutionRecord {
 
     pub blake3_compress_inner_events: Vec<Blake3CompressInnerEvent>,
 
+    pub bitmanip_events: Vec<BitManipEvent>,
+
+    pub memcpy_events: Vec<MemcpyEvent>,
+
+    pub uint256_mulmod_events: Vec<Uint256MulModEvent>,
+
+    /// The bytes committed by the program with the `COMMIT` syscall, in order. They belong to the
+    /// whole execution, so they are not carried over to the shards.
+    pub committed_values: Vec<u8>,
+
     /// Information needed for global chips. This shouldn't really be here but for legacy reasons,
     /// we keep this information in this struct for now.
     pub first_memory_record: Vec<(u32, MemoryRecord, u32)>,
@@ -88,7 +109,16 @@ pub struct ExecutionRecord {
     pub program_memory_record: Vec<(u32, MemoryRecord, u32)>,
 }
 
+/// The default bound on the multiplicity of a lookup in a shard.
+///
+/// Multiplicities are field elements, so a lookup performed `p` times, for `p` the BabyBear
+/// modulus, would silently have multiplicity zero. The bound keeps them far from wrapping around.
+pub const MAX_LOOKUP_MULTIPLICITY: usize = 1 << 27;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ShardingConfig {
+    /// The largest number of instructions of a shard, a power of two. The runtime must not start
+    /// longer shards.
     pub shard_size: usize,
     pub add_len: usize,
     pub mul_len: usize,
@@ -102,17 +132,26 @@ pub struct ShardingConfig {
     pub keccak_len: usize,
     pub weierstrass_add_len: usize,
     pub weierstrass_double_len: usize,
+    /// The bound on the multiplicity of any lookup in a shard.
+    ///
+    /// The instructions of a shard are looked up at most `shard_size` times, which must therefore
+    /// stay below the bound. Byte lookups are all aggregated into the first shard and are not
+    /// split, so sharding fails if one of them is performed more often than the bound.
+    pub max_multiplicity: usize,
+    /// Whether each precompile event is proven in the shard of the ecall which emitted it, rather
+    /// than in the first shards. The runtime starts a new shard early rather than let a syscall
+    /// straddle two, so the events of a syscall are never split between shards either way.
+    pub split_precompile_events: bool,
 }
 
 impl ShardingConfig {
-    pub const fn shard_size(&self) -> usize {
-        self.shard_size
-    }
-}
-
-impl Default for ShardingConfig {
-    fn default() -> Self {
-        let shard_size = env::shard_size();
+    /// Shards of `shard_size` instructions, with as many events of each chip.
+    pub fn with_shard_size(shard_size: usize) -> Self {
+        assert!(
+            shard_size.is_power_of_two(),
+            "the shard size {} is not a power of two",
+            shard_size
+        );
         Self {
             shard_size,
             add_len: shard_size,
@@ -127,11 +166,365 @@ impl Default for ShardingConfig {
             keccak_len: shard_size,
             weierstrass_add_len: shard_size,
             weierstrass_double_len: shard_size,
+            max_multiplicity: MAX_LOOKUP_MULTIPLICITY,
+            split_precompile_events: false,
         }
     }
+
+    pub const fn shard_size(&self) -> usize {
+        self.shard_size
+    }
+}
+
+impl Default for ShardingConfig {
+    fn default() -> Self {
+        Self::with_shard_size(env::shard_size())
+    }
+}
+
+/// A lookup whose aggregated multiplicity in a shard exceeds the bound.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiplicityOverflowError {
+    /// A description of the lookup.
+    pub lookup: String,
+    pub multiplicity: usize,
+    pub bound: usize,
+}
+
+impl std::fmt::Display for MultiplicityOverflowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "multiplicity {} of {} exceeds the bound {}",
+            self.multiplicity, self.lookup, self.bound
+        )
+    }
+}
+
+impl std::error::Error for MultiplicityOverflowError {}
+
+/// An event emitted in a single shard of the execution.
+pub trait ShardableEvent {
+    /// The shard the event was emitted in.
+    fn shard(&self) -> u32;
+
+    /// The clock of the shard at which the event was emitted.
+    fn clk(&self) -> u32;
+}
+
+macro_rules! impl_shardable_event {
+    ($($event:ty),*) => {
+        $(
+            impl ShardableEvent for $event {
+                fn shard(&self) -> u32 {
+                    self.shard
+                }
+
+                fn clk(&self) -> u32 {
+                    self.clk
+                }
+            }
+        )*
+    };
+}
+
+impl_shardable_event!(
+    CpuEvent,
+    ShaExtendEvent,
+    ShaCompressEvent,
+    KeccakPermuteEvent,
+    ECAddEvent,
+    ECDoubleEvent,
+    EdDecompressEvent,
+    K256DecompressEvent,
+    Blake3CompressInnerEvent,
+    BitManipEvent,
+    MemcpyEvent,
+    Uint256MulModEvent
+);
+
+/// The reads of the pointers of a precompile from the registers `a0`, `a1` and `a2`.
+fn register_accesses(
+    records: [MemoryReadRecord; 3],
+) -> impl Iterator<Item = (u32, MemoryRecordEnum)> {
+    [Register::X10, Register::X11, Register::X12]
+        .into_iter()
+        .zip(records)
+        .map(|(register, record)| (register as u32, record.into()))
+}
+
+/// An event of a precompile, which accesses memory on behalf of the ECALL which emitted it.
+pub trait PrecompileEvent: ShardableEvent {
+    /// The memory accesses of the event with their addresses, in the order they were performed.
+    fn memory_accesses(&self) -> Vec<(u32, MemoryRecordEnum)>;
+}
+
+/// The accesses of the records to consecutive words from `ptr`.
+fn word_accesses<R: Copy + Into<MemoryRecordEnum>>(
+    ptr: u32,
+    records: &[R],
+) -> impl Iterator<Item = (u32, MemoryRecordEnum)> + '_ {
+    records
+        .iter()
+        .enumerate()
+        .map(move |(i, record)| (ptr + 4 * i as u32, (*record).into()))
+}
+
+impl PrecompileEvent for ShaExtendEvent {
+    fn memory_accesses(&self) -> Vec<(u32, MemoryRecordEnum)> {
+        (0..48)
+            .flat_map(|j| {
+                let i = j as u32 + 16;
+                [
+                    (i - 15, self.w_i_minus_15_reads[j].into()),
+                    (i - 2, self.w_i_minus_2_reads[j].into()),
+                    (i - 16, self.w_i_minus_16_reads[j].into()),
+                    (i - 7, self.w_i_minus_7_reads[j].into()),
+                    (i, self.w_i_writes[j].into()),
+                ]
+            })
+            .map(|(i, record)| (self.w_ptr + 4 * i, record))
+            .collect()
+    }
+}
+
+impl PrecompileEvent for ShaCompressEvent {
+    fn memory_accesses(&self) -> Vec<(u32, MemoryRecordEnum)> {
+        let h_ptr = self.w_and_h_ptr + 4 * 64;
+        word_accesses(h_ptr, &self.h_read_records)
+            .chain(word_accesses(self.w_and_h_ptr, &self.w_i_read_records))
+            .chain(word_accesses(h_ptr, &self.h_write_records))
+            .collect()
+    }
+}
+
+impl PrecompileEvent for KeccakPermuteEvent {
+    fn memory_accesses(&self) -> Vec<(u32, MemoryRecordEnum)> {
+        word_accesses(self.state_addr, &self.state_read_records)
+            .chain(word_accesses(self.state_addr, &self.state_write_records))
+            .collect()
+    }
 }
 
-#[derive(Debug, Clone, Default)]
+impl PrecompileEvent for ECAddEvent {
+    fn memory_accesses(&self) -> Vec<(u32, MemoryRecordEnum)> {
+        std::iter::once((Register::X11 as u32, self.q_ptr_record.into()))
+            .chain(word_accesses(self.q_ptr, &self.q_memory_records))
+            .chain(word_accesses(self.p_ptr, &self.p_memory_records))
+            .collect()
+    }
+}
+
+impl PrecompileEvent for ECDoubleEvent {
+    fn memory_accesses(&self) -> Vec<(u32, MemoryRecordEnum)> {
+        word_accesses(self.p_ptr, &self.p_memory_records).collect()
+    }
+}
+
+impl PrecompileEvent for EdDecompressEvent {
+    fn memory_accesses(&self) -> Vec<(u32, MemoryRecordEnum)> {
+        let y_ptr = self.ptr + COMPRESSED_POINT_BYTES as u32;
+        word_accesses(y_ptr, &self.y_memory_records)
+            .chain(word_accesses(self.ptr, &self.x_memory_records))
+            .collect()
+    }
+}
+
+impl PrecompileEvent for K256DecompressEvent {
+    fn memory_accesses(&self) -> Vec<(u32, MemoryRecordEnum)> {
+        let x_ptr = self.ptr + COMPRESSED_POINT_BYTES as u32;
+        word_accesses(x_ptr, &self.x_memory_records)
+            .chain(word_accesses(self.ptr, &self.y_memory_records))
+            .collect()
+    }
+}
+
+impl PrecompileEvent for Blake3CompressInnerEvent {
+    fn memory_accesses(&self) -> Vec<(u32, MemoryRecordEnum)> {
+        let mut accesses = Vec::new();
+        for (round, operations) in self.message_reads.iter().enumerate() {
+            for (operation, reads) in operations.iter().enumerate() {
+                for (i, read) in reads.iter().enumerate() {
+                    let index = MSG_SCHEDULE[round][2 * operation + i] as u32;
+                    accesses.push((self.message_ptr + 4 * index, (*read).into()));
+                }
+                for (i, write) in self.state_writes[round][operation].iter().enumerate() {
+                    let index = G_INDEX[operation][i] as u32;
+                    accesses.push((self.state_ptr + 4 * index, (*write).into()));
+                }
+            }
+        }
+        accesses
+    }
+}
+
+impl PrecompileEvent for BitManipEvent {
+    fn memory_accesses(&self) -> Vec<(u32, MemoryRecordEnum)> {
+        vec![
+            (Register::X11 as u32, self.op_record.into()),
+            (self.ptr, self.word_record.into()),
+        ]
+    }
+}
+
+impl PrecompileEvent for MemcpyEvent {
+    fn memory_accesses(&self) -> Vec<(u32, MemoryRecordEnum)> {
+        register_accesses([self.dst_ptr_record, self.src_ptr_record, self.len_record])
+            .chain(word_accesses(
+                self.src_ptr + 4 * self.offset,
+                &self.src_records,
+            ))
+            .chain(word_accesses(
+                self.dst_ptr + 4 * self.offset,
+                &self.dst_records,
+            ))
+            .collect()
+    }
+}
+
+impl PrecompileEvent for Uint256MulModEvent {
+    fn memory_accesses(&self) -> Vec<(u32, MemoryRecordEnum)> {
+        register_accesses([
+            self.a_ptr_record,
+            self.b_ptr_record,
+            self.modulus_ptr_record,
+        ])
+        .chain(word_accesses(self.b_ptr, &self.b_memory_records))
+        .chain(word_accesses(
+            self.modulus_ptr,
+            &self.modulus_memory_records,
+        ))
+        .chain(word_accesses(self.a_ptr, &self.a_memory_records))
+        .collect()
+    }
+}
+
+/// Appends each event to the shard holding the ecall which emitted it, i.e. the first shard whose
+/// last instruction does not come before the event, and the events after the last instruction to
+/// the last shard. The events are in the order they were emitted.
+fn follow_ecalls<E: ShardableEvent>(
+    shards: &mut [ExecutionRecord],
+    events: Vec<E>,
+    events_of: impl Fn(&mut ExecutionRecord) -> &mut Vec<E>,
+) {
+    let mut events = events.into_iter().peekable();
+    for shard in shards.iter_mut() {
+        let end = shard
+            .cpu_events
+            .last()
+            .map(|event| (event.shard, event.clk));
+        while let Some(event) = events.next_if(|event| Some((event.shard(), event.clk())) <= end) {
+            events_of(shard).push(event);
+        }
+    }
+    events_of(shards.last_mut().unwrap()).extend(events);
+}
+
+/// A range of shards which cannot be carved out of an execution record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShardRangeError {
+    /// No instruction was executed in the range.
+    Empty(Range<u32>),
+    /// An access in the range follows an access which was recorded neither before the range nor
+    /// in it.
+    DanglingAccess {
+        addr: u32,
+        shard: u32,
+        timestamp: u32,
+    },
+}
+
+impl std::fmt::Display for ShardRangeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShardRangeError::Empty(shards) => {
+                write!(f, "no instruction was executed in shards {:?}", shards)
+            }
+            ShardRangeError::DanglingAccess {
+                addr,
+                shard,
+                timestamp,
+            } => write!(
+                f,
+                "the access to {:#x} at shard {} clk {} follows an unrecorded access",
+                addr, shard, timestamp
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ShardRangeError {}
+
+/// An inconsistency between consecutive shards of an execution record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShardTransitionError {
+    /// The shard at the position does not have the index following the previous shard.
+    Index { position: usize, index: u32 },
+    /// A shard has no instruction.
+    Empty { index: u32 },
+    /// The instruction does not come after the previous one in the order of the shards and
+    /// their clocks.
+    ClockOrder { shard: u32, clk: u32 },
+    /// The instruction does not start at the program counter the previous one ended at.
+    PcMismatch {
+        shard: u32,
+        clk: u32,
+        pc: u32,
+        expected: u32,
+    },
+    /// The address is initialized more than once over the shards.
+    DoubleInitialization { addr: u32 },
+    /// The address is finalized more than once over the shards.
+    DoubleFinalization { addr: u32 },
+    /// The address is finalized without being initialized, or initialized for use without being
+    /// finalized.
+    UnmatchedMemory { addr: u32 },
+}
+
+impl std::fmt::Display for ShardTransitionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShardTransitionError::Index { position, index } => write!(
+                f,
+                "the shard at position {} has index {} instead of {}",
+                position,
+                index,
+                position + 1
+            ),
+            ShardTransitionError::Empty { index } => {
+                write!(f, "shard {} has no instruction", index)
+            }
+            ShardTransitionError::ClockOrder { shard, clk } => write!(
+                f,
+                "the instruction at shard {} clk {} does not come after the previous one",
+                shard, clk
+            ),
+            ShardTransitionError::PcMismatch {
+                shard,
+                clk,
+                pc,
+                expected,
+            } => write!(
+                f,
+                "the instruction at shard {} clk {} starts at {:#x} instead of {:#x}",
+                shard, clk, pc, expected
+            ),
+            ShardTransitionError::DoubleInitialization { addr } => {
+                write!(f, "{:#x} is initialized more than once", addr)
+            }
+            ShardTransitionError::DoubleFinalization { addr } => {
+                write!(f, "{:#x} is finalized more than once", addr)
+            }
+            ShardTransitionError::UnmatchedMemory { addr } => {
+                write!(f, "{:#x} is not both initialized and finalized", addr)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ShardTransitionError {}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ShardStats {
     pub nb_cpu_events: usize,
     pub nb_add_events: usize,
@@ -163,10 +556,27 @@ impl ExecutionRecord {
     }
 
     pub fn shard(self, config: &ShardingConfig) -> Vec<Self> {
-        // Make the shard vector by splitting CPU and program events.
-        let mut shards = self
-            .cpu_events
-            .chunks(config.shard_size())
+        // Make a shard of the CPU events of each shard of the runtime, so that the shards of the
+        // proof are numbered contiguously.
+        let events = &self.cpu_events;
+        let mut chunks: Vec<&[CpuEvent]> = Vec::new();
+        let mut start = 0;
+        for end in 1..=events.len() {
+            if end == events.len() || events[end].shard != events[start].shard {
+                let chunk = &events[start..end];
+                assert!(
+                    chunk.len() <= config.shard_size(),
+                    "shard {} of the runtime has {} instructions, more than the shard size {}",
+                    chunk[0].shard,
+                    chunk.len(),
+                    config.shard_size()
+                );
+                chunks.push(chunk);
+                start = end;
+            }
+        }
+        let mut shards = chunks
+            .into_iter()
             .enumerate()
             .map(|(i, chunk)| {
                 let mut shard = ExecutionRecord::default();
@@ -259,70 +669,124 @@ impl ExecutionRecord {
             shard.field_events.extend_from_slice(field_chunk);
         }
 
-        // Keccak-256 permute events.
-        for (keccak_chunk, shard) in self
-            .keccak_permute_events
-            .chunks(config.keccak_len)
-            .zip(shards.iter_mut())
-        {
-            shard.keccak_permute_events.extend_from_slice(keccak_chunk);
-        }
+        // Shard the precompile events.
+        if config.split_precompile_events {
+            follow_ecalls(&mut shards, self.sha_extend_events, |shard| {
+                &mut shard.sha_extend_events
+            });
+            follow_ecalls(&mut shards, self.sha_compress_events, |shard| {
+                &mut shard.sha_compress_events
+            });
+            follow_ecalls(&mut shards, self.keccak_permute_events, |shard| {
+                &mut shard.keccak_permute_events
+            });
+            follow_ecalls(&mut shards, self.ed_add_events, |shard| {
+                &mut shard.ed_add_events
+            });
+            follow_ecalls(&mut shards, self.ed_decompress_events, |shard| {
+                &mut shard.ed_decompress_events
+            });
+            follow_ecalls(&mut shards, self.weierstrass_add_events, |shard| {
+                &mut shard.weierstrass_add_events
+            });
+            follow_ecalls(&mut shards, self.weierstrass_double_events, |shard| {
+                &mut shard.weierstrass_double_events
+            });
+            follow_ecalls(&mut shards, self.k256_decompress_events, |shard| {
+                &mut shard.k256_decompress_events
+            });
+            follow_ecalls(&mut shards, self.blake3_compress_inner_events, |shard| {
+                &mut shard.blake3_compress_inner_events
+            });
+            follow_ecalls(&mut shards, self.bitmanip_events, |shard| {
+                &mut shard.bitmanip_events
+            });
+            follow_ecalls(&mut shards, self.uint256_mulmod_events, |shard| {
+                &mut shard.uint256_mulmod_events
+            });
+        } else {
+            // Keccak-256 permute events.
+            for (keccak_chunk, shard) in self
+                .keccak_permute_events
+                .chunks(config.keccak_len)
+                .zip(shards.iter_mut())
+            {
+                shard.keccak_permute_events.extend_from_slice(keccak_chunk);
+            }
 
-        // Weierstrass curve add events.
-        for (weierstrass_add_chunk, shard) in self
-            .weierstrass_add_events
-            .chunks(config.weierstrass_add_len)
-            .zip(shards.iter_mut())
-        {
-            shard
+            // Weierstrass curve add events.
+            for (weierstrass_add_chunk, shard) in self
                 .weierstrass_add_events
-                .extend_from_slice(weierstrass_add_chunk);
-        }
+                .chunks(config.weierstrass_add_len)
+                .zip(shards.iter_mut())
+            {
+                shard
+                    .weierstrass_add_events
+                    .extend_from_slice(weierstrass_add_chunk);
+            }
 
-        // Weierstrass curve double events.
-        for (weierstrass_double_chunk, shard) in self
-            .weierstrass_double_events
-            .chunks(config.weierstrass_double_len)
-            .zip(shards.iter_mut())
-        {
-            shard
+            // Weierstrass curve double events.
+            for (weierstrass_double_chunk, shard) in self
                 .weierstrass_double_events
-                .extend_from_slice(weierstrass_double_chunk);
-        }
-
-        // Put the precompile events in the first shard.
-        let first = shards.first_mut().unwrap();
-
-        // SHA-256 extend events.
-        first
-            .sha_extend_events
-            .extend_from_slice(&self.sha_extend_events);
-
-        // SHA-256 compress events.
-        first
-            .sha_compress_events
-            .extend_from_slice(&self.sha_compress_events);
-
-        // Edwards curve add events.
-        first.ed_add_events.extend_from_slice(&self.ed_add_events);
-
-        // Edwards curve decompress events.
-        first
-            .ed_decompress_events
-            .extend_from_slice(&self.ed_decompress_events);
+                .chunks(config.weierstrass_double_len)
+                .zip(shards.iter_mut())
+            {
+                shard
+                    .weierstrass_double_events
+                    .extend_from_slice(weierstrass_double_chunk);
+            }
 
-        // K256 curve decompress events.
-        first
-            .k256_decompress_events
-            .extend_from_slice(&self.k256_decompress_events);
+            // Put the precompile events in the first shard.
+            let first = shards.first_mut().unwrap();
+
+            // SHA-256 extend events.
+            first
+                .sha_extend_events
+                .extend_from_slice(&self.sha_extend_events);
+
+            // SHA-256 compress events.
+            first
+                .sha_compress_events
+                .extend_from_slice(&self.sha_compress_events);
+
+            // Edwards curve add events.
+            first.ed_add_events.extend_from_slice(&self.ed_add_events);
+
+            // Edwards curve decompress events.
+            first
+                .ed_decompress_events
+                .extend_from_slice(&self.ed_decompress_events);
+
+            // K256 curve decompress events.
+            first
+                .k256_decompress_events
+                .extend_from_slice(&self.k256_decompress_events);
+
+            // Blake3 compress events .
+            first
+                .blake3_compress_inner_events
+                .extend_from_slice(&self.blake3_compress_inner_events);
+
+            // Bit manipulation events.
+            first
+                .bitmanip_events
+                .extend_from_slice(&self.bitmanip_events);
+
+            // Uint256 mulmod events.
+            first
+                .uint256_mulmod_events
+                .extend_from_slice(&self.uint256_mulmod_events);
+        }
 
-        // Blake3 compress events .
-        first
-            .blake3_compress_inner_events
-            .extend_from_slice(&self.blake3_compress_inner_events);
+        // Put all byte lookups and range checks in the first shard (as the table sizes are fixed)
+        shards[0].byte_lookups.extend(&self.byte_lookups);
+        shards[0].range_checks.extend(&self.range_checks);
 
-        // Put all byte lookups in the first shard (as the table size is fixed)
-        first.byte_lookups.extend(&self.byte_lookups);
+        // Memcpy events follow the ecalls which emitted them, so that the chunks of a long copy
+        // are spread over the shards it spans.
+        follow_ecalls(&mut shards, self.memcpy_events, |shard| {
+            &mut shard.memcpy_events
+        });
 
         // Put the memory records in the last shard.
         let last_shard = shards.last_mut().unwrap();
@@ -337,9 +801,250 @@ impl ExecutionRecord {
             .program_memory_record
             .extend_from_slice(&self.program_memory_record);
 
+        for shard in shards.iter() {
+            if let Err(err) = shard.check_multiplicities(config.max_multiplicity) {
+                panic!("shard {}: {}", shard.index, err);
+            }
+        }
+
         shards
     }
 
+    /// Checks that the shards carry the execution over from one to the next: they are numbered from
+    /// one, their instructions follow each other in the order of the shards and of their clocks,
+    /// each starting where the previous one ended, and every address used is initialized and
+    /// finalized exactly once over all the shards.
+    pub fn validate_shard_transitions(
+        shards: &[ExecutionRecord],
+    ) -> Result<(), ShardTransitionError> {
+        let mut previous: Option<&CpuEvent> = None;
+        for (position, shard) in shards.iter().enumerate() {
+            if shard.index as usize != position + 1 {
+                return Err(ShardTransitionError::Index {
+                    position,
+                    index: shard.index,
+                });
+            }
+            if shard.cpu_events.is_empty() {
+                return Err(ShardTransitionError::Empty { index: shard.index });
+            }
+            for event in shard.cpu_events.iter() {
+                if let Some(previous) = previous {
+                    if (event.shard, event.clk) <= (previous.shard, previous.clk) {
+                        return Err(ShardTransitionError::ClockOrder {
+                            shard: event.shard,
+                            clk: event.clk,
+                        });
+                    }
+                    if event.pc != previous.next_pc {
+                        return Err(ShardTransitionError::PcMismatch {
+                            shard: event.shard,
+                            clk: event.clk,
+                            pc: event.pc,
+                            expected: previous.next_pc,
+                        });
+                    }
+                }
+                previous = Some(event);
+            }
+        }
+
+        // The addresses used are those initialized from zero and those of the program image it
+        // marks as used.
+        let mut initialized = BTreeMap::new();
+        let initializations = shards.iter().flat_map(|shard| {
+            shard
+                .first_memory_record
+                .iter()
+                .chain(shard.program_memory_record.iter())
+        });
+        for (addr, _, used) in initializations {
+            if initialized.insert(*addr, *used).is_some() {
+                return Err(ShardTransitionError::DoubleInitialization { addr: *addr });
+            }
+        }
+        let mut finalized = BTreeSet::new();
+        for (addr, _, _) in shards
+            .iter()
+            .flat_map(|shard| shard.last_memory_record.iter())
+        {
+            if !finalized.insert(*addr) {
+                return Err(ShardTransitionError::DoubleFinalization { addr: *addr });
+            }
+            if initialized.get(addr).copied().unwrap_or(0) == 0 {
+                return Err(ShardTransitionError::UnmatchedMemory { addr: *addr });
+            }
+        }
+        if let Some((addr, _)) = initialized
+            .iter()
+            .find(|(addr, used)| **used != 0 && !finalized.contains(*addr))
+        {
+            return Err(ShardTransitionError::UnmatchedMemory { addr: *addr });
+        }
+        Ok(())
+    }
+
+    /// Carves the events of a range of the shards emitted by the runtime out of the record, so that
+    /// they can be proven again without re-executing the program.
+    ///
+    /// The ALU events only carry their clock, which restarts in every shard, so they are derived
+    /// again from the CPU events of the range. The lookups derived from the events, such as the
+    /// byte lookups, are left out and recomputed when the dependencies of the result are generated.
+    ///
+    /// The memory is initialized with the state left by the shards before the range, at the first
+    /// access to each address in the range by the CPU or a precompile, and finalized at the last
+    /// one.
+    pub fn shard_range(&self, shards: Range<u32>) -> Result<ExecutionRecord, ShardRangeError> {
+        fn in_range<E: PrecompileEvent + Clone>(
+            events: &[E],
+            shards: &Range<u32>,
+            accesses: &mut Vec<(u32, MemoryRecordEnum)>,
+        ) -> Vec<E> {
+            let events = events
+                .iter()
+                .filter(|event| shards.contains(&event.shard()))
+                .cloned()
+                .collect::<Vec<_>>();
+            accesses.extend(events.iter().flat_map(PrecompileEvent::memory_accesses));
+            events
+        }
+
+        let mut record = ExecutionRecord::new(self.index, self.program.clone());
+        record.cpu_events = self
+            .cpu_events
+            .iter()
+            .filter(|event| shards.contains(&event.shard()))
+            .cloned()
+            .collect();
+        if record.cpu_events.is_empty() {
+            return Err(ShardRangeError::Empty(shards));
+        }
+
+        // The precompile events are emitted in the shard of their ECALL, along with the accesses
+        // they perform on its behalf.
+        let mut accesses = record
+            .cpu_events
+            .iter()
+            .flat_map(CpuEvent::memory_accesses)
+            .collect::<Vec<_>>();
+        record.sha_extend_events = in_range(&self.sha_extend_events, &shards, &mut accesses);
+        record.sha_compress_events = in_range(&self.sha_compress_events, &shards, &mut accesses);
+        record.keccak_permute_events =
+            in_range(&self.keccak_permute_events, &shards, &mut accesses);
+        record.ed_add_events = in_range(&self.ed_add_events, &shards, &mut accesses);
+        record.ed_decompress_events = in_range(&self.ed_decompress_events, &shards, &mut accesses);
+        record.weierstrass_add_events =
+            in_range(&self.weierstrass_add_events, &shards, &mut accesses);
+        record.weierstrass_double_events =
+            in_range(&self.weierstrass_double_events, &shards, &mut accesses);
+        record.k256_decompress_events =
+            in_range(&self.k256_decompress_events, &shards, &mut accesses);
+        record.blake3_compress_inner_events =
+            in_range(&self.blake3_compress_inner_events, &shards, &mut accesses);
+        record.bitmanip_events = in_range(&self.bitmanip_events, &shards, &mut accesses);
+        record.memcpy_events = in_range(&self.memcpy_events, &shards, &mut accesses);
+        record.uint256_mulmod_events =
+            in_range(&self.uint256_mulmod_events, &shards, &mut accesses);
+
+        let alu_events = record
+            .cpu_events
+            .iter()
+            .filter(|event| event.instruction.is_alu_instruction())
+            .map(|event| {
+                AluEvent::new(
+                    event.clk,
+                    event.instruction.opcode,
+                    event.a,
+                    event.b,
+                    event.c,
+                )
+            })
+            .collect::<Vec<_>>();
+        for event in alu_events {
+            record.add_alu_event(event);
+        }
+
+        // Replay the accesses of the range in order, checking that each one follows either the
+        // previous access to its address in the range or an access before the range.
+        accesses.sort_by_key(|(_, access)| {
+            let record = access.current_record();
+            (record.shard, record.timestamp)
+        });
+        let mut first_records = BTreeMap::<u32, MemoryRecord>::new();
+        let mut last_records = BTreeMap::<u32, MemoryRecord>::new();
+        for (addr, access) in accesses {
+            let (prev, current) = (access.previous_record(), access.current_record());
+            let follows = match last_records.get(&addr) {
+                Some(last) => (prev.shard, prev.timestamp) == (last.shard, last.timestamp),
+                None => prev.shard < shards.start,
+            };
+            if !follows {
+                return Err(ShardRangeError::DanglingAccess {
+                    addr,
+                    shard: current.shard,
+                    timestamp: current.timestamp,
+                });
+            }
+            first_records.entry(addr).or_insert(prev);
+            last_records.insert(addr, current);
+        }
+        record.first_memory_record = first_records
+            .into_iter()
+            .map(|(addr, record)| (addr, record, 1))
+            .collect();
+        record.last_memory_record = last_records
+            .into_iter()
+            .map(|(addr, record)| (addr, record, 1))
+            .collect();
+
+        // The program memory is preprocessed, so all of it is kept, without being used.
+        record.program_memory_record = self
+            .program_memory_record
+            .iter()
+            .map(|(addr, record, _)| (*addr, *record, 0))
+            .collect();
+
+        Ok(record)
+    }
+
+    /// Checks that no lookup of the shard is performed more than `bound` times.
+    pub fn check_multiplicities(&self, bound: usize) -> Result<(), MultiplicityOverflowError> {
+        // Each CPU event looks up one instruction, so no instruction is looked up more often than
+        // there are CPU events.
+        if self.cpu_events.len() > bound {
+            let mut instruction_counts = HashMap::<u32, usize>::new();
+            for event in self.cpu_events.iter() {
+                *instruction_counts.entry(event.pc).or_insert(0) += 1;
+            }
+            if let Some((pc, count)) = instruction_counts
+                .into_iter()
+                .find(|(_, count)| *count > bound)
+            {
+                return Err(MultiplicityOverflowError {
+                    lookup: format!("the instruction at pc {:#x}", pc),
+                    multiplicity: count,
+                    bound,
+                });
+            }
+        }
+
+        if let Some((lookup, mult)) = self.byte_lookups.iter().find(|(_, mult)| **mult > bound) {
+            return Err(MultiplicityOverflowError {
+                lookup: format!("{:?}", lookup),
+                multiplicity: *mult,
+                bound,
+            });
+        }
+        if let Some((check, mult)) = self.range_checks.iter().find(|(_, mult)| **mult > bound) {
+            return Err(MultiplicityOverflowError {
+                lookup: format!("{:?}", check),
+                multiplicity: *mult,
+                bound,
+            });
+        }
+        Ok(())
+    }
+
     pub fn add_mul_event(&mut self, mul_event: AluEvent) {
         self.mul_events.push(mul_event);
     }
@@ -363,6 +1068,46 @@ impl ExecutionRecord {
             .or_insert(1);
     }
 
+    /// Adds a `RangeCheckEvent` to verify that `value` fits in `bits` bits, which must be one of
+    /// the widths of `RANGE_CHECK_BITS`.
+    pub fn add_range_check(&mut self, value: u32, bits: u32) {
+        self.range_checks
+            .entry(RangeCheckEvent::new(value, bits))
+            .and_modify(|i| *i += 1)
+            .or_insert(1);
+    }
+
+    /// Adds an ALU event to the trace of its opcode, ignoring the opcodes with no ALU chip.
+    pub fn add_alu_event(&mut self, event: AluEvent) {
+        match event.opcode {
+            Opcode::ADD => {
+                self.add_events.push(event);
+            }
+            Opcode::SUB => {
+                self.sub_events.push(event);
+            }
+            Opcode::XOR | Opcode::OR | Opcode::AND => {
+                self.bitwise_events.push(event);
+            }
+            Opcode::SLL => {
+                self.shift_left_events.push(event);
+            }
+            Opcode::SRL | Opcode::SRA => {
+                self.shift_right_events.push(event);
+            }
+            Opcode::SLT | Opcode::SLTU => {
+                self.lt_events.push(event);
+            }
+            Opcode::MUL | Opcode::MULHU | Opcode::MULHSU | Opcode::MULH => {
+                self.mul_events.push(event);
+            }
+            Opcode::DIVU | Opcode::REMU | Opcode::DIV | Opcode::REM => {
+                self.divrem_events.push(event);
+            }
+            _ => {}
+        }
+    }
+
     pub fn add_alu_events(&mut self, alu_events: HashMap<Opcode, Vec<AluEvent>>) {
         for opcode in alu_events.keys() {
             match opcode {
@@ -507,6 +1252,11 @@ impl ExecutionRecord {
             .append(&mut other.k256_decompress_events);
         self.blake3_compress_inner_events
             .append(&mut other.blake3_compress_inner_events);
+        self.bitmanip_events.append(&mut other.bitmanip_events);
+        self.memcpy_events.append(&mut other.memcpy_events);
+        self.uint256_mulmod_events
+            .append(&mut other.uint256_mulmod_events);
+        self.committed_values.append(&mut other.committed_values);
 
         for (event, mult) in other.byte_lookups.iter_mut() {
             self.byte_lookups
@@ -514,6 +1264,12 @@ impl ExecutionRecord {
                 .and_modify(|i| *i += *mult)
                 .or_insert(*mult);
         }
+        for (event, mult) in other.range_checks.iter_mut() {
+            self.range_checks
+                .entry(*event)
+                .and_modify(|i| *i += *mult)
+                .or_insert(*mult);
+        }
 
         self.first_memory_record
             .append(&mut other.first_memory_record);
@@ -531,3 +1287,129 @@ pub struct CpuRecord {
     pub c: Option<MemoryRecordEnum>,
     pub memory: Option<MemoryRecordEnum>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ExecutionRecord, ShardTransitionError, ShardingConfig, MAX_LOOKUP_MULTIPLICITY};
+    use crate::bytes::{ByteLookupEvent, ByteOpcode};
+    use crate::runtime::{Opcode, Program, Runtime};
+    use crate::utils::tests::SHA_EXTEND_ELF;
+
+    #[test]
+    fn test_check_multiplicities() {
+        let event = ByteLookupEvent::new(ByteOpcode::U8Range, 0, 0, 1, 2);
+        let mut record = ExecutionRecord::default();
+        record.byte_lookups.insert(event, MAX_LOOKUP_MULTIPLICITY);
+        assert!(record.check_multiplicities(MAX_LOOKUP_MULTIPLICITY).is_ok());
+
+        let mut other = ExecutionRecord::default();
+        other.add_byte_lookup_event(event);
+        record.append(&mut other);
+        let err = record
+            .check_multiplicities(MAX_LOOKUP_MULTIPLICITY)
+            .unwrap_err();
+        assert_eq!(err.multiplicity, MAX_LOOKUP_MULTIPLICITY + 1);
+        assert_eq!(err.bound, MAX_LOOKUP_MULTIPLICITY);
+    }
+
+    #[test]
+    fn test_split_precompile_events() {
+        let mut runtime = Runtime::new(Program::from(SHA_EXTEND_ELF));
+        runtime.shard_size = 1 << 10;
+        runtime.run();
+        let config = ShardingConfig {
+            split_precompile_events: true,
+            ..ShardingConfig::with_shard_size(1 << 10)
+        };
+        let shards = runtime.record.clone().shard(&config);
+        assert!(shards.len() > 1);
+        ExecutionRecord::validate_shard_transitions(&shards).unwrap();
+
+        // Each event is in the shard of its ecall, the first instruction not before the event.
+        for shard in shards.iter() {
+            for event in shard.sha_extend_events.iter() {
+                let ecall = shard
+                    .cpu_events
+                    .iter()
+                    .find(|cpu| (cpu.shard, cpu.clk) >= (event.shard, event.clk))
+                    .unwrap();
+                assert_eq!(ecall.instruction.opcode, Opcode::ECALL);
+            }
+        }
+        let num_events = shards
+            .iter()
+            .map(|shard| shard.sha_extend_events.len())
+            .sum::<usize>();
+        assert_eq!(num_events, runtime.record.sha_extend_events.len());
+
+        let shards = runtime
+            .record
+            .shard(&ShardingConfig::with_shard_size(1 << 10));
+        assert_eq!(
+            shards[0].sha_extend_events.len(),
+            num_events,
+            "the events are kept in the first shard by default"
+        );
+    }
+
+    #[test]
+    fn test_validate_shard_transitions() {
+        let mut runtime = Runtime::new(Program::from(SHA_EXTEND_ELF));
+        runtime.shard_size = 1 << 10;
+        runtime.run();
+        let shards = runtime
+            .record
+            .shard(&ShardingConfig::with_shard_size(1 << 10));
+        assert!(shards.len() > 2);
+        ExecutionRecord::validate_shard_transitions(&shards).unwrap();
+
+        let mut swapped = shards.clone();
+        swapped.swap(1, 2);
+        assert_eq!(
+            ExecutionRecord::validate_shard_transitions(&swapped),
+            Err(ShardTransitionError::Index {
+                position: 1,
+                index: 3
+            })
+        );
+
+        let mut reordered = shards.clone();
+        let previous = *reordered[0].cpu_events.last().unwrap();
+        reordered[1].cpu_events[0].shard = previous.shard;
+        reordered[1].cpu_events[0].clk = previous.clk;
+        assert_eq!(
+            ExecutionRecord::validate_shard_transitions(&reordered),
+            Err(ShardTransitionError::ClockOrder {
+                shard: previous.shard,
+                clk: previous.clk
+            })
+        );
+
+        let mut skipped = shards.clone();
+        skipped[1].cpu_events.remove(0);
+        assert!(matches!(
+            ExecutionRecord::validate_shard_transitions(&skipped),
+            Err(ShardTransitionError::PcMismatch { .. })
+        ));
+
+        let mut finalized_twice = shards.clone();
+        let last = finalized_twice.last().unwrap().last_memory_record[0];
+        finalized_twice[0].last_memory_record.push(last);
+        assert_eq!(
+            ExecutionRecord::validate_shard_transitions(&finalized_twice),
+            Err(ShardTransitionError::DoubleFinalization { addr: last.0 })
+        );
+
+        let mut unfinalized = shards;
+        let last = unfinalized
+            .last_mut()
+            .unwrap()
+            .last_memory_record
+            .pop()
+            .unwrap();
+        assert_eq!(
+            ExecutionRecord::validate_shard_transitions(&unfinalized),
+            Err(ShardTransitionError::UnmatchedMemory { addr: last.0 })
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+
+use super::{CostModel, Opcode, SyscallCode};
+
+/// A summary of the execution of a program.
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionReport {
+    /// The number of times each instruction was executed.
+    pub opcode_counts: HashMap<Opcode, u64>,
+
+    /// The number of clock cycles of the execution, including those of syscalls.
+    ///
+    /// Like the counts, it includes the cycles of unconstrained blocks, which are not proven.
+    pub cycles: u64,
+
+    /// The number of times each syscall was invoked.
+    pub syscall_counts: HashMap<SyscallCode, u64>,
+
+    /// The number of bytes of memory read or written by each syscall.
+    pub syscall_bytes: HashMap<SyscallCode, u64>,
+}
+
+impl ExecutionReport {
+    /// Record an executed instruction which advanced the clock by the given number of cycles.
+    pub fn record_instruction(&mut self, opcode: Opcode, cycles: u64) {
+        *self.opcode_counts.entry(opcode).or_insert(0) += 1;
+        self.cycles += cycles;
+    }
+
+    /// The number of executed instructions, which the proof of the execution commits to unless
+    /// some of them were in unconstrained blocks.
+    pub fn instructions(&self) -> u64 {
+        self.opcode_counts.values().sum()
+    }
+
+    /// Record an invocation of a syscall which touched the given number of bytes of memory.
+    pub fn record_syscall(&mut self, code: SyscallCode, bytes_touched: u64) {
+        *self.syscall_counts.entry(code).or_insert(0) += 1;
+        *self.syscall_bytes.entry(code).or_insert(0) += bytes_touched;
+    }
+}
+
+impl Display for ExecutionReport {
+    /// Breaks the cycles down by instruction category and syscall, as charged by `CostModel`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut categories = BTreeMap::new();
+        for (opcode, count) in self.opcode_counts.iter() {
+            *categories
+                .entry(CostModel::instruction_category(*opcode))
+                .or_insert(0) += CostModel::instruction_cost(*opcode) as u64 * count;
+        }
+        let mut syscalls = self
+            .syscall_counts
+            .iter()
+            .map(|(code, count)| {
+                let input_len = self.syscall_bytes.get(code).copied().unwrap_or(0) / count;
+                (
+                    format!("{:?}", code),
+                    CostModel::syscall_cost(*code, input_len as usize) * count,
+                )
+            })
+            .collect::<Vec<_>>();
+        syscalls.sort();
+
+        write!(f, "{} cycles", self.cycles)?;
+        for (category, cycles) in categories {
+            write!(f, "\n  {}: {}", category, cycles)?;
+        }
+        for (name, cycles) in syscalls {
+            write!(f, "\n  syscall {}: {}", name, cycles)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+
+use super::{ExecutionError, Runtime};
+
+/// A digest of the state of an execution, for comparing two executions of a program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// The number of instructions executed so far.
+    pub global_clk: u32,
+
+    /// The program counter.
+    pub pc: u32,
+
+    /// The values of the registers.
+    pub registers: [u32; 32],
+
+    /// The blake3 digest of the non-zero words of memory, by increasing address.
+    pub memory_digest: [u8; 32],
+}
+
+impl Snapshot {
+    /// Capture the current state of the runtime.
+    pub fn capture(rt: &Runtime) -> Self {
+        let mut words = rt
+            .state
+            .memory
+            .iter()
+            .filter(|(_, (value, _, _))| *value != 0)
+            .map(|(addr, (value, _, _))| (*addr, *value))
+            .collect::<Vec<_>>();
+        words.sort_unstable();
+
+        let mut hasher = blake3::Hasher::new();
+        for (addr, value) in words {
+            hasher.update(&addr.to_le_bytes());
+            hasher.update(&value.to_le_bytes());
+        }
+        Self {
+            global_clk: rt.state.global_clk,
+            pc: rt.state.pc,
+            registers: rt.registers(),
+            memory_digest: hasher.finalize().into(),
+        }
+    }
+}
+
+impl Runtime {
+    /// Execute the program like `run`, capturing a snapshot of the state before the first
+    /// instruction, after every `interval` instructions and once the program halts.
+    pub fn run_and_snapshot(&mut self, interval: u32) -> Vec<Snapshot> {
+        assert!(interval > 0, "snapshots need a positive interval");
+        self.initialize();
+        let mut snapshots = vec![Snapshot::capture(self)];
+        while !self.halted() {
+            if let Err(err) = self.step() {
+                panic!("{}", err);
+            }
+            if self.state.global_clk % interval == 0 || self.halted() {
+                snapshots.push(Snapshot::capture(self));
+            }
+        }
+        if let Err(err) = self.finalize() {
+            panic!("{}", err);
+        }
+        snapshots
+    }
+
+    /// Step the execution until it executed `global_clk` instructions or halted.
+    pub fn run_until(&mut self, global_clk: u32) -> Result<(), ExecutionError> {
+        while self.state.global_clk < global_clk && !self.halted() {
+            self.step()?;
+        }
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
 use hashbrown::HashMap;
 use nohash_hasher::BuildNoHashHasher;
 
-use super::{CpuRecord, ExecutionRecord};
+use super::{Continuation, CpuRecord, ExecutionRecord};
 
 /// Holds data describing the current state of a program's execution.
 #[derive(Debug, Clone, Default)]
@@ -19,6 +19,9 @@ pub struct ExecutionState {
     /// The program counter.
     pub pc: u32,
 
+    /// Whether the program executed the halt syscall, after which no instruction is executed.
+    pub halted: bool,
+
     /// The memory which instructions operate over. Values contain the memory value and last shard
     /// + timestamp that each memory address was accessed.
     pub memory: HashMap<u32, (u32, u32, u32), BuildNoHashHasher<u32>>,
@@ -29,11 +32,24 @@ pub struct ExecutionState {
     /// A ptr to the current position in the input stream incremented by LWA opcode.
     pub input_stream_ptr: usize,
 
+    /// The number of bytes of the input stream read by `LWA` outside of unconstrained blocks,
+    /// which the CPU chip counts into the public values of the shards.
+    pub input_read: usize,
+
     /// A stream of output values from the program (global to entire program).
     pub output_stream: Vec<u8>,
 
     /// A ptr to the current position in the output stream, incremented when reading from output_stream.
     pub output_stream_ptr: usize,
+
+    /// The bytes written by the program to the hint stream and to any custom file descriptor.
+    pub fd_outputs: HashMap<u32, Vec<u8>>,
+
+    /// The progress of a syscall which resumes at the next `ecall`.
+    pub continuation: Option<Continuation>,
+
+    /// The number of syscall invocations which were given a continuation id.
+    pub num_continuations: u32,
 }
 
 impl ExecutionState {
@@ -44,11 +60,16 @@ impl ExecutionState {
             current_shard: 1,
             clk: 0,
             pc: pc_start,
+            halted: false,
             memory: HashMap::default(),
             input_stream: Vec::new(),
             input_stream_ptr: 0,
+            input_read: 0,
             output_stream: Vec::new(),
             output_stream_ptr: 0,
+            fd_outputs: HashMap::new(),
+            continuation: None,
+            num_continuations: 0,
         }
     }
 }
@@ -1,17 +1,21 @@
 use std::collections::HashMap;
 use std::rc::Rc;
 
-use crate::runtime::{Register, Runtime};
+use crate::runtime::{syscall_codes, Register, Runtime};
+use crate::syscall::precompiles::bitmanip::BitManipChip;
 use crate::syscall::precompiles::blake3::Blake3CompressInnerChip;
 use crate::syscall::precompiles::edwards::EdAddAssignChip;
 use crate::syscall::precompiles::edwards::EdDecompressChip;
 use crate::syscall::precompiles::k256::K256DecompressChip;
 use crate::syscall::precompiles::keccak256::KeccakPermuteChip;
+use crate::syscall::precompiles::memcpy::MemcpyChip;
 use crate::syscall::precompiles::sha256::{ShaCompressChip, ShaExtendChip};
+use crate::syscall::precompiles::uint256::Uint256MulModChip;
 use crate::syscall::precompiles::weierstrass::WeierstrassAddAssignChip;
 use crate::syscall::precompiles::weierstrass::WeierstrassDoubleAssignChip;
 use crate::syscall::{
-    SyscallEnterUnconstrained, SyscallExitUnconstrained, SyscallHalt, SyscallLWA, SyscallWrite,
+    SyscallCommit, SyscallCycleCount, SyscallEnterUnconstrained, SyscallExitUnconstrained,
+    SyscallGetenv, SyscallHalt, SyscallLWA, SyscallWrite,
 };
 use crate::utils::ec::edwards::ed25519::{Ed25519, Ed25519Parameters};
 use crate::utils::ec::weierstrass::secp256k1::Secp256k1;
@@ -20,72 +24,214 @@ use crate::{cpu::MemoryReadRecord, cpu::MemoryWriteRecord, runtime::ExecutionRec
 /// A system call is invoked by the the `ecall` instruction with a specific value in register t0.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[allow(non_camel_case_types)]
+#[repr(u32)]
 pub enum SyscallCode {
     /// Halts the program.
-    HALT = 100,
+    HALT = syscall_codes::HALT,
 
     /// Loads a word supplied from the prover.
-    LWA = 101,
+    LWA = syscall_codes::LWA,
 
     /// Executes the `SHA_EXTEND` precompile.
-    SHA_EXTEND = 102,
+    SHA_EXTEND = syscall_codes::SHA_EXTEND,
 
     /// Executes the `SHA_COMPRESS` precompile.
-    SHA_COMPRESS = 103,
+    SHA_COMPRESS = syscall_codes::SHA_COMPRESS,
 
     /// Executes the `ED_ADD` precompile.
-    ED_ADD = 104,
+    ED_ADD = syscall_codes::ED_ADD,
 
     /// Executes the `ED_DECOMPRESS` precompile.
-    ED_DECOMPRESS = 105,
+    ED_DECOMPRESS = syscall_codes::ED_DECOMPRESS,
 
     /// Executes the `KECCAK_PERMUTE` precompile.
-    KECCAK_PERMUTE = 106,
+    KECCAK_PERMUTE = syscall_codes::KECCAK_PERMUTE,
 
     /// Executes the `SECP256K1_ADD` precompile.
-    SECP256K1_ADD = 107,
+    SECP256K1_ADD = syscall_codes::SECP256K1_ADD,
 
     /// Executes the `SECP256K1_DOUBLE` precompile.
-    SECP256K1_DOUBLE = 108,
+    SECP256K1_DOUBLE = syscall_codes::SECP256K1_DOUBLE,
 
     /// Executes the `SECP256K1_DECOMPRESS` precompile.
-    SECP256K1_DECOMPRESS = 109,
+    SECP256K1_DECOMPRESS = syscall_codes::SECP256K1_DECOMPRESS,
 
     /// Enter unconstrained block.
-    ENTER_UNCONSTRAINED = 110,
+    ENTER_UNCONSTRAINED = syscall_codes::ENTER_UNCONSTRAINED,
 
     /// Exit unconstrained block.
-    EXIT_UNCONSTRAINED = 111,
+    EXIT_UNCONSTRAINED = syscall_codes::EXIT_UNCONSTRAINED,
 
     /// Executes the `BLAKE3_COMPRESS_INNER` precompile.
-    BLAKE3_COMPRESS_INNER = 112,
+    BLAKE3_COMPRESS_INNER = syscall_codes::BLAKE3_COMPRESS_INNER,
 
-    WRITE = 999,
+    /// Executes the `BITMANIP` precompile.
+    BITMANIP = syscall_codes::BITMANIP,
+
+    /// Executes the `MEMCPY` precompile.
+    MEMCPY = syscall_codes::MEMCPY,
+
+    /// Looks up an environment variable.
+    GETENV = syscall_codes::GETENV,
+
+    /// Reads the clock of the VM.
+    CYCLE_COUNT = syscall_codes::CYCLE_COUNT,
+
+    /// Executes the `UINT256_MULMOD` precompile.
+    UINT256_MULMOD = syscall_codes::UINT256_MULMOD,
+
+    /// Commits bytes to the public values of the proof.
+    COMMIT = syscall_codes::COMMIT,
+
+    WRITE = syscall_codes::WRITE,
 }
 
+/// The number of syscalls executed by a precompile chip.
+pub const NUM_PRECOMPILES: usize = 12;
+
 impl SyscallCode {
+    /// The syscalls executed by a precompile chip, which receives each of their `ECALL`s.
+    pub const PRECOMPILES: [SyscallCode; NUM_PRECOMPILES] = [
+        SyscallCode::SHA_EXTEND,
+        SyscallCode::SHA_COMPRESS,
+        SyscallCode::ED_ADD,
+        SyscallCode::ED_DECOMPRESS,
+        SyscallCode::KECCAK_PERMUTE,
+        SyscallCode::SECP256K1_ADD,
+        SyscallCode::SECP256K1_DOUBLE,
+        SyscallCode::SECP256K1_DECOMPRESS,
+        SyscallCode::BLAKE3_COMPRESS_INNER,
+        SyscallCode::BITMANIP,
+        SyscallCode::MEMCPY,
+        SyscallCode::UINT256_MULMOD,
+    ];
+
     /// Create a syscall from a u32.
     pub fn from_u32(value: u32) -> Self {
-        match value {
-            100 => SyscallCode::HALT,
-            101 => SyscallCode::LWA,
-            102 => SyscallCode::SHA_EXTEND,
-            103 => SyscallCode::SHA_COMPRESS,
-            104 => SyscallCode::ED_ADD,
-            105 => SyscallCode::ED_DECOMPRESS,
-            106 => SyscallCode::KECCAK_PERMUTE,
-            107 => SyscallCode::SECP256K1_ADD,
-            108 => SyscallCode::SECP256K1_DOUBLE,
-            109 => SyscallCode::SECP256K1_DECOMPRESS,
-            110 => SyscallCode::ENTER_UNCONSTRAINED,
-            111 => SyscallCode::EXIT_UNCONSTRAINED,
-            112 => SyscallCode::BLAKE3_COMPRESS_INNER,
-            999 => SyscallCode::WRITE,
-            _ => panic!("invalid syscall number: {}", value),
-        }
+        Self::try_from(value).unwrap_or_else(|_| panic!("invalid syscall number: {}", value))
+    }
+}
+
+impl TryFrom<u32> for SyscallCode {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        Ok(match value {
+            syscall_codes::HALT => SyscallCode::HALT,
+            syscall_codes::LWA => SyscallCode::LWA,
+            syscall_codes::SHA_EXTEND => SyscallCode::SHA_EXTEND,
+            syscall_codes::SHA_COMPRESS => SyscallCode::SHA_COMPRESS,
+            syscall_codes::ED_ADD => SyscallCode::ED_ADD,
+            syscall_codes::ED_DECOMPRESS => SyscallCode::ED_DECOMPRESS,
+            syscall_codes::KECCAK_PERMUTE => SyscallCode::KECCAK_PERMUTE,
+            syscall_codes::SECP256K1_ADD => SyscallCode::SECP256K1_ADD,
+            syscall_codes::SECP256K1_DOUBLE => SyscallCode::SECP256K1_DOUBLE,
+            syscall_codes::SECP256K1_DECOMPRESS => SyscallCode::SECP256K1_DECOMPRESS,
+            syscall_codes::ENTER_UNCONSTRAINED => SyscallCode::ENTER_UNCONSTRAINED,
+            syscall_codes::EXIT_UNCONSTRAINED => SyscallCode::EXIT_UNCONSTRAINED,
+            syscall_codes::BLAKE3_COMPRESS_INNER => SyscallCode::BLAKE3_COMPRESS_INNER,
+            syscall_codes::BITMANIP => SyscallCode::BITMANIP,
+            syscall_codes::MEMCPY => SyscallCode::MEMCPY,
+            syscall_codes::GETENV => SyscallCode::GETENV,
+            syscall_codes::CYCLE_COUNT => SyscallCode::CYCLE_COUNT,
+            syscall_codes::UINT256_MULMOD => SyscallCode::UINT256_MULMOD,
+            syscall_codes::COMMIT => SyscallCode::COMMIT,
+            syscall_codes::WRITE => SyscallCode::WRITE,
+            _ => return Err(value),
+        })
+    }
+}
+
+impl From<SyscallCode> for u32 {
+    fn from(code: SyscallCode) -> Self {
+        code as u32
+    }
+}
+
+/// How much information about each invoked syscall is logged.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum SyscallLogMode {
+    /// Nothing is logged.
+    #[default]
+    Off,
+
+    /// Only the syscall id and clock are logged.
+    Ids,
+
+    /// The argument registers and a snippet of the memory they point to are logged as well.
+    Full,
+}
+
+/// The configuration for logging the syscalls invoked by a guest program.
+#[derive(Debug, Clone, Default)]
+pub struct SyscallLogging {
+    /// How much information is logged.
+    pub mode: SyscallLogMode,
+
+    /// The syscalls for which only the id is ever logged, because their arguments are sensitive.
+    pub redact: Vec<SyscallCode>,
+}
+
+/// The information logged about a single syscall invocation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyscallLogEntry {
+    /// The invoked syscall.
+    pub code: SyscallCode,
+
+    /// The clock cycle of the invocation.
+    pub clk: u32,
+
+    /// The values of a0 and a1, if arguments are logged.
+    pub args: Option<[u32; 2]>,
+
+    /// The first words of memory pointed to by a0, if arguments are logged.
+    pub snippet: Option<Vec<u32>>,
+}
+
+/// The number of words of memory pointed to by a0 that are logged in full mode.
+pub const SYSCALL_LOG_SNIPPET_WORDS: usize = 4;
+
+impl SyscallLogging {
+    /// Build the log entry of a syscall invocation, returning `None` if logging is off.
+    ///
+    /// The `snippet` closure is only called when the arguments of the syscall may be logged.
+    pub fn entry(
+        &self,
+        code: SyscallCode,
+        clk: u32,
+        args: [u32; 2],
+        snippet: impl FnOnce() -> Vec<u32>,
+    ) -> Option<SyscallLogEntry> {
+        let full = match self.mode {
+            SyscallLogMode::Off => return None,
+            SyscallLogMode::Ids => false,
+            SyscallLogMode::Full => !self.redact.contains(&code),
+        };
+        Some(SyscallLogEntry {
+            code,
+            clk,
+            args: full.then_some(args),
+            snippet: full.then(snippet),
+        })
     }
 }
 
+/// The state handed from one `ecall` of a syscall to the next, when the syscall is too long to
+/// execute in a single one.
+///
+/// Such a syscall executes a bounded amount of work per `ecall` and then re-executes the same
+/// `ecall`, which resumes from the continuation. Every `ecall` emits its own event, so the events
+/// of a single invocation may land in different shards. Their chips chain the events together with
+/// interactions keyed by the continuation id.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Continuation {
+    /// Identifies the invocation, shared by all of its events.
+    pub id: u32,
+
+    /// How much of the work the previous events have done, e.g. the number of words copied.
+    pub progress: u32,
+}
+
 pub trait Syscall {
     /// Execute the syscall and return the resulting value of register a0.
     fn execute(&self, ctx: &mut SyscallContext) -> u32;
@@ -95,6 +241,12 @@ pub trait Syscall {
     fn num_extra_cycles(&self) -> u32 {
         0
     }
+
+    /// Check the arguments of the syscall before it executes, returning why they are invalid if
+    /// they are, so that the runtime stops with an `ExecutionError` instead of panicking.
+    fn check(&self, _rt: &Runtime) -> Result<(), String> {
+        Ok(())
+    }
 }
 
 /// A runtime for syscalls that is protected so that developers cannot arbitrarily modify the runtime.
@@ -102,6 +254,9 @@ pub struct SyscallContext<'a> {
     current_shard: u32,
     pub clk: u32,
 
+    /// The number of bytes of memory read or written through memory records.
+    pub(crate) bytes_touched: u64,
+
     pub(crate) next_pc: u32,
     pub(crate) rt: &'a mut Runtime,
 }
@@ -113,6 +268,7 @@ impl<'a> SyscallContext<'a> {
         Self {
             current_shard,
             clk,
+            bytes_touched: 0,
             next_pc: runtime.state.pc.wrapping_add(4),
             rt: runtime,
         }
@@ -127,6 +283,7 @@ impl<'a> SyscallContext<'a> {
     }
 
     pub fn mr(&mut self, addr: u32) -> (MemoryReadRecord, u32) {
+        self.bytes_touched += 4;
         let record = self.rt.mr(addr, self.current_shard, self.clk);
         (record, record.value)
     }
@@ -143,6 +300,7 @@ impl<'a> SyscallContext<'a> {
     }
 
     pub fn mw(&mut self, addr: u32, value: u32) -> MemoryWriteRecord {
+        self.bytes_touched += 4;
         self.rt.mw(addr, value, self.current_shard, self.clk)
     }
 
@@ -180,6 +338,23 @@ impl<'a> SyscallContext<'a> {
     pub fn set_next_pc(&mut self, next_pc: u32) {
         self.next_pc = next_pc;
     }
+
+    /// Take the continuation left by the previous `ecall` of this syscall, or start a new
+    /// invocation with no progress.
+    pub fn resume(&mut self) -> Continuation {
+        self.rt.state.continuation.take().unwrap_or_else(|| {
+            let id = self.rt.state.num_continuations;
+            self.rt.state.num_continuations += 1;
+            Continuation { id, progress: 0 }
+        })
+    }
+
+    /// Leave `continuation` for the next `ecall`, and execute the same `ecall` again so that the
+    /// syscall resumes from it.
+    pub fn suspend(&mut self, continuation: Continuation) {
+        self.rt.state.continuation = Some(continuation);
+        self.next_pc = self.rt.state.pc;
+    }
 }
 
 pub fn default_syscall_map() -> HashMap<SyscallCode, Rc<dyn Syscall>> {
@@ -217,6 +392,12 @@ pub fn default_syscall_map() -> HashMap<SyscallCode, Rc<dyn Syscall>> {
         SyscallCode::BLAKE3_COMPRESS_INNER,
         Rc::new(Blake3CompressInnerChip::new()),
     );
+    syscall_map.insert(SyscallCode::BITMANIP, Rc::new(BitManipChip::new()));
+    syscall_map.insert(SyscallCode::MEMCPY, Rc::new(MemcpyChip::new()));
+    syscall_map.insert(
+        SyscallCode::UINT256_MULMOD,
+        Rc::new(Uint256MulModChip::new()),
+    );
     syscall_map.insert(
         SyscallCode::ENTER_UNCONSTRAINED,
         Rc::new(SyscallEnterUnconstrained::new()),
@@ -226,6 +407,35 @@ pub fn default_syscall_map() -> HashMap<SyscallCode, Rc<dyn Syscall>> {
         Rc::new(SyscallExitUnconstrained::new()),
     );
     syscall_map.insert(SyscallCode::WRITE, Rc::new(SyscallWrite::new()));
+    syscall_map.insert(SyscallCode::GETENV, Rc::new(SyscallGetenv::new()));
+    syscall_map.insert(SyscallCode::CYCLE_COUNT, Rc::new(SyscallCycleCount::new()));
+    syscall_map.insert(SyscallCode::COMMIT, Rc::new(SyscallCommit::new()));
 
     syscall_map
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{default_syscall_map, syscall_codes, SyscallCode};
+
+    #[test]
+    fn test_syscall_table() {
+        let syscall_map = default_syscall_map();
+        assert_eq!(syscall_map.len(), syscall_codes::SYSCALL_TABLE.len());
+        for (id, name, _) in syscall_codes::SYSCALL_TABLE {
+            let code = SyscallCode::try_from(id).unwrap();
+            assert_eq!(u32::from(code), id);
+            assert_eq!(format!("{:?}", code), name);
+            assert!(syscall_map.contains_key(&code), "{} has no handler", name);
+        }
+        assert_eq!(SyscallCode::try_from(0), Err(0));
+    }
+
+    #[test]
+    fn test_guest_syscall_codes() {
+        // The guest library must take its ids from the shared table rather than redefine them.
+        let guest = include_str!("../../../zkvm/entrypoint/src/syscalls/mod.rs");
+        assert!(guest.contains("include!(\"../../../../core/src/runtime/syscall_codes.rs\");"));
+        assert!(!guest.contains("pub const"));
+    }
+}
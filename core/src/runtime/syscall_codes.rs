@@ -0,0 +1,89 @@
+// The numbering of the syscalls, shared by the runtime and the guest library.
+//
+// `sp1-zkvm` includes this file verbatim with `include!`, so that the ids its `ecall` wrappers put
+// in t0 cannot drift from the ids the runtime dispatches on. It must therefore stay free of
+// imports, inner attributes and anything which needs `std`.
+
+/// Halts the program.
+pub const HALT: u32 = 100;
+
+/// Loads a word supplied from the prover.
+pub const LWA: u32 = 101;
+
+/// Executes `SHA_EXTEND`.
+pub const SHA_EXTEND: u32 = 102;
+
+/// Executes `SHA_COMPRESS`.
+pub const SHA_COMPRESS: u32 = 103;
+
+/// Executes `ED_ADD`.
+pub const ED_ADD: u32 = 104;
+
+/// Executes `ED_DECOMPRESS`.
+pub const ED_DECOMPRESS: u32 = 105;
+
+/// Executes `KECCAK_PERMUTE`.
+pub const KECCAK_PERMUTE: u32 = 106;
+
+/// Executes `SECP256K1_ADD`.
+pub const SECP256K1_ADD: u32 = 107;
+
+/// Executes `SECP256K1_DOUBLE`.
+pub const SECP256K1_DOUBLE: u32 = 108;
+
+/// Executes `K256_DECOMPRESS`.
+pub const SECP256K1_DECOMPRESS: u32 = 109;
+
+/// Enter an unconstrained execution block.
+pub const ENTER_UNCONSTRAINED: u32 = 110;
+
+/// Exit an unconstrained execution block.
+pub const EXIT_UNCONSTRAINED: u32 = 111;
+
+/// Executes `BLAKE3_COMPRESS_INNER`.
+pub const BLAKE3_COMPRESS_INNER: u32 = 112;
+
+/// Executes `BITMANIP`.
+pub const BITMANIP: u32 = 113;
+
+/// Executes `MEMCPY`.
+pub const MEMCPY: u32 = 114;
+
+/// Looks up an environment variable set by the host.
+pub const GETENV: u32 = 115;
+
+/// Reads the clock of the VM.
+pub const CYCLE_COUNT: u32 = 116;
+
+/// Executes `UINT256_MULMOD`.
+pub const UINT256_MULMOD: u32 = 117;
+
+/// Commits bytes to the public values of the proof, which the verifier reads from it.
+pub const COMMIT: u32 = 118;
+
+/// Writes to a file descriptor. Currently only used for `STDOUT/STDERR`.
+pub const WRITE: u32 = 999;
+
+/// Every syscall, as `(id, name, number of arguments which point to memory)`.
+pub const SYSCALL_TABLE: [(u32, &str, usize); 20] = [
+    (HALT, "HALT", 0),
+    (LWA, "LWA", 0),
+    (SHA_EXTEND, "SHA_EXTEND", 1),
+    (SHA_COMPRESS, "SHA_COMPRESS", 1),
+    (ED_ADD, "ED_ADD", 2),
+    (ED_DECOMPRESS, "ED_DECOMPRESS", 1),
+    (KECCAK_PERMUTE, "KECCAK_PERMUTE", 1),
+    (SECP256K1_ADD, "SECP256K1_ADD", 2),
+    (SECP256K1_DOUBLE, "SECP256K1_DOUBLE", 1),
+    (SECP256K1_DECOMPRESS, "SECP256K1_DECOMPRESS", 1),
+    (ENTER_UNCONSTRAINED, "ENTER_UNCONSTRAINED", 0),
+    (EXIT_UNCONSTRAINED, "EXIT_UNCONSTRAINED", 0),
+    (BLAKE3_COMPRESS_INNER, "BLAKE3_COMPRESS_INNER", 2),
+    (BITMANIP, "BITMANIP", 1),
+    (MEMCPY, "MEMCPY", 2),
+    (GETENV, "GETENV", 1),
+    (CYCLE_COUNT, "CYCLE_COUNT", 0),
+    (UINT256_MULMOD, "UINT256_MULMOD", 3),
+    (COMMIT, "COMMIT", 1),
+    (WRITE, "WRITE", 1),
+];
@@ -0,0 +1,36 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// The number of instructions between two checks of the watchdog.
+pub(crate) const TIMEOUT_CHECK_INTERVAL: u32 = 1 << 10;
+
+/// A thread raising a flag once a timeout elapses, unless it is dropped before.
+pub(crate) struct Watchdog {
+    expired: Arc<AtomicBool>,
+    // Dropping the sender wakes the thread up, which then exits without raising the flag.
+    _stop: Sender<()>,
+}
+
+impl Watchdog {
+    pub(crate) fn start(timeout: Duration) -> Self {
+        let expired = Arc::new(AtomicBool::new(false));
+        let (stop, stopped) = mpsc::channel::<()>();
+        let flag = expired.clone();
+        thread::spawn(move || {
+            if let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(timeout) {
+                flag.store(true, Ordering::Relaxed);
+            }
+        });
+        Self {
+            expired,
+            _stop: stop,
+        }
+    }
+
+    pub(crate) fn expired(&self) -> bool {
+        self.expired.load(Ordering::Relaxed)
+    }
+}
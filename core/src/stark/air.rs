@@ -1,7 +1,6 @@
 use crate::air::MachineAir;
 pub use crate::air::SP1AirBuilder;
 use crate::memory::MemoryChipKind;
-use crate::runtime::ExecutionRecord;
 use p3_field::PrimeField32;
 pub use riscv_chips::*;
 
@@ -19,14 +18,19 @@ pub(crate) mod riscv_chips {
     pub use crate::cpu::CpuChip;
     pub use crate::field::FieldLtuChip;
     pub use crate::memory::MemoryGlobalChip;
+    pub use crate::memory::MemoryProgramChip;
     pub use crate::program::ProgramChip;
+    pub use crate::range::RangeCheckChip;
+    pub use crate::syscall::precompiles::bitmanip::BitManipChip;
     pub use crate::syscall::precompiles::blake3::Blake3CompressInnerChip;
     pub use crate::syscall::precompiles::edwards::EdAddAssignChip;
     pub use crate::syscall::precompiles::edwards::EdDecompressChip;
     pub use crate::syscall::precompiles::k256::K256DecompressChip;
     pub use crate::syscall::precompiles::keccak256::KeccakPermuteChip;
+    pub use crate::syscall::precompiles::memcpy::MemcpyChip;
     pub use crate::syscall::precompiles::sha256::ShaCompressChip;
     pub use crate::syscall::precompiles::sha256::ShaExtendChip;
+    pub use crate::syscall::precompiles::uint256::Uint256MulModChip;
     pub use crate::syscall::precompiles::weierstrass::WeierstrassAddAssignChip;
     pub use crate::syscall::precompiles::weierstrass::WeierstrassDoubleAssignChip;
     pub use crate::utils::ec::edwards::ed25519::Ed25519Parameters;
@@ -64,6 +68,8 @@ pub enum RiscvAir<F: PrimeField32> {
     ShiftRight(ShiftRightChip),
     /// A lookup table for byte operations.
     ByteLookup(ByteChip<F>),
+    /// A lookup table for range checks of several bit widths.
+    RangeCheck(RangeCheckChip),
     /// An table for `less than` operation on field elements.
     FieldLTU(FieldLtuChip),
     /// A table for initializing the memory state.
@@ -71,7 +77,7 @@ pub enum RiscvAir<F: PrimeField32> {
     /// A table for finalizing the memory state.
     MemoryFinal(MemoryGlobalChip),
     /// A table for initializing the program memory.
-    ProgramMemory(MemoryGlobalChip),
+    ProgramMemory(MemoryProgramChip),
     /// A precompile for sha256 extend.
     Sha256Extend(ShaExtendChip),
     /// A precompile for sha256 compress.
@@ -90,13 +96,20 @@ pub enum RiscvAir<F: PrimeField32> {
     KeccakP(KeccakPermuteChip),
     /// A precompile for the Blake3 compression function.
     Blake3Compress(Blake3CompressInnerChip),
+    /// A precompile for byte swaps, zero counts and population counts of a word.
+    BitManip(BitManipChip),
+    /// A precompile copying a buffer, in chunks which may span several shards.
+    Memcpy(MemcpyChip),
+    /// A precompile for the product of 256-bit integers modulo a 256-bit integer.
+    Uint256MulMod(Uint256MulModChip),
 }
 
 impl<F: PrimeField32> RiscvAir<F> {
     /// Get all the different RISC-V AIRs.
+    ///
+    /// The machine orders trace generation by the events the chips produce and consume, so a
+    /// machine extending these AIRs may add chips of its own anywhere.
     pub fn get_all() -> Vec<Self> {
-        // The order of the chips is important, as it is used to determine the order of trace
-        // generation. In the future, we will detect that order automatically.
         let mut chips = vec![];
         let cpu = CpuChip::default();
         chips.push(RiscvAir::Cpu(cpu));
@@ -122,6 +135,12 @@ impl<F: PrimeField32> RiscvAir<F> {
         chips.push(RiscvAir::KeccakP(keccak_permute));
         let blake3_compress_inner = Blake3CompressInnerChip::new();
         chips.push(RiscvAir::Blake3Compress(blake3_compress_inner));
+        let bitmanip = BitManipChip::new();
+        chips.push(RiscvAir::BitManip(bitmanip));
+        let memcpy = MemcpyChip::new();
+        chips.push(RiscvAir::Memcpy(memcpy));
+        let uint256_mulmod = Uint256MulModChip::new();
+        chips.push(RiscvAir::Uint256MulMod(uint256_mulmod));
         let add = AddChip::default();
         chips.push(RiscvAir::Add(add));
         let sub = SubChip::default();
@@ -142,45 +161,17 @@ impl<F: PrimeField32> RiscvAir<F> {
         chips.push(RiscvAir::MemoryInit(memory_init));
         let memory_finalize = MemoryGlobalChip::new(MemoryChipKind::Finalize);
         chips.push(RiscvAir::MemoryFinal(memory_finalize));
-        let program_memory_init = MemoryGlobalChip::new(MemoryChipKind::Program);
+        let program_memory_init = MemoryProgramChip::new();
         chips.push(RiscvAir::ProgramMemory(program_memory_init));
         let field_ltu = FieldLtuChip::default();
         chips.push(RiscvAir::FieldLTU(field_ltu));
         let byte = ByteChip::default();
         chips.push(RiscvAir::ByteLookup(byte));
+        let range_check = RangeCheckChip;
+        chips.push(RiscvAir::RangeCheck(range_check));
 
         chips
     }
-
-    /// Returns `true` if the given `shard` includes events for this AIR.
-    pub fn included(&self, shard: &ExecutionRecord) -> bool {
-        match self {
-            RiscvAir::Program(_) => true,
-            RiscvAir::Cpu(_) => true,
-            RiscvAir::Add(_) => !shard.add_events.is_empty(),
-            RiscvAir::Sub(_) => !shard.sub_events.is_empty(),
-            RiscvAir::Bitwise(_) => !shard.bitwise_events.is_empty(),
-            RiscvAir::Mul(_) => !shard.mul_events.is_empty(),
-            RiscvAir::DivRem(_) => !shard.divrem_events.is_empty(),
-            RiscvAir::Lt(_) => !shard.lt_events.is_empty(),
-            RiscvAir::ShiftLeft(_) => !shard.shift_left_events.is_empty(),
-            RiscvAir::ShiftRight(_) => !shard.shift_right_events.is_empty(),
-            RiscvAir::ByteLookup(_) => !shard.byte_lookups.is_empty(),
-            RiscvAir::FieldLTU(_) => !shard.field_events.is_empty(),
-            RiscvAir::MemoryInit(_) => !shard.first_memory_record.is_empty(),
-            RiscvAir::MemoryFinal(_) => !shard.last_memory_record.is_empty(),
-            RiscvAir::ProgramMemory(_) => !shard.program_memory_record.is_empty(),
-            RiscvAir::Sha256Extend(_) => !shard.sha_extend_events.is_empty(),
-            RiscvAir::Sha256Compress(_) => !shard.sha_compress_events.is_empty(),
-            RiscvAir::Ed25519Add(_) => !shard.ed_add_events.is_empty(),
-            RiscvAir::Ed25519Decompress(_) => !shard.ed_decompress_events.is_empty(),
-            RiscvAir::K256Decompress(_) => !shard.k256_decompress_events.is_empty(),
-            RiscvAir::Secp256k1Add(_) => !shard.weierstrass_add_events.is_empty(),
-            RiscvAir::Secp256k1Double(_) => !shard.weierstrass_double_events.is_empty(),
-            RiscvAir::KeccakP(_) => !shard.keccak_permute_events.is_empty(),
-            RiscvAir::Blake3Compress(_) => !shard.blake3_compress_inner_events.is_empty(),
-        }
-    }
 }
 
 impl<F: PrimeField32> PartialEq for RiscvAir<F> {
@@ -0,0 +1,139 @@
+use core::fmt::{Display, Formatter};
+
+use p3_field::Field;
+
+use crate::air::MachineAir;
+use crate::lookup::InteractionKind;
+
+use super::Chip;
+
+/// Two interactions of the same kind with a different number of values.
+///
+/// All the interactions of a kind are summed in one argument, so a message of one arity could
+/// balance one of another, padded with whatever values follow it in the fingerprint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InteractionArityError {
+    pub kind: InteractionKind,
+    /// The chip with the first interaction of the kind, and its number of values.
+    pub expected: (String, usize),
+    /// The chip with an interaction of a different number of values, and that number.
+    pub found: (String, usize),
+}
+
+impl Display for InteractionArityError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{} interactions of {} have {} values, but those of {} have {}",
+            self.kind, self.found.0, self.found.1, self.expected.0, self.expected.1
+        )
+    }
+}
+
+impl std::error::Error for InteractionArityError {}
+
+/// Checks that the interactions of each kind, sent or received by any of the chips, all have the
+/// same number of values.
+pub fn check_interaction_arities<F: Field, A: MachineAir<F>>(
+    chips: &[Chip<F, A>],
+) -> Result<(), InteractionArityError> {
+    let mut arities = Vec::<(InteractionKind, (String, usize))>::new();
+    for chip in chips {
+        for interaction in chip.sends().iter().chain(chip.receives()) {
+            let arity = interaction.values.len();
+            match arities.iter().find(|(kind, _)| *kind == interaction.kind) {
+                Some((_, expected)) if expected.1 != arity => {
+                    return Err(InteractionArityError {
+                        kind: interaction.kind,
+                        expected: expected.clone(),
+                        found: (chip.name(), arity),
+                    });
+                }
+                Some(_) => {}
+                None => arities.push((interaction.kind, (chip.name(), arity))),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_air::{Air, BaseAir};
+    use p3_baby_bear::BabyBear;
+    use p3_field::AbstractField;
+    use p3_matrix::dense::RowMajorMatrix;
+    use p3_matrix::MatrixRowSlices;
+
+    use super::*;
+    use crate::air::{BaseAirBuilder, SP1AirBuilder};
+    use crate::runtime::ExecutionRecord;
+    use crate::stark::{MachineError, RiscvAir, RiscvStark};
+    use crate::utils::BabyBearBlake3;
+
+    /// Sends its columns as a single `Field` interaction.
+    struct FieldSender {
+        name: &'static str,
+        arity: usize,
+    }
+
+    impl<F> BaseAir<F> for FieldSender {
+        fn width(&self) -> usize {
+            self.arity
+        }
+    }
+
+    impl<F: Field> MachineAir<F> for FieldSender {
+        fn name(&self) -> String {
+            self.name.to_string()
+        }
+
+        fn num_real_rows(&self, _: &ExecutionRecord) -> usize {
+            0
+        }
+
+        fn generate_trace(
+            &self,
+            _: &ExecutionRecord,
+            _: &mut ExecutionRecord,
+        ) -> RowMajorMatrix<F> {
+            unimplemented!()
+        }
+    }
+
+    impl<AB: SP1AirBuilder> Air<AB> for FieldSender {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let local = main.row_slice(0).to_vec();
+            builder.send_values(InteractionKind::Field, local, AB::Expr::one());
+        }
+    }
+
+    #[test]
+    fn test_interaction_arities() {
+        let chips = RiscvAir::<BabyBear>::get_all()
+            .into_iter()
+            .map(Chip::new)
+            .collect::<Vec<_>>();
+        check_interaction_arities(&chips).unwrap();
+
+        let airs = vec![
+            FieldSender {
+                name: "Three",
+                arity: 3,
+            },
+            FieldSender {
+                name: "Two",
+                arity: 2,
+            },
+        ];
+        let Err(MachineError::InteractionArity(err)) =
+            RiscvStark::try_from_airs(BabyBearBlake3::new(), airs)
+        else {
+            panic!("the machine accepted interactions of different arities");
+        };
+        assert_eq!(err.kind, InteractionKind::Field);
+        assert_eq!(err.expected, ("Three".to_string(), 3));
+        assert_eq!(err.found, ("Two".to_string(), 2));
+    }
+}
@@ -0,0 +1,171 @@
+use std::borrow::Borrow;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use p3_maybe_rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
+
+use super::{
+    observe_context, ProgramVerificationError, Proof, RiscvStark, StarkGenericConfig, VerifyingKey,
+};
+use crate::air::PublicValues;
+
+/// A verifier for many proofs of the same program, which shares the program-dependent work across
+/// them.
+///
+/// The chips of the machine, the preprocessed commitments and quotient degrees of the verifying
+/// key and the transcript up to the application context are set up once, and each proof starts
+/// from a clone of them.
+pub struct BatchVerifier<SC: StarkGenericConfig> {
+    machine: RiscvStark<SC>,
+    vk: VerifyingKey<SC>,
+    context: Vec<u8>,
+    challenger: SC::Challenger,
+    fail_fast: bool,
+}
+
+impl<SC: StarkGenericConfig> BatchVerifier<SC>
+where
+    SC::Challenger: Clone,
+{
+    /// Creates a verifier for proofs without a context, starting each transcript from `challenger`.
+    pub fn new(machine: RiscvStark<SC>, vk: VerifyingKey<SC>, challenger: SC::Challenger) -> Self {
+        Self::with_context(machine, vk, &[], challenger)
+    }
+
+    /// Creates a verifier for proofs bound to the given application context.
+    pub fn with_context(
+        machine: RiscvStark<SC>,
+        vk: VerifyingKey<SC>,
+        context: &[u8],
+        mut challenger: SC::Challenger,
+    ) -> Self {
+        observe_context::<SC>(&mut challenger, context);
+        Self {
+            machine,
+            vk,
+            context: context.to_vec(),
+            challenger,
+            fail_fast: false,
+        }
+    }
+
+    /// Stops verifying the batch at the first invalid proof. The proofs which are not verified
+    /// then fail with `ProgramVerificationError::NotVerified`.
+    pub fn fail_fast(mut self, fail_fast: bool) -> Self {
+        self.fail_fast = fail_fast;
+        self
+    }
+
+    /// Verify a proof, returning the public values of its last shard.
+    pub fn verify(&self, proof: &Proof<SC>) -> Result<PublicValues<u32>, ProgramVerificationError> {
+        if proof.context != self.context {
+            return Err(ProgramVerificationError::ContextMismatch);
+        }
+        let mut challenger = self.challenger.clone();
+        self.machine
+            .verify_observed(&self.vk, proof, &mut challenger)?;
+        proof
+            .shard_proofs
+            .last()
+            .map(|shard_proof| shard_proof.public_values)
+            .ok_or(ProgramVerificationError::MissingFinalShard)
+    }
+
+    /// Verify each proof of the batch, in parallel on the rayon pool if `parallel` is set, and
+    /// return the result of each in the order of the proofs.
+    pub fn verify_all<P>(
+        &self,
+        proofs: &[P],
+        parallel: bool,
+    ) -> Vec<Result<PublicValues<u32>, ProgramVerificationError>>
+    where
+        P: Borrow<Proof<SC>> + Sync,
+        Self: Sync,
+    {
+        let failed = AtomicBool::new(false);
+        let verify = |proof: &P| {
+            if self.fail_fast && failed.load(Ordering::Relaxed) {
+                return Err(ProgramVerificationError::NotVerified);
+            }
+            let result = self.verify(proof.borrow());
+            if result.is_err() {
+                failed.store(true, Ordering::Relaxed);
+            }
+            result
+        };
+        if parallel {
+            proofs.par_iter().map(verify).collect()
+        } else {
+            proofs.iter().map(verify).collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BatchVerifier;
+    use crate::runtime::{tests::simple_program, Runtime};
+    use crate::stark::{LocalProver, ProgramVerificationError, Proof, RiscvStark};
+    use crate::utils::{BabyBearBlake3, StarkUtils};
+
+    fn clone_proof(proof: &Proof<BabyBearBlake3>) -> Proof<BabyBearBlake3> {
+        bincode::deserialize(&bincode::serialize(proof).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_batch_verify() {
+        let mut runtime = Runtime::new(simple_program().with_halt());
+        runtime.run();
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let (pk, vk) = machine.setup(runtime.program.as_ref());
+        let mut challenger = machine.config().challenger();
+        let proof = machine
+            .prove::<LocalProver<_>>(&pk, runtime.record, &mut challenger)
+            .unwrap();
+        let public_values = proof.shard_proofs.last().unwrap().public_values;
+
+        let mut forged_context = clone_proof(&proof);
+        forged_context.context = b"another application".to_vec();
+        let mut wrong_start_pc = clone_proof(&proof);
+        wrong_start_pc.shard_proofs[0].public_values.start_pc += 4;
+        let proofs = vec![
+            clone_proof(&proof),
+            forged_context,
+            clone_proof(&proof),
+            wrong_start_pc,
+        ];
+
+        let challenger = machine.config().challenger();
+        let batch = BatchVerifier::new(machine, vk, challenger);
+        for parallel in [false, true] {
+            let results = batch.verify_all(&proofs, parallel);
+            assert_eq!(results.len(), proofs.len());
+            assert_eq!(results[0].as_ref().unwrap(), &public_values);
+            assert!(matches!(
+                results[1],
+                Err(ProgramVerificationError::ContextMismatch)
+            ));
+            assert_eq!(results[2].as_ref().unwrap(), &public_values);
+            assert!(matches!(
+                results[3],
+                Err(ProgramVerificationError::PublicValuesMismatch(0))
+            ));
+        }
+
+        // Serially, every proof after the first invalid one is skipped.
+        let batch = batch.fail_fast(true);
+        let results = batch.verify_all(&proofs, false);
+        assert_eq!(results[0].as_ref().unwrap(), &public_values);
+        assert!(matches!(
+            results[1],
+            Err(ProgramVerificationError::ContextMismatch)
+        ));
+        assert!(matches!(
+            results[2],
+            Err(ProgramVerificationError::NotVerified)
+        ));
+        assert!(matches!(
+            results[3],
+            Err(ProgramVerificationError::NotVerified)
+        ));
+    }
+}
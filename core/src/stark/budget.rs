@@ -0,0 +1,170 @@
+use core::fmt::{Display, Formatter};
+
+use p3_field::AbstractExtensionField;
+
+use super::{RiscvStark, StarkAir, StarkGenericConfig};
+use crate::air::MachineAir;
+use crate::runtime::ExecutionRecord;
+
+/// The default bound on the number of trace cells of a shard.
+///
+/// A default shard uses well below this, while a misconfigured shard size which would exhaust the
+/// memory of the prover is refused before any trace is generated.
+pub const DEFAULT_MAX_CELLS: usize = 1 << 33;
+
+/// The number of trace cells a chip needs to prove a shard, in base field elements.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChipCells {
+    pub name: String,
+    pub rows: usize,
+    pub main: usize,
+    pub permutation: usize,
+    pub quotient: usize,
+}
+
+impl ChipCells {
+    pub fn total(&self) -> usize {
+        self.main + self.permutation + self.quotient
+    }
+}
+
+/// A shard whose traces need more cells than the budget of the prover.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellBudgetExceededError {
+    /// The index of the shard.
+    pub shard: u32,
+    pub total: usize,
+    pub budget: usize,
+    /// The cells of each chip of the shard, largest first.
+    pub chips: Vec<ChipCells>,
+    /// The largest power of two shard size which would bring the shard under the budget,
+    /// assuming its cells scale with the shard size.
+    pub suggested_shard_size: usize,
+}
+
+impl Display for CellBudgetExceededError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "shard {} needs {} trace cells, over the budget of {}; try a shard size of at most {}",
+            self.shard, self.total, self.budget, self.suggested_shard_size
+        )?;
+        for chip in self.chips.iter() {
+            write!(
+                f,
+                "\n  {}: {} rows, {} cells ({} main, {} permutation, {} quotient)",
+                chip.name,
+                chip.rows,
+                chip.total(),
+                chip.main,
+                chip.permutation,
+                chip.quotient
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for CellBudgetExceededError {}
+
+impl<SC: StarkGenericConfig, A: StarkAir<SC>> RiscvStark<SC, A> {
+    /// Estimates the cells of the main, permutation and quotient traces of each chip included in
+    /// the shard, from its events and without generating any trace.
+    pub fn shard_cells(&self, shard: &ExecutionRecord) -> Vec<ChipCells> {
+        let ext_degree = <SC::Challenge as AbstractExtensionField<SC::Val>>::D;
+        self.chips()
+            .iter()
+            .filter(|chip| chip.included(shard))
+            .map(|chip| {
+                let shape = chip.trace_shape(shard);
+                let rows = shape.padded_rows;
+                ChipCells {
+                    name: chip.name(),
+                    rows,
+                    main: shape.cells(),
+                    permutation: rows * (chip.num_interactions() + 1) * ext_degree,
+                    quotient: rows * (1 << chip.log_quotient_degree()) * ext_degree,
+                }
+            })
+            .collect()
+    }
+
+    /// Checks that every shard fits in `max_cells` trace cells, before any trace is generated.
+    pub fn check_cell_budget(
+        &self,
+        shards: &[ExecutionRecord],
+        max_cells: usize,
+        shard_size: usize,
+    ) -> Result<(), CellBudgetExceededError> {
+        for shard in shards.iter() {
+            let mut chips = self.shard_cells(shard);
+            let total = chips.iter().map(ChipCells::total).sum::<usize>();
+            if total <= max_cells {
+                continue;
+            }
+            chips.sort_by_key(|chip| std::cmp::Reverse(chip.total()));
+            let scaled = (shard_size as u128 * max_cells as u128 / total as u128) as usize;
+            let suggested_shard_size = match scaled {
+                0 => 1,
+                scaled => 1 << scaled.ilog2(),
+            };
+            return Err(CellBudgetExceededError {
+                shard: shard.index,
+                total,
+                budget: max_cells,
+                chips,
+                suggested_shard_size,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_air::BaseAir;
+
+    use crate::air::MachineAir;
+    use crate::runtime::{tests::simple_program, Runtime};
+    use crate::stark::RiscvStark;
+    use crate::utils::BabyBearBlake3;
+
+    #[test]
+    fn test_cell_budget() {
+        let mut runtime = Runtime::new(simple_program());
+        runtime.run();
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+
+        // A synthetic shard with far more CPU rows than the shard size, as a misconfigured
+        // sharding would produce.
+        let mut shard = runtime.record;
+        shard.index = 1;
+        shard.cpu_events = vec![shard.cpu_events[0]; 1 << 20];
+        let shard_size = 1 << 10;
+
+        let cpu = machine
+            .chips()
+            .iter()
+            .find(|chip| chip.name() == "CPU")
+            .unwrap();
+        let cells = machine.shard_cells(&shard);
+        let cpu_cells = cells.iter().find(|chip| chip.name == "CPU").unwrap();
+        assert_eq!(cpu_cells.rows, 1 << 20);
+        assert_eq!(cpu_cells.main, (1 << 20) * cpu.width());
+
+        let total = cells.iter().map(|chip| chip.total()).sum::<usize>();
+        let shards = [shard];
+        machine
+            .check_cell_budget(&shards, total, shard_size)
+            .unwrap();
+
+        let err = machine
+            .check_cell_budget(&shards, total / 100, shard_size)
+            .unwrap_err();
+        assert_eq!(err.shard, 1);
+        assert_eq!(err.total, total);
+        assert_eq!(err.chips[0].name, "CPU");
+        assert!(err.suggested_shard_size < shard_size / 64);
+        assert!(err.to_string().contains("CPU: 1048576 rows"));
+    }
+}
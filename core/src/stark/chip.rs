@@ -2,18 +2,23 @@ use std::hash::Hash;
 
 use p3_air::{Air, BaseAir, PairBuilder};
 use p3_field::{ExtensionField, Field, PrimeField, PrimeField32};
-use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::{dense::RowMajorMatrix, Matrix};
 use p3_util::log2_ceil_usize;
 
 use crate::{
-    air::{MachineAir, MultiTableAirBuilder, SP1AirBuilder},
+    air::{
+        EventKind, EventValidationError, MachineAir, MultiTableAirBuilder, SP1AirBuilder,
+        TraceShape,
+    },
     lookup::{Interaction, InteractionBuilder},
     runtime::{ExecutionRecord, Program},
+    utils::padded_nb_rows,
 };
 
 use super::{
     eval_permutation_constraints, generate_permutation_trace, DebugConstraintBuilder,
-    ProverConstraintFolder, RiscvAir, StarkGenericConfig, VerifierConstraintFolder,
+    ProverConstraintFolder, StarkGenericConfig, SymbolicConstraintBuilder,
+    VerifierConstraintFolder,
 };
 
 /// An Air that encodes lookups based on interactions.
@@ -24,6 +29,8 @@ pub struct Chip<F: Field, A> {
     sends: Vec<Interaction<F>>,
     /// The interactions that the chip receives.
     receives: Vec<Interaction<F>>,
+    /// The maximum degree of the constraints of the chip, permutation constraints included.
+    max_constraint_degree: usize,
     /// The relative log degree of the quotient polynomial, i.e. `log2(max_constraint_degree - 1)`.
     log_quotient_degree: usize,
 }
@@ -39,16 +46,48 @@ impl<F: Field, A> Chip<F, A> {
         &self.receives
     }
 
+    /// The maximum degree of the constraints of the chip, permutation constraints included.
+    pub const fn max_constraint_degree(&self) -> usize {
+        self.max_constraint_degree
+    }
+
     /// The relative log degree of the quotient polynomial, i.e. `log2(max_constraint_degree - 1)`.
     pub const fn log_quotient_degree(&self) -> usize {
         self.log_quotient_degree
     }
 }
 
-impl<F: PrimeField32> Chip<F, RiscvAir<F>> {
-    /// Returns whether the given chip is included in the execution record of the shard.
-    pub fn included(&self, shard: &ExecutionRecord) -> bool {
-        self.air.included(shard)
+impl<F: PrimeField32, A: MachineAir<F>> Chip<F, A> {
+    /// Returns the number of rows of the trace of the chip for the shard, without generating it.
+    ///
+    /// Chips which pad their traces differently may use fewer rows, so this is an upper estimate.
+    pub fn num_rows(&self, shard: &ExecutionRecord) -> usize {
+        padded_nb_rows(self.num_real_rows(shard))
+    }
+
+    /// Returns the expected shape of the trace of the chip for the shard, without generating it.
+    pub fn trace_shape(&self, shard: &ExecutionRecord) -> TraceShape {
+        TraceShape {
+            real_rows: self.num_real_rows(shard),
+            padded_rows: self.num_rows(shard),
+            width: self.width(),
+        }
+    }
+
+    /// Generates the trace of the chip along with its shape.
+    pub fn generate_trace_with_shape(
+        &self,
+        input: &ExecutionRecord,
+        output: &mut ExecutionRecord,
+    ) -> (RowMajorMatrix<F>, TraceShape) {
+        let mut trace = RowMajorMatrix::new(Vec::new(), self.width());
+        self.generate_trace_into(input, output, &mut trace);
+        let shape = TraceShape {
+            real_rows: self.num_real_rows(input),
+            padded_rows: trace.height(),
+            width: trace.width(),
+        };
+        (trace, shape)
     }
 }
 
@@ -60,6 +99,7 @@ impl<F: PrimeField32> Chip<F, RiscvAir<F>> {
 pub trait StarkAir<SC: StarkGenericConfig>:
     MachineAir<SC::Val>
     + Air<InteractionBuilder<SC::Val>>
+    + Air<SymbolicConstraintBuilder<SC::Val>>
     + for<'a> Air<ProverConstraintFolder<'a, SC>>
     + for<'a> Air<VerifierConstraintFolder<'a, SC>>
     + for<'a> Air<DebugConstraintBuilder<'a, SC::Val, SC::Challenge>>
@@ -69,6 +109,7 @@ pub trait StarkAir<SC: StarkGenericConfig>:
 impl<SC: StarkGenericConfig, T> StarkAir<SC> for T where
     T: MachineAir<SC::Val>
         + Air<InteractionBuilder<SC::Val>>
+        + Air<SymbolicConstraintBuilder<SC::Val>>
         + for<'a> Air<ProverConstraintFolder<'a, SC>>
         + for<'a> Air<VerifierConstraintFolder<'a, SC>>
         + for<'a> Air<DebugConstraintBuilder<'a, SC::Val, SC::Challenge>>
@@ -82,20 +123,30 @@ where
     /// Records the interactions and constraint degree from the air and crates a new chip.
     pub fn new(air: A) -> Self
     where
-        A: Air<InteractionBuilder<F>>,
+        A: MachineAir<F> + Air<InteractionBuilder<F>> + Air<SymbolicConstraintBuilder<F>>,
     {
-        let mut builder = InteractionBuilder::new(air.width());
+        let mut builder = InteractionBuilder::new(air.preprocessed_width(), air.width());
         air.eval(&mut builder);
         let (sends, receives) = builder.interactions();
 
-        // TODO: count constraints from the air.
-        let max_constraint_degree = 3;
+        // Evaluate the constraints symbolically, with the permutation constraints of the
+        // interactions, to find the degree of the quotient.
+        let mut builder = SymbolicConstraintBuilder::new(
+            air.preprocessed_width(),
+            air.width(),
+            sends.len() + receives.len() + 1,
+        );
+        air.eval(&mut builder);
+        eval_permutation_constraints(&sends, &receives, &mut builder);
+        // The quotient has at least the degree of the trace.
+        let max_constraint_degree = builder.max_constraint_degree().max(2);
         let log_quotient_degree = log2_ceil_usize(max_constraint_degree - 1);
 
         Self {
             air,
             sends,
             receives,
+            max_constraint_degree,
             log_quotient_degree,
         }
     }
@@ -106,7 +157,7 @@ where
 
     pub fn generate_permutation_trace<EF: ExtensionField<F>>(
         &self,
-        preprocessed: &Option<RowMajorMatrix<F>>,
+        preprocessed: Option<&RowMajorMatrix<F>>,
         main: &RowMajorMatrix<F>,
         random_elements: &[EF],
     ) -> RowMajorMatrix<EF>
@@ -161,9 +212,42 @@ where
         self.air.generate_trace(input, output)
     }
 
+    fn generate_trace_into(
+        &self,
+        input: &ExecutionRecord,
+        output: &mut ExecutionRecord,
+        trace: &mut RowMajorMatrix<F>,
+    ) {
+        self.air.generate_trace_into(input, output, trace)
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        self.air.num_real_rows(shard)
+    }
+
+    fn included(&self, shard: &ExecutionRecord) -> bool {
+        self.air.included(shard)
+    }
+
+    fn produces(&self) -> &[EventKind] {
+        self.air.produces()
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        self.air.consumes()
+    }
+
     fn generate_dependencies(&self, input: &ExecutionRecord, output: &mut ExecutionRecord) {
         self.air.generate_dependencies(input, output)
     }
+
+    fn validate(&self, input: &ExecutionRecord) -> Result<(), EventValidationError> {
+        self.air.validate(input)
+    }
+
+    fn describe_row(&self, row: &[F]) -> Option<String> {
+        self.air.describe_row(row)
+    }
 }
 
 // Implement AIR directly on Chip, evaluating both execution and permutation constraints.
@@ -202,3 +286,123 @@ where
         self.air.hash(state);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use p3_air::AirBuilder;
+    use p3_baby_bear::BabyBear;
+    use p3_matrix::MatrixRowSlices;
+
+    use super::*;
+    use crate::air::PublicValuesBuilder;
+    use crate::alu::AddChip;
+    use crate::syscall::precompiles::sha256::ShaExtendChip;
+
+    /// Asserts that the product of its columns is zero, with no interactions.
+    struct ProductAir {
+        width: usize,
+    }
+
+    impl<F> BaseAir<F> for ProductAir {
+        fn width(&self) -> usize {
+            self.width
+        }
+    }
+
+    impl<F: Field> MachineAir<F> for ProductAir {
+        fn name(&self) -> String {
+            "Product".to_string()
+        }
+
+        fn num_real_rows(&self, _: &ExecutionRecord) -> usize {
+            0
+        }
+
+        fn generate_trace(
+            &self,
+            _: &ExecutionRecord,
+            _: &mut ExecutionRecord,
+        ) -> RowMajorMatrix<F> {
+            unimplemented!()
+        }
+    }
+
+    impl<AB: SP1AirBuilder> Air<AB> for ProductAir {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let product = main
+                .row_slice(0)
+                .iter()
+                .map(|x| (*x).into())
+                .product::<AB::Expr>();
+            builder.assert_zero(product);
+        }
+    }
+
+    /// Asserts that the product of its three columns is the first public value, if the builder has
+    /// the public values of a shard.
+    struct PublicProductAir;
+
+    impl<F> BaseAir<F> for PublicProductAir {
+        fn width(&self) -> usize {
+            3
+        }
+    }
+
+    impl<F: Field> MachineAir<F> for PublicProductAir {
+        fn name(&self) -> String {
+            "PublicProduct".to_string()
+        }
+
+        fn num_real_rows(&self, _: &ExecutionRecord) -> usize {
+            0
+        }
+
+        fn generate_trace(
+            &self,
+            _: &ExecutionRecord,
+            _: &mut ExecutionRecord,
+        ) -> RowMajorMatrix<F> {
+            unimplemented!()
+        }
+    }
+
+    impl<AB: SP1AirBuilder> Air<AB> for PublicProductAir {
+        fn eval(&self, builder: &mut AB) {
+            let public_value: AB::Expr = builder.public_values()[0].into();
+            let main = builder.main();
+            let local = main.row_slice(0);
+            let product = local[0] * local[1] * local[2];
+            builder.assert_eq(product, public_value);
+        }
+    }
+
+    #[test]
+    fn test_max_constraint_degree() {
+        // The addition gadget asserts `is_real * overflow * (overflow - 256) = 0`, and the
+        // permutation constraint of the first row `is_first_row * (phi - perm * is_real)` has
+        // degree 3 as well.
+        let add = Chip::<BabyBear, _>::new(AddChip::default());
+        assert_eq!(add.max_constraint_degree(), 3);
+        assert_eq!(add.log_quotient_degree(), 1);
+
+        // The memory accesses assert `is_real * (time - use_clk * clk - ...) = 0`, and the flags
+        // `cycle_16_end * cycle_48[2] * (next.i - 16) = 0` on transitions.
+        let sha_extend = Chip::<BabyBear, _>::new(ShaExtendChip::new());
+        assert_eq!(sha_extend.max_constraint_degree(), 3);
+        assert_eq!(sha_extend.log_quotient_degree(), 1);
+
+        let product = Chip::<BabyBear, _>::new(ProductAir { width: 5 });
+        assert_eq!(product.max_constraint_degree(), 5);
+        assert_eq!(product.log_quotient_degree(), 2);
+
+        // A linear AIR still has a quotient the size of the trace.
+        let linear = Chip::<BabyBear, _>::new(ProductAir { width: 1 });
+        assert_eq!(linear.max_constraint_degree(), 2);
+        assert_eq!(linear.log_quotient_degree(), 0);
+
+        // Constraints on the public values count as well.
+        let public_product = Chip::<BabyBear, _>::new(PublicProductAir);
+        assert_eq!(public_product.max_constraint_degree(), 3);
+    }
+}
@@ -26,4 +26,11 @@ pub trait StarkGenericConfig {
 
     /// Returns the PCS used to commit to trace polynomials.
     fn pcs(&self) -> &Self::Pcs;
+
+    /// The identifier of the parameters of the hasher, for configs whose hasher has selectable
+    /// parameters. Verifying keys and proofs record it, and a proof only verifies against a key
+    /// with the same one.
+    fn hasher_params_id(&self) -> u8 {
+        0
+    }
 }
@@ -0,0 +1,315 @@
+//! A corpus of systematically corrupted proofs, each of which the verifier must reject with a
+//! specific error.
+//!
+//! The corpus is derived from a valid sharded proof of the fibonacci program by
+//! `make_invalid_fixtures`, which is deterministic, so the fixtures can be regenerated byte for
+//! byte. Running the tests with `REGENERATE_FIXTURES=1` writes them to `INVALID_FIXTURES_DIR`, and
+//! the conformance test checks any fixture found there against the generated corpus.
+
+use std::path::Path;
+
+use p3_baby_bear::BabyBear;
+use p3_field::AbstractField;
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::{
+    LocalProver, ProgramVerificationError, Proof, Prover, RiscvStark, VerificationError,
+    VerifyingKey,
+};
+use crate::runtime::{tests::fibonacci_program, Runtime, ShardingConfig};
+use crate::utils::{BabyBearBlake3, StarkUtils};
+
+/// The directory the corrupted proofs are written to, relative to the crate root.
+const INVALID_FIXTURES_DIR: &str = "fixtures/invalid_proofs";
+
+/// A way of corrupting a valid proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Corruption {
+    /// The proof claims another application context.
+    ForgedContext,
+    /// A bit of the main commitment of a shard is flipped.
+    FlippedMainCommitment,
+    /// A bit of the quotient commitment of a shard is flipped.
+    FlippedQuotientCommitment,
+    /// The encoding ends halfway through the FRI proof of the last shard.
+    TruncatedFriProof,
+    /// The first shard claims to start at another program counter.
+    WrongStartPc,
+    /// The second shard claims to resume at another program counter.
+    WrongResumePc,
+    /// The opened values of two chips of a shard are swapped.
+    SwappedChips,
+    /// The cumulative sum of a chip is changed.
+    ForgedCumulativeSum,
+    /// A digest element is changed.
+    ForgedDigest,
+    /// Two shards claim the same index.
+    DuplicateShard,
+    /// The first two shards are swapped.
+    ReorderedShards,
+    /// The last shard does not finalize memory.
+    MissingFinalShard,
+    /// The first shard claims to finalize memory too.
+    EarlyFinalShard,
+    /// The proof claims another preprocessed trace of the program chip.
+    ForgedPreprocessedDigest,
+}
+
+impl Corruption {
+    const ALL: [Corruption; 14] = [
+        Corruption::ForgedContext,
+        Corruption::FlippedMainCommitment,
+        Corruption::FlippedQuotientCommitment,
+        Corruption::TruncatedFriProof,
+        Corruption::WrongStartPc,
+        Corruption::WrongResumePc,
+        Corruption::SwappedChips,
+        Corruption::ForgedCumulativeSum,
+        Corruption::ForgedDigest,
+        Corruption::DuplicateShard,
+        Corruption::ReorderedShards,
+        Corruption::MissingFinalShard,
+        Corruption::EarlyFinalShard,
+        Corruption::ForgedPreprocessedDigest,
+    ];
+
+    /// The file name of the fixture.
+    fn file_name(&self) -> String {
+        format!("{:?}.bin", self)
+    }
+
+    /// Whether the corrupted proof no longer decodes, rather than failing verification.
+    fn is_malformed(&self) -> bool {
+        matches!(self, Corruption::TruncatedFriProof)
+    }
+
+    /// Whether `error` is the error the verifier must reject the corrupted proof with.
+    fn is_expected(&self, error: &ProgramVerificationError) -> bool {
+        use ProgramVerificationError::*;
+        match self {
+            Corruption::ForgedContext => matches!(error, ContextMismatch),
+            Corruption::FlippedMainCommitment | Corruption::ForgedDigest => {
+                matches!(error, DigestMismatch)
+            }
+            Corruption::FlippedQuotientCommitment => matches!(
+                error,
+                InvalidSegmentProof(VerificationError::InvalidopeningArgument)
+            ),
+            Corruption::TruncatedFriProof => false,
+            Corruption::WrongStartPc => matches!(error, PublicValuesMismatch(0)),
+            Corruption::WrongResumePc => matches!(error, ShardBoundaryMismatch(1)),
+            Corruption::SwappedChips => matches!(
+                error,
+                InvalidSegmentProof(VerificationError::InvalidProofShape)
+            ),
+            Corruption::ForgedCumulativeSum => matches!(
+                error,
+                InvalidSegmentProof(VerificationError::OodEvaluationMismatch(_))
+            ),
+            Corruption::DuplicateShard => matches!(error, DuplicateShard(0)),
+            Corruption::ReorderedShards => matches!(
+                error,
+                InvalidShardIndex {
+                    expected: 0,
+                    found: 1
+                }
+            ),
+            Corruption::MissingFinalShard => matches!(error, MissingFinalShard),
+            Corruption::EarlyFinalShard => matches!(error, UnexpectedFinalShard(0)),
+            Corruption::ForgedPreprocessedDigest => matches!(error, PreprocessedMismatch(_)),
+        }
+    }
+
+    /// Returns the encoding of `proof` after corrupting it.
+    fn apply(&self, proof: &Proof<BabyBearBlake3>) -> Vec<u8> {
+        let mut proof = clone_proof(proof);
+        match self {
+            Corruption::ForgedContext => proof.context = b"forged".to_vec(),
+            Corruption::FlippedMainCommitment => {
+                let commitment = &mut proof.shard_proofs[0].commitment;
+                commitment.main_commit = flip_bit(&commitment.main_commit);
+            }
+            Corruption::FlippedQuotientCommitment => {
+                let commitment = &mut proof.shard_proofs[0].commitment;
+                commitment.quotient_commit = flip_bit(&commitment.quotient_commit);
+            }
+            Corruption::TruncatedFriProof => {
+                // The opening proof of the last shard is followed by its chip ids and public
+                // values, and by the digest and context of the proof.
+                let bytes = encode(&proof);
+                let last = proof.shard_proofs.last().unwrap();
+                let end = bytes.len()
+                    - encode(&last.chip_ids).len()
+                    - encode(&last.public_values).len()
+                    - encode(&proof.digest).len()
+                    - encode(&proof.context).len();
+                let fri_len = encode(&last.opening_proof).len();
+                return bytes[..end - fri_len / 2].to_vec();
+            }
+            Corruption::WrongStartPc => proof.shard_proofs[0].public_values.start_pc += 4,
+            Corruption::WrongResumePc => proof.shard_proofs[1].public_values.start_pc += 4,
+            Corruption::SwappedChips => {
+                let chips = &mut proof.shard_proofs[0].opened_values.chips;
+                assert_ne!(chips[0].main.local.len(), chips[1].main.local.len());
+                chips.swap(0, 1);
+            }
+            Corruption::ForgedCumulativeSum => {
+                proof.shard_proofs[0].opened_values.chips[0].cumulative_sum += BabyBear::one();
+            }
+            Corruption::ForgedDigest => proof.digest[0] += BabyBear::one(),
+            Corruption::DuplicateShard => proof.shard_proofs[1].index = 0,
+            Corruption::ReorderedShards => proof.shard_proofs.swap(0, 1),
+            Corruption::MissingFinalShard => {
+                let last = proof.shard_proofs.last_mut().unwrap();
+                last.chip_ids.retain(|name| name != "MemoryFinalize");
+            }
+            Corruption::EarlyFinalShard => {
+                let first = &mut proof.shard_proofs[0];
+                first.chip_ids.push("MemoryFinalize".to_string());
+            }
+            Corruption::ForgedPreprocessedDigest => {
+                proof.preprocessed_digests.get_mut("Program").unwrap()[0] ^= 1;
+            }
+        }
+        encode(&proof)
+    }
+}
+
+/// The corruption whose fixture exercises each error of the verifier.
+///
+/// The match is exhaustive, so that a new error cannot be added without a fixture, or a reason
+/// why no corruption of a valid proof can trigger it.
+fn fixture_for(error: &ProgramVerificationError) -> Option<Corruption> {
+    use ProgramVerificationError::*;
+    match error {
+        InvalidSegmentProof(error) => match error {
+            VerificationError::InvalidopeningArgument => {
+                Some(Corruption::FlippedQuotientCommitment)
+            }
+            VerificationError::OodEvaluationMismatch(_) => Some(Corruption::ForgedCumulativeSum),
+            VerificationError::InvalidProofShape => Some(Corruption::SwappedChips),
+        },
+        // Not returned by the verifier.
+        InvalidGlobalProof(_) => None,
+        // The shard whose cumulative sum is forged fails its own constraints first.
+        NonZeroCumulativeSum => None,
+        DuplicateShard(_) => Some(Corruption::DuplicateShard),
+        InvalidShardIndex { .. } => Some(Corruption::ReorderedShards),
+        MissingFinalShard => Some(Corruption::MissingFinalShard),
+        UnexpectedFinalShard(_) => Some(Corruption::EarlyFinalShard),
+        DigestMismatch => Some(Corruption::ForgedDigest),
+        ContextMismatch => Some(Corruption::ForgedContext),
+        PublicValuesMismatch(_) => Some(Corruption::WrongStartPc),
+        ShardBoundaryMismatch(_) => Some(Corruption::WrongResumePc),
+        PreprocessedMismatch(_) => Some(Corruption::ForgedPreprocessedDigest),
+        // Only returned by the batch verifier, for the proofs it skips after a failure.
+        NotVerified => None,
+        // Only returned by `SP1Verifier`, which checks the input accompanying the proof.
+        InvalidStdin(_) => None,
+        // Only returned by `SP1Verifier`, which compares the values claimed by the proof.
+        ClaimedValuesMismatch => None,
+        // The verifying key, not the proof, comes from a machine of another shape.
+        SchemaMismatch => None,
+        // The config, not the proof, has other parameters of the hasher.
+        Version(_) => None,
+    }
+}
+
+fn encode<T: Serialize>(value: &T) -> Vec<u8> {
+    bincode::serialize(value).unwrap()
+}
+
+fn clone_proof(proof: &Proof<BabyBearBlake3>) -> Proof<BabyBearBlake3> {
+    bincode::deserialize(&encode(proof)).unwrap()
+}
+
+/// Flips the lowest bit of the last byte of the encoding of `value`.
+fn flip_bit<T: Serialize + DeserializeOwned>(value: &T) -> T {
+    let mut bytes = encode(value);
+    *bytes.last_mut().unwrap() ^= 1;
+    bincode::deserialize(&bytes).unwrap()
+}
+
+/// Proves the fibonacci program in two shards or more.
+fn prove_fibonacci() -> (
+    RiscvStark<BabyBearBlake3>,
+    VerifyingKey<BabyBearBlake3>,
+    Proof<BabyBearBlake3>,
+) {
+    let mut runtime = Runtime::new(fibonacci_program());
+    runtime.run();
+    let shard_size = (runtime.record.cpu_events.len() / 2).next_power_of_two();
+
+    // Leave room for the longest syscall, which takes 960 clock cycles, in shards of at most
+    // `shard_size` instructions.
+    let mut runtime = Runtime::new(fibonacci_program());
+    runtime.shard_size = 960 / 4 + shard_size as u32 / 2;
+    runtime.run();
+    let machine = RiscvStark::new(BabyBearBlake3::new());
+    let (pk, vk) = machine.setup(runtime.program.as_ref());
+    let config = ShardingConfig::with_shard_size(shard_size);
+    let shards = machine.shard(runtime.record, &config);
+    assert!(shards.len() >= 2);
+    let mut challenger = machine.config().challenger();
+    let proof = LocalProver::prove_shards(&machine, &pk, shards, &mut challenger).unwrap();
+    (machine, vk, proof)
+}
+
+/// Derives the encoded corrupted proofs from a valid proof.
+fn make_invalid_fixtures(proof: &Proof<BabyBearBlake3>) -> Vec<(Corruption, Vec<u8>)> {
+    Corruption::ALL
+        .iter()
+        .map(|corruption| (*corruption, corruption.apply(proof)))
+        .collect()
+}
+
+#[test]
+fn test_invalid_proof_conformance() {
+    let (machine, vk, proof) = prove_fibonacci();
+    let verify = |proof: &Proof<BabyBearBlake3>| {
+        let mut challenger = machine.config().challenger();
+        machine.verify(&vk, proof, &mut challenger)
+    };
+    verify(&proof).unwrap();
+
+    let fixtures = make_invalid_fixtures(&proof);
+    assert_eq!(fixtures, make_invalid_fixtures(&proof));
+
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(INVALID_FIXTURES_DIR);
+    if std::env::var("REGENERATE_FIXTURES").is_ok() {
+        std::fs::create_dir_all(&dir).unwrap();
+        for (corruption, bytes) in fixtures.iter() {
+            std::fs::write(dir.join(corruption.file_name()), bytes).unwrap();
+        }
+    }
+
+    for (corruption, bytes) in fixtures.iter() {
+        let path = dir.join(corruption.file_name());
+        if let Ok(checked_in) = std::fs::read(&path) {
+            assert!(
+                &checked_in == bytes,
+                "{} is stale, regenerate it with REGENERATE_FIXTURES=1",
+                path.display()
+            );
+        }
+
+        let decoded = bincode::deserialize::<Proof<BabyBearBlake3>>(bytes);
+        if corruption.is_malformed() {
+            assert!(decoded.is_err(), "{:?} decoded", corruption);
+            continue;
+        }
+        let error = verify(&decoded.unwrap())
+            .err()
+            .unwrap_or_else(|| panic!("{:?} verified", corruption));
+        assert!(
+            corruption.is_expected(&error),
+            "{:?} was rejected with {:?}",
+            corruption,
+            error
+        );
+        // Several corruptions may trigger the same error, which has one designated fixture.
+        let designated =
+            fixture_for(&error).unwrap_or_else(|| panic!("{:?} has no designated fixture", error));
+        assert!(designated.is_expected(&error));
+    }
+}
@@ -1,42 +1,255 @@
-use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::collections::BTreeMap;
 
 use p3_air::{
     Air, AirBuilder, ExtensionBuilder, PairBuilder, PermutationAirBuilder, TwoRowMatrixView,
 };
-use p3_field::{AbstractField, PrimeField32};
+use p3_field::{AbstractExtensionField, AbstractField, PrimeField32};
 use p3_field::{ExtensionField, Field};
 use p3_matrix::{dense::RowMajorMatrix, Matrix, MatrixRowSlices};
 
-use crate::air::{EmptyMessageBuilder, MachineAir, MultiTableAirBuilder};
+use crate::air::{
+    EmptyMessageBuilder, MachineAir, MultiTableAirBuilder, PublicValuesBuilder, NUM_PUBLIC_VALUES,
+};
+use crate::lookup::InteractionKind;
+use crate::utils::env;
+
+use super::{Chip, StarkAir, StarkGenericConfig};
+
+/// A constraint of a chip which does not evaluate to zero at a row of its trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstraintViolation {
+    /// The name of the chip.
+    pub chip_name: String,
+    /// The index of the row.
+    pub row: usize,
+    /// The index of the constraint, in the order the chip asserts its constraints.
+    pub constraint_index: usize,
+    /// Whether the row is not the last one, in which case the transition constraints apply.
+    pub is_transition: bool,
+    /// The value of the constraint, as the canonical coefficients of an extension element for the
+    /// constraints of the permutation trace.
+    pub value: Vec<u32>,
+}
+
+impl std::fmt::Display for ConstraintViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "constraint {} of chip {} evaluates to {:?} at row {}",
+            self.constraint_index, self.chip_name, self.value, self.row
+        )?;
+        if !self.is_transition {
+            write!(f, " (last row)")?;
+        }
+        Ok(())
+    }
+}
+
+/// A row of a chip's trace at which the constraints are not satisfied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstraintFailure {
+    /// The name of the chip.
+    pub chip: String,
+    /// The index of the failing row.
+    pub row: usize,
+    /// The values of the main trace at the failing row.
+    pub local: Vec<u32>,
+    /// The values of the main trace at the row after the failing row.
+    pub next: Vec<u32>,
+    /// The first constraint not satisfied at the row.
+    pub message: String,
+}
+
+impl std::fmt::Display for ConstraintFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "failed at row {} of chip {}: {}",
+            self.row, self.chip, self.message
+        )?;
+        writeln!(f, "local: {:?}", self.local)?;
+        write!(f, "next:  {:?}", self.next)
+    }
+}
+
+/// Interaction kinds whose sends and receives do not cancel out over the shards of a proof.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InteractionImbalance {
+    /// The unbalanced kinds, in the order of their arguments.
+    pub kinds: Vec<InteractionKind>,
+}
+
+impl std::fmt::Display for InteractionImbalance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kinds = self
+            .kinds
+            .iter()
+            .map(|kind| kind.to_string())
+            .collect::<Vec<_>>();
+        write!(f, "unbalanced interactions of kind {}", kinds.join(", "))
+    }
+}
+
+impl std::error::Error for InteractionImbalance {}
+
+/// The cumulative sum of the permutation trace of a chip split by interaction kind, i.e. for each
+/// kind the sum over the rows of the multiplicities of its sends minus those of its receives, each
+/// weighted by the inverse of the fingerprint of the message held by the permutation trace.
+pub fn cumulative_sums_by_kind<F: Field, EF: ExtensionField<F>, A>(
+    chip: &Chip<F, A>,
+    preprocessed: Option<&RowMajorMatrix<F>>,
+    main: &RowMajorMatrix<F>,
+    perm: &RowMajorMatrix<EF>,
+) -> BTreeMap<InteractionKind, EF> {
+    let mut sums = BTreeMap::new();
+    let interactions = chip
+        .sends()
+        .iter()
+        .map(|interaction| (interaction, F::one()))
+        .chain(
+            chip.receives()
+                .iter()
+                .map(|interaction| (interaction, -F::one())),
+        );
+    for (j, (interaction, sign)) in interactions.enumerate() {
+        let sum = sums.entry(interaction.kind).or_insert_with(EF::zero);
+        for i in 0..main.height() {
+            let preprocessed_row = preprocessed.map_or(&[][..], |p| p.row_slice(i));
+            let mult = interaction
+                .multiplicity
+                .apply::<F, F>(preprocessed_row, main.row_slice(i));
+            *sum += perm.row_slice(i)[j] * (mult * sign);
+        }
+    }
+    sums
+}
 
-use super::{RiscvChip, StarkGenericConfig};
+/// Checks that the cumulative sum of each interaction kind, added over the chips of every shard of
+/// a proof, is zero.
+pub fn check_interaction_balance<EF: Field>(
+    sums: &BTreeMap<InteractionKind, EF>,
+) -> Result<(), InteractionImbalance> {
+    let kinds = sums
+        .iter()
+        .filter(|(_, sum)| !sum.is_zero())
+        .map(|(kind, _)| *kind)
+        .collect::<Vec<_>>();
+    if kinds.is_empty() {
+        Ok(())
+    } else {
+        Err(InteractionImbalance { kinds })
+    }
+}
 
-/// Checks that the constraints of the given AIR are satisfied, including the permutation trace.
+/// Evaluates the constraints of the given AIR, including those of the permutation trace, on every
+/// row, and returns the constraints which are not satisfied, at most `MAX_VIOLATIONS` of them.
 ///
 /// Note that this does not actually verify the proof.
 pub fn debug_constraints<SC: StarkGenericConfig>(
-    chip: &RiscvChip<SC>,
+    chip: &Chip<SC::Val, impl StarkAir<SC>>,
+    preprocessed: Option<&RowMajorMatrix<SC::Val>>,
+    main: &RowMajorMatrix<SC::Val>,
+    perm: &RowMajorMatrix<SC::Challenge>,
+    perm_challenges: &[SC::Challenge],
+    public_values: &[SC::Val],
+) -> Vec<ConstraintViolation>
+where
+    SC::Val: PrimeField32,
+{
+    find_violations::<SC>(
+        chip,
+        preprocessed,
+        main,
+        perm,
+        perm_challenges,
+        public_values,
+        env::max_violations(),
+    )
+}
+
+/// Checks that the constraints of the given AIR are satisfied, returning the first failing row.
+pub fn check_constraints<SC: StarkGenericConfig>(
+    chip: &Chip<SC::Val, impl StarkAir<SC>>,
     preprocessed: Option<&RowMajorMatrix<SC::Val>>,
     main: &RowMajorMatrix<SC::Val>,
     perm: &RowMajorMatrix<SC::Challenge>,
     perm_challenges: &[SC::Challenge],
-) where
+    public_values: &[SC::Val],
+) -> Result<(), ConstraintFailure>
+where
+    SC::Val: PrimeField32,
+{
+    let violations = find_violations::<SC>(
+        chip,
+        preprocessed,
+        main,
+        perm,
+        perm_challenges,
+        public_values,
+        1,
+    );
+    match violations.into_iter().next() {
+        None => Ok(()),
+        Some(violation) => {
+            let next = (violation.row + 1) % main.height();
+            Err(ConstraintFailure {
+                chip: violation.chip_name,
+                row: violation.row,
+                local: main
+                    .row_slice(violation.row)
+                    .iter()
+                    .map(|x| x.as_canonical_u32())
+                    .collect(),
+                next: main
+                    .row_slice(next)
+                    .iter()
+                    .map(|x| x.as_canonical_u32())
+                    .collect(),
+                message: format!(
+                    "constraint {} evaluates to {:?}",
+                    violation.constraint_index, violation.value
+                ),
+            })
+        }
+    }
+}
+
+/// Evaluates the constraints of the given AIR on every row, and returns the first
+/// `max_violations` constraints which are not satisfied, by row and then by constraint.
+///
+/// The public values are those of the shard of the trace, all `NUM_PUBLIC_VALUES` of them.
+pub fn find_violations<SC: StarkGenericConfig>(
+    chip: &Chip<SC::Val, impl StarkAir<SC>>,
+    preprocessed: Option<&RowMajorMatrix<SC::Val>>,
+    main: &RowMajorMatrix<SC::Val>,
+    perm: &RowMajorMatrix<SC::Challenge>,
+    perm_challenges: &[SC::Challenge],
+    public_values: &[SC::Val],
+    max_violations: usize,
+) -> Vec<ConstraintViolation>
+where
     SC::Val: PrimeField32,
 {
     assert_eq!(main.height(), perm.height());
+    assert_eq!(
+        public_values.len(),
+        NUM_PUBLIC_VALUES,
+        "the constraints of a shard need all its public values"
+    );
     let height = main.height();
+    let mut violations = Vec::new();
     if height == 0 {
-        return;
+        return violations;
     }
 
     let cumulative_sum = perm.row_slice(perm.height() - 1).last().copied().unwrap();
 
-    // Check that constraints are satisfied.
-    (0..height).for_each(|i| {
+    for i in 0..height {
+        if violations.len() >= max_violations {
+            break;
+        }
         let i_next = (i + 1) % height;
 
-        let main_local = main.row_slice(i);
-        let main_next = main.row_slice(i_next);
         let preprocessed_local = if preprocessed.is_some() {
             preprocessed.as_ref().unwrap().row_slice(i)
         } else {
@@ -47,8 +260,6 @@ pub fn debug_constraints<SC: StarkGenericConfig>(
         } else {
             &[]
         };
-        let perm_local = perm.row_slice(i);
-        let perm_next = perm.row_slice(i_next);
 
         let mut builder = DebugConstraintBuilder {
             preprocessed: TwoRowMatrixView {
@@ -56,18 +267,21 @@ pub fn debug_constraints<SC: StarkGenericConfig>(
                 next: preprocessed_next,
             },
             main: TwoRowMatrixView {
-                local: main_local,
-                next: main_next,
+                local: main.row_slice(i),
+                next: main.row_slice(i_next),
             },
             perm: TwoRowMatrixView {
-                local: perm_local,
-                next: perm_next,
+                local: perm.row_slice(i),
+                next: perm.row_slice(i_next),
             },
             perm_challenges,
+            public_values,
             cumulative_sum,
             is_first_row: SC::Val::zero(),
             is_last_row: SC::Val::zero(),
             is_transition: SC::Val::one(),
+            constraint_index: 0,
+            violations: Vec::new(),
         };
         if i == 0 {
             builder.is_first_row = SC::Val::one();
@@ -76,15 +290,23 @@ pub fn debug_constraints<SC: StarkGenericConfig>(
             builder.is_last_row = SC::Val::one();
             builder.is_transition = SC::Val::zero();
         }
-        let result = catch_unwind(AssertUnwindSafe(|| {
-            chip.eval(&mut builder);
-        }));
-        if result.is_err() {
-            println!("local: {:?}", main_local);
-            println!("next:  {:?}", main_next);
-            panic!("failed at row {} of chip {}", i, chip.name());
-        }
-    });
+        chip.eval(&mut builder);
+
+        violations.extend(
+            builder
+                .violations
+                .into_iter()
+                .map(|(constraint_index, value)| ConstraintViolation {
+                    chip_name: chip.name(),
+                    row: i,
+                    constraint_index,
+                    is_transition: i != height - 1,
+                    value: value.iter().map(|x| x.as_canonical_u32()).collect(),
+                }),
+        );
+    }
+    violations.truncate(max_violations);
+    violations
 }
 
 /// Checks that all the interactions between the chips has been satisfied.
@@ -98,16 +320,32 @@ pub fn debug_cumulative_sums<F: Field, EF: ExtensionField<F>>(perms: &[RowMajorM
     assert_eq!(sum, EF::zero());
 }
 
-/// A builder for debugging constraints.
+/// A builder for debugging constraints, which records the constraints not evaluating to zero
+/// instead of failing on them.
 pub struct DebugConstraintBuilder<'a, F: Field, EF: ExtensionField<F>> {
     pub(crate) preprocessed: TwoRowMatrixView<'a, F>,
     pub(crate) main: TwoRowMatrixView<'a, F>,
     pub(crate) perm: TwoRowMatrixView<'a, EF>,
     pub(crate) cumulative_sum: EF,
     pub(crate) perm_challenges: &'a [EF],
+    pub(crate) public_values: &'a [F],
     pub(crate) is_first_row: F,
     pub(crate) is_last_row: F,
     pub(crate) is_transition: F,
+    /// The number of constraints asserted so far.
+    pub(crate) constraint_index: usize,
+    /// The index and the value of each constraint asserted so far which is not zero.
+    pub(crate) violations: Vec<(usize, Vec<F>)>,
+}
+
+impl<'a, F: Field, EF: ExtensionField<F>> DebugConstraintBuilder<'a, F, EF> {
+    /// Counts an asserted constraint, recording it if its value is not zero.
+    fn record(&mut self, is_zero: bool, value: impl FnOnce() -> Vec<F>) {
+        if !is_zero {
+            self.violations.push((self.constraint_index, value()));
+        }
+        self.constraint_index += 1;
+    }
 }
 
 impl<'a, F, EF> ExtensionBuilder for DebugConstraintBuilder<'a, F, EF>
@@ -123,7 +361,8 @@ where
     where
         I: Into<Self::ExprEF>,
     {
-        assert_eq!(x.into(), EF::zero(), "constraints must evaluate to zero");
+        let x: EF = x.into();
+        self.record(x.is_zero(), || x.as_base_slice().to_vec());
     }
 }
 
@@ -185,10 +424,7 @@ where
 
     fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I) {
         let f: F = x.into();
-        if f != F::zero() {
-            let backtrace = std::backtrace::Backtrace::force_capture();
-            panic!("constraint failed: {}", backtrace);
-        }
+        self.record(f.is_zero(), || vec![f]);
     }
 }
 
@@ -208,3 +444,174 @@ impl<'a, F: Field, EF: ExtensionField<F>> EmptyMessageBuilder
     for DebugConstraintBuilder<'a, F, EF>
 {
 }
+
+impl<'a, F, EF> PublicValuesBuilder for DebugConstraintBuilder<'a, F, EF>
+where
+    F: Field,
+    EF: ExtensionField<F>,
+{
+    type PublicVar = Self::F;
+
+    const STANDALONE: bool = false;
+
+    fn public_values(&self) -> &[Self::PublicVar] {
+        self.public_values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::BorrowMut;
+    use std::collections::BTreeMap;
+
+    use p3_baby_bear::BabyBear;
+    use p3_field::extension::BinomialExtensionField;
+    use p3_field::AbstractField;
+    use p3_matrix::MatrixRowSlices;
+
+    use super::{check_constraints, check_interaction_balance, find_violations};
+    use crate::air::{MachineAir, PublicValues};
+    use crate::alu::{AddChip, AluEvent};
+    use crate::lookup::InteractionKind;
+    use crate::runtime::tests::fibonacci_program;
+    use crate::runtime::{ExecutionRecord, Opcode, Runtime, ShardingConfig};
+    use crate::stark::prover::add_sums;
+    use crate::stark::{Chip, LocalProver, RiscvAir, RiscvStark};
+    use crate::syscall::precompiles::sha256::{ShaExtendChip, ShaExtendCols, NUM_SHA_EXTEND_COLS};
+    use crate::utils::test_utils::RecordBuilder;
+    use crate::utils::BabyBearBlake3;
+
+    #[test]
+    fn test_check_constraints_reports_failure() {
+        type EF = BinomialExtensionField<BabyBear, 4>;
+
+        let mut shard = ExecutionRecord::default();
+        shard.add_events = vec![AluEvent::new(0, Opcode::ADD, 14, 8, 6)];
+        let chip = Chip::new(RiscvAir::<BabyBear>::Add(AddChip::default()));
+        let mut trace = chip.generate_trace(&shard, &mut ExecutionRecord::default());
+        let challenges = [EF::from_canonical_u32(3), EF::from_canonical_u32(7)];
+        let public_values = PublicValues::from_record(&shard).to_field_elements();
+
+        let perm = chip.generate_permutation_trace(None, &trace, &challenges);
+        assert!(check_constraints::<BabyBearBlake3>(
+            &chip,
+            None,
+            &trace,
+            &perm,
+            &challenges,
+            &public_values
+        )
+        .is_ok());
+
+        // Break the result of the addition.
+        trace.values[0] += BabyBear::one();
+        let perm = chip.generate_permutation_trace(None, &trace, &challenges);
+        let failure = check_constraints::<BabyBearBlake3>(
+            &chip,
+            None,
+            &trace,
+            &perm,
+            &challenges,
+            &public_values,
+        )
+        .unwrap_err();
+        assert_eq!(failure.chip, "Add");
+        assert_eq!(failure.row, 0);
+        assert!(failure.message.starts_with("constraint "));
+    }
+
+    #[test]
+    fn test_find_violations_reports_row() {
+        type EF = BinomialExtensionField<BabyBear, 4>;
+
+        let mut builder = RecordBuilder::new();
+        builder.sha_extend(100, [7; 64]);
+        let shard = builder.build();
+        let chip = Chip::new(RiscvAir::<BabyBear>::Sha256Extend(ShaExtendChip::new()));
+        let mut trace = chip.generate_trace(&shard, &mut ExecutionRecord::default());
+        let challenges = [EF::from_canonical_u32(3), EF::from_canonical_u32(7)];
+        let public_values = PublicValues::from_record(&shard).to_field_elements();
+
+        let perm = chip.generate_permutation_trace(None, &trace, &challenges);
+        let violations = find_violations::<BabyBearBlake3>(
+            &chip,
+            None,
+            &trace,
+            &perm,
+            &challenges,
+            &public_values,
+            16,
+        );
+        assert!(violations.is_empty());
+
+        // Break the sum computing `w[i]` in the middle of the event.
+        let row = 20;
+        let cols: &mut ShaExtendCols<BabyBear> =
+            trace.values[row * NUM_SHA_EXTEND_COLS..(row + 1) * NUM_SHA_EXTEND_COLS].borrow_mut();
+        cols.s2.value.0[0] += BabyBear::one();
+        let perm = chip.generate_permutation_trace(None, &trace, &challenges);
+        let violations = find_violations::<BabyBearBlake3>(
+            &chip,
+            None,
+            &trace,
+            &perm,
+            &challenges,
+            &public_values,
+            16,
+        );
+        assert!(!violations.is_empty());
+        assert!(violations.iter().all(|violation| violation.row == row));
+        assert_eq!(violations[0].chip_name, "ShaExtend");
+        assert!(violations[0].is_transition);
+        assert!(chip
+            .describe_row(trace.row_slice(row))
+            .unwrap()
+            .contains("s2"));
+
+        let violations = find_violations::<BabyBearBlake3>(
+            &chip,
+            None,
+            &trace,
+            &perm,
+            &challenges,
+            &public_values,
+            1,
+        );
+        assert_eq!(violations.len(), 1);
+    }
+
+    /// Dropping an ALU event leaves the ALU interactions unbalanced over the shards, and only them.
+    #[test]
+    fn test_interaction_balance_by_kind() {
+        let mut runtime = Runtime::new(fibonacci_program());
+        runtime.run();
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let sums = |record: ExecutionRecord| {
+            let mut sums = BTreeMap::new();
+            let shards = machine.shard(record, &ShardingConfig::default());
+            for (index, shard) in shards.iter().enumerate() {
+                let traces = machine.generate_shard_traces(shard, index);
+                let chips = machine.shard_chips(shard).collect::<Vec<_>>();
+                let shard_sums = LocalProver::<BabyBearBlake3>::self_check(
+                    &chips,
+                    &shard.program,
+                    &traces.traces,
+                    &traces.public_values.to_field_elements(),
+                )
+                .unwrap();
+                add_sums(&mut sums, shard_sums);
+            }
+            sums
+        };
+
+        let balanced = sums(runtime.record.clone());
+        assert!(balanced.contains_key(&InteractionKind::Alu));
+        assert_eq!(check_interaction_balance(&balanced), Ok(()));
+
+        let mut record = runtime.record;
+        record.add_events.pop();
+        let error = check_interaction_balance(&sums(record)).unwrap_err();
+        assert_eq!(error.kinds, vec![InteractionKind::Alu]);
+        assert_eq!(error.to_string(), "unbalanced interactions of kind Alu");
+    }
+}
@@ -1,5 +1,5 @@
 use super::{PackedChallenge, PackedVal, StarkGenericConfig};
-use crate::air::{EmptyMessageBuilder, MultiTableAirBuilder};
+use crate::air::{EmptyMessageBuilder, MultiTableAirBuilder, PublicValuesBuilder};
 use p3_air::{AirBuilder, ExtensionBuilder, PairBuilder, PermutationAirBuilder, TwoRowMatrixView};
 use p3_field::AbstractField;
 
@@ -9,6 +9,7 @@ pub struct ProverConstraintFolder<'a, SC: StarkGenericConfig> {
     pub main: TwoRowMatrixView<'a, PackedVal<SC>>,
     pub perm: TwoRowMatrixView<'a, PackedChallenge<SC>>,
     pub perm_challenges: &'a [SC::Challenge],
+    pub public_values: &'a [SC::Val],
     pub cumulative_sum: SC::Challenge,
     pub is_first_row: PackedVal<SC>,
     pub is_last_row: PackedVal<SC>,
@@ -95,12 +96,23 @@ impl<'a, SC: StarkGenericConfig> PairBuilder for ProverConstraintFolder<'a, SC>
 
 impl<'a, SC: StarkGenericConfig> EmptyMessageBuilder for ProverConstraintFolder<'a, SC> {}
 
+impl<'a, SC: StarkGenericConfig> PublicValuesBuilder for ProverConstraintFolder<'a, SC> {
+    type PublicVar = Self::F;
+
+    const STANDALONE: bool = false;
+
+    fn public_values(&self) -> &[Self::PublicVar] {
+        self.public_values
+    }
+}
+
 /// A folder for verifier constraints.
 pub struct VerifierConstraintFolder<'a, SC: StarkGenericConfig> {
     pub preprocessed: TwoRowMatrixView<'a, SC::Challenge>,
     pub main: TwoRowMatrixView<'a, SC::Challenge>,
     pub perm: TwoRowMatrixView<'a, SC::Challenge>,
     pub perm_challenges: &'a [SC::Challenge],
+    pub public_values: &'a [SC::Val],
     pub cumulative_sum: SC::Challenge,
     pub is_first_row: SC::Challenge,
     pub is_last_row: SC::Challenge,
@@ -182,3 +194,13 @@ impl<'a, SC: StarkGenericConfig> PairBuilder for VerifierConstraintFolder<'a, SC
 }
 
 impl<'a, SC: StarkGenericConfig> EmptyMessageBuilder for VerifierConstraintFolder<'a, SC> {}
+
+impl<'a, SC: StarkGenericConfig> PublicValuesBuilder for VerifierConstraintFolder<'a, SC> {
+    type PublicVar = Self::F;
+
+    const STANDALONE: bool = false;
+
+    fn public_values(&self) -> &[Self::PublicVar] {
+        self.public_values
+    }
+}
@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use super::ChipStats;
+
+/// Callbacks on the progress of proving, e.g. to report it to a scheduler.
+///
+/// The shards are proven in parallel, so the callbacks of different shards may interleave and run
+/// on any thread.
+pub trait ProverHooks: Send + Sync {
+    /// The shard with the given index, out of `num_shards`, starts being proven.
+    fn shard_start(&self, _index: usize, _num_shards: usize) {}
+
+    /// The shard with the given index, out of `num_shards`, is proven.
+    fn shard_done(&self, _index: usize, _num_shards: usize, _elapsed: Duration) {}
+
+    /// The quotient of a chip of a shard is computed, with the statistics of the chip in the shard.
+    fn chip_proven(&self, _stats: &ChipStats) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::runtime::{Program, Runtime};
+    use crate::utils::tests::FIBONACCI_ELF;
+    use crate::utils::{prove_core_with_hooks, BabyBearBlake3};
+
+    #[derive(Default)]
+    struct CountingHooks {
+        started: AtomicUsize,
+        done: Mutex<Vec<usize>>,
+    }
+
+    impl ProverHooks for CountingHooks {
+        fn shard_start(&self, _: usize, _: usize) {
+            self.started.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn shard_done(&self, index: usize, _: usize, _: Duration) {
+            self.done.lock().unwrap().push(index);
+        }
+    }
+
+    #[test]
+    fn test_prover_hooks() {
+        let mut runtime = Runtime::new(Program::from(FIBONACCI_ELF));
+        runtime.run();
+        let hooks = Arc::new(CountingHooks::default());
+        let proof = prove_core_with_hooks(
+            BabyBearBlake3::new(),
+            runtime,
+            &[],
+            None,
+            Some(hooks.clone()),
+        )
+        .unwrap();
+
+        let num_shards = proof.shard_proofs.len();
+        assert_eq!(hooks.started.load(Ordering::SeqCst), num_shards);
+        let mut done = hooks.done.lock().unwrap().clone();
+        done.sort();
+        assert_eq!(done, (0..num_shards).collect::<Vec<_>>());
+    }
+}
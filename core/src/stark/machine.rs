@@ -1,96 +1,451 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
 use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Instant;
 
+use crate::air::EventValidationError;
 use crate::air::MachineAir;
+use crate::air::PublicValues;
+use crate::io::{StdinBinding, StdinError};
 use crate::runtime::ExecutionRecord;
 use crate::runtime::Program;
+use crate::runtime::ShardTransitionError;
 use crate::runtime::ShardingConfig;
+use crate::utils::env;
+use crate::version::ProofVersionError;
 use p3_challenger::CanObserve;
+#[cfg(feature = "perf")]
+use p3_challenger::CanSample;
+use p3_commit::Pcs;
 use p3_field::AbstractField;
 use p3_field::Field;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_maybe_rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 
+use super::check_interaction_arities;
+use super::generation_levels;
+use super::CellBudgetExceededError;
 use super::Chip;
+use super::ChipCycleError;
+use super::Com;
+use super::ConstraintFailure;
+use super::InteractionArityError;
+use super::InteractionImbalance;
+use super::PcsProverData;
 use super::Proof;
 use super::Prover;
+use super::ProverHooks;
 use super::RiscvAir;
+use super::ShardTraces;
+use super::StarkAir;
 use super::StarkGenericConfig;
 use super::VerificationError;
 use super::Verifier;
+#[cfg(feature = "perf")]
+use super::PROOF_DIGEST_SIZE;
 
 pub type RiscvChip<SC> =
     Chip<<SC as StarkGenericConfig>::Val, RiscvAir<<SC as StarkGenericConfig>::Val>>;
 
+/// The chips every shard proof must include.
+const REQUIRED_CHIPS: [&str; 2] = ["CPU", "Program"];
+
+/// The chip finalizing the memory, which the last shard proof must include and no other may.
+const FINAL_CHIP: &str = "MemoryFinalize";
+
 /// A STARK for proving RISC-V execution.
+///
+/// The machine is generic over the AIR of its chips, so that a crate can prove with chips of its
+/// own by deriving `MachineAir` for an enum with a variant holding a [`RiscvAir`], and building
+/// the machine with [`RiscvStark::from_airs`].
 pub struct RiscvStark<SC: StarkGenericConfig, A = RiscvAir<<SC as StarkGenericConfig>::Val>> {
     /// The STARK settings for the RISC-V STARK.
     config: SC,
-    /// The chips that make up the RISC-V STARK machine, in order of their execution.
+    /// The chips that make up the RISC-V STARK machine.
     chips: Vec<Chip<SC::Val, A>>,
+    /// The indices of the chips in the levels in which they generate their dependencies.
+    generation_levels: Vec<Vec<usize>>,
+    /// The callbacks on the progress of proving, if any.
+    hooks: Option<Arc<dyn ProverHooks>>,
+    /// The number of shards proven at the same time, one per core if unset.
+    shard_workers: Option<usize>,
+    /// Whether the chips of each shard are committed in the order of [`RiscvStark::commit_order`]
+    /// rather than in the order of the machine.
+    order_chips: bool,
+    /// How the execution records are split into shards, the default configuration if unset.
+    sharding: Option<ShardingConfig>,
+    /// The bound on the trace cells of a shard, the one of the environment if unset.
+    max_cells: Option<usize>,
+    /// Whether to check the shards before committing to them, the flag of the environment if
+    /// unset.
+    self_check: Option<bool>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct ProvingKey<SC: StarkGenericConfig> {
     //TODO
     marker: std::marker::PhantomData<SC>,
+    /// The digest of the preprocessed trace of each chip which has one, by chip name.
+    pub(crate) preprocessed_digests: BTreeMap<String, [u8; 32]>,
+    /// The preprocessed trace of each chip which has one, by chip name.
+    pub(crate) preprocessed_traces: BTreeMap<String, RowMajorMatrix<SC::Val>>,
+    /// The commitment to the preprocessed trace of each chip which has one, by chip name.
+    pub(crate) preprocessed_commits: BTreeMap<String, Com<SC>>,
+    /// The data to open the commitment to the preprocessed trace of each chip which has one, by
+    /// chip name.
+    pub(crate) preprocessed_data: BTreeMap<String, Arc<PcsProverData<SC>>>,
 }
 
-#[derive(Debug, Clone)]
+impl<SC: StarkGenericConfig> std::fmt::Debug for ProvingKey<SC> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProvingKey")
+            .field("preprocessed_digests", &self.preprocessed_digests)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Everything about a program the verifier needs, which can be serialized so that a proof is
+/// verified without the program itself.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct VerifyingKey<SC: StarkGenericConfig> {
     // TODO:
     marker: std::marker::PhantomData<SC>,
+    /// The entrypoint of the program.
+    pc_start: u32,
+    /// The log of the quotient degree of each chip, by chip name.
+    log_quotient_degrees: BTreeMap<String, usize>,
+    /// The digest of the preprocessed trace of each chip which has one, by chip name.
+    preprocessed_digests: BTreeMap<String, [u8; 32]>,
+    /// The commitment to the preprocessed trace of each chip which has one, by chip name, which
+    /// the challenger observes and the preprocessed openings of the proof are checked against.
+    preprocessed_commits: BTreeMap<String, Com<SC>>,
+    /// The digest of the schema of the machine the key was set up with.
+    schema_digest: [u8; 32],
+    /// The identifier of the parameters of the hasher of the config the key was set up with.
+    hasher_params: u8,
+}
+
+impl<SC: StarkGenericConfig> std::fmt::Debug for VerifyingKey<SC> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VerifyingKey")
+            .field("pc_start", &self.pc_start)
+            .field("log_quotient_degrees", &self.log_quotient_degrees)
+            .field("preprocessed_digests", &self.preprocessed_digests)
+            .field("schema_digest", &self.schema_digest)
+            .field("hasher_params", &self.hasher_params)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<SC: StarkGenericConfig> VerifyingKey<SC> {
+    /// The digest of the schema of the machine the key was set up with.
+    pub fn schema_digest(&self) -> &[u8; 32] {
+        &self.schema_digest
+    }
+
+    /// The digests of the preprocessed traces of the chips, by chip name.
+    pub fn preprocessed_digests(&self) -> &BTreeMap<String, [u8; 32]> {
+        &self.preprocessed_digests
+    }
+
+    /// The identifier of the parameters of the hasher of the config the key was set up with.
+    pub fn hasher_params(&self) -> u8 {
+        self.hasher_params
+    }
 }
 
 impl<SC: StarkGenericConfig> RiscvStark<SC> {
     /// Create a new RISC-V STARK machine.
     pub fn new(config: SC) -> Self {
-        // The machine consists of a config (input) and a set of chips. The chip vector should
-        // contain the chips in the order they are executed. Each chip's air is able to add events
-        // to another chip's record (depending on interactions), so we order the chips by keeping
-        // track of which chips receive events from which other chips.
+        Self::from_airs(config, RiscvAir::get_all())
+    }
+}
 
-        // First, get all the chips associated with this machine.
-        let chips = RiscvAir::get_all()
-            .into_iter()
-            .map(Chip::new)
-            .collect::<Vec<_>>();
+impl<SC: StarkGenericConfig, A: StarkAir<SC>> RiscvStark<SC, A> {
+    /// Create a machine with a chip for each of the given AIRs, in any order.
+    ///
+    /// Panics if the AIRs depend on each other's events in a cycle, or disagree on the arity of the
+    /// interactions of a kind.
+    pub fn from_airs(config: SC, airs: Vec<A>) -> Self {
+        Self::try_from_airs(config, airs).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Create a machine with a chip for each of the given AIRs, in any order.
+    ///
+    /// Each chip's air is able to add events to another chip's record, so the chips generate their
+    /// dependencies in the order of the graph of the events they produce and consume, which must
+    /// not have a cycle. The interactions of a kind all share one argument, so they must all have
+    /// the same number of values.
+    pub fn try_from_airs(config: SC, airs: Vec<A>) -> Result<Self, MachineError> {
+        let generation_levels = generation_levels::<SC::Val, A>(&airs)?;
+        let chips = airs.into_iter().map(Chip::new).collect::<Vec<_>>();
+        check_interaction_arities(&chips)?;
 
-        Self { config, chips }
+        Ok(Self {
+            config,
+            chips,
+            generation_levels,
+            hooks: None,
+            shard_workers: None,
+            order_chips: false,
+            sharding: None,
+            max_cells: None,
+            self_check: None,
+        })
+    }
+
+    /// Report the progress of the proofs of the machine to the hooks.
+    pub fn with_hooks(mut self, hooks: Arc<dyn ProverHooks>) -> Self {
+        self.hooks = Some(hooks);
+        self
+    }
+
+    /// The callbacks on the progress of proving, if any.
+    pub fn hooks(&self) -> Option<&dyn ProverHooks> {
+        self.hooks.as_deref()
+    }
+
+    /// Prove at most `workers` shards at the same time. Each shard proof forks the challenger
+    /// after all the main commitments are observed, so the proof is the same for any number of
+    /// workers.
+    pub fn with_shard_workers(mut self, workers: usize) -> Self {
+        self.shard_workers = Some(workers.max(1));
+        self
+    }
+
+    /// The number of shards proven at the same time.
+    pub fn shard_workers(&self) -> usize {
+        self.shard_workers.unwrap_or_else(num_cpus::get)
+    }
+
+    /// Commit the chips of each shard in the order of [`RiscvStark::commit_order`]. The verifier
+    /// follows the order of the chip ids of each shard proof, so the proof verifies either way.
+    pub fn with_ordered_chips(mut self) -> Self {
+        self.order_chips = true;
+        self
+    }
+
+    /// Split the execution records proven by the machine into shards with the configuration. The
+    /// program is best executed with the same shard size, so that the shards end close to where
+    /// the runtime started new ones.
+    pub fn with_sharding(mut self, config: ShardingConfig) -> Self {
+        self.sharding = Some(config);
+        self
+    }
+
+    /// How the execution records proven by the machine are split into shards.
+    pub fn sharding(&self) -> ShardingConfig {
+        self.sharding.unwrap_or_default()
+    }
+
+    /// Refuse to prove shards which need more than `max_cells` trace cells.
+    pub fn with_max_cells(mut self, max_cells: usize) -> Self {
+        self.max_cells = Some(max_cells);
+        self
+    }
+
+    /// The bound on the trace cells of a shard, above which proving is refused.
+    pub fn max_cells(&self) -> usize {
+        self.max_cells.unwrap_or_else(env::max_cells)
+    }
+
+    /// Check the events, the constraints and the interactions of the shards before committing to
+    /// them, and refuse to prove shards which fail the check.
+    pub fn with_self_check(mut self, self_check: bool) -> Self {
+        self.self_check = Some(self_check);
+        self
+    }
+
+    /// Whether the shards are checked before committing to them.
+    pub fn self_check(&self) -> bool {
+        self.self_check.unwrap_or_else(env::self_check)
     }
 
     /// Get an array containing a `ChipRef` for all the chips of this RISC-V STARK machine.
-    pub fn chips(&self) -> &[RiscvChip<SC>] {
+    pub fn chips(&self) -> &[Chip<SC::Val, A>] {
         &self.chips
     }
 
+    /// The chips included in the shard, in the order in which they are committed.
     pub fn shard_chips<'a, 'b>(
         &'a self,
         shard: &'b ExecutionRecord,
-    ) -> impl Iterator<Item = &'b RiscvChip<SC>>
+    ) -> impl Iterator<Item = &'b Chip<SC::Val, A>>
     where
         'a: 'b,
     {
-        self.chips.iter().filter(|chip| chip.included(shard))
+        let chips = if self.order_chips {
+            self.commit_order(shard)
+        } else {
+            self.chips
+                .iter()
+                .filter(|chip| chip.included(shard))
+                .collect()
+        };
+        chips.into_iter()
+    }
+
+    /// The chips included in the shard, the tallest padded trace first and then by the log of
+    /// their quotient degree, ties kept in the order of the machine.
+    ///
+    /// The traces of a shard are committed in batches, so this puts the matrices of the same height
+    /// next to each other in every batch, and among them the quotients of the same width.
+    pub fn commit_order(&self, shard: &ExecutionRecord) -> Vec<&Chip<SC::Val, A>> {
+        let mut chips = self
+            .chips
+            .iter()
+            .filter(|chip| chip.included(shard))
+            .map(|chip| (chip, chip.num_rows(shard)))
+            .collect::<Vec<_>>();
+        chips.sort_by_key(|(chip, rows)| (std::cmp::Reverse(*rows), chip.log_quotient_degree()));
+        chips.into_iter().map(|(chip, _)| chip).collect()
+    }
+
+    /// The chips with the given names, in the order of the names, of the shard at `index` among
+    /// `num_shards` shards.
+    ///
+    /// The names must be those of distinct chips of the machine and include the chips every shard
+    /// proves. The memory is finalized in the last shard, and only there.
+    pub fn chips_by_ids(
+        &self,
+        chip_ids: &[String],
+        index: usize,
+        num_shards: usize,
+    ) -> Result<Vec<&Chip<SC::Val, A>>, ProgramVerificationError> {
+        let invalid_shape =
+            || ProgramVerificationError::InvalidSegmentProof(VerificationError::InvalidProofShape);
+        let mut chips = Vec::with_capacity(chip_ids.len());
+        for (i, id) in chip_ids.iter().enumerate() {
+            if chip_ids[..i].contains(id) {
+                return Err(invalid_shape());
+            }
+            let chip = self.chips.iter().find(|chip| chip.name() == *id);
+            chips.push(chip.ok_or_else(invalid_shape)?);
+        }
+
+        // Every shard executes instructions, so it must prove them against the program.
+        if REQUIRED_CHIPS
+            .iter()
+            .any(|name| !chip_ids.iter().any(|id| id == name))
+        {
+            return Err(invalid_shape());
+        }
+        match (
+            chip_ids.iter().any(|id| id == FINAL_CHIP),
+            index + 1 == num_shards,
+        ) {
+            (true, false) => Err(ProgramVerificationError::UnexpectedFinalShard(index)),
+            (false, true) => Err(ProgramVerificationError::MissingFinalShard),
+            _ => Ok(chips),
+        }
     }
 
     /// The setup preprocessing phase.
     ///
     /// Given a program, this function generates the proving and verifying keys. The keys correspond
     /// to the program code and other preprocessed colunms such as lookup tables.
-    pub fn setup(&self, _program: &Program) -> (ProvingKey<SC>, VerifyingKey<SC>) {
+    ///
+    /// The preprocessed trace of each chip which has one is committed to once, here, rather than
+    /// in every shard: the proof opens the commitment along with the main trace of the chip.
+    pub fn setup(&self, program: &Program) -> (ProvingKey<SC>, VerifyingKey<SC>) {
+        let preprocessed_digests = self.preprocessed_digests(program);
+        let mut preprocessed_traces = BTreeMap::new();
+        let mut preprocessed_commits = BTreeMap::new();
+        let mut preprocessed_data = BTreeMap::new();
+        for chip in self
+            .chips
+            .iter()
+            .filter(|chip| chip.preprocessed_width() > 0)
+        {
+            let Some(trace) = chip.generate_preprocessed_trace(program) else {
+                continue;
+            };
+            let (commit, data) = self.config.pcs().commit_batches(vec![trace.clone()]);
+            preprocessed_traces.insert(chip.name(), trace);
+            preprocessed_commits.insert(chip.name(), commit);
+            preprocessed_data.insert(chip.name(), Arc::new(data));
+        }
         (
             ProvingKey {
                 marker: PhantomData,
+                preprocessed_digests: preprocessed_digests.clone(),
+                preprocessed_traces,
+                preprocessed_commits: preprocessed_commits.clone(),
+                preprocessed_data,
             },
             VerifyingKey {
                 marker: PhantomData,
+                pc_start: program.pc_start,
+                log_quotient_degrees: self
+                    .chips
+                    .iter()
+                    .map(|chip| (chip.name(), chip.log_quotient_degree()))
+                    .collect(),
+                preprocessed_digests,
+                preprocessed_commits,
+                schema_digest: self.schema().digest(),
+                hasher_params: self.config.hasher_params_id(),
             },
         )
     }
 
+    /// The digests of the preprocessed traces of the chips which have one, for the given program.
+    pub fn preprocessed_digests(&self, program: &Program) -> BTreeMap<String, [u8; 32]> {
+        self.chips
+            .iter()
+            .filter(|chip| chip.preprocessed_width() > 0)
+            .map(|chip| (chip.name(), chip.preprocessed_digest(program)))
+            .collect()
+    }
+
+    /// Generate the main traces of the chips included in the shard.
+    pub fn generate_shard_traces(
+        &self,
+        shard: &ExecutionRecord,
+        index: usize,
+    ) -> ShardTraces<SC::Val>
+    where
+        A: Sync,
+    {
+        let chips = self.shard_chips(shard).collect::<Vec<_>>();
+        let generated = chips
+            .par_iter()
+            .map(|chip| {
+                let start = Instant::now();
+                let (trace, shape) =
+                    chip.generate_trace_with_shape(shard, &mut ExecutionRecord::default());
+                (trace, shape, start.elapsed())
+            })
+            .collect::<Vec<_>>();
+        let mut traces = Vec::with_capacity(chips.len());
+        let mut shapes = Vec::with_capacity(chips.len());
+        let mut trace_times = Vec::with_capacity(chips.len());
+        for (trace, shape, elapsed) in generated {
+            traces.push(trace);
+            shapes.push(shape);
+            trace_times.push(elapsed);
+        }
+        ShardTraces {
+            index,
+            public_values: PublicValues::from_record(shard),
+            chip_ids: chips.iter().map(|chip| chip.name()).collect(),
+            shapes,
+            traces,
+            trace_times,
+        }
+    }
+
     pub fn shard(
         &self,
         mut record: ExecutionRecord,
         shard_config: &ShardingConfig,
-    ) -> Vec<ExecutionRecord> {
+    ) -> Vec<ExecutionRecord>
+    where
+        A: Sync,
+    {
         // Get the local and global chips.
         let chips = self.chips();
 
@@ -102,13 +457,23 @@ impl<SC: StarkGenericConfig> RiscvStark<SC> {
             record.stats()
         );
 
-        // Generate the trace for each chip to collect events emitted from chips with dependencies.
-        chips.iter().for_each(|chip| {
-            let mut output = ExecutionRecord::default();
-            output.index = record.index;
-            chip.generate_dependencies(&record, &mut output);
-            record.append(&mut output);
-        });
+        // Generate the trace for each chip to collect events emitted from chips with dependencies,
+        // a level at a time so that each chip sees the events of the chips it depends on. The chips
+        // of a level are independent, so they run in parallel.
+        for level in self.generation_levels.iter() {
+            let mut outputs = level
+                .par_iter()
+                .map(|&i| {
+                    let mut output = ExecutionRecord::default();
+                    output.index = record.index;
+                    chips[i].generate_dependencies(&record, &mut output);
+                    output
+                })
+                .collect::<Vec<_>>();
+            for output in outputs.iter_mut() {
+                record.append(output);
+            }
+        }
 
         // Display the statistics about the workload after generate_trace.
         tracing::info!("Record stats finalized {:#?}", record.stats());
@@ -123,29 +488,227 @@ impl<SC: StarkGenericConfig> RiscvStark<SC> {
     ///
     /// Given a proving key `pk` and a matching execution record `record`, this function generates
     /// a STARK proof that the execution record is valid.
-    pub fn prove<P: Prover<SC>>(
+    pub fn prove<P: Prover<SC, A>>(
         &self,
         pk: &ProvingKey<SC>,
         record: ExecutionRecord,
         challenger: &mut SC::Challenger,
-    ) -> Proof<SC> {
+    ) -> Result<Proof<SC>, ProverError>
+    where
+        A: Sync,
+    {
+        self.prove_with_context::<P>(pk, record, &[], challenger)
+    }
+
+    /// Prove the execution record is valid, binding the proof to an application context.
+    ///
+    /// The context (e.g. a chain or application id) is hashed and observed before anything else,
+    /// so the proof only verifies against the same context. An empty context is not observed.
+    pub fn prove_with_context<P: Prover<SC, A>>(
+        &self,
+        pk: &ProvingKey<SC>,
+        record: ExecutionRecord,
+        context: &[u8],
+        challenger: &mut SC::Challenger,
+    ) -> Result<Proof<SC>, ProverError>
+    where
+        A: Sync,
+    {
+        self.prove_with_stdin::<P>(pk, record, context, None, challenger)
+    }
+
+    /// Prove the execution record is valid, binding the proof to an application context and to
+    /// the standard input of the program.
+    ///
+    /// The binding is observed right after the context and recorded in the proof, from where the
+    /// verifier observes it in turn. So are the values the program committed, as claimed values,
+    /// after the binding. Neither is constrained by the shards, see [`StdinBinding`].
+    pub fn prove_with_stdin<P: Prover<SC, A>>(
+        &self,
+        pk: &ProvingKey<SC>,
+        record: ExecutionRecord,
+        context: &[u8],
+        stdin: Option<StdinBinding>,
+        challenger: &mut SC::Challenger,
+    ) -> Result<Proof<SC>, ProverError>
+    where
+        A: Sync,
+    {
+        // The preprocessed traces of the record must be the ones the proving key was set up with.
+        if self.preprocessed_digests(&record.program) != pk.preprocessed_digests {
+            return Err(ProverError::ProgramMismatch);
+        }
+
+        let claimed_values = record.committed_values.clone();
         tracing::info!("Sharding the execution record.");
-        let shards = self.shard(record, &ShardingConfig::default());
+        let config = self.sharding();
+        let shards = self.shard(record, &config);
+        if self.self_check() {
+            ExecutionRecord::validate_shard_transitions(&shards)?;
+        }
+
+        // Refuse shards which would exhaust the memory of the prover before generating any trace.
+        self.check_cell_budget(&shards, self.max_cells(), config.shard_size())?;
+
+        observe_context::<SC>(challenger, context);
+        observe_stdin::<SC>(challenger, stdin.as_ref());
+        observe_claimed_values::<SC>(challenger, &claimed_values);
 
         tracing::info!("Generating the shard proofs.");
-        P::prove_shards(self, pk, shards, challenger)
+        let mut proof = P::prove_shards(self, pk, shards, challenger)?;
+        proof.context = context.to_vec();
+        proof.stdin = stdin;
+        proof.claimed_values = claimed_values;
+        Ok(proof)
+    }
+
+    /// Prove shards whose main traces were generated ahead of time, e.g. loaded from a witness
+    /// bundle, binding the proof like `prove_with_stdin`.
+    ///
+    /// The proof is the one `prove_with_stdin` produces from the record the traces were generated
+    /// from.
+    pub fn prove_traces<P: Prover<SC, A>>(
+        &self,
+        pk: &ProvingKey<SC>,
+        shards: Vec<ShardTraces<SC::Val>>,
+        context: &[u8],
+        stdin: Option<StdinBinding>,
+        claimed_values: Vec<u8>,
+        challenger: &mut SC::Challenger,
+    ) -> Proof<SC> {
+        observe_context::<SC>(challenger, context);
+        observe_stdin::<SC>(challenger, stdin.as_ref());
+        observe_claimed_values::<SC>(challenger, &claimed_values);
+
+        tracing::info!("Generating the shard proofs.");
+        let mut proof = P::prove_traces(self, pk, shards, challenger);
+        proof.context = context.to_vec();
+        proof.stdin = stdin;
+        proof.claimed_values = claimed_values;
+        proof
     }
 
     pub const fn config(&self) -> &SC {
         &self.config
     }
 
+    /// Verify a proof, returning the number of instructions executed by the program.
     pub fn verify(
         &self,
-        _vk: &VerifyingKey<SC>,
+        vk: &VerifyingKey<SC>,
+        proof: &Proof<SC>,
+        challenger: &mut SC::Challenger,
+    ) -> Result<u64, ProgramVerificationError>
+    where
+        SC::Challenger: Clone,
+    {
+        self.verify_with_context(vk, proof, &[], challenger)
+    }
+
+    /// Verify a proof bound to the given application context, returning the number of instructions
+    /// executed by the program.
+    pub fn verify_with_context(
+        &self,
+        vk: &VerifyingKey<SC>,
+        proof: &Proof<SC>,
+        context: &[u8],
+        challenger: &mut SC::Challenger,
+    ) -> Result<u64, ProgramVerificationError>
+    where
+        SC::Challenger: Clone,
+    {
+        // Check that the proof is bound to the expected context, and observe it.
+        if proof.context != context {
+            return Err(ProgramVerificationError::ContextMismatch);
+        }
+        observe_context::<SC>(challenger, context);
+
+        self.verify_observed(vk, proof, challenger)
+    }
+
+    /// Verify a proof whose context has already been checked and observed by the challenger.
+    pub(crate) fn verify_observed(
+        &self,
+        vk: &VerifyingKey<SC>,
+        proof: &Proof<SC>,
+        challenger: &mut SC::Challenger,
+    ) -> Result<u64, ProgramVerificationError>
+    where
+        SC::Challenger: Clone,
+    {
+        if vk.schema_digest != self.schema().digest() {
+            return Err(ProgramVerificationError::SchemaMismatch);
+        }
+        self.verify_hasher_params(vk, proof)?;
+        Self::verify_preprocessed_digests(vk, proof)?;
+
+        // Check that the shards form a complete execution before observing anything.
+        self.verify_shard_order(vk, proof)?;
+
+        observe_stdin::<SC>(challenger, proof.stdin.as_ref());
+        observe_claimed_values::<SC>(challenger, &proof.claimed_values);
+        self.verify_shards(vk, proof, challenger)
+    }
+
+    /// Check that the key and the proof were produced with the parameters of the hasher of the
+    /// config of the machine.
+    fn verify_hasher_params(
+        &self,
+        vk: &VerifyingKey<SC>,
+        proof: &Proof<SC>,
+    ) -> Result<(), ProgramVerificationError> {
+        let expected = self.config.hasher_params_id();
+        for found in [vk.hasher_params, proof.hasher_params] {
+            if found != expected {
+                return Err(ProgramVerificationError::Version(
+                    ProofVersionError::HasherParamsMismatch { expected, found },
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that the proof was produced with the preprocessed traces of the program of the key,
+    /// naming the first chip whose preprocessed trace differs.
+    ///
+    /// The commitments to the preprocessed traces bind the shard proofs to the key already; this
+    /// reports a proof of another program before verifying any shard. Proofs of versions before 5
+    /// hold no digests, and are only checked against the commitments.
+    fn verify_preprocessed_digests(
+        vk: &VerifyingKey<SC>,
+        proof: &Proof<SC>,
+    ) -> Result<(), ProgramVerificationError> {
+        if proof.preprocessed_digests.is_empty() {
+            return Ok(());
+        }
+        let names = vk
+            .preprocessed_digests
+            .keys()
+            .chain(proof.preprocessed_digests.keys());
+        for name in names {
+            if vk.preprocessed_digests.get(name) != proof.preprocessed_digests.get(name) {
+                return Err(ProgramVerificationError::PreprocessedMismatch(name.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Verify the shard proofs of a proof and their cumulative sum, without checking that the
+    /// shards form a complete execution of the program, and return the number of instructions
+    /// executed in the shards.
+    ///
+    /// This is enough for a proof of a range of shards, e.g. one carved out of an execution record
+    /// with [`ExecutionRecord::shard_range`], whose boundaries are checked against the public values
+    /// of the shards around it instead.
+    ///
+    /// The CPU chip constrains the instruction count of each shard to its number of real rows, and
+    /// the count is part of the public values observed by the challenger.
+    pub fn verify_shards(
+        &self,
+        vk: &VerifyingKey<SC>,
         proof: &Proof<SC>,
         challenger: &mut SC::Challenger,
-    ) -> Result<(), ProgramVerificationError>
+    ) -> Result<u64, ProgramVerificationError>
     where
         SC::Challenger: Clone,
     {
@@ -153,21 +716,41 @@ impl<SC: StarkGenericConfig> RiscvStark<SC> {
         // in a map-reduce recursion setting.
         #[cfg(feature = "perf")]
         tracing::info_span!("observe challenges for all segments").in_scope(|| {
-            proof.shard_proofs.iter().for_each(|proof| {
-                challenger.observe(proof.commitment.main_commit.clone());
-            });
-        });
+            let digest = observe_shard_commitments::<SC>(challenger, vk, proof);
+            if digest != proof.digest {
+                return Err(ProgramVerificationError::DigestMismatch);
+            }
+            challenger.observe_slice(&digest);
+            Ok(())
+        })?;
 
         // Verify the segment proofs.
+        let num_shards = proof.shard_proofs.len();
         for (i, proof) in proof.shard_proofs.iter().enumerate() {
             tracing::info_span!("verifying segment", segment = i).in_scope(|| {
-                let chips = self
-                    .chips()
+                let chips = self.chips_by_ids(&proof.chip_ids, i, num_shards)?;
+                // The quotient degrees come from the verifying key, so that a prover cannot shrink
+                // the quotient of a chip.
+                let log_quotient_degrees = chips
+                    .iter()
+                    .map(|chip| vk.log_quotient_degrees.get(&chip.name()).copied())
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or(ProgramVerificationError::InvalidSegmentProof(
+                        VerificationError::InvalidProofShape,
+                    ))?;
+                let preprocessed_commits = chips
                     .iter()
-                    .filter(|chip| proof.chip_ids.contains(&chip.name()))
+                    .map(|chip| vk.preprocessed_commits.get(&chip.name()).cloned())
                     .collect::<Vec<_>>();
-                Verifier::verify_shard(&self.config, &chips, &mut challenger.clone(), proof)
-                    .map_err(ProgramVerificationError::InvalidSegmentProof)
+                Verifier::verify_shard(
+                    &self.config,
+                    &chips,
+                    &log_quotient_degrees,
+                    &preprocessed_commits,
+                    &mut challenger.clone(),
+                    proof,
+                )
+                .map_err(ProgramVerificationError::InvalidSegmentProof)
             })?;
         }
 
@@ -181,10 +764,279 @@ impl<SC: StarkGenericConfig> RiscvStark<SC> {
         }
 
         match sum.is_zero() {
-            true => Ok(()),
+            true => Ok(proof.instruction_count()),
             false => Err(ProgramVerificationError::NonZeroCumulativeSum),
         }
     }
+
+    /// Check, in a single pass over the shards in the order of the proof, that they form the whole
+    /// execution of the program:
+    /// - the shard proofs are indexed contiguously from zero,
+    /// - exactly one shard, the last one, finalizes the memory of the halted program,
+    /// - the public values of every shard are canonical,
+    /// - the first shard starts the program at its entrypoint, and each other shard is the next
+    ///   shard of the execution, resuming where the previous one ended after the instructions of
+    ///   the previous shards,
+    /// - exactly the last shard halts.
+    ///
+    /// The order of execution comes from the public values, which the CPU chip constrains its
+    /// boundary rows to, so the indices chosen by the prover must follow it.
+    fn verify_shard_order(
+        &self,
+        vk: &VerifyingKey<SC>,
+        proof: &Proof<SC>,
+    ) -> Result<(), ProgramVerificationError> {
+        let num_shards = proof.shard_proofs.len();
+        if num_shards == 0 {
+            return Err(ProgramVerificationError::MissingFinalShard);
+        }
+        let mut prior_instruction_count = 0u64;
+        let mut previous: Option<&PublicValues<u32>> = None;
+        for (i, shard_proof) in proof.shard_proofs.iter().enumerate() {
+            // The shards before are indexed `0..i`, so a smaller index is a repeated one.
+            if shard_proof.index < i {
+                return Err(ProgramVerificationError::DuplicateShard(shard_proof.index));
+            }
+            if shard_proof.index != i {
+                return Err(ProgramVerificationError::InvalidShardIndex {
+                    expected: i,
+                    found: shard_proof.index,
+                });
+            }
+            let public_values = &shard_proof.public_values;
+            match previous {
+                // Each shard of the proof is a shard of the runtime, which numbers them in turn.
+                Some(prev) => {
+                    if public_values.shard != prev.shard + 1
+                        || public_values.start_pc != prev.end_pc
+                    {
+                        return Err(ProgramVerificationError::ShardBoundaryMismatch(i));
+                    }
+                }
+                None => {
+                    let starts_program = public_values.shard == 1
+                        && public_values.start_clk == 1
+                        && public_values.start_pc == vk.pc_start;
+                    if !starts_program {
+                        return Err(ProgramVerificationError::PublicValuesMismatch(i));
+                    }
+                }
+            }
+            #[cfg(feature = "perf")]
+            self.chips_by_ids(&shard_proof.chip_ids, i, num_shards)?;
+            if public_values.check_canonical().is_err() {
+                return Err(ProgramVerificationError::PublicValuesMismatch(i));
+            }
+            // The cycle counts read by the program rely on the instructions of the previous
+            // shards.
+            if public_values.prior_instruction_count as u64 != prior_instruction_count {
+                return Err(ProgramVerificationError::PublicValuesMismatch(i));
+            }
+            prior_instruction_count += public_values.instruction_count as u64;
+            // Only the last shard halts, and it must: a proof stopping anywhere else does not prove
+            // the whole execution.
+            if (public_values.halted == 1) != (i + 1 == num_shards) {
+                return Err(ProgramVerificationError::PublicValuesMismatch(i));
+            }
+            previous = Some(public_values);
+        }
+        Ok(())
+    }
+}
+
+/// Verify a proof of the RISC-V machine with only the config and the verifying key of the program,
+/// returning the number of instructions executed by the program.
+///
+/// Unlike setting up the key, this needs neither the program nor a runtime, and never touches the
+/// data of the prover.
+pub fn verify_proof<SC: StarkGenericConfig>(
+    config: SC,
+    vk: &VerifyingKey<SC>,
+    proof: &Proof<SC>,
+    challenger: &mut SC::Challenger,
+) -> Result<u64, ProgramVerificationError>
+where
+    SC::Challenger: Clone,
+{
+    RiscvStark::new(config).verify(vk, proof, challenger)
+}
+
+/// Observe the commitment to the public values of a shard, i.e. the hash of their canonical
+/// encoding, so that external verifiers can recompute it from the same bytes.
+pub(crate) fn observe_public_values<SC: StarkGenericConfig>(
+    challenger: &mut SC::Challenger,
+    public_values: &PublicValues<u32>,
+) {
+    for byte in public_values.commitment() {
+        challenger.observe(SC::Val::from_canonical_u8(byte));
+    }
+}
+
+/// Observe the commitments to the preprocessed traces of the key, then the commitments and public
+/// values of all the shards of a proof, and sample the digest the proof should hold.
+#[cfg(feature = "perf")]
+pub(crate) fn observe_shard_commitments<SC: StarkGenericConfig>(
+    challenger: &mut SC::Challenger,
+    vk: &VerifyingKey<SC>,
+    proof: &Proof<SC>,
+) -> [SC::Val; PROOF_DIGEST_SIZE] {
+    observe_preprocessed_commitments::<SC>(challenger, &vk.preprocessed_commits);
+    challenger.observe(SC::Val::from_canonical_usize(proof.shard_proofs.len()));
+    for shard_proof in proof.shard_proofs.iter() {
+        challenger.observe(SC::Val::from_canonical_usize(shard_proof.index));
+        challenger.observe(shard_proof.commitment.main_commit.clone());
+        observe_public_values::<SC>(challenger, &shard_proof.public_values);
+    }
+    core::array::from_fn(|_| challenger.sample())
+}
+
+/// Observe the commitments to the preprocessed traces of the chips, in the order of their names.
+pub(crate) fn observe_preprocessed_commitments<SC: StarkGenericConfig>(
+    challenger: &mut SC::Challenger,
+    commitments: &BTreeMap<String, Com<SC>>,
+) {
+    for commitment in commitments.values() {
+        challenger.observe(commitment.clone());
+    }
+}
+
+/// Observe the hash of an application context. An empty context is not observed, so that proofs
+/// without a context are unchanged.
+pub(crate) fn observe_context<SC: StarkGenericConfig>(
+    challenger: &mut SC::Challenger,
+    context: &[u8],
+) {
+    if context.is_empty() {
+        return;
+    }
+    let hash = blake3::hash(context);
+    for byte in hash.as_bytes() {
+        challenger.observe(SC::Val::from_canonical_u8(*byte));
+    }
+}
+
+/// Observe the binding of a proof to its standard input. A proof without one observes nothing,
+/// like one without a context.
+pub(crate) fn observe_stdin<SC: StarkGenericConfig>(
+    challenger: &mut SC::Challenger,
+    stdin: Option<&StdinBinding>,
+) {
+    let Some(stdin) = stdin else {
+        return;
+    };
+    for byte in stdin.to_bytes() {
+        challenger.observe(SC::Val::from_canonical_u8(byte));
+    }
+}
+
+/// Observe the hash of the values the prover claims the program committed. No shard constrains
+/// them, so observing them only ties the proof to the claim. A proof which claims nothing observes
+/// nothing, like a proof without a context.
+pub(crate) fn observe_claimed_values<SC: StarkGenericConfig>(
+    challenger: &mut SC::Challenger,
+    claimed_values: &[u8],
+) {
+    if claimed_values.is_empty() {
+        return;
+    }
+    let hash = blake3::hash(claimed_values);
+    for byte in hash.as_bytes() {
+        challenger.observe(SC::Val::from_canonical_u8(*byte));
+    }
+}
+
+/// Why the AIRs given to a machine do not make one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MachineError {
+    ChipCycle(ChipCycleError),
+    InteractionArity(InteractionArityError),
+}
+
+impl Display for MachineError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MachineError::ChipCycle(err) => write!(f, "{}", err),
+            MachineError::InteractionArity(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for MachineError {}
+
+impl From<ChipCycleError> for MachineError {
+    fn from(err: ChipCycleError) -> Self {
+        MachineError::ChipCycle(err)
+    }
+}
+
+impl From<InteractionArityError> for MachineError {
+    fn from(err: InteractionArityError) -> Self {
+        MachineError::InteractionArity(err)
+    }
+}
+
+/// Why an execution record could not be proven.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProverError {
+    /// A shard needs more trace cells than the budget of the prover.
+    CellBudgetExceeded(CellBudgetExceededError),
+    /// The proving key was set up for a different program than the one of the record.
+    ProgramMismatch,
+    /// The self check found consecutive shards which do not follow each other.
+    ShardTransition(ShardTransitionError),
+    /// The self check found an event which the chip it belongs to cannot prove.
+    InvalidEvent(EventValidationError),
+    /// The self check found a row at which the constraints of a chip are not satisfied.
+    ConstraintFailure(ConstraintFailure),
+    /// The self check found interactions which do not balance over the shards.
+    InteractionImbalance(InteractionImbalance),
+}
+
+impl Display for ProverError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProverError::CellBudgetExceeded(err) => write!(f, "{}", err),
+            ProverError::ProgramMismatch => {
+                write!(f, "the proving key was set up for a different program")
+            }
+            ProverError::ShardTransition(err) => write!(f, "self check failed: {}", err),
+            ProverError::InvalidEvent(err) => write!(f, "self check failed: {}", err),
+            ProverError::ConstraintFailure(failure) => write!(f, "self check failed: {}", failure),
+            ProverError::InteractionImbalance(err) => write!(f, "self check failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ProverError {}
+
+impl From<CellBudgetExceededError> for ProverError {
+    fn from(err: CellBudgetExceededError) -> Self {
+        ProverError::CellBudgetExceeded(err)
+    }
+}
+
+impl From<ShardTransitionError> for ProverError {
+    fn from(err: ShardTransitionError) -> Self {
+        ProverError::ShardTransition(err)
+    }
+}
+
+impl From<EventValidationError> for ProverError {
+    fn from(err: EventValidationError) -> Self {
+        ProverError::InvalidEvent(err)
+    }
+}
+
+impl From<ConstraintFailure> for ProverError {
+    fn from(failure: ConstraintFailure) -> Self {
+        ProverError::ConstraintFailure(failure)
+    }
+}
+
+impl From<InteractionImbalance> for ProverError {
+    fn from(err: InteractionImbalance) -> Self {
+        ProverError::InteractionImbalance(err)
+    }
 }
 
 #[derive(Debug)]
@@ -192,12 +1044,54 @@ pub enum ProgramVerificationError {
     InvalidSegmentProof(VerificationError),
     InvalidGlobalProof(VerificationError),
     NonZeroCumulativeSum,
+    /// The same shard index appears more than once.
+    DuplicateShard(usize),
+    /// The shards are not indexed contiguously from zero in the order of their execution.
+    InvalidShardIndex {
+        expected: usize,
+        found: usize,
+    },
+    /// No shard contains the end of the execution.
+    MissingFinalShard,
+    /// The end of the execution is in a shard other than the last one.
+    UnexpectedFinalShard(usize),
+    /// The shard proofs do not match the digest of the proof, e.g. because they come from
+    /// different runs.
+    DigestMismatch,
+    /// The proof is bound to a different application context than the expected one.
+    ContextMismatch,
+    /// The public values of a shard do not match the program, e.g. a wrong entrypoint.
+    PublicValuesMismatch(usize),
+    /// A shard is not the shard of the execution after the previous one, or does not resume at the
+    /// program counter where the previous one ended.
+    ShardBoundaryMismatch(usize),
+    /// The proof was not verified because another proof of its batch failed first.
+    NotVerified,
+    /// The input accompanying the proof is not the one it binds.
+    InvalidStdin(StdinError),
+    /// The values the prover claims the program committed are not the expected ones.
+    ClaimedValuesMismatch,
+    /// The verifying key was set up with a machine of another shape.
+    SchemaMismatch,
+    /// The proof or the verifying key was produced in a version the machine does not verify, e.g.
+    /// with other parameters of the hasher.
+    Version(ProofVersionError),
+    /// The proof was produced with another preprocessed trace of the chip than the one of the
+    /// key, i.e. for another program.
+    PreprocessedMismatch(String),
 }
 
 #[cfg(test)]
 #[allow(non_snake_case)]
 pub mod tests {
 
+    use p3_baby_bear::BabyBear;
+    use p3_field::{AbstractField, Field};
+    use p3_matrix::{dense::RowMajorMatrix, Matrix};
+
+    use crate::air::MachineAir;
+    use crate::air::PublicValues;
+    use crate::lookup::{debug_interactions_with_all_chips, InteractionKind};
     use crate::runtime::tests::ecall_lwa_program;
     use crate::runtime::tests::fibonacci_program;
     use crate::runtime::tests::simple_memory_program;
@@ -205,9 +1099,26 @@ pub mod tests {
     use crate::runtime::Instruction;
     use crate::runtime::Opcode;
     use crate::runtime::Program;
+    use crate::runtime::Runtime;
+    use crate::runtime::{ExecutionRecord, ShardRangeError, ShardingConfig};
+    use crate::stark::Challenge;
+    use crate::stark::LocalProver;
+    use crate::stark::Proof;
+    use crate::stark::Prover;
+    use crate::stark::RiscvAir;
     use crate::utils;
     use crate::utils::run_test;
     use crate::utils::setup_logger;
+    use crate::utils::tests::SHA_EXTEND_ELF;
+    use crate::utils::BabyBearBlake3;
+    use crate::utils::StarkUtils;
+
+    use super::ProgramVerificationError;
+    use super::ProverError;
+    use super::RiscvStark;
+    use super::VerificationError;
+    use super::VerifyingKey;
+    use super::FINAL_CHIP;
 
     #[test]
     fn test_simple_prove() {
@@ -355,4 +1266,864 @@ pub mod tests {
         let program = simple_memory_program();
         run_test(program).unwrap();
     }
+
+    /// Chips without events are left out of a shard, so a proof never opens an all-zero trace.
+    #[test]
+    fn test_empty_chips_excluded() {
+        let mut runtime = Runtime::new(fibonacci_program());
+        runtime.run();
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let shards = machine.shard(runtime.record, &ShardingConfig::default());
+        for shard in shards.iter() {
+            let chips = machine.shard_chips(shard).collect::<Vec<_>>();
+            assert!(chips.len() < machine.chips().len());
+            for chip in chips {
+                let trace = chip.generate_trace(shard, &mut ExecutionRecord::default());
+                assert!(
+                    trace.values.iter().any(|value| !value.is_zero()),
+                    "{} is included with an all-zero trace",
+                    chip.name()
+                );
+            }
+        }
+    }
+
+    /// A proof of a program without syscalls opens no precompile chip, and a proof leaving out a
+    /// chip whose interactions the other chips need does not verify.
+    #[test]
+    fn test_precompile_chips_excluded() {
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let precompiles = machine
+            .chips()
+            .iter()
+            .filter(|chip| {
+                chip.receives()
+                    .iter()
+                    .any(|interaction| interaction.kind == InteractionKind::Syscall)
+            })
+            .map(|chip| chip.name())
+            .collect::<Vec<_>>();
+        assert!(precompiles.contains(&"ShaExtend".to_string()));
+
+        let mut runtime = Runtime::new(simple_program().with_halt());
+        runtime.run();
+        let (pk, vk) = machine.setup(runtime.program.as_ref());
+        let mut challenger = machine.config().challenger();
+        let proof = machine
+            .prove::<LocalProver<_>>(&pk, runtime.record, &mut challenger)
+            .unwrap();
+        for shard_proof in proof.shard_proofs.iter() {
+            assert!(shard_proof.chip_ids.contains(&"Add".to_string()));
+            assert!(shard_proof
+                .chip_ids
+                .iter()
+                .all(|id| !precompiles.contains(id)));
+        }
+        let mut challenger = machine.config().challenger();
+        machine.verify(&vk, &proof, &mut challenger).unwrap();
+
+        let airs = RiscvAir::get_all()
+            .into_iter()
+            .filter(|air| air.name() != "Add")
+            .collect();
+        let machine = RiscvStark::from_airs(BabyBearBlake3::new(), airs);
+        let mut runtime = Runtime::new(simple_program().with_halt());
+        runtime.run();
+        let (pk, vk) = machine.setup(runtime.program.as_ref());
+        let mut challenger = machine.config().challenger();
+        let proof = machine
+            .prove::<LocalProver<_>>(&pk, runtime.record, &mut challenger)
+            .unwrap();
+        let mut challenger = machine.config().challenger();
+        assert!(matches!(
+            machine.verify(&vk, &proof, &mut challenger),
+            Err(ProgramVerificationError::NonZeroCumulativeSum)
+        ));
+    }
+
+    /// The shape of every generated trace matches the rows expected from the events of the shard.
+    #[test]
+    fn test_trace_shapes() {
+        let mut runtime = Runtime::new(fibonacci_program());
+        runtime.run();
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let shards = machine.shard(runtime.record, &ShardingConfig::default());
+        for shard in shards.iter() {
+            for chip in machine.chips() {
+                let (trace, shape) =
+                    chip.generate_trace_with_shape(shard, &mut ExecutionRecord::default());
+                let name = chip.name();
+                assert_eq!(shape.real_rows, chip.num_real_rows(shard), "{}", name);
+                assert_eq!(shape.padded_rows, trace.height(), "{}", name);
+                assert_eq!(shape.width, trace.width(), "{}", name);
+                assert!(shape.real_rows <= shape.padded_rows, "{}", name);
+                assert!(shape.padded_rows <= chip.num_rows(shard), "{}", name);
+                assert!(!chip.included(shard) || shape.real_rows > 0, "{}", name);
+            }
+        }
+    }
+
+    /// Generating a trace in place, into a buffer still holding the trace of another chip, yields
+    /// the same matrix and the same events as generating it into a new one.
+    #[test]
+    fn test_trace_into_reused_buffer() {
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let mut buffer = RowMajorMatrix::new(vec![BabyBear::one(); 1 << 12], 8);
+        for program in [fibonacci_program(), Program::from(SHA_EXTEND_ELF)] {
+            let mut runtime = Runtime::new(program);
+            runtime.run();
+            let shards = machine.shard(runtime.record, &ShardingConfig::default());
+            for shard in shards.iter() {
+                for chip in machine.chips() {
+                    let mut output = ExecutionRecord::default();
+                    let trace = chip.generate_trace(shard, &mut output);
+                    let mut output_into = ExecutionRecord::default();
+                    chip.generate_trace_into(shard, &mut output_into, &mut buffer);
+
+                    let name = chip.name();
+                    assert_eq!(buffer.width(), trace.width(), "{}", name);
+                    assert_eq!(buffer.values, trace.values, "{}", name);
+                    assert_eq!(output_into.byte_lookups, output.byte_lookups, "{}", name);
+                    assert_eq!(output_into.range_checks, output.range_checks, "{}", name);
+                    assert_eq!(output_into.field_events, output.field_events, "{}", name);
+                }
+            }
+        }
+    }
+
+    /// The order in which the chips are registered does not change the events of the shards, nor
+    /// any trace.
+    #[test]
+    fn test_scrambled_chip_order() {
+        let mut scrambled = RiscvAir::get_all();
+        scrambled.reverse();
+        scrambled.rotate_left(7);
+        let canonical = RiscvStark::new(BabyBearBlake3::new());
+        let scrambled = RiscvStark::from_airs(BabyBearBlake3::new(), scrambled);
+
+        for program in [fibonacci_program(), Program::from(SHA_EXTEND_ELF)] {
+            let mut runtime = Runtime::new(program);
+            runtime.run();
+            let config = ShardingConfig::default();
+            let canonical_shards = canonical.shard(runtime.record.clone(), &config);
+            let scrambled_shards = scrambled.shard(runtime.record, &config);
+            assert_eq!(canonical_shards.len(), scrambled_shards.len());
+
+            for (canonical_shard, scrambled_shard) in
+                canonical_shards.iter().zip(scrambled_shards.iter())
+            {
+                for chip in canonical.chips() {
+                    let other = scrambled
+                        .chips()
+                        .iter()
+                        .find(|other| other.name() == chip.name())
+                        .unwrap();
+                    let trace =
+                        chip.generate_trace(canonical_shard, &mut ExecutionRecord::default());
+                    let other_trace =
+                        other.generate_trace(scrambled_shard, &mut ExecutionRecord::default());
+                    assert_eq!(trace.values, other_trace.values, "{}", chip.name());
+                }
+            }
+        }
+    }
+
+    fn prove_sharded(
+        program: Program,
+        shard_size: usize,
+    ) -> (
+        RiscvStark<BabyBearBlake3>,
+        VerifyingKey<BabyBearBlake3>,
+        Proof<BabyBearBlake3>,
+    ) {
+        prove_sharded_with(RiscvStark::new(BabyBearBlake3::new()), program, shard_size)
+    }
+
+    fn prove_sharded_with(
+        machine: RiscvStark<BabyBearBlake3>,
+        program: Program,
+        shard_size: usize,
+    ) -> (
+        RiscvStark<BabyBearBlake3>,
+        VerifyingKey<BabyBearBlake3>,
+        Proof<BabyBearBlake3>,
+    ) {
+        let mut runtime = Runtime::new(program.with_halt());
+        // Every shard leaves room for the longest syscall, which takes 960 clock cycles, so the
+        // runtime starts a new shard every `shard_size` instructions.
+        runtime.shard_size = 960 / 4 + shard_size as u32;
+        runtime.run();
+        let (pk, vk) = machine.setup(runtime.program.as_ref());
+        let config = ShardingConfig::with_shard_size(shard_size);
+        let shards = machine.shard(runtime.record, &config);
+        assert!(shards.len() > 2);
+        let mut challenger = machine.config().challenger();
+        let proof = LocalProver::prove_shards(&machine, &pk, shards, &mut challenger).unwrap();
+        (machine, vk, proof)
+    }
+
+    #[test]
+    fn test_sharded_prove() {
+        let (machine, vk, proof) = prove_sharded(simple_memory_program(), 8);
+        let mut challenger = machine.config().challenger();
+        machine.verify(&vk, &proof, &mut challenger).unwrap();
+    }
+
+    #[test]
+    fn test_shard_workers_prove_identical() {
+        let proofs = [1, 4].map(|workers| {
+            let machine = RiscvStark::new(BabyBearBlake3::new()).with_shard_workers(workers);
+            assert_eq!(machine.shard_workers(), workers);
+            let (machine, vk, proof) = prove_sharded_with(machine, simple_memory_program(), 8);
+            let mut challenger = machine.config().challenger();
+            machine.verify(&vk, &proof, &mut challenger).unwrap();
+            assert!(proof.shard_proofs.len() >= 3);
+            bincode::serialize(&proof).unwrap()
+        });
+        assert_eq!(proofs[0], proofs[1]);
+    }
+
+    #[test]
+    fn test_ordered_chips_prove() {
+        let mut runtime = Runtime::new(fibonacci_program());
+        runtime.run();
+        let machine = RiscvStark::new(BabyBearBlake3::new()).with_ordered_chips();
+        let (pk, vk) = machine.setup(runtime.program.as_ref());
+        let shards = machine.shard(runtime.record, &ShardingConfig::default());
+        let orders = shards
+            .iter()
+            .map(|shard| {
+                let order = machine.commit_order(shard);
+                for pair in order.windows(2) {
+                    assert!(pair[0].num_rows(shard) >= pair[1].num_rows(shard));
+                }
+                order.iter().map(|chip| chip.name()).collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let mut challenger = machine.config().challenger();
+        let proof = LocalProver::prove_shards(&machine, &pk, shards, &mut challenger).unwrap();
+        for (shard_proof, order) in proof.shard_proofs.iter().zip(orders.iter()) {
+            assert_eq!(&shard_proof.chip_ids, order);
+        }
+
+        // A machine committing its chips in its own order verifies the proof as well.
+        let canonical = RiscvStark::new(BabyBearBlake3::new());
+        for machine in [&machine, &canonical] {
+            let mut challenger = machine.config().challenger();
+            machine.verify(&vk, &proof, &mut challenger).unwrap();
+        }
+
+        // A chip may not be opened twice.
+        let mut proof = proof;
+        let chip_ids = &mut proof.shard_proofs[0].chip_ids;
+        let i = chip_ids
+            .iter()
+            .position(|id| id != "MemoryFinalize" && *id != chip_ids[0])
+            .unwrap();
+        chip_ids[i] = chip_ids[0].clone();
+        let mut challenger = canonical.config().challenger();
+        let result = canonical.verify(&vk, &proof, &mut challenger);
+        assert!(matches!(
+            result,
+            Err(ProgramVerificationError::InvalidSegmentProof(
+                VerificationError::InvalidProofShape
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_drop_last_shard() {
+        let (machine, vk, mut proof) = prove_sharded(simple_memory_program(), 8);
+        proof.shard_proofs.pop();
+        let mut challenger = machine.config().challenger();
+        let result = machine.verify(&vk, &proof, &mut challenger);
+        assert!(matches!(
+            result,
+            Err(ProgramVerificationError::MissingFinalShard)
+        ));
+    }
+
+    #[test]
+    fn test_shard_chips_checked() {
+        let (machine, vk, mut proof) = prove_sharded(simple_memory_program(), 8);
+        let num_shards = proof.shard_proofs.len();
+        assert!(num_shards > 1);
+        let chip_ids = proof.shard_proofs[0].chip_ids.clone();
+        assert!(machine.chips_by_ids(&chip_ids, 0, num_shards).is_ok());
+
+        // The memory is finalized in the last shard only.
+        assert!(matches!(
+            machine.chips_by_ids(&chip_ids, num_shards - 1, num_shards),
+            Err(ProgramVerificationError::MissingFinalShard)
+        ));
+        let mut finalized = chip_ids.clone();
+        finalized.push(FINAL_CHIP.to_string());
+        assert!(matches!(
+            machine.chips_by_ids(&finalized, 0, num_shards),
+            Err(ProgramVerificationError::UnexpectedFinalShard(0))
+        ));
+
+        // A shard without the CPU chip proves none of its instructions.
+        proof.shard_proofs[0].chip_ids.retain(|id| id != "CPU");
+        let mut challenger = machine.config().challenger();
+        assert!(matches!(
+            machine.verify(&vk, &proof, &mut challenger),
+            Err(ProgramVerificationError::InvalidSegmentProof(
+                VerificationError::InvalidProofShape
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_duplicate_shard() {
+        let (machine, vk, mut proof) = prove_sharded(simple_memory_program(), 8);
+        let bytes = bincode::serialize(&proof.shard_proofs[1]).unwrap();
+        proof
+            .shard_proofs
+            .insert(2, bincode::deserialize(&bytes).unwrap());
+        let mut challenger = machine.config().challenger();
+        let result = machine.verify(&vk, &proof, &mut challenger);
+        assert!(matches!(
+            result,
+            Err(ProgramVerificationError::DuplicateShard(1))
+        ));
+    }
+
+    #[test]
+    fn test_reorder_shards() {
+        let (machine, vk, mut proof) = prove_sharded(simple_memory_program(), 8);
+        proof.shard_proofs.swap(0, 1);
+        let mut challenger = machine.config().challenger();
+        let result = machine.verify(&vk, &proof, &mut challenger);
+        assert!(matches!(
+            result,
+            Err(ProgramVerificationError::InvalidShardIndex {
+                expected: 0,
+                found: 1
+            })
+        ));
+    }
+
+    /// Every shard of a record missing an ALU event proves, but the ALU interactions do not
+    /// balance over the shards.
+    #[test]
+    fn test_unbalanced_interactions_rejected() {
+        let mut runtime = Runtime::new(fibonacci_program());
+        runtime.run();
+        runtime.record.add_events.pop();
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let (pk, vk) = machine.setup(runtime.program.as_ref());
+        let mut challenger = machine.config().challenger();
+        let proof = machine
+            .prove::<LocalProver<_>>(&pk, runtime.record, &mut challenger)
+            .unwrap();
+        let mut challenger = machine.config().challenger();
+        let result = machine.verify(&vk, &proof, &mut challenger);
+        assert!(matches!(
+            result,
+            Err(ProgramVerificationError::NonZeroCumulativeSum)
+        ));
+    }
+
+    #[test]
+    fn test_indices_out_of_execution_order() {
+        let (machine, vk, mut proof) = prove_sharded(simple_memory_program(), 8);
+        // Relabel the second and third shards, and list them in the order of their new indices.
+        proof.shard_proofs[1].index = 2;
+        proof.shard_proofs[2].index = 1;
+        proof.shard_proofs.swap(1, 2);
+        let mut challenger = machine.config().challenger();
+        let result = machine.verify(&vk, &proof, &mut challenger);
+        assert!(matches!(
+            result,
+            Err(ProgramVerificationError::ShardBoundaryMismatch(1))
+        ));
+    }
+
+    #[test]
+    fn test_skipped_shard() {
+        let (machine, vk, mut proof) = prove_sharded(simple_memory_program(), 8);
+        // Renumber the shards from the third one on, so that shard 2 is followed by shard 4.
+        for shard_proof in proof.shard_proofs[2..].iter_mut() {
+            shard_proof.public_values.shard += 1;
+        }
+        assert_eq!(proof.shard_proofs[1].public_values.shard, 2);
+        assert_eq!(proof.shard_proofs[2].public_values.shard, 4);
+        let mut challenger = machine.config().challenger();
+        let result = machine.verify(&vk, &proof, &mut challenger);
+        assert!(matches!(
+            result,
+            Err(ProgramVerificationError::ShardBoundaryMismatch(2))
+        ));
+    }
+
+    fn counter_program(step: u32) -> Program {
+        let instructions = (0..32)
+            .map(|_| Instruction::new(Opcode::ADD, 29, 29, step, false, true))
+            .collect();
+        Program::new(instructions, 0, 0)
+    }
+
+    #[test]
+    fn test_swap_shard_between_runs() {
+        let (machine, vk, mut proof) = prove_sharded(counter_program(1), 8);
+        let (_, _, other) = prove_sharded(counter_program(2), 8);
+        assert_eq!(proof.shard_proofs.len(), other.shard_proofs.len());
+        proof.shard_proofs[1] = other.shard_proofs.into_iter().nth(1).unwrap();
+        let mut challenger = machine.config().challenger();
+        let result = machine.verify(&vk, &proof, &mut challenger);
+        assert!(matches!(
+            result,
+            Err(ProgramVerificationError::DigestMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_context_binding() {
+        let mut runtime = Runtime::new(simple_memory_program());
+        runtime.run();
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let (pk, vk) = machine.setup(runtime.program.as_ref());
+        let mut challenger = machine.config().challenger();
+        let mut proof = machine
+            .prove_with_context::<LocalProver<_>>(&pk, runtime.record, b"app-a", &mut challenger)
+            .unwrap();
+        assert_eq!(proof.context, b"app-a");
+
+        let verify = |proof: &Proof<BabyBearBlake3>, context: &[u8]| {
+            let mut challenger = machine.config().challenger();
+            machine.verify_with_context(&vk, proof, context, &mut challenger)
+        };
+        verify(&proof, b"app-a").unwrap();
+        assert!(matches!(
+            verify(&proof, b"app-b"),
+            Err(ProgramVerificationError::ContextMismatch)
+        ));
+        assert!(matches!(
+            verify(&proof, &[]),
+            Err(ProgramVerificationError::ContextMismatch)
+        ));
+
+        // Relabeling the proof does not help, since the context is part of the transcript.
+        proof.context = b"app-b".to_vec();
+        assert!(verify(&proof, b"app-b").is_err());
+    }
+    #[test]
+    fn test_tampered_entrypoint() {
+        let (machine, mut vk, proof) = prove_sharded(simple_memory_program(), 8);
+        vk.pc_start += 4;
+        let mut challenger = machine.config().challenger();
+        let result = machine.verify(&vk, &proof, &mut challenger);
+        assert!(matches!(
+            result,
+            Err(ProgramVerificationError::PublicValuesMismatch(0))
+        ));
+    }
+
+    #[test]
+    fn test_entry_symbols() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::ADD, 30, 0, 8, false, true),
+            Instruction::new(Opcode::ADD, 29, 0, 7, false, true),
+            Instruction::new(Opcode::ADD, 31, 30, 29, false, false),
+        ];
+        let mut program = Program::new(instructions, 0, 0).with_halt();
+        program.symbols = [
+            ("prove_transfer".to_string(), 0),
+            ("prove_mint".to_string(), 8),
+        ]
+        .into_iter()
+        .collect();
+
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let prove = |name: &str| {
+            let mut runtime = Runtime::new(program.clone().with_entry_symbol(name));
+            runtime.run();
+            let (pk, vk) = machine.setup(runtime.program.as_ref());
+            let mut challenger = machine.config().challenger();
+            let proof = machine
+                .prove::<LocalProver<_>>(&pk, runtime.record, &mut challenger)
+                .unwrap();
+            (vk, proof)
+        };
+        let verify = |vk: &VerifyingKey<BabyBearBlake3>, proof: &Proof<BabyBearBlake3>| {
+            let mut challenger = machine.config().challenger();
+            machine.verify(vk, proof, &mut challenger)
+        };
+
+        let (transfer_vk, transfer_proof) = prove("prove_transfer");
+        let (mint_vk, mint_proof) = prove("prove_mint");
+        verify(&transfer_vk, &transfer_proof).unwrap();
+        verify(&mint_vk, &mint_proof).unwrap();
+        assert!(matches!(
+            verify(&transfer_vk, &mint_proof),
+            Err(ProgramVerificationError::PublicValuesMismatch(0))
+        ));
+        assert!(matches!(
+            verify(&mint_vk, &transfer_proof),
+            Err(ProgramVerificationError::PublicValuesMismatch(0))
+        ));
+    }
+
+    #[test]
+    fn test_tampered_cycle_count() {
+        let (machine, vk, mut proof) = prove_sharded(simple_memory_program(), 8);
+        proof.shard_proofs.last_mut().unwrap().public_values.end_clk += 4;
+        let mut challenger = machine.config().challenger();
+        assert!(machine.verify(&vk, &proof, &mut challenger).is_err());
+    }
+
+    #[test]
+    fn test_shard_boundary_mismatch() {
+        let (machine, vk, proof) = prove_sharded(simple_memory_program(), 8);
+        let verify = |proof: &Proof<BabyBearBlake3>| {
+            let mut challenger = machine.config().challenger();
+            machine.verify(&vk, proof, &mut challenger)
+        };
+
+        // Restart the second shard at a different program counter.
+        let mut tampered: Proof<BabyBearBlake3> =
+            bincode::deserialize(&bincode::serialize(&proof).unwrap()).unwrap();
+        tampered.shard_proofs[1].public_values.start_pc += 4;
+        assert!(matches!(
+            verify(&tampered),
+            Err(ProgramVerificationError::ShardBoundaryMismatch(1))
+        ));
+
+        // Number the second shard as the first one.
+        let mut tampered: Proof<BabyBearBlake3> =
+            bincode::deserialize(&bincode::serialize(&proof).unwrap()).unwrap();
+        tampered.shard_proofs[1].public_values.shard -= 1;
+        assert!(matches!(
+            verify(&tampered),
+            Err(ProgramVerificationError::ShardBoundaryMismatch(1))
+        ));
+
+        // Moving both sides of the boundary together is caught by the CPU constraints.
+        let mut tampered = proof;
+        tampered.shard_proofs[0].public_values.end_pc += 4;
+        tampered.shard_proofs[1].public_values.start_pc += 4;
+        assert!(verify(&tampered).is_err());
+    }
+
+    #[test]
+    fn test_instruction_count() {
+        let mut runtime = Runtime::new(simple_memory_program().with_halt());
+        runtime.run();
+        let instructions = runtime.report.instructions();
+        assert_eq!(instructions, runtime.state.global_clk as u64);
+
+        let (machine, vk, proof) = prove_sharded(simple_memory_program(), 8);
+        let mut challenger = machine.config().challenger();
+        let count = machine.verify(&vk, &proof, &mut challenger).unwrap();
+        assert_eq!(count, instructions);
+        assert_eq!(proof.instruction_count(), instructions);
+
+        // Claim one more instruction in the first shard.
+        let mut tampered: Proof<BabyBearBlake3> =
+            bincode::deserialize(&bincode::serialize(&proof).unwrap()).unwrap();
+        tampered.shard_proofs[0].public_values.instruction_count += 1;
+        let mut challenger = machine.config().challenger();
+        assert!(machine.verify(&vk, &tampered, &mut challenger).is_err());
+
+        // Claim fewer instructions before the second shard.
+        let mut tampered = proof;
+        tampered.shard_proofs[1]
+            .public_values
+            .prior_instruction_count -= 1;
+        let mut challenger = machine.config().challenger();
+        assert!(matches!(
+            machine.verify(&vk, &tampered, &mut challenger),
+            Err(ProgramVerificationError::PublicValuesMismatch(1))
+        ));
+    }
+
+    #[test]
+    fn test_halt_before_last_shard() {
+        let (machine, vk, mut proof) = prove_sharded(simple_memory_program(), 8);
+        proof.shard_proofs[0].public_values.halted = 1;
+        let mut challenger = machine.config().challenger();
+        let result = machine.verify(&vk, &proof, &mut challenger);
+        assert!(matches!(
+            result,
+            Err(ProgramVerificationError::PublicValuesMismatch(0))
+        ));
+    }
+
+    #[test]
+    fn test_last_shard_not_halted() {
+        let (machine, vk, mut proof) = prove_sharded(simple_memory_program(), 8);
+        let last = proof.shard_proofs.len() - 1;
+        proof.shard_proofs[last].public_values.halted = 0;
+        let mut challenger = machine.config().challenger();
+        let result = machine.verify(&vk, &proof, &mut challenger);
+        assert!(matches!(
+            result,
+            Err(ProgramVerificationError::PublicValuesMismatch(i)) if i == last
+        ));
+    }
+
+    #[test]
+    fn test_verify_proof_with_key() {
+        let (_, vk, proof) = prove_sharded(simple_memory_program(), 8);
+        let vk: VerifyingKey<BabyBearBlake3> =
+            bincode::deserialize(&bincode::serialize(&vk).unwrap()).unwrap();
+        let config = BabyBearBlake3::new();
+        let mut challenger = config.challenger();
+        super::verify_proof(config, &vk, &proof, &mut challenger).unwrap();
+
+        // Flip a single opened value of the main trace of a chip.
+        let bytes = bincode::serialize(&proof).unwrap();
+        let mut proof: Proof<BabyBearBlake3> = bincode::deserialize(&bytes).unwrap();
+        proof.shard_proofs[1].opened_values.chips[0].main.local[0] +=
+            Challenge::<BabyBearBlake3>::one();
+        let config = BabyBearBlake3::new();
+        let mut challenger = config.challenger();
+        let result = super::verify_proof(config, &vk, &proof, &mut challenger);
+        assert!(matches!(
+            result,
+            Err(ProgramVerificationError::InvalidSegmentProof(_))
+        ));
+    }
+
+    /// A proof of a program does not verify against the key of another program with the same
+    /// shape, since the key commits to the preprocessed traces of the program.
+    #[test]
+    fn test_modified_program_rejected() {
+        let mut runtime = Runtime::new(fibonacci_program());
+        runtime.run();
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let (pk, vk) = machine.setup(runtime.program.as_ref());
+        let mut challenger = machine.config().challenger();
+        let proof = machine
+            .prove::<LocalProver<_>>(&pk, runtime.record, &mut challenger)
+            .unwrap();
+        let mut challenger = machine.config().challenger();
+        machine.verify(&vk, &proof, &mut challenger).unwrap();
+
+        let mut program = fibonacci_program();
+        program.instructions[0].op_c = program.instructions[0].op_c.wrapping_add(1);
+        let (_, modified_vk) = machine.setup(&program);
+        assert_ne!(
+            modified_vk.preprocessed_digests(),
+            vk.preprocessed_digests()
+        );
+        let mut challenger = machine.config().challenger();
+        let result = machine.verify(&modified_vk, &proof, &mut challenger);
+        assert!(matches!(
+            result,
+            Err(ProgramVerificationError::PreprocessedMismatch(ref chip)) if chip == "Program"
+        ));
+    }
+
+    #[test]
+    fn test_shrunk_quotient() {
+        let (machine, vk, proof) = prove_sharded(simple_memory_program(), 8);
+        let bytes = bincode::serialize(&proof).unwrap();
+        let mut proof: Proof<BabyBearBlake3> = bincode::deserialize(&bytes).unwrap();
+        let quotient = &mut proof.shard_proofs[0].opened_values.chips[0].quotient;
+        quotient.truncate(quotient.len() / 2);
+        let mut challenger = machine.config().challenger();
+        let result = machine.verify(&vk, &proof, &mut challenger);
+        assert!(matches!(
+            result,
+            Err(ProgramVerificationError::InvalidSegmentProof(
+                VerificationError::InvalidProofShape
+            ))
+        ));
+    }
+
+    /// Runs the fibonacci program in at least three shards of the runtime.
+    fn run_in_three_shards() -> Runtime {
+        let mut runtime = Runtime::new(fibonacci_program());
+        runtime.run();
+        let cycles = runtime.record.cpu_events.len() as u32;
+
+        let mut runtime = Runtime::new(fibonacci_program());
+        // Every shard leaves room for the longest syscall, which takes 960 clock cycles.
+        runtime.shard_size = 960 / 4 + cycles / 3 + 1;
+        runtime.run();
+        assert!(runtime.state.current_shard >= 3);
+        runtime
+    }
+
+    #[test]
+    fn test_shard_range() {
+        let runtime = run_in_three_shards();
+        let record = &runtime.record;
+        let range = record.shard_range(2..3).unwrap();
+        assert!(range.cpu_events.iter().all(|event| event.shard == 2));
+        assert_eq!(
+            range.add_events.len(),
+            range
+                .cpu_events
+                .iter()
+                .filter(|event| event.instruction.opcode == Opcode::ADD)
+                .count()
+        );
+
+        // The memory accesses of the range balance on their own.
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        assert!(debug_interactions_with_all_chips::<BabyBearBlake3>(
+            machine.chips(),
+            &range,
+            vec![InteractionKind::Memory],
+        ));
+
+        assert_eq!(
+            record.shard_range(0..1).unwrap_err(),
+            ShardRangeError::Empty(0..1)
+        );
+    }
+
+    #[test]
+    fn test_shard_range_with_precompile() {
+        let program = Program::from(SHA_EXTEND_ELF);
+        let mut runtime = Runtime::new(program.clone());
+        runtime.run();
+        let cycles = runtime.record.cpu_events.len() as u32;
+
+        let mut runtime = Runtime::new(program);
+        runtime.shard_size = 960 / 4 + cycles / 3 + 1;
+        runtime.run();
+        let record = &runtime.record;
+        let shard = record.sha_extend_events.last().unwrap().shard;
+
+        let range = record.shard_range(shard..shard + 1).unwrap();
+        assert!(!range.sha_extend_events.is_empty());
+        assert!(range
+            .sha_extend_events
+            .iter()
+            .all(|event| event.shard == shard));
+        assert_eq!(
+            range.sha_extend_events.len(),
+            record
+                .sha_extend_events
+                .iter()
+                .filter(|event| event.shard == shard)
+                .count()
+        );
+
+        // The accesses of the precompile are initialized and finalized with the ones of the CPU.
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        assert!(debug_interactions_with_all_chips::<BabyBearBlake3>(
+            machine.chips(),
+            &range,
+            vec![InteractionKind::Memory],
+        ));
+    }
+
+    #[test]
+    fn test_prove_shard_range() {
+        let runtime = run_in_three_shards();
+        let record = &runtime.record;
+
+        // The boundaries of the middle shard in the full execution.
+        let middle = ExecutionRecord {
+            cpu_events: record
+                .cpu_events
+                .iter()
+                .filter(|event| event.shard == 2)
+                .cloned()
+                .collect(),
+            ..Default::default()
+        };
+        let expected = PublicValues::from_record(&middle);
+
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let (pk, vk) = machine.setup(runtime.program.as_ref());
+        let range = record.shard_range(2..3).unwrap();
+        let mut challenger = machine.config().challenger();
+        let proof = machine
+            .prove::<LocalProver<_>>(&pk, range, &mut challenger)
+            .unwrap();
+
+        assert_eq!(proof.shard_proofs.len(), 1);
+        let public_values = &proof.shard_proofs[0].public_values;
+        assert_eq!(public_values.commitment(), expected.commitment());
+
+        let mut challenger = machine.config().challenger();
+        machine.verify_shards(&vk, &proof, &mut challenger).unwrap();
+
+        // The range does not start the program, so it is not a proof of its execution.
+        let mut challenger = machine.config().challenger();
+        assert!(matches!(
+            machine.verify(&vk, &proof, &mut challenger),
+            Err(ProgramVerificationError::PublicValuesMismatch(0))
+        ));
+    }
+
+    /// The preprocessed traces, and thus the keys, do not depend on how many threads generate them.
+    #[test]
+    fn test_preprocessed_digests_deterministic() {
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let program = fibonacci_program();
+        let (_, vk) = machine.setup(&program);
+        assert_eq!(
+            vk.preprocessed_digests()
+                .keys()
+                .map(String::as_str)
+                .collect::<Vec<_>>(),
+            vec!["Byte", "MemoryProgram", "Program", "RangeCheck"]
+        );
+        for num_threads in 1..=10 {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .build()
+                .unwrap();
+            let digests = pool.install(|| machine.preprocessed_digests(&program));
+            assert_eq!(&digests, vk.preprocessed_digests());
+        }
+
+        // The digests commit to the program.
+        assert_ne!(
+            &machine.preprocessed_digests(&simple_program()),
+            vk.preprocessed_digests()
+        );
+    }
+
+    #[test]
+    fn test_proving_key_program_mismatch() {
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let (pk, _) = machine.setup(&simple_program());
+        let mut runtime = Runtime::new(fibonacci_program());
+        runtime.run();
+        let mut challenger = machine.config().challenger();
+        let result = machine.prove::<LocalProver<_>>(&pk, runtime.record, &mut challenger);
+        assert!(matches!(result, Err(ProverError::ProgramMismatch)));
+    }
+
+    /// With the self check on, a wrong addition is reported as a constraint failure instead of
+    /// being proven.
+    #[test]
+    fn test_self_check_constraint_failure() {
+        let machine = RiscvStark::new(BabyBearBlake3::new()).with_self_check(true);
+        let mut runtime = Runtime::new(fibonacci_program());
+        runtime.run();
+        let (pk, _) = machine.setup(runtime.program.as_ref());
+        let mut record = runtime.record;
+        record.add_events[0].a = record.add_events[0].a.wrapping_add(1);
+        let mut challenger = machine.config().challenger();
+        let result = machine.prove::<LocalProver<_>>(&pk, record, &mut challenger);
+        assert!(matches!(result, Err(ProverError::ConstraintFailure(_))));
+    }
+
+    #[test]
+    fn test_prove_cell_budget_exceeded() {
+        let machine = RiscvStark::new(BabyBearBlake3::new()).with_max_cells(1 << 10);
+        let program = simple_program().with_halt();
+        let (pk, _) = machine.setup(&program);
+        let mut runtime = Runtime::new(program);
+        runtime.run();
+        let mut challenger = machine.config().challenger();
+        let result = machine.prove::<LocalProver<_>>(&pk, runtime.record, &mut challenger);
+        match result {
+            Err(ProverError::CellBudgetExceeded(err)) => {
+                assert_eq!(err.shard, 1);
+                assert_eq!(err.budget, 1 << 10);
+            }
+            _ => panic!("expected the cell budget to be exceeded"),
+        }
+    }
 }
@@ -1,26 +1,48 @@
 mod air;
+mod arity;
+mod batch;
+mod budget;
 mod chip;
 mod config;
+#[cfg(all(test, feature = "perf"))]
+mod conformance;
 mod debug;
 mod folder;
+mod hooks;
 mod machine;
+mod order;
 mod permutation;
 mod prover;
 mod quotient;
+mod schema;
+mod stats;
+#[cfg(feature = "perf")]
+mod summary;
+mod symbolic;
 mod types;
 mod util;
 mod verifier;
 mod zerofier_coset;
 
 pub use air::*;
+pub use arity::*;
+pub use batch::*;
+pub use budget::*;
 pub use chip::*;
 pub use config::*;
 pub use debug::*;
 pub use folder::*;
+pub use hooks::*;
 pub use machine::*;
+pub use order::*;
 pub use permutation::*;
 pub use prover::*;
 pub use quotient::*;
+pub use schema::*;
+pub use stats::*;
+#[cfg(feature = "perf")]
+pub use summary::*;
+pub use symbolic::*;
 pub use types::*;
 pub use verifier::*;
 
@@ -0,0 +1,230 @@
+use core::fmt::{Display, Formatter};
+
+use p3_field::Field;
+
+use crate::air::MachineAir;
+
+/// The chips of a machine whose dependencies are generated from each other's events in a cycle, so
+/// that no order generates all the events of each of them before its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChipCycleError {
+    /// The names of the chips on the cycle, or depending on it, in alphabetical order.
+    pub chips: Vec<String>,
+}
+
+impl Display for ChipCycleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "the chips {} depend on each other's events in a cycle",
+            self.chips.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for ChipCycleError {}
+
+/// Orders the generation of the dependencies of the given AIRs in levels, by their index.
+///
+/// An AIR comes after every AIR which produces events it consumes. The AIRs of a level depend on
+/// none of each other, so they can generate their dependencies in parallel. Within a level, the
+/// AIRs are sorted by name, so that the order of the events they emit, and hence the traces, does
+/// not depend on the order in which the AIRs were given.
+pub fn generation_levels<F: Field, A: MachineAir<F>>(
+    airs: &[A],
+) -> Result<Vec<Vec<usize>>, ChipCycleError> {
+    let depends_on = |consumer: &A, producer: &A| {
+        producer
+            .produces()
+            .iter()
+            .any(|kind| consumer.consumes().contains(kind))
+    };
+    let mut in_degrees = airs
+        .iter()
+        .map(|consumer| {
+            airs.iter()
+                .filter(|producer| depends_on(consumer, producer))
+                .count()
+        })
+        .collect::<Vec<_>>();
+
+    let mut levels = Vec::new();
+    let mut placed = vec![false; airs.len()];
+    let mut remaining = airs.len();
+    while remaining > 0 {
+        let mut level = (0..airs.len())
+            .filter(|&i| !placed[i] && in_degrees[i] == 0)
+            .collect::<Vec<_>>();
+        if level.is_empty() {
+            let mut chips = (0..airs.len())
+                .filter(|&i| !placed[i])
+                .map(|i| airs[i].name())
+                .collect::<Vec<_>>();
+            chips.sort();
+            return Err(ChipCycleError { chips });
+        }
+        level.sort_by_key(|&i| airs[i].name());
+
+        for &i in level.iter() {
+            placed[i] = true;
+            for (j, consumer) in airs.iter().enumerate() {
+                if depends_on(consumer, &airs[i]) {
+                    in_degrees[j] -= 1;
+                }
+            }
+        }
+        remaining -= level.len();
+        levels.push(level);
+    }
+    Ok(levels)
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_air::BaseAir;
+    use p3_baby_bear::BabyBear;
+    use p3_matrix::dense::RowMajorMatrix;
+
+    use super::*;
+    use crate::air::EventKind;
+    use crate::runtime::ExecutionRecord;
+    use crate::stark::RiscvAir;
+
+    struct KindsAir {
+        name: &'static str,
+        produces: &'static [EventKind],
+        consumes: &'static [EventKind],
+    }
+
+    impl<F> BaseAir<F> for KindsAir {
+        fn width(&self) -> usize {
+            1
+        }
+    }
+
+    impl<F: Field> MachineAir<F> for KindsAir {
+        fn name(&self) -> String {
+            self.name.to_string()
+        }
+
+        fn produces(&self) -> &[EventKind] {
+            self.produces
+        }
+
+        fn consumes(&self) -> &[EventKind] {
+            self.consumes
+        }
+
+        fn num_real_rows(&self, _: &ExecutionRecord) -> usize {
+            0
+        }
+
+        fn generate_trace(
+            &self,
+            _: &ExecutionRecord,
+            _: &mut ExecutionRecord,
+        ) -> RowMajorMatrix<F> {
+            unimplemented!()
+        }
+    }
+
+    fn names(airs: &[KindsAir], levels: &[Vec<usize>]) -> Vec<Vec<&'static str>> {
+        levels
+            .iter()
+            .map(|level| level.iter().map(|&i| airs[i].name).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_generation_levels() {
+        let airs = [
+            KindsAir {
+                name: "Byte",
+                produces: &[],
+                consumes: &[EventKind::ByteLookup],
+            },
+            KindsAir {
+                name: "Mul",
+                produces: &[EventKind::ByteLookup],
+                consumes: &[EventKind::Mul],
+            },
+            KindsAir {
+                name: "DivRem",
+                produces: &[EventKind::Mul, EventKind::ByteLookup],
+                consumes: &[EventKind::DivRem],
+            },
+            KindsAir {
+                name: "CPU",
+                produces: &[EventKind::DivRem, EventKind::Mul],
+                consumes: &[EventKind::Cpu],
+            },
+            KindsAir {
+                name: "Program",
+                produces: &[],
+                consumes: &[EventKind::Cpu],
+            },
+        ];
+        let levels = generation_levels::<BabyBear, _>(&airs).unwrap();
+        assert_eq!(
+            names(&airs, &levels),
+            [
+                vec!["CPU", "Program"],
+                vec!["DivRem"],
+                vec!["Mul"],
+                vec!["Byte"]
+            ]
+        );
+
+        // Every chip of the RISC-V machine comes after the chips producing the events it consumes.
+        let airs = RiscvAir::<BabyBear>::get_all();
+        let levels = generation_levels::<BabyBear, _>(&airs).unwrap();
+        let position = |name: &str| {
+            levels
+                .iter()
+                .position(|level| level.iter().any(|&i| airs[i].name() == name))
+                .unwrap()
+        };
+        assert_eq!(position("CPU"), 0);
+        assert!(position("DivRem") < position("Mul"));
+        assert!(position("Mul") < position("Byte"));
+        assert!(position("ShaExtend") < position("FieldLTU"));
+        assert!(position("FieldLTU") < position("RangeCheck"));
+        assert_eq!(position("Byte"), levels.len() - 1);
+    }
+
+    #[test]
+    fn test_generation_levels_cycle() {
+        let airs = [
+            KindsAir {
+                name: "Cpu",
+                produces: &[],
+                consumes: &[EventKind::Cpu],
+            },
+            KindsAir {
+                name: "Add",
+                produces: &[EventKind::Sub],
+                consumes: &[EventKind::Add],
+            },
+            KindsAir {
+                name: "Sub",
+                produces: &[EventKind::Add],
+                consumes: &[EventKind::Sub],
+            },
+            KindsAir {
+                name: "Byte",
+                produces: &[],
+                consumes: &[EventKind::Add],
+            },
+        ];
+        let err = generation_levels::<BabyBear, _>(&airs).unwrap_err();
+        assert_eq!(err.chips, ["Add", "Byte", "Sub"]);
+
+        // A chip consuming the events it produces would never see those of its own.
+        let airs = [KindsAir {
+            name: "Add",
+            produces: &[EventKind::Add],
+            consumes: &[EventKind::Add],
+        }];
+        assert!(generation_levels::<BabyBear, _>(&airs).is_err());
+    }
+}
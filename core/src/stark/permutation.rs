@@ -27,11 +27,12 @@ pub fn generate_interaction_rlc_elements<F: Field, EF: AbstractExtensionField<F>
 /// Generates the permutation trace for the given chip and main trace based on a variant of LogUp.
 ///
 /// The permutation trace has (N+1)*EF::NUM_COLS columns, where N is the number of interactions in
-/// the chip.
+/// the chip. The interactions may read the preprocessed trace, which has the height of the main
+/// trace.
 pub(crate) fn generate_permutation_trace<F: PrimeField, EF: ExtensionField<F>>(
     sends: &[Interaction<F>],
     receives: &[Interaction<F>],
-    preprocessed: &Option<RowMajorMatrix<F>>,
+    preprocessed: Option<&RowMajorMatrix<F>>,
     main: &RowMajorMatrix<F>,
     random_elements: &[EF],
 ) -> RowMajorMatrix<EF> {
@@ -41,8 +42,17 @@ pub(crate) fn generate_permutation_trace<F: PrimeField, EF: ExtensionField<F>>(
     // Generate the RLC elements to uniquely identify each item in the looked up tuple.
     let betas = random_elements[1].powers();
 
-    // TODO: Get the preprocessed trace and handle it properly.
-    // let preprocessed = chip.preprocessed_trace();
+    if let Some(preprocessed) = preprocessed {
+        assert_eq!(
+            preprocessed.height(),
+            main.height(),
+            "the preprocessed and main traces have different heights"
+        );
+    }
+    let preprocessed_row = |i: usize| match preprocessed {
+        Some(preprocessed) => preprocessed.row_slice(i),
+        None => &[],
+    };
 
     // Iterate over the rows of the main trace to compute the permutation trace values. In
     // particular, for each row i, interaction j, and columns c_0, ..., c_{k-1} we compute the sum:
@@ -51,82 +61,78 @@ pub(crate) fn generate_permutation_trace<F: PrimeField, EF: ExtensionField<F>>(
     //
     // where f_{i, c_k} is the value at row i for column c_k. The computed value is essentially a
     // fingerprint for the interaction.
+    //
+    // The values are written chunk by chunk into a preallocated buffer, so that the trace is never
+    // materialized more than once.
     let chunk_rate = 1 << 8;
     let permutation_trace_width = sends.len() + receives.len() + 1;
-    let mut permutation_trace_values = {
-        // Compute the permutation trace values in parallel.
-
-        let mut parallel = match preprocessed {
-            Some(_) => unimplemented!(),
-            None => main
-                .par_row_chunks(chunk_rate)
-                .flat_map(|main_rows_chunk| {
-                    main_rows_chunk
-                        .rows()
-                        .flat_map(|main_row| {
-                            compute_permutation_row(
-                                main_row,
-                                &[],
-                                sends,
-                                receives,
-                                &alphas,
-                                betas.clone(),
-                            )
-                        })
-                        .collect::<Vec<_>>()
-                })
-                .collect::<Vec<_>>(),
-        };
-
-        // Compute the permutation trace values for the remainder.
-        let remainder = main.height() % chunk_rate;
-        for i in 0..remainder {
-            let perm_row = compute_permutation_row(
-                main.row_slice(main.height() - remainder + i),
-                &[],
-                sends,
-                receives,
-                &alphas,
-                betas.clone(),
-            );
-            parallel.extend(perm_row);
-        }
-        parallel
-    };
+    let chunk_len = chunk_rate * permutation_trace_width;
+    let mut permutation_trace_values = vec![EF::zero(); main.height() * permutation_trace_width];
 
-    // The permutation trace is actually the multiplicative inverse of the RLC's we computed above.
-    permutation_trace_values
-        .chunks_mut(chunk_rate)
-        .par_bridge()
-        .for_each(|chunk| batch_multiplicative_inverse_inplace(chunk));
-    let mut permutation_trace =
-        RowMajorMatrix::new(permutation_trace_values, permutation_trace_width);
-
-    // Weight each row of the permutation trace by the respective multiplicities.
-    let mut phi = vec![EF::zero(); permutation_trace.height()];
+    // For each chunk, the permutation trace is the multiplicative inverse of the RLC's, weighted by
+    // the respective multiplicities into a running sum local to the chunk.
     let nb_sends = sends.len();
-    for (i, (main_row, permutation_row)) in main
-        .rows()
-        .zip(permutation_trace.as_view_mut().rows_mut())
+    let chunk_sums = permutation_trace_values
+        .par_chunks_mut(chunk_len)
         .enumerate()
-    {
-        if i > 0 {
-            phi[i] = phi[i - 1];
-        }
-        // All all sends
-        for (j, send) in sends.iter().enumerate() {
-            let mult = send.multiplicity.apply::<F, F>(&[], main_row);
-            phi[i] += EF::from_base(mult) * permutation_row[j];
-        }
-        // Subtract all receives
-        for (j, rec) in receives.iter().enumerate() {
-            let mult = rec.multiplicity.apply::<F, F>(&[], main_row);
-            phi[i] -= EF::from_base(mult) * permutation_row[nb_sends + j];
-        }
-        *permutation_row.last_mut().unwrap() = phi[i];
-    }
+        .map(|(chunk_index, chunk)| {
+            let first_row = chunk_index * chunk_rate;
+            for (i, permutation_row) in chunk.chunks_exact_mut(permutation_trace_width).enumerate()
+            {
+                populate_permutation_row(
+                    permutation_row,
+                    main.row_slice(first_row + i),
+                    preprocessed_row(first_row + i),
+                    sends,
+                    receives,
+                    &alphas,
+                    betas.clone(),
+                );
+            }
+            batch_multiplicative_inverse_inplace(chunk);
+
+            let mut phi = EF::zero();
+            for (i, permutation_row) in chunk.chunks_exact_mut(permutation_trace_width).enumerate()
+            {
+                let main_row = main.row_slice(first_row + i);
+                let prep_row = preprocessed_row(first_row + i);
+                // Add all sends
+                for (j, send) in sends.iter().enumerate() {
+                    let mult = send.multiplicity.apply::<F, F>(prep_row, main_row);
+                    phi += EF::from_base(mult) * permutation_row[j];
+                }
+                // Subtract all receives
+                for (j, rec) in receives.iter().enumerate() {
+                    let mult = rec.multiplicity.apply::<F, F>(prep_row, main_row);
+                    phi -= EF::from_base(mult) * permutation_row[nb_sends + j];
+                }
+                *permutation_row.last_mut().unwrap() = phi;
+            }
+            phi
+        })
+        .collect::<Vec<_>>();
+
+    // Turn the local running sums into a global one by offsetting each chunk with the sum of all
+    // the chunks before it.
+    let offsets = chunk_sums
+        .into_iter()
+        .scan(EF::zero(), |sum, chunk_sum| {
+            let offset = *sum;
+            *sum += chunk_sum;
+            Some(offset)
+        })
+        .collect::<Vec<_>>();
+    permutation_trace_values
+        .par_chunks_mut(chunk_len)
+        .zip(offsets.into_par_iter())
+        .skip(1)
+        .for_each(|(chunk, offset)| {
+            for permutation_row in chunk.chunks_exact_mut(permutation_trace_width) {
+                *permutation_row.last_mut().unwrap() += offset;
+            }
+        });
 
-    permutation_trace
+    RowMajorMatrix::new(permutation_trace_values, permutation_trace_width)
 }
 
 /// Evaluates the permutation constraints for the given chip.
@@ -221,6 +227,28 @@ pub fn compute_permutation_row<F: PrimeField, EF: ExtensionField<F>>(
 ) -> Vec<EF> {
     let width = sends.len() + receives.len() + 1;
     let mut row = vec![EF::zero(); width];
+    populate_permutation_row(
+        &mut row,
+        main_row,
+        preprocessed_row,
+        sends,
+        receives,
+        alphas,
+        betas,
+    );
+    row
+}
+
+/// Writes the permutation fingerprint of a row into `row`, leaving its last column untouched.
+pub fn populate_permutation_row<F: PrimeField, EF: ExtensionField<F>>(
+    row: &mut [EF],
+    main_row: &[F],
+    preprocessed_row: &[F],
+    sends: &[Interaction<F>],
+    receives: &[Interaction<F>],
+    alphas: &[EF],
+    betas: Powers<EF>,
+) {
     for (i, interaction) in sends.iter().chain(receives.iter()).enumerate() {
         let alpha = alphas[interaction.argument_index()];
         row[i] = alpha;
@@ -228,5 +256,121 @@ pub fn compute_permutation_row<F: PrimeField, EF: ExtensionField<F>>(
             row[i] += beta * columns.apply::<F, F>(preprocessed_row, main_row)
         }
     }
-    row
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_air::BaseAir;
+    use p3_baby_bear::BabyBear;
+    use p3_field::extension::BinomialExtensionField;
+    use p3_field::{AbstractExtensionField, AbstractField, Field};
+    use p3_matrix::{dense::RowMajorMatrix, Matrix, MatrixRowSlices};
+    use rand::{thread_rng, Rng};
+
+    use super::{compute_permutation_row, generate_interaction_rlc_elements};
+    use crate::air::MachineAir;
+    use crate::alu::AddChip;
+    use crate::cpu::CpuChip;
+    use crate::program::ProgramChip;
+    use crate::runtime::tests::fibonacci_program;
+    use crate::stark::{Chip, RiscvAir};
+
+    type EF = BinomialExtensionField<BabyBear, 4>;
+
+    /// A row-by-row implementation of the permutation trace used as a reference.
+    fn reference_permutation_trace(
+        chip: &Chip<BabyBear, RiscvAir<BabyBear>>,
+        preprocessed: Option<&RowMajorMatrix<BabyBear>>,
+        main: &RowMajorMatrix<BabyBear>,
+        random_elements: &[EF],
+    ) -> RowMajorMatrix<EF> {
+        let (sends, receives) = (chip.sends(), chip.receives());
+        let alphas = generate_interaction_rlc_elements(sends, receives, random_elements[0]);
+        let width = sends.len() + receives.len() + 1;
+        let mut values = Vec::with_capacity(main.height() * width);
+        let mut phi = EF::zero();
+        for i in 0..main.height() {
+            let main_row = main.row_slice(i);
+            let preprocessed_row = preprocessed.map_or(&[][..], |p| p.row_slice(i));
+            let mut row = compute_permutation_row(
+                main_row,
+                preprocessed_row,
+                sends,
+                receives,
+                &alphas,
+                random_elements[1].powers(),
+            );
+            for (j, interaction) in sends.iter().chain(receives.iter()).enumerate() {
+                let inverse = row[j].try_inverse().unwrap_or(EF::zero());
+                let mult = EF::from_base(
+                    interaction
+                        .multiplicity
+                        .apply::<BabyBear, BabyBear>(preprocessed_row, main_row),
+                );
+                row[j] = inverse;
+                if j < sends.len() {
+                    phi += mult * inverse;
+                } else {
+                    phi -= mult * inverse;
+                }
+            }
+            row[width - 1] = phi;
+            values.extend(row);
+        }
+        RowMajorMatrix::new(values, width)
+    }
+
+    #[test]
+    fn test_permutation_trace_matches_reference() {
+        let mut rng = thread_rng();
+        let chips = [
+            Chip::new(RiscvAir::<BabyBear>::Cpu(CpuChip::default())),
+            Chip::new(RiscvAir::<BabyBear>::Add(AddChip::default())),
+        ];
+        for chip in chips.iter() {
+            // Heights below, at and above the chunk size, including a partial last chunk.
+            for height in [4, 256, 2048, 300] {
+                let width = chip.width();
+                let main = RowMajorMatrix::new(
+                    (0..height * width)
+                        .map(|_| rng.gen())
+                        .collect::<Vec<BabyBear>>(),
+                    width,
+                );
+                let random_elements: [EF; 2] = [rng.gen(), rng.gen()];
+                let perm = chip.generate_permutation_trace(None, &main, &random_elements);
+                let expected = reference_permutation_trace(chip, None, &main, &random_elements);
+                assert_eq!(perm.width, expected.width);
+                assert_eq!(perm.values, expected.values);
+            }
+        }
+    }
+
+    /// The interactions of the program chip read the instructions from its preprocessed trace.
+    #[test]
+    fn test_preprocessed_permutation_trace_matches_reference() {
+        let mut rng = thread_rng();
+        let chip = Chip::new(RiscvAir::<BabyBear>::Program(ProgramChip::new()));
+        let program = fibonacci_program();
+        let preprocessed = chip.generate_preprocessed_trace(&program).unwrap();
+        let (height, width) = (preprocessed.height(), chip.width());
+        let main = RowMajorMatrix::new(
+            (0..height * width)
+                .map(|_| BabyBear::from_canonical_u32(rng.gen_range(0..1 << 16)))
+                .collect::<Vec<_>>(),
+            width,
+        );
+        let random_elements: [EF; 2] = [rng.gen(), rng.gen()];
+        let perm = chip.generate_permutation_trace(Some(&preprocessed), &main, &random_elements);
+        let expected =
+            reference_permutation_trace(&chip, Some(&preprocessed), &main, &random_elements);
+        assert_eq!(perm.values, expected.values);
+
+        // The fingerprints depend on the instructions of the preprocessed trace.
+        let mut modified = preprocessed.clone();
+        modified.values[0] += BabyBear::one();
+        let modified_perm =
+            chip.generate_permutation_trace(Some(&modified), &main, &random_elements);
+        assert_ne!(perm.row_slice(0), modified_perm.row_slice(0));
+    }
 }
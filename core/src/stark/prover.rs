@@ -1,31 +1,39 @@
-use super::{quotient_values, RiscvStark};
-use super::{ProvingKey, RiscvChip};
+use super::{quotient_values, ProverError, RiscvAir, RiscvStark};
+use super::{Chip, ChipStats, ProverHooks, ProvingKey, StarkAir};
 use itertools::izip;
 #[cfg(not(feature = "perf"))]
 use p3_air::BaseAir;
-use p3_challenger::{CanObserve, FieldChallenger};
+use p3_challenger::{CanObserve, CanSample, FieldChallenger};
 use p3_commit::{Pcs, UnivariatePcs, UnivariatePcsWithLde};
 use p3_field::{AbstractExtensionField, AbstractField};
-use p3_field::{ExtensionField, PrimeField};
+use p3_field::{ExtensionField, Field, PrimeField};
 use p3_field::{PrimeField32, TwoAdicField};
 use p3_matrix::dense::RowMajorMatrix;
 use p3_matrix::MatrixRows;
 use p3_matrix::{Matrix, MatrixRowSlices};
 use p3_maybe_rayon::prelude::*;
-use p3_util::log2_ceil_usize;
 use p3_util::log2_strict_usize;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufWriter;
 use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Instant;
 
+use super::debug_constraints;
+use super::machine::{observe_preprocessed_commitments, observe_public_values};
 use super::util::decompose_and_flatten;
+use super::ConstraintFailure;
+use super::{check_constraints, check_interaction_balance, cumulative_sums_by_kind};
 use super::{types::*, StarkGenericConfig};
-use crate::air::MachineAir;
-use crate::runtime::ExecutionRecord;
+use crate::air::{MachineAir, PublicValues, TraceShape};
+use crate::lookup::InteractionKind;
+use crate::runtime::{ExecutionRecord, Program};
 use crate::utils::env;
-
-#[cfg(not(feature = "perf"))]
-use crate::stark::debug_constraints;
+use crate::utils::matrix::{to_csv, ColumnNames};
 
 fn chunk_vec<T>(mut vec: Vec<T>, chunk_size: usize) -> Vec<Vec<T>> {
     let mut result = Vec::new();
@@ -37,17 +45,27 @@ fn chunk_vec<T>(mut vec: Vec<T>, chunk_size: usize) -> Vec<Vec<T>> {
     result
 }
 
-pub trait Prover<SC: StarkGenericConfig> {
+pub trait Prover<SC: StarkGenericConfig, A = RiscvAir<<SC as StarkGenericConfig>::Val>> {
     fn prove_shards(
-        machine: &RiscvStark<SC>,
+        machine: &RiscvStark<SC, A>,
         pk: &ProvingKey<SC>,
         shards: Vec<ExecutionRecord>,
         challenger: &mut SC::Challenger,
+    ) -> Result<Proof<SC>, ProverError>;
+
+    /// Prove shards whose main traces were generated ahead of time, in the order of their
+    /// indices.
+    fn prove_traces(
+        machine: &RiscvStark<SC, A>,
+        pk: &ProvingKey<SC>,
+        shards: Vec<ShardTraces<SC::Val>>,
+        challenger: &mut SC::Challenger,
     ) -> Proof<SC>;
 }
 
-impl<SC> Prover<SC> for LocalProver<SC>
+impl<SC, A> Prover<SC, A> for LocalProver<SC>
 where
+    A: StarkAir<SC> + Send + Sync,
     SC::Val: Send + Sync,
     SC: StarkGenericConfig + Send + Sync,
     SC::Challenger: Clone,
@@ -57,25 +75,34 @@ where
     ShardMainData<SC>: Serialize + DeserializeOwned,
 {
     fn prove_shards(
-        machine: &RiscvStark<SC>,
+        machine: &RiscvStark<SC, A>,
         pk: &ProvingKey<SC>,
         shards: Vec<ExecutionRecord>,
         challenger: &mut SC::Challenger,
-    ) -> Proof<SC> {
+    ) -> Result<Proof<SC>, ProverError> {
         tracing::info!("Generating and commiting traces for each shard.");
         // Generate and commit the traces for each segment.
-        let (shard_commits, shard_data) = Self::commit_shards(machine, &shards);
+        let (shard_commits, shard_data) = Self::commit_shards(machine, &shards)?;
 
-        // Observe the challenges for each segment.
-        tracing::info_span!("observing all challenges").in_scope(|| {
-            shard_commits.into_iter().for_each(|commitment| {
-                challenger.observe(commitment);
-            });
+        let public_values = shards
+            .iter()
+            .map(PublicValues::from_record)
+            .collect::<Vec<_>>();
+        let digest = tracing::info_span!("observing all challenges").in_scope(|| {
+            observe_main_commitments::<SC>(
+                challenger,
+                &pk.preprocessed_commits,
+                shard_commits,
+                &public_values,
+            )
         });
 
         // Generate a proof for each segment. Note that we clone the challenger so we can observe
-        // identical global challenges across the segments.
-        let chunk_size = std::cmp::max(shards.len() / num_cpus::get(), 1);
+        // identical global challenges across the segments. The shards are split into a chunk per
+        // worker, each proven in order.
+        let num_shards = shards.len();
+        let hooks = machine.hooks();
+        let chunk_size = std::cmp::max(num_shards.div_ceil(machine.shard_workers()), 1);
         let config = machine.config();
         let reconstruct_commitments = env::reconstruct_commitments();
         let shard_data_chunks = chunk_vec(shard_data, chunk_size);
@@ -91,6 +118,11 @@ where
                     .enumerate()
                     .map(|(j, (data, shard))| {
                         let idx = i * chunk_size + j;
+                        if let Some(hooks) = hooks {
+                            hooks.shard_start(idx, num_shards);
+                        }
+                        let start = Instant::now();
+                        // The shards were checked when first committed to, if requested.
                         let data = if reconstruct_commitments {
                             Self::commit_main(config, machine, &shard, idx)
                         } else {
@@ -98,17 +130,161 @@ where
                                 .expect("failed to materialize shard main data")
                         };
                         let chips = machine.shard_chips(&shard).collect::<Vec<_>>();
-                        Self::prove_shard(config, pk, &chips, data, &mut challenger.clone())
+                        let shard_proof = Self::prove_shard(
+                            config,
+                            pk,
+                            &chips,
+                            data,
+                            &mut challenger.clone(),
+                            hooks,
+                        );
+                        if let Some(hooks) = hooks {
+                            hooks.shard_done(idx, num_shards, start.elapsed());
+                        }
+                        shard_proof
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .flatten()
+            .collect::<Vec<_>>();
+
+        Ok(Proof {
+            shard_proofs,
+            digest,
+            context: Vec::new(),
+            stdin: None,
+            hasher_params: machine.config().hasher_params_id(),
+            claimed_values: Vec::new(),
+            preprocessed_digests: pk.preprocessed_digests.clone(),
+        })
+    }
+
+    fn prove_traces(
+        machine: &RiscvStark<SC, A>,
+        pk: &ProvingKey<SC>,
+        shards: Vec<ShardTraces<SC::Val>>,
+        challenger: &mut SC::Challenger,
+    ) -> Proof<SC> {
+        let config = machine.config();
+        let num_shards = shards.len();
+        for (index, shard) in shards.iter().enumerate() {
+            if shard.index != index {
+                panic!("the traces of shard {} are out of order", shard.index);
+            }
+            if let Err(err) = machine.chips_by_ids(&shard.chip_ids, index, num_shards) {
+                panic!(
+                    "the traces of shard {} do not match the chips of the machine: {:?}",
+                    shard.index, err
+                );
+            }
+        }
+
+        // The traces are all in memory already, so their commitments are kept there too.
+        let shard_data = tracing::info_span!("commit main for all shards").in_scope(|| {
+            shards
+                .into_par_iter()
+                .map(|shard| Self::commit_traces(config, shard))
+                .collect::<Vec<_>>()
+        });
+        let commitments = shard_data
+            .iter()
+            .map(|data| data.main_commit.clone())
+            .collect::<Vec<_>>();
+        let public_values = shard_data
+            .iter()
+            .map(|data| data.public_values)
+            .collect::<Vec<_>>();
+        let digest = tracing::info_span!("observing all challenges").in_scope(|| {
+            observe_main_commitments::<SC>(
+                challenger,
+                &pk.preprocessed_commits,
+                commitments,
+                &public_values,
+            )
+        });
+
+        let hooks = machine.hooks();
+        let chunk_size = std::cmp::max(num_shards.div_ceil(machine.shard_workers()), 1);
+        let shard_proofs = chunk_vec(shard_data, chunk_size)
+            .into_par_iter()
+            .enumerate()
+            .map(|(i, datas)| {
+                datas
+                    .into_iter()
+                    .enumerate()
+                    .map(|(j, data)| {
+                        let idx = i * chunk_size + j;
+                        if let Some(hooks) = hooks {
+                            hooks.shard_start(idx, num_shards);
+                        }
+                        let start = Instant::now();
+                        let chips = machine
+                            .chips_by_ids(&data.chip_ids, idx, num_shards)
+                            .expect("the chip ids were checked");
+                        let shard_proof = Self::prove_shard(
+                            config,
+                            pk,
+                            &chips,
+                            data,
+                            &mut challenger.clone(),
+                            hooks,
+                        );
+                        if let Some(hooks) = hooks {
+                            hooks.shard_done(idx, num_shards, start.elapsed());
+                        }
+                        shard_proof
                     })
                     .collect::<Vec<_>>()
             })
             .flatten()
             .collect::<Vec<_>>();
 
-        Proof { shard_proofs }
+        Proof {
+            shard_proofs,
+            digest,
+            context: Vec::new(),
+            stdin: None,
+            hasher_params: config.hasher_params_id(),
+            claimed_values: Vec::new(),
+            preprocessed_digests: pk.preprocessed_digests.clone(),
+        }
     }
 }
 
+/// Add the cumulative sums of each interaction kind to the running ones.
+pub(crate) fn add_sums<EF: Field>(
+    sums: &mut BTreeMap<InteractionKind, EF>,
+    other: BTreeMap<InteractionKind, EF>,
+) {
+    for (kind, sum) in other {
+        *sums.entry(kind).or_insert_with(EF::zero) += sum;
+    }
+}
+
+/// Observe the commitments to the preprocessed traces of the proving key, then the number of shards
+/// and the challenges for each shard, binding each commitment and the public values of its shard
+/// to its shard index. The digest of all the commitments is then sampled and absorbed, so that
+/// every shard proof depends on the full set of shards and on the program.
+pub(crate) fn observe_main_commitments<SC: StarkGenericConfig>(
+    challenger: &mut SC::Challenger,
+    preprocessed_commits: &BTreeMap<String, Com<SC>>,
+    commitments: Vec<Com<SC>>,
+    public_values: &[PublicValues<u32>],
+) -> [SC::Val; PROOF_DIGEST_SIZE] {
+    observe_preprocessed_commitments::<SC>(challenger, preprocessed_commits);
+    challenger.observe(SC::Val::from_canonical_usize(commitments.len()));
+    for (index, (commitment, public_values)) in
+        commitments.into_iter().zip(public_values).enumerate()
+    {
+        challenger.observe(SC::Val::from_canonical_usize(index));
+        challenger.observe(commitment);
+        observe_public_values::<SC>(challenger, public_values);
+    }
+    let digest: [SC::Val; PROOF_DIGEST_SIZE] = core::array::from_fn(|_| challenger.sample());
+    challenger.observe_slice(&digest);
+    digest
+}
+
 pub struct LocalProver<SC>(PhantomData<SC>);
 
 impl<SC> LocalProver<SC>
@@ -120,49 +296,264 @@ where
     PcsProverData<SC>: Send + Sync,
     ShardMainData<SC>: Serialize + DeserializeOwned,
 {
-    fn commit_main(
+    /// Commit to the main traces of a single shard, for proving it apart from the others,
+    /// checking the shard first if the machine self checks.
+    pub fn commit_shard<A: StarkAir<SC> + Send + Sync>(
+        machine: &RiscvStark<SC, A>,
+        shard: &ExecutionRecord,
+        index: usize,
+    ) -> Result<ShardMainData<SC>, ProverError>
+    where
+        SC::Val: PrimeField32,
+    {
+        let (data, _) = Self::commit_main_with_sums(
+            machine.config(),
+            machine,
+            shard,
+            index,
+            machine.self_check(),
+        )?;
+        Ok(data)
+    }
+
+    /// Prove a single shard from the commitment to its main traces, with a challenger which has
+    /// observed the commitments of every shard of the proof, like the ones `prove_shards` proves
+    /// each shard with.
+    pub fn prove_committed_shard<A: StarkAir<SC> + Send + Sync>(
+        machine: &RiscvStark<SC, A>,
+        pk: &ProvingKey<SC>,
+        shard: &ExecutionRecord,
+        shard_data: ShardMainData<SC>,
+        challenger: &mut SC::Challenger,
+    ) -> ShardProof<SC>
+    where
+        SC::Val: PrimeField32,
+    {
+        let chips = machine.shard_chips(shard).collect::<Vec<_>>();
+        Self::prove_shard(
+            machine.config(),
+            pk,
+            &chips,
+            shard_data,
+            challenger,
+            machine.hooks(),
+        )
+    }
+
+    /// Commit to the main traces of a shard without checking it.
+    fn commit_main<A: StarkAir<SC> + Send + Sync>(
         config: &SC,
-        machine: &RiscvStark<SC>,
+        machine: &RiscvStark<SC, A>,
         shard: &ExecutionRecord,
         index: usize,
     ) -> ShardMainData<SC>
+    where
+        SC::Val: PrimeField32,
+    {
+        let (data, _) = Self::commit_main_with_sums(config, machine, shard, index, false)
+            .expect("committing without a self check cannot fail");
+        data
+    }
+
+    /// Commit to the main traces of a shard, returning with the commitment the cumulative sum of
+    /// each interaction kind over the chips of the shard when self checking, none otherwise.
+    fn commit_main_with_sums<A: StarkAir<SC> + Send + Sync>(
+        config: &SC,
+        machine: &RiscvStark<SC, A>,
+        shard: &ExecutionRecord,
+        index: usize,
+        self_check: bool,
+    ) -> Result<(ShardMainData<SC>, BTreeMap<InteractionKind, SC::Challenge>), ProverError>
     where
         SC::Val: PrimeField32,
     {
         // Filter the chips based on what is used.
         let filtered_chips = machine.shard_chips(shard).collect::<Vec<_>>();
 
+        // Check that the events are well-formed before generating the traces, if requested.
+        if self_check {
+            for chip in filtered_chips.iter() {
+                chip.validate(shard)?;
+            }
+        }
+
         // For each chip, generate the trace.
-        let traces = filtered_chips
-            .par_iter()
-            .map(|chip| chip.generate_trace(shard, &mut ExecutionRecord::default()))
-            .collect::<Vec<_>>();
+        let shard_traces = machine.generate_shard_traces(shard, index);
 
-        // Commit to the batch of traces.
-        let (main_commit, main_data) = config.pcs().commit_batches(traces.to_vec());
+        // Dump the trace of the requested chip, if any.
+        if let Some((chip_name, path)) = env::dump_trace_csv() {
+            for (chip_id, trace) in shard_traces.chip_ids.iter().zip(shard_traces.traces.iter()) {
+                if *chip_id == chip_name {
+                    Self::dump_trace(trace, &path, index);
+                }
+            }
+        }
 
-        // Get the filtered chip ids.
-        let chip_ids = filtered_chips
-            .iter()
-            .map(|chip| chip.name())
-            .collect::<Vec<_>>();
+        // Check the constraints of the traces before committing to them, if requested.
+        let mut sums = BTreeMap::new();
+        if self_check {
+            sums = tracing::info_span!("self check", shard = index).in_scope(|| {
+                Self::self_check(
+                    &filtered_chips,
+                    &shard.program,
+                    &shard_traces.traces,
+                    &shard_traces.public_values.to_field_elements(),
+                )
+            })?;
+        }
+
+        Ok((Self::commit_traces(config, shard_traces), sums))
+    }
+
+    /// Commit to the batch of the main traces of a shard.
+    fn commit_traces(config: &SC, shard_traces: ShardTraces<SC::Val>) -> ShardMainData<SC> {
+        let ShardTraces {
+            index,
+            public_values,
+            chip_ids,
+            shapes,
+            traces,
+            trace_times,
+        } = shard_traces;
+        let start = Instant::now();
+        let (main_commit, main_data) = config.pcs().commit_batches(traces.to_vec());
+        let commit_time = start.elapsed();
 
         ShardMainData {
             traces,
+            shapes,
             main_commit,
             main_data,
             chip_ids,
             index,
+            public_values,
+            trace_times,
+            commit_time,
         }
     }
 
-    /// Prove the program for the given shard and given a commitment to the main data.
-    fn prove_shard(
+    /// Write the trace of a shard as CSV, to `path` with the index of the shard inserted before the
+    /// extension, e.g. `cpu.3.csv` for `cpu.csv`.
+    fn dump_trace(trace: &RowMajorMatrix<SC::Val>, path: &str, index: usize)
+    where
+        SC::Val: PrimeField32,
+    {
+        let path = Path::new(path);
+        let path = match path.extension() {
+            Some(extension) => {
+                path.with_extension(format!("{}.{}", index, extension.to_string_lossy()))
+            }
+            None => path.with_extension(index.to_string()),
+        };
+        let file = File::create(&path).expect("failed to create the trace file");
+        let names = ColumnNames::indexed(trace.width());
+        to_csv(trace, &names, BufWriter::new(file)).expect("failed to write the trace");
+        tracing::info!("dumped the trace of shard {} to {}", index, path.display());
+    }
+
+    /// Check the constraints of each chip on its main trace and on its preprocessed trace for the
+    /// program, returning the cumulative sum of each interaction kind over the chips, or the first
+    /// failure found.
+    ///
+    /// The permutation challenges are fixed, since this check only serves to catch bugs early.
+    pub(crate) fn self_check<A: StarkAir<SC> + Send + Sync>(
+        chips: &[&Chip<SC::Val, A>],
+        program: &Program,
+        traces: &[RowMajorMatrix<SC::Val>],
+        public_values: &[SC::Val],
+    ) -> Result<BTreeMap<InteractionKind, SC::Challenge>, ConstraintFailure>
+    where
+        SC::Val: PrimeField32,
+    {
+        let permutation_challenges = [
+            SC::Challenge::from_base(SC::Val::from_canonical_u32(0x12345678)),
+            SC::Challenge::from_base(SC::Val::from_canonical_u32(0x0abcdef1)),
+        ];
+        let results = chips
+            .par_iter()
+            .zip(traces.par_iter())
+            .map(|(chip, main_trace)| {
+                let preprocessed = chip.generate_preprocessed_trace(program);
+                let perm_trace = chip.generate_permutation_trace(
+                    preprocessed.as_ref(),
+                    main_trace,
+                    &permutation_challenges,
+                );
+                check_constraints::<SC>(
+                    chip,
+                    preprocessed.as_ref(),
+                    main_trace,
+                    &perm_trace,
+                    &permutation_challenges,
+                    public_values,
+                )
+                .map(|_| {
+                    cumulative_sums_by_kind(chip, preprocessed.as_ref(), main_trace, &perm_trace)
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut sums = BTreeMap::new();
+        for result in results {
+            add_sums(&mut sums, result?);
+        }
+        Ok(sums)
+    }
+
+    /// Evaluate the constraints of each chip on its traces, logging each constraint which is not
+    /// satisfied with the row it fails at, and panicking if there are any.
+    fn report_violations<A: StarkAir<SC> + Send + Sync>(
+        chips: &[&Chip<SC::Val, A>],
+        preprocessed_traces: &[Option<&RowMajorMatrix<SC::Val>>],
+        traces: &[RowMajorMatrix<SC::Val>],
+        permutation_traces: &[RowMajorMatrix<SC::Challenge>],
+        permutation_challenges: &[SC::Challenge],
+        public_values: &[SC::Val],
+    ) where
+        SC::Val: PrimeField32,
+    {
+        let violations = chips
+            .par_iter()
+            .zip(preprocessed_traces.par_iter())
+            .zip(traces.par_iter().zip(permutation_traces.par_iter()))
+            .map(|((chip, preprocessed), (main_trace, perm_trace))| {
+                debug_constraints::<SC>(
+                    chip,
+                    *preprocessed,
+                    main_trace,
+                    perm_trace,
+                    permutation_challenges,
+                    public_values,
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut num_violations = 0;
+        for (chip, (main_trace, violations)) in chips.iter().zip(traces.iter().zip(violations)) {
+            for violation in violations {
+                let row = main_trace.row_slice(violation.row);
+                let row = chip.describe_row(row).unwrap_or_else(|| {
+                    format!(
+                        "{:?}",
+                        row.iter().map(|x| x.as_canonical_u32()).collect::<Vec<_>>()
+                    )
+                });
+                tracing::error!("{}\n{}", violation, row);
+                num_violations += 1;
+            }
+        }
+        if num_violations > 0 {
+            panic!("{} constraints are not satisfied", num_violations);
+        }
+    }
+
+    /// Prove the program for the given shard and given a commitment to the main data, reporting
+    /// the statistics of each chip to the hooks.
+    fn prove_shard<A: StarkAir<SC> + Send + Sync>(
         config: &SC,
-        _pk: &ProvingKey<SC>,
-        chips: &[&RiscvChip<SC>],
+        pk: &ProvingKey<SC>,
+        chips: &[&Chip<SC::Val, A>],
         shard_data: ShardMainData<SC>,
         challenger: &mut SC::Challenger,
+        hooks: Option<&dyn ProverHooks>,
     ) -> ShardProof<SC>
     where
         SC::Val: PrimeField32,
@@ -171,14 +562,26 @@ where
     {
         // Get the traces.
         let traces = &shard_data.traces;
+        let public_values = shard_data.public_values.to_field_elements::<SC::Val>();
 
         let log_degrees = traces
             .iter()
             .map(|trace| log2_strict_usize(trace.height()))
             .collect::<Vec<_>>();
-        // TODO: read dynamically from Chip.
-        let max_constraint_degree = 3;
-        let log_quotient_degree = log2_ceil_usize(max_constraint_degree - 1);
+        let log_quotient_degrees = chips
+            .iter()
+            .map(|chip| chip.log_quotient_degree())
+            .collect::<Vec<_>>();
+        let log_blowup = config.pcs().log_blowup();
+        for (chip, log_quotient_degree) in chips.iter().zip(log_quotient_degrees.iter()) {
+            assert!(
+                *log_quotient_degree <= log_blowup,
+                "the constraints of chip {} have degree {}, too high for a blowup of 2^{}",
+                chip.name(),
+                chip.max_constraint_degree(),
+                log_blowup
+            );
+        }
         let g_subgroups = log_degrees
             .iter()
             .map(|log_deg| SC::Val::two_adic_generator(*log_deg))
@@ -190,16 +593,39 @@ where
             permutation_challenges.push(challenger.sample_ext_element());
         }
 
+        // The preprocessed traces of the chips which have one, committed to by the proving key.
+        let preprocessed_traces = chips
+            .iter()
+            .map(|chip| pk.preprocessed_traces.get(&chip.name()))
+            .collect::<Vec<_>>();
+        for (chip, (preprocessed, main_trace)) in chips
+            .iter()
+            .zip(preprocessed_traces.iter().zip(traces.iter()))
+        {
+            if let Some(preprocessed) = preprocessed {
+                assert_eq!(
+                    preprocessed.height(),
+                    main_trace.height(),
+                    "the main trace of chip {} does not have the height of its preprocessed trace",
+                    chip.name()
+                );
+            }
+        }
+
         // Generate the permutation traces.
         let mut permutation_traces = Vec::with_capacity(chips.len());
         let mut cumulative_sums = Vec::with_capacity(chips.len());
         tracing::info_span!("generate permutation traces").in_scope(|| {
             chips
                 .par_iter()
+                .zip(preprocessed_traces.par_iter())
                 .zip(traces.par_iter())
-                .map(|(chip, main_trace)| {
-                    let perm_trace =
-                        chip.generate_permutation_trace(&None, main_trace, &permutation_challenges);
+                .map(|((chip, preprocessed), main_trace)| {
+                    let perm_trace = chip.generate_permutation_trace(
+                        *preprocessed,
+                        main_trace,
+                        &permutation_challenges,
+                    );
                     let cumulative_sum = perm_trace
                         .row_slice(main_trace.height() - 1)
                         .last()
@@ -210,17 +636,33 @@ where
                 .unzip_into_vecs(&mut permutation_traces, &mut cumulative_sums);
         });
 
+        // Check that the table-specific constraints are correct for each chip.
+        if cfg!(not(feature = "perf")) || env::debug_constraints() {
+            tracing::info_span!("debug constraints").in_scope(|| {
+                Self::report_violations(
+                    chips,
+                    &preprocessed_traces,
+                    traces,
+                    &permutation_traces,
+                    &permutation_challenges,
+                    &public_values,
+                )
+            });
+        }
+
         // Compute some statistics.
         for i in 0..chips.len() {
-            let trace_width = traces[i].width();
+            let shape = &shard_data.shapes[i];
+            let trace_width = shape.width;
             let permutation_width = permutation_traces[i].width();
             let total_width = trace_width + permutation_width;
             tracing::debug!(
-                "{:<11} | Cols = {:<5} | Rows = {:<5} | Cells = {:<10} | Main Cols = {:.2}% | Perm Cols = {:.2}%",
+                "{:<11} | Cols = {:<5} | Rows = {:<5} | Real Rows = {:<5} | Cells = {:<10} | Main Cols = {:.2}% | Perm Cols = {:.2}%",
                 chips[i].name(),
                 total_width,
-                traces[i].height(),
-                total_width * traces[i].height(),
+                shape.padded_rows,
+                shape.real_rows,
+                total_width * shape.padded_rows,
                 (100f32 * trace_width as f32) / total_width as f32,
                 (100f32 * permutation_width as f32) / total_width as f32,
             );
@@ -240,13 +682,17 @@ where
         challenger.observe(permutation_commit.clone());
 
         // For each chip, compute the quotient polynomial.
-        let log_stride_for_quotient = config.pcs().log_blowup() - log_quotient_degree;
+        let log_strides_for_quotient = log_quotient_degrees
+            .iter()
+            .map(|log_quotient_degree| log_blowup - log_quotient_degree)
+            .collect::<Vec<_>>();
         let main_ldes = tracing::info_span!("get main ldes").in_scope(|| {
             config
                 .pcs()
                 .get_ldes(&shard_data.main_data)
                 .into_iter()
-                .map(|lde| lde.vertically_strided(1 << log_stride_for_quotient, 0))
+                .zip(log_strides_for_quotient.iter())
+                .map(|(lde, log_stride)| lde.vertically_strided(1 << log_stride, 0))
                 .collect::<Vec<_>>()
         });
         let permutation_ldes = tracing::info_span!("get perm ldes").in_scope(|| {
@@ -254,39 +700,78 @@ where
                 .pcs()
                 .get_ldes(&permutation_data)
                 .into_iter()
-                .map(|lde| lde.vertically_strided(1 << log_stride_for_quotient, 0))
+                .zip(log_strides_for_quotient.iter())
+                .map(|(lde, log_stride)| lde.vertically_strided(1 << log_stride, 0))
                 .collect::<Vec<_>>()
         });
-        let alpha: SC::Challenge = challenger.sample_ext_element::<SC::Challenge>();
-
-        // Compute the quotient values.
-        let quotient_values = tracing::info_span!("compute quotient values").in_scope(|| {
-            (0..chips.len())
-                .into_par_iter()
-                .map(|i| {
-                    quotient_values(
-                        config,
-                        chips[i],
-                        cumulative_sums[i],
-                        log_degrees[i],
-                        &main_ldes[i],
-                        &permutation_ldes[i],
-                        &permutation_challenges,
-                        alpha,
-                    )
+        let preprocessed_ldes = tracing::info_span!("get preprocessed ldes").in_scope(|| {
+            chips
+                .iter()
+                .zip(log_strides_for_quotient.iter())
+                .map(|(chip, log_stride)| {
+                    let data = pk.preprocessed_data.get(&chip.name())?;
+                    let lde = config.pcs().get_ldes(&**data).pop().unwrap();
+                    Some(lde.vertically_strided(1 << log_stride, 0))
                 })
                 .collect::<Vec<_>>()
         });
+        let alpha: SC::Challenge = challenger.sample_ext_element::<SC::Challenge>();
+
+        // Compute the quotient values.
+        let (quotient_values, quotient_times): (Vec<_>, Vec<_>) =
+            tracing::info_span!("compute quotient values").in_scope(|| {
+                (0..chips.len())
+                    .into_par_iter()
+                    .map(|i| {
+                        let start = Instant::now();
+                        let values = quotient_values(
+                            config,
+                            chips[i],
+                            cumulative_sums[i],
+                            log_degrees[i],
+                            preprocessed_ldes[i].as_ref(),
+                            &main_ldes[i],
+                            &permutation_ldes[i],
+                            &permutation_challenges,
+                            &public_values,
+                            alpha,
+                        );
+                        (values, start.elapsed())
+                    })
+                    .unzip()
+            });
+
+        // Report the statistics of each chip, sharing the time of the batch commitment to the
+        // main traces by their cells.
+        if let Some(hooks) = hooks {
+            let cells = |shape: &TraceShape| shape.padded_rows * shape.width;
+            let total_cells = shard_data.shapes.iter().map(cells).sum::<usize>().max(1);
+            for (i, chip) in chips.iter().enumerate() {
+                let shape = &shard_data.shapes[i];
+                hooks.chip_proven(&ChipStats {
+                    name: chip.name(),
+                    shard: shard_data.index,
+                    real_rows: shape.real_rows,
+                    padded_rows: shape.padded_rows,
+                    trace_gen: shard_data.trace_times[i],
+                    commit: shard_data
+                        .commit_time
+                        .mul_f64(cells(shape) as f64 / total_cells as f64),
+                    quotient: quotient_times[i],
+                });
+            }
+        }
 
         // Compute the quotient chunks.
         let quotient_chunks = tracing::info_span!("decompose and flatten").in_scope(|| {
             quotient_values
                 .into_iter()
-                .map(|values| {
+                .zip(log_quotient_degrees.iter())
+                .map(|(values, log_quotient_degree)| {
                     decompose_and_flatten::<SC>(
                         values,
                         SC::Challenge::from_base(config.pcs().coset_shift()),
-                        log_quotient_degree,
+                        *log_quotient_degree,
                     )
                 })
                 .collect::<Vec<_>>()
@@ -295,17 +780,20 @@ where
         // Check the shapes of the quotient chunks.
         #[cfg(not(feature = "perf"))]
         for (i, mat) in quotient_chunks.iter().enumerate() {
-            assert_eq!(mat.width(), SC::Challenge::D << log_quotient_degree);
+            assert_eq!(mat.width(), SC::Challenge::D << log_quotient_degrees[i]);
             assert_eq!(mat.height(), traces[i].height());
         }
 
-        let num_quotient_chunks = quotient_chunks.len();
         let coset_shifts = tracing::info_span!("coset shift").in_scope(|| {
-            let shift = config
-                .pcs()
-                .coset_shift()
-                .exp_power_of_2(log_quotient_degree);
-            vec![shift; chips.len()]
+            log_quotient_degrees
+                .iter()
+                .map(|log_quotient_degree| {
+                    config
+                        .pcs()
+                        .coset_shift()
+                        .exp_power_of_2(*log_quotient_degree)
+                })
+                .collect::<Vec<_>>()
         });
         let (quotient_commit, quotient_data) = tracing::info_span!("commit shifted batches")
             .in_scope(|| {
@@ -328,26 +816,51 @@ where
                     .collect::<Vec<_>>()
             });
 
-        let zeta_quot_pow = zeta.exp_power_of_2(log_quotient_degree);
-        let quotient_opening_points = (0..num_quotient_chunks)
-            .map(|_| vec![zeta_quot_pow])
+        let quotient_opening_points = log_quotient_degrees
+            .iter()
+            .map(|log_quotient_degree| vec![zeta.exp_power_of_2(*log_quotient_degree)])
+            .collect::<Vec<_>>();
+
+        // The preprocessed traces committed to by the proving key are opened at the points of the
+        // main traces, each in a batch of its own.
+        let preprocessed_data = chips
+            .iter()
+            .enumerate()
+            .filter_map(|(i, chip)| {
+                let data = pk.preprocessed_data.get(&chip.name())?;
+                Some((i, data, vec![trace_opening_points[i].clone()]))
+            })
             .collect::<Vec<_>>();
 
         let (openings, opening_proof) = tracing::info_span!("open multi batches").in_scope(|| {
-            config.pcs().open_multi_batches(
-                &[
-                    (&shard_data.main_data, &trace_opening_points),
-                    (&permutation_data, &trace_opening_points),
-                    (&quotient_data, &quotient_opening_points),
-                ],
-                challenger,
-            )
+            let mut batches = vec![
+                (&shard_data.main_data, trace_opening_points.as_slice()),
+                (&permutation_data, trace_opening_points.as_slice()),
+                (&quotient_data, quotient_opening_points.as_slice()),
+            ];
+            for (_, data, points) in preprocessed_data.iter() {
+                batches.push((&***data, points.as_slice()));
+            }
+            config.pcs().open_multi_batches(&batches, challenger)
         });
 
         #[cfg(feature = "perf")]
         {
             // Collect the opened values for each chip.
-            let [main_values, permutation_values, quotient_values] = openings.try_into().unwrap();
+            let mut openings = openings.into_iter();
+            let main_values = openings.next().unwrap();
+            let permutation_values = openings.next().unwrap();
+            let quotient_values = openings.next().unwrap();
+            let mut preprocessed_opened_values = (0..chips.len())
+                .map(|_| AirOpenedValues {
+                    local: vec![],
+                    next: vec![],
+                })
+                .collect::<Vec<_>>();
+            for ((i, _, _), mut values) in preprocessed_data.iter().zip(openings) {
+                let [local, next] = values.pop().unwrap().try_into().unwrap();
+                preprocessed_opened_values[*i] = AirOpenedValues { local, next };
+            }
             let main_opened_values = main_values
                 .into_iter()
                 .map(|op| {
@@ -368,6 +881,7 @@ where
                 .collect::<Vec<_>>();
 
             let opened_values = izip!(
+                preprocessed_opened_values,
                 main_opened_values,
                 permutation_opened_values,
                 quotient_opened_values,
@@ -375,16 +889,15 @@ where
                 log_degrees
             )
             .map(
-                |(main, permutation, quotient, cumulative_sum, log_degree)| ChipOpenedValues {
-                    preprocessed: AirOpenedValues {
-                        local: vec![],
-                        next: vec![],
-                    },
-                    main,
-                    permutation,
-                    quotient,
-                    cumulative_sum,
-                    log_degree,
+                |(preprocessed, main, permutation, quotient, cumulative_sum, log_degree)| {
+                    ChipOpenedValues {
+                        preprocessed,
+                        main,
+                        permutation,
+                        quotient,
+                        cumulative_sum,
+                        log_degree,
+                    }
                 },
             )
             .collect::<Vec<_>>();
@@ -401,42 +914,34 @@ where
                 },
                 opening_proof,
                 chip_ids: chips.iter().map(|chip| chip.name()).collect::<Vec<_>>(),
+                public_values: shard_data.public_values,
             }
         }
 
-        // Check that the table-specific constraints are correct for each chip.
-        #[cfg(not(feature = "perf"))]
-        tracing::info_span!("debug constraints").in_scope(|| {
-            for i in 0..chips.len() {
-                debug_constraints(
-                    &chips[i],
-                    None,
-                    &traces[i],
-                    &permutation_traces[i],
-                    &permutation_challenges,
-                );
-            }
-        });
-
         #[cfg(not(feature = "perf"))]
         return ShardProof {
             main_commit: shard_data.main_commit.clone(),
             traces,
             permutation_traces,
             chip_ids: chips.iter().map(|chip| chip.name()).collect::<Vec<_>>(),
+            public_values: shard_data.public_values,
         };
     }
 
-    fn commit_shards<F, EF>(
-        machine: &RiscvStark<SC>,
+    fn commit_shards<F, EF, A>(
+        machine: &RiscvStark<SC, A>,
         shards: &[ExecutionRecord],
-    ) -> (
-        Vec<<SC::Pcs as Pcs<SC::Val, RowMajorMatrix<SC::Val>>>::Commitment>,
-        Vec<ShardMainDataWrapper<SC>>,
-    )
+    ) -> Result<
+        (
+            Vec<<SC::Pcs as Pcs<SC::Val, RowMajorMatrix<SC::Val>>>::Commitment>,
+            Vec<ShardMainDataWrapper<SC>>,
+        ),
+        ProverError,
+    >
     where
         F: PrimeField + TwoAdicField + PrimeField32,
         EF: ExtensionField<F>,
+        A: StarkAir<SC> + Send + Sync,
         SC: StarkGenericConfig<Val = F, Challenge = EF> + Send + Sync,
         SC::Challenger: Clone,
         <SC::Pcs as Pcs<SC::Val, RowMajorMatrix<SC::Val>>>::Commitment: Send + Sync,
@@ -450,6 +955,8 @@ where
         // keeping all the shards in memory.
         let save_disk_threshold = env::save_disk_threshold();
         let reconstruct_commitments = env::reconstruct_commitments();
+        let self_check = machine.self_check();
+        let interaction_sums = Mutex::new(BTreeMap::new());
         let (commitments, shard_main_data): (Vec<_>, Vec<_>) =
             tracing::info_span!("commit main for all shards").in_scope(|| {
                 let chunk_size = std::cmp::max(shards.len() / num_cpus::get(), 1);
@@ -462,8 +969,14 @@ where
                             .enumerate()
                             .map(|(j, shard)| {
                                 let index = i * chunk_size + j;
-                                let data = tracing::info_span!("shard commit main", shard = index)
-                                    .in_scope(|| Self::commit_main(config, machine, shard, index));
+                                let (data, sums) =
+                                    tracing::info_span!("shard commit main", shard = index)
+                                        .in_scope(|| {
+                                            Self::commit_main_with_sums(
+                                                config, machine, shard, index, self_check,
+                                            )
+                                        })?;
+                                add_sums(&mut interaction_sums.lock().unwrap(), sums);
                                 let commitment = data.main_commit.clone();
                                 let file = tempfile::tempfile().unwrap();
                                 let data = if reconstruct_commitments {
@@ -475,15 +988,19 @@ where
                                 } else {
                                     data.to_in_memory()
                                 };
-                                (commitment, data)
+                                Ok((commitment, data))
                             })
-                            .collect::<Vec<_>>()
+                            .collect::<Result<Vec<_>, ProverError>>()
                     })
-                    .flatten()
-                    .collect::<Vec<_>>()
-                    .into_iter()
-                    .unzip()
-            });
+                    .collect::<Result<Vec<_>, ProverError>>()
+                    .map(|batches| batches.into_iter().flatten().unzip())
+            })?;
+
+        // The interactions of each kind must balance over all the shards, before proving any.
+        if self_check {
+            let sums = interaction_sums.into_inner().unwrap();
+            check_interaction_balance(&sums)?;
+        }
 
         #[cfg(not(feature = "perf"))]
         {
@@ -502,6 +1019,6 @@ where
             }
         }
 
-        (commitments, shard_main_data)
+        Ok((commitments, shard_main_data))
     }
 }
@@ -21,9 +21,11 @@ pub fn quotient_values<SC, A, MainLde, PermLde>(
     chip: &Chip<SC::Val, A>,
     cumulative_sum: SC::Challenge,
     degree_bits: usize,
+    preprocessed_lde: Option<&MainLde>,
     main_lde: &MainLde,
     permutation_lde: &PermLde,
     perm_challenges: &[SC::Challenge],
+    public_values: &[SC::Val],
     alpha: SC::Challenge,
 ) -> Vec<SC::Challenge>
 where
@@ -67,6 +69,24 @@ where
             let is_first_row = *PackedVal::<SC>::from_slice(&lagrange_first_evals[i_range.clone()]);
             let is_last_row = *PackedVal::<SC>::from_slice(&lagrange_last_evals[i_range]);
 
+            let preprocessed_width = preprocessed_lde.map_or(0, |lde| lde.width());
+            let preprocessed_local: Vec<_> = (0..preprocessed_width)
+                .map(|col| {
+                    PackedVal::<SC>::from_fn(|offset| {
+                        let row = wrap(i_local_start + offset);
+                        preprocessed_lde.unwrap().get(row, col)
+                    })
+                })
+                .collect();
+            let preprocessed_next: Vec<_> = (0..preprocessed_width)
+                .map(|col| {
+                    PackedVal::<SC>::from_fn(|offset| {
+                        let row = wrap(i_next_start + offset);
+                        preprocessed_lde.unwrap().get(row, col)
+                    })
+                })
+                .collect();
+
             let local: Vec<_> = (0..main_lde.width())
                 .map(|col| {
                     PackedVal::<SC>::from_fn(|offset| {
@@ -111,8 +131,8 @@ where
             let accumulator = PackedChallenge::<SC>::zero();
             let mut folder = ProverConstraintFolder {
                 preprocessed: TwoRowMatrixView {
-                    local: &[],
-                    next: &[],
+                    local: &preprocessed_local,
+                    next: &preprocessed_next,
                 },
                 main: TwoRowMatrixView {
                     local: &local,
@@ -123,6 +143,7 @@ where
                     next: &perm_next,
                 },
                 perm_challenges,
+                public_values,
                 cumulative_sum,
                 is_first_row,
                 is_last_row,
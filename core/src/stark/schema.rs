@@ -0,0 +1,259 @@
+//! The structural constants of a machine, for verifiers written outside of Rust.
+
+use p3_air::BaseAir;
+use p3_field::{AbstractExtensionField, Field};
+use serde::{Deserialize, Serialize};
+
+use super::{RiscvStark, StarkAir, StarkGenericConfig};
+use crate::air::{MachineAir, CANONICAL_PUBLIC_VALUES_FIELDS};
+use crate::lookup::Interaction;
+
+/// The version of the layout of `MachineSchema`, bumped whenever a field is added or changes
+/// meaning.
+pub const MACHINE_SCHEMA_VERSION: u32 = 1;
+
+/// The rotations at which the main and permutation traces are opened: the current row and the next.
+pub const TRACE_OPENING_ROTATIONS: [usize; 2] = [0, 1];
+
+/// The shape of an interaction: its kind and how many values it sends or receives.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InteractionSchema {
+    pub kind: String,
+    pub num_values: usize,
+}
+
+/// The structural constants of a chip.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChipSchema {
+    pub name: String,
+    pub main_width: usize,
+    pub preprocessed_width: usize,
+    /// The width of the permutation trace in base field columns.
+    pub permutation_width: usize,
+    pub constraint_degree: usize,
+    pub log_quotient_degree: usize,
+    pub sends: Vec<InteractionSchema>,
+    pub receives: Vec<InteractionSchema>,
+}
+
+/// A public value of a shard and its offset in the canonical encoding.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicValueSchema {
+    pub name: String,
+    pub offset: usize,
+}
+
+/// The structural constants of a machine, which a verifier needs besides the verifying key.
+///
+/// The verifying key embeds the digest of the schema of the machine it was set up with, so that a
+/// verifier generated from a schema detects a machine whose shape drifted from it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MachineSchema {
+    pub version: u32,
+    /// The degree of the extension field the challenges are sampled from.
+    pub extension_degree: usize,
+    pub trace_opening_rotations: Vec<usize>,
+    /// The chips of the machine, sorted by name.
+    pub chips: Vec<ChipSchema>,
+    pub public_values: Vec<PublicValueSchema>,
+}
+
+impl MachineSchema {
+    /// The schema as pretty-printed JSON, the form in which it is dumped and digested.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// The blake3 digest of the JSON of the schema.
+    pub fn digest(&self) -> [u8; 32] {
+        blake3::hash(self.to_json().as_bytes()).into()
+    }
+
+    /// The widths and constraint degree of every chip as TOML, one table per chip, which is what
+    /// each chip costs the prover.
+    pub fn to_shape_toml(&self) -> String {
+        let mut toml = String::new();
+        for chip in self.chips.iter() {
+            toml.push_str(&format!(
+                "[chips.\"{}\"]\nmain_width = {}\npreprocessed_width = {}\npermutation_width = {}\n\
+                 constraint_degree = {}\n\n",
+                chip.name,
+                chip.main_width,
+                chip.preprocessed_width,
+                chip.permutation_width,
+                chip.constraint_degree
+            ));
+        }
+        toml
+    }
+}
+
+fn interaction_schemas<F: Field>(interactions: &[Interaction<F>]) -> Vec<InteractionSchema> {
+    interactions
+        .iter()
+        .map(|interaction| InteractionSchema {
+            kind: format!("{:?}", interaction.kind),
+            num_values: interaction.values.len(),
+        })
+        .collect()
+}
+
+impl<SC: StarkGenericConfig, A: StarkAir<SC>> RiscvStark<SC, A> {
+    /// The structural constants of the machine.
+    pub fn schema(&self) -> MachineSchema {
+        let extension_degree = <SC::Challenge as AbstractExtensionField<SC::Val>>::D;
+        let mut chips = self
+            .chips()
+            .iter()
+            .map(|chip| ChipSchema {
+                name: chip.name(),
+                main_width: chip.width(),
+                preprocessed_width: chip.preprocessed_width(),
+                permutation_width: (chip.num_interactions() + 1) * extension_degree,
+                constraint_degree: chip.max_constraint_degree(),
+                log_quotient_degree: chip.log_quotient_degree(),
+                sends: interaction_schemas(chip.sends()),
+                receives: interaction_schemas(chip.receives()),
+            })
+            .collect::<Vec<_>>();
+        chips.sort_by(|a, b| a.name.cmp(&b.name));
+
+        MachineSchema {
+            version: MACHINE_SCHEMA_VERSION,
+            extension_degree,
+            trace_opening_rotations: TRACE_OPENING_ROTATIONS.to_vec(),
+            chips,
+            public_values: CANONICAL_PUBLIC_VALUES_FIELDS
+                .iter()
+                .enumerate()
+                .map(|(i, name)| PublicValueSchema {
+                    name: name.to_string(),
+                    offset: 4 * i,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::path::Path;
+
+    use super::*;
+    use crate::runtime::tests::simple_program;
+    use crate::utils::BabyBearBlake3;
+
+    /// The pinned schema of the RISC-V machine, relative to the crate root.
+    const SCHEMA_FIXTURE: &str = "fixtures/machine_schema.json";
+
+    /// The pinned shape of the chips of the RISC-V machine, relative to the crate root.
+    const SHAPE_FIXTURE: &str = "fixtures/machine_shape.toml";
+
+    /// Reads the values of a shape snapshot, keyed by `chip.field`.
+    fn parse_shape(toml: &str) -> BTreeMap<String, String> {
+        let mut values = BTreeMap::new();
+        let mut chip = "";
+        for line in toml.lines().map(str::trim) {
+            if let Some(name) = line
+                .strip_prefix("[chips.\"")
+                .and_then(|line| line.strip_suffix("\"]"))
+            {
+                chip = name;
+            } else if let Some((field, value)) = line.split_once(" = ") {
+                values.insert(format!("{}.{}", chip, field), value.to_string());
+            }
+        }
+        values
+    }
+
+    /// The values which differ between two shape snapshots, one per line.
+    fn shape_diff(pinned: &str, current: &str) -> String {
+        let (pinned, current) = (parse_shape(pinned), parse_shape(current));
+        let keys = pinned.keys().chain(current.keys()).collect::<BTreeSet<_>>();
+        keys.into_iter()
+            .filter_map(|key| match (pinned.get(key), current.get(key)) {
+                (Some(old), Some(new)) if old != new => {
+                    Some(format!("  {}: {} -> {}", key, old, new))
+                }
+                (Some(old), None) => Some(format!("  {}: {} -> removed", key, old)),
+                (None, Some(new)) => Some(format!("  {}: added -> {}", key, new)),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_machine_schema() {
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let schema = machine.schema();
+        let json = schema.to_json();
+        assert_eq!(
+            serde_json::from_str::<MachineSchema>(&json).unwrap(),
+            schema
+        );
+
+        let cpu = schema.chips.iter().find(|chip| chip.name == "CPU").unwrap();
+        assert_eq!(cpu.constraint_degree, 3);
+        assert!(!cpu.sends.is_empty() && cpu.main_width > 0);
+        assert!(schema
+            .chips
+            .windows(2)
+            .all(|pair| pair[0].name < pair[1].name));
+
+        let (_, vk) = machine.setup(&simple_program());
+        assert_eq!(vk.schema_digest(), &schema.digest());
+
+        // Changing the shape of any chip must update the pinned schema on purpose.
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(SCHEMA_FIXTURE);
+        if std::env::var("REGENERATE_FIXTURES").is_ok() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, &json).unwrap();
+        }
+        if let Ok(pinned) = std::fs::read_to_string(&path) {
+            assert!(
+                pinned == json,
+                "the machine schema changed, review {} and regenerate it with REGENERATE_FIXTURES=1",
+                path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn machine_shape_snapshot() {
+        let schema = RiscvStark::new(BabyBearBlake3::new()).schema();
+        let toml = schema.to_shape_toml();
+        assert_eq!(parse_shape(&toml).len(), 4 * schema.chips.len());
+        assert!(schema.chips.iter().any(|chip| chip.name == "ShaExtend"));
+
+        // Widening a chip, or raising its degree, must update the pinned shape in the same change.
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(SHAPE_FIXTURE);
+        if std::env::var("REGENERATE_FIXTURES").is_ok() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, &toml).unwrap();
+        }
+        if let Ok(pinned) = std::fs::read_to_string(&path) {
+            let diff = shape_diff(&pinned, &toml);
+            assert!(
+                diff.is_empty(),
+                "the shape of the machine changed:\n{}\nreview the change and regenerate {} with \
+                 REGENERATE_FIXTURES=1",
+                diff,
+                path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn test_shape_diff() {
+        let pinned = "[chips.\"Add\"]\nmain_width = 10\nconstraint_degree = 3\n\n\
+                      [chips.\"Old\"]\nmain_width = 1\n";
+        let current = "[chips.\"Add\"]\nmain_width = 12\nconstraint_degree = 3\n";
+        assert_eq!(
+            shape_diff(pinned, current),
+            "  Add.main_width: 10 -> 12\n  Old.main_width: 1 -> removed"
+        );
+        assert_eq!(shape_diff(current, current), "");
+    }
+}
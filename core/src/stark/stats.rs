@@ -0,0 +1,231 @@
+use core::fmt::{Display, Formatter};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::ProverHooks;
+
+/// The sizes of the trace of a chip in a shard and the time spent proving it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChipStats {
+    pub name: String,
+    pub shard: usize,
+    pub real_rows: usize,
+    pub padded_rows: usize,
+    /// The time spent generating the main trace, zero for traces generated ahead of time.
+    pub trace_gen: Duration,
+    /// The share of the chip in the commitment to the main traces of the shard, which are
+    /// committed in a single batch, in proportion to the cells of its trace.
+    pub commit: Duration,
+    /// The time spent computing the quotient values of the chip.
+    pub quotient: Duration,
+}
+
+impl ChipStats {
+    pub fn total(&self) -> Duration {
+        self.trace_gen + self.commit + self.quotient
+    }
+}
+
+/// The statistics of every chip of every shard of a proof, in the order the chips were proven.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProverStats {
+    pub chips: Vec<ChipStats>,
+}
+
+impl ProverStats {
+    /// The statistics of each chip summed over the shards, with the number of shards the chip is
+    /// in, the slowest chip first.
+    pub fn by_chip(&self) -> Vec<(ChipStats, usize)> {
+        let mut totals: Vec<(ChipStats, usize)> = Vec::new();
+        for stats in self.chips.iter() {
+            let (total, num_shards) = match totals
+                .iter_mut()
+                .find(|(total, _)| total.name == stats.name)
+            {
+                Some(total) => total,
+                None => {
+                    let total = ChipStats {
+                        name: stats.name.clone(),
+                        ..Default::default()
+                    };
+                    totals.push((total, 0));
+                    totals.last_mut().unwrap()
+                }
+            };
+            *num_shards += 1;
+            total.real_rows += stats.real_rows;
+            total.padded_rows += stats.padded_rows;
+            total.trace_gen += stats.trace_gen;
+            total.commit += stats.commit;
+            total.quotient += stats.quotient;
+        }
+        totals.sort_by(|(a, _), (b, _)| b.total().cmp(&a.total()).then(a.name.cmp(&b.name)));
+        totals
+    }
+}
+
+impl Display for ProverStats {
+    /// A table of the statistics of each chip summed over the shards, the slowest chip first.
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{:<24} {:>6} {:>12} {:>12} {:>12} {:>12} {:>12}",
+            "chip", "shards", "real rows", "padded rows", "trace (ms)", "commit (ms)", "quot. (ms)"
+        )?;
+        for (stats, num_shards) in self.by_chip() {
+            write!(
+                f,
+                "\n{:<24} {:>6} {:>12} {:>12} {:>12.1} {:>12.1} {:>12.1}",
+                stats.name,
+                num_shards,
+                stats.real_rows,
+                stats.padded_rows,
+                stats.trace_gen.as_secs_f64() * 1000.0,
+                stats.commit.as_secs_f64() * 1000.0,
+                stats.quotient.as_secs_f64() * 1000.0,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Hooks collecting the statistics of the chips as they are proven, and forwarding every callback
+/// to the hooks they wrap, if any.
+#[derive(Default)]
+pub struct StatsCollector {
+    stats: Mutex<ProverStats>,
+    inner: Option<Arc<dyn ProverHooks>>,
+}
+
+impl StatsCollector {
+    pub fn new(inner: Option<Arc<dyn ProverHooks>>) -> Self {
+        Self {
+            stats: Mutex::default(),
+            inner,
+        }
+    }
+
+    /// The statistics collected so far.
+    pub fn stats(&self) -> ProverStats {
+        self.stats.lock().unwrap().clone()
+    }
+}
+
+impl ProverHooks for StatsCollector {
+    fn shard_start(&self, index: usize, num_shards: usize) {
+        if let Some(inner) = &self.inner {
+            inner.shard_start(index, num_shards);
+        }
+    }
+
+    fn shard_done(&self, index: usize, num_shards: usize, elapsed: Duration) {
+        if let Some(inner) = &self.inner {
+            inner.shard_done(index, num_shards, elapsed);
+        }
+    }
+
+    fn chip_proven(&self, stats: &ChipStats) {
+        self.stats.lock().unwrap().chips.push(stats.clone());
+        if let Some(inner) = &self.inner {
+            inner.chip_proven(stats);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::runtime::{Program, Runtime};
+    use crate::stark::RiscvStark;
+    use crate::utils::tests::FIBONACCI_ELF;
+    use crate::utils::{prove_core_with_hooks, BabyBearBlake3};
+
+    #[test]
+    fn test_stats_match_traces() {
+        let mut runtime = Runtime::new(Program::from(FIBONACCI_ELF));
+        runtime.run();
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let shards = machine.shard(
+            runtime.record.clone(),
+            &crate::runtime::ShardingConfig::default(),
+        );
+        let expected = shards
+            .iter()
+            .enumerate()
+            .flat_map(|(index, shard)| {
+                let traces = machine.generate_shard_traces(shard, index);
+                traces
+                    .chip_ids
+                    .into_iter()
+                    .zip(traces.shapes)
+                    .map(move |(name, shape)| (index, name, shape.real_rows, shape.padded_rows))
+            })
+            .collect::<Vec<_>>();
+
+        let collector = Arc::new(StatsCollector::default());
+        prove_core_with_hooks(
+            BabyBearBlake3::new(),
+            runtime,
+            &[],
+            None,
+            Some(collector.clone()),
+        )
+        .unwrap();
+        let stats = collector.stats();
+
+        let mut actual = stats
+            .chips
+            .iter()
+            .map(|chip| {
+                (
+                    chip.shard,
+                    chip.name.clone(),
+                    chip.real_rows,
+                    chip.padded_rows,
+                )
+            })
+            .collect::<Vec<_>>();
+        actual.sort();
+        let mut expected = expected;
+        expected.sort();
+        assert_eq!(actual, expected);
+        assert!(stats
+            .chips
+            .iter()
+            .any(|chip| chip.quotient > Duration::ZERO));
+
+        let table = stats.to_string();
+        assert!(table.starts_with("chip"));
+        assert_eq!(table.lines().count(), 1 + stats.by_chip().len());
+        assert!(table.contains("CPU"));
+    }
+
+    #[test]
+    fn test_by_chip() {
+        let chip = |name: &str, shard, rows, ms| ChipStats {
+            name: name.to_string(),
+            shard,
+            real_rows: rows,
+            padded_rows: rows.next_power_of_two(),
+            quotient: Duration::from_millis(ms),
+            ..Default::default()
+        };
+        let stats = ProverStats {
+            chips: vec![
+                chip("Add", 0, 3, 1),
+                chip("Cpu", 0, 5, 4),
+                chip("Add", 1, 7, 2),
+            ],
+        };
+        let by_chip = stats.by_chip();
+        assert_eq!(by_chip.len(), 2);
+        assert_eq!(by_chip[0].0.name, "Cpu");
+        let (add, num_shards) = &by_chip[1];
+        assert_eq!((*num_shards, add.real_rows, add.padded_rows), (2, 10, 12));
+        assert_eq!(add.quotient, Duration::from_millis(3));
+    }
+}
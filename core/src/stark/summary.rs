@@ -0,0 +1,318 @@
+use core::fmt::{Display, Formatter};
+
+use serde::Serialize;
+
+use super::{Proof, ShardProof, StarkGenericConfig};
+
+/// The number of leading bytes of a commitment shown in a summary.
+const COMMITMENT_PREFIX_LEN: usize = 8;
+
+/// The name of the config, without its module path.
+fn config_name<SC>() -> &'static str {
+    let name = std::any::type_name::<SC>();
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+/// The leading bytes of the encoding of a commitment, in hex.
+fn commitment_prefix<C: Serialize>(commitment: &C) -> String {
+    let bytes = bincode::serialize(commitment).unwrap();
+    let prefix = hex::encode(&bytes[..bytes.len().min(COMMITMENT_PREFIX_LEN)]);
+    format!("{}..", prefix)
+}
+
+fn serialized_size<T: Serialize>(value: &T) -> u64 {
+    bincode::serialized_size(value).unwrap()
+}
+
+/// The bytes taken by each component of the encoding of a proof, or of a shard proof.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProofSizeReport {
+    pub main_commitments: u64,
+    pub permutation_commitments: u64,
+    pub quotient_commitments: u64,
+    /// The opened values of the quotients of the chips.
+    pub quotient_openings: u64,
+    /// The opening proofs of the PCS, made of the FRI query proofs.
+    pub opening_proofs: u64,
+    /// The opened values of each chip but those of its quotient, by chip name, in the order the
+    /// chips first appear.
+    pub chips: Vec<(String, u64)>,
+    /// The indices, chip ids and public values of the shards, and for a whole proof its digest,
+    /// context and the other fields around the shard proofs.
+    pub other: u64,
+    /// The size of the whole encoding, which the components add up to up to the length prefixes
+    /// of the lists.
+    pub total: u64,
+}
+
+impl ProofSizeReport {
+    /// The sum of the sizes of the components.
+    pub fn components(&self) -> u64 {
+        self.main_commitments
+            + self.permutation_commitments
+            + self.quotient_commitments
+            + self.quotient_openings
+            + self.opening_proofs
+            + self.chips.iter().map(|(_, size)| size).sum::<u64>()
+            + self.other
+    }
+
+    /// Adds the sizes of the components of a shard proof.
+    fn add_shard(&mut self, shard: &ProofSizeReport) {
+        self.main_commitments += shard.main_commitments;
+        self.permutation_commitments += shard.permutation_commitments;
+        self.quotient_commitments += shard.quotient_commitments;
+        self.quotient_openings += shard.quotient_openings;
+        self.opening_proofs += shard.opening_proofs;
+        for (name, size) in shard.chips.iter() {
+            match self.chips.iter_mut().find(|(total, _)| total == name) {
+                Some((_, total)) => *total += size,
+                None => self.chips.push((name.clone(), *size)),
+            }
+        }
+        self.other += shard.other;
+    }
+}
+
+impl Display for ProofSizeReport {
+    /// A table of the bytes of each component and their share of the total.
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let mut rows = vec![
+            ("main commitments".to_string(), self.main_commitments),
+            (
+                "permutation commitments".to_string(),
+                self.permutation_commitments,
+            ),
+            (
+                "quotient commitments".to_string(),
+                self.quotient_commitments,
+            ),
+            ("quotient openings".to_string(), self.quotient_openings),
+            ("FRI query proofs".to_string(), self.opening_proofs),
+        ];
+        rows.extend(
+            self.chips
+                .iter()
+                .map(|(name, size)| (format!("{} opened values", name), *size)),
+        );
+        rows.push(("other".to_string(), self.other));
+
+        write!(f, "{:<32} {:>12} {:>8}", "component", "bytes", "share")?;
+        let total = self.total.max(1) as f64;
+        for (name, size) in rows {
+            write!(
+                f,
+                "\n{:<32} {:>12} {:>7.1}%",
+                name,
+                size,
+                size as f64 * 100.0 / total
+            )?;
+        }
+        write!(f, "\n{:<32} {:>12}", "total", self.total)
+    }
+}
+
+impl<SC: StarkGenericConfig + Serialize> ShardProof<SC> {
+    /// The bytes taken by each component of the encoding of the shard proof.
+    pub fn size_report(&self) -> ProofSizeReport {
+        let chips = self
+            .opened_values
+            .chips
+            .iter()
+            .enumerate()
+            .map(|(i, values)| {
+                let name = self
+                    .chip_ids
+                    .get(i)
+                    .cloned()
+                    .unwrap_or_else(|| "<unnamed>".to_string());
+                (
+                    name,
+                    serialized_size(values) - serialized_size(&values.quotient),
+                )
+            })
+            .collect();
+        ProofSizeReport {
+            main_commitments: serialized_size(&self.commitment.main_commit),
+            permutation_commitments: serialized_size(&self.commitment.permutation_commit),
+            quotient_commitments: serialized_size(&self.commitment.quotient_commit),
+            quotient_openings: self
+                .opened_values
+                .chips
+                .iter()
+                .map(|values| serialized_size(&values.quotient))
+                .sum(),
+            opening_proofs: serialized_size(&self.opening_proof),
+            chips,
+            other: serialized_size(&self.index)
+                + serialized_size(&self.chip_ids)
+                + serialized_size(&self.public_values),
+            total: serialized_size(self),
+        }
+    }
+}
+
+impl<SC: StarkGenericConfig + Serialize> Proof<SC> {
+    /// The bytes taken by each component of the encoding of the proof, summed over its shards.
+    /// Whatever the shard proofs do not account for is counted in `other`.
+    pub fn size_report(&self) -> ProofSizeReport {
+        let mut report = ProofSizeReport::default();
+        for shard_proof in self.shard_proofs.iter() {
+            report.add_shard(&shard_proof.size_report());
+        }
+        report.total = serialized_size(self);
+        report.other += report.total.saturating_sub(report.components());
+        report
+    }
+}
+
+impl<SC: StarkGenericConfig + Serialize> Display for ShardProof<SC> {
+    /// Describes the shard without verifying it: its chips with their log degrees and widths, its
+    /// commitments, cumulative sums and public values.
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        writeln!(
+            f,
+            "shard {} ({}), {} bytes",
+            self.index,
+            config_name::<SC>(),
+            serialized_size(self)
+        )?;
+        writeln!(f, "  public values: {}", self.public_values)?;
+        writeln!(
+            f,
+            "  commitments: main {}, permutation {}, quotient {}",
+            commitment_prefix(&self.commitment.main_commit),
+            commitment_prefix(&self.commitment.permutation_commit),
+            commitment_prefix(&self.commitment.quotient_commit)
+        )?;
+        writeln!(f, "  cumulative sum: {:?}", self.cumulative_sum())?;
+        write!(f, "  chips:")?;
+        for (i, values) in self.opened_values.chips.iter().enumerate() {
+            let name = self.chip_ids.get(i).map_or("<unnamed>", String::as_str);
+            write!(
+                f,
+                "\n    {}: log degree {}, width {}, cumulative sum {:?}",
+                name,
+                values.log_degree,
+                values.main.local.len(),
+                values.cumulative_sum
+            )?;
+        }
+        if self.chip_ids.len() != self.opened_values.chips.len() {
+            write!(
+                f,
+                "\n    {} chip ids for {} opened chips",
+                self.chip_ids.len(),
+                self.opened_values.chips.len()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<SC: StarkGenericConfig + Serialize> Display for Proof<SC> {
+    /// Describes the proof and each of its shards, without verifying anything.
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        writeln!(
+            f,
+            "proof ({}) with {} shards, {} bytes",
+            config_name::<SC>(),
+            self.shard_proofs.len(),
+            serialized_size(self)
+        )?;
+        writeln!(f, "digest: {:?}", self.digest)?;
+        write!(f, "context: {}", hex::encode(&self.context))?;
+        for shard_proof in self.shard_proofs.iter() {
+            write!(f, "\n{}", shard_proof)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::{tests::simple_program, Runtime};
+    use crate::stark::{LocalProver, RiscvStark};
+    use crate::utils::{BabyBearBlake3, StarkUtils};
+
+    use super::commitment_prefix;
+
+    #[test]
+    fn test_proof_summary() {
+        let mut runtime = Runtime::new(simple_program());
+        runtime.run();
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let (pk, _) = machine.setup(runtime.program.as_ref());
+        let mut challenger = machine.config().challenger();
+        let proof = machine
+            .prove::<LocalProver<_>>(&pk, runtime.record, &mut challenger)
+            .unwrap();
+
+        let shard = &proof.shard_proofs[0];
+        let mut expected = vec![
+            format!(
+                "proof (BabyBearBlake3) with 1 shards, {} bytes",
+                bincode::serialized_size(&proof).unwrap()
+            ),
+            format!("digest: {:?}", proof.digest),
+            "context: ".to_string(),
+            format!(
+                "shard 0 (BabyBearBlake3), {} bytes",
+                bincode::serialized_size(shard).unwrap()
+            ),
+            format!(
+                "  public values: shard 1, pc 0x0..{:#x}, clk 1..{}, halted 0, exit code [0, 0, 0, 0]",
+                shard.public_values.end_pc, shard.public_values.end_clk
+            ),
+            format!(
+                "  commitments: main {}, permutation {}, quotient {}",
+                commitment_prefix(&shard.commitment.main_commit),
+                commitment_prefix(&shard.commitment.permutation_commit),
+                commitment_prefix(&shard.commitment.quotient_commit)
+            ),
+            format!("  cumulative sum: {:?}", shard.cumulative_sum()),
+            "  chips:".to_string(),
+        ];
+        for (name, values) in shard.chip_ids.iter().zip(shard.opened_values.chips.iter()) {
+            expected.push(format!(
+                "    {}: log degree {}, width {}, cumulative sum {:?}",
+                name,
+                values.log_degree,
+                values.main.local.len(),
+                values.cumulative_sum
+            ));
+        }
+        assert_eq!(proof.to_string(), expected.join("\n"));
+        assert!(proof.to_string().contains("    CPU: log degree "));
+        assert_eq!(commitment_prefix(&[0xabu8; 32]), "abababababababab..");
+    }
+
+    #[test]
+    fn test_size_report() {
+        let mut runtime = Runtime::new(simple_program());
+        runtime.run();
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let (pk, _) = machine.setup(runtime.program.as_ref());
+        let mut challenger = machine.config().challenger();
+        let proof = machine
+            .prove::<LocalProver<_>>(&pk, runtime.record, &mut challenger)
+            .unwrap();
+
+        // The components of a shard leave out only the length prefixes of the serialization.
+        let shard = &proof.shard_proofs[0];
+        let report = shard.size_report();
+        assert_eq!(report.total, bincode::serialized_size(shard).unwrap());
+        assert!(report.components() <= report.total);
+        assert!(report.total - report.components() <= 16);
+        assert_eq!(report.chips.len(), shard.chip_ids.len());
+        assert!(report.opening_proofs > 0 && report.quotient_openings > 0);
+
+        let report = proof.size_report();
+        assert_eq!(report.total, bincode::serialized_size(&proof).unwrap());
+        assert_eq!(report.components(), report.total);
+        let table = report.to_string();
+        assert!(table.starts_with("component"));
+        assert!(table.contains("FRI query proofs"));
+        assert!(table.contains("CPU opened values"));
+    }
+}
@@ -0,0 +1,159 @@
+use std::marker::PhantomData;
+
+use p3_air::{AirBuilder, ExtensionBuilder, PairBuilder, PermutationAirBuilder};
+use p3_field::{AbstractField, Field};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{SymbolicExpression, SymbolicVariable};
+
+use crate::air::{
+    EmptyMessageBuilder, MultiTableAirBuilder, PublicValuesBuilder, NUM_PUBLIC_VALUES,
+};
+
+/// A builder which evaluates the constraints of a chip symbolically to measure their degree,
+/// including the permutation constraints of its interactions.
+///
+/// Only the degrees matter, so the permutation trace and its challenges are in the base field.
+pub struct SymbolicConstraintBuilder<F: Field> {
+    preprocessed: RowMajorMatrix<SymbolicVariable<F>>,
+    main: RowMajorMatrix<SymbolicVariable<F>>,
+    permutation: RowMajorMatrix<SymbolicVariable<F>>,
+    permutation_challenges: Vec<F>,
+    public_values: Vec<SymbolicPublicValue<F>>,
+    max_constraint_degree: usize,
+}
+
+/// A symbolic public value, the `index`-th of the public values of a shard.
+///
+/// A public value is the same on every row, so it adds nothing to the degree of a constraint:
+/// it evaluates to a constant expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SymbolicPublicValue<F> {
+    pub index: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: Field> From<SymbolicPublicValue<F>> for SymbolicExpression<F> {
+    fn from(_: SymbolicPublicValue<F>) -> Self {
+        SymbolicExpression::Constant(F::zero())
+    }
+}
+
+/// A matrix of the local and next rows of symbolic variables of the given width.
+fn symbolic_rows<F: Field>(width: usize) -> RowMajorMatrix<SymbolicVariable<F>> {
+    let values = [false, true]
+        .into_iter()
+        .flat_map(|is_next| (0..width).map(move |column| SymbolicVariable::new(is_next, column)))
+        .collect();
+    RowMajorMatrix::new(values, width)
+}
+
+impl<F: Field> SymbolicConstraintBuilder<F> {
+    /// Creates a builder for traces of the given widths, the permutation width being the number
+    /// of interactions plus one.
+    pub fn new(preprocessed_width: usize, width: usize, permutation_width: usize) -> Self {
+        Self {
+            preprocessed: symbolic_rows(preprocessed_width),
+            main: symbolic_rows(width),
+            permutation: symbolic_rows(permutation_width),
+            permutation_challenges: vec![F::two(); 2],
+            public_values: (0..NUM_PUBLIC_VALUES)
+                .map(|index| SymbolicPublicValue {
+                    index,
+                    _phantom: PhantomData,
+                })
+                .collect(),
+            max_constraint_degree: 0,
+        }
+    }
+
+    /// The maximum degree of the constraints asserted so far, the selectors of the first, last
+    /// and transition rows included.
+    pub fn max_constraint_degree(&self) -> usize {
+        self.max_constraint_degree
+    }
+}
+
+impl<F: Field> AirBuilder for SymbolicConstraintBuilder<F> {
+    type F = F;
+    type Expr = SymbolicExpression<F>;
+    type Var = SymbolicVariable<F>;
+    type M = RowMajorMatrix<Self::Var>;
+
+    fn main(&self) -> Self::M {
+        self.main.clone()
+    }
+
+    fn is_first_row(&self) -> Self::Expr {
+        SymbolicExpression::IsFirstRow
+    }
+
+    fn is_last_row(&self) -> Self::Expr {
+        SymbolicExpression::IsLastRow
+    }
+
+    fn is_transition_window(&self, size: usize) -> Self::Expr {
+        if size == 2 {
+            SymbolicExpression::IsTransition
+        } else {
+            panic!("uni-stark only supports a window size of 2")
+        }
+    }
+
+    fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I) {
+        let degree = x.into().degree_multiple();
+        self.max_constraint_degree = self.max_constraint_degree.max(degree);
+    }
+}
+
+impl<F: Field> ExtensionBuilder for SymbolicConstraintBuilder<F> {
+    type EF = F;
+    type ExprEF = SymbolicExpression<F>;
+    type VarEF = SymbolicVariable<F>;
+
+    fn assert_zero_ext<I>(&mut self, x: I)
+    where
+        I: Into<Self::ExprEF>,
+    {
+        self.assert_zero(x);
+    }
+}
+
+impl<F: Field> PermutationAirBuilder for SymbolicConstraintBuilder<F> {
+    type MP = RowMajorMatrix<Self::VarEF>;
+
+    fn permutation(&self) -> Self::MP {
+        self.permutation.clone()
+    }
+
+    fn permutation_randomness(&self) -> &[Self::EF] {
+        &self.permutation_challenges
+    }
+}
+
+impl<F: Field> PairBuilder for SymbolicConstraintBuilder<F> {
+    fn preprocessed(&self) -> Self::M {
+        self.preprocessed.clone()
+    }
+}
+
+impl<F: Field> MultiTableAirBuilder for SymbolicConstraintBuilder<F> {
+    type Sum = SymbolicExpression<F>;
+
+    fn cumulative_sum(&self) -> Self::Sum {
+        SymbolicExpression::Constant(F::zero())
+    }
+}
+
+impl<F: Field> PublicValuesBuilder for SymbolicConstraintBuilder<F> {
+    type PublicVar = SymbolicPublicValue<F>;
+
+    const STANDALONE: bool = false;
+
+    /// The public values of a shard, so that the degree includes the boundary constraints which
+    /// refer to them.
+    fn public_values(&self) -> &[Self::PublicVar] {
+        &self.public_values
+    }
+}
+
+impl<F: Field> EmptyMessageBuilder for SymbolicConstraintBuilder<F> {}
@@ -1,6 +1,8 @@
 use std::{
+    collections::BTreeMap,
     fs::File,
     io::{BufReader, BufWriter, Seek},
+    time::Duration,
 };
 
 use bincode::{deserialize_from, Error};
@@ -15,6 +17,8 @@ use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use tracing::trace;
 
 use super::StarkGenericConfig;
+use crate::air::{PublicValues, TraceShape};
+use crate::io::StdinBinding;
 
 pub type Val<SC> = <SC as StarkGenericConfig>::Val;
 pub type PackedVal<SC> = <<SC as StarkGenericConfig>::Val as Field>::Packing;
@@ -37,26 +41,41 @@ pub type QuotientOpenedValues<T> = Vec<T>;
 #[serde(bound(deserialize = "PcsProverData<SC>: Deserialize<'de>"))]
 pub struct ShardMainData<SC: StarkGenericConfig> {
     pub traces: Vec<ValMat<SC>>,
+    pub shapes: Vec<TraceShape>,
     pub main_commit: Com<SC>,
     pub main_data: PcsProverData<SC>,
     pub chip_ids: Vec<String>,
     pub index: usize,
+    pub public_values: PublicValues<u32>,
+    /// The time spent generating the trace of each chip.
+    pub trace_times: Vec<Duration>,
+    /// The time spent committing to the traces.
+    pub commit_time: Duration,
 }
 
 impl<SC: StarkGenericConfig> ShardMainData<SC> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         traces: Vec<ValMat<SC>>,
+        shapes: Vec<TraceShape>,
         main_commit: Com<SC>,
         main_data: PcsProverData<SC>,
         chip_ids: Vec<String>,
         index: usize,
+        public_values: PublicValues<u32>,
+        trace_times: Vec<Duration>,
+        commit_time: Duration,
     ) -> Self {
         Self {
             traces,
+            shapes,
             main_commit,
             main_data,
             chip_ids,
             index,
+            public_values,
+            trace_times,
+            commit_time,
         }
     }
 
@@ -81,6 +100,19 @@ impl<SC: StarkGenericConfig> ShardMainData<SC> {
     }
 }
 
+/// The main traces of the chips of a shard, generated without committing to them.
+#[derive(Debug, Clone)]
+pub struct ShardTraces<F> {
+    pub index: usize,
+    pub public_values: PublicValues<u32>,
+    /// The names of the chips with a trace, in the order of the chips of the machine.
+    pub chip_ids: Vec<String>,
+    pub shapes: Vec<TraceShape>,
+    pub traces: Vec<RowMajorMatrix<F>>,
+    /// The time spent generating each trace, zero for traces generated by another process.
+    pub trace_times: Vec<Duration>,
+}
+
 pub enum ShardMainDataWrapper<SC: StarkGenericConfig> {
     InMemory(ShardMainData<SC>),
     TempFile(File, u64),
@@ -141,6 +173,7 @@ pub struct ShardProof<SC: StarkGenericConfig> {
     pub opened_values: ShardOpenedValues<Challenge<SC>>,
     pub opening_proof: OpeningProof<SC>,
     pub chip_ids: Vec<String>,
+    pub public_values: PublicValues<u32>,
 }
 
 #[cfg(not(feature = "perf"))]
@@ -150,6 +183,7 @@ pub struct ShardProof<SC: StarkGenericConfig> {
     pub traces: Vec<ValMat<SC>>,
     pub permutation_traces: Vec<ChallengeMat<SC>>,
     pub chip_ids: Vec<String>,
+    pub public_values: PublicValues<u32>,
 }
 
 impl<T: Serialize> ShardOpenedValues<T> {
@@ -157,22 +191,30 @@ impl<T: Serialize> ShardOpenedValues<T> {
         let mut main_vals = vec![];
         let mut permutation_vals = vec![];
         let mut quotient_vals = vec![];
+        let mut preprocessed_vals = vec![];
 
         let to_values = |values: AirOpenedValues<T>| vec![values.local, values.next];
         for chip_values in self.chips {
             let ChipOpenedValues {
+                preprocessed,
                 main,
                 permutation,
                 quotient,
                 ..
             } = chip_values;
 
+            // Each preprocessed trace is a batch of its own, after the batches of the shard.
+            if !preprocessed.local.is_empty() {
+                preprocessed_vals.push(vec![to_values(preprocessed)]);
+            }
             main_vals.push(to_values(main));
             permutation_vals.push(to_values(permutation));
             quotient_vals.push(vec![quotient]);
         }
 
-        vec![main_vals, permutation_vals, quotient_vals]
+        let mut values = vec![main_vals, permutation_vals, quotient_vals];
+        values.extend(preprocessed_vals);
+        values
     }
 }
 
@@ -194,7 +236,59 @@ impl<SC: StarkGenericConfig> ShardProof<SC> {
     }
 }
 
+/// The number of field elements in the digest binding the shard proofs of a proof together.
+pub const PROOF_DIGEST_SIZE: usize = 8;
+
 #[derive(Serialize, Deserialize)]
 pub struct Proof<SC: StarkGenericConfig> {
     pub shard_proofs: Vec<ShardProof<SC>>,
+
+    /// A digest sampled from the transcript after observing the commitments of all shards, which
+    /// is observed in turn before proving each shard.
+    #[serde(
+        serialize_with = "crate::utils::codec::serialize_field_array",
+        deserialize_with = "crate::utils::codec::deserialize_field_array"
+    )]
+    pub digest: [Val<SC>; PROOF_DIGEST_SIZE],
+
+    /// The application context the proof is bound to, empty if it is not bound to any.
+    pub context: Vec<u8>,
+
+    /// The binding of the proof to the standard input of the program, if it was proven from one.
+    #[serde(default)]
+    pub stdin: Option<StdinBinding>,
+
+    /// The identifier of the parameters of the hasher the proof was produced with.
+    #[serde(default)]
+    pub hasher_params: u8,
+
+    /// The bytes the prover claims the program committed with the `COMMIT` syscall, observed by
+    /// the challenger before the shards. The shards do not constrain them to the ones the program
+    /// committed.
+    #[serde(default, rename = "committed_values")]
+    pub claimed_values: Vec<u8>,
+
+    /// The digest of the preprocessed trace of each chip which has one, by chip name, from the
+    /// proving key the proof was produced with. Empty in proofs of versions before 5.
+    #[serde(default)]
+    pub preprocessed_digests: BTreeMap<String, [u8; 32]>,
+}
+
+impl<SC: StarkGenericConfig> Proof<SC> {
+    /// The number of instructions executed in all the shards, as claimed by their public values.
+    pub fn instruction_count(&self) -> u64 {
+        self.shard_proofs
+            .iter()
+            .map(|shard_proof| shard_proof.public_values.instruction_count as u64)
+            .sum()
+    }
+
+    /// The number of bytes of the input stream read in all the shards, as claimed by their public
+    /// values.
+    pub fn input_read(&self) -> u64 {
+        self.shard_proofs
+            .iter()
+            .map(|shard_proof| shard_proof.public_values.input_read as u64)
+            .sum()
+    }
 }
@@ -17,7 +17,8 @@ use std::marker::PhantomData;
 
 use super::folder::VerifierConstraintFolder;
 use super::types::*;
-use super::RiscvChip;
+use super::Chip;
+use super::StarkAir;
 use super::StarkGenericConfig;
 
 use core::fmt::Display;
@@ -26,10 +27,15 @@ pub struct Verifier<SC>(PhantomData<SC>);
 
 impl<SC: StarkGenericConfig> Verifier<SC> {
     /// Verify a proof for a collection of air chips.
+    ///
+    /// `preprocessed_commits` holds the commitment of the verifying key to the preprocessed trace
+    /// of each chip which has one, which its preprocessed openings are checked against.
     #[cfg(feature = "perf")]
-    pub fn verify_shard(
+    pub fn verify_shard<A: StarkAir<SC>>(
         config: &SC,
-        chips: &[&RiscvChip<SC>],
+        chips: &[&Chip<SC::Val, A>],
+        log_quotient_degrees: &[usize],
+        preprocessed_commits: &[Option<Com<SC>>],
         challenger: &mut SC::Challenger,
         proof: &ShardProof<SC>,
     ) -> Result<(), VerificationError> {
@@ -39,13 +45,23 @@ impl<SC: StarkGenericConfig> Verifier<SC> {
             commitment,
             opened_values,
             opening_proof,
+            public_values,
             ..
         } = proof;
+        let public_values = public_values.to_field_elements::<SC::Val>();
+
+        Self::verify_shape(
+            chips,
+            log_quotient_degrees,
+            preprocessed_commits,
+            opened_values,
+        )?;
 
         let (main_dims, perm_dims, quot_dims): (Vec<_>, Vec<_>, Vec<_>) = chips
             .iter()
             .zip(opened_values.chips.iter())
-            .map(|(chip, val)| {
+            .zip(log_quotient_degrees.iter())
+            .map(|((chip, val), log_quotient_degree)| {
                 (
                     Dimensions {
                         width: chip.width(),
@@ -56,14 +72,24 @@ impl<SC: StarkGenericConfig> Verifier<SC> {
                         height: 1 << val.log_degree,
                     },
                     Dimensions {
-                        width: SC::Challenge::D << chip.log_quotient_degree(),
+                        width: SC::Challenge::D << log_quotient_degree,
                         height: 1 << val.log_degree,
                     },
                 )
             })
             .multiunzip();
 
-        let dims = &[main_dims, perm_dims, quot_dims];
+        let mut dims = vec![main_dims, perm_dims, quot_dims];
+        // One batch per preprocessed trace, committed to on its own by the verifying key.
+        let preprocessed_chips = (0..chips.len())
+            .filter(|&i| preprocessed_commits[i].is_some())
+            .collect::<Vec<_>>();
+        for &i in preprocessed_chips.iter() {
+            dims.push(vec![Dimensions {
+                width: chips[i].preprocessed_width(),
+                height: 1 << opened_values.chips[i].log_degree,
+            }]);
+        }
 
         let g_subgroups = opened_values
             .chips
@@ -97,20 +123,33 @@ impl<SC: StarkGenericConfig> Verifier<SC> {
             .map(|g| vec![zeta, zeta * *g])
             .collect::<Vec<_>>();
 
-        let quotient_opening_points = chips
+        let quotient_opening_points = log_quotient_degrees
             .iter()
-            .map(|chip| vec![zeta.exp_power_of_2(chip.log_quotient_degree())])
+            .map(|log_quotient_degree| vec![zeta.exp_power_of_2(*log_quotient_degree)])
             .collect::<Vec<_>>();
 
+        let preprocessed_opening_points = preprocessed_chips
+            .iter()
+            .map(|&i| vec![trace_opening_points[i].clone()])
+            .collect::<Vec<_>>();
+        let mut batches = vec![
+            (main_commit.clone(), trace_opening_points.as_slice()),
+            (permutation_commit.clone(), trace_opening_points.as_slice()),
+            (quotient_commit.clone(), quotient_opening_points.as_slice()),
+        ];
+        for (&i, points) in preprocessed_chips
+            .iter()
+            .zip(preprocessed_opening_points.iter())
+        {
+            let commit = preprocessed_commits[i].clone().unwrap();
+            batches.push((commit, points.as_slice()));
+        }
+
         config
             .pcs()
             .verify_multi_batches(
-                &[
-                    (main_commit.clone(), &trace_opening_points),
-                    (permutation_commit.clone(), &trace_opening_points),
-                    (quotient_commit.clone(), &quotient_opening_points),
-                ],
-                dims,
+                &batches,
+                &dims,
                 opened_values.clone().into_values(),
                 opening_proof,
                 challenger,
@@ -128,6 +167,7 @@ impl<SC: StarkGenericConfig> Verifier<SC> {
                 zeta,
                 alpha,
                 &permutation_challenges,
+                &public_values,
             )
             .map_err(|_| VerificationError::OodEvaluationMismatch(chip.name()))?;
         }
@@ -136,23 +176,73 @@ impl<SC: StarkGenericConfig> Verifier<SC> {
     }
 
     #[cfg(not(feature = "perf"))]
-    pub fn verify_shard(
+    pub fn verify_shard<A: StarkAir<SC>>(
         _config: &SC,
-        _chips: &[&RiscvChip<SC>],
+        _chips: &[&Chip<SC::Val, A>],
+        _log_quotient_degrees: &[usize],
+        _preprocessed_commits: &[Option<Com<SC>>],
         _challenger: &mut SC::Challenger,
         _proof: &ShardProof<SC>,
     ) -> Result<(), VerificationError> {
         Ok(())
     }
 
+    /// Check that the opened values of each chip have the widths implied by the chip and by the
+    /// quotient degree of the verifying key, rather than trusting the shapes chosen by the prover.
+    ///
+    /// Every chip with a preprocessed trace must have its commitment in the verifying key, and
+    /// only those chips open one.
     #[cfg(feature = "perf")]
-    fn verify_constraints(
-        chip: &RiscvChip<SC>,
+    fn verify_shape<A: StarkAir<SC>>(
+        chips: &[&Chip<SC::Val, A>],
+        log_quotient_degrees: &[usize],
+        preprocessed_commits: &[Option<Com<SC>>],
+        opened_values: &ShardOpenedValues<SC::Challenge>,
+    ) -> Result<(), VerificationError> {
+        if chips.len() != opened_values.chips.len()
+            || chips.len() != log_quotient_degrees.len()
+            || chips.len() != preprocessed_commits.len()
+        {
+            return Err(VerificationError::InvalidProofShape);
+        }
+        for (chip, values, log_quotient_degree, commit) in izip!(
+            chips.iter(),
+            opened_values.chips.iter(),
+            log_quotient_degrees.iter(),
+            preprocessed_commits.iter()
+        ) {
+            let main_width = chip.width();
+            let perm_width = chip.num_interactions() * SC::Challenge::D;
+            let preprocessed_width = chip.preprocessed_width();
+            let opened_preprocessed_width = if commit.is_some() {
+                preprocessed_width
+            } else {
+                0
+            };
+            let valid = (preprocessed_width > 0) == commit.is_some()
+                && values.preprocessed.local.len() == opened_preprocessed_width
+                && values.preprocessed.next.len() == opened_preprocessed_width
+                && values.main.local.len() == main_width
+                && values.main.next.len() == main_width
+                && values.permutation.local.len() == perm_width
+                && values.permutation.next.len() == perm_width
+                && values.quotient.len() == SC::Challenge::D << log_quotient_degree;
+            if !valid {
+                return Err(VerificationError::InvalidProofShape);
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "perf")]
+    fn verify_constraints<A: StarkAir<SC>>(
+        chip: &Chip<SC::Val, A>,
         opening: ChipOpenedValues<SC::Challenge>,
         g: SC::Val,
         zeta: SC::Challenge,
         alpha: SC::Challenge,
         permutation_challenges: &[SC::Challenge],
+        public_values: &[SC::Val],
     ) -> Result<(), OodEvaluationMismatch> {
         let z_h = zeta.exp_power_of_2(opening.log_degree) - SC::Challenge::one();
         let is_first_row = z_h / (zeta - SC::Val::one());
@@ -205,6 +295,7 @@ impl<SC: StarkGenericConfig> Verifier<SC> {
             main: opening.main.view(),
             perm: perm_opening.view(),
             perm_challenges: permutation_challenges,
+            public_values,
             cumulative_sum: opening.cumulative_sum,
             is_first_row,
             is_last_row,
@@ -233,6 +324,8 @@ pub enum VerificationError {
     ///
     /// `constraints(zeta)` did not match `quotient(zeta) Z_H(zeta)`.
     OodEvaluationMismatch(String),
+    /// The opened values do not have the shape expected from the verifying key.
+    InvalidProofShape,
 }
 
 impl Display for VerificationError {
@@ -244,6 +337,9 @@ impl Display for VerificationError {
             VerificationError::OodEvaluationMismatch(chip) => {
                 write!(f, "Out-of-domain evaluation mismatch on chip {}", chip)
             }
+            VerificationError::InvalidProofShape => {
+                write!(f, "Invalid proof shape")
+            }
         }
     }
 }
@@ -0,0 +1,68 @@
+use crate::runtime::{Register, Syscall, SyscallContext};
+
+pub struct SyscallCommit;
+
+impl SyscallCommit {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Syscall for SyscallCommit {
+    /// Appends the `a1` bytes at `a0` to the committed values of the execution, which the proof
+    /// carries as values claimed by the prover. The bytes are read without memory records, so no
+    /// shard constrains them.
+    fn execute(&self, ctx: &mut SyscallContext) -> u32 {
+        let rt = &mut ctx.rt;
+        let ptr = rt.register(Register::X10);
+        let len = rt.register(Register::X11);
+        let bytes = (0..len).map(|i| rt.byte(ptr + i)).collect::<Vec<u8>>();
+        rt.record.committed_values.extend_from_slice(&bytes);
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::{Instruction, Opcode, Program, Runtime, SyscallCode};
+    use crate::stark::{LocalProver, RiscvStark};
+    use crate::utils::{self, BabyBearBlake3, StarkUtils};
+
+    /// Stores `value` at 0x1000 and commits its four bytes.
+    fn commit_program(value: u32) -> Program {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, value, false, true),
+            Instruction::new(Opcode::SW, 29, 0, 0x1000, false, true),
+            Instruction::new(Opcode::ADD, 5, 0, SyscallCode::COMMIT as u32, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 0x1000, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 4, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+        ];
+        Program::new(instructions, 0, 0).with_halt()
+    }
+
+    #[test]
+    fn test_commit_prove() {
+        utils::setup_logger();
+        let mut runtime = Runtime::new(commit_program(42));
+        runtime.run();
+        assert_eq!(runtime.record.committed_values, 42u32.to_le_bytes());
+
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let (pk, vk) = machine.setup(runtime.program.as_ref());
+        let mut challenger = machine.config().challenger();
+        let mut proof = machine
+            .prove_with_stdin::<LocalProver<_>>(&pk, runtime.record, &[], None, &mut challenger)
+            .unwrap();
+        let mut challenger = machine.config().challenger();
+        machine.verify(&vk, &proof, &mut challenger).unwrap();
+        let value = u32::from_le_bytes(proof.claimed_values[..4].try_into().unwrap());
+        assert_eq!(value, 42);
+
+        // Other claimed bytes change the transcript, so this proof no longer verifies. Nothing stops
+        // a prover from proving the other claim from the start.
+        proof.claimed_values[0] ^= 1;
+        let mut challenger = machine.config().challenger();
+        assert!(machine.verify(&vk, &proof, &mut challenger).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+use crate::runtime::{Register, Syscall, SyscallContext};
+
+pub struct SyscallCycleCount;
+
+impl SyscallCycleCount {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Syscall for SyscallCycleCount {
+    /// Returns the global cycle counter as a `u64`: the number of instructions executed before the
+    /// `ECALL` over all shards, with the low word in `a0` and the high word in `a1`.
+    ///
+    /// Both words are ordinary register writes of the `ECALL` row. The CPU chip constrains the
+    /// bytes of `a0` to the instructions counted in the previous shards plus those before the row
+    /// in its own shard, and those of `a1` to zero, since the count of a proven execution stays
+    /// below the modulus of the field.
+    fn execute(&self, ctx: &mut SyscallContext) -> u32 {
+        ctx.rt.rw_memory(Register::X11, 0);
+        ctx.rt.state.global_clk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::{Instruction, Opcode, Program, Register, Runtime, SyscallCode};
+    use crate::utils::{self, prove_core, BabyBearBlake3};
+
+    /// Reads the clock into x20 (low) and x21 (high), then again into x22 and x23, and sets x24 to
+    /// 1 if the second reading is greater than the first.
+    fn cycle_count_program() -> Program {
+        let instructions = vec![
+            Instruction::new(
+                Opcode::ADD,
+                5,
+                0,
+                SyscallCode::CYCLE_COUNT as u32,
+                false,
+                true,
+            ),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+            Instruction::new(Opcode::ADD, 20, 10, 0, false, true),
+            Instruction::new(Opcode::ADD, 21, 11, 0, false, true),
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::ADD, 29, 29, 37, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+            Instruction::new(Opcode::ADD, 22, 10, 0, false, true),
+            Instruction::new(Opcode::ADD, 23, 11, 0, false, true),
+            Instruction::new(Opcode::BLTU, 21, 23, 12, false, true),
+            Instruction::new(Opcode::BNE, 21, 23, 12, false, true),
+            Instruction::new(Opcode::BGEU, 20, 22, 8, false, true),
+            Instruction::new(Opcode::ADD, 24, 0, 1, false, true),
+        ];
+        Program::new(instructions, 0, 0)
+    }
+
+    #[test]
+    fn test_cycle_count() {
+        let mut runtime = Runtime::new(cycle_count_program());
+        runtime.run();
+        let first = runtime.register(Register::X20);
+        let second = runtime.register(Register::X22);
+        assert_eq!(runtime.register(Register::X21), 0);
+        assert_eq!(runtime.register(Register::X23), 0);
+        // The first reading follows the `ADD` which loads the syscall id, and five instructions
+        // separate the readings.
+        assert_eq!(first, 1);
+        assert_eq!(second - first, 5);
+        assert_eq!(runtime.register(Register::X24), 1);
+
+        // The counter goes on across shards, rather than starting over with the clock.
+        let mut runtime = Runtime::new(cycle_count_program());
+        runtime.shard_size = 4;
+        runtime.run();
+        assert!(runtime.state.current_shard > 1);
+        assert_eq!(runtime.register(Register::X20), 1);
+        assert_eq!(runtime.register(Register::X22), 6);
+        assert_eq!(runtime.register(Register::X24), 1);
+    }
+
+    #[test]
+    fn test_cycle_count_prove() {
+        utils::setup_logger();
+        let mut runtime = Runtime::new(cycle_count_program());
+        runtime.run();
+        prove_core(BabyBearBlake3::new(), runtime).unwrap();
+    }
+}
@@ -0,0 +1,115 @@
+use crate::runtime::{Register, Syscall, SyscallContext};
+
+/// The value returned to the program when looking up an environment variable which is not set.
+pub const ENV_MISSING: u32 = u32::MAX;
+
+pub struct SyscallGetenv;
+
+impl SyscallGetenv {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Syscall for SyscallGetenv {
+    /// Looks up the environment variable whose key is the `a1` bytes at `a0`.
+    ///
+    /// Returns the length of its value, and queues the first `a2` bytes of the value at the front
+    /// of the input stream, from where the program reads them with `LWA`. The value never enters
+    /// memory without going through the input stream, so the lookup needs no constraints of its
+    /// own.
+    fn execute(&self, ctx: &mut SyscallContext) -> u32 {
+        let a0 = Register::X10;
+        let a1 = Register::X11;
+        let a2 = Register::X12;
+        let rt = &mut ctx.rt;
+        let key_ptr = rt.register(a0);
+        let key_len = rt.register(a1);
+        let out_cap = rt.register(a2) as usize;
+        let key = (0..key_len)
+            .map(|i| rt.byte(key_ptr + i))
+            .collect::<Vec<u8>>();
+        let Some(value) = rt.env.get(&key) else {
+            return ENV_MISSING;
+        };
+        let len = value.len();
+        let queued = value[..len.min(out_cap)].to_vec();
+        let ptr = rt.state.input_stream_ptr;
+        rt.state.input_stream.splice(ptr..ptr, queued);
+        len as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ENV_MISSING;
+    use crate::runtime::{Instruction, Opcode, Program, Register, Runtime, SyscallCode};
+    use crate::utils::{self, prove_core, BabyBearBlake3};
+
+    const KEY_PTR: u32 = 0x1000;
+
+    /// Looks up `MODE` reading at most one byte of its value, and sets x21 to 0 if it is not set,
+    /// to 100 if its value starts with `1` and to 200 otherwise.
+    fn getenv_program() -> Program {
+        let key = u32::from_le_bytes(*b"MODE");
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, key, false, true),
+            Instruction::new(Opcode::SW, 29, 0, KEY_PTR, false, true),
+            Instruction::new(Opcode::ADD, 5, 0, SyscallCode::GETENV as u32, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, KEY_PTR, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 4, false, true),
+            Instruction::new(Opcode::ADD, 12, 0, 1, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+            Instruction::new(Opcode::ADD, 20, 10, 0, false, true),
+            Instruction::new(Opcode::ADD, 6, 0, ENV_MISSING, false, true),
+            Instruction::new(Opcode::BEQ, 20, 6, 36, false, true),
+            Instruction::new(Opcode::ADD, 5, 0, SyscallCode::LWA as u32, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 0, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 1, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+            Instruction::new(Opcode::ADD, 21, 0, 200, false, true),
+            Instruction::new(Opcode::ADD, 6, 0, b'1' as u32, false, true),
+            Instruction::new(Opcode::BNE, 10, 6, 8, false, true),
+            Instruction::new(Opcode::ADD, 21, 0, 100, false, true),
+        ];
+        Program::new(instructions, 0, 0)
+    }
+
+    fn run(env: Option<&[u8]>) -> Runtime {
+        let mut runtime = Runtime::new(getenv_program());
+        runtime.write_stdin_slice(b"xyz");
+        if let Some(value) = env {
+            runtime.set_env(b"MODE", value);
+        }
+        runtime.run();
+        runtime
+    }
+
+    #[test]
+    fn test_getenv() {
+        let runtime = run(None);
+        assert_eq!(runtime.register(Register::X20), ENV_MISSING);
+        assert_eq!(runtime.register(Register::X21), 0);
+        assert_eq!(runtime.state.input_stream, b"xyz");
+
+        let runtime = run(Some(b"1"));
+        assert_eq!(runtime.register(Register::X20), 1);
+        assert_eq!(runtime.register(Register::X21), 100);
+
+        // A value longer than the buffer is truncated, but its full length is returned, and the
+        // input which follows is read as usual.
+        let runtime = run(Some(b"0abc"));
+        assert_eq!(runtime.register(Register::X20), 4);
+        assert_eq!(runtime.register(Register::X21), 200);
+        assert_eq!(runtime.state.input_stream, b"0xyz");
+        assert_eq!(runtime.state.input_stream_ptr, 1);
+    }
+
+    #[test]
+    fn test_getenv_prove() {
+        utils::setup_logger();
+        for env in [None, Some(b"1".as_slice())] {
+            prove_core(BabyBearBlake3::new(), run(env)).unwrap();
+        }
+    }
+}
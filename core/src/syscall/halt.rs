@@ -11,6 +11,7 @@ impl SyscallHalt {
 impl Syscall for SyscallHalt {
     fn execute(&self, ctx: &mut SyscallContext) -> u32 {
         ctx.set_next_pc(0);
+        ctx.rt.state.halted = true;
         ctx.register_unsafe(Register::X10)
     }
 }
@@ -11,23 +11,123 @@ impl SyscallLWA {
 }
 
 impl Syscall for SyscallLWA {
+    /// Reads the next `a1` bytes of the input stream, at most a word, and returns them in `a0`.
+    ///
+    /// The byte count is read through the memory access of the `ECALL` row, so that the CPU chip
+    /// can add it to the bytes read in the shard, which the public values of the shard expose.
     fn execute(&self, ctx: &mut SyscallContext) -> u32 {
         // TODO: in the future this will be used for private vs. public inputs.
         let a0 = Register::X10;
         let a1 = Register::X11;
         let _ = ctx.register_unsafe(a0);
-        let num_bytes = ctx.register_unsafe(a1) as usize;
+        let num_bytes = ctx.rt.rr_memory(a1) as usize;
+        assert!(
+            num_bytes <= 4,
+            "LWA reads at most a word of input, not {} bytes",
+            num_bytes
+        );
         let mut read_bytes = [0u8; 4];
-        for i in 0..num_bytes {
+        for byte in read_bytes.iter_mut().take(num_bytes) {
             if ctx.rt.state.input_stream_ptr >= ctx.rt.state.input_stream.len() {
                 tracing::error!(
                     "Not enough input words were passed in. Use --input to pass in more words."
                 );
                 exit(1);
             }
-            read_bytes[i] = ctx.rt.state.input_stream[ctx.rt.state.input_stream_ptr];
+            *byte = ctx.rt.state.input_stream[ctx.rt.state.input_stream_ptr];
             ctx.rt.state.input_stream_ptr += 1;
         }
+        if !ctx.rt.unconstrained {
+            ctx.rt.state.input_read += num_bytes;
+        }
         u32::from_le_bytes(read_bytes)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+    use p3_field::extension::BinomialExtensionField;
+    use p3_field::AbstractField;
+
+    use crate::air::{MachineAir, PublicValues};
+    use crate::cpu::CpuChip;
+    use crate::runtime::{
+        ExecutionRecord, Instruction, Opcode, Program, Register, Runtime, SyscallCode,
+    };
+    use crate::stark::{check_constraints, Chip, RiscvAir};
+    use crate::utils::{self, prove_core, BabyBearBlake3};
+
+    /// Reads three bytes of the input into x20, then one more into x21.
+    fn lwa_program() -> Program {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, SyscallCode::LWA as u32, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 3, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+            Instruction::new(Opcode::ADD, 20, 10, 0, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 1, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+            Instruction::new(Opcode::ADD, 21, 10, 0, false, true),
+        ];
+        Program::new(instructions, 0, 0).with_halt()
+    }
+
+    fn run() -> Runtime {
+        let mut runtime = Runtime::new(lwa_program());
+        runtime.write_stdin_slice(b"sp1!??");
+        runtime.run();
+        runtime
+    }
+
+    #[test]
+    fn test_lwa_input_read() {
+        type EF = BinomialExtensionField<BabyBear, 4>;
+
+        let runtime = run();
+        assert_eq!(runtime.register(Register::X20), 0x0031_7073);
+        assert_eq!(runtime.register(Register::X21), b'!' as u32);
+        assert_eq!(runtime.state.input_read, 4);
+        let public_values = PublicValues::from_record(&runtime.record);
+        assert_eq!(public_values.input_read, 4);
+
+        let chip = Chip::new(RiscvAir::<BabyBear>::Cpu(CpuChip::default()));
+        let trace = chip.generate_trace(&runtime.record, &mut ExecutionRecord::default());
+        let challenges = [EF::from_canonical_u32(3), EF::from_canonical_u32(7)];
+        let perm = chip.generate_permutation_trace(None, &trace, &challenges);
+        let check = |public_values: PublicValues<u32>| {
+            check_constraints::<BabyBearBlake3>(
+                &chip,
+                None,
+                &trace,
+                &perm,
+                &challenges,
+                &public_values.to_field_elements(),
+            )
+        };
+        check(public_values).unwrap();
+
+        // Claim the program read one more byte.
+        let mut tampered = public_values;
+        tampered.input_read += 1;
+        assert!(check(tampered).is_err());
+    }
+
+    #[test]
+    fn test_lwa_prove() {
+        utils::setup_logger();
+        prove_core(BabyBearBlake3::new(), run()).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "LWA reads at most a word of input")]
+    fn test_lwa_more_than_a_word() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, SyscallCode::LWA as u32, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 5, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+        ];
+        let mut runtime = Runtime::new(Program::new(instructions, 0, 0));
+        runtime.write_stdin_slice(b"sp1!??");
+        runtime.run();
+    }
+}
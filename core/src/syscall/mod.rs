@@ -1,9 +1,15 @@
+mod commit;
+mod cycle_count;
+mod getenv;
 mod halt;
 mod lwa;
 pub mod precompiles;
 mod unconstrained;
 mod write;
 
+pub use commit::*;
+pub use cycle_count::*;
+pub use getenv::*;
 pub use halt::*;
 pub use lwa::*;
 pub use unconstrained::*;
@@ -0,0 +1,161 @@
+use core::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_field::AbstractField;
+use p3_matrix::MatrixRowSlices;
+
+use crate::{
+    air::{SP1AirBuilder, SyscallAirBuilder},
+    bytes::ByteOpcode,
+    memory::MemoryCols,
+    operations::IsZeroOperation,
+    runtime::{Register, Syscall, SyscallCode},
+};
+
+use super::{
+    columns::{BitManipCols, NUM_BITMANIP_COLS},
+    BitManipChip, BitManipOp,
+};
+
+impl<F> BaseAir<F> for BitManipChip {
+    fn width(&self) -> usize {
+        NUM_BITMANIP_COLS
+    }
+}
+
+impl<AB> Air<AB> for BitManipChip
+where
+    AB: SP1AirBuilder,
+{
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local: &BitManipCols<AB::Var> = main.row_slice(0).borrow();
+
+        // Exactly one operation is selected on real rows.
+        let flags = [
+            (local.is_bswap, BitManipOp::Bswap),
+            (local.is_clz, BitManipOp::Clz),
+            (local.is_ctz, BitManipOp::Ctz),
+            (local.is_popcount, BitManipOp::Popcount),
+        ];
+        builder.assert_bool(local.is_real);
+        let mut is_real = AB::Expr::zero();
+        let mut op = AB::Expr::zero();
+        for (flag, flag_op) in flags {
+            builder.assert_bool(flag);
+            is_real += flag.into();
+            op += flag * AB::F::from_canonical_u32(flag_op as u32);
+        }
+        builder.assert_eq(is_real, local.is_real);
+
+        // The operation must be the value of a1.
+        let op_value = local.op_access.value();
+        builder.when(local.is_real).assert_eq(op_value[0], op);
+        for i in 1..4 {
+            builder.when(local.is_real).assert_zero(op_value[i]);
+        }
+
+        // Look up the statistics of every byte of the input, which also range checks the input.
+        let input = *local.word_access.prev_value();
+        let output = *local.word_access.value();
+        for i in 0..4 {
+            builder.send_byte(
+                ByteOpcode::Popcount.as_field::<AB::F>(),
+                local.popcount[i],
+                input[i],
+                AB::F::zero(),
+                local.is_real,
+            );
+            builder.send_byte(
+                ByteOpcode::CLZ.as_field::<AB::F>(),
+                local.clz[i],
+                input[i],
+                AB::F::zero(),
+                local.is_real,
+            );
+            builder.send_byte(
+                ByteOpcode::CTZ.as_field::<AB::F>(),
+                local.ctz[i],
+                input[i],
+                AB::F::zero(),
+                local.is_real,
+            );
+            IsZeroOperation::<AB::F>::eval(
+                builder,
+                input[i].into(),
+                local.byte_is_zero[i],
+                local.is_real.into(),
+            );
+        }
+
+        // Accumulate the zero flags from both ends of the word.
+        let is_zero = local.byte_is_zero.map(|cols| cols.result);
+        builder
+            .when(local.is_real)
+            .assert_eq(local.high_zero[0], is_zero[3]);
+        builder
+            .when(local.is_real)
+            .assert_eq(local.low_zero[0], is_zero[0]);
+        for i in 1..3 {
+            builder
+                .when(local.is_real)
+                .assert_eq(local.high_zero[i], local.high_zero[i - 1] * is_zero[3 - i]);
+            builder
+                .when(local.is_real)
+                .assert_eq(local.low_zero[i], local.low_zero[i - 1] * is_zero[i]);
+        }
+
+        // A byte only contributes to the zero counts if all the bytes before it are zero.
+        let mut clz: AB::Expr = local.clz[3].into();
+        let mut ctz: AB::Expr = local.ctz[0].into();
+        for i in 0..3 {
+            clz += local.high_zero[i] * local.clz[2 - i];
+            ctz += local.low_zero[i] * local.ctz[i + 1];
+        }
+        let popcount =
+            local.popcount[0] + local.popcount[1] + local.popcount[2] + local.popcount[3];
+
+        // Constrain the output.
+        for i in 0..4 {
+            builder
+                .when(local.is_bswap)
+                .assert_eq(output[i], input[3 - i]);
+        }
+        builder.when(local.is_clz).assert_eq(output[0], clz);
+        builder.when(local.is_ctz).assert_eq(output[0], ctz);
+        builder
+            .when(local.is_popcount)
+            .assert_eq(output[0], popcount);
+        for i in 1..4 {
+            builder
+                .when(local.is_clz + local.is_ctz + local.is_popcount)
+                .assert_zero(output[i]);
+        }
+
+        // Constrain the memory accesses.
+        builder.constraint_memory_access(
+            local.shard,
+            local.clk, // clk + 0 -> Memory
+            AB::F::from_canonical_u32(Register::X11 as u32),
+            &local.op_access,
+            local.is_real,
+        );
+        builder.constraint_memory_access(
+            local.shard,
+            local.clk, // clk + 0 -> Memory
+            local.ptr,
+            &local.word_access,
+            local.is_real,
+        );
+
+        // Receive the syscall from the ECALL which invokes the operation.
+        builder.receive_syscall(
+            local.shard,
+            local.clk + AB::F::from_canonical_u32(self.num_extra_cycles()),
+            AB::F::from_canonical_u32(SyscallCode::BITMANIP as u32),
+            local.ptr,
+            AB::F::zero(),
+            local.is_real,
+        );
+    }
+}
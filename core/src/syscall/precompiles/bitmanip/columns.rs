@@ -0,0 +1,44 @@
+use core::borrow::{Borrow, BorrowMut};
+use core::mem::size_of;
+
+use sp1_derive::AlignedBorrow;
+
+use crate::memory::{MemoryReadCols, MemoryWriteCols};
+use crate::operations::IsZeroOperation;
+
+pub const NUM_BITMANIP_COLS: usize = size_of::<BitManipCols<u8>>();
+
+#[derive(AlignedBorrow, Default, Debug, Clone, Copy)]
+#[repr(C)]
+pub struct BitManipCols<T> {
+    /// Inputs.
+    pub shard: T,
+    pub clk: T,
+    pub ptr: T,
+
+    /// The operation read from a1, as one-hot flags.
+    pub op_access: MemoryReadCols<T>,
+    pub is_bswap: T,
+    pub is_clz: T,
+    pub is_ctz: T,
+    pub is_popcount: T,
+
+    /// The word at `ptr`: the previous value is the input and the new value is the output.
+    pub word_access: MemoryWriteCols<T>,
+
+    /// The per-byte statistics of the input, looked up in the byte table.
+    pub popcount: [T; 4],
+    pub clz: [T; 4],
+    pub ctz: [T; 4],
+
+    /// Whether each byte of the input is zero.
+    pub byte_is_zero: [IsZeroOperation<T>; 4],
+
+    /// `high_zero[i]` is one if the `i + 1` most significant bytes of the input are zero.
+    pub high_zero: [T; 3],
+
+    /// `low_zero[i]` is one if the `i + 1` least significant bytes of the input are zero.
+    pub low_zero: [T; 3],
+
+    pub is_real: T,
+}
@@ -0,0 +1,56 @@
+use crate::{
+    runtime::{Register, Runtime, Syscall},
+    syscall::precompiles::{
+        bitmanip::{BitManipEvent, BitManipOp},
+        SyscallContext,
+    },
+};
+
+use super::BitManipChip;
+
+impl Syscall for BitManipChip {
+    fn execute(&self, rt: &mut SyscallContext) -> u32 {
+        // Read `ptr` from register a0 and the operation from register a1.
+        let ptr = rt.register_unsafe(Register::X10);
+        assert_eq!(
+            ptr % 4,
+            0,
+            "bitmanip pointer {:#x} is not word aligned",
+            ptr
+        );
+        let (op_record, op) = rt.mr(Register::X11 as u32);
+        let op = BitManipOp::from_u32(op).unwrap_or_else(|| panic!("invalid bitmanip op: {}", op));
+
+        // Replace the word at `ptr` by the result of the operation.
+        let input = rt.word_unsafe(ptr);
+        let output = op.apply(input);
+        let word_record = rt.mw(ptr, output);
+
+        let shard = rt.current_shard();
+        let clk = rt.clk;
+        rt.record_mut().bitmanip_events.push(BitManipEvent {
+            shard,
+            clk,
+            ptr,
+            op,
+            input,
+            output,
+            op_record,
+            word_record,
+        });
+
+        ptr
+    }
+
+    fn check(&self, rt: &Runtime) -> Result<(), String> {
+        let ptr = rt.register(Register::X10);
+        if ptr % 4 != 0 {
+            return Err(format!("pointer {:#x} is not word aligned", ptr));
+        }
+        let op = rt.register(Register::X11);
+        match BitManipOp::from_u32(op) {
+            Some(_) => Ok(()),
+            None => Err(format!("invalid op {}", op)),
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use serde::{Deserialize, Serialize};
+
+use crate::syscall::precompiles::{MemoryReadRecord, MemoryWriteRecord};
+
+mod air;
+pub mod columns;
+mod execute;
+mod trace;
+
+/// The bit manipulation performed by the `BITMANIP` precompile, selected by the value of a1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BitManipOp {
+    /// Reverse the order of the bytes.
+    Bswap = 0,
+
+    /// Count the leading zeros.
+    Clz = 1,
+
+    /// Count the trailing zeros.
+    Ctz = 2,
+
+    /// Count the set bits.
+    Popcount = 3,
+}
+
+impl BitManipOp {
+    /// Create an operation from a u32, if it is one.
+    pub fn from_u32(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(BitManipOp::Bswap),
+            1 => Some(BitManipOp::Clz),
+            2 => Some(BitManipOp::Ctz),
+            3 => Some(BitManipOp::Popcount),
+            _ => None,
+        }
+    }
+
+    /// Apply the operation to the given word.
+    pub fn apply(self, value: u32) -> u32 {
+        match self {
+            BitManipOp::Bswap => value.swap_bytes(),
+            BitManipOp::Clz => value.leading_zeros(),
+            BitManipOp::Ctz => value.trailing_zeros(),
+            BitManipOp::Popcount => value.count_ones(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct BitManipEvent {
+    pub shard: u32,
+    pub clk: u32,
+    pub ptr: u32,
+    pub op: BitManipOp,
+    pub input: u32,
+    pub output: u32,
+    pub op_record: MemoryReadRecord,
+    pub word_record: MemoryWriteRecord,
+}
+
+/// A precompile replacing the word at a0 by its byte swap, leading or trailing zero count, or
+/// population count, depending on a1.
+#[derive(Default)]
+pub struct BitManipChip;
+
+impl BitManipChip {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(test)]
+pub mod bitmanip_tests {
+    use crate::lookup::{unbalanced_interactions, InteractionKind};
+    use crate::runtime::{ExecutionError, Instruction, Opcode, Program, Runtime, SyscallCode};
+    use crate::stark::RiscvStark;
+    use crate::utils::{run_test, setup_logger, BabyBearBlake3};
+
+    use super::{BitManipEvent, BitManipOp};
+
+    const OPS: [BitManipOp; 4] = [
+        BitManipOp::Bswap,
+        BitManipOp::Clz,
+        BitManipOp::Ctz,
+        BitManipOp::Popcount,
+    ];
+
+    fn inputs() -> Vec<u32> {
+        let mut inputs = vec![0, u32::MAX, 0x1234_5678, 0x00f0_0f00];
+        inputs.extend((0..32).map(|i| 1 << i));
+        inputs
+    }
+
+    /// A program applying every operation to every input, each in its own memory slot.
+    pub fn bitmanip_program() -> (Program, Vec<(u32, u32)>) {
+        let mut instructions = vec![];
+        let mut expected = vec![];
+        let bitmanip = SyscallCode::BITMANIP as u32;
+        let mut ptr = 100;
+        for input in inputs() {
+            for op in OPS {
+                instructions.extend(vec![
+                    Instruction::new(Opcode::ADD, 29, 0, input, false, true),
+                    Instruction::new(Opcode::ADD, 30, 0, ptr, false, true),
+                    Instruction::new(Opcode::SW, 29, 30, 0, false, true),
+                    Instruction::new(Opcode::ADD, 5, 0, bitmanip, false, true),
+                    Instruction::new(Opcode::ADD, 10, 0, ptr, false, true),
+                    Instruction::new(Opcode::ADD, 11, 0, op as u32, false, true),
+                    Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+                ]);
+                expected.push((ptr, op.apply(input)));
+                ptr += 4;
+            }
+        }
+        (Program::new(instructions, 0, 0), expected)
+    }
+
+    #[test]
+    fn test_bitmanip_apply() {
+        for input in inputs() {
+            assert_eq!(BitManipOp::Bswap.apply(input), input.swap_bytes());
+            assert_eq!(BitManipOp::Clz.apply(input), input.leading_zeros());
+            assert_eq!(BitManipOp::Ctz.apply(input), input.trailing_zeros());
+            assert_eq!(BitManipOp::Popcount.apply(input), input.count_ones());
+        }
+        assert_eq!(BitManipOp::Clz.apply(0), 32);
+        assert_eq!(BitManipOp::Ctz.apply(0), 32);
+        assert_eq!(BitManipOp::Popcount.apply(u32::MAX), 32);
+    }
+
+    /// A program invoking the precompile on the word at `ptr` with the operation `op`.
+    fn invoke(ptr: u32, op: u32) -> Program {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, SyscallCode::BITMANIP as u32, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, ptr, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, op, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+        ];
+        Program::new(instructions, 0, 0)
+    }
+
+    #[test]
+    fn test_bitmanip_from_u32() {
+        for op in OPS {
+            assert_eq!(BitManipOp::from_u32(op as u32), Some(op));
+        }
+        assert_eq!(BitManipOp::from_u32(4), None);
+        assert_eq!(BitManipOp::from_u32(u32::MAX), None);
+    }
+
+    #[test]
+    fn test_bitmanip_invalid_op() {
+        let mut runtime = Runtime::new(invoke(100, 4));
+        assert_eq!(
+            runtime.try_run(),
+            Err(ExecutionError::InvalidSyscall {
+                pc: 12,
+                syscall: SyscallCode::BITMANIP,
+                reason: "invalid op 4".to_string(),
+            })
+        );
+        assert!(runtime.record.bitmanip_events.is_empty());
+    }
+
+    #[test]
+    fn test_bitmanip_misaligned_pointer() {
+        let mut runtime = Runtime::new(invoke(102, BitManipOp::Bswap as u32));
+        assert_eq!(
+            runtime.try_run(),
+            Err(ExecutionError::InvalidSyscall {
+                pc: 12,
+                syscall: SyscallCode::BITMANIP,
+                reason: "pointer 0x66 is not word aligned".to_string(),
+            })
+        );
+        assert!(runtime.record.bitmanip_events.is_empty());
+    }
+
+    #[test]
+    fn test_bitmanip_program_execute() {
+        let (program, expected) = bitmanip_program();
+        let mut runtime = Runtime::new(program);
+        runtime.run();
+        for (ptr, value) in expected {
+            assert_eq!(runtime.word(ptr), value);
+        }
+        assert_eq!(
+            runtime.record.bitmanip_events.len(),
+            inputs().len() * OPS.len()
+        );
+    }
+
+    #[test]
+    fn test_bitmanip_prove_babybear() {
+        setup_logger();
+        let (program, _) = bitmanip_program();
+        run_test(program).unwrap();
+    }
+
+    #[test]
+    fn test_bitmanip_event_without_ecall() {
+        let (program, _) = bitmanip_program();
+        let mut runtime = Runtime::new(program);
+        runtime.run();
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let unbalanced = |record| {
+            unbalanced_interactions::<BabyBearBlake3>(
+                machine.chips(),
+                record,
+                vec![InteractionKind::Syscall],
+            )
+        };
+        assert!(unbalanced(&runtime.record).is_empty());
+
+        // An operation which no ECALL invokes.
+        let mut record = runtime.record;
+        let event = record.bitmanip_events[0];
+        record.bitmanip_events.push(BitManipEvent {
+            clk: event.clk + 100,
+            ..event
+        });
+        let unbalanced = unbalanced(&record);
+        assert_eq!(unbalanced.len(), 1);
+        assert_eq!(unbalanced[0].kind, InteractionKind::Syscall);
+        assert_eq!(unbalanced[0].chip_name, "BitManip");
+        assert_eq!(unbalanced[0].row, record.bitmanip_events.len() - 1);
+    }
+}
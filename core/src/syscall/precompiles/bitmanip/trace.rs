@@ -0,0 +1,106 @@
+use std::borrow::BorrowMut;
+
+use p3_field::PrimeField32;
+use p3_matrix::dense::RowMajorMatrix;
+use tracing::instrument;
+
+use crate::{
+    air::{EventKind, MachineAir},
+    bytes::{ByteLookupEvent, ByteOpcode},
+    runtime::ExecutionRecord,
+    utils::pad_rows,
+};
+
+use super::{
+    columns::{BitManipCols, NUM_BITMANIP_COLS},
+    BitManipChip, BitManipOp,
+};
+
+impl<F: PrimeField32> MachineAir<F> for BitManipChip {
+    fn name(&self) -> String {
+        "BitManip".to_string()
+    }
+
+    fn produces(&self) -> &[EventKind] {
+        &[EventKind::ByteLookup, EventKind::Field]
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::BitManip]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.bitmanip_events.len()
+    }
+
+    #[instrument(name = "generate BitManip trace", skip_all)]
+    fn generate_trace(
+        &self,
+        input: &ExecutionRecord,
+        output: &mut ExecutionRecord,
+    ) -> RowMajorMatrix<F> {
+        let mut rows = Vec::new();
+        let mut new_field_events = Vec::new();
+
+        for event in input.bitmanip_events.iter() {
+            let mut row = [F::zero(); NUM_BITMANIP_COLS];
+            let cols: &mut BitManipCols<F> = row.as_mut_slice().borrow_mut();
+
+            cols.shard = F::from_canonical_u32(event.shard);
+            cols.clk = F::from_canonical_u32(event.clk);
+            cols.ptr = F::from_canonical_u32(event.ptr);
+            cols.is_real = F::one();
+
+            cols.op_access
+                .populate(event.op_record, &mut new_field_events);
+            cols.is_bswap = F::from_bool(event.op == BitManipOp::Bswap);
+            cols.is_clz = F::from_bool(event.op == BitManipOp::Clz);
+            cols.is_ctz = F::from_bool(event.op == BitManipOp::Ctz);
+            cols.is_popcount = F::from_bool(event.op == BitManipOp::Popcount);
+
+            cols.word_access
+                .populate(event.word_record, &mut new_field_events);
+
+            // Look up the statistics of every byte of the input.
+            let bytes = event.input.to_le_bytes();
+            let mut is_zero = [false; 4];
+            for (i, byte) in bytes.into_iter().enumerate() {
+                let (popcount, clz, ctz) = (
+                    byte.count_ones(),
+                    byte.leading_zeros(),
+                    byte.trailing_zeros(),
+                );
+                cols.popcount[i] = F::from_canonical_u32(popcount);
+                cols.clz[i] = F::from_canonical_u32(clz);
+                cols.ctz[i] = F::from_canonical_u32(ctz);
+                is_zero[i] = cols.byte_is_zero[i].populate(byte as u32) == 1;
+
+                output.add_byte_lookup_events(vec![
+                    ByteLookupEvent::new(ByteOpcode::Popcount, popcount, 0, byte as u32, 0),
+                    ByteLookupEvent::new(ByteOpcode::CLZ, clz, 0, byte as u32, 0),
+                    ByteLookupEvent::new(ByteOpcode::CTZ, ctz, 0, byte as u32, 0),
+                ]);
+            }
+
+            // Accumulate the zero flags from both ends of the word.
+            let (mut high_zero, mut low_zero) = (true, true);
+            for i in 0..3 {
+                high_zero &= is_zero[3 - i];
+                low_zero &= is_zero[i];
+                cols.high_zero[i] = F::from_bool(high_zero);
+                cols.low_zero[i] = F::from_bool(low_zero);
+            }
+
+            rows.push(row);
+        }
+        output.add_field_events(&new_field_events);
+
+        pad_rows(&mut rows, || [F::zero(); NUM_BITMANIP_COLS]);
+
+        // Convert the trace to a row major matrix.
+        RowMajorMatrix::new(
+            rows.into_iter().flatten().collect::<Vec<_>>(),
+            NUM_BITMANIP_COLS,
+        )
+    }
+}
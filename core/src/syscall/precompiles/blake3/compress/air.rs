@@ -7,7 +7,8 @@ use super::{
     Blake3CompressInnerChip, G_INDEX, MSG_SCHEDULE, NUM_MSG_WORDS_PER_CALL,
     NUM_STATE_WORDS_PER_CALL, OPERATION_COUNT, ROUND_COUNT,
 };
-use crate::air::{BaseAirBuilder, SP1AirBuilder, WORD_SIZE};
+use crate::air::{BaseAirBuilder, SP1AirBuilder, SyscallAirBuilder, WORD_SIZE};
+use crate::runtime::{Syscall, SyscallCode};
 
 use core::borrow::Borrow;
 use p3_matrix::MatrixRowSlices;
@@ -32,6 +33,20 @@ where
         self.constrain_memory(builder, local);
 
         self.constrain_g_operation(builder, local);
+
+        // Receive the syscall from the ECALL which invokes the compression, on its first call.
+        builder.assert_eq(
+            local.receives_syscall,
+            local.is_round_index_n[0] * local.is_operation_index_n[0] * local.is_real,
+        );
+        builder.receive_syscall(
+            local.segment,
+            local.clk + AB::F::from_canonical_u32(self.num_extra_cycles()),
+            AB::F::from_canonical_u32(SyscallCode::BLAKE3_COMPRESS_INNER as u32),
+            local.state_ptr,
+            AB::F::zero(),
+            local.receives_syscall,
+        );
     }
 }
 
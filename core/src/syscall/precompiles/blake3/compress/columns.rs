@@ -52,4 +52,8 @@ pub struct Blake3CompressInnerCols<T> {
 
     /// Indicates if the current call is real or not.
     pub is_real: T,
+
+    /// Indicates if the current call is the first of a real event, which receives the syscall
+    /// from its ECALL.
+    pub receives_syscall: T,
 }
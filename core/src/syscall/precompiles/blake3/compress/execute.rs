@@ -13,7 +13,8 @@ impl Syscall for Blake3CompressInnerChip {
     }
 
     fn execute(&self, rt: &mut SyscallContext) -> u32 {
-        // TODO: These pointers have to be constrained.
+        // TODO: The message pointer has to be constrained, the state pointer in a0 is handed to the
+        // chip by the ECALL.
         let state_ptr = rt.register_unsafe(Register::X10);
         let message_ptr = rt.register_unsafe(Register::X11);
 
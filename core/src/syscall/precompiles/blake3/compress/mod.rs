@@ -25,6 +25,8 @@ mod columns;
 mod execute;
 mod g;
 mod trace;
+use serde::{Deserialize, Serialize};
+
 use crate::cpu::{MemoryReadRecord, MemoryWriteRecord};
 
 /// The number of `Word`s in the message of the compress inner operation.
@@ -88,7 +90,7 @@ pub(crate) fn g_func(input: [u32; 6]) -> [u32; 4] {
     [a, b, c, d]
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Blake3CompressInnerEvent {
     pub clk: u32,
     pub shard: u32,
@@ -109,42 +111,65 @@ impl Blake3CompressInnerChip {
 
 #[cfg(test)]
 pub mod compress_tests {
+    use std::borrow::BorrowMut;
+
+    use p3_air::BaseAir;
+    use p3_baby_bear::BabyBear;
+    use p3_field::extension::BinomialExtensionField;
+    use p3_field::AbstractField;
+
+    use crate::air::{MachineAir, PublicValues};
+    use crate::runtime::ExecutionRecord;
     use crate::runtime::Instruction;
     use crate::runtime::Opcode;
     use crate::runtime::Register;
+    use crate::runtime::Runtime;
     use crate::runtime::SyscallCode;
+    use crate::stark::{check_constraints, Chip, RiscvAir};
     use crate::utils::run_test;
     use crate::utils::setup_logger;
     use crate::utils::tests::BLAKE3_COMPRESS_ELF;
+    use crate::utils::BabyBearBlake3;
     use crate::Program;
 
-    use super::MSG_SIZE;
+    use super::columns::Blake3CompressInnerCols;
+    use super::{Blake3CompressInnerChip, MSG_SIZE, OPERATION_COUNT, ROUND_COUNT};
 
     /// The number of `Word`s in the state of the compress inner operation.
     const STATE_SIZE: usize = 16;
 
+    /// The address of the state in the programs of the tests.
+    const STATE_PTR: u32 = 100;
+
+    /// The initialization vector of blake3.
+    const IV: [u32; 8] = [
+        0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A, 0x510E527F, 0x9B05688C, 0x1F83D9AB,
+        0x5BE0CD19,
+    ];
+
     pub fn blake3_compress_internal_program() -> Program {
-        let state_ptr = 100;
+        // The i-th word of the state is 1000 + i and the i-th word of the message is 2000 + i,
+        // arbitrary numbers that are easy to spot while debugging.
+        blake3_compress_program(
+            core::array::from_fn(|i| 1000 + i as u32),
+            core::array::from_fn(|i| 2000 + i as u32),
+        )
+    }
+
+    /// A program which stores the state and the message in memory and compresses them.
+    pub fn blake3_compress_program(state: [u32; STATE_SIZE], message: [u32; MSG_SIZE]) -> Program {
+        let state_ptr = STATE_PTR;
         let msg_ptr = 500;
         let mut instructions = vec![];
 
-        for i in 0..STATE_SIZE {
-            // Store 1000 + i in memory for the i-th word of the state. 1000 + i is an arbitrary
-            // number that is easy to spot while debugging.
-            instructions.extend(vec![
-                Instruction::new(Opcode::ADD, 29, 0, 1000 + i as u32, false, true),
-                Instruction::new(Opcode::ADD, 30, 0, state_ptr + i as u32 * 4, false, true),
-                Instruction::new(Opcode::SW, 29, 30, 0, false, true),
-            ]);
-        }
-        for i in 0..MSG_SIZE {
-            // Store 2000 + i in memory for the i-th word of the message. 2000 + i is an arbitrary
-            // number that is easy to spot while debugging.
-            instructions.extend(vec![
-                Instruction::new(Opcode::ADD, 29, 0, 2000 + i as u32, false, true),
-                Instruction::new(Opcode::ADD, 30, 0, msg_ptr + i as u32 * 4, false, true),
-                Instruction::new(Opcode::SW, 29, 30, 0, false, true),
-            ]);
+        for (ptr, words) in [(state_ptr, &state[..]), (msg_ptr, &message[..])] {
+            for (i, word) in words.iter().enumerate() {
+                instructions.extend(vec![
+                    Instruction::new(Opcode::ADD, 29, 0, *word, false, true),
+                    Instruction::new(Opcode::ADD, 30, 0, ptr + i as u32 * 4, false, true),
+                    Instruction::new(Opcode::SW, 29, 30, 0, false, true),
+                ]);
+            }
         }
         instructions.extend(vec![
             Instruction::new(
@@ -169,6 +194,102 @@ pub mod compress_tests {
         run_test(program).unwrap();
     }
 
+    /// Hashes an input of at most one block, which is a single compression of the block with the
+    /// flags of the start and the end of the root chunk, with the precompile.
+    fn hash_single_block(input: &[u8]) -> [u8; 32] {
+        const CHUNK_START: u32 = 1;
+        const CHUNK_END: u32 = 2;
+        const ROOT: u32 = 8;
+
+        let mut block = [0u8; 64];
+        block[..input.len()].copy_from_slice(input);
+        let message = core::array::from_fn(|i| {
+            u32::from_le_bytes(block[4 * i..4 * i + 4].try_into().unwrap())
+        });
+        let mut state = [0u32; STATE_SIZE];
+        state[..8].copy_from_slice(&IV);
+        state[8..12].copy_from_slice(&IV[..4]);
+        state[14] = input.len() as u32;
+        state[15] = CHUNK_START | CHUNK_END | ROOT;
+
+        let mut runtime = Runtime::new(blake3_compress_program(state, message));
+        runtime.run();
+        let state = (0..STATE_SIZE as u32)
+            .map(|i| runtime.word(STATE_PTR + 4 * i))
+            .collect::<Vec<_>>();
+
+        // The output of the compression folds the second half of the state into the first one.
+        let mut hash = [0u8; 32];
+        for i in 0..8 {
+            hash[4 * i..4 * i + 4].copy_from_slice(&(state[i] ^ state[i + 8]).to_le_bytes());
+        }
+        hash
+    }
+
+    #[test]
+    fn test_blake3_reference() {
+        for input in [&b""[..], &b"abc"[..], &[0x5a; 64][..]] {
+            assert_eq!(
+                hash_single_block(input),
+                *blake3::hash(input).as_bytes(),
+                "input of {} bytes",
+                input.len()
+            );
+        }
+    }
+
+    #[test]
+    fn test_generated_trace_constraints() {
+        type EF = BinomialExtensionField<BabyBear, 4>;
+
+        let mut runtime = Runtime::new(blake3_compress_internal_program());
+        runtime.run();
+        let chip = Chip::new(RiscvAir::<BabyBear>::Blake3Compress(
+            Blake3CompressInnerChip::new(),
+        ));
+        let mut trace = chip.generate_trace(&runtime.record, &mut ExecutionRecord::default());
+        // The rows of the event and the padding, whose flags continue the calls of `g`.
+        assert_eq!(trace.values.len(), 64 * chip.width());
+        let challenges = [EF::from_canonical_u32(3), EF::from_canonical_u32(7)];
+        let public_values = PublicValues::from_record(&runtime.record).to_field_elements();
+
+        let perm = chip.generate_permutation_trace(None, &trace, &challenges);
+        check_constraints::<BabyBearBlake3>(
+            &chip,
+            None,
+            &trace,
+            &perm,
+            &challenges,
+            &public_values,
+        )
+        .unwrap();
+
+        // The padding row after the last call of `g` is the first call of the next round.
+        let padding: &mut Blake3CompressInnerCols<BabyBear> = trace.values
+            [ROUND_COUNT * OPERATION_COUNT * chip.width()..][..chip.width()]
+            .borrow_mut();
+        assert_eq!(padding.is_real, BabyBear::zero());
+        assert_eq!(padding.is_operation_index_n[0], BabyBear::one());
+
+        // Tamper with the result of the last call of `g`.
+        let row = ROUND_COUNT * OPERATION_COUNT - 1;
+        let cols: &mut Blake3CompressInnerCols<BabyBear> =
+            trace.values[row * chip.width()..][..chip.width()].borrow_mut();
+        cols.g.result[0][0] += BabyBear::one();
+        let perm = chip.generate_permutation_trace(None, &trace, &challenges);
+        let failure = check_constraints::<BabyBearBlake3>(
+            &chip,
+            None,
+            &trace,
+            &perm,
+            &challenges,
+            &public_values,
+        )
+        .unwrap_err();
+        assert_eq!(failure.chip, "Blake3CompressInner");
+        assert_eq!(failure.row, row);
+    }
+
     #[test]
     fn test_blake3_compress_inner_elf() {
         setup_logger();
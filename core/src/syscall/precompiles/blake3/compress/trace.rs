@@ -1,15 +1,18 @@
 use std::borrow::BorrowMut;
 
 use crate::cpu::MemoryRecordEnum;
+use crate::field::event::FieldEvent;
 use crate::runtime::ExecutionRecord;
 use crate::syscall::precompiles::blake3::compress::columns::NUM_BLAKE3_COMPRESS_INNER_COLS;
-use crate::syscall::precompiles::blake3::{Blake3CompressInnerChip, ROUND_COUNT};
-use crate::utils::pad_rows;
+use crate::syscall::precompiles::blake3::{
+    Blake3CompressInnerChip, Blake3CompressInnerEvent, ROUND_COUNT,
+};
+use crate::syscall::precompiles::{generate_round_trace, round_flag_rows};
 
 use p3_field::PrimeField;
 use p3_matrix::dense::RowMajorMatrix;
 
-use crate::air::MachineAir;
+use crate::air::{EventKind, MachineAir};
 
 use super::columns::Blake3CompressInnerCols;
 use super::{
@@ -22,95 +25,112 @@ impl<F: PrimeField> MachineAir<F> for Blake3CompressInnerChip {
         "Blake3CompressInner".to_string()
     }
 
+    fn produces(&self) -> &[EventKind] {
+        &[EventKind::ByteLookup, EventKind::Field]
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::Blake3CompressInner]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.blake3_compress_inner_events.len() * ROUND_COUNT * OPERATION_COUNT
+    }
+
     fn generate_trace(
         &self,
         input: &ExecutionRecord,
         output: &mut ExecutionRecord,
     ) -> RowMajorMatrix<F> {
-        let mut rows = Vec::new();
-
-        let mut new_field_events = Vec::new();
-
-        for i in 0..input.blake3_compress_inner_events.len() {
-            let event = input.blake3_compress_inner_events[i];
-
-            let mut clk = event.clk;
-            for round in 0..ROUND_COUNT {
-                for operation in 0..OPERATION_COUNT {
-                    let mut row = [F::zero(); NUM_BLAKE3_COMPRESS_INNER_COLS];
-                    let cols: &mut Blake3CompressInnerCols<F> = row.as_mut_slice().borrow_mut();
-
-                    // Assign basic values to the columns.
-                    {
-                        cols.segment = F::from_canonical_u32(event.shard);
-                        cols.clk = F::from_canonical_u32(clk);
-
-                        cols.round_index = F::from_canonical_u32(round as u32);
-                        cols.is_round_index_n[round] = F::one();
-
-                        cols.operation_index = F::from_canonical_u32(operation as u32);
-                        cols.is_operation_index_n[operation] = F::one();
-
-                        for i in 0..NUM_STATE_WORDS_PER_CALL {
-                            cols.state_index[i] = F::from_canonical_usize(G_INDEX[operation][i]);
-                        }
-
-                        for i in 0..NUM_MSG_WORDS_PER_CALL {
-                            cols.msg_schedule[i] =
-                                F::from_canonical_usize(MSG_SCHEDULE[round][2 * operation + i]);
-                        }
-                    }
-
-                    // Memory columns.
-                    {
-                        cols.message_ptr = F::from_canonical_u32(event.message_ptr);
-                        for i in 0..NUM_MSG_WORDS_PER_CALL {
-                            cols.message_reads[i].populate(
-                                event.message_reads[round][operation][i],
-                                &mut new_field_events,
-                            );
-                        }
-
-                        cols.state_ptr = F::from_canonical_u32(event.state_ptr);
-                        for i in 0..NUM_STATE_WORDS_PER_CALL {
-                            cols.state_reads_writes[i].populate(
-                                MemoryRecordEnum::Write(event.state_writes[round][operation][i]),
-                                &mut new_field_events,
-                            );
-                        }
-                    }
-
-                    // Apply the `g` operation.
-                    {
-                        let input: [u32; G_INPUT_SIZE] = [
-                            event.state_writes[round][operation][0].prev_value,
-                            event.state_writes[round][operation][1].prev_value,
-                            event.state_writes[round][operation][2].prev_value,
-                            event.state_writes[round][operation][3].prev_value,
-                            event.message_reads[round][operation][0].value,
-                            event.message_reads[round][operation][1].value,
-                        ];
-
-                        cols.g.populate(output, input);
-                    }
-
-                    clk += 4;
-
-                    cols.is_real = F::one();
-
-                    rows.push(row);
-                }
-            }
+        let mut trace = RowMajorMatrix::new(Vec::new(), NUM_BLAKE3_COMPRESS_INNER_COLS);
+        self.generate_trace_into(input, output, &mut trace);
+        trace
+    }
+
+    fn generate_trace_into(
+        &self,
+        input: &ExecutionRecord,
+        output: &mut ExecutionRecord,
+        trace: &mut RowMajorMatrix<F>,
+    ) {
+        generate_round_trace(
+            &input.blake3_compress_inner_events,
+            &flag_rows::<F>(),
+            populate_row,
+            output,
+            trace,
+        );
+    }
+}
+
+/// The rows with only the columns which depend on the call of `g` populated, one for each call of
+/// an event.
+fn flag_rows<F: PrimeField>() -> Vec<[F; NUM_BLAKE3_COMPRESS_INNER_COLS]> {
+    round_flag_rows(ROUND_COUNT * OPERATION_COUNT, |row, call| {
+        let cols: &mut Blake3CompressInnerCols<F> = row.borrow_mut();
+        let (round, operation) = (call / OPERATION_COUNT, call % OPERATION_COUNT);
+
+        cols.round_index = F::from_canonical_u32(round as u32);
+        cols.is_round_index_n[round] = F::one();
+
+        cols.operation_index = F::from_canonical_u32(operation as u32);
+        cols.is_operation_index_n[operation] = F::one();
+
+        for i in 0..NUM_STATE_WORDS_PER_CALL {
+            cols.state_index[i] = F::from_canonical_usize(G_INDEX[operation][i]);
+        }
+
+        for i in 0..NUM_MSG_WORDS_PER_CALL {
+            cols.msg_schedule[i] = F::from_canonical_usize(MSG_SCHEDULE[round][2 * operation + i]);
         }
+    })
+}
 
-        output.add_field_events(&new_field_events);
+/// Populates the row of the `call`-th call of `g` of an event over its flags.
+fn populate_row<F: PrimeField>(
+    event: &Blake3CompressInnerEvent,
+    call: usize,
+    row: &mut [F],
+    output: &mut ExecutionRecord,
+    new_field_events: &mut Vec<FieldEvent>,
+) {
+    let cols: &mut Blake3CompressInnerCols<F> = row.borrow_mut();
+    let (round, operation) = (call / OPERATION_COUNT, call % OPERATION_COUNT);
+
+    cols.segment = F::from_canonical_u32(event.shard);
+    cols.clk = F::from_canonical_u32(event.clk + 4 * call as u32);
+
+    // Memory columns.
+    {
+        cols.message_ptr = F::from_canonical_u32(event.message_ptr);
+        for i in 0..NUM_MSG_WORDS_PER_CALL {
+            cols.message_reads[i]
+                .populate(event.message_reads[round][operation][i], new_field_events);
+        }
 
-        pad_rows(&mut rows, || [F::zero(); NUM_BLAKE3_COMPRESS_INNER_COLS]);
+        cols.state_ptr = F::from_canonical_u32(event.state_ptr);
+        for i in 0..NUM_STATE_WORDS_PER_CALL {
+            cols.state_reads_writes[i].populate(
+                MemoryRecordEnum::Write(event.state_writes[round][operation][i]),
+                new_field_events,
+            );
+        }
+    }
 
-        // Convert the trace to a row major matrix.
-        RowMajorMatrix::new(
-            rows.into_iter().flatten().collect::<Vec<_>>(),
-            NUM_BLAKE3_COMPRESS_INNER_COLS,
-        )
+    // Apply the `g` operation.
+    {
+        let input: [u32; G_INPUT_SIZE] = [
+            event.state_writes[round][operation][0].prev_value,
+            event.state_writes[round][operation][1].prev_value,
+            event.state_writes[round][operation][2].prev_value,
+            event.state_writes[round][operation][3].prev_value,
+            event.message_reads[round][operation][0].value,
+            event.message_reads[round][operation][1].value,
+        ];
+
+        cols.g.populate(output, input);
     }
+
+    cols.is_real = F::one();
+    cols.receives_syscall = F::from_bool(call == 0);
 }
@@ -1,5 +1,5 @@
-use crate::air::MachineAir;
-use crate::air::SP1AirBuilder;
+use crate::air::{EventKind, MachineAir};
+use crate::air::{SP1AirBuilder, SyscallAirBuilder};
 use crate::field::event::FieldEvent;
 use crate::memory::MemoryCols;
 use crate::memory::MemoryReadCols;
@@ -11,7 +11,7 @@ use crate::operations::field::field_op::FieldOperation;
 use crate::operations::field::params::Limbs;
 use crate::operations::field::params::NUM_LIMBS;
 use crate::runtime::ExecutionRecord;
-use crate::runtime::Syscall;
+use crate::runtime::{Syscall, SyscallCode};
 use crate::syscall::precompiles::create_ec_add_event;
 use crate::syscall::precompiles::SyscallContext;
 use crate::utils::ec::edwards::EdwardsParameters;
@@ -126,6 +126,18 @@ impl<F: PrimeField32, E: EllipticCurve + EdwardsParameters> MachineAir<F> for Ed
         "EdAddAssign".to_string()
     }
 
+    fn produces(&self) -> &[EventKind] {
+        &[EventKind::Field]
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::EdAdd]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.ed_add_events.len()
+    }
+
     #[instrument(name = "generate Ed Add trace", skip_all)]
     fn generate_trace(
         &self,
@@ -284,6 +296,16 @@ where
                 row.is_real,
             );
         }
+
+        // Receive the syscall from the ECALL which invokes the addition.
+        builder.receive_syscall(
+            row.shard,
+            row.clk + AB::F::from_canonical_u32(self.num_extra_cycles()),
+            AB::F::from_canonical_u32(SyscallCode::ED_ADD as u32),
+            row.p_ptr,
+            AB::F::zero(),
+            row.is_real,
+        );
     }
 }
 
@@ -309,4 +331,37 @@ mod tests {
         utils::setup_logger();
         SP1Prover::prove(ED25519_ELF, SP1Stdin::new()).unwrap();
     }
+
+    /// The guest verifies a real signature with the patched ed25519-dalek, which goes through the
+    /// add and decompress precompiles, and the host agrees with it using ed25519-dalek itself.
+    #[test]
+    fn test_ed25519_signature_matches_host() {
+        use ed25519_dalek::{Signature, VerifyingKey};
+
+        use crate::runtime::{Program, Runtime};
+        use crate::SP1Verifier;
+
+        // The key, message and signature checked by the guest.
+        let pub_bytes: [u8; 32] =
+            hex::decode("ec172b93ad5e563bf4932c70e1245034c35467ef2efd4d64ebf819683467e2bf")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let msg_bytes = hex::decode(
+            "616263616263616263616263616263616263616263616263616263616263616263616263616263",
+        )
+        .unwrap();
+        let sig_bytes = hex::decode("46557EFE96D22D07E104D9D7FAB558FB02F6B13116056E6D7C300D7BB132059907D538EAC68EC7864AA2AC2E23EA7082A04002B0ACDAC2FF8CCAD7E80E64DD00").unwrap();
+        let verifying_key = VerifyingKey::from_bytes(&pub_bytes).unwrap();
+        let signature = Signature::try_from(&sig_bytes[..]).unwrap();
+        verifying_key.verify_strict(&msg_bytes, &signature).unwrap();
+
+        let mut runtime = Runtime::new(Program::from(ED25519_ELF));
+        runtime.run();
+        assert!(!runtime.record.ed_add_events.is_empty());
+        assert!(!runtime.record.ed_decompress_events.is_empty());
+
+        let proof = SP1Prover::prove(ED25519_ELF, SP1Stdin::new()).unwrap();
+        SP1Verifier::verify(ED25519_ELF, &proof).unwrap();
+    }
 }
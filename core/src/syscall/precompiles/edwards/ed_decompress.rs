@@ -1,7 +1,7 @@
 use crate::air::BaseAirBuilder;
-use crate::air::MachineAir;
-use crate::air::SP1AirBuilder;
 use crate::air::WORD_SIZE;
+use crate::air::{EventKind, MachineAir};
+use crate::air::{SP1AirBuilder, SyscallAirBuilder};
 use crate::cpu::MemoryReadRecord;
 use crate::cpu::MemoryWriteRecord;
 use crate::memory::MemoryReadCols;
@@ -10,7 +10,7 @@ use crate::operations::field::field_op::FieldOpCols;
 use crate::operations::field::field_op::FieldOperation;
 use crate::operations::field::field_sqrt::FieldSqrtCols;
 use crate::runtime::ExecutionRecord;
-use crate::runtime::Syscall;
+use crate::runtime::{Syscall, SyscallCode};
 use crate::syscall::precompiles::SyscallContext;
 use crate::utils::bytes_to_words_le;
 use crate::utils::ec::edwards::ed25519::decompress;
@@ -37,10 +37,11 @@ use p3_matrix::MatrixRowSlices;
 use std::marker::PhantomData;
 
 use p3_matrix::dense::RowMajorMatrix;
+use serde::{Deserialize, Serialize};
 use sp1_derive::AlignedBorrow;
 use std::fmt::Debug;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct EdDecompressEvent {
     pub shard: u32,
     pub clk: u32,
@@ -75,6 +76,7 @@ pub struct EdDecompressCols<T> {
     pub(crate) u_div_v: FieldOpCols<T>,
     pub(crate) x: FieldSqrtCols<T>,
     pub(crate) neg_x: FieldOpCols<T>,
+    pub(crate) x_least_bits: [T; 8],
 }
 
 impl<F: PrimeField32> EdDecompressCols<F> {
@@ -111,6 +113,12 @@ impl<F: PrimeField32> EdDecompressCols<F> {
         let x = self.x.populate::<P>(&u_div_v, ed25519_sqrt);
         self.neg_x
             .populate::<P>(&BigUint::zero(), &x, FieldOperation::Sub);
+        // Decompose bits of least significant X byte
+        let x_bytes = x.to_bytes_le();
+        let x_lsb = if x_bytes.is_empty() { 0 } else { x_bytes[0] };
+        for i in 0..8 {
+            self.x_least_bits[i] = F::from_canonical_u32(((x_lsb >> i) & 1) as u32);
+        }
     }
 }
 
@@ -178,6 +186,26 @@ impl<V: Copy> EdDecompressCols<V> {
             );
         }
 
+        // Constrain decomposition of least significant byte of X into `x_least_bits`
+        for i in 0..8 {
+            builder.when(self.is_real).assert_bool(self.x_least_bits[i]);
+        }
+        let x_least_byte = self.x.multiplication.result.0[0];
+        let powers_of_two = [1, 2, 4, 8, 16, 32, 64, 128].map(AB::F::from_canonical_u32);
+        let recomputed_byte: AB::Expr = self
+            .x_least_bits
+            .iter()
+            .zip(powers_of_two)
+            .map(|(p, b)| (*p).into() * b)
+            .sum();
+        builder
+            .when(self.is_real)
+            .assert_eq(recomputed_byte, x_least_byte);
+
+        // The square root is the even one, so that the sign bit alone picks between X and -X. When
+        // X is zero, both are zero and a set sign bit still gives zero.
+        builder.when(self.is_real).assert_zero(self.x_least_bits[0]);
+
         let x_limbs = limbs_from_access(&self.x_access);
         builder
             .when(self.is_real)
@@ -201,7 +229,7 @@ impl<E: EdwardsParameters> Syscall for EdDecompressChip<E> {
 
         let start_clk = rt.clk;
 
-        // TODO: this will have to be be constrained, but can do it later.
+        // The pointer in a0 is handed to the chip by the ECALL.
         let slice_ptr = rt.register_unsafe(a0);
         if slice_ptr % 4 != 0 {
             panic!();
@@ -276,6 +304,18 @@ impl<F: PrimeField32, E: EdwardsParameters> MachineAir<F> for EdDecompressChip<E
         "EdDecompress".to_string()
     }
 
+    fn produces(&self) -> &[EventKind] {
+        &[EventKind::Field]
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::EdDecompress]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.ed_decompress_events.len()
+    }
+
     fn generate_trace(
         &self,
         input: &ExecutionRecord,
@@ -321,6 +361,16 @@ where
         let main = builder.main();
         let row: &EdDecompressCols<AB::Var> = main.row_slice(0).borrow();
         row.eval::<AB, E::BaseField, E>(builder);
+
+        // Receive the syscall from the ECALL which invokes the decompression.
+        builder.receive_syscall(
+            row.shard,
+            row.clk + AB::F::from_canonical_u32(self.num_extra_cycles()),
+            AB::F::from_canonical_u32(SyscallCode::ED_DECOMPRESS as u32),
+            row.ptr,
+            AB::F::zero(),
+            row.is_real,
+        );
     }
 }
 
@@ -1,7 +1,7 @@
 use crate::air::BaseAirBuilder;
-use crate::air::MachineAir;
-use crate::air::SP1AirBuilder;
 use crate::air::Word;
+use crate::air::{EventKind, MachineAir};
+use crate::air::{SP1AirBuilder, SyscallAirBuilder};
 use crate::cpu::MemoryReadRecord;
 use crate::cpu::MemoryWriteRecord;
 use crate::memory::MemoryReadCols;
@@ -10,7 +10,7 @@ use crate::operations::field::field_op::FieldOpCols;
 use crate::operations::field::field_op::FieldOperation;
 use crate::operations::field::field_sqrt::FieldSqrtCols;
 use crate::runtime::ExecutionRecord;
-use crate::runtime::Syscall;
+use crate::runtime::{Syscall, SyscallCode};
 use crate::syscall::precompiles::SyscallContext;
 use crate::utils::bytes_to_words_le;
 use crate::utils::ec::field::FieldParameters;
@@ -40,10 +40,11 @@ use p3_matrix::MatrixRowSlices;
 use std::str::FromStr;
 
 use p3_matrix::dense::RowMajorMatrix;
+use serde::{Deserialize, Serialize};
 use sp1_derive::AlignedBorrow;
 use std::fmt::Debug;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct K256DecompressEvent {
     pub shard: u32,
     pub clk: u32,
@@ -80,7 +81,7 @@ impl Syscall for K256DecompressChip {
 
         let start_clk = rt.clk;
 
-        // TODO: this will have to be be constrained, but can do it later.
+        // The pointer in a0 is handed to the chip by the ECALL.
         let slice_ptr = rt.register_unsafe(a0);
         if slice_ptr % 4 != 0 {
             panic!();
@@ -290,6 +291,18 @@ impl<F: PrimeField32> MachineAir<F> for K256DecompressChip {
         "K256Decompress".to_string()
     }
 
+    fn produces(&self) -> &[EventKind] {
+        &[EventKind::Field]
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::K256Decompress]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.k256_decompress_events.len()
+    }
+
     fn generate_trace(
         &self,
         input: &ExecutionRecord,
@@ -352,6 +365,16 @@ where
         let main = builder.main();
         let row: &K256DecompressCols<AB::Var> = main.row_slice(0).borrow();
         row.eval::<AB>(builder);
+
+        // Receive the syscall from the ECALL which invokes the decompression.
+        builder.receive_syscall(
+            row.shard,
+            row.clk + AB::F::from_canonical_u32(self.num_extra_cycles()),
+            AB::F::from_canonical_u32(SyscallCode::SECP256K1_DECOMPRESS as u32),
+            row.ptr,
+            AB::F::zero(),
+            row.is_real,
+        );
     }
 }
 
@@ -362,8 +385,8 @@ pub mod tests {
     use rand::rngs::StdRng;
     use rand::SeedableRng;
 
-    use crate::utils::setup_logger;
-    use crate::utils::tests::SECP256K1_DECOMPRESS_ELF;
+    use crate::utils::tests::{ECRECOVER_ELF, SECP256K1_DECOMPRESS_ELF};
+    use crate::utils::{setup_logger, BabyBearBlake3};
     use crate::{SP1Prover, SP1Stdin, SP1Verifier};
 
     #[test]
@@ -388,4 +411,21 @@ pub mod tests {
             SP1Verifier::verify(SECP256K1_DECOMPRESS_ELF, &proof).unwrap();
         }
     }
+
+    /// Recovers the public key of an ECDSA signature under the fast config, which exercises the
+    /// secp256k1 chips end to end.
+    #[test]
+    fn test_ecrecover() {
+        setup_logger();
+        let mut proof =
+            SP1Prover::prove_with_config(ECRECOVER_ELF, SP1Stdin::new(), BabyBearBlake3::new())
+                .unwrap();
+        let mut pubkey = [0; 65];
+        proof.stdout.read_slice(&mut pubkey);
+        assert_eq!(
+            pubkey.to_vec(),
+            hex::decode("044a071e8a6e10aada2b8cf39fa3b5fb3400b04e99ea8ae64ceea1a977dbeaf5d5f8c8fbd10b71ab14cd561f7df8eb6da50f8a8d81ba564342244d26d1d4211595").unwrap()
+        );
+        SP1Verifier::verify_with_config(ECRECOVER_ELF, &proof, BabyBearBlake3::new()).unwrap();
+    }
 }
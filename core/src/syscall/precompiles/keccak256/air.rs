@@ -6,8 +6,9 @@ use p3_keccak_air::{KeccakAir, KeccakCols, NUM_KECCAK_COLS, U64_LIMBS};
 use p3_matrix::MatrixRowSlices;
 
 use crate::{
-    air::{SP1AirBuilder, SubAirBuilder},
+    air::{SP1AirBuilder, SubAirBuilder, SyscallAirBuilder},
     memory::MemoryCols,
+    runtime::{Syscall, SyscallCode},
 };
 
 use super::{
@@ -85,6 +86,20 @@ where
             }
         }
 
+        // Receive the syscall from the ECALL which invokes the permutation, on its first row.
+        builder.assert_eq(
+            local_mem.receives_syscall,
+            local_keccak.step_flags[0] * local_mem.is_real,
+        );
+        builder.receive_syscall(
+            local_mem.shard,
+            local_mem.clk + AB::F::from_canonical_u32(self.num_extra_cycles()),
+            AB::F::from_canonical_u32(SyscallCode::KECCAK_PERMUTE as u32),
+            local_mem.state_addr,
+            AB::F::zero(),
+            local_mem.receives_syscall,
+        );
+
         let mut sub_builder =
             SubAirBuilder::<AB, KeccakAir, AB::Var>::new(builder, 0..NUM_KECCAK_COLS);
 
@@ -19,6 +19,10 @@ pub(crate) struct KeccakMemCols<T> {
     pub do_memory_check: T,
 
     pub is_real: T,
+
+    /// Whether the row is the first of a real permutation, which receives the syscall from its
+    /// ECALL.
+    pub receives_syscall: T,
 }
 
 pub const NUM_KECCAK_MEM_COLS: usize = size_of::<KeccakMemCols<u8>>();
@@ -1,6 +1,7 @@
 use crate::syscall::precompiles::{MemoryReadRecord, MemoryWriteRecord};
 
 use p3_keccak_air::KeccakAir;
+use serde::{Deserialize, Serialize};
 
 mod air;
 pub mod columns;
@@ -12,13 +13,21 @@ const STATE_SIZE: usize = 25;
 // The permutation state is 25 u64's.  Our word size is 32 bits, so it is 50 words.
 const STATE_NUM_WORDS: usize = 25 * 2;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct KeccakPermuteEvent {
     pub shard: u32,
     pub clk: u32,
     pub pre_state: [u64; STATE_SIZE],
     pub post_state: [u64; STATE_SIZE],
+    #[serde(
+        serialize_with = "crate::utils::codec::serialize_array",
+        deserialize_with = "crate::utils::codec::deserialize_array"
+    )]
     pub state_read_records: [MemoryReadRecord; STATE_NUM_WORDS],
+    #[serde(
+        serialize_with = "crate::utils::codec::serialize_array",
+        deserialize_with = "crate::utils::codec::deserialize_array"
+    )]
     pub state_write_records: [MemoryWriteRecord; STATE_NUM_WORDS],
     pub state_addr: u32,
 }
@@ -37,12 +46,17 @@ impl KeccakPermuteChip {
 
 #[cfg(test)]
 pub mod permute_tests {
+    use p3_baby_bear::BabyBear;
+
+    use crate::air::MachineAir;
     use crate::utils::run_test;
     use crate::{
-        runtime::{Instruction, Opcode, Program, Runtime},
+        runtime::{ExecutionRecord, Instruction, Opcode, Program, Runtime},
         utils::{self, tests::KECCAK_PERMUTE_ELF},
     };
 
+    use super::KeccakPermuteChip;
+
     pub fn keccak_permute_program() -> Program {
         let digest_ptr = 100;
         let mut instructions = vec![Instruction::new(Opcode::ADD, 29, 0, 1, false, true)];
@@ -82,4 +96,51 @@ pub mod permute_tests {
         let program = Program::from(KECCAK_PERMUTE_ELF);
         run_test(program).unwrap();
     }
+
+    #[test]
+    fn test_keccak_permute_matches_reference() {
+        let mut runtime = Runtime::new(Program::from(KECCAK_PERMUTE_ELF));
+        runtime.run();
+
+        // The guest permutes a state of ones 25 times.
+        let mut expected = [1u64; 25];
+        tiny_keccak::keccakf(&mut expected);
+        let events = &runtime.record.keccak_permute_events;
+        assert_eq!(events.len(), 25);
+        assert!(events
+            .iter()
+            .all(|event| event.pre_state == [1; 25] && event.post_state == expected));
+
+        let chip = KeccakPermuteChip::new();
+        assert!(MachineAir::<BabyBear>::included(&chip, &runtime.record));
+        assert!(!MachineAir::<BabyBear>::included(
+            &chip,
+            &ExecutionRecord::default()
+        ));
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut runtime = Runtime::new(keccak_permute_program());
+        runtime.run();
+        let record = runtime.record;
+        let chip = KeccakPermuteChip::new();
+        MachineAir::<BabyBear>::validate(&chip, &record).unwrap();
+
+        let mut wrong_post_state = record.clone();
+        wrong_post_state.keccak_permute_events[0].post_state[0] ^= 1;
+        let error = MachineAir::<BabyBear>::validate(&chip, &wrong_post_state).unwrap_err();
+        assert_eq!((error.chip.as_str(), error.index), ("KeccakPermute", 0));
+        assert_eq!(
+            error.reason,
+            "the post state is not the permutation of the pre state"
+        );
+
+        let mut wrong_shard = record;
+        wrong_shard.keccak_permute_events[0].state_read_records[5].shard += 1;
+        let error = MachineAir::<BabyBear>::validate(&chip, &wrong_shard).unwrap_err();
+        assert!(error
+            .reason
+            .starts_with("read of state word 5: accessed at"));
+    }
 }
@@ -9,12 +9,15 @@ use p3_maybe_rayon::prelude::{ParallelIterator, ParallelSlice};
 use tracing::instrument;
 
 use crate::{
-    air::MachineAir, runtime::ExecutionRecord, syscall::precompiles::keccak256::STATE_SIZE,
+    air::{EventKind, EventValidationError, MachineAir},
+    cpu::MemoryRecordEnum,
+    runtime::ExecutionRecord,
+    syscall::precompiles::keccak256::STATE_SIZE,
 };
 
 use super::{
     columns::{KeccakMemCols, NUM_KECCAK_MEM_COLS},
-    KeccakPermuteChip,
+    KeccakPermuteChip, KeccakPermuteEvent,
 };
 
 impl<F: PrimeField32> MachineAir<F> for KeccakPermuteChip {
@@ -22,6 +25,27 @@ impl<F: PrimeField32> MachineAir<F> for KeccakPermuteChip {
         "KeccakPermute".to_string()
     }
 
+    fn produces(&self) -> &[EventKind] {
+        &[EventKind::Field]
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::KeccakPermute]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.keccak_permute_events.len() * NUM_ROUNDS
+    }
+
+    fn validate(&self, input: &ExecutionRecord) -> Result<(), EventValidationError> {
+        for (index, event) in input.keccak_permute_events.iter().enumerate() {
+            validate_event(event).map_err(|reason| {
+                EventValidationError::new(<Self as MachineAir<F>>::name(self), index, reason)
+            })?;
+        }
+        Ok(())
+    }
+
     #[instrument(name = "generate KeccakPermute trace", skip_all)]
     fn generate_trace(
         &self,
@@ -112,6 +136,7 @@ impl<F: PrimeField32> MachineAir<F> for KeccakPermuteChip {
 
                                 col.state_addr = F::from_canonical_u32(event.unwrap().state_addr);
                                 col.do_memory_check = F::one();
+                                col.receives_syscall = F::one();
                             }
 
                             // if this is the last row, then populate write memory accesses
@@ -158,3 +183,50 @@ impl<F: PrimeField32> MachineAir<F> for KeccakPermuteChip {
         )
     }
 }
+
+/// Check that an event reads the state on its first round and writes the permuted state over it
+/// on its last round.
+fn validate_event(event: &KeccakPermuteEvent) -> Result<(), String> {
+    let mut post_state = event.pre_state;
+    tiny_keccak::keccakf(&mut post_state);
+    if post_state != event.post_state {
+        return Err("the post state is not the permutation of the pre state".to_string());
+    }
+
+    let write_clk = event.clk + 4 * (NUM_ROUNDS as u32 - 1);
+    for (i, (read, write)) in event
+        .state_read_records
+        .iter()
+        .zip(event.state_write_records.iter())
+        .enumerate()
+    {
+        let shift = 32 * (i % 2);
+        let pre = (event.pre_state[i / 2] >> shift) as u32;
+        let post = (event.post_state[i / 2] >> shift) as u32;
+        MemoryRecordEnum::from(*read)
+            .check_access(event.shard, event.clk)
+            .map_err(|reason| format!("read of state word {}: {}", i, reason))?;
+        MemoryRecordEnum::from(*write)
+            .check_access(event.shard, write_clk)
+            .map_err(|reason| format!("write of state word {}: {}", i, reason))?;
+        if read.value != pre {
+            return Err(format!(
+                "read of state word {} does not match the pre state",
+                i
+            ));
+        }
+        if write.prev_value != read.value {
+            return Err(format!(
+                "write of state word {} does not overwrite the read value",
+                i
+            ));
+        }
+        if write.value != post {
+            return Err(format!(
+                "write of state word {} does not match the post state",
+                i
+            ));
+        }
+    }
+    Ok(())
+}
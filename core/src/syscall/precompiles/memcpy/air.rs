@@ -0,0 +1,145 @@
+use core::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_field::AbstractField;
+use p3_matrix::MatrixRowSlices;
+
+use crate::{
+    air::{SP1AirBuilder, SyscallAirBuilder},
+    lookup::{InteractionKind, InteractionTag},
+    memory::MemoryCols,
+    runtime::{Register, Syscall, SyscallCode},
+};
+
+use super::{
+    columns::{MemcpyCols, NUM_MEMCPY_COLS},
+    MemcpyChip, MEMCPY_CHUNK_WORDS,
+};
+
+impl<F> BaseAir<F> for MemcpyChip {
+    fn width(&self) -> usize {
+        NUM_MEMCPY_COLS
+    }
+}
+
+impl<AB> Air<AB> for MemcpyChip
+where
+    AB: SP1AirBuilder,
+{
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local: &MemcpyCols<AB::Var> = main.row_slice(0).borrow();
+
+        builder.assert_bool(local.is_real);
+        builder.assert_bool(local.resumes);
+        builder.assert_bool(local.continues);
+        builder.when(local.resumes).assert_one(local.is_real);
+        builder.when(local.continues).assert_one(local.is_real);
+
+        // The copied words are a prefix of the chunk.
+        let mut num_words = AB::Expr::zero();
+        for i in 0..MEMCPY_CHUNK_WORDS {
+            builder.assert_bool(local.is_copied[i]);
+            builder.when(local.is_copied[i]).assert_one(local.is_real);
+            if i > 0 {
+                builder
+                    .when(local.is_copied[i])
+                    .assert_one(local.is_copied[i - 1]);
+            }
+            num_words += local.is_copied[i].into();
+        }
+
+        let dst_ptr = local.dst_ptr_access.value().reduce::<AB>();
+        let src_ptr = local.src_ptr_access.value().reduce::<AB>();
+        let len = local.len_access.value().reduce::<AB>();
+
+        // A copy starts with no words copied, and every event but the last copies a whole chunk.
+        // The last one copies the rest of the buffer.
+        builder
+            .when(local.is_real)
+            .when_not(local.resumes)
+            .assert_zero(local.offset);
+        builder
+            .when(local.continues)
+            .assert_one(local.is_copied[MEMCPY_CHUNK_WORDS - 1]);
+        builder
+            .when(local.is_real)
+            .when_not(local.continues)
+            .assert_eq(local.offset + num_words.clone(), len.clone());
+
+        // Receive the state of the copy from the previous event, and hand it to the next one.
+        builder.receive_tagged_values(
+            InteractionKind::Continuation,
+            InteractionTag::MemcpyContinuation,
+            [
+                local.id.into(),
+                dst_ptr.clone(),
+                src_ptr.clone(),
+                len.clone(),
+                local.offset.into(),
+            ],
+            local.resumes,
+        );
+        builder.send_tagged_values(
+            InteractionKind::Continuation,
+            InteractionTag::MemcpyContinuation,
+            [
+                local.id.into(),
+                dst_ptr.clone(),
+                src_ptr.clone(),
+                len,
+                local.offset + num_words,
+            ],
+            local.continues,
+        );
+
+        // Receive the syscall from the ECALL which emitted the event, every chunk of a copy being
+        // emitted by an ECALL of its own.
+        builder.receive_syscall(
+            local.shard,
+            local.clk + AB::F::from_canonical_u32(self.num_extra_cycles()),
+            AB::F::from_canonical_u32(SyscallCode::MEMCPY as u32),
+            dst_ptr.clone(),
+            local.continues,
+            local.is_real,
+        );
+
+        // Constrain the memory accesses.
+        for (register, access) in [
+            (Register::X10, &local.dst_ptr_access),
+            (Register::X11, &local.src_ptr_access),
+            (Register::X12, &local.len_access),
+        ] {
+            builder.constraint_memory_access(
+                local.shard,
+                local.clk, // clk + 0 -> Memory
+                AB::F::from_canonical_u32(register as u32),
+                access,
+                local.is_real,
+            );
+        }
+        for i in 0..MEMCPY_CHUNK_WORDS {
+            let word_offset = (local.offset + AB::Expr::from_canonical_usize(i))
+                * AB::Expr::from_canonical_u32(4);
+            builder.constraint_memory_access(
+                local.shard,
+                local.clk, // clk + 0 -> Memory
+                src_ptr.clone() + word_offset.clone(),
+                &local.src_access[i],
+                local.is_copied[i],
+            );
+            builder.constraint_memory_access(
+                local.shard,
+                local.clk + AB::F::from_canonical_u32(4), // clk + 4 -> Memory
+                dst_ptr.clone() + word_offset,
+                &local.dst_access[i],
+                local.is_copied[i],
+            );
+
+            // The destination receives the word of the source.
+            builder
+                .when(local.is_copied[i])
+                .assert_word_eq(*local.dst_access[i].value(), *local.src_access[i].value());
+        }
+    }
+}
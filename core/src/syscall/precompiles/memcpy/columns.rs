@@ -0,0 +1,43 @@
+use core::borrow::{Borrow, BorrowMut};
+use core::mem::size_of;
+
+use sp1_derive::AlignedBorrow;
+
+use super::MEMCPY_CHUNK_WORDS;
+use crate::memory::{MemoryReadCols, MemoryWriteCols};
+
+pub const NUM_MEMCPY_COLS: usize = size_of::<MemcpyCols<u8>>();
+
+#[derive(AlignedBorrow, Default, Debug, Clone, Copy)]
+#[repr(C)]
+pub struct MemcpyCols<T> {
+    /// Inputs.
+    pub shard: T,
+    pub clk: T,
+
+    /// The continuation id shared by all the events of the copy.
+    pub id: T,
+
+    /// The number of words copied by the previous events of the copy.
+    pub offset: T,
+
+    /// Whether a previous event hands the copy off to this one.
+    pub resumes: T,
+
+    /// Whether this event hands the copy off to a later one.
+    pub continues: T,
+
+    /// The destination, source and length in words, read from a0, a1 and a2.
+    pub dst_ptr_access: MemoryReadCols<T>,
+    pub src_ptr_access: MemoryReadCols<T>,
+    pub len_access: MemoryReadCols<T>,
+
+    /// Whether each word of the chunk is copied by this event. The copied words are a prefix.
+    pub is_copied: [T; MEMCPY_CHUNK_WORDS],
+
+    /// The words of the chunk read from the source and written to the destination.
+    pub src_access: [MemoryReadCols<T>; MEMCPY_CHUNK_WORDS],
+    pub dst_access: [MemoryWriteCols<T>; MEMCPY_CHUNK_WORDS],
+
+    pub is_real: T,
+}
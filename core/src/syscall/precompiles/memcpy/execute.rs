@@ -0,0 +1,67 @@
+use crate::{
+    runtime::{Continuation, Register, Syscall},
+    syscall::precompiles::{
+        memcpy::{MemcpyEvent, MEMCPY_CHUNK_WORDS},
+        SyscallContext,
+    },
+};
+
+use super::MemcpyChip;
+
+impl Syscall for MemcpyChip {
+    fn execute(&self, rt: &mut SyscallContext) -> u32 {
+        let start_clk = rt.clk;
+
+        // Read the destination, the source and the length in words from a0, a1 and a2.
+        let (dst_ptr_record, dst_ptr) = rt.mr(Register::X10 as u32);
+        let (src_ptr_record, src_ptr) = rt.mr(Register::X11 as u32);
+        let (len_record, len) = rt.mr(Register::X12 as u32);
+        if dst_ptr % 4 != 0 || src_ptr % 4 != 0 {
+            panic!();
+        }
+
+        // Copy the next chunk of the buffer, which the previous events have left off at `offset`.
+        let Continuation {
+            id,
+            progress: offset,
+        } = rt.resume();
+        let num_words = (len - offset).min(MEMCPY_CHUNK_WORDS as u32);
+        let (src_records, words) = rt.mr_slice(src_ptr + 4 * offset, num_words as usize);
+        // When we write to the destination, we want the clk to be incremented.
+        rt.clk += 4;
+        let dst_records = rt.mw_slice(dst_ptr + 4 * offset, &words);
+        rt.clk += 4;
+
+        // Execute this ecall again until the whole buffer is copied.
+        let continues = offset + num_words < len;
+        if continues {
+            rt.suspend(Continuation {
+                id,
+                progress: offset + num_words,
+            });
+        }
+
+        let shard = rt.current_shard();
+        rt.record_mut().memcpy_events.push(MemcpyEvent {
+            shard,
+            clk: start_clk,
+            id,
+            dst_ptr,
+            src_ptr,
+            len,
+            offset,
+            continues,
+            dst_ptr_record,
+            src_ptr_record,
+            len_record,
+            src_records,
+            dst_records,
+        });
+
+        dst_ptr
+    }
+
+    fn num_extra_cycles(&self) -> u32 {
+        8
+    }
+}
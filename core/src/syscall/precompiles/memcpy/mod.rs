@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+
+use crate::syscall::precompiles::{MemoryReadRecord, MemoryWriteRecord};
+
+mod air;
+pub mod columns;
+mod execute;
+mod trace;
+
+/// The maximum number of words copied by a single event of the `MEMCPY` precompile.
+pub const MEMCPY_CHUNK_WORDS: usize = 8;
+
+/// A chunk of a copy. A copy of more than `MEMCPY_CHUNK_WORDS` words is split into several events,
+/// one per `ecall`, which hand the copy off to each other through its continuation id.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemcpyEvent {
+    pub shard: u32,
+    pub clk: u32,
+    pub id: u32,
+    pub dst_ptr: u32,
+    pub src_ptr: u32,
+
+    /// The number of words of the whole copy.
+    pub len: u32,
+
+    /// The number of words copied by the previous events of the copy.
+    pub offset: u32,
+
+    /// Whether the copy continues in a later event.
+    pub continues: bool,
+
+    pub dst_ptr_record: MemoryReadRecord,
+    pub src_ptr_record: MemoryReadRecord,
+    pub len_record: MemoryReadRecord,
+
+    /// The records of the words copied by this event, at most `MEMCPY_CHUNK_WORDS` of them.
+    pub src_records: Vec<MemoryReadRecord>,
+    pub dst_records: Vec<MemoryWriteRecord>,
+}
+
+/// A precompile copying a2 words from a1 to a0. The buffers must not overlap.
+///
+/// Each `ecall` copies at most `MEMCPY_CHUNK_WORDS` words and executes again until the whole buffer
+/// is copied, so a copy of any length may span several shards.
+#[derive(Default)]
+pub struct MemcpyChip;
+
+impl MemcpyChip {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(test)]
+pub mod memcpy_tests {
+    use crate::runtime::{Instruction, Opcode, Program, Runtime, SyscallCode};
+    use crate::utils::{run_test, setup_logger};
+
+    use super::MEMCPY_CHUNK_WORDS;
+
+    const SRC_PTR: u32 = 0x1000;
+    const DST_PTR: u32 = 0x8000;
+
+    fn word(i: u32) -> u32 {
+        i.wrapping_mul(0x9e37_79b9) ^ 0x5555_5555
+    }
+
+    /// A program filling a buffer of `len` words and copying it with a single `ecall`.
+    pub fn memcpy_program(len: u32) -> Program {
+        let mut instructions = vec![];
+        for i in 0..len {
+            instructions.extend(vec![
+                Instruction::new(Opcode::ADD, 29, 0, word(i), false, true),
+                Instruction::new(Opcode::ADD, 30, 0, SRC_PTR + 4 * i, false, true),
+                Instruction::new(Opcode::SW, 29, 30, 0, false, true),
+            ]);
+        }
+        instructions.extend(vec![
+            Instruction::new(Opcode::ADD, 5, 0, SyscallCode::MEMCPY as u32, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, DST_PTR, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, SRC_PTR, false, true),
+            Instruction::new(Opcode::ADD, 12, 0, len, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+        ]);
+        Program::new(instructions, 0, 0)
+    }
+
+    #[test]
+    fn test_memcpy_execute() {
+        for len in [0, 1, 7, 8, 9, 20, 64] {
+            let mut runtime = Runtime::new(memcpy_program(len));
+            runtime.run();
+            for i in 0..len {
+                assert_eq!(runtime.word(DST_PTR + 4 * i), word(i));
+            }
+
+            // The copy is split into chunks chained by a single continuation.
+            let events = &runtime.record.memcpy_events;
+            let num_events = (len as usize).div_ceil(MEMCPY_CHUNK_WORDS).max(1);
+            assert_eq!(events.len(), num_events);
+            assert!(runtime.state.continuation.is_none());
+            for (i, event) in events.iter().enumerate() {
+                assert_eq!(event.id, 0);
+                assert_eq!(event.offset as usize, i * MEMCPY_CHUNK_WORDS);
+                assert_eq!(event.continues, i + 1 < num_events);
+                assert_eq!(event.len, len);
+            }
+
+            // The ecall executes once per chunk, and the guest sees its registers unchanged.
+            assert_eq!(
+                runtime.report.syscall_counts[&SyscallCode::MEMCPY],
+                num_events as u64
+            );
+            assert_eq!(runtime.register(crate::runtime::Register::X10), DST_PTR);
+        }
+    }
+
+    #[test]
+    fn test_memcpy_spans_runtime_shards() {
+        let mut runtime = Runtime::new(memcpy_program(256));
+        // Leave room for a few chunks in every shard, past the largest syscall.
+        runtime.shard_size = 260;
+        runtime.run();
+        for i in 0..256 {
+            assert_eq!(runtime.word(DST_PTR + 4 * i), word(i));
+        }
+        let events = &runtime.record.memcpy_events;
+        assert!(events.first().unwrap().shard < events.last().unwrap().shard);
+    }
+
+    #[test]
+    fn test_memcpy_prove_babybear() {
+        setup_logger();
+        run_test(memcpy_program(20)).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "perf")]
+    fn test_memcpy_prove_across_shards() {
+        use crate::runtime::ShardingConfig;
+        use crate::stark::{LocalProver, Prover, RiscvStark};
+        use crate::utils::{BabyBearBlake3, StarkUtils};
+
+        setup_logger();
+        // The 40 words are filled by the first 124 instructions, so the 5 chunks of the copy
+        // straddle the boundary of shards of 128 instructions. Each ecall of the copy takes 12
+        // clock cycles, and the shard leaves room for the longest syscall, which takes 960, so the
+        // runtime starts a new shard once the clock reaches 1 + 124 * 4 + 4 * 12 = 376 * 4 - 960.
+        let mut runtime = Runtime::new(memcpy_program(40).with_halt());
+        runtime.shard_size = 376;
+        runtime.run();
+        let config = ShardingConfig {
+            shard_size: 128,
+            ..Default::default()
+        };
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let (pk, vk) = machine.setup(runtime.program.as_ref());
+        let shards = machine.shard(runtime.record, &config);
+        assert_eq!(shards.len(), 2);
+        assert_eq!(shards[0].memcpy_events.len(), 4);
+        assert_eq!(shards[1].memcpy_events.len(), 1);
+
+        let mut challenger = machine.config().challenger();
+        let proof = LocalProver::prove_shards(&machine, &pk, shards, &mut challenger).unwrap();
+        let mut challenger = machine.config().challenger();
+        machine.verify(&vk, &proof, &mut challenger).unwrap();
+    }
+}
@@ -0,0 +1,87 @@
+use std::borrow::BorrowMut;
+
+use p3_field::PrimeField32;
+use p3_matrix::dense::RowMajorMatrix;
+use tracing::instrument;
+
+use crate::{
+    air::{EventKind, MachineAir},
+    runtime::ExecutionRecord,
+    utils::pad_rows,
+};
+
+use super::{
+    columns::{MemcpyCols, NUM_MEMCPY_COLS},
+    MemcpyChip,
+};
+
+impl<F: PrimeField32> MachineAir<F> for MemcpyChip {
+    fn name(&self) -> String {
+        "Memcpy".to_string()
+    }
+
+    fn produces(&self) -> &[EventKind] {
+        &[EventKind::Field]
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::Memcpy]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.memcpy_events.len()
+    }
+
+    #[instrument(name = "generate Memcpy trace", skip_all)]
+    fn generate_trace(
+        &self,
+        input: &ExecutionRecord,
+        output: &mut ExecutionRecord,
+    ) -> RowMajorMatrix<F> {
+        let mut rows = Vec::new();
+        let mut new_field_events = Vec::new();
+
+        for event in input.memcpy_events.iter() {
+            let mut row = [F::zero(); NUM_MEMCPY_COLS];
+            let cols: &mut MemcpyCols<F> = row.as_mut_slice().borrow_mut();
+
+            cols.shard = F::from_canonical_u32(event.shard);
+            cols.clk = F::from_canonical_u32(event.clk);
+            cols.id = F::from_canonical_u32(event.id);
+            cols.offset = F::from_canonical_u32(event.offset);
+            cols.resumes = F::from_bool(event.offset != 0);
+            cols.continues = F::from_bool(event.continues);
+            cols.is_real = F::one();
+
+            cols.dst_ptr_access
+                .populate(event.dst_ptr_record, &mut new_field_events);
+            cols.src_ptr_access
+                .populate(event.src_ptr_record, &mut new_field_events);
+            cols.len_access
+                .populate(event.len_record, &mut new_field_events);
+
+            // The words past the end of the buffer are left unused.
+            for (i, (src_record, dst_record)) in event
+                .src_records
+                .iter()
+                .zip(event.dst_records.iter())
+                .enumerate()
+            {
+                cols.is_copied[i] = F::one();
+                cols.src_access[i].populate(*src_record, &mut new_field_events);
+                cols.dst_access[i].populate(*dst_record, &mut new_field_events);
+            }
+
+            rows.push(row);
+        }
+        output.add_field_events(&new_field_events);
+
+        pad_rows(&mut rows, || [F::zero(); NUM_MEMCPY_COLS]);
+
+        // Convert the trace to a row major matrix.
+        RowMajorMatrix::new(
+            rows.into_iter().flatten().collect::<Vec<_>>(),
+            NUM_MEMCPY_COLS,
+        )
+    }
+}
@@ -1,21 +1,30 @@
+pub mod bitmanip;
 pub mod blake3;
 pub mod edwards;
 pub mod k256;
 pub mod keccak256;
+pub mod memcpy;
 pub mod sha256;
+pub mod uint256;
 pub mod weierstrass;
 
 use num::BigUint;
+use p3_field::PrimeField;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_maybe_rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::air::SP1AirBuilder;
+use crate::field::event::FieldEvent;
 use crate::operations::field::params::Limbs;
-use crate::runtime::SyscallContext;
+use crate::runtime::{ExecutionRecord, SyscallContext};
 use crate::utils::ec::field::FieldParameters;
 use crate::utils::ec::{AffinePoint, EllipticCurve};
+use crate::utils::{padded_nb_rows, zero_trace};
 use crate::{cpu::MemoryReadRecord, cpu::MemoryWriteRecord};
 
 /// Elliptic curve add event.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct ECAddEvent {
     pub shard: u32,
     pub clk: u32,
@@ -34,7 +43,7 @@ pub fn create_ec_add_event<E: EllipticCurve>(rt: &mut SyscallContext) -> ECAddEv
 
     let start_clk = rt.clk;
 
-    // TODO: these will have to be be constrained, but can do it later.
+    // The pointer in a0 is handed to the chip by the ECALL.
     let p_ptr = rt.register_unsafe(a0);
     if p_ptr % 4 != 0 {
         panic!();
@@ -75,7 +84,7 @@ pub fn create_ec_add_event<E: EllipticCurve>(rt: &mut SyscallContext) -> ECAddEv
 }
 
 /// Elliptic curve double event.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct ECDoubleEvent {
     pub shard: u32,
     pub clk: u32,
@@ -89,7 +98,7 @@ pub fn create_ec_double_event<E: EllipticCurve>(rt: &mut SyscallContext) -> ECDo
 
     let start_clk = rt.clk;
 
-    // TODO: these will have to be be constrained, but can do it later.
+    // The pointer in a0 is handed to the chip by the ECALL.
     let p_ptr = rt.register_unsafe(a0);
     if p_ptr % 4 != 0 {
         panic!();
@@ -117,6 +126,72 @@ pub fn create_ec_double_event<E: EllipticCurve>(rt: &mut SyscallContext) -> ECDo
     }
 }
 
+/// The rows of a precompile taking `rounds` rows per event with only the columns which depend on
+/// the position of the row within its event populated, e.g. the flags tracking the rounds.
+pub(crate) fn round_flag_rows<F: PrimeField, const N: usize>(
+    rounds: usize,
+    populate_flags: impl Fn(&mut [F], usize),
+) -> Vec<[F; N]> {
+    (0..rounds)
+        .map(|round| {
+            let mut row = [F::zero(); N];
+            populate_flags(&mut row, round);
+            row
+        })
+        .collect()
+}
+
+/// Generates the trace of a precompile whose events each take one row per round, e.g. a hash
+/// compression or a message schedule, with the flag rows of [`round_flag_rows`].
+///
+/// Each row starts as the flag row of its round and `populate_row` fills in the rest from the event,
+/// including the memory records, whose field events it collects. The events are populated in
+/// parallel, each into a record of its own, and the records are merged into `output` in the order
+/// of the events. The padding rows continue the flags of the rounds, so that the constraints
+/// between the flags of consecutive rows hold on the padding as well.
+pub(crate) fn generate_round_trace<F, E, const N: usize>(
+    events: &[E],
+    flag_rows: &[[F; N]],
+    populate_row: impl Fn(&E, usize, &mut [F], &mut ExecutionRecord, &mut Vec<FieldEvent>) + Sync,
+    output: &mut ExecutionRecord,
+    trace: &mut RowMajorMatrix<F>,
+) where
+    F: PrimeField,
+    E: Sync,
+{
+    let rounds = flag_rows.len();
+    let nb_rows = events.len() * rounds;
+    zero_trace(trace, N, padded_nb_rows(nb_rows));
+    let (real, padding) = trace.values.split_at_mut(nb_rows * N);
+
+    let records = real
+        .par_chunks_mut(rounds * N)
+        .zip(events.par_iter())
+        .map(|(rows, event)| {
+            let mut record = ExecutionRecord {
+                index: output.index,
+                ..Default::default()
+            };
+            let mut new_field_events = Vec::new();
+            for (round, row) in rows.chunks_exact_mut(N).enumerate() {
+                row.copy_from_slice(&flag_rows[round]);
+                populate_row(event, round, row, &mut record, &mut new_field_events);
+            }
+            record.add_field_events(&new_field_events);
+            record
+        })
+        .collect::<Vec<_>>();
+
+    // Merge the records in the order of the events.
+    for mut record in records {
+        output.append(&mut record);
+    }
+
+    for (i, row) in padding.chunks_exact_mut(N).enumerate() {
+        row.copy_from_slice(&flag_rows[(nb_rows + i) % rounds]);
+    }
+}
+
 pub fn limbs_from_biguint<AB, F: FieldParameters>(value: &BigUint) -> Limbs<AB::Expr>
 where
     AB: SP1AirBuilder,
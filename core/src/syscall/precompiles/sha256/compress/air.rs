@@ -3,11 +3,12 @@ use p3_field::AbstractField;
 
 use super::columns::{ShaCompressCols, NUM_SHA_COMPRESS_COLS};
 use super::ShaCompressChip;
-use crate::air::{BaseAirBuilder, SP1AirBuilder, Word, WordAirBuilder};
+use crate::air::{BaseAirBuilder, SP1AirBuilder, SyscallAirBuilder, Word, WordAirBuilder};
 use crate::memory::MemoryCols;
 use crate::operations::{
     AddOperation, AndOperation, FixedRotateRightOperation, NotOperation, XorOperation,
 };
+use crate::runtime::{Syscall, SyscallCode};
 use core::borrow::Borrow;
 use p3_matrix::MatrixRowSlices;
 
@@ -33,6 +34,20 @@ where
         self.constrain_compression_ops(builder, local);
 
         self.constrain_finalize_ops(builder, local);
+
+        // Receive the syscall from the ECALL which invokes the compression, on its first row.
+        builder.assert_eq(
+            local.receives_syscall,
+            local.octet[0] * local.octet_num[0] * local.is_real,
+        );
+        builder.receive_syscall(
+            local.shard,
+            local.clk + AB::F::from_canonical_u32(self.num_extra_cycles()),
+            AB::F::from_canonical_u32(SyscallCode::SHA_COMPRESS as u32),
+            local.w_and_h_ptr,
+            AB::F::zero(),
+            local.receives_syscall,
+        );
     }
 }
 
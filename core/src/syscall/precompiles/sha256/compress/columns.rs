@@ -90,4 +90,7 @@ pub struct ShaCompressCols<T> {
     // Instead, we can use octet_num[9] for that.
     // pub is_finalize: T,
     pub is_real: T,
+
+    /// Whether the row is the first of a real event, which receives the syscall from its ECALL.
+    pub receives_syscall: T,
 }
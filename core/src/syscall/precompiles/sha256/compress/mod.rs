@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 use crate::cpu::{MemoryReadRecord, MemoryWriteRecord};
 
 mod air;
@@ -16,14 +18,22 @@ pub const SHA_COMPRESS_K: [u32; 64] = [
     0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
 ];
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct ShaCompressEvent {
     pub shard: u32,
     pub clk: u32,
     pub w_and_h_ptr: u32,
+    #[serde(
+        serialize_with = "crate::utils::codec::serialize_array",
+        deserialize_with = "crate::utils::codec::deserialize_array"
+    )]
     pub w: [u32; 64],
     pub h: [u32; 8],
     pub h_read_records: [MemoryReadRecord; 8],
+    #[serde(
+        serialize_with = "crate::utils::codec::serialize_array",
+        deserialize_with = "crate::utils::codec::deserialize_array"
+    )]
     pub w_i_read_records: [MemoryReadRecord; 64],
     pub h_write_records: [MemoryWriteRecord; 8],
 }
@@ -40,11 +50,16 @@ impl ShaCompressChip {
 #[cfg(test)]
 pub mod compress_tests {
 
+    use p3_baby_bear::BabyBear;
+
     use crate::{
-        runtime::{Instruction, Opcode, Program},
+        air::MachineAir,
+        runtime::{Instruction, Opcode, Program, Runtime},
         utils::{run_test, setup_logger},
     };
 
+    use super::ShaCompressChip;
+
     pub fn sha_compress_program() -> Program {
         let w_ptr = 100;
         let mut instructions = vec![Instruction::new(Opcode::ADD, 29, 0, 5, false, true)];
@@ -68,4 +83,27 @@ pub mod compress_tests {
         let program = sha_compress_program();
         run_test(program).unwrap();
     }
+
+    #[test]
+    fn test_validate() {
+        let mut runtime = Runtime::new(sha_compress_program());
+        runtime.run();
+        let record = runtime.record;
+        let chip = ShaCompressChip::new();
+        MachineAir::<BabyBear>::validate(&chip, &record).unwrap();
+
+        let mut overwritten = record.clone();
+        overwritten.sha_compress_events[0].h_write_records[2].prev_value += 1;
+        let error = MachineAir::<BabyBear>::validate(&chip, &overwritten).unwrap_err();
+        assert_eq!((error.chip.as_str(), error.index), ("ShaCompress", 0));
+        assert_eq!(
+            error.reason,
+            "write of h[2] does not overwrite the read value"
+        );
+
+        let mut wrong_w = record;
+        wrong_w.sha_compress_events[0].w[10] ^= 1;
+        let error = MachineAir::<BabyBear>::validate(&chip, &wrong_w).unwrap_err();
+        assert_eq!(error.reason, "read of w[10] does not match the event");
+    }
 }
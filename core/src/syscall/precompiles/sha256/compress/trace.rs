@@ -4,14 +4,15 @@ use p3_field::PrimeField;
 use p3_matrix::dense::RowMajorMatrix;
 
 use crate::{
-    air::{MachineAir, Word},
+    air::{EventKind, EventValidationError, MachineAir, Word},
+    cpu::MemoryRecordEnum,
     memory::MemoryCols,
     runtime::ExecutionRecord,
 };
 
 use super::{
     columns::{ShaCompressCols, NUM_SHA_COMPRESS_COLS},
-    ShaCompressChip, SHA_COMPRESS_K,
+    ShaCompressChip, ShaCompressEvent, SHA_COMPRESS_K,
 };
 
 impl<F: PrimeField> MachineAir<F> for ShaCompressChip {
@@ -19,6 +20,27 @@ impl<F: PrimeField> MachineAir<F> for ShaCompressChip {
         "ShaCompress".to_string()
     }
 
+    fn produces(&self) -> &[EventKind] {
+        &[EventKind::ByteLookup, EventKind::Field]
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::ShaCompress]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.sha_compress_events.len() * 80
+    }
+
+    fn validate(&self, input: &ExecutionRecord) -> Result<(), EventValidationError> {
+        for (index, event) in input.sha_compress_events.iter().enumerate() {
+            validate_event(event).map_err(|reason| {
+                EventValidationError::new(<Self as MachineAir<F>>::name(self), index, reason)
+            })?;
+        }
+        Ok(())
+    }
+
     fn generate_trace(
         &self,
         input: &ExecutionRecord,
@@ -72,6 +94,7 @@ impl<F: PrimeField> MachineAir<F> for ShaCompressChip {
                     7 => cols.h = *cols.mem.value(),
                     _ => panic!("unsupported j"),
                 };
+                cols.receives_syscall = F::from_bool(j == 0);
 
                 v[0] = cols.a;
                 v[1] = cols.b;
@@ -257,3 +280,69 @@ impl<F: PrimeField> MachineAir<F> for ShaCompressChip {
         )
     }
 }
+
+/// Check that an event reads `h` and `w`, and then writes the compressed `h` over the read one, at
+/// the clocks the AIR expects.
+fn validate_event(event: &ShaCompressEvent) -> Result<(), String> {
+    for (i, read) in event.h_read_records.iter().enumerate() {
+        MemoryRecordEnum::from(*read)
+            .check_access(event.shard, event.clk + 4 * i as u32)
+            .map_err(|reason| format!("read of h[{}]: {}", i, reason))?;
+        if read.value != event.h[i] {
+            return Err(format!("read of h[{}] does not match the event", i));
+        }
+    }
+    for (i, read) in event.w_i_read_records.iter().enumerate() {
+        MemoryRecordEnum::from(*read)
+            .check_access(event.shard, event.clk + 32 + 4 * i as u32)
+            .map_err(|reason| format!("read of w[{}]: {}", i, reason))?;
+        if read.value != event.w[i] {
+            return Err(format!("read of w[{}] does not match the event", i));
+        }
+    }
+
+    let mut v = event.h;
+    for (w_i, k_i) in event.w.iter().zip(SHA_COMPRESS_K.iter()) {
+        let [a, b, c, d, e, f, g, h] = v;
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let temp1 = h
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(*k_i)
+            .wrapping_add(*w_i);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let temp2 = s0.wrapping_add(maj);
+        v = [
+            temp1.wrapping_add(temp2),
+            a,
+            b,
+            c,
+            d.wrapping_add(temp1),
+            e,
+            f,
+            g,
+        ];
+    }
+
+    for (i, write) in event.h_write_records.iter().enumerate() {
+        MemoryRecordEnum::from(*write)
+            .check_access(event.shard, event.clk + 288 + 4 * i as u32)
+            .map_err(|reason| format!("write of h[{}]: {}", i, reason))?;
+        if write.prev_value != event.h[i] {
+            return Err(format!(
+                "write of h[{}] does not overwrite the read value",
+                i
+            ));
+        }
+        let expected = event.h[i].wrapping_add(v[i]);
+        if write.value != expected {
+            return Err(format!(
+                "write of h[{}] is 0x{:08x} instead of 0x{:08x}",
+                i, write.value, expected
+            ));
+        }
+    }
+    Ok(())
+}
@@ -1,11 +1,12 @@
 use p3_air::{Air, AirBuilder, BaseAir};
 
 use super::{ShaExtendChip, ShaExtendCols, NUM_SHA_EXTEND_COLS};
-use crate::air::{BaseAirBuilder, SP1AirBuilder};
+use crate::air::{BaseAirBuilder, SP1AirBuilder, SyscallAirBuilder};
 use crate::memory::MemoryCols;
 use crate::operations::{
     Add4Operation, FixedRotateRightOperation, FixedShiftRightOperation, XorOperation,
 };
+use crate::runtime::{Syscall, SyscallCode};
 use core::borrow::Borrow;
 use p3_field::AbstractField;
 use p3_matrix::MatrixRowSlices;
@@ -175,5 +176,16 @@ where
             &local.w_i,
             local.is_real,
         );
+
+        // Receive the syscall from the ECALL which invokes the extension, on its first row.
+        builder.assert_eq(local.receives_syscall, local.cycle_48_start * local.is_real);
+        builder.receive_syscall(
+            local.shard,
+            local.clk + AB::F::from_canonical_u32(self.num_extra_cycles()),
+            AB::F::from_canonical_u32(SyscallCode::SHA_EXTEND as u32),
+            local.w_ptr,
+            AB::F::zero(),
+            local.receives_syscall,
+        );
     }
 }
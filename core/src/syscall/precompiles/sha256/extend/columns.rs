@@ -60,4 +60,7 @@ pub struct ShaExtendCols<T> {
 
     /// Selector.
     pub is_real: T,
+
+    /// Whether the row is the first of a real event, which receives the syscall from its ECALL.
+    pub receives_syscall: T,
 }
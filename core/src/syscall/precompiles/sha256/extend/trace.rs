@@ -1,27 +1,164 @@
-use std::borrow::BorrowMut;
+use std::borrow::{Borrow, BorrowMut};
 
 use p3_field::PrimeField;
 use p3_matrix::dense::RowMajorMatrix;
 
-use crate::{air::MachineAir, runtime::ExecutionRecord};
+use crate::{
+    air::{EventKind, EventValidationError, MachineAir},
+    cpu::MemoryRecordEnum,
+    field::event::FieldEvent,
+    runtime::ExecutionRecord,
+    syscall::precompiles::{generate_round_trace, round_flag_rows},
+};
 
-use super::{ShaExtendChip, ShaExtendCols, NUM_SHA_EXTEND_COLS};
+use super::{ShaExtendChip, ShaExtendCols, ShaExtendEvent, NUM_SHA_EXTEND_COLS};
 
 impl<F: PrimeField> MachineAir<F> for ShaExtendChip {
     fn name(&self) -> String {
         "ShaExtend".to_string()
     }
 
+    fn produces(&self) -> &[EventKind] {
+        &[EventKind::ByteLookup, EventKind::Field]
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::ShaExtend]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.sha_extend_events.len() * 48
+    }
+
+    fn validate(&self, input: &ExecutionRecord) -> Result<(), EventValidationError> {
+        for (index, event) in input.sha_extend_events.iter().enumerate() {
+            validate_event(event).map_err(|reason| {
+                EventValidationError::new(<Self as MachineAir<F>>::name(self), index, reason)
+            })?;
+        }
+        Ok(())
+    }
+
+    fn describe_row(&self, row: &[F]) -> Option<String> {
+        let cols: &ShaExtendCols<F> = row.borrow();
+        Some(format!("{:#?}", cols))
+    }
+
     fn generate_trace(
         &self,
         input: &ExecutionRecord,
         output: &mut ExecutionRecord,
     ) -> RowMajorMatrix<F> {
+        let mut trace = RowMajorMatrix::new(Vec::new(), NUM_SHA_EXTEND_COLS);
+        self.generate_trace_into(input, output, &mut trace);
+        trace
+    }
+
+    fn generate_trace_into(
+        &self,
+        input: &ExecutionRecord,
+        output: &mut ExecutionRecord,
+        trace: &mut RowMajorMatrix<F>,
+    ) {
+        generate_round_trace(
+            &input.sha_extend_events,
+            &flag_rows::<F>(),
+            populate_row,
+            output,
+            trace,
+        );
+    }
+}
+
+/// The rows with only the flag columns populated, one for each of the 48 steps of an event.
+fn flag_rows<F: PrimeField>() -> Vec<[F; NUM_SHA_EXTEND_COLS]> {
+    round_flag_rows(48, |row, j| {
+        let cols: &mut ShaExtendCols<F> = row.borrow_mut();
+        cols.populate_flags(j);
+    })
+}
+
+/// Populates the row of the `j`-th step of an event over its flags.
+fn populate_row<F: PrimeField>(
+    event: &ShaExtendEvent,
+    j: usize,
+    row: &mut [F],
+    output: &mut ExecutionRecord,
+    new_field_events: &mut Vec<FieldEvent>,
+) {
+    let cols: &mut ShaExtendCols<F> = row.borrow_mut();
+
+    cols.shard = F::from_canonical_u32(event.shard);
+    cols.clk = F::from_canonical_u32(event.clk);
+    cols.w_ptr = F::from_canonical_u32(event.w_ptr);
+
+    cols.w_i_minus_15
+        .populate(event.w_i_minus_15_reads[j], new_field_events);
+    cols.w_i_minus_2
+        .populate(event.w_i_minus_2_reads[j], new_field_events);
+    cols.w_i_minus_16
+        .populate(event.w_i_minus_16_reads[j], new_field_events);
+    cols.w_i_minus_7
+        .populate(event.w_i_minus_7_reads[j], new_field_events);
+
+    // Compute `s0`.
+    let w_i_minus_15 = event.w_i_minus_15_reads[j].value;
+    let w_i_minus_15_rr_7 = cols.w_i_minus_15_rr_7.populate(output, w_i_minus_15, 7);
+    let w_i_minus_15_rr_18 = cols.w_i_minus_15_rr_18.populate(output, w_i_minus_15, 18);
+    let w_i_minus_15_rs_3 = cols.w_i_minus_15_rs_3.populate(output, w_i_minus_15, 3);
+    let s0_intermediate =
+        cols.s0_intermediate
+            .populate(output, w_i_minus_15_rr_7, w_i_minus_15_rr_18);
+    let s0 = cols.s0.populate(output, s0_intermediate, w_i_minus_15_rs_3);
+
+    // Compute `s1`.
+    let w_i_minus_2 = event.w_i_minus_2_reads[j].value;
+    let w_i_minus_2_rr_17 = cols.w_i_minus_2_rr_17.populate(output, w_i_minus_2, 17);
+    let w_i_minus_2_rr_19 = cols.w_i_minus_2_rr_19.populate(output, w_i_minus_2, 19);
+    let w_i_minus_2_rs_10 = cols.w_i_minus_2_rs_10.populate(output, w_i_minus_2, 10);
+    let s1_intermediate =
+        cols.s1_intermediate
+            .populate(output, w_i_minus_2_rr_17, w_i_minus_2_rr_19);
+    let s1 = cols.s1.populate(output, s1_intermediate, w_i_minus_2_rs_10);
+
+    // Compute `s2`.
+    let w_i_minus_7 = event.w_i_minus_7_reads[j].value;
+    let w_i_minus_16 = event.w_i_minus_16_reads[j].value;
+    cols.s2.populate(output, w_i_minus_16, s0, w_i_minus_7, s1);
+
+    cols.w_i.populate(event.w_i_writes[j], new_field_events);
+
+    cols.is_real = F::one();
+    cols.receives_syscall = cols.cycle_48_start;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::BorrowMut;
+
+    use p3_baby_bear::BabyBear;
+    use p3_field::AbstractField;
+
+    use crate::{
+        air::MachineAir,
+        runtime::ExecutionRecord,
+        syscall::precompiles::sha256::{ShaExtendChip, ShaExtendCols},
+        utils::test_utils::RecordBuilder,
+    };
+
+    use super::NUM_SHA_EXTEND_COLS;
+
+    /// The serial trace generation the chip had before it was parallelized, kept as a reference.
+    fn reference_trace(
+        input: &ExecutionRecord,
+        output: &mut ExecutionRecord,
+    ) -> Vec<[BabyBear; NUM_SHA_EXTEND_COLS]> {
+        type F = BabyBear;
+
         let mut rows = Vec::new();
 
         let mut new_field_events = Vec::new();
-        for i in 0..input.sha_extend_events.len() {
-            let event = input.sha_extend_events[i];
+        for event in input.sha_extend_events.iter() {
             for j in 0..48usize {
                 let mut row = [F::zero(); NUM_SHA_EXTEND_COLS];
                 let cols: &mut ShaExtendCols<F> = row.as_mut_slice().borrow_mut();
@@ -69,6 +206,7 @@ impl<F: PrimeField> MachineAir<F> for ShaExtendChip {
                     .populate(event.w_i_writes[j], &mut new_field_events);
 
                 cols.is_real = F::one();
+                cols.receives_syscall = cols.cycle_48_start;
                 rows.push(row);
             }
         }
@@ -86,11 +224,72 @@ impl<F: PrimeField> MachineAir<F> for ShaExtendChip {
             cols.populate_flags(i);
             rows.push(row);
         }
+        rows
+    }
+
+    #[test]
+    fn test_parallel_trace_matches_serial() {
+        let mut builder = RecordBuilder::new();
+        for k in 0..37u32 {
+            let mut w = [0u32; 64];
+            for (i, w_i) in w.iter_mut().take(16).enumerate() {
+                *w_i = (k * 16 + i as u32).wrapping_mul(0x9e3779b9);
+            }
+            builder.sha_extend(0x1000 + k * 0x100, w);
+        }
+        let shard = builder.build();
+
+        let mut serial_output = ExecutionRecord {
+            index: shard.index,
+            ..Default::default()
+        };
+        let rows = reference_trace(&shard, &mut serial_output);
+
+        let mut output = ExecutionRecord {
+            index: shard.index,
+            ..Default::default()
+        };
+        let trace = ShaExtendChip::new().generate_trace(&shard, &mut output);
+        assert_eq!(trace.values, rows.concat());
+        assert_eq!(output.byte_lookups, serial_output.byte_lookups);
+        assert_eq!(output.field_events, serial_output.field_events);
+    }
+}
+
+/// Check that an event performs each of its 48 rounds as four reads followed by a write at the
+/// clocks the AIR expects, and that each write holds the extension of its reads.
+fn validate_event(event: &ShaExtendEvent) -> Result<(), String> {
+    for round in 0..48 {
+        let clk = event.clk + 20 * round as u32;
+        let reads = [
+            ("w[i-15]", event.w_i_minus_15_reads[round]),
+            ("w[i-2]", event.w_i_minus_2_reads[round]),
+            ("w[i-16]", event.w_i_minus_16_reads[round]),
+            ("w[i-7]", event.w_i_minus_7_reads[round]),
+        ];
+        for (offset, (name, read)) in reads.iter().enumerate() {
+            MemoryRecordEnum::from(*read)
+                .check_access(event.shard, clk + 4 * offset as u32)
+                .map_err(|reason| format!("read of {} in round {}: {}", name, round, reason))?;
+        }
+        let write = event.w_i_writes[round];
+        MemoryRecordEnum::from(write)
+            .check_access(event.shard, clk + 16)
+            .map_err(|reason| format!("write of w[i] in round {}: {}", round, reason))?;
 
-        // Convert the trace to a row major matrix.
-        RowMajorMatrix::new(
-            rows.into_iter().flatten().collect::<Vec<_>>(),
-            NUM_SHA_EXTEND_COLS,
-        )
+        let [w_i_minus_15, w_i_minus_2, w_i_minus_16, w_i_minus_7] = reads.map(|(_, r)| r.value);
+        let s0 = w_i_minus_15.rotate_right(7) ^ w_i_minus_15.rotate_right(18) ^ (w_i_minus_15 >> 3);
+        let s1 = w_i_minus_2.rotate_right(17) ^ w_i_minus_2.rotate_right(19) ^ (w_i_minus_2 >> 10);
+        let w_i = s1
+            .wrapping_add(w_i_minus_16)
+            .wrapping_add(s0)
+            .wrapping_add(w_i_minus_7);
+        if write.value != w_i {
+            return Err(format!(
+                "write of w[i] in round {} is 0x{:08x} instead of 0x{:08x}",
+                round, write.value, w_i
+            ));
+        }
     }
+    Ok(())
 }
@@ -0,0 +1,151 @@
+use core::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_field::AbstractField;
+use p3_matrix::MatrixRowSlices;
+
+use crate::{
+    air::{Polynomial, SP1AirBuilder, SyscallAirBuilder},
+    memory::MemoryCols,
+    runtime::{Register, Syscall, SyscallCode},
+};
+
+use super::{
+    columns::{Uint256MulModCols, NUM_UINT256_MULMOD_COLS},
+    Uint256MulModChip, UINT256_NUM_LIMBS, UINT256_NUM_WORDS, UINT256_WITNESS_OFFSET,
+};
+
+impl<F> BaseAir<F> for Uint256MulModChip {
+    fn width(&self) -> usize {
+        NUM_UINT256_MULMOD_COLS
+    }
+}
+
+impl<AB> Air<AB> for Uint256MulModChip
+where
+    AB: SP1AirBuilder,
+{
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local: &Uint256MulModCols<AB::Var> = main.row_slice(0).borrow();
+
+        builder.assert_bool(local.is_real);
+
+        // The byte limbs of the operands, little endian.
+        let a = local
+            .a_access
+            .iter()
+            .flat_map(|access| access.prev_value().0)
+            .collect::<Vec<_>>();
+        let result = local
+            .a_access
+            .iter()
+            .flat_map(|access| access.value().0)
+            .collect::<Vec<_>>();
+        let b = local
+            .b_access
+            .iter()
+            .flat_map(|access| access.value().0)
+            .collect::<Vec<_>>();
+        let modulus = local
+            .modulus_access
+            .iter()
+            .flat_map(|access| access.value().0)
+            .collect::<Vec<_>>();
+
+        // a * b = quotient * modulus + result in the integers: the vanishing polynomial has a root
+        // at 256, so it is the product of (x - 256) with the witness polynomial.
+        let poly = |limbs: &[AB::Var]| -> Polynomial<AB::Expr> {
+            limbs.iter().map(|&limb| limb.into()).collect()
+        };
+        let p_vanishing =
+            &(&poly(&a) * &poly(&b)) - &(&poly(&local.quotient) * &poly(&modulus)) - poly(&result);
+        let limb = AB::Expr::from_canonical_u32(1 << 8);
+        let offset = AB::Expr::from_canonical_usize(UINT256_WITNESS_OFFSET);
+        let p_witness: Polynomial<AB::Expr> = local
+            .witness_low
+            .iter()
+            .zip(local.witness_high.iter())
+            .map(|(&low, &high)| low.into() + high.into() * limb.clone() - offset.clone())
+            .collect();
+        let root_monomial = Polynomial::new(vec![-limb, AB::Expr::one()]);
+        let constraints = p_vanishing - &(p_witness * root_monomial);
+        for constraint in constraints.as_coefficients() {
+            builder.when(local.is_real).assert_zero(constraint);
+        }
+
+        // result + result_gap + 1 = modulus, so the result is less than a non-zero modulus.
+        for i in 0..UINT256_NUM_LIMBS {
+            let carry_in: AB::Expr = if i == 0 {
+                local.is_real.into()
+            } else {
+                local.gap_carry[i - 1].into()
+            };
+            builder.assert_bool(local.gap_carry[i]);
+            builder.assert_eq(
+                result[i] + local.result_gap[i] + carry_in,
+                modulus[i] + local.gap_carry[i] * AB::F::from_canonical_u32(1 << 8),
+            );
+        }
+        builder.assert_zero(local.gap_carry[UINT256_NUM_LIMBS - 1]);
+
+        // The limbs are bytes, and the witness limbs are u16s split into bytes.
+        builder.slice_range_check_u8(&result, local.is_real);
+        builder.slice_range_check_u8(&local.quotient, local.is_real);
+        builder.slice_range_check_u8(&local.result_gap, local.is_real);
+        builder.slice_range_check_u8(&local.witness_low, local.is_real);
+        builder.slice_range_check_u8(&local.witness_high, local.is_real);
+
+        // Constrain the memory accesses.
+        let a_ptr = local.a_ptr_access.value().reduce::<AB>();
+        let b_ptr = local.b_ptr_access.value().reduce::<AB>();
+        let modulus_ptr = local.modulus_ptr_access.value().reduce::<AB>();
+        for (register, access) in [
+            (Register::X10, &local.a_ptr_access),
+            (Register::X11, &local.b_ptr_access),
+            (Register::X12, &local.modulus_ptr_access),
+        ] {
+            builder.constraint_memory_access(
+                local.shard,
+                local.clk, // clk + 0 -> Memory
+                AB::F::from_canonical_u32(register as u32),
+                access,
+                local.is_real,
+            );
+        }
+        for i in 0..UINT256_NUM_WORDS {
+            let word_offset = AB::F::from_canonical_usize(4 * i);
+            builder.constraint_memory_access(
+                local.shard,
+                local.clk, // clk + 0 -> Memory
+                b_ptr.clone() + word_offset,
+                &local.b_access[i],
+                local.is_real,
+            );
+            builder.constraint_memory_access(
+                local.shard,
+                local.clk, // clk + 0 -> Memory
+                modulus_ptr.clone() + word_offset,
+                &local.modulus_access[i],
+                local.is_real,
+            );
+            builder.constraint_memory_access(
+                local.shard,
+                local.clk + AB::F::from_canonical_u32(4), // clk + 4 -> Memory
+                a_ptr.clone() + word_offset,
+                &local.a_access[i],
+                local.is_real,
+            );
+        }
+
+        // Receive the syscall from the ECALL which invokes the product.
+        builder.receive_syscall(
+            local.shard,
+            local.clk + AB::F::from_canonical_u32(self.num_extra_cycles()),
+            AB::F::from_canonical_u32(SyscallCode::UINT256_MULMOD as u32),
+            a_ptr,
+            AB::F::zero(),
+            local.is_real,
+        );
+    }
+}
@@ -0,0 +1,44 @@
+use core::borrow::{Borrow, BorrowMut};
+use core::mem::size_of;
+
+use sp1_derive::AlignedBorrow;
+
+use super::{
+    UINT256_NUM_LIMBS, UINT256_NUM_QUOTIENT_LIMBS, UINT256_NUM_WITNESS_LIMBS, UINT256_NUM_WORDS,
+};
+use crate::memory::{MemoryReadCols, MemoryWriteCols};
+
+pub const NUM_UINT256_MULMOD_COLS: usize = size_of::<Uint256MulModCols<u8>>();
+
+#[derive(AlignedBorrow, Default, Debug, Clone, Copy)]
+#[repr(C)]
+pub struct Uint256MulModCols<T> {
+    /// Inputs.
+    pub shard: T,
+    pub clk: T,
+
+    /// The pointers to a, b and the modulus, read from a0, a1 and a2.
+    pub a_ptr_access: MemoryReadCols<T>,
+    pub b_ptr_access: MemoryReadCols<T>,
+    pub modulus_ptr_access: MemoryReadCols<T>,
+
+    pub b_access: [MemoryReadCols<T>; UINT256_NUM_WORDS],
+    pub modulus_access: [MemoryReadCols<T>; UINT256_NUM_WORDS],
+
+    /// The words of a, replaced by the result.
+    pub a_access: [MemoryWriteCols<T>; UINT256_NUM_WORDS],
+
+    /// The quotient of `a * b` by the modulus.
+    pub quotient: [T; UINT256_NUM_QUOTIENT_LIMBS],
+
+    /// The witness of `a * b - quotient * modulus - result`, split into its low and high bytes.
+    pub witness_low: [T; UINT256_NUM_WITNESS_LIMBS],
+    pub witness_high: [T; UINT256_NUM_WITNESS_LIMBS],
+
+    /// The limbs of `modulus - 1 - result`, and the carries of its addition to the result, which
+    /// show that the result is less than the modulus.
+    pub result_gap: [T; UINT256_NUM_LIMBS],
+    pub gap_carry: [T; UINT256_NUM_LIMBS],
+
+    pub is_real: T,
+}
@@ -0,0 +1,71 @@
+use num::BigUint;
+
+use crate::{
+    runtime::{Register, Syscall},
+    syscall::precompiles::{
+        uint256::{Uint256MulModEvent, UINT256_NUM_WORDS},
+        SyscallContext,
+    },
+};
+
+use super::Uint256MulModChip;
+
+impl Syscall for Uint256MulModChip {
+    fn execute(&self, rt: &mut SyscallContext) -> u32 {
+        let start_clk = rt.clk;
+
+        // Read the pointers to a, b and the modulus from a0, a1 and a2.
+        let (a_ptr_record, a_ptr) = rt.mr(Register::X10 as u32);
+        let (b_ptr_record, b_ptr) = rt.mr(Register::X11 as u32);
+        let (modulus_ptr_record, modulus_ptr) = rt.mr(Register::X12 as u32);
+        if a_ptr % 4 != 0 || b_ptr % 4 != 0 || modulus_ptr % 4 != 0 {
+            panic!();
+        }
+
+        let a: [u32; UINT256_NUM_WORDS] = rt
+            .slice_unsafe(a_ptr, UINT256_NUM_WORDS)
+            .try_into()
+            .unwrap();
+        let (b_memory_records, b) = rt.mr_slice(b_ptr, UINT256_NUM_WORDS);
+        let (modulus_memory_records, modulus) = rt.mr_slice(modulus_ptr, UINT256_NUM_WORDS);
+        let modulus_int = BigUint::from_slice(&modulus);
+        if modulus_int == BigUint::from(0u32) {
+            panic!("uint256 mulmod by a zero modulus");
+        }
+
+        let result = (BigUint::from_slice(&a) * BigUint::from_slice(&b)) % modulus_int;
+        let mut result_words = result.to_u32_digits();
+        result_words.resize(UINT256_NUM_WORDS, 0);
+
+        // When we write to a, we want the clk to be incremented.
+        rt.clk += 4;
+        let a_memory_records = rt.mw_slice(a_ptr, &result_words);
+        rt.clk += 4;
+
+        let shard = rt.current_shard();
+        rt.record_mut()
+            .uint256_mulmod_events
+            .push(Uint256MulModEvent {
+                shard,
+                clk: start_clk,
+                a_ptr,
+                a,
+                b_ptr,
+                b: b.try_into().unwrap(),
+                modulus_ptr,
+                modulus: modulus.try_into().unwrap(),
+                a_ptr_record,
+                b_ptr_record,
+                modulus_ptr_record,
+                b_memory_records: b_memory_records.try_into().unwrap(),
+                modulus_memory_records: modulus_memory_records.try_into().unwrap(),
+                a_memory_records: a_memory_records.try_into().unwrap(),
+            });
+
+        a_ptr
+    }
+
+    fn num_extra_cycles(&self) -> u32 {
+        8
+    }
+}
@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+
+use crate::syscall::precompiles::{MemoryReadRecord, MemoryWriteRecord};
+
+mod air;
+pub mod columns;
+mod execute;
+mod trace;
+
+/// The number of words of an operand of the `UINT256_MULMOD` precompile.
+pub const UINT256_NUM_WORDS: usize = 8;
+
+/// The number of byte limbs of an operand of the `UINT256_MULMOD` precompile.
+pub const UINT256_NUM_LIMBS: usize = 4 * UINT256_NUM_WORDS;
+
+/// The number of byte limbs of the quotient of `a * b` by the modulus, which is at most 512 bits.
+pub const UINT256_NUM_QUOTIENT_LIMBS: usize = 2 * UINT256_NUM_LIMBS;
+
+/// The number of limbs of the witness of the vanishing polynomial `a * b - q * m - r`, whose degree
+/// is that of `q * m`.
+pub const UINT256_NUM_WITNESS_LIMBS: usize = UINT256_NUM_QUOTIENT_LIMBS + UINT256_NUM_LIMBS - 2;
+
+/// The shift making the coefficients of the witness polynomial positive.
+pub const UINT256_WITNESS_OFFSET: usize = 1 << 14;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Uint256MulModEvent {
+    pub shard: u32,
+    pub clk: u32,
+    pub a_ptr: u32,
+    pub a: [u32; UINT256_NUM_WORDS],
+    pub b_ptr: u32,
+    pub b: [u32; UINT256_NUM_WORDS],
+    pub modulus_ptr: u32,
+    pub modulus: [u32; UINT256_NUM_WORDS],
+
+    pub a_ptr_record: MemoryReadRecord,
+    pub b_ptr_record: MemoryReadRecord,
+    pub modulus_ptr_record: MemoryReadRecord,
+
+    pub b_memory_records: [MemoryReadRecord; UINT256_NUM_WORDS],
+    pub modulus_memory_records: [MemoryReadRecord; UINT256_NUM_WORDS],
+    pub a_memory_records: [MemoryWriteRecord; UINT256_NUM_WORDS],
+}
+
+/// A precompile replacing the 256-bit integer at a0 by its product with the one at a1, modulo the
+/// one at a2. The integers are little endian words, and the modulus must not be zero.
+#[derive(Default)]
+pub struct Uint256MulModChip;
+
+impl Uint256MulModChip {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(test)]
+pub mod uint256_tests {
+    use num::bigint::RandBigInt;
+    use num::{BigUint, One, Zero};
+    use rand::thread_rng;
+
+    use crate::runtime::{Instruction, Opcode, Program, Runtime, SyscallCode};
+    use crate::utils::{run_test, setup_logger};
+
+    use super::UINT256_NUM_WORDS;
+
+    fn words(value: &BigUint) -> [u32; UINT256_NUM_WORDS] {
+        let mut words = [0; UINT256_NUM_WORDS];
+        for (word, digit) in words.iter_mut().zip(value.iter_u32_digits()) {
+            *word = digit;
+        }
+        words
+    }
+
+    fn operands() -> Vec<(BigUint, BigUint, BigUint)> {
+        let mut rng = thread_rng();
+        let max = (BigUint::one() << 256) - 1u32;
+        let mut operands = (0..8)
+            .map(|_| {
+                let modulus = rng.gen_biguint(256).max(BigUint::one());
+                (rng.gen_biguint(256), rng.gen_biguint(256), modulus)
+            })
+            .collect::<Vec<_>>();
+        operands.extend(vec![
+            (BigUint::zero(), max.clone(), max.clone()),
+            (max.clone(), max.clone(), max.clone()),
+            (max.clone(), max.clone(), BigUint::one()),
+            (max.clone(), max.clone(), BigUint::from(7u32)),
+            // An operand larger than the modulus.
+            (max.clone(), BigUint::one(), BigUint::from(1u32 << 31)),
+        ]);
+        operands
+    }
+
+    /// A program multiplying each triple of operands in its own memory slots, with the expected
+    /// product at the address of its first operand.
+    pub fn uint256_mulmod_program() -> (Program, Vec<(u32, BigUint)>) {
+        let mut instructions = vec![];
+        let mut expected = vec![];
+        let mut ptr = 0x1000;
+        for (a, b, modulus) in operands() {
+            let (a_ptr, b_ptr, modulus_ptr) = (ptr, ptr + 32, ptr + 64);
+            for (base, value) in [(a_ptr, &a), (b_ptr, &b), (modulus_ptr, &modulus)] {
+                for (i, word) in words(value).into_iter().enumerate() {
+                    instructions.extend(vec![
+                        Instruction::new(Opcode::ADD, 29, 0, word, false, true),
+                        Instruction::new(Opcode::ADD, 30, 0, base + 4 * i as u32, false, true),
+                        Instruction::new(Opcode::SW, 29, 30, 0, false, true),
+                    ]);
+                }
+            }
+            instructions.extend(vec![
+                Instruction::new(
+                    Opcode::ADD,
+                    5,
+                    0,
+                    SyscallCode::UINT256_MULMOD as u32,
+                    false,
+                    true,
+                ),
+                Instruction::new(Opcode::ADD, 10, 0, a_ptr, false, true),
+                Instruction::new(Opcode::ADD, 11, 0, b_ptr, false, true),
+                Instruction::new(Opcode::ADD, 12, 0, modulus_ptr, false, true),
+                Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+            ]);
+            expected.push((a_ptr, (a * b) % modulus));
+            ptr += 96;
+        }
+        (Program::new(instructions, 0, 0), expected)
+    }
+
+    #[test]
+    fn test_uint256_mulmod_execute() {
+        let (program, expected) = uint256_mulmod_program();
+        let mut runtime = Runtime::new(program);
+        runtime.run();
+        for (ptr, value) in expected.iter() {
+            let result = (0..UINT256_NUM_WORDS as u32)
+                .map(|i| runtime.word(ptr + 4 * i))
+                .collect::<Vec<_>>();
+            assert_eq!(result, words(value));
+        }
+        assert_eq!(runtime.record.uint256_mulmod_events.len(), expected.len());
+    }
+
+    #[test]
+    fn test_uint256_mulmod_prove_babybear() {
+        setup_logger();
+        let (program, _) = uint256_mulmod_program();
+        run_test(program).unwrap();
+    }
+}
@@ -0,0 +1,144 @@
+use std::borrow::BorrowMut;
+
+use num::{BigUint, One};
+use p3_field::PrimeField32;
+use p3_matrix::dense::RowMajorMatrix;
+use tracing::instrument;
+
+use crate::{
+    air::{EventKind, MachineAir, Polynomial},
+    operations::field::util::{compute_root_quotient_and_shift, split_u16_limbs_to_u8_limbs},
+    runtime::ExecutionRecord,
+    utils::pad_rows,
+};
+
+use super::{
+    columns::{Uint256MulModCols, NUM_UINT256_MULMOD_COLS},
+    Uint256MulModChip, UINT256_NUM_LIMBS, UINT256_NUM_QUOTIENT_LIMBS, UINT256_WITNESS_OFFSET,
+};
+
+/// The `len` little endian bytes of `value`.
+fn to_limbs(value: &BigUint, len: usize) -> Vec<u8> {
+    let mut limbs = value.to_bytes_le();
+    limbs.resize(len, 0);
+    limbs
+}
+
+fn to_field<F: PrimeField32>(limbs: &[u8]) -> Vec<F> {
+    limbs
+        .iter()
+        .map(|&limb| F::from_canonical_u8(limb))
+        .collect()
+}
+
+impl<F: PrimeField32> MachineAir<F> for Uint256MulModChip {
+    fn name(&self) -> String {
+        "Uint256MulMod".to_string()
+    }
+
+    fn produces(&self) -> &[EventKind] {
+        &[EventKind::ByteLookup, EventKind::Field]
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::Uint256MulMod]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.uint256_mulmod_events.len()
+    }
+
+    #[instrument(name = "generate Uint256MulMod trace", skip_all)]
+    fn generate_trace(
+        &self,
+        input: &ExecutionRecord,
+        output: &mut ExecutionRecord,
+    ) -> RowMajorMatrix<F> {
+        let mut rows = Vec::new();
+        let mut new_field_events = Vec::new();
+
+        for event in input.uint256_mulmod_events.iter() {
+            let mut row = [F::zero(); NUM_UINT256_MULMOD_COLS];
+            let cols: &mut Uint256MulModCols<F> = row.as_mut_slice().borrow_mut();
+
+            cols.shard = F::from_canonical_u32(event.shard);
+            cols.clk = F::from_canonical_u32(event.clk);
+            cols.is_real = F::one();
+
+            cols.a_ptr_access
+                .populate(event.a_ptr_record, &mut new_field_events);
+            cols.b_ptr_access
+                .populate(event.b_ptr_record, &mut new_field_events);
+            cols.modulus_ptr_access
+                .populate(event.modulus_ptr_record, &mut new_field_events);
+            for i in 0..event.a.len() {
+                cols.b_access[i].populate(event.b_memory_records[i], &mut new_field_events);
+                cols.modulus_access[i]
+                    .populate(event.modulus_memory_records[i], &mut new_field_events);
+                cols.a_access[i].populate(event.a_memory_records[i], &mut new_field_events);
+            }
+
+            // Compute the result and the quotient in the integers.
+            let a = BigUint::from_slice(&event.a);
+            let b = BigUint::from_slice(&event.b);
+            let modulus = BigUint::from_slice(&event.modulus);
+            let product = &a * &b;
+            let result = &product % &modulus;
+            let quotient = &product / &modulus;
+            let gap = &modulus - BigUint::one() - &result;
+
+            let a_limbs = to_limbs(&a, UINT256_NUM_LIMBS);
+            let b_limbs = to_limbs(&b, UINT256_NUM_LIMBS);
+            let modulus_limbs = to_limbs(&modulus, UINT256_NUM_LIMBS);
+            let result_limbs = to_limbs(&result, UINT256_NUM_LIMBS);
+            let quotient_limbs = to_limbs(&quotient, UINT256_NUM_QUOTIENT_LIMBS);
+            let gap_limbs = to_limbs(&gap, UINT256_NUM_LIMBS);
+
+            // The witness of the vanishing polynomial a * b - quotient * modulus - result.
+            let p_a = Polynomial::new(to_field::<F>(&a_limbs));
+            let p_b = Polynomial::new(to_field::<F>(&b_limbs));
+            let p_modulus = Polynomial::new(to_field::<F>(&modulus_limbs));
+            let p_result = Polynomial::new(to_field::<F>(&result_limbs));
+            let p_quotient = Polynomial::new(to_field::<F>(&quotient_limbs));
+            let p_vanishing = &(&p_a * &p_b) - &(&p_quotient * &p_modulus) - p_result;
+            let p_witness =
+                compute_root_quotient_and_shift(&p_vanishing, UINT256_WITNESS_OFFSET, 8);
+            let (p_witness_low, p_witness_high) = split_u16_limbs_to_u8_limbs(&p_witness);
+
+            cols.quotient = to_field::<F>(&quotient_limbs).try_into().unwrap();
+            cols.witness_low = p_witness_low.clone().try_into().unwrap();
+            cols.witness_high = p_witness_high.clone().try_into().unwrap();
+            cols.result_gap = to_field::<F>(&gap_limbs).try_into().unwrap();
+
+            // The carries of result + gap + 1 = modulus.
+            let mut carry = 1;
+            for i in 0..UINT256_NUM_LIMBS {
+                carry = (result_limbs[i] as u32 + gap_limbs[i] as u32 + carry) >> 8;
+                cols.gap_carry[i] = F::from_canonical_u32(carry);
+            }
+
+            let witness_bytes = |limbs: &[F]| {
+                limbs
+                    .iter()
+                    .map(|limb| limb.as_canonical_u32() as u8)
+                    .collect::<Vec<_>>()
+            };
+            output.add_u8_range_checks(&result_limbs);
+            output.add_u8_range_checks(&quotient_limbs);
+            output.add_u8_range_checks(&gap_limbs);
+            output.add_u8_range_checks(&witness_bytes(&p_witness_low));
+            output.add_u8_range_checks(&witness_bytes(&p_witness_high));
+
+            rows.push(row);
+        }
+        output.add_field_events(&new_field_events);
+
+        pad_rows(&mut rows, || [F::zero(); NUM_UINT256_MULMOD_COLS]);
+
+        // Convert the trace to a row major matrix.
+        RowMajorMatrix::new(
+            rows.into_iter().flatten().collect::<Vec<_>>(),
+            NUM_UINT256_MULMOD_COLS,
+        )
+    }
+}
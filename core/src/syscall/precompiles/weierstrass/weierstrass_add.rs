@@ -1,5 +1,5 @@
-use crate::air::MachineAir;
-use crate::air::SP1AirBuilder;
+use crate::air::{EventKind, MachineAir};
+use crate::air::{SP1AirBuilder, SyscallAirBuilder};
 use crate::memory::MemoryCols;
 use crate::memory::MemoryReadCols;
 use crate::memory::MemoryWriteCols;
@@ -8,7 +8,7 @@ use crate::operations::field::field_op::FieldOperation;
 use crate::operations::field::params::NUM_LIMBS;
 use crate::runtime::ExecutionRecord;
 use crate::runtime::Register;
-use crate::runtime::Syscall;
+use crate::runtime::{Syscall, SyscallCode};
 use crate::syscall::precompiles::create_ec_add_event;
 use crate::syscall::precompiles::SyscallContext;
 use crate::utils::ec::weierstrass::WeierstrassParameters;
@@ -149,6 +149,18 @@ impl<F: PrimeField32, E: EllipticCurve + WeierstrassParameters> MachineAir<F>
         "WeierstrassAddAssign".to_string()
     }
 
+    fn produces(&self) -> &[EventKind] {
+        &[EventKind::Field]
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::WeierstrassAdd]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.weierstrass_add_events.len()
+    }
+
     fn generate_trace(
         &self,
         input: &ExecutionRecord,
@@ -334,6 +346,16 @@ where
             &row.p_access,
             row.is_real,
         );
+
+        // Receive the syscall from the ECALL which invokes the addition.
+        builder.receive_syscall(
+            row.shard,
+            row.clk + AB::F::from_canonical_u32(self.num_extra_cycles()),
+            AB::F::from_canonical_u32(SyscallCode::SECP256K1_ADD as u32),
+            row.p_ptr,
+            AB::F::zero(),
+            row.is_real,
+        );
     }
 }
 
@@ -1,12 +1,12 @@
-use crate::air::MachineAir;
-use crate::air::SP1AirBuilder;
+use crate::air::{EventKind, MachineAir};
+use crate::air::{SP1AirBuilder, SyscallAirBuilder};
 use crate::memory::MemoryCols;
 use crate::memory::MemoryWriteCols;
 use crate::operations::field::field_op::FieldOpCols;
 use crate::operations::field::field_op::FieldOperation;
 use crate::operations::field::params::NUM_LIMBS;
 use crate::runtime::ExecutionRecord;
-use crate::runtime::Syscall;
+use crate::runtime::{Syscall, SyscallCode};
 use crate::syscall::precompiles::create_ec_double_event;
 use crate::syscall::precompiles::limbs_from_biguint;
 use crate::syscall::precompiles::SyscallContext;
@@ -165,6 +165,18 @@ impl<F: PrimeField32, E: EllipticCurve + WeierstrassParameters> MachineAir<F>
         "WeierstrassDoubleAssign".to_string()
     }
 
+    fn produces(&self) -> &[EventKind] {
+        &[EventKind::Field]
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::WeierstrassDouble]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.weierstrass_double_events.len()
+    }
+
     #[instrument(name = "generate WeierstrassDoubleAssign trace", skip_all)]
     fn generate_trace(
         &self,
@@ -361,6 +373,16 @@ where
             &row.p_access,
             row.is_real,
         );
+
+        // Receive the syscall from the ECALL which invokes the doubling.
+        builder.receive_syscall(
+            row.shard,
+            row.clk + AB::F::from_canonical_u32(self.num_extra_cycles()),
+            AB::F::from_canonical_u32(SyscallCode::SECP256K1_DOUBLE as u32),
+            row.p_ptr,
+            AB::F::zero(),
+            row.is_real,
+        );
     }
 }
 
@@ -39,6 +39,7 @@ impl Syscall for SyscallExitUnconstrained {
     fn execute(&self, ctx: &mut SyscallContext) -> u32 {
         // Reset the state of the runtime.
         if ctx.rt.unconstrained {
+            let exit_pc = ctx.rt.state.pc;
             ctx.rt.state.global_clk = ctx.rt.unconstrained_state.global_clk;
             ctx.rt.state.clk = ctx.rt.unconstrained_state.clk;
             ctx.rt.state.pc = ctx.rt.unconstrained_state.pc;
@@ -55,6 +56,11 @@ impl Syscall for SyscallExitUnconstrained {
             }
             ctx.rt.record = std::mem::take(&mut ctx.rt.unconstrained_state.record);
             ctx.rt.cpu_record = std::mem::take(&mut ctx.rt.unconstrained_state.op_record);
+            // The events of the block are dropped, so the last recorded instruction is followed
+            // by this ecall.
+            if let Some(event) = ctx.rt.record.cpu_events.last_mut() {
+                event.next_pc = exit_pc;
+            }
             ctx.rt.unconstrained = false;
         }
         ctx.rt.unconstrained_state = ForkState::default();
@@ -1,8 +1,22 @@
+use std::io::Write;
+
 use crate::{
     runtime::{Register, Syscall, SyscallContext},
     utils::u32_to_comma_separated,
 };
 
+/// The file descriptor of the public output stream.
+pub const FD_PUBLIC_OUTPUT: u32 = 3;
+
+/// The file descriptor of the hint stream, whose bytes are appended to the input stream.
+pub const FD_HINT: u32 = 4;
+
+/// The error returned to the program when writing to a file descriptor without a sink.
+pub const EBADF: u32 = 9;
+
+/// The error returned to the program when the sink of a file descriptor fails.
+pub const EIO: u32 = 5;
+
 pub struct SyscallWrite;
 
 impl SyscallWrite {
@@ -18,55 +32,68 @@ impl Syscall for SyscallWrite {
         let a2 = Register::X12;
         let rt = &mut ctx.rt;
         let fd = rt.register(a0);
-        if fd == 1 || fd == 2 || fd == 3 || fd == 4 {
-            let write_buf = rt.register(a1);
-            let nbytes = rt.register(a2);
-            // Read nbytes from memory starting at write_buf.
-            let bytes = (0..nbytes)
-                .map(|i| rt.byte(write_buf + i))
-                .collect::<Vec<u8>>();
-            let slice = bytes.as_slice();
-            if fd == 1 {
-                let s = core::str::from_utf8(slice).unwrap();
-                if s.contains("cycle-tracker-start:") {
-                    let fn_name = s
-                        .split("cycle-tracker-start:")
-                        .last()
-                        .unwrap()
-                        .trim_end()
-                        .trim_start();
-                    let depth = rt.cycle_tracker.len() as u32;
-                    rt.cycle_tracker
-                        .insert(fn_name.to_string(), (rt.state.global_clk, depth));
-                    let padding = (0..depth).map(|_| "│ ").collect::<String>();
-                    log::info!("{}┌╴{}", padding, fn_name);
-                } else if s.contains("cycle-tracker-end:") {
-                    let fn_name = s
-                        .split("cycle-tracker-end:")
-                        .last()
-                        .unwrap()
-                        .trim_end()
-                        .trim_start();
-                    let (start, depth) = rt.cycle_tracker.remove(fn_name).unwrap_or((0, 0));
-                    // Leftpad by 2 spaces for each depth.
-                    let padding = (0..depth).map(|_| "│ ").collect::<String>();
-                    log::info!(
-                        "{}└╴{} cycles",
-                        padding,
-                        u32_to_comma_separated(rt.state.global_clk - start)
-                    );
-                } else {
-                    log::info!("stdout: {}", s.trim_end());
-                }
-            } else if fd == 2 {
-                let s = core::str::from_utf8(slice).unwrap();
-                log::info!("stderr: {}", s.trim_end());
-            } else if fd == 3 {
+        if fd == 0 || (fd > FD_HINT && !rt.output_sinks.contains_key(&fd)) {
+            return EBADF;
+        }
+        let write_buf = rt.register(a1);
+        let nbytes = rt.register(a2);
+        // Read nbytes from memory starting at write_buf.
+        let bytes = (0..nbytes)
+            .map(|i| rt.byte(write_buf + i))
+            .collect::<Vec<u8>>();
+        let slice = bytes.as_slice();
+        if fd == 1 {
+            let s = core::str::from_utf8(slice).unwrap();
+            if s.contains("cycle-tracker-start:") {
+                let fn_name = s
+                    .split("cycle-tracker-start:")
+                    .last()
+                    .unwrap()
+                    .trim_end()
+                    .trim_start();
+                let depth = rt.cycle_tracker.len() as u32;
+                rt.cycle_tracker
+                    .insert(fn_name.to_string(), (rt.state.global_clk, depth));
+                let padding = (0..depth).map(|_| "│ ").collect::<String>();
+                log::info!("{}┌╴{}", padding, fn_name);
+            } else if s.contains("cycle-tracker-end:") {
+                let fn_name = s
+                    .split("cycle-tracker-end:")
+                    .last()
+                    .unwrap()
+                    .trim_end()
+                    .trim_start();
+                let (start, depth) = rt.cycle_tracker.remove(fn_name).unwrap_or((0, 0));
+                // Leftpad by 2 spaces for each depth.
+                let padding = (0..depth).map(|_| "│ ").collect::<String>();
+                log::info!(
+                    "{}└╴{} cycles",
+                    padding,
+                    u32_to_comma_separated(rt.state.global_clk - start)
+                );
+            } else {
+                log::info!("stdout: {}", s.trim_end());
+            }
+        } else if fd == 2 {
+            let s = core::str::from_utf8(slice).unwrap();
+            log::info!("stderr: {}", s.trim_end());
+        } else {
+            if fd == FD_PUBLIC_OUTPUT {
                 rt.state.output_stream.extend_from_slice(slice);
-            } else if fd == 4 {
-                rt.state.input_stream.extend_from_slice(slice);
             } else {
-                unreachable!()
+                if fd == FD_HINT {
+                    rt.state.input_stream.extend_from_slice(slice);
+                }
+                rt.state
+                    .fd_outputs
+                    .entry(fd)
+                    .or_default()
+                    .extend_from_slice(slice);
+            }
+            if let Some(sink) = rt.output_sinks.get(&fd) {
+                if sink.borrow_mut().write_all(slice).is_err() {
+                    return EIO;
+                }
             }
         }
         0
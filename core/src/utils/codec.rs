@@ -0,0 +1,367 @@
+//! The canonical encoding of field elements, shared by everything which writes them as bytes.
+//!
+//! An element of the base field is the 4 little-endian bytes of its canonical value, so that it
+//! reads back as a `u32` in the guest. An element of the degree-4 extension field is the encodings
+//! of its coefficients, from the constant one up. Decoding rejects values which are not below the
+//! modulus, so that every element has exactly one encoding.
+
+use std::fmt::{Display, Formatter};
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+
+use p3_field::{AbstractExtensionField, PrimeField32};
+use serde::de::{Error as _, SeqAccess, Visitor};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The length of the encoding of an element of the base field.
+pub const FIELD_BYTES: usize = 4;
+
+/// The degree of the extension field the codec encodes.
+pub const EXT_DEGREE: usize = 4;
+
+/// The length of the encoding of an element of the extension field.
+pub const EXT_BYTES: usize = FIELD_BYTES * EXT_DEGREE;
+
+/// Why bytes do not decode to field elements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodecError {
+    /// The value is not below the modulus of the field.
+    NonCanonical(u32),
+    /// The length is not a multiple of the length of an element.
+    InvalidLength { len: usize, element_len: usize },
+}
+
+impl Display for CodecError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CodecError::NonCanonical(value) => {
+                write!(f, "{} is not a canonical field element", value)
+            }
+            CodecError::InvalidLength { len, element_len } => write!(
+                f,
+                "{} bytes are not a whole number of {} byte elements",
+                len, element_len
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+impl From<CodecError> for io::Error {
+    fn from(err: CodecError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+pub fn encode_field<F: PrimeField32>(value: F) -> [u8; FIELD_BYTES] {
+    value.as_canonical_u32().to_le_bytes()
+}
+
+pub fn decode_field<F: PrimeField32>(bytes: [u8; FIELD_BYTES]) -> Result<F, CodecError> {
+    let value = u32::from_le_bytes(bytes);
+    if value >= F::ORDER_U32 {
+        return Err(CodecError::NonCanonical(value));
+    }
+    Ok(F::from_canonical_u32(value))
+}
+
+pub fn encode_ext<F: PrimeField32, EF: AbstractExtensionField<F>>(value: EF) -> [u8; EXT_BYTES] {
+    assert_eq!(EF::D, EXT_DEGREE, "the codec encodes a degree 4 extension");
+    let mut bytes = [0; EXT_BYTES];
+    for (chunk, coefficient) in bytes
+        .chunks_exact_mut(FIELD_BYTES)
+        .zip(value.as_base_slice())
+    {
+        chunk.copy_from_slice(&encode_field(*coefficient));
+    }
+    bytes
+}
+
+pub fn decode_ext<F: PrimeField32, EF: AbstractExtensionField<F>>(
+    bytes: [u8; EXT_BYTES],
+) -> Result<EF, CodecError> {
+    assert_eq!(EF::D, EXT_DEGREE, "the codec encodes a degree 4 extension");
+    let coefficients = bytes
+        .chunks_exact(FIELD_BYTES)
+        .map(|chunk| decode_field(chunk.try_into().unwrap()))
+        .collect::<Result<Vec<F>, _>>()?;
+    Ok(EF::from_base_slice(&coefficients))
+}
+
+pub fn encode_fields<F: PrimeField32>(values: &[F]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| encode_field(*value))
+        .collect()
+}
+
+pub fn decode_fields<F: PrimeField32>(bytes: &[u8]) -> Result<Vec<F>, CodecError> {
+    check_length(bytes, FIELD_BYTES)?;
+    bytes
+        .chunks_exact(FIELD_BYTES)
+        .map(|chunk| decode_field(chunk.try_into().unwrap()))
+        .collect()
+}
+
+pub fn encode_exts<F: PrimeField32, EF: AbstractExtensionField<F>>(values: &[EF]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| encode_ext(value.clone()))
+        .collect()
+}
+
+pub fn decode_exts<F: PrimeField32, EF: AbstractExtensionField<F>>(
+    bytes: &[u8],
+) -> Result<Vec<EF>, CodecError> {
+    check_length(bytes, EXT_BYTES)?;
+    bytes
+        .chunks_exact(EXT_BYTES)
+        .map(|chunk| decode_ext(chunk.try_into().unwrap()))
+        .collect()
+}
+
+fn check_length(bytes: &[u8], element_len: usize) -> Result<(), CodecError> {
+    if bytes.len() % element_len != 0 {
+        return Err(CodecError::InvalidLength {
+            len: bytes.len(),
+            element_len,
+        });
+    }
+    Ok(())
+}
+
+/// Serializes an array of field elements as their encodings, for `#[serde(serialize_with)]`.
+pub fn serialize_field_array<S: Serializer, F: PrimeField32, const N: usize>(
+    values: &[F; N],
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    [[u8; FIELD_BYTES]; N]: Serialize,
+{
+    values.map(encode_field).serialize(serializer)
+}
+
+/// Deserializes an array of field elements from their encodings, for `#[serde(deserialize_with)]`,
+/// rejecting non-canonical ones.
+pub fn deserialize_field_array<'de, D: Deserializer<'de>, F: PrimeField32, const N: usize>(
+    deserializer: D,
+) -> Result<[F; N], D::Error>
+where
+    [[u8; FIELD_BYTES]; N]: Deserialize<'de>,
+{
+    let encodings = <[[u8; FIELD_BYTES]; N]>::deserialize(deserializer)?;
+    let mut values = [F::zero(); N];
+    for (value, bytes) in values.iter_mut().zip(encodings) {
+        *value = decode_field(bytes).map_err(D::Error::custom)?;
+    }
+    Ok(values)
+}
+
+/// Serializes an array as a tuple, for `#[serde(serialize_with)]` on the arrays longer than the 32
+/// elements serde implements `Serialize` for.
+pub fn serialize_array<S: Serializer, T: Serialize, const N: usize>(
+    values: &[T; N],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut tuple = serializer.serialize_tuple(N)?;
+    for value in values {
+        tuple.serialize_element(value)?;
+    }
+    tuple.end()
+}
+
+/// Deserializes an array written by `serialize_array`, for `#[serde(deserialize_with)]`.
+pub fn deserialize_array<'de, D: Deserializer<'de>, T: Deserialize<'de>, const N: usize>(
+    deserializer: D,
+) -> Result<[T; N], D::Error> {
+    struct ArrayVisitor<T, const N: usize>(PhantomData<T>);
+
+    impl<'de, T: Deserialize<'de>, const N: usize> Visitor<'de> for ArrayVisitor<T, N> {
+        type Value = [T; N];
+
+        fn expecting(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "an array of {} elements", N)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<[T; N], A::Error> {
+            let mut values = Vec::with_capacity(N);
+            for index in 0..N {
+                let value = seq
+                    .next_element()?
+                    .ok_or_else(|| A::Error::invalid_length(index, &self))?;
+                values.push(value);
+            }
+            Ok(values
+                .try_into()
+                .unwrap_or_else(|_| unreachable!("exactly N values are read")))
+        }
+    }
+
+    deserializer.deserialize_tuple(N, ArrayVisitor(PhantomData))
+}
+
+pub fn write_field<F: PrimeField32, W: Write>(writer: &mut W, value: F) -> io::Result<()> {
+    writer.write_all(&encode_field(value))
+}
+
+/// Reads a field element, failing with `io::ErrorKind::InvalidData` if it is not canonical.
+pub fn read_field<F: PrimeField32, R: Read>(reader: &mut R) -> io::Result<F> {
+    let mut bytes = [0; FIELD_BYTES];
+    reader.read_exact(&mut bytes)?;
+    Ok(decode_field(bytes)?)
+}
+
+pub fn write_ext<F: PrimeField32, EF: AbstractExtensionField<F>, W: Write>(
+    writer: &mut W,
+    value: EF,
+) -> io::Result<()> {
+    writer.write_all(&encode_ext(value))
+}
+
+/// Reads an extension field element, failing with `io::ErrorKind::InvalidData` if one of its
+/// coefficients is not canonical.
+pub fn read_ext<F: PrimeField32, EF: AbstractExtensionField<F>, R: Read>(
+    reader: &mut R,
+) -> io::Result<EF> {
+    let mut bytes = [0; EXT_BYTES];
+    reader.read_exact(&mut bytes)?;
+    Ok(decode_ext(bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+    use p3_field::extension::BinomialExtensionField;
+    use p3_field::AbstractField;
+    use proptest::prelude::*;
+
+    use super::*;
+
+    type Ext = BinomialExtensionField<BabyBear, 4>;
+
+    fn canonical() -> impl Strategy<Value = u32> {
+        0..BabyBear::ORDER_U32
+    }
+
+    fn non_canonical() -> impl Strategy<Value = u32> {
+        BabyBear::ORDER_U32..=u32::MAX
+    }
+
+    fn ext(coefficients: [u32; 4]) -> Ext {
+        Ext::from_base_slice(&coefficients.map(BabyBear::from_canonical_u32))
+    }
+
+    proptest! {
+        #[test]
+        fn test_field_roundtrip(value in canonical()) {
+            let element = BabyBear::from_canonical_u32(value);
+            let bytes = encode_field(element);
+            prop_assert_eq!(bytes, value.to_le_bytes());
+            prop_assert_eq!(decode_field::<BabyBear>(bytes), Ok(element));
+
+            let mut stream = Vec::new();
+            write_field(&mut stream, element).unwrap();
+            prop_assert_eq!(read_field::<BabyBear, _>(&mut stream.as_slice()).unwrap(), element);
+        }
+
+        #[test]
+        fn test_field_rejects_non_canonical(value in non_canonical()) {
+            let bytes = value.to_le_bytes();
+            prop_assert_eq!(
+                decode_field::<BabyBear>(bytes),
+                Err(CodecError::NonCanonical(value))
+            );
+            prop_assert!(decode_fields::<BabyBear>(&[[0; 4], bytes].concat()).is_err());
+            let err = read_field::<BabyBear, _>(&mut bytes.as_slice()).unwrap_err();
+            prop_assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+
+        #[test]
+        fn test_ext_roundtrip(coefficients in prop::array::uniform4(canonical())) {
+            let element = ext(coefficients);
+            let bytes = encode_ext::<BabyBear, _>(element);
+            prop_assert_eq!(decode_ext::<BabyBear, Ext>(bytes), Ok(element));
+            let values = [element, Ext::one(), element * element];
+            let encoded = encode_exts::<BabyBear, _>(&values);
+            prop_assert_eq!(decode_exts::<BabyBear, Ext>(&encoded).unwrap(), values);
+
+            let mut stream = Vec::new();
+            write_ext::<BabyBear, _, _>(&mut stream, element).unwrap();
+            prop_assert_eq!(
+                read_ext::<BabyBear, Ext, _>(&mut stream.as_slice()).unwrap(),
+                element
+            );
+        }
+
+        #[test]
+        fn test_ext_rejects_non_canonical(
+            coefficients in prop::array::uniform4(canonical()),
+            index in 0..EXT_DEGREE,
+            value in non_canonical(),
+        ) {
+            let mut bytes = encode_ext::<BabyBear, _>(ext(coefficients));
+            bytes[index * FIELD_BYTES..(index + 1) * FIELD_BYTES]
+                .copy_from_slice(&value.to_le_bytes());
+            prop_assert_eq!(
+                decode_ext::<BabyBear, Ext>(bytes),
+                Err(CodecError::NonCanonical(value))
+            );
+            prop_assert!(decode_exts::<BabyBear, Ext>(&bytes).is_err());
+            prop_assert!(read_ext::<BabyBear, Ext, _>(&mut bytes.as_slice()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_field_array_serde() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Digest(
+            #[serde(
+                serialize_with = "serialize_field_array",
+                deserialize_with = "deserialize_field_array"
+            )]
+            [BabyBear; 2],
+        );
+
+        let digest = Digest([BabyBear::from_canonical_u32(7), -BabyBear::one()]);
+        let bytes = bincode::serialize(&digest).unwrap();
+        assert_eq!(bytes, encode_fields(&digest.0));
+        assert_eq!(bincode::deserialize::<Digest>(&bytes).unwrap(), digest);
+
+        let mut non_canonical = bytes.clone();
+        non_canonical[4..].copy_from_slice(&BabyBear::ORDER_U32.to_le_bytes());
+        assert!(bincode::deserialize::<Digest>(&non_canonical).is_err());
+    }
+
+    #[test]
+    fn test_array_serde() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Words(
+            #[serde(
+                serialize_with = "serialize_array",
+                deserialize_with = "deserialize_array"
+            )]
+            [u32; 48],
+        );
+
+        let words = Words(core::array::from_fn(|i| i as u32 * 3));
+        let bytes = bincode::serialize(&words).unwrap();
+        assert_eq!(bytes.len(), 48 * 4);
+        assert_eq!(bincode::deserialize::<Words>(&bytes).unwrap(), words);
+        assert!(bincode::deserialize::<Words>(&bytes[..47 * 4]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_length() {
+        assert_eq!(
+            decode_fields::<BabyBear>(&[0; 6]),
+            Err(CodecError::InvalidLength {
+                len: 6,
+                element_len: FIELD_BYTES
+            })
+        );
+        assert!(decode_exts::<BabyBear, Ext>(&[0; 20]).is_err());
+        assert!(read_field::<BabyBear, _>(&mut [0u8; 3].as_slice()).is_err());
+    }
+}
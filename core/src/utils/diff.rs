@@ -0,0 +1,203 @@
+//! Localizing where two executions of a program first differ, to hunt down nondeterminism.
+
+use crate::runtime::{Register, Runtime, Snapshot};
+
+/// The first state at which two executions differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// The number of instructions executed when the states first differ.
+    pub first_cycle: u32,
+
+    /// The program counter of the instruction after which the states differ, in the first
+    /// execution, or `None` if they differ before any instruction.
+    pub pc: Option<u32>,
+
+    /// The registers which differ, with their value in each execution.
+    pub register_diffs: Vec<(Register, u32, u32)>,
+
+    /// The lowest address past the registers whose word differs, with its value in each execution.
+    pub first_memory_diff: Option<(u32, u32, u32)>,
+}
+
+/// The index of the first snapshot at which two executions snapshotted at the same interval differ.
+///
+/// Executions which diverged are assumed to stay apart, so that the snapshots can be binary
+/// searched. A missing snapshot differs from any other.
+pub fn diff_snapshots(a: &[Snapshot], b: &[Snapshot]) -> Option<usize> {
+    let common = a.len().min(b.len());
+    let (mut lo, mut hi) = (0, common);
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        if a[mid] == b[mid] {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    if lo == common && a.len() == b.len() {
+        None
+    } else {
+        Some(lo)
+    }
+}
+
+/// Execute clones of the runtimes `a` and `b`, which have not started, and find the first state at
+/// which they differ.
+///
+/// The executions are snapshotted every `interval` instructions to find the first interval over
+/// which they diverge, which is then re-executed from its start and bisected down to a single
+/// instruction. The observers of the runtimes are called again on each re-execution, so that the
+/// nondeterminism they inject is reproduced.
+pub fn diff_executions(a: &Runtime, b: &Runtime, interval: u32) -> Option<Divergence> {
+    let a_snapshots = a.clone().run_and_snapshot(interval);
+    let b_snapshots = b.clone().run_and_snapshot(interval);
+    let index = diff_snapshots(&a_snapshots, &b_snapshots)?;
+
+    let (mut a, mut b) = (a.clone(), b.clone());
+    a.initialize();
+    b.initialize();
+    if index == 0 {
+        return Some(divergence(&a, &b, None));
+    }
+
+    // The states are equal after `lo` instructions and differ after `hi` of them.
+    let mut lo = a_snapshots[index - 1].global_clk;
+    let mut hi = lo + interval;
+    advance(&mut a, lo);
+    advance(&mut b, lo);
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        let (mut a_mid, mut b_mid) = (a.clone(), b.clone());
+        advance(&mut a_mid, mid);
+        advance(&mut b_mid, mid);
+        if Snapshot::capture(&a_mid) == Snapshot::capture(&b_mid) {
+            (a, b, lo) = (a_mid, b_mid, mid);
+        } else {
+            hi = mid;
+        }
+    }
+
+    let pc = a.state.pc;
+    advance(&mut a, hi);
+    advance(&mut b, hi);
+    Some(divergence(&a, &b, Some(pc)))
+}
+
+fn advance(rt: &mut Runtime, global_clk: u32) {
+    if let Err(err) = rt.run_until(global_clk) {
+        panic!("{}", err);
+    }
+}
+
+fn divergence(a: &Runtime, b: &Runtime, pc: Option<u32>) -> Divergence {
+    let register_diffs = a
+        .registers()
+        .into_iter()
+        .zip(b.registers())
+        .enumerate()
+        .filter(|(_, (a, b))| a != b)
+        .map(|(i, (a, b))| (Register::from_u32(i as u32), a, b))
+        .collect();
+    let first_memory_diff = a
+        .state
+        .memory
+        .keys()
+        .chain(b.state.memory.keys())
+        .copied()
+        .filter(|&addr| addr >= 32 && a.word(addr) != b.word(addr))
+        .min()
+        .map(|addr| (addr, a.word(addr), b.word(addr)));
+    Divergence {
+        first_cycle: a.state.global_clk.max(b.state.global_clk),
+        pc,
+        register_diffs,
+        first_memory_diff,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::runtime::{ExecutionObserver, Instruction, Opcode, Program};
+
+    /// Sums 50 down to 1 into x29, storing each partial sum at 0x1000.
+    fn sum_program() -> Program {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, 50, false, true),
+            Instruction::new(Opcode::ADD, 29, 0, 0, false, true),
+            Instruction::new(Opcode::ADD, 29, 29, 5, false, false),
+            Instruction::new(Opcode::SW, 29, 0, 0x1000, false, true),
+            Instruction::new(Opcode::SUB, 5, 5, 1, false, true),
+            Instruction::new(Opcode::BNE, 5, 0, -12i32 as u32, false, true),
+        ];
+        Program::new(instructions, 0, 0)
+    }
+
+    /// A host hook flipping the low bit of x29 before the instruction at `global_clk`.
+    struct FlipRegister {
+        global_clk: u32,
+    }
+
+    impl ExecutionObserver for FlipRegister {
+        fn before_instruction(&self, rt: &mut Runtime) {
+            if rt.state.global_clk == self.global_clk {
+                rt.state.memory.get_mut(&(Register::X29 as u32)).unwrap().0 ^= 1;
+            }
+        }
+    }
+
+    /// A host hook writing to a word the program never touches before the instruction at
+    /// `global_clk`.
+    struct WriteMemory {
+        global_clk: u32,
+    }
+
+    impl ExecutionObserver for WriteMemory {
+        fn before_instruction(&self, rt: &mut Runtime) {
+            if rt.state.global_clk == self.global_clk {
+                rt.state.memory.insert(0x2000, (7, 0, 0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_diff_identical_executions() {
+        let runtime = Runtime::new(sum_program());
+        let a = runtime.clone().run_and_snapshot(16);
+        let b = runtime.clone().run_and_snapshot(16);
+        assert_eq!(a.len(), 2 + 202 / 16);
+        assert_eq!(diff_snapshots(&a, &b), None);
+        assert_eq!(diff_executions(&runtime, &runtime, 16), None);
+    }
+
+    #[test]
+    fn test_diff_executions() {
+        let a = Runtime::new(sum_program());
+
+        // The hook runs before the branch closing the 25th iteration, at pc 20.
+        let mut b = a.clone();
+        b.observers.push(Rc::new(FlipRegister { global_clk: 101 }));
+        for interval in [1, 16, 1000] {
+            let divergence = diff_executions(&a, &b, interval).unwrap();
+            assert_eq!(divergence.first_cycle, 102);
+            assert_eq!(divergence.pc, Some(20));
+            let sum = (26..=50).sum::<u32>();
+            assert_eq!(divergence.register_diffs, [(Register::X29, sum, sum ^ 1)]);
+            assert_eq!(divergence.first_memory_diff, None);
+        }
+
+        let mut b = a.clone();
+        b.observers.push(Rc::new(WriteMemory { global_clk: 57 }));
+        let divergence = diff_executions(&a, &b, 16).unwrap();
+        assert_eq!(divergence.first_cycle, 58);
+        assert!(divergence.register_diffs.is_empty());
+        assert_eq!(divergence.first_memory_diff, Some((0x2000, 0, 7)));
+
+        // The snapshots alone find the interval of the divergence.
+        let a_snapshots = a.clone().run_and_snapshot(16);
+        let b_snapshots = b.clone().run_and_snapshot(16);
+        assert_eq!(diff_snapshots(&a_snapshots, &b_snapshots), Some(4));
+    }
+}
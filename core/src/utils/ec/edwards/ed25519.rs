@@ -123,9 +123,10 @@ pub fn decompress(compressed_point: &CompressedEdwardsY) -> AffinePoint<Ed25519>
     let mut x = ed25519_sqrt(&u_div_v);
 
     // sqrt always returns the nonnegative square root,
-    // so we negate according to the supplied sign bit.
+    // so we negate according to the supplied sign bit. The negation of zero is zero, rather than
+    // the modulus, so that the result stays reduced.
     if sign {
-        x = modulus - &x;
+        x = (modulus - &x) % modulus;
     }
 
     AffinePoint::new(x, y.clone())
@@ -170,4 +171,21 @@ mod tests {
             point = point.clone() + point.clone();
         }
     }
+
+    #[test]
+    fn test_ed25519_decompress_zero_x() {
+        // The points with y = 1 and y = -1 have x = 0, so a set sign bit must still give x = 0.
+        let minus_one = Ed25519BaseField::modulus() - BigUint::one();
+        for y in [BigUint::one(), minus_one] {
+            let mut compressed = [0u8; 32];
+            let y_bytes = y.to_le_bytes();
+            compressed[..y_bytes.len()].copy_from_slice(&y_bytes);
+            for sign in [0, 1] {
+                compressed[31] = (compressed[31] & 0b0111_1111) | (sign << 7);
+                let point = decompress(&CompressedEdwardsY(compressed));
+                assert_eq!(point.x, BigUint::from(0u32));
+                assert_eq!(point.y, y);
+            }
+        }
+    }
 }
@@ -1,3 +1,6 @@
+/// The largest shard size, whose clock cycles still fit in a `u32`.
+pub const MAX_SHARD_SIZE: usize = 1 << 29;
+
 /// Gets the number of rows which by default should be used for each chip to maximize padding.
 ///
 /// Some chips, such as FieldLTU, may use a constant multiple of this value to optimize performance.
@@ -7,6 +10,12 @@ pub fn shard_size() -> usize {
         Err(_) => 1 << 19,
     };
     assert!(value != 0 && (value & (value - 1)) == 0);
+    assert!(
+        value <= MAX_SHARD_SIZE,
+        "SHARD_SIZE {} exceeds the maximum of {}",
+        value,
+        MAX_SHARD_SIZE
+    );
     value
 }
 
@@ -25,3 +34,45 @@ pub fn reconstruct_commitments() -> bool {
         Err(_) => true,
     }
 }
+
+/// Gets the bound on the number of trace cells of a shard, above which proving is refused.
+pub fn max_cells() -> usize {
+    match std::env::var("MAX_CELLS") {
+        Ok(val) => val.parse().unwrap(),
+        Err(_) => crate::stark::DEFAULT_MAX_CELLS,
+    }
+}
+
+/// Gets the flag for whether to check the constraints of every shard before committing to it.
+pub fn self_check() -> bool {
+    match std::env::var("SELF_CHECK") {
+        Ok(val) => val == "true",
+        Err(_) => false,
+    }
+}
+
+/// Gets the flag for whether to report every constraint violation of a shard before proving it.
+pub fn debug_constraints() -> bool {
+    match std::env::var("DEBUG_CONSTRAINTS") {
+        Ok(val) => val == "true",
+        Err(_) => false,
+    }
+}
+
+/// Gets the number of constraint violations reported for each chip when debugging constraints.
+pub fn max_violations() -> usize {
+    match std::env::var("MAX_VIOLATIONS") {
+        Ok(val) => val.parse().unwrap(),
+        Err(_) => 16,
+    }
+}
+
+/// Gets the chip whose traces should be dumped as CSV, and the path to dump them to, from a
+/// `chip:path` value.
+pub fn dump_trace_csv() -> Option<(String, String)> {
+    let value = std::env::var("DUMP_TRACE_CSV").ok()?;
+    let (chip, path) = value
+        .split_once(':')
+        .expect("DUMP_TRACE_CSV must be of the form chip:path");
+    Some((chip.to_string(), path.to_string()))
+}
@@ -0,0 +1,99 @@
+use p3_baby_bear::BabyBear;
+use p3_field::AbstractExtensionField;
+
+use crate::air::Extension;
+
+/// The extension field over which FRI folding is performed.
+pub type FoldExtension = Extension<BabyBear>;
+
+/// The inputs of a single FRI fold step for one opened matrix row.
+#[derive(Debug, Clone, Copy)]
+pub struct FoldInput {
+    /// The point at which the codeword is queried.
+    pub x: BabyBear,
+
+    /// The out-of-domain point at which the polynomial was opened.
+    pub z: FoldExtension,
+
+    /// The random challenge used to batch the reduced openings.
+    pub alpha: FoldExtension,
+
+    /// The claimed evaluation of the polynomial at `z`.
+    pub p_at_z: FoldExtension,
+
+    /// The evaluation of the polynomial at `x`.
+    pub p_at_x: FoldExtension,
+
+    /// The reduced opening accumulated so far at this log height.
+    pub ro: FoldExtension,
+
+    /// The current power of `alpha` at this log height.
+    pub alpha_pow: FoldExtension,
+}
+
+/// The outputs of a single FRI fold step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FoldOutput {
+    /// The updated reduced opening.
+    pub ro: FoldExtension,
+
+    /// The updated power of `alpha`.
+    pub alpha_pow: FoldExtension,
+}
+
+/// The reference implementation of a FRI fold step, i.e. accumulating the quotient
+/// `(p(z) - p(x)) / (z - x)` into the reduced opening.
+pub fn fold_row(input: &FoldInput) -> FoldOutput {
+    let quotient = (input.p_at_z - input.p_at_x) / (input.z - FoldExtension::from_base(input.x));
+    FoldOutput {
+        ro: input.ro + input.alpha_pow * quotient,
+        alpha_pow: input.alpha_pow * input.alpha,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+    use p3_field::AbstractExtensionField;
+    use p3_field::AbstractField;
+    use rand::{thread_rng, Rng};
+
+    use super::{fold_row, FoldExtension, FoldInput};
+    use crate::air::{ext_from_words, Word};
+
+    fn random_ext() -> FoldExtension {
+        let mut rng = thread_rng();
+        ext_from_words([(); 4].map(|_| Word::from(rng.gen_range(0u32..(1 << 27)))))
+    }
+
+    #[test]
+    fn test_fold_row() {
+        for _ in 0..100 {
+            let input = FoldInput {
+                x: BabyBear::from_canonical_u32(thread_rng().gen_range(1..(1 << 27))),
+                z: random_ext(),
+                alpha: random_ext(),
+                p_at_z: random_ext(),
+                p_at_x: random_ext(),
+                ro: random_ext(),
+                alpha_pow: random_ext(),
+            };
+            let output = fold_row(&input);
+
+            // The quotient times `z - x` must give back the difference of the evaluations.
+            let quotient = (output.ro - input.ro) / input.alpha_pow;
+            assert_eq!(
+                quotient * (input.z - FoldExtension::from_base(input.x)),
+                input.p_at_z - input.p_at_x
+            );
+            assert_eq!(output.alpha_pow, input.alpha_pow * input.alpha);
+
+            // Folding a row whose evaluations agree leaves the reduced opening unchanged.
+            let output = fold_row(&FoldInput {
+                p_at_x: input.p_at_z,
+                ..input
+            });
+            assert_eq!(output.ro, input.ro);
+        }
+    }
+}
@@ -25,3 +25,23 @@ pub fn setup_logger() {
             .init();
     });
 }
+
+/// The logger of `setup_logger`, writing to stderr instead of stdout, e.g. to keep stdout for
+/// machine readable output.
+pub fn setup_stderr_logger() {
+    INIT.call_once(|| {
+        let default_filter = "off";
+        let env_filter =
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter));
+        tracing_subscriber::fmt::Subscriber::builder()
+            .compact()
+            .with_file(false)
+            .with_target(false)
+            .with_thread_names(false)
+            .with_env_filter(env_filter)
+            .with_span_events(FmtSpan::CLOSE)
+            .with_writer(std::io::stderr)
+            .finish()
+            .init();
+    });
+}
@@ -0,0 +1,187 @@
+//! Column-major access to traces, for tools which compare, mutate or export them column by column.
+
+use std::io::{self, BufRead, Write};
+
+use p3_field::PrimeField32;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+
+/// The names of the columns of a trace, by column index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnNames(Vec<String>);
+
+impl ColumnNames {
+    pub fn new(names: Vec<String>) -> Self {
+        Self(names)
+    }
+
+    /// Names the columns of a trace of the given width by their index.
+    pub fn indexed(width: usize) -> Self {
+        Self((0..width).map(|i| format!("col_{}", i)).collect())
+    }
+
+    /// The name of the column at index `i`.
+    pub fn name(&self, i: usize) -> &str {
+        &self.0[i]
+    }
+
+    /// The index of the column with the given name.
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.0.iter().position(|column| column == name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// The values of the column at index `i`, from the first row to the last.
+pub fn column<F: Clone>(mat: &RowMajorMatrix<F>, i: usize) -> Vec<F> {
+    assert!(
+        i < mat.width(),
+        "column {} of a matrix of width {}",
+        i,
+        mat.width()
+    );
+    mat.values
+        .iter()
+        .skip(i)
+        .step_by(mat.width())
+        .cloned()
+        .collect()
+}
+
+/// The transpose of the matrix, whose rows are the columns of `mat`.
+pub fn transpose<F: Clone + Send + Sync>(mat: &RowMajorMatrix<F>) -> RowMajorMatrix<F> {
+    let values = (0..mat.width())
+        .flat_map(|i| column(mat, i))
+        .collect::<Vec<_>>();
+    RowMajorMatrix::new(values, mat.height())
+}
+
+/// The columns of the matrix with their names.
+pub fn named_columns<'a, F: Clone>(
+    mat: &'a RowMajorMatrix<F>,
+    names: &'a ColumnNames,
+) -> impl Iterator<Item = (&'a str, Vec<F>)> + 'a {
+    assert_eq!(names.len(), mat.width(), "one name per column");
+    (0..mat.width()).map(move |i| (names.name(i), column(mat, i)))
+}
+
+/// Writes the matrix as CSV, with a header of column names and a line of canonical `u32` values
+/// per row.
+pub fn to_csv<F: PrimeField32, W: Write>(
+    mat: &RowMajorMatrix<F>,
+    names: &ColumnNames,
+    mut writer: W,
+) -> io::Result<()> {
+    assert_eq!(names.len(), mat.width(), "one name per column");
+    writeln!(writer, "{}", names.0.join(","))?;
+    for row in mat.values.chunks(mat.width()) {
+        let row = row
+            .iter()
+            .map(|value| value.as_canonical_u32().to_string())
+            .collect::<Vec<_>>();
+        writeln!(writer, "{}", row.join(","))?;
+    }
+    Ok(())
+}
+
+/// Reads a matrix written by `to_csv`, with the names of its columns.
+///
+/// Values which are not canonical field elements are rejected, so that a CSV round-trips exactly.
+pub fn from_csv<F: PrimeField32, R: BufRead>(
+    reader: R,
+) -> io::Result<(ColumnNames, RowMajorMatrix<F>)> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+    let mut lines = reader.lines();
+    let header = lines
+        .next()
+        .ok_or_else(|| invalid("missing header".to_string()))??;
+    let names = ColumnNames::new(header.split(',').map(String::from).collect());
+
+    let mut values = Vec::new();
+    for (i, line) in lines.enumerate() {
+        let line = line?;
+        let row = line
+            .split(',')
+            .map(|value| {
+                value
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|value| *value < F::ORDER_U32)
+                    .map(F::from_canonical_u32)
+                    .ok_or_else(|| invalid(format!("invalid value {:?} in row {}", value, i)))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        if row.len() != names.len() {
+            return Err(invalid(format!(
+                "row {} has {} values for {} columns",
+                i,
+                row.len(),
+                names.len()
+            )));
+        }
+        values.extend(row);
+    }
+    let width = names.len();
+    Ok((names, RowMajorMatrix::new(values, width)))
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+    use p3_field::AbstractField;
+
+    use super::*;
+
+    fn small_matrix() -> RowMajorMatrix<BabyBear> {
+        let values = (0..12u32)
+            .map(|i| BabyBear::from_canonical_u32(i * 1000))
+            .chain([BabyBear::neg_one(), BabyBear::zero(), BabyBear::one()])
+            .collect();
+        RowMajorMatrix::new(values, 3)
+    }
+
+    #[test]
+    fn test_column() {
+        let mat = small_matrix();
+        for i in 0..mat.width() {
+            let expected = (0..mat.height())
+                .map(|row| mat.values[row * mat.width() + i])
+                .collect::<Vec<_>>();
+            assert_eq!(column(&mat, i), expected);
+        }
+
+        let transposed = transpose(&mat);
+        assert_eq!(transposed.width(), mat.height());
+        assert_eq!(transposed.height(), mat.width());
+        assert_eq!(transpose(&transposed).values, mat.values);
+
+        let names = ColumnNames::new(vec!["a".into(), "b".into(), "c".into()]);
+        let columns = named_columns(&mat, &names).collect::<Vec<_>>();
+        assert_eq!(columns[1], ("b", column(&mat, 1)));
+        assert_eq!(names.index("c"), Some(2));
+    }
+
+    #[test]
+    fn test_csv_roundtrip() {
+        let mat = small_matrix();
+        let names = ColumnNames::indexed(mat.width());
+        let mut csv = Vec::new();
+        to_csv(&mat, &names, &mut csv).unwrap();
+        assert!(csv.starts_with(b"col_0,col_1,col_2\n0,1000,2000\n"));
+        assert!(csv.ends_with(b"2013265920,0,1\n"));
+
+        let (decoded_names, decoded) = from_csv::<BabyBear, _>(csv.as_slice()).unwrap();
+        assert_eq!(decoded_names, names);
+        assert_eq!(decoded.values, mat.values);
+
+        assert!(from_csv::<BabyBear, _>(b"a,b\n1,2013265921\n".as_slice()).is_err());
+        assert!(from_csv::<BabyBear, _>(b"a,b\n1\n".as_slice()).is_err());
+    }
+}
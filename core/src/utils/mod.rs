@@ -1,20 +1,31 @@
 mod buffer;
+pub mod codec;
+mod diff;
 pub mod ec;
 pub mod env;
+pub mod fri;
 mod logger;
+pub mod matrix;
 mod poseidon2_instance;
 mod programs;
 mod prove;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
 mod tracer;
 
 pub use buffer::*;
+pub use diff::*;
 pub use logger::*;
+pub use poseidon2_instance::{Poseidon2BabyBear, Poseidon2Params};
 pub use prove::*;
 pub use tracer::*;
 
 #[cfg(test)]
 pub use programs::*;
 
+use p3_field::Field;
+use p3_matrix::dense::RowMajorMatrix;
+
 use crate::{memory::MemoryCols, operations::field::params::Limbs};
 
 pub const fn indices_arr<const N: usize>() -> [usize; N] {
@@ -60,12 +71,28 @@ pub fn limbs_from_access<T: Copy, M: MemoryCols<T>>(cols: &[M]) -> Limbs<T> {
     Limbs(sized)
 }
 
-pub fn pad_rows<T: Clone, const N: usize>(rows: &mut Vec<[T; N]>, row_fn: impl Fn() -> [T; N]) {
-    let nb_rows = rows.len();
-    let mut padded_nb_rows = nb_rows.next_power_of_two();
+/// Resets `trace` to `height` rows of zeros of the given width, keeping its allocation, for
+/// `MachineAir::generate_trace_into` to fill in place.
+pub fn zero_trace<F: Field>(trace: &mut RowMajorMatrix<F>, width: usize, height: usize) {
+    let mut values = core::mem::take(&mut trace.values);
+    values.clear();
+    values.resize(width * height, F::zero());
+    *trace = RowMajorMatrix::new(values, width);
+}
+
+/// The number of rows of a trace with `nb_rows` real rows once padded.
+pub(crate) fn padded_nb_rows(nb_rows: usize) -> usize {
+    let padded_nb_rows = nb_rows.next_power_of_two();
     if padded_nb_rows == 2 || padded_nb_rows == 1 {
-        padded_nb_rows = 4;
+        4
+    } else {
+        padded_nb_rows
     }
+}
+
+pub fn pad_rows<T: Clone, const N: usize>(rows: &mut Vec<[T; N]>, row_fn: impl Fn() -> [T; N]) {
+    let nb_rows = rows.len();
+    let padded_nb_rows = padded_nb_rows(nb_rows);
     if padded_nb_rows == nb_rows {
         return;
     }
@@ -73,6 +100,12 @@ pub fn pad_rows<T: Clone, const N: usize>(rows: &mut Vec<[T; N]>, row_fn: impl F
     rows.resize(padded_nb_rows, dummy_row);
 }
 
+/// Pads the rows like `pad_rows`, generating each padding row from its index in the trace.
+pub fn pad_rows_to<T, const N: usize>(rows: &mut Vec<[T; N]>, row_fn: impl Fn(usize) -> [T; N]) {
+    let nb_rows = rows.len();
+    rows.extend((nb_rows..padded_nb_rows(nb_rows)).map(row_fn));
+}
+
 /// Converts a slice of words to a byte array in little endian.
 pub fn words_to_bytes_le<const B: usize>(words: &[u32]) -> [u8; B] {
     debug_assert_eq!(words.len() * 4, B);
@@ -1,6 +1,7 @@
 use lazy_static::lazy_static;
 use p3_baby_bear::BabyBear;
 use p3_field::AbstractField;
+use p3_poseidon2::{DiffusionMatrixBabybear, DiffusionPermutation, Poseidon2};
 
 lazy_static! {
     // These constants are created by a RNG.
@@ -550,3 +551,175 @@ lazy_static! {
         ]
     ];
 }
+
+/// The parameters of a Poseidon2 permutation over BabyBear.
+///
+/// `BabyBearPoseidon2` builds its hasher and its challenger from a set of these, and records the
+/// `id` of the set in its verifying keys and proofs, so a proof is never checked with a hasher of
+/// other parameters than the one it was produced with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Poseidon2Params {
+    /// The identifier of the set, recorded in verifying keys and proof headers.
+    pub id: u8,
+    pub name: &'static str,
+    /// The width of the state of the permutation.
+    pub width: usize,
+    /// The number of full rounds, half of them before the partial rounds and half after.
+    pub rounds_f: usize,
+    /// The number of partial rounds.
+    pub rounds_p: usize,
+}
+
+/// A Poseidon2 permutation over BabyBear of the given width, with the S-box of every set of
+/// parameters.
+pub type Poseidon2BabyBear<const WIDTH: usize> =
+    Poseidon2<BabyBear, DiffusionMatrixBabybear, WIDTH, { Poseidon2Params::SBOX_DEGREE }>;
+
+impl Poseidon2Params {
+    /// The degree of the S-box.
+    pub const SBOX_DEGREE: u64 = 7;
+
+    /// Width 16 with 8 full and 22 partial rounds, the parameters of the hasher before they were
+    /// selectable. Its round constants are `RC_16_30`.
+    pub const BABYBEAR_16_30: Poseidon2Params = Poseidon2Params {
+        id: 0,
+        name: "babybear-16-30",
+        width: 16,
+        rounds_f: 8,
+        rounds_p: 22,
+    };
+
+    /// Width 16 with 8 full and 13 partial rounds, the fewest which give 128 bits of security at
+    /// this width and S-box degree.
+    pub const BABYBEAR_16_21: Poseidon2Params = Poseidon2Params {
+        id: 1,
+        name: "babybear-16-21",
+        width: 16,
+        rounds_f: 8,
+        rounds_p: 13,
+    };
+
+    /// Width 24 with 8 full and 21 partial rounds, the fewest which give 128 bits of security at
+    /// this width and S-box degree.
+    pub const BABYBEAR_24_29: Poseidon2Params = Poseidon2Params {
+        id: 2,
+        name: "babybear-24-29",
+        width: 24,
+        rounds_f: 8,
+        rounds_p: 21,
+    };
+
+    /// Every set of parameters, by id.
+    pub const ALL: [Poseidon2Params; 3] = [
+        Self::BABYBEAR_16_30,
+        Self::BABYBEAR_16_21,
+        Self::BABYBEAR_24_29,
+    ];
+
+    /// The set of parameters with the given id.
+    pub fn from_id(id: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|params| params.id == id)
+    }
+
+    /// The total number of rounds.
+    pub fn num_rounds(&self) -> usize {
+        self.rounds_f + self.rounds_p
+    }
+
+    /// The round constants, one row per round.
+    ///
+    /// `BABYBEAR_16_30` keeps the constants of `RC_16_30`. Every other set has its own, read from
+    /// the BLAKE3 output stream of its name four little-endian bytes at a time, so that no two
+    /// sets share their constants.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `WIDTH` is not the width of the parameters.
+    pub fn round_constants<const WIDTH: usize>(&self) -> Vec<[BabyBear; WIDTH]> {
+        assert_eq!(
+            self.width, WIDTH,
+            "the parameters {} have width {}",
+            self.name, self.width
+        );
+        if *self == Self::BABYBEAR_16_30 {
+            return RC_16_30
+                .iter()
+                .map(|row| core::array::from_fn(|i| row[i]))
+                .collect();
+        }
+        let mut reader = blake3::Hasher::new()
+            .update(self.name.as_bytes())
+            .finalize_xof();
+        (0..self.num_rounds())
+            .map(|_| {
+                core::array::from_fn(|_| {
+                    let mut bytes = [0u8; 4];
+                    reader.fill(&mut bytes);
+                    BabyBear::from_wrapped_u32(u32::from_le_bytes(bytes))
+                })
+            })
+            .collect()
+    }
+
+    /// The permutation with these parameters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `WIDTH` is not the width of the parameters.
+    pub fn permutation<const WIDTH: usize>(&self) -> Poseidon2BabyBear<WIDTH>
+    where
+        DiffusionMatrixBabybear: DiffusionPermutation<BabyBear, WIDTH>,
+    {
+        Poseidon2BabyBear::<WIDTH>::new(
+            self.rounds_f,
+            self.rounds_p,
+            self.round_constants(),
+            DiffusionMatrixBabybear,
+        )
+    }
+}
+
+impl Default for Poseidon2Params {
+    fn default() -> Self {
+        Self::BABYBEAR_16_30
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_symmetric::Permutation;
+
+    use super::*;
+
+    #[test]
+    fn test_poseidon2_params() {
+        for (index, params) in Poseidon2Params::ALL.into_iter().enumerate() {
+            assert_eq!(params.id as usize, index);
+            assert_eq!(Poseidon2Params::from_id(params.id), Some(params));
+        }
+        assert_eq!(Poseidon2Params::from_id(3), None);
+
+        // The sets of the same width are different permutations, with their own constants.
+        let [params_16_30, params_16_21, params_24_29] = Poseidon2Params::ALL;
+        let input: [BabyBear; 16] = core::array::from_fn(|i| BabyBear::from_canonical_usize(i));
+        assert_ne!(
+            params_16_30.permutation::<16>().permute(input),
+            params_16_21.permutation::<16>().permute(input)
+        );
+        let constants = params_16_21.round_constants::<16>();
+        assert_eq!(constants.len(), params_16_21.num_rounds());
+        assert_ne!(constants[..], RC_16_30[..params_16_21.num_rounds()]);
+
+        // The width-24 set permutes a state of 24 elements.
+        let input: [BabyBear; 24] = core::array::from_fn(|i| BabyBear::from_canonical_usize(i));
+        let output = params_24_29.permutation::<24>().permute(input);
+        assert_ne!(output, input);
+        assert_eq!(params_24_29.round_constants::<24>().len(), 29);
+    }
+
+    #[test]
+    #[should_panic(expected = "width")]
+    fn test_poseidon2_params_width_mismatch() {
+        Poseidon2Params::BABYBEAR_24_29.round_constants::<16>();
+    }
+}
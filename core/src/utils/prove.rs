@@ -1,10 +1,13 @@
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
 use std::time::Instant;
 
-use crate::utils::poseidon2_instance::RC_16_30;
 use crate::{
+    io::StdinBinding,
+    lookup::InteractionBuilder,
     runtime::{Program, Runtime},
     stark::{LocalProver, OpeningProof, ShardMainData},
-    stark::{RiscvStark, StarkGenericConfig},
+    stark::{ProverError, ProverHooks, RiscvStark, StarkGenericConfig},
 };
 pub use baby_bear_blake3::BabyBearBlake3;
 use p3_commit::Pcs;
@@ -31,7 +34,7 @@ pub fn get_cycles(program: Program) -> u64 {
     runtime.state.global_clk as u64
 }
 
-pub fn prove(program: Program) -> crate::stark::Proof<BabyBearBlake3> {
+pub fn prove(program: Program) -> Result<crate::stark::Proof<BabyBearBlake3>, ProverError> {
     let runtime = tracing::info_span!("runtime.run(...)").in_scope(|| {
         let mut runtime = Runtime::new(program);
         runtime.run();
@@ -42,10 +45,17 @@ pub fn prove(program: Program) -> crate::stark::Proof<BabyBearBlake3> {
 }
 
 #[cfg(test)]
-pub fn run_test(program: Program) -> Result<(), crate::stark::ProgramVerificationError> {
+pub fn run_test(program: Program) -> Result<u64, crate::stark::ProgramVerificationError> {
     #[cfg(not(feature = "perf"))]
     use crate::lookup::{debug_interactions_with_all_chips, InteractionKind};
 
+    // Programs built from instructions rather than from an ELF stop by leaving the program, which
+    // a proof does not accept as the end of the execution.
+    let program = if program.encodings.is_empty() {
+        program.with_halt()
+    } else {
+        program
+    };
     let runtime = tracing::info_span!("runtime.run(...)").in_scope(|| {
         let mut runtime = Runtime::new(program);
         runtime.run();
@@ -53,13 +63,14 @@ pub fn run_test(program: Program) -> Result<(), crate::stark::ProgramVerificatio
     });
     let config = BabyBearBlake3::new();
 
-    let machine = RiscvStark::new(config);
+    let machine = RiscvStark::new(config).with_self_check(true);
     let (pk, vk) = machine.setup(runtime.program.as_ref());
     let mut challenger = machine.config().challenger();
 
     let start = Instant::now();
     let proof = tracing::info_span!("runtime.prove(...)")
-        .in_scope(|| machine.prove::<LocalProver<_>>(&pk, runtime.record, &mut challenger));
+        .in_scope(|| machine.prove::<LocalProver<_>>(&pk, runtime.record, &mut challenger))
+        .unwrap();
 
     #[cfg(not(feature = "perf"))]
     assert!(debug_interactions_with_all_chips(
@@ -83,7 +94,7 @@ pub fn run_test(program: Program) -> Result<(), crate::stark::ProgramVerificatio
     machine.verify(&vk, &proof, &mut challenger)
 }
 
-pub fn prove_elf(elf: &[u8]) -> crate::stark::Proof<BabyBearBlake3> {
+pub fn prove_elf(elf: &[u8]) -> Result<crate::stark::Proof<BabyBearBlake3>, ProverError> {
     let program = Program::from(elf);
     prove(program)
 }
@@ -91,7 +102,7 @@ pub fn prove_elf(elf: &[u8]) -> crate::stark::Proof<BabyBearBlake3> {
 pub fn prove_core<SC: StarkGenericConfig + StarkUtils + Send + Sync + Serialize>(
     config: SC,
     runtime: Runtime,
-) -> crate::stark::Proof<SC>
+) -> Result<crate::stark::Proof<SC>, ProverError>
 where
     SC::Challenger: Clone,
     OpeningProof<SC>: Send + Sync,
@@ -100,17 +111,102 @@ where
     ShardMainData<SC>: Serialize + DeserializeOwned,
     <SC as StarkGenericConfig>::Val: PrimeField32,
 {
-    let mut challenger = config.challenger();
+    prove_core_with_context(config, runtime, &[])
+}
+
+/// Proves the execution of a runtime, binding the proof to an application context.
+pub fn prove_core_with_context<SC: StarkGenericConfig + StarkUtils + Send + Sync + Serialize>(
+    config: SC,
+    runtime: Runtime,
+    context: &[u8],
+) -> Result<crate::stark::Proof<SC>, ProverError>
+where
+    SC::Challenger: Clone,
+    OpeningProof<SC>: Send + Sync,
+    <SC::Pcs as Pcs<SC::Val, RowMajorMatrix<SC::Val>>>::Commitment: Send + Sync,
+    <SC::Pcs as Pcs<SC::Val, RowMajorMatrix<SC::Val>>>::ProverData: Send + Sync,
+    ShardMainData<SC>: Serialize + DeserializeOwned,
+    <SC as StarkGenericConfig>::Val: PrimeField32,
+{
+    prove_core_with_stdin(config, runtime, context, None)
+}
+
+/// Proves the execution of a runtime, binding the proof to an application context and to the
+/// standard input of the program.
+pub fn prove_core_with_stdin<SC: StarkGenericConfig + StarkUtils + Send + Sync + Serialize>(
+    config: SC,
+    runtime: Runtime,
+    context: &[u8],
+    stdin: Option<StdinBinding>,
+) -> Result<crate::stark::Proof<SC>, ProverError>
+where
+    SC::Challenger: Clone,
+    OpeningProof<SC>: Send + Sync,
+    <SC::Pcs as Pcs<SC::Val, RowMajorMatrix<SC::Val>>>::Commitment: Send + Sync,
+    <SC::Pcs as Pcs<SC::Val, RowMajorMatrix<SC::Val>>>::ProverData: Send + Sync,
+    ShardMainData<SC>: Serialize + DeserializeOwned,
+    <SC as StarkGenericConfig>::Val: PrimeField32,
+{
+    prove_core_with_hooks(config, runtime, context, stdin, None)
+}
+
+/// Proves the execution of a runtime like `prove_core_with_stdin`, reporting the progress of the
+/// proof to the hooks.
+pub fn prove_core_with_hooks<SC: StarkGenericConfig + StarkUtils + Send + Sync + Serialize>(
+    config: SC,
+    runtime: Runtime,
+    context: &[u8],
+    stdin: Option<StdinBinding>,
+    hooks: Option<Arc<dyn ProverHooks>>,
+) -> Result<crate::stark::Proof<SC>, ProverError>
+where
+    SC::Challenger: Clone,
+    OpeningProof<SC>: Send + Sync,
+    <SC::Pcs as Pcs<SC::Val, RowMajorMatrix<SC::Val>>>::Commitment: Send + Sync,
+    <SC::Pcs as Pcs<SC::Val, RowMajorMatrix<SC::Val>>>::ProverData: Send + Sync,
+    ShardMainData<SC>: Serialize + DeserializeOwned,
+    <SC as StarkGenericConfig>::Val: PrimeField32,
+{
+    let mut machine = RiscvStark::new(config);
+    if let Some(hooks) = hooks {
+        machine = machine.with_hooks(hooks);
+    }
+    prove_core_with_machine(machine, runtime, context, stdin)
+}
+
+/// Proves the execution of a runtime like `prove_core_with_stdin`, with a machine set up by the
+/// caller, e.g. with hooks or a number of shard workers.
+pub fn prove_core_with_machine<SC: StarkGenericConfig + StarkUtils + Send + Sync + Serialize>(
+    machine: RiscvStark<SC>,
+    runtime: Runtime,
+    context: &[u8],
+    stdin: Option<StdinBinding>,
+) -> Result<crate::stark::Proof<SC>, ProverError>
+where
+    SC::Challenger: Clone,
+    OpeningProof<SC>: Send + Sync,
+    <SC::Pcs as Pcs<SC::Val, RowMajorMatrix<SC::Val>>>::Commitment: Send + Sync,
+    <SC::Pcs as Pcs<SC::Val, RowMajorMatrix<SC::Val>>>::ProverData: Send + Sync,
+    ShardMainData<SC>: Serialize + DeserializeOwned,
+    <SC as StarkGenericConfig>::Val: PrimeField32,
+{
+    let mut challenger = machine.config().challenger();
 
     let start = Instant::now();
 
-    let machine = RiscvStark::new(config);
     let (pk, _) = machine.setup(runtime.program.as_ref());
 
     // Prove the program.
     let cycles = runtime.state.global_clk;
-    let proof = tracing::info_span!("runtime.prove(...)")
-        .in_scope(|| machine.prove::<LocalProver<_>>(&pk, runtime.record, &mut challenger));
+    let proof = tracing::info_span!("runtime.prove(...)").in_scope(|| {
+        machine.prove_with_stdin::<LocalProver<_>>(
+            &pk,
+            runtime.record,
+            context,
+            stdin,
+            &mut challenger,
+        )
+    })?;
     let time = start.elapsed().as_millis();
     let nb_bytes = bincode::serialize(&proof).unwrap().len();
 
@@ -122,7 +218,7 @@ where
         Size::from_bytes(nb_bytes),
     );
 
-    proof
+    Ok(proof)
 }
 
 pub fn uni_stark_prove<SC, A>(
@@ -155,145 +251,342 @@ where
     p3_uni_stark::verify(config.uni_stark_config(), air, challenger, proof)
 }
 
+/// A proof of a single AIR on its own, with the plain uni-stark semantics of Plonky3.
+pub type SingleProof<SC> = Proof<<SC as StarkUtils>::UniConfig>;
+
+/// An error proving or verifying a single AIR on its own.
+#[derive(Debug)]
+pub enum SingleChipError {
+    /// The AIR sends or receives interactions, which only balance against the other chips of a
+    /// machine.
+    Interactions {
+        air: &'static str,
+        sends: usize,
+        receives: usize,
+    },
+    /// The proof does not verify.
+    Verification(p3_uni_stark::VerificationError),
+}
+
+impl Display for SingleChipError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SingleChipError::Interactions {
+                air,
+                sends,
+                receives,
+            } => write!(
+                f,
+                "{} has {} sends and {} receives, which a proof of the AIR on its own cannot \
+                 balance; prove it as a chip of a `RiscvStark` instead",
+                air, sends, receives
+            ),
+            SingleChipError::Verification(err) => write!(f, "verification failed: {:?}", err),
+        }
+    }
+}
+
+impl std::error::Error for SingleChipError {}
+
+/// Checks that the AIR has no interactions, which a uni-stark proof would silently drop.
+fn check_no_interactions<F, A>(air: &A) -> Result<(), SingleChipError>
+where
+    F: p3_field::Field,
+    A: Air<InteractionBuilder<F>>,
+{
+    let mut builder = InteractionBuilder::new(0, <A as BaseAir<F>>::width(air));
+    air.eval(&mut builder);
+    let (sends, receives) = builder.interactions();
+    if sends.is_empty() && receives.is_empty() {
+        Ok(())
+    } else {
+        Err(SingleChipError::Interactions {
+            air: std::any::type_name::<A>(),
+            sends: sends.len(),
+            receives: receives.len(),
+        })
+    }
+}
+
+/// Proves a single AIR on its own from a trace, without the machine: no preprocessed, permutation
+/// or public values, just the constraints of the AIR on the trace.
+///
+/// Meant for experimenting with an AIR and for fast tests of gadgets. The AIR must not have
+/// interactions, as nothing would balance them.
+pub fn prove_single_chip<SC, A>(
+    config: &SC,
+    air: &A,
+    trace: RowMajorMatrix<SC::Val>,
+) -> Result<SingleProof<SC>, SingleChipError>
+where
+    SC: StarkUtils,
+    A: Air<InteractionBuilder<SC::Val>>
+        + Air<p3_uni_stark::SymbolicAirBuilder<SC::Val>>
+        + for<'a> Air<p3_uni_stark::ProverConstraintFolder<'a, SC::UniConfig>>
+        + for<'a> Air<p3_uni_stark::DebugConstraintBuilder<'a, SC::Val>>,
+{
+    check_no_interactions(air)?;
+    let mut challenger = config.challenger();
+    Ok(uni_stark_prove(config, air, &mut challenger, trace))
+}
+
+/// Verifies a proof of a single AIR made with [`prove_single_chip`].
+pub fn verify_single_chip<SC, A>(
+    config: &SC,
+    air: &A,
+    proof: &SingleProof<SC>,
+) -> Result<(), SingleChipError>
+where
+    SC: StarkUtils,
+    A: Air<InteractionBuilder<SC::Val>>
+        + Air<p3_uni_stark::SymbolicAirBuilder<SC::Val>>
+        + for<'a> Air<p3_uni_stark::VerifierConstraintFolder<'a, SC::Challenge>>
+        + for<'a> Air<p3_uni_stark::DebugConstraintBuilder<'a, SC::Val>>,
+{
+    check_no_interactions(air)?;
+    let mut challenger = config.challenger();
+    uni_stark_verify(config, air, &mut challenger, proof).map_err(SingleChipError::Verification)
+}
+
 pub use baby_bear_keccak::BabyBearKeccak;
 pub use baby_bear_poseidon2::BabyBearPoseidon2;
-use p3_air::Air;
+pub use baby_bear_poseidon2_24::BabyBearPoseidon2W24;
+use p3_air::{Air, BaseAir};
 use p3_matrix::dense::RowMajorMatrix;
 use p3_uni_stark::Proof;
 
-pub(super) mod baby_bear_poseidon2 {
+/// Defines a module with a config whose hasher and challenger use a Poseidon2 permutation of the
+/// given width, with the sets of `Poseidon2Params` of that width.
+macro_rules! baby_bear_poseidon2_config {
+    ($module:ident, $config:ident, $width:literal, $default:expr) => {
+        pub(super) mod $module {
 
-    use crate::utils::prove::RC_16_30;
-    use p3_baby_bear::BabyBear;
-    use p3_challenger::DuplexChallenger;
-    use p3_commit::ExtensionMmcs;
-    use p3_dft::Radix2DitParallel;
-    use p3_field::{extension::BinomialExtensionField, Field};
-    use p3_fri::{FriConfig, TwoAdicFriPcs, TwoAdicFriPcsConfig};
-    use p3_merkle_tree::FieldMerkleTreeMmcs;
-    use p3_poseidon2::{DiffusionMatrixBabybear, Poseidon2};
-    use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
-    use serde::{Deserialize, Serialize};
+            use std::sync::{Arc, OnceLock};
 
-    use crate::stark::StarkGenericConfig;
+            use crate::utils::{Poseidon2BabyBear, Poseidon2Params};
+            use p3_baby_bear::BabyBear;
+            use p3_challenger::{CanObserve, DuplexChallenger};
+            use p3_commit::ExtensionMmcs;
+            use p3_dft::Radix2DitParallel;
+            use p3_field::{extension::BinomialExtensionField, AbstractField, Field};
+            use p3_fri::{FriConfig, TwoAdicFriPcs, TwoAdicFriPcsConfig};
+            use p3_merkle_tree::FieldMerkleTreeMmcs;
+            use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+            use serde::{Deserialize, Serialize};
 
-    use super::StarkUtils;
+            use crate::stark::StarkGenericConfig;
 
-    pub type Val = BabyBear;
+            use super::StarkUtils;
 
-    pub type Challenge = BinomialExtensionField<Val, 4>;
+            pub type Val = BabyBear;
 
-    pub type Perm = Poseidon2<Val, DiffusionMatrixBabybear, 16, 7>;
-    pub type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+            pub type Challenge = BinomialExtensionField<Val, 4>;
 
-    pub type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+            pub type Perm = Poseidon2BabyBear<$width>;
+            pub type MyHash = PaddingFreeSponge<Perm, $width, 8, 8>;
 
-    pub type ValMmcs = FieldMerkleTreeMmcs<
-        <Val as Field>::Packing,
-        <Val as Field>::Packing,
-        MyHash,
-        MyCompress,
-        8,
-    >;
-    pub type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+            pub type MyCompress = TruncatedPermutation<Perm, 2, 8, $width>;
 
-    pub type Dft = Radix2DitParallel;
+            pub type ValMmcs = FieldMerkleTreeMmcs<
+                <Val as Field>::Packing,
+                <Val as Field>::Packing,
+                MyHash,
+                MyCompress,
+                8,
+            >;
+            pub type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
 
-    pub type Challenger = DuplexChallenger<Val, Perm, 16>;
+            pub type Dft = Radix2DitParallel;
 
-    type Pcs =
-        TwoAdicFriPcs<TwoAdicFriPcsConfig<Val, Challenge, Challenger, Dft, ValMmcs, ChallengeMmcs>>;
+            pub type Challenger = DuplexChallenger<Val, Perm, $width>;
 
-    #[derive(Deserialize)]
-    #[serde(from = "std::marker::PhantomData<BabyBearPoseidon2>")]
-    pub struct BabyBearPoseidon2 {
-        perm: Perm,
-        pcs: Pcs,
-    }
+            type Pcs = TwoAdicFriPcs<
+                TwoAdicFriPcsConfig<Val, Challenge, Challenger, Dft, ValMmcs, ChallengeMmcs>,
+            >;
 
-    /// Implement serialization manually instead of using serde to avoid cloing the config.
-    impl Serialize for BabyBearPoseidon2 {
-        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-        where
-            S: serde::Serializer,
-        {
-            std::marker::PhantomData::<BabyBearPoseidon2>.serialize(serializer)
-        }
-    }
+            pub struct $config {
+                params: Poseidon2Params,
+                components: Arc<Components>,
+            }
 
-    impl From<std::marker::PhantomData<BabyBearPoseidon2>> for BabyBearPoseidon2 {
-        fn from(_: std::marker::PhantomData<BabyBearPoseidon2>) -> Self {
-            Self::new()
-        }
-    }
+            /// The permutation and the PCS of a set of parameters. They are constants, so they are
+            /// built once per set and shared by every config of the set.
+            struct Components {
+                perm: Perm,
+                pcs: Pcs,
+            }
 
-    impl Clone for BabyBearPoseidon2 {
-        fn clone(&self) -> Self {
-            Self::new()
-        }
-    }
+            #[allow(clippy::declare_interior_mutable_const)]
+            const UNBUILT: OnceLock<Arc<Components>> = OnceLock::new();
 
-    impl BabyBearPoseidon2 {
-        pub fn new() -> Self {
-            let perm = Perm::new(8, 22, RC_16_30.to_vec(), DiffusionMatrixBabybear);
+            /// The components of every set of parameters of this width, by id, built on first use.
+            static COMPONENTS: [OnceLock<Arc<Components>>; Poseidon2Params::ALL.len()] =
+                [UNBUILT; Poseidon2Params::ALL.len()];
 
-            let hash = MyHash::new(perm.clone());
+            /// The config of the default parameters returned by `shared`.
+            static SHARED: OnceLock<Arc<$config>> = OnceLock::new();
 
-            let compress = MyCompress::new(perm.clone());
+            impl Components {
+                fn build(params: Poseidon2Params) -> Self {
+                    let perm = params.permutation::<$width>();
 
-            let val_mmcs = ValMmcs::new(hash, compress);
+                    let hash = MyHash::new(perm.clone());
 
-            let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
+                    let compress = MyCompress::new(perm.clone());
 
-            let dft = Dft {};
+                    let val_mmcs = ValMmcs::new(hash, compress);
 
-            let fri_config = FriConfig {
-                log_blowup: 1,
-                num_queries: 100,
-                proof_of_work_bits: 16,
-                mmcs: challenge_mmcs,
-            };
-            let pcs = Pcs::new(fri_config, dft, val_mmcs);
+                    let challenge_mmcs = ChallengeMmcs::new(val_mmcs.clone());
 
-            Self { pcs, perm }
-        }
-    }
+                    let dft = Dft {};
 
-    impl StarkUtils for BabyBearPoseidon2 {
-        type UniConfig = Self;
+                    let fri_config = FriConfig {
+                        log_blowup: 1,
+                        num_queries: 100,
+                        proof_of_work_bits: 16,
+                        mmcs: challenge_mmcs,
+                    };
+                    let pcs = Pcs::new(fri_config, dft, val_mmcs);
 
-        fn challenger(&self) -> Self::Challenger {
-            Challenger::new(self.perm.clone())
-        }
+                    Self { perm, pcs }
+                }
+            }
 
-        fn uni_stark_config(&self) -> &Self::UniConfig {
-            self
-        }
-    }
+            /// Implement serialization manually instead of using serde to avoid cloing the config.
+            /// Only the id of the parameters of the hasher is serialized.
+            impl Serialize for $config {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: serde::Serializer,
+                {
+                    self.params.id.serialize(serializer)
+                }
+            }
 
-    impl StarkGenericConfig for BabyBearPoseidon2 {
-        type Val = Val;
-        type Challenge = Challenge;
-        type Pcs = Pcs;
-        type Challenger = Challenger;
+            impl<'de> Deserialize<'de> for $config {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: serde::Deserializer<'de>,
+                {
+                    let id = u8::deserialize(deserializer)?;
+                    let params = Poseidon2Params::from_id(id)
+                        .filter(|params| params.width == $width)
+                        .ok_or_else(|| {
+                            serde::de::Error::custom(format!(
+                                "unknown Poseidon2 parameters {} of width {}",
+                                id, $width
+                            ))
+                        })?;
+                    Ok(Self::with_params(params))
+                }
+            }
 
-        fn pcs(&self) -> &Self::Pcs {
-            &self.pcs
-        }
-    }
+            impl Clone for $config {
+                fn clone(&self) -> Self {
+                    Self {
+                        params: self.params,
+                        components: self.components.clone(),
+                    }
+                }
+            }
 
-    impl p3_uni_stark::StarkGenericConfig for BabyBearPoseidon2 {
-        type Val = Val;
-        type Challenge = Challenge;
-        type Pcs = Pcs;
-        type Challenger = Challenger;
+            impl $config {
+                pub fn new() -> Self {
+                    Self::with_params($default)
+                }
 
-        fn pcs(&self) -> &Self::Pcs {
-            &self.pcs
+                /// A config whose hasher and challenger use the given Poseidon2 parameters.
+                ///
+                /// The permutation and the PCS of the sets in `Poseidon2Params::ALL` are built by
+                /// the first config of the set and shared by the following ones, so creating a
+                /// config is cheap after the first.
+                ///
+                /// # Panics
+                ///
+                /// Panics if the width of the parameters is not the one of the config.
+                pub fn with_params(params: Poseidon2Params) -> Self {
+                    assert_eq!(
+                        params.width, $width,
+                        "the parameters {} are not of width {}",
+                        params.name, $width
+                    );
+                    let components = match Poseidon2Params::from_id(params.id) {
+                        Some(known) if known == params => COMPONENTS[params.id as usize]
+                            .get_or_init(|| Arc::new(Components::build(params)))
+                            .clone(),
+                        _ => Arc::new(Components::build(params)),
+                    };
+                    Self { params, components }
+                }
+
+                /// The config of the default parameters, shared by every caller.
+                pub fn shared() -> Arc<Self> {
+                    SHARED.get_or_init(|| Arc::new(Self::new())).clone()
+                }
+
+                /// The parameters of the Poseidon2 permutation of the hasher and the challenger.
+                pub fn params(&self) -> Poseidon2Params {
+                    self.params
+                }
+            }
+
+            impl StarkUtils for $config {
+                type UniConfig = Self;
+
+                /// A challenger whose transcript starts with the id of the parameters.
+                fn challenger(&self) -> Self::Challenger {
+                    let mut challenger = Challenger::new(self.components.perm.clone());
+                    challenger.observe(Val::from_canonical_u8(self.params.id));
+                    challenger
+                }
+
+                fn uni_stark_config(&self) -> &Self::UniConfig {
+                    self
+                }
+            }
+
+            impl StarkGenericConfig for $config {
+                type Val = Val;
+                type Challenge = Challenge;
+                type Pcs = Pcs;
+                type Challenger = Challenger;
+
+                fn pcs(&self) -> &Self::Pcs {
+                    &self.components.pcs
+                }
+
+                fn hasher_params_id(&self) -> u8 {
+                    self.params.id
+                }
+            }
+
+            impl p3_uni_stark::StarkGenericConfig for $config {
+                type Val = Val;
+                type Challenge = Challenge;
+                type Pcs = Pcs;
+                type Challenger = Challenger;
+
+                fn pcs(&self) -> &Self::Pcs {
+                    &self.components.pcs
+                }
+            }
         }
-    }
+    };
 }
 
+baby_bear_poseidon2_config!(
+    baby_bear_poseidon2,
+    BabyBearPoseidon2,
+    16,
+    Poseidon2Params::default()
+);
+baby_bear_poseidon2_config!(
+    baby_bear_poseidon2_24,
+    BabyBearPoseidon2W24,
+    24,
+    Poseidon2Params::BABYBEAR_24_29
+);
+
 pub(super) mod baby_bear_keccak {
 
     use p3_baby_bear::BabyBear;
@@ -513,6 +806,9 @@ pub(super) mod baby_bear_blake3 {
     }
 
     impl BabyBearBlake3 {
+        /// The FRI parameters as `(log_blowup, num_queries, proof_of_work_bits)`.
+        pub const FRI_PARAMS: (usize, usize, usize) = (LOG_BLOWUP, NUM_QUERIES, PROOF_OF_WORK_BITS);
+
         pub fn new() -> Self {
             let byte_hash = ByteHash {};
             let field_hash: SerializingHasher32<Blake3U32> = FieldHash::new(byte_hash);
@@ -719,3 +1015,88 @@ pub(super) mod baby_bear_blake3 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use p3_air::{Air, AirBuilder, BaseAir};
+    use p3_baby_bear::BabyBear;
+    use p3_field::AbstractField;
+    use p3_matrix::dense::RowMajorMatrix;
+    use p3_matrix::MatrixRowSlices;
+
+    use super::{prove_single_chip, verify_single_chip, BabyBearPoseidon2, SingleChipError};
+    use crate::alu::{AddChip, NUM_ADD_COLS};
+
+    /// An AIR whose rows hold a value and its cube.
+    struct CubeAir;
+
+    impl<F> BaseAir<F> for CubeAir {
+        fn width(&self) -> usize {
+            2
+        }
+    }
+
+    impl<AB: AirBuilder> Air<AB> for CubeAir {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let local = main.row_slice(0);
+            let (x, cube) = (local[0], local[1]);
+            builder.assert_eq(x * x * x, cube);
+        }
+    }
+
+    #[test]
+    fn test_prove_single_chip() {
+        let config = BabyBearPoseidon2::new();
+        let values = (0..16u32)
+            .flat_map(|i| [i, i * i * i])
+            .map(BabyBear::from_canonical_u32)
+            .collect::<Vec<_>>();
+        let trace = RowMajorMatrix::new(values, 2);
+
+        let proof = prove_single_chip(&config, &CubeAir, trace).unwrap();
+        verify_single_chip(&config, &CubeAir, &proof).unwrap();
+    }
+
+    #[test]
+    fn test_prove_single_chip_interactions() {
+        let config = BabyBearPoseidon2::new();
+        let trace = RowMajorMatrix::new(vec![BabyBear::zero(); 4 * NUM_ADD_COLS], NUM_ADD_COLS);
+        let err = prove_single_chip(&config, &AddChip, trace).unwrap_err();
+        assert!(matches!(err, SingleChipError::Interactions { sends, .. } if sends > 0));
+        assert!(err.to_string().contains("AddChip has"));
+        assert!(err
+            .to_string()
+            .contains("prove it as a chip of a `RiscvStark`"));
+    }
+
+    #[test]
+    fn test_shared_config_across_threads() {
+        use std::sync::Arc;
+
+        use crate::utils::Poseidon2Params;
+
+        // Configs are created from many threads at once, each proving with its own and with the
+        // shared one.
+        std::thread::scope(|scope| {
+            for i in 0..8u32 {
+                scope.spawn(move || {
+                    let values = (0..16u32)
+                        .flat_map(|j| [i + j, (i + j).pow(3)])
+                        .map(BabyBear::from_canonical_u32)
+                        .collect::<Vec<_>>();
+                    let trace = RowMajorMatrix::new(values, 2);
+                    let config = BabyBearPoseidon2::new();
+                    let proof = prove_single_chip(&config, &CubeAir, trace).unwrap();
+                    verify_single_chip(&*BabyBearPoseidon2::shared(), &CubeAir, &proof).unwrap();
+                });
+            }
+        });
+        assert!(Arc::ptr_eq(
+            &BabyBearPoseidon2::shared(),
+            &BabyBearPoseidon2::shared()
+        ));
+        let other = BabyBearPoseidon2::with_params(Poseidon2Params::BABYBEAR_16_21);
+        assert_eq!(other.params(), Poseidon2Params::BABYBEAR_16_21);
+    }
+}
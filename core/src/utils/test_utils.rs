@@ -0,0 +1,539 @@
+//! Utilities for crafting synthetic execution records in tests without running a guest program,
+//! and for checking operation gadgets on a single row.
+//!
+//! # Example
+//!
+//! Testing a precompile only requires producing its event with consistent memory records, which
+//! the builder takes care of. The body of `RecordBuilder::sha_extend` is an example: it reads and
+//! writes the message schedule, then emits the CPU row of the ecall which invokes `SHA_EXTEND`.
+//!
+//! ```
+//! use sp1_core::runtime::SyscallCode;
+//! use sp1_core::utils::test_utils::RecordBuilder;
+//!
+//! // Initialize the first word of the message schedule.
+//! let mut builder = RecordBuilder::new();
+//! builder.memory(0x1000, &[(1, 41)]);
+//! let clk = builder.clk();
+//! let read = builder.mr(0x1000, clk);
+//! assert_eq!((read.value, read.prev_timestamp), (41, 1));
+//!
+//! // Extend the schedule, which emits the event and the ecall of `SHA_EXTEND`.
+//! builder.sha_extend(0x1000, [7; 64]);
+//! let clk = builder.clk();
+//! let write = builder.mw(0x1000, 0, clk);
+//! assert_eq!(write.prev_value, 7);
+//!
+//! // Emit the global memory events of every address accessed so far.
+//! builder.memory_init();
+//!
+//! let record = builder.build();
+//! assert_eq!(record.sha_extend_events.len(), 1);
+//! assert_eq!(record.cpu_events[0].b, SyscallCode::SHA_EXTEND as u32);
+//! assert_eq!(record.first_memory_record.len(), record.last_memory_record.len());
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use hashbrown::HashMap;
+use lazy_static::lazy_static;
+use p3_air::{AirBuilder, TwoRowMatrixView};
+use p3_baby_bear::BabyBear;
+use p3_field::{AbstractField, PrimeField32};
+
+use crate::air::{AirInteraction, MessageBuilder, PublicValuesBuilder, SP1AirBuilder};
+use crate::bytes::{ByteChip, ByteLookupEvent, ByteOpcode};
+use crate::cpu::{CpuEvent, MemoryReadRecord, MemoryRecord, MemoryRecordEnum, MemoryWriteRecord};
+use crate::field::event::FieldEvent;
+use crate::lookup::InteractionKind;
+use crate::range::{RangeCheckChip, RangeCheckEvent};
+use crate::runtime::{
+    default_syscall_map, AccessPosition, ExecutionRecord, Instruction, Opcode, Program, Register,
+    SyscallCode,
+};
+use crate::syscall::precompiles::sha256::ShaExtendEvent;
+
+/// A builder for execution records whose events are internally consistent, i.e. every memory
+/// record points to the previous access of the same address.
+///
+/// It emits the CPU rows of ecalls, SHA extend events, byte lookups, field events and the global
+/// memory events. Other events are pushed through `record_mut`.
+pub struct RecordBuilder {
+    /// The shard that all events are emitted in.
+    shard: u32,
+
+    /// The next free clock cycle.
+    clk: u32,
+
+    /// The memory state as a map of address to (value, shard, timestamp).
+    memory: HashMap<u32, (u32, u32, u32)>,
+
+    /// The instructions of the program that the CPU rows execute.
+    instructions: Vec<Instruction>,
+
+    /// The record being built.
+    record: ExecutionRecord,
+}
+
+impl RecordBuilder {
+    /// Create a new builder for the first shard.
+    pub fn new() -> Self {
+        Self {
+            shard: 1,
+            clk: 1,
+            memory: HashMap::new(),
+            instructions: Vec::new(),
+            record: ExecutionRecord::default(),
+        }
+    }
+
+    /// The next clock cycle that is guaranteed to be after every access made so far.
+    pub fn clk(&self) -> u32 {
+        self.clk
+    }
+
+    /// The record being built.
+    pub fn record_mut(&mut self) -> &mut ExecutionRecord {
+        &mut self.record
+    }
+
+    /// Read from memory at the given clock cycle.
+    pub fn mr(&mut self, addr: u32, clk: u32) -> MemoryReadRecord {
+        let shard = self.shard;
+        let entry = self.memory.entry(addr).or_insert((0, 0, 0));
+        let (value, prev_shard, prev_timestamp) = *entry;
+        (entry.1, entry.2) = (shard, clk);
+        self.clk = self.clk.max(clk + 1);
+        MemoryReadRecord::new(value, shard, clk, prev_shard, prev_timestamp)
+    }
+
+    /// Write to memory at the given clock cycle.
+    pub fn mw(&mut self, addr: u32, value: u32, clk: u32) -> MemoryWriteRecord {
+        let shard = self.shard;
+        let entry = self.memory.entry(addr).or_insert((0, 0, 0));
+        let (prev_value, prev_shard, prev_timestamp) = *entry;
+        *entry = (value, shard, clk);
+        self.clk = self.clk.max(clk + 1);
+        MemoryWriteRecord::new(value, shard, clk, prev_value, prev_shard, prev_timestamp)
+    }
+
+    /// Write the given sequence of `(clk, value)` pairs to a memory address.
+    pub fn memory(&mut self, addr: u32, writes: &[(u32, u32)]) -> &mut Self {
+        for (clk, value) in writes.iter() {
+            self.mw(addr, *value, *clk);
+        }
+        self
+    }
+
+    /// Emit a byte lookup, e.g. one which the trace of a chip would add.
+    pub fn byte_lookup(&mut self, event: ByteLookupEvent) -> &mut Self {
+        self.record.add_byte_lookup_event(event);
+        self
+    }
+
+    /// Emit a field event checking whether `b < c` equals `ltu`.
+    pub fn field_event(&mut self, ltu: bool, b: u32, c: u32) -> &mut Self {
+        self.record.add_field_event(FieldEvent::new(ltu, b, c));
+        self
+    }
+
+    /// Emit the memory initialize and finalize events of every address accessed so far, as the
+    /// runtime does once the program halts. Every address starts at zero, since the program built
+    /// by the builder has no initial memory.
+    pub fn memory_init(&mut self) -> &mut Self {
+        let mut addrs = self.memory.keys().copied().collect::<Vec<_>>();
+        addrs.sort_unstable();
+        for addr in addrs {
+            let (value, shard, timestamp) = self.memory[&addr];
+            let init = MemoryRecord {
+                value: 0,
+                shard: 0,
+                timestamp: 0,
+            };
+            let last = MemoryRecord {
+                value,
+                shard,
+                timestamp,
+            };
+            self.record.first_memory_record.push((addr, init, 1));
+            self.record.last_memory_record.push((addr, last, 1));
+        }
+        self
+    }
+
+    /// Emit the CPU row of an ecall happening at `clk`, with `args` placed in a0, a1, ....
+    ///
+    /// The argument registers are written right before the precompile starts executing. Note that
+    /// these writes are not backed by CPU rows.
+    pub fn syscall(&mut self, clk: u32, code: SyscallCode, args: &[u32]) -> &mut Self {
+        let extra_cycles = default_syscall_map()
+            .get(&code)
+            .map(|syscall| syscall.num_extra_cycles())
+            .unwrap_or(0);
+        let start_clk = clk
+            .checked_sub(extra_cycles)
+            .expect("clk is before the syscall");
+
+        // Set up the syscall id and the arguments.
+        let t0 = Register::X5 as u32;
+        let a0 = Register::X10 as u32;
+        self.mw(t0, code as u32, start_clk - 1);
+        for (i, arg) in args.iter().enumerate() {
+            self.mw(a0 + i as u32, *arg, start_clk - 1);
+        }
+
+        // The ecall reads the syscall id and writes the return value back to a0.
+        let a = args.first().copied().unwrap_or(0);
+        let b_record = self.mr(t0, clk + AccessPosition::B as u32);
+        let a_record = self.mw(a0, a, clk + AccessPosition::A as u32);
+        let instruction = Instruction::new(Opcode::ECALL, a0, t0, 0, false, true);
+        let pc = self.instructions.len() as u32 * 4;
+        self.instructions.push(instruction);
+        self.record.cpu_events.push(CpuEvent {
+            shard: self.shard,
+            clk,
+            global_clk: self.record.cpu_events.len() as u32,
+            pc,
+            next_pc: pc + 4,
+            instruction,
+            a,
+            a_record: Some(MemoryRecordEnum::Write(a_record)),
+            b: code as u32,
+            b_record: Some(MemoryRecordEnum::Read(b_record)),
+            c: 0,
+            c_record: None,
+            memory: None,
+            memory_record: None,
+        });
+        self.clk = self.clk.max(clk + 4);
+        self
+    }
+
+    /// Emit a SHA extend call on the message schedule `w_init` stored at `w_ptr`, followed by the
+    /// CPU row of the ecall which invokes it, so that the syscall sent by the CPU is the one the
+    /// chip receives.
+    pub fn sha_extend(&mut self, w_ptr: u32, w_init: [u32; 64]) -> &mut Self {
+        // Initialize the message schedule.
+        let init_clk = self.clk;
+        for (i, w) in w_init.iter().enumerate() {
+            self.mw(w_ptr + i as u32 * 4, *w, init_clk);
+        }
+
+        // Leave room for the argument registers to be written.
+        let clk_init = init_clk + 2;
+        let mut clk = clk_init;
+        let mut w_i_minus_15_reads = Vec::new();
+        let mut w_i_minus_2_reads = Vec::new();
+        let mut w_i_minus_16_reads = Vec::new();
+        let mut w_i_minus_7_reads = Vec::new();
+        let mut w_i_writes = Vec::new();
+        for i in 16..64 {
+            let w_i_minus_15 = self.mr(w_ptr + (i - 15) * 4, clk);
+            clk += 4;
+            let w_i_minus_2 = self.mr(w_ptr + (i - 2) * 4, clk);
+            clk += 4;
+            let w_i_minus_16 = self.mr(w_ptr + (i - 16) * 4, clk);
+            clk += 4;
+            let w_i_minus_7 = self.mr(w_ptr + (i - 7) * 4, clk);
+            clk += 4;
+
+            let (w15, w2) = (w_i_minus_15.value, w_i_minus_2.value);
+            let s0 = w15.rotate_right(7) ^ w15.rotate_right(18) ^ (w15 >> 3);
+            let s1 = w2.rotate_right(17) ^ w2.rotate_right(19) ^ (w2 >> 10);
+            let w_i = s1
+                .wrapping_add(w_i_minus_16.value)
+                .wrapping_add(s0)
+                .wrapping_add(w_i_minus_7.value);
+            w_i_writes.push(self.mw(w_ptr + i * 4, w_i, clk));
+            clk += 4;
+
+            w_i_minus_15_reads.push(w_i_minus_15);
+            w_i_minus_2_reads.push(w_i_minus_2);
+            w_i_minus_16_reads.push(w_i_minus_16);
+            w_i_minus_7_reads.push(w_i_minus_7);
+        }
+
+        self.record.sha_extend_events.push(ShaExtendEvent {
+            shard: self.shard,
+            clk: clk_init,
+            w_ptr,
+            w_i_minus_15_reads: w_i_minus_15_reads.try_into().unwrap(),
+            w_i_minus_2_reads: w_i_minus_2_reads.try_into().unwrap(),
+            w_i_minus_16_reads: w_i_minus_16_reads.try_into().unwrap(),
+            w_i_minus_7_reads: w_i_minus_7_reads.try_into().unwrap(),
+            w_i_writes: w_i_writes.try_into().unwrap(),
+        });
+
+        self.syscall(clk, SyscallCode::SHA_EXTEND, &[w_ptr])
+    }
+
+    /// Finish building the record. The program consists of the instructions of the CPU rows.
+    pub fn build(self) -> ExecutionRecord {
+        let mut record = self.record;
+        record.index = self.shard;
+        record.program = Arc::new(Program::new(self.instructions, 0, 0));
+        record
+    }
+}
+
+impl Default for RecordBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An operation gadget checked on a single row, which holds the inputs of the gadget, followed by
+/// its columns and an `is_real` flag.
+pub trait GadgetUnderTest {
+    /// The inputs of the gadget.
+    type Input: Copy + Debug;
+
+    /// The number of columns holding the inputs.
+    const NUM_INPUT_COLS: usize;
+
+    /// The number of columns of the gadget.
+    const NUM_COLS: usize;
+
+    /// Writes the inputs and populates the columns of the gadget, returning its output.
+    fn populate(
+        input: Self::Input,
+        inputs: &mut [BabyBear],
+        cols: &mut [BabyBear],
+        record: &mut ExecutionRecord,
+    ) -> u32;
+
+    /// Evaluates the constraints of the gadget.
+    fn eval<AB: SP1AirBuilder>(
+        builder: &mut AB,
+        input: Self::Input,
+        inputs: &[AB::Var],
+        cols: &[AB::Var],
+        is_real: AB::Var,
+    );
+
+    /// The columns of the gadget which the constraints leave free for the given input, e.g. the
+    /// inverse of a zero byte.
+    fn free_cols(_input: Self::Input) -> Vec<usize> {
+        Vec::new()
+    }
+}
+
+lazy_static! {
+    /// Every valid byte lookup, i.e. the rows of the byte table.
+    static ref BYTE_LOOKUPS: BTreeMap<ByteLookupEvent, (usize, usize)> =
+        ByteChip::<BabyBear>::trace_and_map().1;
+}
+
+/// A builder evaluating the constraints of a single row, which records the failures instead of
+/// panicking on the first one.
+///
+/// Byte lookups and range checks are checked against their tables, and the other interactions are
+/// ignored.
+pub struct GadgetBuilder<'a> {
+    row: &'a [BabyBear],
+    failures: Vec<String>,
+}
+
+impl<'a> GadgetBuilder<'a> {
+    pub fn new(row: &'a [BabyBear]) -> Self {
+        Self {
+            row,
+            failures: Vec::new(),
+        }
+    }
+
+    /// The constraints and lookups which failed so far.
+    pub fn failures(&self) -> &[String] {
+        &self.failures
+    }
+
+    fn check_byte_lookup(&mut self, interaction: &AirInteraction<BabyBear>) {
+        let values = interaction
+            .values
+            .iter()
+            .map(|value| value.as_canonical_u32())
+            .collect::<Vec<_>>();
+        let opcode = ByteOpcode::all()
+            .into_iter()
+            .find(|opcode| *opcode as u32 == values[0]);
+        let valid = opcode.is_some_and(|opcode| {
+            let event = ByteLookupEvent::new(opcode, values[1], values[2], values[3], values[4]);
+            BYTE_LOOKUPS.contains_key(&event)
+        });
+        if !valid {
+            self.failures
+                .push(format!("invalid byte lookup {:?}", values));
+        }
+    }
+
+    fn check_range_check(&mut self, interaction: &AirInteraction<BabyBear>) {
+        let event = RangeCheckEvent::new(
+            interaction.values[0].as_canonical_u32(),
+            interaction.values[1].as_canonical_u32(),
+        );
+        if RangeCheckChip::table_position(&event).is_none() {
+            self.failures
+                .push(format!("invalid range check {:?}", event));
+        }
+    }
+}
+
+impl<'a> AirBuilder for GadgetBuilder<'a> {
+    type F = BabyBear;
+    type Expr = BabyBear;
+    type Var = BabyBear;
+    type M = TwoRowMatrixView<'a, BabyBear>;
+
+    fn main(&self) -> Self::M {
+        TwoRowMatrixView::new(self.row, self.row)
+    }
+
+    fn is_first_row(&self) -> Self::Expr {
+        BabyBear::one()
+    }
+
+    fn is_last_row(&self) -> Self::Expr {
+        BabyBear::one()
+    }
+
+    fn is_transition_window(&self, _size: usize) -> Self::Expr {
+        BabyBear::zero()
+    }
+
+    fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I) {
+        let x = x.into();
+        if x != BabyBear::zero() {
+            self.failures.push(format!("constraint evaluates to {}", x));
+        }
+    }
+}
+
+impl<'a> MessageBuilder<AirInteraction<BabyBear>> for GadgetBuilder<'a> {
+    fn send(&mut self, message: AirInteraction<BabyBear>) {
+        if message.multiplicity == BabyBear::zero() {
+            return;
+        }
+        match message.kind {
+            InteractionKind::Byte => self.check_byte_lookup(&message),
+            InteractionKind::Range => self.check_range_check(&message),
+            _ => {}
+        }
+    }
+
+    fn receive(&mut self, _message: AirInteraction<BabyBear>) {}
+}
+
+impl<'a> PublicValuesBuilder for GadgetBuilder<'a> {
+    type PublicVar = Self::F;
+
+    const STANDALONE: bool = true;
+
+    fn public_values(&self) -> &[Self::PublicVar] {
+        &[]
+    }
+}
+
+/// Evaluates a gadget on a row, returning the failures of its constraints.
+fn eval_gadget<G: GadgetUnderTest>(input: G::Input, row: &[BabyBear]) -> Vec<String> {
+    let (inputs, rest) = row.split_at(G::NUM_INPUT_COLS);
+    let (cols, is_real) = rest.split_at(G::NUM_COLS);
+    let mut builder = GadgetBuilder::new(row);
+    G::eval(&mut builder, input, inputs, cols, is_real[0]);
+    builder.failures
+}
+
+/// Checks the contract of a gadget on the given input: the populated row satisfies the
+/// constraints, the output is the expected one, and changing any column of the gadget but the free
+/// ones breaks a constraint.
+pub fn check_gadget<G: GadgetUnderTest>(input: G::Input, expected: u32) -> Result<(), String> {
+    let mut row = vec![BabyBear::zero(); G::NUM_INPUT_COLS + G::NUM_COLS + 1];
+    let (inputs, rest) = row.split_at_mut(G::NUM_INPUT_COLS);
+    let (cols, is_real) = rest.split_at_mut(G::NUM_COLS);
+    is_real[0] = BabyBear::one();
+    let output = G::populate(input, inputs, cols, &mut ExecutionRecord::default());
+    if output != expected {
+        return Err(format!("output {} instead of {}", output, expected));
+    }
+
+    let failures = eval_gadget::<G>(input, &row);
+    if !failures.is_empty() {
+        return Err(format!("the populated row fails: {:?}", failures));
+    }
+
+    let free_cols = G::free_cols(input);
+    for col in (0..G::NUM_COLS).filter(|col| !free_cols.contains(col)) {
+        let mut mutated = row.clone();
+        mutated[G::NUM_INPUT_COLS + col] += BabyBear::one();
+        if eval_gadget::<G>(input, &mutated).is_empty() {
+            return Err(format!("column {} of the gadget is not constrained", col));
+        }
+    }
+    Ok(())
+}
+
+/// Checks the contract of an operation gadget implementing `GadgetUnderTest` on random inputs
+/// drawn from a proptest strategy, against a reference function computing its output.
+///
+/// ```ignore
+/// gadget_prop_test!(AddOperation, (any::<u32>(), any::<u32>()), |(a, b): (u32, u32)| {
+///     a.wrapping_add(b)
+/// });
+/// ```
+#[cfg(test)]
+macro_rules! gadget_prop_test {
+    ($gadget:ident, $strategy:expr, $reference:expr) => {{
+        let reference = $reference;
+        let mut runner = ::proptest::test_runner::TestRunner::default();
+        let result = runner.run(&$strategy, |input| {
+            $crate::utils::test_utils::check_gadget::<$gadget<::p3_baby_bear::BabyBear>>(
+                input,
+                reference(input),
+            )
+            .map_err(::proptest::test_runner::TestCaseError::fail)
+        });
+        if let Err(err) = result {
+            panic!("{}: {}", stringify!($gadget), err);
+        }
+    }};
+}
+
+#[cfg(test)]
+pub(crate) use gadget_prop_test;
+
+#[cfg(test)]
+mod tests {
+    use crate::bytes::{ByteLookupEvent, ByteOpcode};
+    use crate::field::event::FieldEvent;
+
+    use super::RecordBuilder;
+
+    #[test]
+    fn test_record_builder_global_events() {
+        let mut builder = RecordBuilder::new();
+        builder.memory(0x2000, &[(1, 5), (3, 6)]);
+        builder.memory(0x1000, &[(2, 9)]);
+        let event = ByteLookupEvent::new(ByteOpcode::LTU, 1, 0, 3, 4);
+        builder.byte_lookup(event).byte_lookup(event);
+        builder.field_event(true, 3, 4);
+        builder.memory_init();
+        let record = builder.build();
+
+        assert_eq!(record.byte_lookups[&event], 2);
+        assert_eq!(record.field_events, vec![FieldEvent::new(true, 3, 4)]);
+
+        // The addresses are in order, starting at zero and ending at their last write.
+        let first = &record.first_memory_record;
+        let last = &record.last_memory_record;
+        assert_eq!(
+            first.iter().map(|r| r.0).collect::<Vec<_>>(),
+            [0x1000, 0x2000]
+        );
+        assert!(first
+            .iter()
+            .all(|(_, r, m)| (r.value, r.timestamp, *m) == (0, 0, 1)));
+        assert_eq!((last[0].1.value, last[0].1.timestamp), (9, 2));
+        assert_eq!((last[1].1.value, last[1].1.timestamp), (6, 3));
+    }
+}
@@ -0,0 +1,579 @@
+//! Versions of the encoding of proofs in proof files.
+//!
+//! The `proof` field of a file written by `SP1ProofWithIO::save` holds the hex of an encoded
+//! proof. Since version 2, the encoding starts with a header:
+//!
+//! ```text
+//! magic (4 bytes) | version (1 byte) | bincode of the proof
+//! ```
+//!
+//! Since version 3, the header also holds the identifier of the parameters of the hasher the proof
+//! was produced with, after the version:
+//!
+//! ```text
+//! magic (4 bytes) | version (1 byte) | hasher parameters (1 byte) | bincode of the proof
+//! ```
+//!
+//! Since version 4, the proof holds the values the prover claims the program committed, with the
+//! header of version 3.
+//!
+//! Since version 5, the proof holds the digests of the preprocessed traces it was produced with,
+//! with the header of version 3.
+//!
+//! Version 1 has no header, and holds the digest of the proof in the serde encoding of the field
+//! instead of its canonical one. Files of version 1 are read only with the `legacy-proofs` feature.
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::io::StdinBinding;
+use crate::stark::{Proof, ShardProof, StarkGenericConfig, Val, PROOF_DIGEST_SIZE};
+#[cfg(feature = "legacy-proofs")]
+use crate::utils::{BabyBearBlake3, StarkUtils};
+#[cfg(feature = "legacy-proofs")]
+use crate::{SP1ProofWithIO, SP1Stdin, SP1Stdout};
+
+/// The bytes every encoded proof of version 2 or later starts with.
+pub const PROOF_MAGIC: [u8; 4] = *b"SP1P";
+
+/// A version of the encoding of proofs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ProofVersion {
+    /// No header, and the digest in the serde encoding of the field.
+    V1 = 1,
+    /// A header, and the digest in the canonical encoding of `utils::codec`.
+    V2 = 2,
+    /// The header also holds the identifier of the parameters of the hasher.
+    V3 = 3,
+    /// The proof also holds the values the prover claims the program committed.
+    V4 = 4,
+    /// The proof also holds the digests of the preprocessed traces.
+    V5 = 5,
+}
+
+impl ProofVersion {
+    /// The version proofs are encoded in.
+    pub const CURRENT: ProofVersion = ProofVersion::V5;
+
+    /// The version of an encoded proof, from its header.
+    pub fn of(bytes: &[u8]) -> Result<Self, ProofVersionError> {
+        let Some(rest) = bytes.strip_prefix(PROOF_MAGIC.as_slice()) else {
+            return Ok(ProofVersion::V1);
+        };
+        match rest.first() {
+            Some(2) => Ok(ProofVersion::V2),
+            Some(3) => Ok(ProofVersion::V3),
+            Some(4) => Ok(ProofVersion::V4),
+            Some(5) => Ok(ProofVersion::V5),
+            Some(&version) => Err(ProofVersionError::Unsupported(version)),
+            None => Err(ProofVersionError::Malformed("missing version".to_string())),
+        }
+    }
+
+    /// The header encoded proofs of the version start with, for a proof produced with the given
+    /// parameters of the hasher.
+    pub fn header(self, hasher_params: u8) -> Vec<u8> {
+        match self {
+            ProofVersion::V1 => Vec::new(),
+            ProofVersion::V2 => [PROOF_MAGIC.as_slice(), &[self as u8]].concat(),
+            ProofVersion::V3 | ProofVersion::V4 | ProofVersion::V5 => {
+                [PROOF_MAGIC.as_slice(), &[self as u8, hasher_params]].concat()
+            }
+        }
+    }
+
+    /// The length of the header of the version.
+    pub fn header_len(self) -> usize {
+        self.header(0).len()
+    }
+}
+
+impl Display for ProofVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", *self as u8)
+    }
+}
+
+/// Why an encoded proof could not be read or upgraded.
+#[derive(Debug)]
+pub enum ProofVersionError {
+    /// The header names a version this build does not know.
+    Unsupported(u8),
+    /// The version is only read with the `legacy-proofs` feature.
+    LegacyDisabled(ProofVersion),
+    /// The proof file or the proof does not decode.
+    Malformed(String),
+    /// The digest of the proof is not the one the current transcript samples from its shards, so
+    /// the proof was produced with another transcript and no encoding of it would verify.
+    TranscriptMismatch(ProofVersion),
+    /// The proof or the verifying key was produced with other parameters of the hasher than the
+    /// config it is verified with.
+    HasherParamsMismatch { expected: u8, found: u8 },
+}
+
+impl Display for ProofVersionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProofVersionError::Unsupported(version) => {
+                write!(f, "unsupported proof version {}", version)
+            }
+            ProofVersionError::LegacyDisabled(version) => write!(
+                f,
+                "proofs of version {} are only read with the legacy-proofs feature",
+                version
+            ),
+            ProofVersionError::Malformed(reason) => write!(f, "malformed proof: {}", reason),
+            ProofVersionError::TranscriptMismatch(version) => write!(
+                f,
+                "the proof of version {} was produced with a different transcript and cannot be \
+                 upgraded",
+                version
+            ),
+            ProofVersionError::HasherParamsMismatch { expected, found } => write!(
+                f,
+                "produced with hasher parameters {}, but verified with parameters {}",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ProofVersionError {}
+
+/// Encodes the proof in the current version.
+pub fn encode_proof<SC: StarkGenericConfig + Serialize>(proof: &Proof<SC>) -> Vec<u8> {
+    let mut bytes = ProofVersion::CURRENT.header(proof.hasher_params);
+    bincode::serialize_into(&mut bytes, proof).unwrap();
+    bytes
+}
+
+/// Decodes a proof of any version this build reads, and the version it was encoded in.
+pub fn decode_proof<SC: StarkGenericConfig + DeserializeOwned>(
+    bytes: &[u8],
+) -> Result<(Proof<SC>, ProofVersion), ProofVersionError> {
+    let version = ProofVersion::of(bytes)?;
+    let payload = bytes
+        .get(version.header_len()..)
+        .ok_or_else(|| ProofVersionError::Malformed("truncated header".to_string()))?;
+    let proof = match version {
+        ProofVersion::V2 => bincode::deserialize::<ProofV2<SC>>(payload).map(Proof::from),
+        ProofVersion::V3 => bincode::deserialize::<ProofV3<SC>>(payload).map(Proof::from),
+        ProofVersion::V4 => bincode::deserialize::<ProofV4<SC>>(payload).map(Proof::from),
+        ProofVersion::V5 => bincode::deserialize(payload),
+        #[cfg(feature = "legacy-proofs")]
+        ProofVersion::V1 => bincode::deserialize::<legacy::ProofV1<SC>>(payload).map(Proof::from),
+        #[cfg(not(feature = "legacy-proofs"))]
+        ProofVersion::V1 => return Err(ProofVersionError::LegacyDisabled(version)),
+    };
+    let proof = proof.map_err(|e| ProofVersionError::Malformed(e.to_string()))?;
+    if version >= ProofVersion::V3 && bytes[PROOF_MAGIC.len() + 1] != proof.hasher_params {
+        return Err(ProofVersionError::Malformed(
+            "the header and the proof name different hasher parameters".to_string(),
+        ));
+    }
+    Ok((proof, version))
+}
+
+/// The layout of a proof of version 2, before proofs recorded the parameters of the hasher, which
+/// were then always those of id 0.
+#[derive(Serialize, Deserialize)]
+struct ProofV2<SC: StarkGenericConfig> {
+    shard_proofs: Vec<ShardProof<SC>>,
+    #[serde(
+        serialize_with = "crate::utils::codec::serialize_field_array",
+        deserialize_with = "crate::utils::codec::deserialize_field_array"
+    )]
+    digest: [Val<SC>; PROOF_DIGEST_SIZE],
+    context: Vec<u8>,
+    stdin: Option<StdinBinding>,
+}
+
+impl<SC: StarkGenericConfig> From<ProofV2<SC>> for Proof<SC> {
+    fn from(proof: ProofV2<SC>) -> Self {
+        Proof {
+            shard_proofs: proof.shard_proofs,
+            digest: proof.digest,
+            context: proof.context,
+            stdin: proof.stdin,
+            hasher_params: 0,
+            claimed_values: Vec::new(),
+            preprocessed_digests: BTreeMap::new(),
+        }
+    }
+}
+
+/// The layout of a proof of version 3, before proofs held claimed committed values.
+#[derive(Serialize, Deserialize)]
+struct ProofV3<SC: StarkGenericConfig> {
+    shard_proofs: Vec<ShardProof<SC>>,
+    #[serde(
+        serialize_with = "crate::utils::codec::serialize_field_array",
+        deserialize_with = "crate::utils::codec::deserialize_field_array"
+    )]
+    digest: [Val<SC>; PROOF_DIGEST_SIZE],
+    context: Vec<u8>,
+    stdin: Option<StdinBinding>,
+    hasher_params: u8,
+}
+
+impl<SC: StarkGenericConfig> From<ProofV3<SC>> for Proof<SC> {
+    fn from(proof: ProofV3<SC>) -> Self {
+        Proof {
+            shard_proofs: proof.shard_proofs,
+            digest: proof.digest,
+            context: proof.context,
+            stdin: proof.stdin,
+            hasher_params: proof.hasher_params,
+            claimed_values: Vec::new(),
+            preprocessed_digests: BTreeMap::new(),
+        }
+    }
+}
+
+/// The layout of a proof of version 4, before proofs held the digests of the preprocessed traces.
+#[derive(Serialize, Deserialize)]
+struct ProofV4<SC: StarkGenericConfig> {
+    shard_proofs: Vec<ShardProof<SC>>,
+    #[serde(
+        serialize_with = "crate::utils::codec::serialize_field_array",
+        deserialize_with = "crate::utils::codec::deserialize_field_array"
+    )]
+    digest: [Val<SC>; PROOF_DIGEST_SIZE],
+    context: Vec<u8>,
+    stdin: Option<StdinBinding>,
+    hasher_params: u8,
+    claimed_values: Vec<u8>,
+}
+
+impl<SC: StarkGenericConfig> From<ProofV4<SC>> for Proof<SC> {
+    fn from(proof: ProofV4<SC>) -> Self {
+        Proof {
+            shard_proofs: proof.shard_proofs,
+            digest: proof.digest,
+            context: proof.context,
+            stdin: proof.stdin,
+            hasher_params: proof.hasher_params,
+            claimed_values: proof.claimed_values,
+            preprocessed_digests: BTreeMap::new(),
+        }
+    }
+}
+
+/// A proof file, with the proof still encoded.
+#[cfg(feature = "legacy-proofs")]
+#[derive(Serialize, Deserialize)]
+struct EncodedProofFile {
+    proof: String,
+    stdin: SP1Stdin,
+    stdout: SP1Stdout,
+}
+
+#[cfg(feature = "legacy-proofs")]
+impl SP1ProofWithIO<BabyBearBlake3> {
+    /// Re-encodes a proof file of any version this build reads in the current version, for the
+    /// program of the ELF.
+    ///
+    /// Only the encoding changes, so this refuses proofs whose digest the current transcript does
+    /// not reproduce: their shards were proven with another transcript, and would not verify in any
+    /// encoding. The transcript observes the commitments to the preprocessed traces of the
+    /// program, whose digests the upgraded proof records.
+    pub fn upgrade(elf: &[u8], json: &[u8]) -> Result<Vec<u8>, ProofVersionError> {
+        use crate::runtime::Program;
+        use crate::stark::{
+            observe_claimed_values, observe_context, observe_shard_commitments, observe_stdin,
+            RiscvStark,
+        };
+
+        let file = serde_json::from_slice::<EncodedProofFile>(json)
+            .map_err(|e| ProofVersionError::Malformed(e.to_string()))?;
+        let bytes =
+            hex::decode(&file.proof).map_err(|e| ProofVersionError::Malformed(e.to_string()))?;
+        let (mut proof, version) = decode_proof::<BabyBearBlake3>(&bytes)?;
+
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let (_, vk) = machine.setup(&Program::from(elf));
+        let mut challenger = machine.config().challenger();
+        observe_context::<BabyBearBlake3>(&mut challenger, &proof.context);
+        observe_stdin::<BabyBearBlake3>(&mut challenger, proof.stdin.as_ref());
+        observe_claimed_values::<BabyBearBlake3>(&mut challenger, &proof.claimed_values);
+        if observe_shard_commitments::<BabyBearBlake3>(&mut challenger, &vk, &proof) != proof.digest
+        {
+            return Err(ProofVersionError::TranscriptMismatch(version));
+        }
+        proof.preprocessed_digests = vk.preprocessed_digests().clone();
+
+        let file = EncodedProofFile {
+            proof: hex::encode(encode_proof(&proof)),
+            ..file
+        };
+        Ok(serde_json::to_vec(&file).unwrap())
+    }
+}
+
+#[cfg(feature = "legacy-proofs")]
+pub(crate) mod legacy {
+    use serde::{Deserialize, Serialize};
+
+    use crate::io::StdinBinding;
+    use crate::stark::{Proof, ShardProof, StarkGenericConfig, Val, PROOF_DIGEST_SIZE};
+
+    /// The layout of a proof of version 1.
+    #[derive(Serialize, Deserialize)]
+    pub struct ProofV1<SC: StarkGenericConfig> {
+        pub shard_proofs: Vec<ShardProof<SC>>,
+        pub digest: [Val<SC>; PROOF_DIGEST_SIZE],
+        pub context: Vec<u8>,
+        pub stdin: Option<StdinBinding>,
+    }
+
+    impl<SC: StarkGenericConfig> From<ProofV1<SC>> for Proof<SC> {
+        fn from(proof: ProofV1<SC>) -> Self {
+            Proof {
+                shard_proofs: proof.shard_proofs,
+                digest: proof.digest,
+                context: proof.context,
+                stdin: proof.stdin,
+                hasher_params: 0,
+                claimed_values: Vec::new(),
+                preprocessed_digests: Default::default(),
+            }
+        }
+    }
+
+    impl<SC: StarkGenericConfig> From<Proof<SC>> for ProofV1<SC> {
+        fn from(proof: Proof<SC>) -> Self {
+            ProofV1 {
+                shard_proofs: proof.shard_proofs,
+                digest: proof.digest,
+                context: proof.context,
+                stdin: proof.stdin,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::Program;
+    use crate::stark::{ProgramVerificationError, RiscvStark};
+    use crate::utils::{
+        tests::FIBONACCI_IO_ELF, BabyBearBlake3, BabyBearPoseidon2, BabyBearPoseidon2W24,
+        Poseidon2Params,
+    };
+    use crate::{SP1ProofWithIO, SP1Prover, SP1Stdin, SP1Verifier};
+
+    #[test]
+    fn test_proof_version() {
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&3u32);
+        let proof = SP1Prover::prove(FIBONACCI_IO_ELF, stdin).unwrap();
+
+        let bytes = encode_proof(&proof.proof);
+        assert!(bytes.starts_with(b"SP1P\x05\x00"));
+        let (decoded, version) = decode_proof::<BabyBearBlake3>(&bytes).unwrap();
+        assert_eq!(version, ProofVersion::V5);
+        assert_eq!(decoded.digest, proof.proof.digest);
+        assert!(!decoded.preprocessed_digests.is_empty());
+
+        // Proofs of version 4 are still read, without digests of the preprocessed traces.
+        let v4 = ProofV4::<BabyBearBlake3> {
+            shard_proofs: bincode::deserialize(&bincode::serialize(&decoded.shard_proofs).unwrap())
+                .unwrap(),
+            digest: decoded.digest,
+            context: decoded.context.clone(),
+            stdin: decoded.stdin,
+            hasher_params: decoded.hasher_params,
+            claimed_values: decoded.claimed_values.clone(),
+        };
+        let bytes = [ProofVersion::V4.header(0), bincode::serialize(&v4).unwrap()].concat();
+        let (v4, version) = decode_proof::<BabyBearBlake3>(&bytes).unwrap();
+        assert_eq!(version, ProofVersion::V4);
+        assert!(v4.preprocessed_digests.is_empty());
+        assert_eq!(v4.claimed_values, decoded.claimed_values);
+
+        // Proofs of version 3 are still read, without claimed values.
+        let v3 = ProofV3::<BabyBearBlake3> {
+            shard_proofs: bincode::deserialize(&bincode::serialize(&decoded.shard_proofs).unwrap())
+                .unwrap(),
+            digest: decoded.digest,
+            context: decoded.context.clone(),
+            stdin: decoded.stdin,
+            hasher_params: decoded.hasher_params,
+        };
+        let bytes = [ProofVersion::V3.header(0), bincode::serialize(&v3).unwrap()].concat();
+        let (v3, version) = decode_proof::<BabyBearBlake3>(&bytes).unwrap();
+        assert_eq!(version, ProofVersion::V3);
+        assert!(v3.claimed_values.is_empty());
+        assert_eq!(v3.digest, proof.proof.digest);
+
+        // Proofs of version 2 are still read, with the parameters of the hasher they always had.
+        let v2 = ProofV2::<BabyBearBlake3> {
+            shard_proofs: decoded.shard_proofs,
+            digest: decoded.digest,
+            context: decoded.context,
+            stdin: decoded.stdin,
+        };
+        let bytes = [ProofVersion::V2.header(0), bincode::serialize(&v2).unwrap()].concat();
+        let (decoded, version) = decode_proof::<BabyBearBlake3>(&bytes).unwrap();
+        assert_eq!(version, ProofVersion::V2);
+        assert_eq!(decoded.hasher_params, 0);
+        assert_eq!(decoded.digest, proof.proof.digest);
+
+        let json = serde_json::to_vec(&proof).unwrap();
+        let decoded = serde_json::from_slice::<SP1ProofWithIO<BabyBearBlake3>>(&json).unwrap();
+        SP1Verifier::verify(FIBONACCI_IO_ELF, &decoded).unwrap();
+
+        assert!(matches!(
+            decode_proof::<BabyBearBlake3>(b"SP1P\x09"),
+            Err(ProofVersionError::Unsupported(9))
+        ));
+        assert!(matches!(
+            decode_proof::<BabyBearBlake3>(b"SP1P\x03"),
+            Err(ProofVersionError::Malformed(_))
+        ));
+        #[cfg(not(feature = "legacy-proofs"))]
+        assert!(matches!(
+            decode_proof::<BabyBearBlake3>(&bincode::serialize(&proof.proof).unwrap()),
+            Err(ProofVersionError::LegacyDisabled(ProofVersion::V1))
+        ));
+    }
+
+    #[test]
+    fn test_hasher_params() {
+        let prove = |config| {
+            let mut stdin = SP1Stdin::new();
+            stdin.write(&3u32);
+            SP1Prover::prove_with_config(FIBONACCI_IO_ELF, stdin, config).unwrap()
+        };
+        let configs = [
+            Poseidon2Params::BABYBEAR_16_30,
+            Poseidon2Params::BABYBEAR_16_21,
+        ]
+        .map(BabyBearPoseidon2::with_params);
+        let proofs = configs.clone().map(prove);
+
+        for (proof, config) in proofs.iter().zip(configs.clone()) {
+            let id = config.params().id;
+            assert_eq!(proof.proof.hasher_params, id);
+            assert!(encode_proof(&proof.proof).starts_with(&ProofVersion::V5.header(id)));
+
+            let machine = RiscvStark::new(config.clone());
+            let (_, vk) = machine.setup(&Program::from(FIBONACCI_IO_ELF));
+            assert_eq!(vk.hasher_params(), id);
+
+            SP1Verifier::verify_with_config(FIBONACCI_IO_ELF, proof, config).unwrap();
+        }
+
+        // A proof only verifies with the parameters it was produced with.
+        for (proof, config) in proofs.iter().zip(configs.into_iter().rev()) {
+            let expected = config.params().id;
+            let result = SP1Verifier::verify_with_config(FIBONACCI_IO_ELF, proof, config);
+            assert!(matches!(
+                result,
+                Err(ProgramVerificationError::Version(
+                    ProofVersionError::HasherParamsMismatch { expected: e, found }
+                )) if e == expected && found == proof.proof.hasher_params
+            ));
+        }
+
+        // The width-24 set proves with its own config, and its proofs do not verify with a
+        // config of width 16 even when decoded as one.
+        let wide_config = BabyBearPoseidon2W24::new();
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&3u32);
+        let wide =
+            SP1Prover::prove_with_config(FIBONACCI_IO_ELF, stdin, wide_config.clone()).unwrap();
+        let id = Poseidon2Params::BABYBEAR_24_29.id;
+        assert_eq!(wide.proof.hasher_params, id);
+        SP1Verifier::verify_with_config(FIBONACCI_IO_ELF, &wide, wide_config).unwrap();
+
+        let (proof, _) = decode_proof::<BabyBearPoseidon2>(&encode_proof(&wide.proof)).unwrap();
+        let narrow = SP1ProofWithIO {
+            proof,
+            stdin: wide.stdin,
+            stdout: wide.stdout,
+        };
+        let result =
+            SP1Verifier::verify_with_config(FIBONACCI_IO_ELF, &narrow, BabyBearPoseidon2::new());
+        assert!(matches!(
+            result,
+            Err(ProgramVerificationError::Version(
+                ProofVersionError::HasherParamsMismatch { expected: 0, found }
+            )) if found == id
+        ));
+    }
+
+    #[cfg(feature = "legacy-proofs")]
+    mod legacy {
+        use std::path::Path;
+
+        use super::*;
+        use crate::version::legacy::ProofV1;
+
+        /// A proof file of version 1, checked in to catch changes which break reading it.
+        const V1_FIXTURE: &str = "fixtures/proofs/fibonacci_io_v1.json";
+
+        /// Writes the proof as a file of version 1.
+        fn to_v1_json(proof: SP1ProofWithIO<BabyBearBlake3>) -> Vec<u8> {
+            let file = EncodedProofFile {
+                proof: hex::encode(bincode::serialize(&ProofV1::from(proof.proof)).unwrap()),
+                stdin: proof.stdin,
+                stdout: proof.stdout,
+            };
+            serde_json::to_vec(&file).unwrap()
+        }
+
+        fn v1_json() -> Vec<u8> {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(V1_FIXTURE);
+            if std::env::var("REGENERATE_FIXTURES").is_ok() {
+                let mut stdin = SP1Stdin::new();
+                stdin.write(&3u32);
+                let proof = SP1Prover::prove(FIBONACCI_IO_ELF, stdin).unwrap();
+                std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                std::fs::write(&path, to_v1_json(proof)).unwrap();
+            }
+            std::fs::read(&path).unwrap_or_else(|_| {
+                let mut stdin = SP1Stdin::new();
+                stdin.write(&3u32);
+                to_v1_json(SP1Prover::prove(FIBONACCI_IO_ELF, stdin).unwrap())
+            })
+        }
+
+        #[test]
+        fn test_verify_v1() {
+            let json = v1_json();
+            let proof = serde_json::from_slice::<SP1ProofWithIO<BabyBearBlake3>>(&json).unwrap();
+            SP1Verifier::verify(FIBONACCI_IO_ELF, &proof).unwrap();
+        }
+
+        #[test]
+        fn test_upgrade_v1() {
+            let json = v1_json();
+            let upgraded = SP1ProofWithIO::upgrade(FIBONACCI_IO_ELF, &json).unwrap();
+            let file = serde_json::from_slice::<EncodedProofFile>(&upgraded).unwrap();
+            let bytes = hex::decode(file.proof).unwrap();
+            assert_eq!(ProofVersion::of(&bytes).unwrap(), ProofVersion::CURRENT);
+            let proof =
+                serde_json::from_slice::<SP1ProofWithIO<BabyBearBlake3>>(&upgraded).unwrap();
+            SP1Verifier::verify(FIBONACCI_IO_ELF, &proof).unwrap();
+
+            // Upgrading is idempotent.
+            assert_eq!(
+                SP1ProofWithIO::upgrade(FIBONACCI_IO_ELF, &upgraded).unwrap(),
+                upgraded
+            );
+
+            // A proof whose digest the transcript does not sample is refused.
+            let mut proof =
+                serde_json::from_slice::<SP1ProofWithIO<BabyBearBlake3>>(&json).unwrap();
+            proof.proof.context = b"another transcript".to_vec();
+            assert!(matches!(
+                SP1ProofWithIO::upgrade(FIBONACCI_IO_ELF, &to_v1_json(proof)),
+                Err(ProofVersionError::TranscriptMismatch(ProofVersion::V1))
+            ));
+        }
+    }
+}
@@ -0,0 +1,486 @@
+//! Witnesses of executions, for proving them on other machines than the ones executing them.
+//!
+//! A [`WitnessBundle`] holds the main trace of every chip of every shard of an execution, with
+//! everything else a prover needs besides the program, which is only referenced by the digests of
+//! its preprocessed traces. `SP1Prover::prove_witness` proves a bundle into the same proof as
+//! `SP1Prover::prove_with_options` does from the execution.
+//!
+//! A bundle is saved to a directory as
+//!
+//! ```text
+//! manifest.json
+//! shard-<index>/<chip>.trace
+//! ```
+//!
+//! where the manifest is a [`WitnessManifest`] in JSON, written after the traces, and each trace
+//! file is laid out as
+//!
+//! ```text
+//! magic (4 bytes) | version (1 byte) | width (u32 LE) | height (u32 LE)
+//!     | chunks of at most TRACE_CHUNK_ROWS rows: number of rows (u32 LE) | the rows
+//! ```
+//!
+//! with the rows in row-major order, and each field element in the canonical encoding of
+//! `utils::codec`.
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use p3_baby_bear::BabyBear;
+use p3_field::PrimeField32;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use serde::{Deserialize, Serialize};
+
+use crate::air::{PublicValues, TraceShape};
+use crate::io::StdinBinding;
+use crate::runtime::{Runtime, ShardStats, ShardingConfig};
+use crate::stark::{CellBudgetExceededError, RiscvStark, ShardTraces};
+use crate::utils::codec::{read_field, write_field};
+use crate::utils::{env, BabyBearBlake3};
+use crate::{SP1Stdin, SP1Stdout};
+
+/// The bytes every trace file starts with.
+pub const WITNESS_MAGIC: [u8; 4] = *b"SP1W";
+
+/// The version of the layout of bundles.
+pub const WITNESS_VERSION: u8 = 1;
+
+/// The most rows a chunk of a trace file holds.
+pub const TRACE_CHUNK_ROWS: usize = 1 << 16;
+
+/// The name of the manifest in the directory of a bundle.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// The description of a bundle, and everything in it but the traces.
+#[derive(Serialize, Deserialize)]
+pub struct WitnessManifest {
+    pub version: u8,
+    /// The hex digests of the preprocessed traces of the program, by chip name.
+    pub preprocessed_digests: BTreeMap<String, String>,
+    pub stdin: SP1Stdin,
+    pub stdout: SP1Stdout,
+    /// The binding of the proof to `stdin`.
+    pub stdin_binding: StdinBinding,
+    /// The bytes the program committed, which the proof carries as claimed values.
+    #[serde(default, rename = "committed_values")]
+    pub claimed_values: Vec<u8>,
+    pub shards: Vec<ShardManifest>,
+}
+
+/// The description of the traces of a shard.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardManifest {
+    pub index: usize,
+    pub public_values: PublicValues<u32>,
+    /// The number of events of each kind in the shard.
+    pub stats: ShardStats,
+    /// The chips with a trace, in the order of the chips of the machine.
+    pub chips: Vec<ChipManifest>,
+}
+
+/// The description of the trace of a chip in a shard.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChipManifest {
+    pub name: String,
+    /// The path of the trace file, relative to the directory of the bundle.
+    pub file: String,
+    pub shape: TraceShape,
+}
+
+/// The main traces of all the shards of an execution, which do not depend on any challenge.
+pub struct WitnessBundle {
+    pub manifest: WitnessManifest,
+    /// The traces of each shard, in the order of the chips of its manifest.
+    pub traces: Vec<Vec<RowMajorMatrix<BabyBear>>>,
+}
+
+/// Why a bundle could not be generated, saved or loaded.
+#[derive(Debug)]
+pub enum WitnessError {
+    Io(io::Error),
+    Manifest(serde_json::Error),
+    /// The manifest is of a version this build does not read.
+    UnsupportedVersion(u8),
+    /// A trace file is missing, malformed, or does not match its entry in the manifest.
+    Trace {
+        file: String,
+        reason: String,
+    },
+    /// The shards are not indexed contiguously from zero.
+    InvalidShardIndex {
+        expected: usize,
+        found: usize,
+    },
+    /// The bundle is not a witness of the program, whose preprocessed traces differ.
+    ProgramMismatch,
+    /// A shard has too many cells to generate its traces.
+    CellBudget(CellBudgetExceededError),
+}
+
+impl Display for WitnessError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WitnessError::Io(e) => write!(f, "{}", e),
+            WitnessError::Manifest(e) => write!(f, "invalid witness manifest: {}", e),
+            WitnessError::UnsupportedVersion(version) => {
+                write!(f, "unsupported witness version {}", version)
+            }
+            WitnessError::Trace { file, reason } => {
+                write!(f, "invalid trace file {}: {}", file, reason)
+            }
+            WitnessError::InvalidShardIndex { expected, found } => {
+                write!(
+                    f,
+                    "expected shard {} in the manifest, found {}",
+                    expected, found
+                )
+            }
+            WitnessError::ProgramMismatch => {
+                write!(f, "the witness was generated for a different program")
+            }
+            WitnessError::CellBudget(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for WitnessError {}
+
+impl From<io::Error> for WitnessError {
+    fn from(e: io::Error) -> Self {
+        WitnessError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for WitnessError {
+    fn from(e: serde_json::Error) -> Self {
+        WitnessError::Manifest(e)
+    }
+}
+
+impl WitnessBundle {
+    /// Generates the traces of every shard of the executed runtime.
+    ///
+    /// The record is sharded and its traces generated as `SP1Prover::prove_with_options` does
+    /// before proving.
+    pub fn generate(
+        runtime: Runtime,
+        stdin: SP1Stdin,
+        stdout: SP1Stdout,
+        stdin_binding: StdinBinding,
+    ) -> Result<Self, WitnessError> {
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let preprocessed_digests = machine
+            .preprocessed_digests(&runtime.program)
+            .into_iter()
+            .map(|(name, digest)| (name, hex::encode(digest)))
+            .collect();
+
+        let claimed_values = runtime.record.committed_values.clone();
+        let config = ShardingConfig::with_shard_size(runtime.shard_size as usize);
+        let shards = machine.shard(runtime.record, &config);
+        machine
+            .check_cell_budget(&shards, env::max_cells(), config.shard_size())
+            .map_err(WitnessError::CellBudget)?;
+
+        let (shards, traces) = shards
+            .iter()
+            .enumerate()
+            .map(|(index, shard)| {
+                let shard_traces = machine.generate_shard_traces(shard, index);
+                let chips = shard_traces
+                    .chip_ids
+                    .into_iter()
+                    .zip(shard_traces.shapes)
+                    .map(|(name, shape)| ChipManifest {
+                        file: format!("shard-{}/{}.trace", index, name),
+                        name,
+                        shape,
+                    })
+                    .collect();
+                let manifest = ShardManifest {
+                    index,
+                    public_values: shard_traces.public_values,
+                    stats: shard.stats(),
+                    chips,
+                };
+                (manifest, shard_traces.traces)
+            })
+            .unzip();
+
+        Ok(Self {
+            manifest: WitnessManifest {
+                version: WITNESS_VERSION,
+                preprocessed_digests,
+                stdin,
+                stdout,
+                stdin_binding,
+                claimed_values,
+                shards,
+            },
+            traces,
+        })
+    }
+
+    /// Writes the bundle to the directory, creating it if needed.
+    pub fn save(&self, dir: &Path) -> Result<(), WitnessError> {
+        for (shard, traces) in self.manifest.shards.iter().zip(self.traces.iter()) {
+            for (chip, trace) in shard.chips.iter().zip(traces.iter()) {
+                let path = dir.join(&chip.file);
+                fs::create_dir_all(path.parent().unwrap())?;
+                let mut writer = BufWriter::new(File::create(&path)?);
+                write_trace(&mut writer, trace, TRACE_CHUNK_ROWS)?;
+                writer.flush()?;
+            }
+        }
+        // The manifest comes last, so that a directory with one holds all of its traces.
+        fs::write(
+            dir.join(MANIFEST_FILE),
+            serde_json::to_vec_pretty(&self.manifest)?,
+        )?;
+        Ok(())
+    }
+
+    /// Reads a bundle from the directory, checking that every trace of the manifest is there with
+    /// its shape.
+    pub fn load(dir: &Path) -> Result<Self, WitnessError> {
+        let manifest =
+            serde_json::from_slice::<WitnessManifest>(&fs::read(dir.join(MANIFEST_FILE))?)?;
+        if manifest.version != WITNESS_VERSION {
+            return Err(WitnessError::UnsupportedVersion(manifest.version));
+        }
+
+        let mut traces = Vec::with_capacity(manifest.shards.len());
+        for (expected, shard) in manifest.shards.iter().enumerate() {
+            if shard.index != expected {
+                return Err(WitnessError::InvalidShardIndex {
+                    expected,
+                    found: shard.index,
+                });
+            }
+            let shard_traces = shard
+                .chips
+                .iter()
+                .map(|chip| {
+                    let invalid = |reason: String| WitnessError::Trace {
+                        file: chip.file.clone(),
+                        reason,
+                    };
+                    let file =
+                        File::open(dir.join(&chip.file)).map_err(|e| invalid(e.to_string()))?;
+                    let trace = read_trace::<BabyBear, _>(&mut BufReader::new(file))
+                        .map_err(|e| invalid(e.to_string()))?;
+                    if trace.width() != chip.shape.width || trace.height() != chip.shape.padded_rows
+                    {
+                        return Err(invalid(format!(
+                            "the trace is {}x{}, but the manifest says {}x{}",
+                            trace.height(),
+                            trace.width(),
+                            chip.shape.padded_rows,
+                            chip.shape.width
+                        )));
+                    }
+                    Ok(trace)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            traces.push(shard_traces);
+        }
+
+        Ok(Self { manifest, traces })
+    }
+
+    /// Checks that the bundle is a witness of the program with the given digests of preprocessed
+    /// traces.
+    pub fn check_program(
+        &self,
+        preprocessed_digests: &BTreeMap<String, [u8; 32]>,
+    ) -> Result<(), WitnessError> {
+        let digests = preprocessed_digests
+            .iter()
+            .map(|(name, digest)| (name.clone(), hex::encode(digest)))
+            .collect::<BTreeMap<_, _>>();
+        if digests != self.manifest.preprocessed_digests {
+            return Err(WitnessError::ProgramMismatch);
+        }
+        Ok(())
+    }
+
+    /// The traces of the shards, as the prover takes them, and the manifest.
+    pub fn into_shard_traces(self) -> (Vec<ShardTraces<BabyBear>>, WitnessManifest) {
+        let WitnessBundle {
+            mut manifest,
+            traces,
+        } = self;
+        let shards = std::mem::take(&mut manifest.shards)
+            .into_iter()
+            .zip(traces)
+            .map(|(shard, traces)| {
+                let (chip_ids, shapes): (Vec<_>, _) = shard
+                    .chips
+                    .into_iter()
+                    .map(|chip| (chip.name, chip.shape))
+                    .unzip();
+                ShardTraces {
+                    index: shard.index,
+                    public_values: shard.public_values,
+                    trace_times: vec![Duration::ZERO; chip_ids.len()],
+                    chip_ids,
+                    shapes,
+                    traces,
+                }
+            })
+            .collect();
+        (shards, manifest)
+    }
+}
+
+fn invalid_data(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason.to_string())
+}
+
+fn write_u32<W: Write>(writer: &mut W, value: usize) -> io::Result<()> {
+    let value = u32::try_from(value).map_err(|_| invalid_data("dimension exceeds u32"))?;
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<usize> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes) as usize)
+}
+
+/// Writes a trace in the layout of trace files, in chunks of at most `chunk_rows` rows.
+pub fn write_trace<F: PrimeField32, W: Write>(
+    writer: &mut W,
+    trace: &RowMajorMatrix<F>,
+    chunk_rows: usize,
+) -> io::Result<()> {
+    writer.write_all(&WITNESS_MAGIC)?;
+    writer.write_all(&[WITNESS_VERSION])?;
+    write_u32(writer, trace.width())?;
+    write_u32(writer, trace.height())?;
+    if trace.width() == 0 {
+        return Ok(());
+    }
+    for chunk in trace.values.chunks(chunk_rows * trace.width()) {
+        write_u32(writer, chunk.len() / trace.width())?;
+        for &value in chunk {
+            write_field(writer, value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads a trace in the layout of trace files, failing on anything after its last row.
+pub fn read_trace<F: PrimeField32, R: Read>(reader: &mut R) -> io::Result<RowMajorMatrix<F>> {
+    let mut header = [0u8; 5];
+    reader.read_exact(&mut header)?;
+    if header[..4] != WITNESS_MAGIC {
+        return Err(invalid_data("not a trace file"));
+    }
+    if header[4] != WITNESS_VERSION {
+        return Err(invalid_data("unsupported trace file version"));
+    }
+    let width = read_u32(reader)?;
+    let height = read_u32(reader)?;
+
+    let mut values = Vec::with_capacity(width * height);
+    let mut rows = 0;
+    while width > 0 && rows < height {
+        let chunk_rows = read_u32(reader)?;
+        if chunk_rows == 0 || chunk_rows > height - rows {
+            return Err(invalid_data("invalid number of rows in a chunk"));
+        }
+        for _ in 0..chunk_rows * width {
+            values.push(read_field(reader)?);
+        }
+        rows += chunk_rows;
+    }
+    if reader.read(&mut [0u8])? != 0 {
+        return Err(invalid_data("trailing bytes after the last row"));
+    }
+    Ok(RowMajorMatrix::new(values, width))
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_field::AbstractField;
+
+    use super::*;
+    use crate::utils::tests::{FIBONACCI_ELF, FIBONACCI_IO_ELF};
+    use crate::{SP1Prover, SP1ProverOptions, SP1Verifier};
+
+    fn stdin() -> SP1Stdin {
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&3u32);
+        stdin
+    }
+
+    #[test]
+    fn test_trace_file() {
+        let values = (0..24)
+            .map(BabyBear::from_canonical_u32)
+            .collect::<Vec<_>>();
+        let trace = RowMajorMatrix::new(values, 3);
+        let mut bytes = Vec::new();
+        write_trace(&mut bytes, &trace, 3).unwrap();
+        assert!(bytes.starts_with(b"SP1W\x01"));
+        let read = read_trace::<BabyBear, _>(&mut bytes.as_slice()).unwrap();
+        assert_eq!(read.values, trace.values);
+        assert_eq!(read.width(), 3);
+
+        assert!(read_trace::<BabyBear, _>(&mut &bytes[..bytes.len() - 1]).is_err());
+        bytes.push(0);
+        assert!(read_trace::<BabyBear, _>(&mut bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_prove_witness() {
+        let bundle =
+            SP1Prover::generate_witness(FIBONACCI_IO_ELF, stdin(), SP1ProverOptions::default())
+                .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        bundle.save(dir.path()).unwrap();
+        let num_shards = bundle.manifest.shards.len();
+        assert!(dir.path().join(MANIFEST_FILE).exists());
+        assert!(dir.path().join("shard-0/CPU.trace").exists());
+
+        let loaded = WitnessBundle::load(dir.path()).unwrap();
+        assert_eq!(loaded.manifest.shards, bundle.manifest.shards);
+        assert!(loaded.manifest.shards[0].stats.nb_cpu_events > 0);
+
+        // The proof of the bundle is the one of the execution.
+        let from_bundle = SP1Prover::prove_witness(FIBONACCI_IO_ELF, loaded).unwrap();
+        let direct =
+            SP1Prover::prove_with_options(FIBONACCI_IO_ELF, stdin(), SP1ProverOptions::default())
+                .unwrap();
+        assert_eq!(from_bundle.proof.shard_proofs.len(), num_shards);
+        assert_eq!(
+            bincode::serialize(&from_bundle.proof).unwrap(),
+            bincode::serialize(&direct.proof).unwrap()
+        );
+        SP1Verifier::verify(FIBONACCI_IO_ELF, &from_bundle).unwrap();
+
+        // A bundle is only proven for its program.
+        let loaded = WitnessBundle::load(dir.path()).unwrap();
+        let Err(err) = SP1Prover::prove_witness(FIBONACCI_ELF, loaded) else {
+            panic!("the bundle was proven for another program");
+        };
+        assert!(matches!(
+            err.downcast::<WitnessError>(),
+            Ok(WitnessError::ProgramMismatch)
+        ));
+
+        // A bundle missing a trace does not load.
+        let file = &bundle.manifest.shards[0].chips[0].file;
+        fs::remove_file(dir.path().join(file)).unwrap();
+        assert!(matches!(
+            WitnessBundle::load(dir.path()),
+            Err(WitnessError::Trace { file: missing, .. }) if missing == *file
+        ));
+    }
+}
@@ -0,0 +1,78 @@
+//! Checks the peak heap usage of the permutation trace generation.
+//!
+//! This runs in its own test binary so that the tracking allocator only sees this test.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use p3_air::BaseAir;
+use p3_baby_bear::BabyBear;
+use p3_field::extension::BinomialExtensionField;
+use p3_matrix::dense::RowMajorMatrix;
+use rand::{thread_rng, Rng};
+use sp1_core::cpu::CpuChip;
+use sp1_core::stark::{Chip, RiscvAir};
+
+/// An allocator that records the peak number of live heap bytes.
+struct PeakAllocator;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for PeakAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let current = CURRENT.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(current, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: PeakAllocator = PeakAllocator;
+
+#[test]
+fn test_permutation_trace_peak_heap() {
+    type EF = BinomialExtensionField<BabyBear, 4>;
+
+    let mut rng = thread_rng();
+    let chip = Chip::new(RiscvAir::<BabyBear>::Cpu(CpuChip::default()));
+    let height = 1 << 14;
+    let width = chip.width();
+    let main = RowMajorMatrix::new(
+        (0..height * width)
+            .map(|_| rng.gen())
+            .collect::<Vec<BabyBear>>(),
+        width,
+    );
+    let random_elements: [EF; 2] = [rng.gen(), rng.gen()];
+
+    // A single worker bounds the scratch space to one chunk at a time.
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build()
+        .unwrap();
+    let (perm, peak) = pool.install(|| {
+        let baseline = CURRENT.load(Ordering::SeqCst);
+        PEAK.store(baseline, Ordering::SeqCst);
+        let perm = chip.generate_permutation_trace(None, &main, &random_elements);
+        (perm, PEAK.load(Ordering::SeqCst) - baseline)
+    });
+
+    // The trace is allocated once. Materializing the rows separately before copying them into
+    // the trace would at least double the peak.
+    let trace_bytes = perm.values.len() * std::mem::size_of::<EF>();
+    assert!(
+        peak <= trace_bytes + trace_bytes / 8,
+        "peak heap of {} bytes for a permutation trace of {} bytes",
+        peak,
+        trace_bytes
+    );
+}
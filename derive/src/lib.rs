@@ -60,6 +60,12 @@ pub fn aligned_borrow_derive(input: TokenStream) -> TokenStream {
     methods.into()
 }
 
+/// Derives `BaseAir`, `MachineAir` and `Air` for an enum of AIRs, each variant of which holds a
+/// single AIR, by dispatching to the AIR of the variant.
+///
+/// The generated code refers to `sp1_core`, `p3_air` and `p3_matrix`, so crates outside of
+/// `sp1-core` can derive a machine of their own, e.g. one with a variant holding a `RiscvAir` and
+/// variants for extra chips.
 #[proc_macro_derive(MachineAir)]
 pub fn machine_air_derive(input: TokenStream) -> TokenStream {
     let ast: syn::DeriveInput = syn::parse(input).unwrap();
@@ -109,40 +115,89 @@ pub fn machine_air_derive(input: TokenStream) -> TokenStream {
             let name_arms = variants.iter().map(|(variant_name, field)| {
                 let field_ty = &field.ty;
                 quote! {
-                    #name::#variant_name(x) => <#field_ty as crate::air::MachineAir<F>>::name(x)
+                    #name::#variant_name(x) => <#field_ty as sp1_core::air::MachineAir<F>>::name(x)
                 }
             });
 
             let preprocessed_width_arms = variants.iter().map(|(variant_name, field)| {
                 let field_ty = &field.ty;
                 quote! {
-                    #name::#variant_name(x) => <#field_ty as crate::air::MachineAir<F>>::preprocessed_width(x)
+                    #name::#variant_name(x) => <#field_ty as sp1_core::air::MachineAir<F>>::preprocessed_width(x)
                 }
             });
 
             let generate_preprocessed_trace_arms = variants.iter().map(|(variant_name, field)| {
                 let field_ty = &field.ty;
                 quote! {
-                    #name::#variant_name(x) => <#field_ty as crate::air::MachineAir<F>>::generate_preprocessed_trace(x, program)
+                    #name::#variant_name(x) => <#field_ty as sp1_core::air::MachineAir<F>>::generate_preprocessed_trace(x, program)
                 }
             });
 
             let generate_trace_arms = variants.iter().map(|(variant_name, field)| {
                 let field_ty = &field.ty;
                 quote! {
-                    #name::#variant_name(x) => <#field_ty as crate::air::MachineAir<F>>::generate_trace(x, input, output)
+                    #name::#variant_name(x) => <#field_ty as sp1_core::air::MachineAir<F>>::generate_trace(x, input, output)
+                }
+            });
+
+            let generate_trace_into_arms = variants.iter().map(|(variant_name, field)| {
+                let field_ty = &field.ty;
+                quote! {
+                    #name::#variant_name(x) => <#field_ty as sp1_core::air::MachineAir<F>>::generate_trace_into(x, input, output, trace)
+                }
+            });
+
+            let num_real_rows_arms = variants.iter().map(|(variant_name, field)| {
+                let field_ty = &field.ty;
+                quote! {
+                    #name::#variant_name(x) => <#field_ty as sp1_core::air::MachineAir<F>>::num_real_rows(x, shard)
+                }
+            });
+
+            let included_arms = variants.iter().map(|(variant_name, field)| {
+                let field_ty = &field.ty;
+                quote! {
+                    #name::#variant_name(x) => <#field_ty as sp1_core::air::MachineAir<F>>::included(x, shard)
+                }
+            });
+
+            let produces_arms = variants.iter().map(|(variant_name, field)| {
+                let field_ty = &field.ty;
+                quote! {
+                    #name::#variant_name(x) => <#field_ty as sp1_core::air::MachineAir<F>>::produces(x)
+                }
+            });
+
+            let consumes_arms = variants.iter().map(|(variant_name, field)| {
+                let field_ty = &field.ty;
+                quote! {
+                    #name::#variant_name(x) => <#field_ty as sp1_core::air::MachineAir<F>>::consumes(x)
                 }
             });
 
             let generate_dependencies_arms = variants.iter().map(|(variant_name, field)| {
                 let field_ty = &field.ty;
                 quote! {
-                    #name::#variant_name(x) => <#field_ty as crate::air::MachineAir<F>>::generate_dependencies(x, input, output)
+                    #name::#variant_name(x) => <#field_ty as sp1_core::air::MachineAir<F>>::generate_dependencies(x, input, output)
+                }
+            });
+
+            let validate_arms = variants.iter().map(|(variant_name, field)| {
+                let field_ty = &field.ty;
+                quote! {
+                    #name::#variant_name(x) => <#field_ty as sp1_core::air::MachineAir<F>>::validate(x, input)
+                }
+            });
+
+            let describe_row_arms = variants.iter().map(|(variant_name, field)| {
+                let field_ty = &field.ty;
+                quote! {
+                    #name::#variant_name(x) => <#field_ty as sp1_core::air::MachineAir<F>>::describe_row(x, row)
                 }
             });
 
             let machine_air = quote! {
-                impl #impl_generics crate::air::MachineAir<F> for #name #ty_generics #where_clause {
+                impl #impl_generics sp1_core::air::MachineAir<F> for #name #ty_generics #where_clause {
                     fn name(&self) -> String {
                         match self {
                             #(#name_arms,)*
@@ -157,7 +212,7 @@ pub fn machine_air_derive(input: TokenStream) -> TokenStream {
 
                     fn generate_preprocessed_trace(
                         &self,
-                        program: &crate::runtime::Program,
+                        program: &sp1_core::runtime::Program,
                     ) -> Option<p3_matrix::dense::RowMajorMatrix<F>> {
                         match self {
                             #(#generate_preprocessed_trace_arms,)*
@@ -166,23 +221,73 @@ pub fn machine_air_derive(input: TokenStream) -> TokenStream {
 
                     fn generate_trace(
                         &self,
-                        input: &crate::runtime::ExecutionRecord,
-                        output: &mut crate::runtime::ExecutionRecord,
+                        input: &sp1_core::runtime::ExecutionRecord,
+                        output: &mut sp1_core::runtime::ExecutionRecord,
                     ) -> p3_matrix::dense::RowMajorMatrix<F> {
                         match self {
                             #(#generate_trace_arms,)*
                         }
                     }
 
+                    fn generate_trace_into(
+                        &self,
+                        input: &sp1_core::runtime::ExecutionRecord,
+                        output: &mut sp1_core::runtime::ExecutionRecord,
+                        trace: &mut p3_matrix::dense::RowMajorMatrix<F>,
+                    ) {
+                        match self {
+                            #(#generate_trace_into_arms,)*
+                        }
+                    }
+
+                    fn num_real_rows(&self, shard: &sp1_core::runtime::ExecutionRecord) -> usize {
+                        match self {
+                            #(#num_real_rows_arms,)*
+                        }
+                    }
+
+                    fn included(&self, shard: &sp1_core::runtime::ExecutionRecord) -> bool {
+                        match self {
+                            #(#included_arms,)*
+                        }
+                    }
+
+                    fn produces(&self) -> &[sp1_core::air::EventKind] {
+                        match self {
+                            #(#produces_arms,)*
+                        }
+                    }
+
+                    fn consumes(&self) -> &[sp1_core::air::EventKind] {
+                        match self {
+                            #(#consumes_arms,)*
+                        }
+                    }
+
                     fn generate_dependencies(
                         &self,
-                        input: &crate::runtime::ExecutionRecord,
-                        output: &mut crate::runtime::ExecutionRecord,
+                        input: &sp1_core::runtime::ExecutionRecord,
+                        output: &mut sp1_core::runtime::ExecutionRecord,
                     ) {
                         match self {
                             #(#generate_dependencies_arms,)*
                         }
                     }
+
+                    fn validate(
+                        &self,
+                        input: &sp1_core::runtime::ExecutionRecord,
+                    ) -> Result<(), sp1_core::air::EventValidationError> {
+                        match self {
+                            #(#validate_arms,)*
+                        }
+                    }
+
+                    fn describe_row(&self, row: &[F]) -> Option<String> {
+                        match self {
+                            #(#describe_row_arms,)*
+                        }
+                    }
                 }
             };
 
@@ -193,12 +298,13 @@ pub fn machine_air_derive(input: TokenStream) -> TokenStream {
                 }
             });
 
-            // Attach an extra generic AB : crate::air::SP1AirBuilder to the generics of the enum
+            // Attach an extra generic AB : sp1_core::air::SP1AirBuilder + p3_air::PairBuilder to the
+            // generics of the enum, as some chips read their preprocessed trace.
             let generics = &ast.generics;
             let mut new_generics = generics.clone();
-            new_generics
-                .params
-                .push(syn::parse_quote! { AB: crate::air::SP1AirBuilder<F = F> });
+            new_generics.params.push(
+                syn::parse_quote! { AB: sp1_core::air::SP1AirBuilder<F = F> + p3_air::PairBuilder },
+            );
 
             let (air_impl_generics, _, _) = new_generics.split_for_impl();
 
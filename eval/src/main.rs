@@ -2,8 +2,10 @@ use clap::{command, Parser};
 use csv::WriterBuilder;
 use serde::Serialize;
 use sp1_core::runtime::{Program, Runtime};
-use sp1_core::utils::{get_cycles, prove_core, BabyBearBlake3, BabyBearKeccak, BabyBearPoseidon2};
-use sp1_core::{SP1ProofWithIO, SP1Stdin, SP1Stdout, SP1Verifier};
+use sp1_core::utils::{
+    get_cycles, prove_core_with_stdin, BabyBearBlake3, BabyBearKeccak, BabyBearPoseidon2,
+};
+use sp1_core::{SP1ProofWithIO, SP1Stdin, SP1Stdout, SP1Verifier, StdinBinding, StdinPolicy};
 use std::fmt;
 use std::fs::OpenOptions;
 use std::io;
@@ -127,6 +129,11 @@ fn main() {
     }
 }
 
+/// The binding of a proof to the empty input the programs are evaluated with.
+fn empty_stdin() -> StdinBinding {
+    StdinBinding::new(StdinPolicy::ConsumedPrefix, &SP1Stdin::new(), 0)
+}
+
 fn run_evaluation(hashfn: &HashFnId, program: &Program, elf: &[u8]) -> (f64, f64, f64) {
     match hashfn {
         HashFnId::Blake3 => {
@@ -137,7 +144,8 @@ fn run_evaluation(hashfn: &HashFnId, program: &Program, elf: &[u8]) -> (f64, f64
 
             let config = BabyBearBlake3::new();
             let prove_start = Instant::now();
-            let proof = prove_core(config.clone(), runtime);
+            let proof =
+                prove_core_with_stdin(config.clone(), runtime, &[], Some(empty_stdin())).unwrap();
             let prove_duration = prove_start.elapsed().as_secs_f64();
             let proof = SP1ProofWithIO {
                 stdin: SP1Stdin::new(),
@@ -159,7 +167,8 @@ fn run_evaluation(hashfn: &HashFnId, program: &Program, elf: &[u8]) -> (f64, f64
 
             let config = BabyBearPoseidon2::new();
             let prove_start = Instant::now();
-            let proof = prove_core(config.clone(), runtime);
+            let proof =
+                prove_core_with_stdin(config.clone(), runtime, &[], Some(empty_stdin())).unwrap();
             let prove_duration = prove_start.elapsed().as_secs_f64();
             let proof = SP1ProofWithIO {
                 stdin: SP1Stdin::new(),
@@ -181,7 +190,8 @@ fn run_evaluation(hashfn: &HashFnId, program: &Program, elf: &[u8]) -> (f64, f64
 
             let config = BabyBearKeccak::new();
             let prove_start = Instant::now();
-            let proof = prove_core(config.clone(), runtime);
+            let proof =
+                prove_core_with_stdin(config.clone(), runtime, &[], Some(empty_stdin())).unwrap();
             let prove_duration = prove_start.elapsed().as_secs_f64();
             let proof = SP1ProofWithIO {
                 stdin: SP1Stdin::new(),
@@ -0,0 +1,23 @@
+//! Recovers a secp256k1 public key from a signature the given number of times, read from stdin.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use hex_literal::hex;
+use sp1_zkvm::precompiles::secp256k1::ecrecover;
+use std::hint::black_box;
+
+const MSG_HASH: [u8; 32] = hex!("5ae8317d34d1e595e3fa7247db80c0af4320cce1116de187f8f7e2e099c0d8d0");
+const SIGNATURE: [u8; 65] = hex!("45c0b7f8c09a9e1f1cea0c25785594427b6bf8f9f878a8af0b1abbb48e16d0920d8becd0c220f67c51217eecfd7184ef0732481c843857e6bc7fc095c4f6b78801");
+const PUBKEY: [u8; 65] = hex!("044a071e8a6e10aada2b8cf39fa3b5fb3400b04e99ea8ae64ceea1a977dbeaf5d5f8c8fbd10b71ab14cd561f7df8eb6da50f8a8d81ba564342244d26d1d4211595");
+
+pub fn main() {
+    let iterations = sp1_zkvm::io::read::<u32>();
+
+    for _ in 0..iterations {
+        let pubkey = ecrecover(black_box(&SIGNATURE), black_box(&MSG_HASH)).unwrap();
+        assert_eq!(pubkey, PUBKEY);
+    }
+
+    sp1_zkvm::io::write(&iterations);
+}
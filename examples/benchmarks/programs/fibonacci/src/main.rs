@@ -0,0 +1,18 @@
+//! Computes the `n`th fibonacci number modulo 2^32, where `n` is read from stdin.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+pub fn main() {
+    let n = sp1_zkvm::io::read::<u32>();
+
+    let mut a: u32 = 0;
+    let mut b: u32 = 1;
+    for _ in 0..n {
+        let sum = a.wrapping_add(b);
+        a = b;
+        b = sum;
+    }
+
+    sp1_zkvm::io::write(&a);
+}
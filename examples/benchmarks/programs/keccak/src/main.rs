@@ -0,0 +1,18 @@
+//! Hashes a buffer of the given number of KB, read from stdin, with the keccak precompile.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use tiny_keccak::{Hasher, Keccak};
+
+pub fn main() {
+    let kb = sp1_zkvm::io::read::<u32>();
+    let input = vec![0xab; kb as usize * 1024];
+
+    let mut hasher = Keccak::v256();
+    hasher.update(&input);
+    let mut digest = [0u8; 32];
+    hasher.finalize(&mut digest);
+
+    sp1_zkvm::io::write_slice(&digest);
+}
@@ -0,0 +1,23 @@
+//! Copies a buffer of the given number of KB, read from stdin, back and forth between two buffers.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use std::hint::black_box;
+
+const ROUNDS: usize = 8;
+
+pub fn main() {
+    let kb = sp1_zkvm::io::read::<u32>();
+    let len = kb as usize * 1024;
+
+    let mut src = (0..len).map(|i| i as u8).collect::<Vec<_>>();
+    let mut dst = vec![0u8; len];
+    for _ in 0..ROUNDS {
+        dst.copy_from_slice(black_box(&src));
+        src.copy_from_slice(black_box(&dst));
+    }
+
+    let checksum = dst.iter().fold(0u32, |acc, x| acc.wrapping_add(*x as u32));
+    sp1_zkvm::io::write(&checksum);
+}
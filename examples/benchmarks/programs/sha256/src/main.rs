@@ -0,0 +1,15 @@
+//! Hashes a buffer of the given number of KB, read from stdin, with the sha256 precompiles.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use sha2::{Digest, Sha256};
+
+pub fn main() {
+    let kb = sp1_zkvm::io::read::<u32>();
+    let input = vec![0xab; kb as usize * 1024];
+
+    let digest = Sha256::digest(&input);
+
+    sp1_zkvm::io::write_slice(&digest);
+}
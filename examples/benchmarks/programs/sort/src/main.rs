@@ -0,0 +1,25 @@
+//! Sorts the given number of pseudorandom words, read from stdin.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+pub fn main() {
+    let n = sp1_zkvm::io::read::<u32>();
+
+    // Fill the input with a xorshift sequence so that the workload is deterministic.
+    let mut state: u32 = 0x9e3779b9;
+    let mut values = (0..n)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        })
+        .collect::<Vec<_>>();
+
+    values.sort_unstable();
+    assert!(values.windows(2).all(|pair| pair[0] <= pair[1]));
+
+    sp1_zkvm::io::write(&values.first().copied());
+    sp1_zkvm::io::write(&values.last().copied());
+}
@@ -0,0 +1,14 @@
+fn main() {
+    // The harness reads the ELFs of the guests when it runs, so they are only built on request.
+    #[cfg(feature = "build-guests")]
+    for workload in [
+        "fibonacci",
+        "sha256",
+        "keccak",
+        "ecdsa",
+        "sort",
+        "memcpy",
+    ] {
+        sp1_helper::build_program(&format!("../programs/{}", workload));
+    }
+}
@@ -0,0 +1,378 @@
+//! A harness which runs the benchmark guests under execute-only and full proving, and collects the
+//! execution reports and prover metrics of every run into a single CSV or JSON report.
+//!
+//! The ELFs of the guests are read when the harness runs. Building with the `build-guests` feature
+//! builds them first with `cargo prove build`.
+
+use clap::{Parser, ValueEnum};
+use serde::Serialize;
+use sp1_core::runtime::{ExecutionReport, Program, Runtime};
+use sp1_core::utils::{self, BabyBearBlake3, BabyBearPoseidon2};
+use sp1_core::{SP1Prover, SP1Stdin, SP1Verifier};
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// A benchmark guest, with the sizes it is run at, from smallest to largest.
+struct Workload {
+    name: &'static str,
+    /// The path of the ELF of the guest, relative to the crate of the harness.
+    elf: &'static str,
+    sizes: &'static [u32],
+    /// The input of the guest for a size.
+    input: fn(u32) -> SP1Stdin,
+}
+
+/// The benchmark guests, each of which reads its size as a `u32` from stdin.
+const WORKLOADS: &[Workload] = &[
+    Workload {
+        name: "fibonacci",
+        elf: "../programs/fibonacci/elf/riscv32im-succinct-zkvm-elf",
+        sizes: &[100, 10_000, 100_000],
+        input: stdin,
+    },
+    Workload {
+        name: "sha256",
+        elf: "../programs/sha256/elf/riscv32im-succinct-zkvm-elf",
+        sizes: &[1, 16, 64],
+        input: stdin,
+    },
+    Workload {
+        name: "keccak",
+        elf: "../programs/keccak/elf/riscv32im-succinct-zkvm-elf",
+        sizes: &[1, 16, 64],
+        input: stdin,
+    },
+    Workload {
+        name: "ecdsa",
+        elf: "../programs/ecdsa/elf/riscv32im-succinct-zkvm-elf",
+        sizes: &[1, 4, 16],
+        input: stdin,
+    },
+    Workload {
+        name: "sort",
+        elf: "../programs/sort/elf/riscv32im-succinct-zkvm-elf",
+        sizes: &[256, 4096, 65_536],
+        input: stdin,
+    },
+    Workload {
+        name: "memcpy",
+        elf: "../programs/memcpy/elf/riscv32im-succinct-zkvm-elf",
+        sizes: &[16, 256, 1024],
+        input: stdin,
+    },
+];
+
+/// The configuration to prove with.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ConfigId {
+    /// Blake3 hashing, which is the fastest to prove with natively.
+    Fast,
+    /// Poseidon2 hashing, which is the configuration proofs are recursively verified with.
+    Secure,
+}
+
+/// Whether to only execute the guests or to also prove them.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    Execute,
+    Prove,
+}
+
+#[derive(Parser)]
+#[command(about = "Run the benchmark guests and report their performance.")]
+struct BenchmarkArgs {
+    /// Only run the workloads whose name contains this string.
+    #[arg(long)]
+    filter: Option<String>,
+
+    #[arg(long, value_enum, default_value_t = ConfigId::Fast)]
+    config: ConfigId,
+
+    /// The modes to run each workload under.
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = [Mode::Execute, Mode::Prove])]
+    mode: Vec<Mode>,
+
+    /// Only run the smallest size of each workload.
+    #[arg(long)]
+    smallest: bool,
+
+    /// The report to write, as JSON if the path ends in `.json` and as CSV otherwise.
+    #[arg(long, default_value = "benchmarks.csv")]
+    output: String,
+}
+
+/// The metrics of proving and verifying a guest.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProverMetrics {
+    /// The number of shards in the proof.
+    pub shards: usize,
+
+    /// The size of the serialized proof in bytes.
+    pub proof_size: usize,
+
+    /// The duration of the prover in seconds, including execution.
+    pub prove_duration: f64,
+
+    /// The duration of the verifier in seconds.
+    pub verify_duration: f64,
+}
+
+/// A row of the report.
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchmarkResult {
+    pub workload: String,
+    pub size: u32,
+    pub config: String,
+    pub mode: String,
+    pub cycles: u64,
+    pub syscalls: u64,
+    pub syscall_bytes: u64,
+    pub execution_duration: f64,
+    pub shards: Option<usize>,
+    pub proof_size: Option<usize>,
+    pub prove_duration: Option<f64>,
+    pub verify_duration: Option<f64>,
+}
+
+fn main() {
+    utils::setup_logger();
+    let args = BenchmarkArgs::parse();
+
+    let results = run_workloads(
+        args.filter.as_deref(),
+        args.config,
+        &args.mode,
+        args.smallest,
+    )
+    .unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+
+    write_report(&results, &args.output).expect("failed to write the report");
+    println!("wrote {} results to {}", results.len(), args.output);
+}
+
+/// Runs the selected workloads under each mode.
+///
+/// The ELFs of all the selected workloads are read first, so that a missing guest fails the run
+/// before anything is measured.
+fn run_workloads(
+    filter: Option<&str>,
+    config: ConfigId,
+    modes: &[Mode],
+    smallest: bool,
+) -> Result<Vec<BenchmarkResult>, MissingElfError> {
+    let workloads = WORKLOADS
+        .iter()
+        .filter(|workload| filter.map_or(true, |filter| workload.name.contains(filter)))
+        .map(|workload| Ok((workload, load_elf(workload)?)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut results = Vec::new();
+    for (workload, elf) in workloads {
+        let sizes = if smallest {
+            &workload.sizes[..1]
+        } else {
+            workload.sizes
+        };
+        for &size in sizes {
+            for &mode in modes {
+                println!("running {} with size {} ({:?})", workload.name, size, mode);
+                results.push(run_workload(workload, &elf, size, config, mode));
+            }
+        }
+    }
+    Ok(results)
+}
+
+/// The error of a workload whose guest has not been built.
+#[derive(Debug)]
+pub struct MissingElfError {
+    pub workload: &'static str,
+    pub path: PathBuf,
+}
+
+impl Display for MissingElfError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the ELF of the {} guest is missing at {}; build it with `cargo prove build` in its \
+             program directory, or run the harness with `--features build-guests`",
+            self.workload,
+            self.path.display()
+        )
+    }
+}
+
+impl std::error::Error for MissingElfError {}
+
+/// Reads the ELF of the guest of a workload.
+fn load_elf(workload: &Workload) -> Result<Vec<u8>, MissingElfError> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(workload.elf);
+    std::fs::read(&path).map_err(|_| MissingElfError {
+        workload: workload.name,
+        path,
+    })
+}
+
+/// Runs a workload at the given size, proving it if requested.
+fn run_workload(
+    workload: &Workload,
+    elf: &[u8],
+    size: u32,
+    config: ConfigId,
+    mode: Mode,
+) -> BenchmarkResult {
+    let (cycles, report, execution_duration) = execute(elf, (workload.input)(size));
+    let metrics = (mode == Mode::Prove).then(|| prove(elf, (workload.input)(size), config));
+    BenchmarkResult {
+        workload: workload.name.to_string(),
+        size,
+        config: format!("{:?}", config).to_lowercase(),
+        mode: format!("{:?}", mode).to_lowercase(),
+        cycles,
+        syscalls: report.syscall_counts.values().sum(),
+        syscall_bytes: report.syscall_bytes.values().sum(),
+        execution_duration,
+        shards: metrics.as_ref().map(|m| m.shards),
+        proof_size: metrics.as_ref().map(|m| m.proof_size),
+        prove_duration: metrics.as_ref().map(|m| m.prove_duration),
+        verify_duration: metrics.as_ref().map(|m| m.verify_duration),
+    }
+}
+
+fn stdin(size: u32) -> SP1Stdin {
+    let mut stdin = SP1Stdin::new();
+    stdin.write(&size);
+    stdin
+}
+
+/// Executes a guest, returning its cycle count, its execution report, and the duration.
+fn execute(elf: &[u8], stdin: SP1Stdin) -> (u64, ExecutionReport, f64) {
+    let mut runtime = Runtime::new(Program::from(elf));
+    runtime.write_stdin_slice(&stdin.buffer.data);
+    let start = Instant::now();
+    runtime.run();
+    let duration = start.elapsed().as_secs_f64();
+    (runtime.state.global_clk as u64, runtime.report, duration)
+}
+
+/// Proves and verifies a guest under the given configuration.
+fn prove(elf: &[u8], stdin: SP1Stdin, config: ConfigId) -> ProverMetrics {
+    match config {
+        ConfigId::Fast => {
+            let start = Instant::now();
+            let proof = SP1Prover::prove_with_config(elf, stdin, BabyBearBlake3::new())
+                .expect("proving failed");
+            let prove_duration = start.elapsed().as_secs_f64();
+
+            let start = Instant::now();
+            SP1Verifier::verify_with_config(elf, &proof, BabyBearBlake3::new())
+                .expect("verification failed");
+            let verify_duration = start.elapsed().as_secs_f64();
+
+            ProverMetrics {
+                shards: proof.proof.shard_proofs.len(),
+                proof_size: bincode::serialize(&proof.proof).unwrap().len(),
+                prove_duration,
+                verify_duration,
+            }
+        }
+        ConfigId::Secure => {
+            let start = Instant::now();
+            let proof = SP1Prover::prove_with_config(elf, stdin, BabyBearPoseidon2::new())
+                .expect("proving failed");
+            let prove_duration = start.elapsed().as_secs_f64();
+
+            let start = Instant::now();
+            SP1Verifier::verify_with_config(elf, &proof, BabyBearPoseidon2::new())
+                .expect("verification failed");
+            let verify_duration = start.elapsed().as_secs_f64();
+
+            ProverMetrics {
+                shards: proof.proof.shard_proofs.len(),
+                proof_size: bincode::serialize(&proof.proof).unwrap().len(),
+                prove_duration,
+                verify_duration,
+            }
+        }
+    }
+}
+
+fn write_report(results: &[BenchmarkResult], path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let file = File::create(path)?;
+    if Path::new(path)
+        .extension()
+        .map_or(false, |ext| ext == "json")
+    {
+        serde_json::to_writer_pretty(file, results)?;
+    } else {
+        let mut writer = csv::Writer::from_writer(file);
+        for result in results {
+            writer.serialize(result)?;
+        }
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs the smallest size of every workload under the fast config. The guests are only built
+    /// with the `build-guests` feature.
+    #[cfg(feature = "build-guests")]
+    #[test]
+    fn smoke_test() {
+        let results =
+            run_workloads(None, ConfigId::Fast, &[Mode::Execute, Mode::Prove], true).unwrap();
+        assert_eq!(results.len(), 2 * WORKLOADS.len());
+        for result in results {
+            assert!(result.cycles > 0, "{} did not execute", result.workload);
+        }
+    }
+
+    /// Runs the harness on the ELF of the fibonacci example, which is checked in, and writes both
+    /// kinds of report.
+    #[test]
+    fn test_committed_guest() {
+        let workload = Workload {
+            name: "fibonacci-example",
+            elf: "../../fibonacci/program/elf/riscv32im-succinct-zkvm-elf",
+            sizes: &[1],
+            input: stdin,
+        };
+        let elf = load_elf(&workload).unwrap();
+        let results = [Mode::Execute, Mode::Prove]
+            .map(|mode| run_workload(&workload, &elf, 1, ConfigId::Fast, mode));
+        assert!(results.iter().all(|result| result.cycles > 0));
+        assert!(results[0].shards.is_none());
+        assert!(results[1].shards.unwrap() > 0);
+
+        let dir = std::env::temp_dir();
+        for name in ["benchmarks-test.csv", "benchmarks-test.json"] {
+            let path = dir.join(name);
+            write_report(&results, path.to_str().unwrap()).unwrap();
+            assert!(std::fs::read_to_string(&path)
+                .unwrap()
+                .contains("fibonacci-example"));
+        }
+    }
+
+    #[test]
+    fn test_missing_elf() {
+        let workload = Workload {
+            name: "missing",
+            elf: "../programs/missing/elf/riscv32im-succinct-zkvm-elf",
+            sizes: &[1],
+            input: stdin,
+        };
+        let err = load_elf(&workload).unwrap_err();
+        assert_eq!(err.workload, "missing");
+        assert!(err.to_string().contains("programs/missing/elf"));
+    }
+}
@@ -1,4 +1,4 @@
-use sp1_core::{SP1Prover, SP1Stdin, SP1Verifier};
+use sp1_core::{SP1Prover, SP1Stdin, UnverifiedProof};
 
 const ELF: &[u8] = include_bytes!("../../program/elf/riscv32im-succinct-zkvm-elf");
 
@@ -20,7 +20,9 @@ fn main() {
     println!("is_valid_move: {}", is_valid_move);
 
     // Verify proof.
-    SP1Verifier::verify(ELF, &proof).expect("verification failed");
+    let proof = UnverifiedProof::from(proof)
+        .verify(ELF)
+        .expect("verification failed");
 
     // Save proof.
     proof
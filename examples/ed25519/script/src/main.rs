@@ -1,4 +1,4 @@
-use sp1_core::{utils, SP1Prover, SP1Stdin, SP1Verifier};
+use sp1_core::{utils, SP1Prover, SP1Stdin, UnverifiedProof};
 
 const ED25519_ELF: &[u8] = include_bytes!("../../program/elf/riscv32im-succinct-zkvm-elf");
 
@@ -9,7 +9,9 @@ fn main() {
     let proof = SP1Prover::prove(ED25519_ELF, stdin).expect("proving failed");
 
     // Verify proof.
-    SP1Verifier::verify(ED25519_ELF, &proof).expect("verification failed");
+    let proof = UnverifiedProof::from(proof)
+        .verify(ED25519_ELF)
+        .expect("verification failed");
 
     // Save proof.
     proof
@@ -1,4 +1,4 @@
-use sp1_core::{utils, SP1Prover, SP1Stdin, SP1Verifier};
+use sp1_core::{utils, SP1Prover, SP1Stdin, UnverifiedProof};
 
 /// The ELF we want to execute inside the zkVM.
 const ELF: &[u8] = include_bytes!("../../program/elf/riscv32im-succinct-zkvm-elf");
@@ -21,7 +21,9 @@ fn main() {
     println!("b: {}", b);
 
     // Verify proof.
-    SP1Verifier::verify(ELF, &proof).expect("verification failed");
+    let proof = UnverifiedProof::from(proof)
+        .verify(ELF)
+        .expect("verification failed");
 
     // Save the proof.
     proof
@@ -1,7 +1,7 @@
 //! A simple script to generate and verify the proof of a given program.
 
 use lib::{Account, Transaction};
-use sp1_core::{utils, SP1Prover, SP1Stdin, SP1Verifier};
+use sp1_core::{utils, SP1Prover, SP1Stdin, UnverifiedProof};
 
 const JSON_ELF: &[u8] = include_bytes!("../../program/elf/riscv32im-succinct-zkvm-elf");
 
@@ -58,7 +58,9 @@ fn main() {
     );
 
     // Verify proof.
-    SP1Verifier::verify(JSON_ELF, &proof).expect("verification failed");
+    let proof = UnverifiedProof::from(proof)
+        .verify(JSON_ELF)
+        .expect("verification failed");
 
     // Save proof.
     proof
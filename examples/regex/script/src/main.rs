@@ -1,4 +1,4 @@
-use sp1_core::{utils, SP1Prover, SP1Stdin, SP1Verifier};
+use sp1_core::{utils, SP1Prover, SP1Stdin, UnverifiedProof};
 
 /// The ELF we want to execute inside the zkVM.
 const REGEX_IO_ELF: &[u8] = include_bytes!("../../program/elf/riscv32im-succinct-zkvm-elf");
@@ -25,7 +25,9 @@ fn main() {
     println!("res: {}", res);
 
     // Verify proof.
-    SP1Verifier::verify(REGEX_IO_ELF, &proof).expect("verification failed");
+    let proof = UnverifiedProof::from(proof)
+        .verify(REGEX_IO_ELF)
+        .expect("verification failed");
 
     // Save the proof.
     proof
@@ -0,0 +1,147 @@
+//! A machine extending the RISC-V AIRs of `sp1-core` with a chip defined outside of it.
+//!
+//! The chip itself is a toy: it commits to the low byte of the result of each `ADD` of a shard.
+//! The point is the wiring, which is all an out-of-tree crate needs to add a chip of its own:
+//! an enum deriving `MachineAir` with a variant for the core AIRs, and a machine built with
+//! [`RiscvStark::from_airs`](sp1_core::stark::RiscvStark::from_airs).
+
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_field::PrimeField32;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::MatrixRowSlices;
+use sp1_core::air::{BaseAirBuilder, EventKind, MachineAir, SP1AirBuilder};
+use sp1_core::runtime::ExecutionRecord;
+use sp1_core::stark::RiscvAir;
+use sp1_core::utils::pad_to_power_of_two;
+
+/// The number of main trace columns for `AddResultChip`: the low byte and a real row selector.
+pub const NUM_ADD_RESULT_COLS: usize = 2;
+
+/// A chip with a row per `ADD`, holding the low byte of its result.
+#[derive(Default)]
+pub struct AddResultChip;
+
+impl<F: PrimeField32> MachineAir<F> for AddResultChip {
+    fn name(&self) -> String {
+        "AddResult".to_string()
+    }
+
+    fn consumes(&self) -> &[EventKind] {
+        &[EventKind::Add]
+    }
+
+    fn num_real_rows(&self, shard: &ExecutionRecord) -> usize {
+        shard.add_events.len()
+    }
+
+    fn generate_trace(
+        &self,
+        input: &ExecutionRecord,
+        _output: &mut ExecutionRecord,
+    ) -> RowMajorMatrix<F> {
+        let mut values = input
+            .add_events
+            .iter()
+            .flat_map(|event| [F::from_canonical_u8(event.a as u8), F::one()])
+            .collect::<Vec<_>>();
+
+        pad_to_power_of_two::<NUM_ADD_RESULT_COLS, F>(&mut values);
+
+        RowMajorMatrix::new(values, NUM_ADD_RESULT_COLS)
+    }
+}
+
+impl<F> BaseAir<F> for AddResultChip {
+    fn width(&self) -> usize {
+        NUM_ADD_RESULT_COLS
+    }
+}
+
+impl<AB> Air<AB> for AddResultChip
+where
+    AB: SP1AirBuilder,
+{
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let (byte, is_real) = (local[0], local[1]);
+
+        builder.assert_bool(is_real);
+        builder.when_not(is_real).assert_zero(byte);
+
+        // Degree 3 constraint to avoid "OodEvaluationMismatch".
+        builder.assert_zero(byte * byte * is_real - byte * byte * is_real);
+    }
+}
+
+/// The RISC-V AIRs of `sp1-core` together with `AddResultChip`.
+#[derive(MachineAir)]
+pub enum ExtendedAir<F: PrimeField32> {
+    /// One of the core RISC-V AIRs.
+    Riscv(RiscvAir<F>),
+    /// The chip added by this crate.
+    AddResult(AddResultChip),
+}
+
+impl<F: PrimeField32> ExtendedAir<F> {
+    /// Get all the AIRs of the extended machine.
+    ///
+    /// `AddResultChip` consumes the `ADD` events the CPU emits, so the machine generates its trace
+    /// after the one of the CPU wherever it is placed.
+    pub fn get_all() -> Vec<Self> {
+        let mut chips = RiscvAir::get_all()
+            .into_iter()
+            .map(ExtendedAir::Riscv)
+            .collect::<Vec<_>>();
+        chips.push(ExtendedAir::AddResult(AddResultChip));
+        chips
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sp1_core::runtime::{Instruction, Opcode, Program, Runtime};
+    use sp1_core::stark::{LocalProver, RiscvStark};
+    use sp1_core::utils::{self, BabyBearBlake3, StarkUtils};
+
+    use super::ExtendedAir;
+
+    fn add_program() -> Program {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::ADD, 30, 0, 37, false, true),
+            Instruction::new(Opcode::ADD, 31, 30, 29, false, false),
+            Instruction::new(Opcode::ADD, 31, 31, 300, false, true),
+        ];
+        Program::new(instructions, 0, 0).with_halt()
+    }
+
+    #[test]
+    fn test_extended_machine_prove() {
+        utils::setup_logger();
+        let mut runtime = Runtime::new(add_program());
+        runtime.run();
+
+        let machine = RiscvStark::from_airs(BabyBearBlake3::new(), ExtendedAir::get_all());
+        let (pk, vk) = machine.setup(runtime.program.as_ref());
+        let mut challenger = machine.config().challenger();
+        let proof = machine
+            .prove::<LocalProver<_>>(&pk, runtime.record, &mut challenger)
+            .unwrap();
+        assert!(proof
+            .shard_proofs
+            .iter()
+            .any(|shard_proof| shard_proof.chip_ids.contains(&"AddResult".to_string())));
+
+        let mut challenger = machine.config().challenger();
+        machine.verify(&vk, &proof, &mut challenger).unwrap();
+
+        // The core machine does not know the extra chip, so it cannot verify the proof.
+        let core_machine = RiscvStark::new(BabyBearBlake3::new());
+        let (_, core_vk) = core_machine.setup(runtime.program.as_ref());
+        let mut challenger = core_machine.config().challenger();
+        assert!(core_machine
+            .verify(&core_vk, &proof, &mut challenger)
+            .is_err());
+    }
+}
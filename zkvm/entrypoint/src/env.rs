@@ -0,0 +1,54 @@
+use alloc::vec::Vec;
+
+/// The start of the memory region holding the arguments passed by the host.
+const ARGS_START: usize = 0x0C00_0000;
+
+/// Returns the arguments passed by the host with `Program::with_args`, or no arguments if the host
+/// did not pass any.
+pub fn args() -> Vec<Vec<u8>> {
+    let table = ARGS_START as *const u32;
+    // SAFETY: The host lays out the argument table at `ARGS_START`, above the heap. Memory which
+    // was not written reads as zero, i.e. as an empty table.
+    unsafe {
+        let argc = table.read_volatile() as usize;
+        (0..argc)
+            .map(|i| {
+                let ptr = table.add(1 + 2 * i).read_volatile() as *const u8;
+                let len = table.add(2 + 2 * i).read_volatile() as usize;
+                core::slice::from_raw_parts(ptr, len).to_vec()
+            })
+            .collect()
+    }
+}
+
+/// Returns the value of the environment variable `key` set by the host with `SP1Stdin::set_env`,
+/// or `None` if it is not set.
+pub fn var(key: &str) -> Option<Vec<u8>> {
+    let mut value = Vec::new();
+    loop {
+        let len = crate::syscalls::sys_getenv(
+            key.as_ptr(),
+            key.len(),
+            value.as_mut_ptr(),
+            value.capacity(),
+        );
+        if len == crate::syscalls::ENV_MISSING {
+            return None;
+        }
+        if len <= value.capacity() {
+            // SAFETY: `sys_getenv` wrote the `len` bytes of the value to the buffer.
+            unsafe { value.set_len(len) };
+            return Some(value);
+        }
+        // The value was truncated: look it up again with room for all of it.
+        value.reserve_exact(len);
+    }
+}
+
+/// Returns the cycle count of the execution so far.
+///
+/// This is the number of instructions executed before the call, counted across shards, so the
+/// difference of two readings is the number of instructions between them.
+pub fn cycle_count() -> u64 {
+    crate::syscalls::sys_cycle_count()
+}
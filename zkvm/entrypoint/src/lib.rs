@@ -1,4 +1,6 @@
+pub mod env;
 pub mod heap;
+pub mod stack;
 pub mod syscalls;
 pub mod io {
     pub use sp1_precompiles::io::*;
@@ -6,12 +8,22 @@ pub mod io {
 pub mod precompiles {
     pub use sp1_precompiles::*;
 }
+pub mod ops {
+    pub use sp1_precompiles::ops::*;
+}
 
 extern crate alloc;
 
+/// Sets up the entry point of the guest, which calls the given function.
+///
+/// The size of the stack in bytes defaults to `stack::DEFAULT_STACK_SIZE`, and can be set with
+/// `entrypoint!(main, stack_size = 64 * 1024)`.
 #[macro_export]
 macro_rules! entrypoint {
     ($path:path) => {
+        $crate::entrypoint!($path, stack_size = $crate::stack::DEFAULT_STACK_SIZE);
+    };
+    ($path:path, stack_size = $stack_size:expr) => {
         const ZKVM_ENTRY: fn() = $path;
 
         use $crate::heap::SimpleAlloc;
@@ -19,6 +31,10 @@ macro_rules! entrypoint {
         #[global_allocator]
         static HEAP: SimpleAlloc = SimpleAlloc;
 
+        #[no_mangle]
+        #[used]
+        static SP1_STACK_SIZE: u32 = $stack_size;
+
         mod zkvm_generated_main {
 
             #[no_mangle]
@@ -29,28 +45,75 @@ macro_rules! entrypoint {
     };
 }
 
+/// Exports additional entry points, which the host selects with `Program::with_entry_symbol`.
+///
+/// Each entry point is exported under the name of its function and, like `_start`, sets up the
+/// global and stack pointers, calls the function and halts. The functions themselves must not be
+/// `#[no_mangle]`, since their names are taken by the entry points.
+#[macro_export]
+macro_rules! entrypoints {
+    ($($name:ident),+ $(,)?) => {
+        $(
+            #[cfg(target_os = "zkvm")]
+            const _: () = {
+                extern "C" fn entry() -> ! {
+                    $name();
+                    $crate::syscalls::syscall_halt()
+                }
+
+                core::arch::global_asm!(
+                    concat!(
+                        ".section .text.", stringify!($name), ";\n",
+                        ".globl ", stringify!($name), ";\n",
+                        ".type ", stringify!($name), ", @function;\n",
+                        stringify!($name), ":\n",
+                        ".option push;\n",
+                        ".option norelax;\n",
+                        "la gp, __global_pointer$;\n",
+                        ".option pop;\n",
+                        "la sp, {0}\n",
+                        "lw sp, 0(sp)\n",
+                        "jal ra, {1};\n",
+                    ),
+                    sym $crate::zkvm::STACK_TOP,
+                    sym entry,
+                );
+            };
+        )+
+    };
+}
+
 #[cfg(all(target_os = "zkvm", feature = "libm"))]
 mod libm;
 
 #[cfg(target_os = "zkvm")]
-mod zkvm {
+#[doc(hidden)]
+pub mod zkvm {
+    use crate::stack::STACK_CANARY;
     use crate::syscalls::syscall_halt;
     use getrandom::{register_custom_getrandom, Error};
 
     #[cfg(not(feature = "interface"))]
     #[no_mangle]
     unsafe extern "C" fn __start() {
-        {
-            extern "C" {
-                fn main();
-            }
-            main()
+        extern "C" {
+            fn main();
+            static SP1_STACK_SIZE: u32;
+        }
+
+        let canary = (STACK_TOP - SP1_STACK_SIZE) as *mut u32;
+        core::ptr::write_volatile(canary, STACK_CANARY);
+
+        main();
+
+        if core::ptr::read_volatile(canary) != STACK_CANARY {
+            panic!("stack overflow");
         }
 
         syscall_halt();
     }
 
-    static STACK_TOP: u32 = 0x0020_0400;
+    pub static STACK_TOP: u32 = 0x0020_0400;
 
     core::arch::global_asm!(include_str!("memset.s"));
     core::arch::global_asm!(include_str!("memcpy.s"));
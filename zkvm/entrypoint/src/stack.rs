@@ -0,0 +1,32 @@
+//! The layout of the guest stack and its overflow checks.
+//!
+//! The stack grows down from `STACK_TOP` over the number of bytes given to `entrypoint!`, which
+//! exports it so that the host stops the program on a store below the stack. The lowest word of
+//! the stack holds a canary, which is checked when `main` returns.
+
+/// The size of the stack if `entrypoint!` is not given one.
+pub const DEFAULT_STACK_SIZE: u32 = 0x0020_0000;
+
+/// The word kept at the bottom of the stack, which only an overflowing stack overwrites.
+pub const STACK_CANARY: u32 = 0x5354_4b43;
+
+/// Panics if the stack pointer is at or below the canary.
+///
+/// Calling it at the start of a deeply recursive function fails cleanly before the frame is
+/// written, even when the host does not check the stack.
+#[cfg(feature = "stack-probe")]
+#[inline(always)]
+pub fn probe() {
+    #[cfg(target_os = "zkvm")]
+    {
+        extern "C" {
+            static SP1_STACK_SIZE: u32;
+        }
+        let sp: u32;
+        unsafe { core::arch::asm!("mv {}, sp", out(reg) sp) };
+        let floor = crate::zkvm::STACK_TOP - unsafe { SP1_STACK_SIZE };
+        if sp <= floor + 4 {
+            panic!("stack overflow with sp 0x{:08x}", sp);
+        }
+    }
+}
@@ -0,0 +1,20 @@
+#[cfg(target_os = "zkvm")]
+use core::arch::asm;
+
+/// Replaces the word at `word` by the result of the bit manipulation `op`.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_bitmanip(word: *mut u32, op: u32) {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        asm!(
+            "ecall",
+            in("t0") crate::syscalls::BITMANIP,
+            in("a0") word,
+            in("a1") op
+        );
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
@@ -0,0 +1,20 @@
+#[cfg(target_os = "zkvm")]
+use core::arch::asm;
+
+/// Commits `len` bytes at `buf` to the public values of the proof.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_commit(buf: *const u8, len: usize) {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        asm!(
+            "ecall",
+            in("t0") crate::syscalls::COMMIT,
+            in("a0") buf,
+            in("a1") len
+        );
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
@@ -55,19 +55,82 @@ pub extern "C" fn syscall_read(fd: u32, read_buf: *mut u8, nbytes: usize) {
     unreachable!()
 }
 
-/// Write data to the prover.
+/// Write data to the prover, returning zero on success and an errno otherwise.
 #[allow(unused_variables)]
 #[no_mangle]
-pub extern "C" fn syscall_write(fd: u32, write_buf: *const u8, nbytes: usize) {
+pub extern "C" fn syscall_write(fd: u32, write_buf: *const u8, nbytes: usize) -> u32 {
     #[cfg(target_os = "zkvm")]
     unsafe {
+        let result;
         asm!(
             "ecall",
             in("t0") crate::syscalls::WRITE,
-            in("a0") fd,
+            inlateout("a0") fd => result,
             in("a1") write_buf,
             in("a2") nbytes,
         );
+        return result;
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
+
+/// The length returned by `sys_getenv` for an environment variable which is not set.
+pub const ENV_MISSING: usize = u32::MAX as usize;
+
+/// Looks up the environment variable set by the host with the key at `key_ptr`, copying at most
+/// `out_cap` bytes of its value to `out_ptr`.
+///
+/// Returns the full length of the value, which exceeds `out_cap` if the value was truncated, or
+/// `ENV_MISSING` if the variable is not set.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn sys_getenv(
+    key_ptr: *const u8,
+    key_len: usize,
+    out_ptr: *mut u8,
+    out_cap: usize,
+) -> usize {
+    #[cfg(target_os = "zkvm")]
+    {
+        let len: usize;
+        unsafe {
+            asm!(
+                "ecall",
+                in("t0") crate::syscalls::GETENV,
+                inlateout("a0") key_ptr => len,
+                in("a1") key_len,
+                in("a2") out_cap,
+            );
+        }
+        if len != ENV_MISSING {
+            // The runtime queues the bytes of the value which fit at the front of the input.
+            syscall_read(0, out_ptr, len.min(out_cap));
+        }
+        return len;
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
+
+/// Reads the global cycle counter of the VM, i.e. the number of instructions executed so far over
+/// all shards, from the low word in `a0` and the high word in `a1`.
+#[no_mangle]
+pub extern "C" fn sys_cycle_count() -> u64 {
+    #[cfg(target_os = "zkvm")]
+    {
+        let (lo, hi): (u32, u32);
+        unsafe {
+            asm!(
+                "ecall",
+                in("t0") crate::syscalls::CYCLE_COUNT,
+                lateout("a0") lo,
+                lateout("a1") hi,
+            );
+        }
+        return ((hi as u64) << 32) | lo as u64;
     }
 
     #[cfg(not(target_os = "zkvm"))]
@@ -0,0 +1,21 @@
+#[cfg(target_os = "zkvm")]
+use core::arch::asm;
+
+/// Copies `len` words from `src` to `dst`. The buffers must be word aligned and must not overlap.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_memcpy(dst: *mut u32, src: *const u32, len: usize) {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        asm!(
+            "ecall",
+            in("t0") crate::syscalls::MEMCPY,
+            in("a0") dst,
+            in("a1") src,
+            in("a2") len
+        );
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
@@ -0,0 +1,22 @@
+#[cfg(target_os = "zkvm")]
+use core::arch::asm;
+
+/// Replaces the 256-bit integer at `a` by its product with the one at `b`, modulo the one at
+/// `modulus`. The integers are eight little endian words, and the modulus must not be zero.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_uint256_mulmod(a: *mut u32, b: *const u32, modulus: *const u32) {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        asm!(
+            "ecall",
+            in("t0") crate::syscalls::UINT256_MULMOD,
+            in("a0") a,
+            in("a1") b,
+            in("a2") modulus
+        );
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
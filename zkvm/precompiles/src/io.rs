@@ -1,5 +1,5 @@
 #![allow(unused_unsafe)]
-use crate::{syscall_read, syscall_write};
+use crate::{syscall_commit, syscall_read, syscall_write};
 use bincode;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
@@ -30,8 +30,9 @@ impl std::io::Write for SyscallWriter {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
         let nbytes = buf.len();
         let write_buf = buf.as_ptr();
-        unsafe {
-            syscall_write(self.fd, write_buf, nbytes);
+        let errno = unsafe { syscall_write(self.fd, write_buf, nbytes) };
+        if errno != 0 {
+            return Err(std::io::Error::from_raw_os_error(errno as i32));
         }
         Ok(nbytes)
     }
@@ -62,6 +63,24 @@ pub fn write_slice(buf: &[u8]) {
     my_reader.write_all(buf).unwrap();
 }
 
+/// Commits `value` to the output as one frame: its length as a little-endian `u64`, then its
+/// bincode. The host decodes the frames with `SP1Stdout::decode` and `SP1Stdout::decode_all`.
+pub fn commit<T: Serialize>(value: &T) {
+    let bytes = bincode::serialize(value).expect("serialization failed");
+    let mut my_writer = SyscallWriter { fd: FD_IO };
+    my_writer
+        .write_all(&(bytes.len() as u64).to_le_bytes())
+        .unwrap();
+    my_writer.write_all(&bytes).unwrap();
+}
+
+/// Appends `buf` to the values the proof claims the program committed, which the verifier reads
+/// with `SP1ProofWithIO::claimed_values`. The proof does not constrain them, so a verifier which
+/// needs them sound must re-execute the program.
+pub fn commit_values(buf: &[u8]) {
+    unsafe { syscall_commit(buf.as_ptr(), buf.len()) }
+}
+
 pub fn hint<T: Serialize>(value: &T) {
     let writer = SyscallWriter { fd: FD_HINT };
     bincode::serialize_into(writer, value).expect("serialization failed");
@@ -71,3 +90,11 @@ pub fn hint_slice(buf: &[u8]) {
     let mut my_reader = SyscallWriter { fd: FD_HINT };
     my_reader.write_all(buf).unwrap();
 }
+
+/// Writes `buf` to the file descriptor `fd`, which the host routes to the sink registered for it.
+///
+/// Fails with `EBADF` if the host has no sink for `fd`.
+pub fn write_fd(fd: u32, buf: &[u8]) -> std::io::Result<()> {
+    let mut my_writer = SyscallWriter { fd };
+    my_writer.write_all(buf)
+}
@@ -1,10 +1,11 @@
 pub mod io;
+pub mod ops;
 pub mod secp256k1;
 pub mod unconstrained;
 
 extern "C" {
     pub fn syscall_halt() -> !;
-    pub fn syscall_write(fd: u32, write_buf: *const u8, nbytes: usize);
+    pub fn syscall_write(fd: u32, write_buf: *const u8, nbytes: usize) -> u32;
     pub fn syscall_read(fd: u32, read_buf: *mut u8, nbytes: usize);
     pub fn syscall_sha256_extend(w: *mut u32);
     pub fn syscall_sha256_compress(w: *mut u32, state: *mut u32);
@@ -15,6 +16,10 @@ extern "C" {
     pub fn syscall_secp256k1_decompress(point: &mut [u8; 64], is_odd: bool);
     pub fn syscall_keccak_permute(state: *mut u64);
     pub fn syscall_blake3_compress_inner(p: *mut u32, q: *const u32);
+    pub fn syscall_bitmanip(word: *mut u32, op: u32);
+    pub fn syscall_memcpy(dst: *mut u32, src: *const u32, len: usize);
+    pub fn syscall_uint256_mulmod(a: *mut u32, b: *const u32, modulus: *const u32);
+    pub fn syscall_commit(buf: *const u8, len: usize);
     pub fn syscall_enter_unconstrained() -> bool;
     pub fn syscall_exit_unconstrained();
     pub fn sys_alloc_aligned(bytes: usize, align: usize) -> *mut u8;
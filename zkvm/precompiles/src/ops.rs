@@ -0,0 +1,35 @@
+//! Bit manipulation intrinsics backed by the `BITMANIP` precompile.
+
+use crate::syscall_bitmanip;
+
+const BSWAP: u32 = 0;
+const CLZ: u32 = 1;
+const CTZ: u32 = 2;
+const POPCOUNT: u32 = 3;
+
+fn bitmanip(mut value: u32, op: u32) -> u32 {
+    unsafe {
+        syscall_bitmanip(&mut value, op);
+    }
+    value
+}
+
+/// Reverses the byte order of `value`, like `u32::swap_bytes`.
+pub fn bswap(value: u32) -> u32 {
+    bitmanip(value, BSWAP)
+}
+
+/// Counts the leading zeros of `value`, like `u32::leading_zeros`.
+pub fn clz(value: u32) -> u32 {
+    bitmanip(value, CLZ)
+}
+
+/// Counts the trailing zeros of `value`, like `u32::trailing_zeros`.
+pub fn ctz(value: u32) -> u32 {
+    bitmanip(value, CTZ)
+}
+
+/// Counts the set bits of `value`, like `u32::count_ones`.
+pub fn popcount(value: u32) -> u32 {
+    bitmanip(value, POPCOUNT)
+}